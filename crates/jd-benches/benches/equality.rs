@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::alloc::report;
+use jd_benches::CorpusRegistry;
+use jd_core::DiffOptions;
+
+/// The built-in corpora plus any listed in `JD_BENCH_CORPORA`.
+fn corpora() -> CorpusRegistry {
+    CorpusRegistry::from_env().expect("failed to load corpora")
+}
+
+/// `jd_core::equal` against `diff(..).is_empty()`, on equal documents (a
+/// copy of `after`, the common case for gate checks) and on the differing
+/// pair, with exact and tolerant number comparison.
+fn bench_equal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("equal");
+    let options = [
+        ("exact", DiffOptions::default()),
+        ("precision", DiffOptions::default().with_precision(0.01).expect("valid precision")),
+    ];
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let copy = dataset.after().clone();
+        let pairs =
+            [("same", (&copy, dataset.after())), ("changed", (dataset.before(), dataset.after()))];
+        group.throughput(Throughput::Bytes(corpus.fixture_bytes() as u64));
+        for (label, options) in &options {
+            for (pair, (lhs, rhs)) in pairs {
+                let name = format!("{}/{pair}-{label}", corpus.name());
+                report(&format!("equal/{name}"), || jd_core::equal(lhs, rhs, options));
+                report(&format!("diff-is-empty/{name}"), || lhs.diff(rhs, options).is_empty());
+                group.bench_function(BenchmarkId::new("equal", &name), |b| {
+                    b.iter(|| black_box(jd_core::equal(lhs, rhs, options)));
+                });
+                group.bench_function(BenchmarkId::new("diff-is-empty", &name), |b| {
+                    b.iter(|| black_box(lhs.diff(rhs, options).is_empty()));
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_equal);
+criterion_main!(benches);
@@ -0,0 +1,116 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::CorpusRegistry;
+use jd_core::{Diff, DiffOptions, Node, RenderConfig};
+
+/// The built-in corpora plus any listed in `JD_BENCH_CORPORA`.
+fn corpora() -> CorpusRegistry {
+    CorpusRegistry::from_env().expect("failed to load corpora")
+}
+
+/// Reads each corpus's diff back from the native, JSON Patch, and merge
+/// patch formats, as `jd -p` and `jd -t` do.
+fn bench_patch_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("patch-read");
+    for corpus in &corpora() {
+        let diff = corpus.load().expect("failed to load dataset").diff(&DiffOptions::default());
+        let native = diff.render(&RenderConfig::default());
+        group.throughput(Throughput::Bytes(native.len() as u64));
+        group.bench_with_input(BenchmarkId::new("native", corpus.name()), &native, |b, native| {
+            b.iter(|| black_box(Diff::from_native_str(native).expect("native diff reads")));
+        });
+        let patch = diff.render_patch().expect("JSON Patch renders");
+        group.throughput(Throughput::Bytes(patch.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("json-patch", corpus.name()),
+            &patch,
+            |b, patch| {
+                b.iter(|| black_box(Diff::from_patch_str(patch).expect("JSON Patch reads")));
+            },
+        );
+        // Diffs that edit arrays have no merge patch form.
+        if let Ok(merge) = diff.render_merge() {
+            group.throughput(Throughput::Bytes(merge.len() as u64));
+            group.bench_with_input(BenchmarkId::new("merge", corpus.name()), &merge, |b, merge| {
+                b.iter(|| black_box(Diff::from_merge_str(merge).expect("merge patch reads")));
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Applies each corpus's diff supplied as JSON Patch text, end to end.
+fn bench_json_patch_apply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("patch-apply-json-patch");
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let patch =
+            dataset.diff(&DiffOptions::default()).render_patch().expect("JSON Patch renders");
+        group.throughput(Throughput::Bytes(corpus.fixture_bytes() as u64));
+        group.bench_function(corpus.name(), |b| {
+            b.iter(|| {
+                let diff = Diff::from_patch_str(&patch).expect("JSON Patch reads");
+                black_box(dataset.before().apply_patch(&diff).expect("patch success"))
+            });
+        });
+    }
+    group.finish();
+}
+
+/// The `jd -t` translations between diff formats and between document
+/// formats.
+fn bench_translate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("translate");
+    let config = RenderConfig::default();
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let diff = dataset.diff(&DiffOptions::default());
+        let native = diff.render(&config);
+        group.bench_with_input(
+            BenchmarkId::new("jd2patch", corpus.name()),
+            &native,
+            |b, native| {
+                b.iter(|| {
+                    let diff = Diff::from_native_str(native).expect("native diff reads");
+                    black_box(diff.render_patch().expect("JSON Patch renders"))
+                });
+            },
+        );
+        let patch = diff.render_patch().expect("JSON Patch renders");
+        group.bench_with_input(BenchmarkId::new("patch2jd", corpus.name()), &patch, |b, patch| {
+            b.iter(|| {
+                let diff = Diff::from_patch_str(patch).expect("JSON Patch reads");
+                black_box(diff.render(&config))
+            });
+        });
+        if diff.render_merge().is_ok() {
+            group.bench_with_input(
+                BenchmarkId::new("jd2merge", corpus.name()),
+                &native,
+                |b, native| {
+                    b.iter(|| {
+                        let diff = Diff::from_native_str(native).expect("native diff reads");
+                        black_box(diff.render_merge().expect("merge patch renders"))
+                    });
+                },
+            );
+        }
+        let json = dataset.before().to_json_value().expect("JSON document").to_string();
+        group.bench_with_input(BenchmarkId::new("json2yaml", corpus.name()), &json, |b, json| {
+            b.iter(|| {
+                let node = Node::from_json_str(json).expect("JSON parses");
+                black_box(node.to_yaml_string())
+            });
+        });
+        let yaml = dataset.before().to_yaml_string().expect("YAML document");
+        group.bench_with_input(BenchmarkId::new("yaml2json", corpus.name()), &yaml, |b, yaml| {
+            b.iter(|| {
+                let node = Node::from_yaml_str(yaml).expect("YAML parses");
+                black_box(node.to_json_value().map(|value| value.to_string()))
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_patch_read, bench_json_patch_apply, bench_translate);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use jd_benches::huge::{read_json, HugeArray};
+use jd_core::Node;
+
+/// Parses the `huge-array` corpus from disk, streaming with
+/// `Node::from_json_reader` and, for comparison, reading it into a string
+/// first. The corpus is generated on the first run and cached.
+fn bench_huge_parse(c: &mut Criterion) {
+    let corpus = HugeArray::new().materialize().expect("failed to generate huge-array");
+    let path = corpus.before_path();
+    let bytes = std::fs::metadata(path).expect("generated corpus").len();
+    let mut group = c.benchmark_group("huge-array");
+    group.sample_size(10).measurement_time(Duration::from_secs(60));
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function("from-json-reader", |b| {
+        b.iter(|| black_box(read_json(path).expect("JSON parses")));
+    });
+    group.bench_function("from-json-str", |b| {
+        b.iter(|| {
+            let json = std::fs::read_to_string(path).expect("corpus reads");
+            black_box(Node::from_json_str(&json).expect("JSON parses"))
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_huge_parse);
+criterion_main!(benches);
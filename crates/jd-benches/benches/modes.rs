@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::CorpusRegistry;
+use jd_core::{ArrayMode, DiffOptions, Jd, Node};
+
+/// The built-in corpora plus any listed in `JD_BENCH_CORPORA`.
+fn corpora() -> CorpusRegistry {
+    CorpusRegistry::from_env().expect("failed to load corpora")
+}
+
+/// Parses each corpus's documents as JSON and as YAML.
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let json = dataset.before().to_json_value().expect("JSON document").to_string();
+        let yaml = dataset.before().to_yaml_string().expect("YAML document");
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", corpus.name()), &json, |b, json| {
+            b.iter(|| black_box(Node::from_json_str(json).expect("JSON parses")));
+        });
+        group.throughput(Throughput::Bytes(yaml.len() as u64));
+        group.bench_with_input(BenchmarkId::new("yaml", corpus.name()), &yaml, |b, yaml| {
+            b.iter(|| black_box(Node::from_yaml_str(yaml).expect("YAML parses")));
+        });
+    }
+    group.finish();
+}
+
+/// Equality, hashing, and diffing with arrays compared as sets and multisets.
+fn bench_set_modes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set-modes");
+    for mode in [ArrayMode::Set, ArrayMode::MultiSet] {
+        let options = DiffOptions::default().with_array_mode(mode).expect("valid array mode");
+        let label = format!("{mode:?}").to_lowercase();
+        let jd = Jd::with_options(options.clone());
+        for corpus in &corpora() {
+            let dataset = corpus.load().expect("failed to load dataset");
+            let (before, after) = (dataset.before(), dataset.after());
+            group.bench_function(BenchmarkId::new(format!("equal-{label}"), corpus.name()), |b| {
+                b.iter(|| black_box(before.eq_with_options(after, &options)));
+            });
+            group.bench_function(BenchmarkId::new(format!("hash-{label}"), corpus.name()), |b| {
+                b.iter(|| black_box(before.hash_code(&options)));
+            });
+            // Array modes without a diff engine report an error; their diff
+            // benchmarks start running once the engine lands.
+            if jd.diff(before, after).is_ok() {
+                group.bench_function(
+                    BenchmarkId::new(format!("diff-{label}"), corpus.name()),
+                    |b| {
+                        b.iter(|| black_box(jd.diff(before, after)));
+                    },
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_set_modes);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::alloc::report;
+use jd_core::diff::{DiffBuilder, DiffElement, PathSegment};
+use jd_core::{node, Diff, Node};
+
+/// Records in the patched array.
+const LEN: i64 = 10_000;
+
+fn record(id: i64) -> Node {
+    node!({"id": id, "name": (format!("item-{id}"))})
+}
+
+/// `{"items": [..]}` with `LEN` records.
+fn document() -> Node {
+    node!({ "items": ((0..LEN).map(record).collect::<Vec<_>>()) })
+}
+
+/// One hunk per `stride` records, each adding or removing a single record
+/// with one record of context on either side, as `jd` writes list diffs.
+/// Indices count the records already added or removed by earlier hunks.
+fn edits(insert: bool, stride: i64) -> Diff {
+    let mut builder = DiffBuilder::new();
+    for (hunk, at) in (stride..LEN).step_by(stride as usize).enumerate() {
+        let hunk = hunk as i64;
+        let index = if insert { at + hunk } else { at - hunk };
+        let after = if insert { at } else { at + 1 };
+        let element = DiffElement::new()
+            .with_path(vec![PathSegment::key("items"), PathSegment::index(index)])
+            .with_before(vec![record(at - 1)])
+            .with_after(vec![record(after)]);
+        let element = if insert {
+            element.with_add(vec![record(-at)])
+        } else {
+            element.with_remove(vec![record(at)])
+        };
+        builder = builder.with_element(element).expect("valid hunk");
+    }
+    builder.build()
+}
+
+/// Worst cases for applying list hunks: many single-record insertions into
+/// or removals from one array of `LEN` records.
+fn bench_list_hunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("patch-list-hunks");
+    group.sample_size(10);
+    let document = document();
+    for (name, insert) in [("insert", true), ("remove", false)] {
+        for stride in [100, 10] {
+            let diff = edits(insert, stride);
+            let id = format!("{name}-every-{stride}");
+            report(&format!("patch-list-hunks/{id}"), || {
+                document.apply_patch(&diff).expect("patch success")
+            });
+            group.throughput(Throughput::Elements(diff.len() as u64));
+            group.bench_function(BenchmarkId::new(id, LEN), |b| {
+                b.iter(|| black_box(document.apply_patch(&diff).expect("patch success")));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_list_hunks);
+criterion_main!(benches);
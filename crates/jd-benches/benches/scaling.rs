@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::alloc::report;
+use jd_benches::generator::{ChangeKind, Workload};
+use jd_core::DiffOptions;
+
+/// LCS cost as a single array grows, for each kind of edit.
+fn bench_array_length(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scaling-array-length");
+    let options = DiffOptions::default();
+    for kind in [ChangeKind::Replace, ChangeKind::Insert, ChangeKind::Delete] {
+        for array_len in [64, 256, 1024, 4096] {
+            let workload =
+                Workload::new().with_depth(0).with_array_len(array_len).with_change_kind(kind);
+            let dataset = workload.generate();
+            report(&format!("scaling-array-length/{kind}/{array_len}"), || dataset.diff(&options));
+            group.throughput(Throughput::Elements(array_len as u64));
+            group.bench_with_input(
+                BenchmarkId::new(kind.to_string(), array_len),
+                &dataset,
+                |b, dataset| b.iter(|| black_box(dataset.diff(&options))),
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Diff cost as the share of edited elements grows.
+fn bench_change_ratio(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scaling-change-ratio");
+    let options = DiffOptions::default();
+    for ratio in [0.0, 0.01, 0.1, 0.5, 1.0] {
+        let workload = Workload::new()
+            .with_depth(0)
+            .with_array_len(1024)
+            .with_change_kind(ChangeKind::Mixed)
+            .with_change_ratio(ratio);
+        let dataset = workload.generate();
+        group.bench_with_input(BenchmarkId::from_parameter(ratio), &dataset, |b, dataset| {
+            b.iter(|| black_box(dataset.diff(&options)));
+        });
+    }
+    group.finish();
+}
+
+/// Object traversal cost as documents grow deeper and wider.
+fn bench_shape(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scaling-shape");
+    let options = DiffOptions::default();
+    for (depth, fan_out) in [(1, 64), (2, 8), (3, 4), (6, 2), (10, 2)] {
+        let workload = Workload::new().with_depth(depth).with_fan_out(fan_out).with_array_len(8);
+        let dataset = workload.generate();
+        report(&format!("scaling-shape/{}", workload.label()), || dataset.diff(&options));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(workload.label()),
+            &dataset,
+            |b, dataset| {
+                b.iter(|| black_box(dataset.diff(&options)));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_array_length, bench_change_ratio, bench_shape);
+criterion_main!(benches);
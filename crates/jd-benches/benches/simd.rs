@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jd_benches::CorpusRegistry;
+use jd_core::{DiffOptions, HashAlgorithm, Node};
+
+/// The corpora with the most text per document.
+const CORPORA: [&str; 2] = ["kubernetes-deployment", "github-issue"];
+
+/// Parses each corpus with serde_json, through a `serde_json::Value`, and
+/// with `Node::from_json_str`, which uses simd-json.
+fn bench_parse(c: &mut Criterion) {
+    let registry = CorpusRegistry::builtin();
+    let mut group = c.benchmark_group("simd-parse");
+    for name in CORPORA {
+        let json = registry.get(name).expect("built-in corpus").before_json();
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::new("serde_json", name), json, |b, json| {
+            b.iter(|| {
+                let value = serde_json::from_str(json).expect("JSON parses");
+                black_box(Node::from_json_value(value).expect("JSON converts"))
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("simd-json", name), json, |b, json| {
+            b.iter(|| black_box(Node::from_json_str(json).expect("JSON parses")));
+        });
+    }
+    group.finish();
+}
+
+/// Hashes each corpus, and an array of 4 KiB strings, with FNV-1a and with
+/// XXH3.
+fn bench_hash(c: &mut Criterion) {
+    let registry = CorpusRegistry::builtin();
+    let mut group = c.benchmark_group("simd-hash");
+    let mut documents = Vec::new();
+    for name in CORPORA {
+        let corpus = registry.get(name).expect("built-in corpus");
+        let dataset = corpus.load().expect("failed to load dataset");
+        documents.push((name, corpus.before_json().len(), dataset.before().clone()));
+    }
+    let blobs = Node::Array((0..64).map(|i| Node::from(format!("{i:04}").repeat(1024))).collect());
+    documents.push(("long-strings", 64 * 4096, blobs));
+    for (name, bytes, document) in &documents {
+        group.throughput(Throughput::Bytes(*bytes as u64));
+        for (label, algorithm) in [("fnv64", HashAlgorithm::Fnv64), ("xxh3", HashAlgorithm::Xxh3)] {
+            let options = DiffOptions::default().with_hash_algorithm(algorithm);
+            group.bench_function(BenchmarkId::new(label, name), |b| {
+                b.iter(|| black_box(document.hash_code(&options)));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_hash);
+criterion_main!(benches);
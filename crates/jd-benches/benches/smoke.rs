@@ -1,12 +1,20 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use jd_benches::available_corpora;
-use jd_core::{DiffOptions, RenderConfig};
+use jd_benches::alloc::report;
+use jd_benches::CorpusRegistry;
+use jd_core::diff::{diff_many, diff_nodes, diff_nodes_with_scratch};
+use jd_core::{DiffOptions, DiffScratch, Node, RenderConfig};
+
+/// The built-in corpora plus any listed in `JD_BENCH_CORPORA`.
+fn corpora() -> CorpusRegistry {
+    CorpusRegistry::from_env().expect("failed to load corpora")
+}
 
 fn bench_diff(c: &mut Criterion) {
     let mut group = c.benchmark_group("diff");
     let options = DiffOptions::default();
-    for corpus in available_corpora() {
+    for corpus in &corpora() {
         let dataset = corpus.load().expect("failed to load dataset");
+        report(&format!("diff/{}", corpus.name()), || dataset.diff(&options));
         group.throughput(Throughput::Bytes(corpus.fixture_bytes() as u64));
         group.bench_with_input(
             BenchmarkId::from_parameter(corpus.name()),
@@ -25,9 +33,12 @@ fn bench_diff(c: &mut Criterion) {
 fn bench_patch_apply(c: &mut Criterion) {
     let mut group = c.benchmark_group("patch-apply");
     let options = DiffOptions::default();
-    for corpus in available_corpora() {
+    for corpus in &corpora() {
         let dataset = corpus.load().expect("failed to load dataset");
         let diff = dataset.diff(&options);
+        report(&format!("patch-apply/{}", corpus.name()), || {
+            dataset.before().apply_patch(&diff).expect("patch success")
+        });
         group.throughput(Throughput::Bytes(corpus.fixture_bytes() as u64));
         group.bench_function(corpus.name(), {
             let dataset = dataset.clone();
@@ -49,7 +60,7 @@ fn bench_render(c: &mut Criterion) {
 
     {
         let mut native = c.benchmark_group("render-native");
-        for corpus in available_corpora() {
+        for corpus in &corpora() {
             let dataset = corpus.load().expect("failed to load dataset");
             let diff = dataset.diff(&options);
             native.throughput(Throughput::Elements(diff.len() as u64));
@@ -68,7 +79,7 @@ fn bench_render(c: &mut Criterion) {
 
     {
         let mut json_patch = c.benchmark_group("render-json-patch");
-        for corpus in available_corpora() {
+        for corpus in &corpora() {
             let dataset = corpus.load().expect("failed to load dataset");
             let diff = dataset.diff(&options);
             json_patch.throughput(Throughput::Elements(diff.len() as u64));
@@ -86,5 +97,91 @@ fn bench_render(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench_diff, bench_patch_apply, bench_render);
+/// Colored rendering of a long string replaced by a copy with eight
+/// characters changed, which highlights the changed characters.
+fn bench_string_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render-string-diff");
+    group.sample_size(10);
+    let options = DiffOptions::default();
+    let config = RenderConfig::default().with_color(true);
+    for len in [1_000, 4_000, 100_000, 1_000_000] {
+        let old: String = (0..len).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+        let new: String = old
+            .char_indices()
+            .map(|(i, ch)| if i % (len / 8) == len / 16 { '#' } else { ch })
+            .collect();
+        let diff = Node::from(old).diff(&Node::from(new), &options);
+        group.throughput(Throughput::Bytes(2 * len as u64));
+        group.bench_function(BenchmarkId::from_parameter(len), |b| {
+            b.iter(|| black_box(diff.render(&config)));
+        });
+    }
+    group.finish();
+}
+
+/// Diffs and renders each corpus as a service would, one call after
+/// another, with fresh buffers and with a reused `DiffScratch`.
+fn bench_scratch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diff-render");
+    let options = DiffOptions::default();
+    let config = RenderConfig::default();
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let (before, after) = (dataset.before(), dataset.after());
+        // Reported after a first diff has sized the buffers.
+        let mut scratch = DiffScratch::new();
+        let _ = diff_nodes_with_scratch(before, after, &options, &mut scratch);
+        report(&format!("diff-render/fresh/{}", corpus.name()), || {
+            diff_nodes(before, after, &options).render(&config)
+        });
+        report(&format!("diff-render/scratch/{}", corpus.name()), || {
+            let diff = diff_nodes_with_scratch(before, after, &options, &mut scratch);
+            scratch.render(&diff, &config).len()
+        });
+        group.throughput(Throughput::Bytes(corpus.fixture_bytes() as u64));
+        group.bench_function(BenchmarkId::new("fresh", corpus.name()), |b| {
+            b.iter(|| black_box(diff_nodes(before, after, &options).render(&config)));
+        });
+        group.bench_function(BenchmarkId::new("scratch", corpus.name()), |b| {
+            b.iter(|| {
+                let diff = diff_nodes_with_scratch(before, after, &options, &mut scratch);
+                black_box(scratch.render(&diff, &config).len())
+            });
+        });
+    }
+    group.finish();
+}
+
+/// A batch of 32 copies of each corpus pair, diffed one after another and
+/// through `diff_many`.
+fn bench_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diff-many");
+    let options = DiffOptions::default();
+    for corpus in &corpora() {
+        let dataset = corpus.load().expect("failed to load dataset");
+        let pairs = vec![(dataset.before(), dataset.after()); 32];
+        group.throughput(Throughput::Bytes(32 * corpus.fixture_bytes() as u64));
+        group.bench_function(BenchmarkId::new("sequential", corpus.name()), |b| {
+            b.iter(|| {
+                let diffs: Vec<_> =
+                    pairs.iter().map(|&(lhs, rhs)| diff_nodes(lhs, rhs, &options)).collect();
+                black_box(diffs)
+            });
+        });
+        group.bench_function(BenchmarkId::new("parallel", corpus.name()), |b| {
+            b.iter(|| black_box(diff_many(pairs.iter().copied(), &options)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_diff,
+    bench_patch_apply,
+    bench_render,
+    bench_string_render,
+    bench_scratch,
+    bench_many
+);
 criterion_main!(benches);
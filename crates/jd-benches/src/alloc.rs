@@ -0,0 +1,146 @@
+//! Allocation counting for benchmarks.
+//!
+//! Criterion measures time, but memory is the bigger risk for large inputs:
+//! the list diff keeps an O(n·m) LCS table. [`CountingAllocator`] wraps the
+//! system allocator and tallies allocations, bytes, and the peak number of
+//! live bytes, and [`measure`] reports those for a closure.
+//!
+//! Counting needs the allocator installed as the global allocator. Enable
+//! the `count-allocations` feature to have this crate install it, or
+//! install it yourself:
+//!
+//! ```
+//! use jd_benches::alloc::{measure, CountingAllocator};
+//!
+//! # #[cfg(not(feature = "count-allocations"))]
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator;
+//!
+//! let (buffer, stats) = measure(|| vec![0_u8; 4096]);
+//! assert!(stats.allocations >= 1);
+//! assert!(stats.peak_bytes >= buffer.len());
+//! ```
+//!
+//! Counters are process-wide, so allocations made by other threads while a
+//! closure runs are included.
+#![allow(unsafe_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator plus allocation counters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn record(size: usize) {
+        ACTIVE.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to `System`; the counters only
+// observe sizes.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::record(new_size);
+        }
+        new
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocation activity observed by [`measure`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Allocations and reallocations made.
+    pub allocations: u64,
+    /// Bytes requested by those allocations.
+    pub bytes: u64,
+    /// Highest number of live bytes above the level when measuring began.
+    pub peak_bytes: usize,
+}
+
+/// Reports whether [`CountingAllocator`] is the global allocator, that is
+/// whether [`measure`] observes anything.
+///
+/// ```
+/// use jd_benches::alloc::{is_counting, measure};
+///
+/// let (_, stats) = measure(|| vec![1, 2, 3]);
+/// if !is_counting() {
+///     assert_eq!(stats.allocations, 0);
+/// }
+/// ```
+#[must_use]
+pub fn is_counting() -> bool {
+    // Anything allocated at all proves the allocator is installed.
+    drop(std::hint::black_box(Box::new(0_u8)));
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Runs `f` and returns its result with the allocations it made. All counts
+/// are zero unless [`CountingAllocator`] is the global allocator.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocationStats) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let live = LIVE.load(Ordering::Relaxed);
+    PEAK.store(live, Ordering::Relaxed);
+    let value = f();
+    let stats = AllocationStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: BYTES.load(Ordering::Relaxed) - bytes,
+        peak_bytes: PEAK.load(Ordering::Relaxed).saturating_sub(live),
+    };
+    (value, stats)
+}
+
+/// Runs `f` once and, when allocations are being counted, prints its
+/// allocation stats under `label` to standard error, next to Criterion's
+/// timings. Benchmarks call this before timing the same work.
+pub fn report<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    let (value, stats) = measure(f);
+    if is_counting() {
+        eprintln!(
+            "allocations {label}: {} allocations, {} bytes, peak {} bytes",
+            stats.allocations, stats.bytes, stats.peak_bytes
+        );
+    }
+    value
+}
@@ -0,0 +1,267 @@
+//! Regression checks of Criterion results against a stored baseline.
+//!
+//! A [`Baseline`] holds a median time in nanoseconds per benchmark, grouped
+//! like Criterion's benchmark groups, and a tolerance: the largest allowed
+//! ratio of a current median to its baseline. [`Baseline::check`] reads the
+//! `estimates.json` Criterion saved for a run and reports every benchmark
+//! that regressed beyond the tolerance or has no results.
+//!
+//! Baselines are JSON files shaped like `baselines/criterion-ci.json`:
+//!
+//! ```json
+//! {
+//!   "metadata": { "tolerance": 1.25, "unit": "ns" },
+//!   "benchmarks": { "diff": { "github-issue": 20259.18 } }
+//! }
+//! ```
+//!
+//! Save a run with `cargo bench -p jd-benches --bench smoke -- --save-baseline
+//! current`, then check it:
+//!
+//! ```no_run
+//! use jd_benches::baseline::Baseline;
+//!
+//! let baseline = Baseline::load("crates/jd-benches/baselines/criterion-ci.json").unwrap();
+//! let report = baseline.check("target/criterion", "current").unwrap();
+//! print!("{report}");
+//! assert!(report.passed());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+/// Tolerance used when neither the baseline nor the caller sets one.
+pub const DEFAULT_TOLERANCE: f64 = 1.25;
+
+/// Stored median times and the allowed slowdown.
+///
+/// ```
+/// use jd_benches::baseline::Baseline;
+///
+/// let baseline = Baseline::from_json_str(
+///     r#"{"metadata":{"tolerance":1.1},"benchmarks":{"diff":{"github-issue":100.0}}}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(baseline.tolerance(), 1.1);
+/// assert_eq!(baseline.median("diff", "github-issue"), Some(100.0));
+/// assert_eq!(baseline.with_tolerance(2.0).tolerance(), 2.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Baseline {
+    tolerance: f64,
+    benchmarks: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+#[derive(Deserialize)]
+struct BaselineFile {
+    #[serde(default)]
+    metadata: Metadata,
+    benchmarks: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+#[derive(Default, Deserialize)]
+struct Metadata {
+    tolerance: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Estimates {
+    median: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+impl Baseline {
+    /// Reads a baseline file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        Self::from_json_str(&json).map_err(|err| {
+            io::Error::new(err.kind(), format!("{}: invalid baseline: {err}", path.display()))
+        })
+    }
+
+    /// Parses a baseline from JSON. The tolerance defaults to
+    /// [`DEFAULT_TOLERANCE`] when `metadata.tolerance` is absent.
+    pub fn from_json_str(json: &str) -> io::Result<Self> {
+        let file: BaselineFile = serde_json::from_str(json)?;
+        Ok(Self {
+            tolerance: file.metadata.tolerance.unwrap_or(DEFAULT_TOLERANCE),
+            benchmarks: file.benchmarks,
+        })
+    }
+
+    /// Overrides the stored tolerance.
+    #[must_use]
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// The largest allowed ratio of a current median to its baseline.
+    #[must_use]
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// The stored median of `bench` in `group`, in nanoseconds.
+    #[must_use]
+    pub fn median(&self, group: &str, bench: &str) -> Option<f64> {
+        self.benchmarks.get(group)?.get(bench).copied()
+    }
+
+    /// Compares the run `run_name` saved under `results_root`, normally
+    /// `target/criterion`, against this baseline.
+    ///
+    /// Missing results count as failures in the report; an unreadable or
+    /// malformed `estimates.json` is an error.
+    pub fn check(
+        &self,
+        results_root: impl AsRef<Path>,
+        run_name: &str,
+    ) -> io::Result<RegressionReport> {
+        let results_root = results_root.as_ref();
+        let mut checks = Vec::new();
+        for (group, entries) in &self.benchmarks {
+            for (bench, &baseline) in entries {
+                let path =
+                    results_root.join(group).join(bench).join(run_name).join("estimates.json");
+                let outcome = if path.is_file() {
+                    let actual = read_median(&path)?;
+                    let ratio = actual / baseline;
+                    if ratio.is_finite() && ratio <= self.tolerance {
+                        Outcome::Ok { actual, ratio }
+                    } else {
+                        Outcome::Regressed { actual, ratio }
+                    }
+                } else {
+                    Outcome::Missing { path }
+                };
+                checks.push(Check {
+                    group: group.clone(),
+                    bench: bench.clone(),
+                    baseline,
+                    outcome,
+                });
+            }
+        }
+        Ok(RegressionReport { tolerance: self.tolerance, checks })
+    }
+}
+
+fn read_median(path: &Path) -> io::Result<f64> {
+    let json = fs::read_to_string(path)?;
+    let estimates: Estimates = serde_json::from_str(&json).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid estimates file {}: {err}", path.display()),
+        )
+    })?;
+    Ok(estimates.median.point_estimate)
+}
+
+/// Result of comparing one benchmark with its baseline.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Within tolerance.
+    Ok {
+        /// Current median, in nanoseconds.
+        actual: f64,
+        /// Current median over the baseline median.
+        ratio: f64,
+    },
+    /// Slower than the tolerance allows, or the ratio is not finite.
+    Regressed {
+        /// Current median, in nanoseconds.
+        actual: f64,
+        /// Current median over the baseline median.
+        ratio: f64,
+    },
+    /// Criterion saved no results for the benchmark.
+    Missing {
+        /// The `estimates.json` that was expected.
+        path: PathBuf,
+    },
+}
+
+/// One benchmark's check.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Check {
+    /// Criterion benchmark group, such as `diff`.
+    pub group: String,
+    /// Benchmark within the group, such as `github-issue`.
+    pub bench: String,
+    /// Baseline median, in nanoseconds.
+    pub baseline: f64,
+    /// How the current run compares.
+    pub outcome: Outcome,
+}
+
+impl Check {
+    /// Whether the benchmark is within tolerance.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        matches!(self.outcome, Outcome::Ok { .. })
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { group, bench, baseline, .. } = self;
+        match &self.outcome {
+            Outcome::Ok { actual, ratio } => write!(
+                f,
+                "ok {group}/{bench}: actual {actual:.3} ns vs baseline {baseline:.3} ns ({ratio:.2}x)"
+            ),
+            Outcome::Regressed { actual, ratio } => write!(
+                f,
+                "regression detected for {group}/{bench}: actual {actual:.3} ns vs baseline {baseline:.3} ns ({ratio:.2}x)"
+            ),
+            Outcome::Missing { path } => {
+                write!(f, "missing results for {group}/{bench}: {} not found", path.display())
+            }
+        }
+    }
+}
+
+/// Every benchmark's check, in group and benchmark name order.
+///
+/// Displaying the report prints one line per benchmark.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RegressionReport {
+    /// Tolerance the checks used.
+    pub tolerance: f64,
+    /// One entry per baseline benchmark.
+    pub checks: Vec<Check>,
+}
+
+impl RegressionReport {
+    /// Whether every benchmark is within tolerance.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(Check::passed)
+    }
+
+    /// The benchmarks that regressed or have no results.
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{check}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+//! Fails when Criterion results regress against a stored baseline.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use jd_benches::baseline::Baseline;
+
+/// Compares the medians of a saved Criterion run against a baseline file
+/// and exits with failure when any benchmark is slower than the tolerance
+/// allows or has no results. Under GitHub Actions, failures are also
+/// printed as error annotations.
+#[derive(Debug, Parser)]
+#[command(name = "check-regressions", version)]
+struct Cli {
+    /// Baseline JSON file.
+    #[arg(
+        long,
+        value_name = "FILE",
+        default_value = "crates/jd-benches/baselines/criterion-ci.json"
+    )]
+    baseline: PathBuf,
+    /// Directory holding Criterion output.
+    #[arg(long, value_name = "DIR", default_value = "target/criterion")]
+    results_root: PathBuf,
+    /// Name of the saved Criterion run to check.
+    #[arg(long, default_value = "current")]
+    run_name: String,
+    /// Largest allowed slowdown, overriding the baseline's own.
+    #[arg(long, value_name = "RATIO")]
+    tolerance: Option<f64>,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let mut baseline = Baseline::load(&cli.baseline).context("failed to load baseline")?;
+    if let Some(tolerance) = cli.tolerance {
+        baseline = baseline.with_tolerance(tolerance);
+    }
+    let report = baseline.check(&cli.results_root, &cli.run_name)?;
+    print!("{report}");
+    if report.passed() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if std::env::var_os("GITHUB_ACTIONS").is_some() {
+        for failure in report.failures() {
+            println!("::error ::{failure} (limit {:.2}x)", report.tolerance);
+        }
+    }
+    eprintln!("{} benchmark(s) regressed or have no results", report.failures().count());
+    Ok(ExitCode::FAILURE)
+}
@@ -0,0 +1,65 @@
+//! Compares the Rust `jd` binary with Go `jd` on every benchmark corpus.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use jd_benches::compare::Comparer;
+use jd_benches::CorpusRegistry;
+
+/// Report formats.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+/// Times Go `jd` and the Rust `jd` on the benchmark corpora and checks that
+/// they print the same output. Corpora listed in `JD_BENCH_CORPORA` are
+/// included.
+#[derive(Debug, Parser)]
+#[command(name = "compare", version)]
+struct Cli {
+    /// Path to the Go `jd` binary.
+    #[arg(long, value_name = "PATH")]
+    go_bin: PathBuf,
+    /// Path to the Rust `jd` binary.
+    #[arg(long, value_name = "PATH", default_value = "target/release/jd")]
+    rust_bin: PathBuf,
+    /// Timed runs per binary and corpus.
+    #[arg(long, default_value_t = 5)]
+    runs: usize,
+    /// Report format.
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+    /// Write the report to a file instead of standard output.
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Flags passed to both binaries before the two documents.
+    #[arg(last = true, value_name = "JD_ARGS")]
+    args: Vec<String>,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let registry = CorpusRegistry::from_env().context("failed to load corpora")?;
+    let report = Comparer::new(&cli.go_bin, &cli.rust_bin)
+        .with_runs(cli.runs)
+        .with_args(&cli.args)
+        .compare_all(&registry)?;
+    let rendered = match cli.format {
+        Format::Markdown => report.to_markdown(),
+        Format::Json => report.to_json() + "\n",
+    };
+    match &cli.output {
+        Some(path) => std::fs::write(path, rendered)
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => print!("{rendered}"),
+    }
+    if !report.all_match() {
+        eprintln!("outputs differ; see the report");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,219 @@
+//! Wall-clock and output comparisons of the Rust `jd` binary against Go `jd`.
+//!
+//! A [`Comparer`] writes each corpus to a scratch directory, runs both
+//! binaries on it several times, and records their exit codes, timings, and
+//! whether they printed the same output. A [`ComparisonReport`] collects the
+//! results and renders them as a markdown table or as JSON.
+//!
+//! ```no_run
+//! use jd_benches::compare::{Comparer, ComparisonReport};
+//! use jd_benches::CorpusRegistry;
+//!
+//! let comparer = Comparer::new("/usr/local/bin/jd", "target/release/jd").with_runs(10);
+//! let report = comparer.compare_all(&CorpusRegistry::builtin()).unwrap();
+//! println!("{}", report.to_markdown());
+//! assert!(report.all_match());
+//! ```
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{fs, io};
+
+use serde::Serialize;
+
+use crate::{Corpus, CorpusRegistry};
+
+/// Runs Go `jd` and the Rust `jd` on corpora and compares them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparer {
+    go_binary: PathBuf,
+    rust_binary: PathBuf,
+    runs: usize,
+    args: Vec<String>,
+}
+
+impl Comparer {
+    /// Creates a comparer running each binary five times per corpus in the
+    /// default diff mode.
+    #[must_use]
+    pub fn new(go_binary: impl Into<PathBuf>, rust_binary: impl Into<PathBuf>) -> Self {
+        Self {
+            go_binary: go_binary.into(),
+            rust_binary: rust_binary.into(),
+            runs: 5,
+            args: Vec::new(),
+        }
+    }
+
+    /// Sets how many timed runs each binary gets per corpus, at least one.
+    /// An untimed warm-up run precedes them.
+    #[must_use]
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(1);
+        self
+    }
+
+    /// Sets flags passed to both binaries before the two document paths,
+    /// such as `["-f", "patch"]`.
+    #[must_use]
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Compares the binaries on one corpus.
+    pub fn compare(&self, corpus: &Corpus) -> io::Result<CorpusComparison> {
+        let scratch = tempfile::tempdir()?;
+        let (before, after) =
+            (scratch.path().join("before.json"), scratch.path().join("after.json"));
+        fs::write(&before, corpus.before_json())?;
+        fs::write(&after, corpus.after_json())?;
+        let (go, go_stdout) = self.measure(&self.go_binary, &before, &after)?;
+        let (rust, rust_stdout) = self.measure(&self.rust_binary, &before, &after)?;
+        let outputs_match = go.exit_code == rust.exit_code && go_stdout == rust_stdout;
+        Ok(CorpusComparison { corpus: corpus.name().to_string(), go, rust, outputs_match })
+    }
+
+    /// Compares the binaries on every corpus in `registry`.
+    pub fn compare_all(&self, registry: &CorpusRegistry) -> io::Result<ComparisonReport> {
+        let results =
+            registry.iter().map(|corpus| self.compare(corpus)).collect::<io::Result<_>>()?;
+        Ok(ComparisonReport { args: self.args.clone(), runs: self.runs, results })
+    }
+
+    fn measure(&self, binary: &Path, before: &Path, after: &Path) -> io::Result<(Run, Vec<u8>)> {
+        let run = || {
+            let start = Instant::now();
+            let output = Command::new(binary)
+                .args(&self.args)
+                .arg(before)
+                .arg(after)
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+                .map_err(|err| {
+                    io::Error::new(err.kind(), format!("{}: {err}", binary.display()))
+                })?;
+            Ok::<_, io::Error>((start.elapsed(), output))
+        };
+        let (_, warm_up) = run()?;
+        let mut times = Vec::with_capacity(self.runs);
+        for _ in 0..self.runs {
+            times.push(run()?.0);
+        }
+        times.sort();
+        let millis = |time: Duration| time.as_secs_f64() * 1000.0;
+        let result = Run {
+            exit_code: warm_up.status.code(),
+            best_ms: millis(times[0]),
+            median_ms: millis(times[times.len() / 2]),
+        };
+        Ok((result, warm_up.stdout))
+    }
+}
+
+/// How one binary fared on a corpus.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Run {
+    /// Exit code, `None` when killed by a signal.
+    pub exit_code: Option<i32>,
+    /// Fastest timed run, in milliseconds.
+    pub best_ms: f64,
+    /// Median timed run, in milliseconds.
+    pub median_ms: f64,
+}
+
+/// Both binaries' results on one corpus.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CorpusComparison {
+    /// Name of the corpus.
+    pub corpus: String,
+    /// Go `jd`'s result.
+    pub go: Run,
+    /// The Rust `jd`'s result.
+    pub rust: Run,
+    /// Whether both binaries exited alike and printed identical output.
+    pub outputs_match: bool,
+}
+
+impl CorpusComparison {
+    /// How many times faster the Rust binary's median run is.
+    #[must_use]
+    pub fn speedup(&self) -> f64 {
+        self.go.median_ms / self.rust.median_ms
+    }
+}
+
+/// Results of comparing the binaries on a set of corpora.
+///
+/// ```
+/// use jd_benches::compare::{ComparisonReport, CorpusComparison, Run};
+///
+/// let run = |median_ms| Run { exit_code: Some(1), best_ms: median_ms, median_ms };
+/// let report = ComparisonReport {
+///     args: Vec::new(),
+///     runs: 5,
+///     results: vec![CorpusComparison { corpus: "github-issue".into(), go: run(20.0), rust: run(5.0), outputs_match: true }],
+/// };
+/// assert!(report.to_markdown().contains("| github-issue | 5.00 | 20.00 | 4.00× | match |"));
+/// assert!(report.to_json().contains(r#""outputs_match": true"#));
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ComparisonReport {
+    /// Flags both binaries ran with.
+    pub args: Vec<String>,
+    /// Timed runs per binary and corpus.
+    pub runs: usize,
+    /// One entry per corpus, in registry order.
+    pub results: Vec<CorpusComparison>,
+}
+
+impl ComparisonReport {
+    /// Whether the binaries agreed on every corpus.
+    #[must_use]
+    pub fn all_match(&self) -> bool {
+        self.results.iter().all(|result| result.outputs_match)
+    }
+
+    /// Renders the report as a markdown table of median times.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "Median of {} run(s) of `jd {}`, in milliseconds.\n\n",
+            self.runs,
+            self.args
+                .iter()
+                .map(String::as_str)
+                .chain(["BEFORE", "AFTER"])
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        out.push_str(
+            "| Corpus | Rust | Go | Speedup | Output |\n| --- | ---: | ---: | ---: | --- |\n",
+        );
+        for result in &self.results {
+            let _ = writeln!(
+                out,
+                "| {} | {:.2} | {:.2} | {:.2}× | {} |",
+                result.corpus,
+                result.rust.median_ms,
+                result.go.median_ms,
+                result.speedup(),
+                if result.outputs_match { "match" } else { "DIFFERS" }
+            );
+        }
+        out
+    }
+
+    /// Renders the report as pretty-printed JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("reports serialize")
+    }
+}
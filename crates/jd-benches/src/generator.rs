@@ -0,0 +1,288 @@
+//! Parameterized synthetic documents for scaling benchmarks.
+//!
+//! A [`Workload`] describes a document shape and an edit pattern. The
+//! document is a tree of objects `depth` levels deep with `fan_out` members
+//! per object, whose leaves are arrays of `array_len` numbers and strings.
+//! The "after" document copies it and edits each array element with
+//! probability `change_ratio`, using the workload's [`ChangeKind`]. Output
+//! is fully determined by the parameters and the seed, so runs compare.
+//!
+//! ```
+//! use jd_benches::generator::{ChangeKind, Workload};
+//! use jd_core::DiffOptions;
+//!
+//! let workload = Workload::new().with_depth(0).with_array_len(1000).with_change_kind(ChangeKind::Insert);
+//! let dataset = workload.generate();
+//! assert!(dataset.after().to_json_value().unwrap().as_array().unwrap().len() > 1000);
+//! assert!(!dataset.diff(&DiffOptions::default()).is_empty());
+//! ```
+
+use std::fmt;
+
+use jd_core::Node;
+use serde_json::{json, Map, Value};
+
+use crate::{Corpus, Dataset};
+
+/// How a [`Workload`] edits the array elements it selects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Replace the element with a new value.
+    #[default]
+    Replace,
+    /// Insert a new value after the element.
+    Insert,
+    /// Delete the element.
+    Delete,
+    /// Pick one of the other kinds for each edit.
+    Mixed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Replace => "replace",
+            Self::Insert => "insert",
+            Self::Delete => "delete",
+            Self::Mixed => "mixed",
+        })
+    }
+}
+
+/// Parameters of a generated before/after document pair.
+///
+/// ```
+/// use jd_benches::generator::Workload;
+///
+/// let workload = Workload::new().with_depth(2).with_fan_out(3).with_array_len(10);
+/// assert_eq!(workload.label(), "d2-f3-a10-r0.1-replace");
+/// assert_eq!(workload.generate().before(), workload.generate().before());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workload {
+    depth: usize,
+    fan_out: usize,
+    array_len: usize,
+    change_ratio: f64,
+    change_kind: ChangeKind,
+    seed: u64,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            fan_out: 4,
+            array_len: 16,
+            change_ratio: 0.1,
+            change_kind: ChangeKind::Replace,
+            seed: 0x006a_642d_7273,
+        }
+    }
+}
+
+impl Workload {
+    /// Creates a workload of depth 1, fan-out 4, 16-element arrays, and a
+    /// 10% replacement ratio.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many levels of objects sit above the leaf arrays; 0 makes
+    /// the document a single array.
+    #[must_use]
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets how many members each object has.
+    #[must_use]
+    pub fn with_fan_out(mut self, fan_out: usize) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    /// Sets how many elements each leaf array has.
+    #[must_use]
+    pub fn with_array_len(mut self, array_len: usize) -> Self {
+        self.array_len = array_len;
+        self
+    }
+
+    /// Sets the probability of editing each array element, clamped to
+    /// `0.0..=1.0`.
+    #[must_use]
+    pub fn with_change_ratio(mut self, change_ratio: f64) -> Self {
+        self.change_ratio = change_ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how selected elements are edited.
+    #[must_use]
+    pub fn with_change_kind(mut self, change_kind: ChangeKind) -> Self {
+        self.change_kind = change_kind;
+        self
+    }
+
+    /// Sets the seed of the pseudo-random values and edit choices.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// A short label naming the parameters, for benchmark ids.
+    #[must_use]
+    pub fn label(&self) -> String {
+        format!(
+            "d{}-f{}-a{}-r{}-{}",
+            self.depth, self.fan_out, self.array_len, self.change_ratio, self.change_kind
+        )
+    }
+
+    /// Generates the document pair.
+    #[must_use]
+    pub fn generate(&self) -> Dataset {
+        let (before, after) = self.generate_json();
+        let node = |value| Node::from_json_value(value).expect("generated JSON is canonical");
+        Dataset::new(node(before), node(after))
+    }
+
+    /// Generates the document pair as a corpus named `name`, so it can be
+    /// registered alongside fixture corpora.
+    ///
+    /// ```
+    /// use jd_benches::{generator::Workload, CorpusRegistry};
+    ///
+    /// let workload = Workload::new().with_array_len(64);
+    /// let registry = CorpusRegistry::builtin().with_corpus(workload.to_corpus("synthetic"));
+    /// let corpus = registry.get("synthetic").unwrap();
+    /// assert_eq!(corpus.load().unwrap().before(), workload.generate().before());
+    /// ```
+    #[must_use]
+    pub fn to_corpus(&self, name: impl Into<String>) -> Corpus {
+        let (before, after) = self.generate_json();
+        Corpus::owned(
+            name.into(),
+            format!("Synthetic workload {}.", self.label()),
+            before.to_string(),
+            after.to_string(),
+        )
+    }
+
+    fn generate_json(&self) -> (Value, Value) {
+        let mut rng = XorShift::new(self.seed);
+        let before = self.document(self.depth, &mut rng);
+        let after = self.edit(&before, &mut rng);
+        (before, after)
+    }
+
+    fn document(&self, depth: usize, rng: &mut XorShift) -> Value {
+        if depth == 0 {
+            return Value::Array((0..self.array_len).map(|_| leaf(rng)).collect());
+        }
+        let members = (0..self.fan_out).map(|i| (format!("k{i}"), self.document(depth - 1, rng)));
+        Value::Object(members.collect::<Map<_, _>>())
+    }
+
+    fn edit(&self, value: &Value, rng: &mut XorShift) -> Value {
+        match value {
+            Value::Object(members) => Value::Object(
+                members.iter().map(|(key, value)| (key.clone(), self.edit(value, rng))).collect(),
+            ),
+            Value::Array(items) => {
+                let mut edited = Vec::with_capacity(items.len());
+                for item in items {
+                    if rng.next_f64() >= self.change_ratio {
+                        edited.push(item.clone());
+                        continue;
+                    }
+                    let kind = match self.change_kind {
+                        ChangeKind::Mixed => {
+                            [ChangeKind::Replace, ChangeKind::Insert, ChangeKind::Delete]
+                                [rng.below(3)]
+                        }
+                        kind => kind,
+                    };
+                    match kind {
+                        ChangeKind::Replace => edited.push(leaf(rng)),
+                        ChangeKind::Insert => edited.extend([item.clone(), leaf(rng)]),
+                        _ => {}
+                    }
+                }
+                Value::Array(edited)
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+/// A number or short string; strings keep hashing and equality realistic.
+fn leaf(rng: &mut XorShift) -> Value {
+    let n = rng.next_u64() % 1_000_000;
+    if n % 2 == 0 {
+        json!(n)
+    } else {
+        json!(format!("v{n}"))
+    }
+}
+
+/// Small deterministic generator; benchmarks need stable inputs, not
+/// statistical quality.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jd_core::DiffOptions;
+
+    use super::*;
+
+    #[test]
+    fn change_kinds_shape_the_after_document() {
+        let len = |workload: Workload| {
+            let after = workload.generate().after().to_json_value().unwrap();
+            after.as_array().unwrap().len()
+        };
+        let base = Workload::new().with_depth(0).with_array_len(100).with_change_ratio(1.0);
+        assert_eq!(len(base.clone()), 100);
+        assert_eq!(len(base.clone().with_change_kind(ChangeKind::Insert)), 200);
+        assert_eq!(len(base.clone().with_change_kind(ChangeKind::Delete)), 0);
+        let unchanged = base.with_change_ratio(0.0).generate();
+        assert!(unchanged.diff(&DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn nested_documents_patch_cleanly() {
+        let dataset = Workload::new()
+            .with_depth(3)
+            .with_fan_out(2)
+            .with_change_kind(ChangeKind::Mixed)
+            .with_change_ratio(0.3)
+            .generate();
+        let diff = dataset.diff(&DiffOptions::default());
+        assert_eq!(&dataset.before().apply_patch(&diff).unwrap(), dataset.after());
+    }
+}
@@ -0,0 +1,195 @@
+//! Corpora too large to embed, generated on disk on first use.
+//!
+//! The built-in corpora are compiled into the crate with `include_str!`,
+//! which is fine for kilobytes but would make builds crawl for inputs of
+//! hundreds of megabytes. [`HugeArray`] instead writes its documents to a
+//! cache directory the first time they are needed and reuses them after
+//! that; an [`OnDiskCorpus`] streams them back with
+//! [`Node::from_json_reader`].
+//!
+//! ```
+//! use jd_benches::huge::HugeArray;
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let corpus = HugeArray::new().with_len(1000).with_dir(dir.path()).materialize().unwrap();
+//! assert!(corpus.before_path().starts_with(dir.path()));
+//! let dataset = corpus.load().unwrap();
+//! assert_eq!(dataset.before().to_json_value().unwrap().as_array().unwrap().len(), 1000);
+//! assert_ne!(dataset.before(), dataset.after());
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use jd_core::Node;
+
+use crate::Dataset;
+
+/// Elements in the default `huge-array` corpus; each document is about
+/// 160 MB.
+pub const DEFAULT_LEN: usize = 2_000_000;
+
+/// Environment variable overriding where generated corpora are cached.
+pub const CACHE_DIR_ENV: &str = "JD_BENCH_CACHE_DIR";
+
+/// Every `CHANGE_EVERY`th element differs between the two documents.
+const CHANGE_EVERY: usize = 1000;
+
+/// The `huge-array` corpus: a top-level array of small records, with one in
+/// a thousand changed in the "after" document.
+///
+/// ```
+/// use jd_benches::huge::{HugeArray, DEFAULT_LEN};
+///
+/// assert_eq!(HugeArray::new().len(), DEFAULT_LEN);
+/// assert_eq!(HugeArray::new().with_len(10).len(), 10);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HugeArray {
+    len: usize,
+    dir: PathBuf,
+}
+
+impl Default for HugeArray {
+    fn default() -> Self {
+        let dir = std::env::var_os(CACHE_DIR_ENV).map_or_else(
+            || Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/jd-benches"),
+            PathBuf::from,
+        );
+        Self { len: DEFAULT_LEN, dir }
+    }
+}
+
+impl HugeArray {
+    /// Creates the default corpus of [`DEFAULT_LEN`] elements, cached in
+    /// [`CACHE_DIR_ENV`] or else the workspace's `target/jd-benches`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of array elements.
+    #[must_use]
+    pub fn with_len(mut self, len: usize) -> Self {
+        self.len = len;
+        self
+    }
+
+    /// Sets the cache directory.
+    #[must_use]
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Returns the number of array elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Reports whether the documents are empty arrays.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the documents unless they are already cached, and returns the
+    /// corpus reading them.
+    pub fn materialize(&self) -> io::Result<OnDiskCorpus> {
+        let dir = self.dir.join(format!("huge-array-{}", self.len));
+        let corpus = OnDiskCorpus {
+            name: "huge-array".to_string(),
+            before: dir.join("before.json"),
+            after: dir.join("after.json"),
+        };
+        fs::create_dir_all(&dir)?;
+        for (path, changed) in [(&corpus.before, false), (&corpus.after, true)] {
+            if !path.is_file() {
+                self.write(path, changed)?;
+            }
+        }
+        Ok(corpus)
+    }
+
+    /// Streams one document to `path`, renaming it into place only once
+    /// complete so an interrupted run never leaves a truncated cache.
+    fn write(&self, path: &Path, changed: bool) -> io::Result<()> {
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        out.write_all(b"[")?;
+        for i in 0..self.len {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            let version = if changed && i % CHANGE_EVERY == 0 { 2 } else { 1 };
+            write!(
+                out,
+                r#"{{"id":{i},"name":"item-{i:08}","version":{version},"tags":["t{}","t{}"],"score":{}.5}}"#,
+                i % 7,
+                i % 13,
+                i % 1000,
+            )?;
+        }
+        out.write_all(b"]\n")?;
+        out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(partial, path)
+    }
+}
+
+/// A corpus whose documents stay on disk until loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OnDiskCorpus {
+    name: String,
+    before: PathBuf,
+    after: PathBuf,
+}
+
+impl OnDiskCorpus {
+    /// Returns the short identifier used for benchmark labels.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the "before" document.
+    #[must_use]
+    pub fn before_path(&self) -> &Path {
+        &self.before
+    }
+
+    /// Path of the "after" document.
+    #[must_use]
+    pub fn after_path(&self) -> &Path {
+        &self.after
+    }
+
+    /// Total size in bytes of both documents.
+    pub fn fixture_bytes(&self) -> io::Result<u64> {
+        Ok(fs::metadata(&self.before)?.len() + fs::metadata(&self.after)?.len())
+    }
+
+    /// Streams both documents into canonical nodes.
+    pub fn load(&self) -> io::Result<Dataset> {
+        Ok(Dataset::new(read_json(&self.before)?, read_json(&self.after)?))
+    }
+}
+
+/// Parses the JSON document at `path` without reading it into a string
+/// first.
+///
+/// ```
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("doc.json");
+/// std::fs::write(&path, "[1,2]").unwrap();
+/// assert_eq!(jd_benches::huge::read_json(&path).unwrap(), jd_core::Node::from_json_str("[1,2]").unwrap());
+/// ```
+pub fn read_json(path: impl AsRef<Path>) -> io::Result<Node> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+    Node::from_json_reader(BufReader::new(file)).map_err(|err| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {err}", path.display()))
+    })
+}
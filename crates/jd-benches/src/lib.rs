@@ -21,11 +21,29 @@
 //! let diff = dataset.diff(&DiffOptions::default());
 //! assert!(!diff.is_empty());
 //! ```
-#![forbid(unsafe_code)]
+//!
+//! Benchmarks can also run against your own payloads: a
+//! [`CorpusRegistry`] combines the built-in corpora with datasets loaded
+//! from disk at runtime, such as the directories listed in
+//! [`CORPORA_ENV`].
+#![deny(unsafe_code)]
 #![warn(missing_docs)]
 
+pub mod alloc;
+pub mod baseline;
+pub mod compare;
+pub mod generator;
+pub mod huge;
+mod registry;
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::{fs, io};
+
 use jd_core::{CanonicalizeError, Diff, DiffOptions, Node, RenderConfig};
 
+pub use registry::{CorpusRegistry, CORPORA_ENV};
+
 const KUBERNETES_BEFORE: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/kubernetes/before.json"));
 const KUBERNETES_AFTER: &str =
@@ -38,37 +56,115 @@ const LARGE_ARRAY_BEFORE: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/large-array/before.json"));
 const LARGE_ARRAY_AFTER: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/large-array/after.json"));
+const DUPLICATES_BEFORE: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/duplicates/before.json"));
+const DUPLICATES_AFTER: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/duplicates/after.json"));
 
 /// Identifies a benchmark corpus backed by JSON fixtures.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Corpus {
-    name: &'static str,
-    description: &'static str,
-    before: &'static str,
-    after: &'static str,
+    name: Cow<'static, str>,
+    description: Cow<'static, str>,
+    before: Cow<'static, str>,
+    after: Cow<'static, str>,
 }
 
 impl Corpus {
-    /// Creates a new corpus definition.
+    /// Creates a new corpus definition from embedded fixtures.
     const fn new(
         name: &'static str,
         description: &'static str,
         before: &'static str,
         after: &'static str,
     ) -> Self {
-        Self { name, description, before, after }
+        Self {
+            name: Cow::Borrowed(name),
+            description: Cow::Borrowed(description),
+            before: Cow::Borrowed(before),
+            after: Cow::Borrowed(after),
+        }
+    }
+
+    /// Creates a corpus from JSON texts held in memory.
+    pub(crate) fn owned(name: String, description: String, before: String, after: String) -> Self {
+        Self {
+            name: Cow::Owned(name),
+            description: Cow::Owned(description),
+            before: Cow::Owned(before),
+            after: Cow::Owned(after),
+        }
+    }
+
+    /// Creates a corpus from JSON documents read from `before` and `after`.
+    ///
+    /// The files are read eagerly so that benchmarks never time disk access;
+    /// they are parsed by [`Corpus::load`].
+    ///
+    /// ```
+    /// use jd_benches::Corpus;
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("a.json"), r#"{"n":1}"#).unwrap();
+    /// std::fs::write(dir.path().join("b.json"), r#"{"n":2}"#).unwrap();
+    /// let corpus = Corpus::from_paths("mine", dir.path().join("a.json"), dir.path().join("b.json")).unwrap();
+    /// assert_eq!(corpus.name(), "mine");
+    /// assert_eq!(corpus.fixture_bytes(), 14);
+    /// assert_eq!(corpus.load().unwrap().diff(&Default::default()).len(), 1);
+    /// ```
+    pub fn from_paths(
+        name: impl Into<String>,
+        before: impl AsRef<Path>,
+        after: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let (before, after) = (before.as_ref(), after.as_ref());
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+        };
+        Ok(Self::owned(
+            name.into(),
+            format!("Loaded from {} and {}.", before.display(), after.display()),
+            read(before)?,
+            read(after)?,
+        ))
+    }
+
+    /// Replaces the corpus description.
+    ///
+    /// ```
+    /// # use jd_benches::available_corpora;
+    /// let corpus = available_corpora()[0].clone().with_description("Renamed.");
+    /// assert_eq!(corpus.description(), "Renamed.");
+    /// ```
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Cow::Owned(description.into());
+        self
     }
 
     /// Returns the short identifier used for benchmark labels.
     #[must_use]
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     /// Human-readable summary of what the dataset represents.
     #[must_use]
-    pub fn description(&self) -> &'static str {
-        self.description
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the "before" document as JSON text.
+    #[must_use]
+    pub fn before_json(&self) -> &str {
+        &self.before
+    }
+
+    /// Returns the "after" document as JSON text.
+    #[must_use]
+    pub fn after_json(&self) -> &str {
+        &self.after
     }
 
     /// Returns the total size in bytes of the source fixtures.
@@ -93,8 +189,8 @@ impl Corpus {
     /// ```
     pub fn load(&self) -> Result<Dataset, CanonicalizeError> {
         Ok(Dataset {
-            before: Node::from_json_str(self.before)?,
-            after: Node::from_json_str(self.after)?,
+            before: Node::from_json_str(&self.before)?,
+            after: Node::from_json_str(&self.after)?,
         })
     }
 }
@@ -107,6 +203,20 @@ pub struct Dataset {
 }
 
 impl Dataset {
+    /// Creates a dataset from already canonical documents.
+    ///
+    /// ```
+    /// use jd_benches::Dataset;
+    /// use jd_core::Node;
+    ///
+    /// let dataset = Dataset::new(Node::Null, Node::Bool(true));
+    /// assert_eq!(dataset.diff(&Default::default()).len(), 1);
+    /// ```
+    #[must_use]
+    pub fn new(before: Node, after: Node) -> Self {
+        Self { before, after }
+    }
+
     /// Returns the canonicalized "before" document.
     #[must_use]
     pub fn before(&self) -> &Node {
@@ -172,9 +282,17 @@ const CORPORA: &[Corpus] = &[
         LARGE_ARRAY_BEFORE,
         LARGE_ARRAY_AFTER,
     ),
+    Corpus::new(
+        "duplicate-elements",
+        "Synthetic arrays dominated by repeated values, for set and multiset modes.",
+        DUPLICATES_BEFORE,
+        DUPLICATES_AFTER,
+    ),
 ];
 
-/// Returns the registered benchmark corpora.
+/// Returns the built-in benchmark corpora.
+///
+/// Use [`CorpusRegistry::from_env`] to include corpora supplied at runtime.
 #[must_use]
 pub fn available_corpora() -> &'static [Corpus] {
     CORPORA
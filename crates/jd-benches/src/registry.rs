@@ -0,0 +1,190 @@
+//! Runtime registration of benchmark corpora.
+
+use std::path::Path;
+use std::{env, fs, io, slice};
+
+use crate::{available_corpora, Corpus};
+
+/// Environment variable listing extra corpus directories for
+/// [`CorpusRegistry::from_env`], separated like `PATH`.
+pub const CORPORA_ENV: &str = "JD_BENCH_CORPORA";
+
+const BEFORE: &str = "before.json";
+const AFTER: &str = "after.json";
+
+/// An ordered set of benchmark corpora with unique names.
+///
+/// Registries start empty or from the built-in corpora and grow with
+/// corpora built in code or loaded from directories, so benchmarks can run
+/// against your own payloads without editing this crate.
+///
+/// ```
+/// use jd_benches::{Corpus, CorpusRegistry};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("before.json"), "[1,2,3]").unwrap();
+/// std::fs::write(dir.path().join("after.json"), "[1,3]").unwrap();
+/// let registry = CorpusRegistry::builtin()
+///     .with_corpus(Corpus::from_paths("mine", dir.path().join("before.json"), dir.path().join("after.json")).unwrap());
+/// assert_eq!(registry.corpora().len(), 5);
+/// assert!(registry.get("mine").is_some());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CorpusRegistry {
+    corpora: Vec<Corpus>,
+}
+
+impl CorpusRegistry {
+    /// Creates an empty registry.
+    ///
+    /// ```
+    /// # use jd_benches::CorpusRegistry;
+    /// assert!(CorpusRegistry::new().corpora().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry holding the built-in corpora.
+    ///
+    /// ```
+    /// # use jd_benches::CorpusRegistry;
+    /// assert!(CorpusRegistry::builtin().get("github-issue").is_some());
+    /// ```
+    #[must_use]
+    pub fn builtin() -> Self {
+        Self { corpora: available_corpora().to_vec() }
+    }
+
+    /// Creates a registry holding the built-in corpora plus those found in
+    /// each directory listed in [`CORPORA_ENV`], loaded as by
+    /// [`CorpusRegistry::with_dir`].
+    ///
+    /// ```
+    /// # use jd_benches::{CorpusRegistry, CORPORA_ENV};
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let payload = dir.path().join("payload");
+    /// std::fs::create_dir(&payload).unwrap();
+    /// std::fs::write(payload.join("before.json"), "{}").unwrap();
+    /// std::fs::write(payload.join("after.json"), r#"{"a":1}"#).unwrap();
+    /// std::env::set_var(CORPORA_ENV, dir.path());
+    /// let registry = CorpusRegistry::from_env().unwrap();
+    /// assert!(registry.get("payload").is_some());
+    /// assert!(registry.get("large-array").is_some());
+    /// ```
+    pub fn from_env() -> io::Result<Self> {
+        let mut registry = Self::builtin();
+        if let Some(dirs) = env::var_os(CORPORA_ENV) {
+            for dir in env::split_paths(&dirs).filter(|dir| !dir.as_os_str().is_empty()) {
+                registry = registry.with_dir(dir)?;
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Adds `corpus`, replacing any registered corpus with the same name.
+    ///
+    /// ```
+    /// # use jd_benches::CorpusRegistry;
+    /// let registry = CorpusRegistry::builtin();
+    /// let github = registry.get("github-issue").unwrap().clone().with_description("Mine.");
+    /// let registry = registry.with_corpus(github);
+    /// assert_eq!(registry.corpora().len(), 4);
+    /// assert_eq!(registry.get("github-issue").unwrap().description(), "Mine.");
+    /// ```
+    #[must_use]
+    pub fn with_corpus(mut self, corpus: Corpus) -> Self {
+        match self.corpora.iter_mut().find(|existing| existing.name() == corpus.name()) {
+            Some(existing) => *existing = corpus,
+            None => self.corpora.push(corpus),
+        }
+        self
+    }
+
+    /// Adds the corpora stored under `dir`, laid out like this crate's
+    /// `fixtures` directory.
+    ///
+    /// A directory holding `before.json` and `after.json` is one corpus
+    /// named after the directory; otherwise each such subdirectory is one,
+    /// in name order. Fails when no corpus is found.
+    ///
+    /// ```
+    /// # use jd_benches::CorpusRegistry;
+    /// let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+    /// let registry = CorpusRegistry::new().with_dir(fixtures).unwrap();
+    /// let names: Vec<_> = registry.iter().map(|corpus| corpus.name()).collect();
+    /// assert_eq!(names, ["duplicates", "github", "kubernetes", "large-array"]);
+    /// ```
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut dirs = if dir.join(BEFORE).is_file() && dir.join(AFTER).is_file() {
+            vec![dir.to_path_buf()]
+        } else {
+            let mut dirs = Vec::new();
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.join(BEFORE).is_file() && path.join(AFTER).is_file() {
+                    dirs.push(path);
+                }
+            }
+            dirs
+        };
+        if dirs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}: no {BEFORE} and {AFTER} pairs found", dir.display()),
+            ));
+        }
+        dirs.sort();
+        for dir in dirs {
+            let name = dir.file_name().map_or_else(
+                || dir.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            self = self.with_corpus(Corpus::from_paths(name, dir.join(BEFORE), dir.join(AFTER))?);
+        }
+        Ok(self)
+    }
+
+    /// Returns the registered corpora in registration order.
+    #[must_use]
+    pub fn corpora(&self) -> &[Corpus] {
+        &self.corpora
+    }
+
+    /// Looks up a corpus by name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Corpus> {
+        self.corpora.iter().find(|corpus| corpus.name() == name)
+    }
+
+    /// Iterates over the registered corpora in registration order.
+    pub fn iter(&self) -> slice::Iter<'_, Corpus> {
+        self.corpora.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CorpusRegistry {
+    type Item = &'a Corpus;
+    type IntoIter = slice::Iter<'a, Corpus>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_without_corpora_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("half")).unwrap();
+        fs::write(dir.path().join("half").join(BEFORE), "{}").unwrap();
+        let err = CorpusRegistry::new().with_dir(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(CorpusRegistry::new().with_dir(dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use jd_benches::alloc::{is_counting, measure};
+use jd_benches::generator::Workload;
+use jd_core::DiffOptions;
+
+#[cfg(not(feature = "count-allocations"))]
+#[global_allocator]
+static ALLOCATOR: jd_benches::alloc::CountingAllocator = jd_benches::alloc::CountingAllocator;
+
+#[test]
+fn measures_allocations_and_peak() {
+    assert!(is_counting());
+    let (buffer, stats) = measure(|| {
+        let mut buffer = Vec::with_capacity(1000);
+        buffer.extend(0..2000_u32);
+        buffer
+    });
+    assert!(stats.allocations >= 2, "{stats:?}");
+    assert!(stats.bytes >= 12_000, "{stats:?}");
+    assert!(stats.peak_bytes >= buffer.capacity() * 4, "{stats:?}");
+
+    let (_, nothing) = measure(|| 1 + 1);
+    assert_eq!(nothing.allocations, 0);
+}
+
+#[test]
+fn list_diff_peak_grows_with_array_length() {
+    let peak = |len| {
+        let dataset = Workload::new().with_depth(0).with_array_len(len).generate();
+        measure(|| dataset.diff(&DiffOptions::default())).1.peak_bytes
+    };
+    assert!(peak(400) > peak(100));
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::Path;
+
+use jd_benches::baseline::{Baseline, Outcome};
+
+const BASELINE: &str = r#"{
+  "metadata": { "tolerance": 1.25, "unit": "ns" },
+  "benchmarks": {
+    "diff": { "github-issue": 100.0, "large-array": 1000.0 },
+    "patch-apply": { "github-issue": 50.0 }
+  }
+}"#;
+
+/// Saves a Criterion-shaped `estimates.json` with the given median.
+fn save_estimate(root: &Path, group: &str, bench: &str, median: f64) {
+    let dir = root.join(group).join(bench).join("current");
+    fs::create_dir_all(&dir).unwrap();
+    let estimates = format!(
+        r#"{{"mean":{{"point_estimate":{median}}},"median":{{"point_estimate":{median},"standard_error":1.0}}}}"#
+    );
+    fs::write(dir.join("estimates.json"), estimates).unwrap();
+}
+
+#[test]
+fn passes_within_tolerance() {
+    let results = tempfile::tempdir().unwrap();
+    save_estimate(results.path(), "diff", "github-issue", 120.0);
+    save_estimate(results.path(), "diff", "large-array", 900.0);
+    save_estimate(results.path(), "patch-apply", "github-issue", 50.0);
+
+    let report =
+        Baseline::from_json_str(BASELINE).unwrap().check(results.path(), "current").unwrap();
+    assert!(report.passed());
+    assert_eq!(report.checks.len(), 3);
+    assert_eq!(
+        report.checks[0].to_string(),
+        "ok diff/github-issue: actual 120.000 ns vs baseline 100.000 ns (1.20x)"
+    );
+}
+
+#[test]
+fn reports_regressions_and_missing_results() {
+    let results = tempfile::tempdir().unwrap();
+    save_estimate(results.path(), "diff", "github-issue", 130.0);
+    save_estimate(results.path(), "diff", "large-array", 900.0);
+
+    let baseline = Baseline::from_json_str(BASELINE).unwrap();
+    let report = baseline.check(results.path(), "current").unwrap();
+    assert!(!report.passed());
+    let failures: Vec<_> = report.failures().map(|check| check.bench.as_str()).collect();
+    assert_eq!(failures, ["github-issue", "github-issue"]);
+    assert!(matches!(report.checks[0].outcome, Outcome::Regressed { ratio, .. } if ratio > 1.25));
+    assert!(matches!(report.checks[2].outcome, Outcome::Missing { .. }));
+
+    let report = baseline.with_tolerance(1.5).check(results.path(), "current").unwrap();
+    assert_eq!(report.failures().count(), 1);
+}
+
+#[test]
+fn malformed_inputs_are_errors() {
+    let results = tempfile::tempdir().unwrap();
+    let dir = results.path().join("diff").join("github-issue").join("current");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("estimates.json"), "{}").unwrap();
+    let err =
+        Baseline::from_json_str(BASELINE).unwrap().check(results.path(), "current").unwrap_err();
+    assert!(err.to_string().contains("invalid estimates file"));
+
+    assert!(Baseline::from_json_str(r#"{"metadata":{}}"#).is_err());
+    assert!(Baseline::load(results.path().join("missing.json")).is_err());
+}
+
+#[test]
+fn committed_baseline_loads() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/baselines/criterion-ci.json");
+    let baseline = Baseline::load(path).unwrap();
+    assert_eq!(baseline.tolerance(), 1.25);
+    assert!(baseline.median("diff", "github-issue").is_some());
+}
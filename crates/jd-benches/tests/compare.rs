@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use jd_benches::compare::Comparer;
+use jd_benches::CorpusRegistry;
+
+/// Writes an executable shell script standing in for a `jd` binary.
+fn fake_jd(dir: &Path, name: &str, body: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn reports_timings_and_output_parity() {
+    let bin = tempfile::tempdir().unwrap();
+    let go = fake_jd(bin.path(), "go", r#"echo "$@" | sed 's|[^ ]*/||g'; exit 1"#);
+    let same = fake_jd(bin.path(), "same", r#"echo "$@" | sed 's|[^ ]*/||g'; exit 1"#);
+    let other = fake_jd(bin.path(), "other", "echo different; exit 1");
+    let registry = CorpusRegistry::builtin();
+
+    let report = Comparer::new(&go, &same)
+        .with_runs(2)
+        .with_args(["-f", "patch"])
+        .compare_all(&registry)
+        .unwrap();
+    assert!(report.all_match());
+    assert_eq!(report.results.len(), registry.corpora().len());
+    let first = &report.results[0];
+    assert_eq!(first.rust.exit_code, Some(1));
+    assert!(first.rust.best_ms <= first.rust.median_ms);
+    assert!(report.to_markdown().contains("`jd -f patch BEFORE AFTER`"));
+
+    let report = Comparer::new(&go, &other).with_runs(1).compare_all(&registry).unwrap();
+    assert!(!report.all_match());
+    assert!(report.to_markdown().contains("DIFFERS"));
+}
+
+#[test]
+fn missing_binaries_are_errors() {
+    let registry = CorpusRegistry::builtin();
+    let comparer = Comparer::new("/nonexistent/go-jd", "/nonexistent/jd");
+    let err = comparer.compare(&registry.corpora()[0]).unwrap_err();
+    assert!(err.to_string().contains("/nonexistent/go-jd"));
+}
@@ -0,0 +1,126 @@
+/* jd: structural diff and patch for JSON and YAML. Generated by cbindgen; do not edit. */
+
+#ifndef JD_H
+#define JD_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call.
+typedef enum JdStatus {
+  // The call succeeded.
+  JD_STATUS_OK = 0,
+  // A required pointer was null or a string was not UTF-8.
+  JD_STATUS_INVALID_ARGUMENT = 1,
+  // A document is not valid JSON or YAML.
+  JD_STATUS_PARSE = 2,
+  // The options are invalid.
+  JD_STATUS_OPTIONS = 3,
+  // The diff could not be computed.
+  JD_STATUS_DIFF = 4,
+  // A diff could not be read in the given format.
+  JD_STATUS_READ = 5,
+  // A diff could not be rendered in the requested format.
+  JD_STATUS_RENDER = 6,
+  // A diff does not apply to the document.
+  JD_STATUS_PATCH = 7,
+  // jd panicked; this is a bug.
+  JD_STATUS_PANIC = 8,
+} JdStatus;
+
+// Diff text formats.
+typedef enum JdFormat {
+  // The native jd format.
+  JD_FORMAT_NATIVE = 0,
+  // RFC 6902 JSON Patch.
+  JD_FORMAT_PATCH = 1,
+  // RFC 7386 JSON Merge Patch.
+  JD_FORMAT_MERGE = 2,
+} JdFormat;
+
+// An opaque diff, created by [`jd_diff`] or [`jd_diff_read`] and released
+// with [`jd_diff_free`].
+typedef struct JdDiff JdDiff;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Diffs document `a` against document `b`, each JSON or YAML.
+//
+// `opts` is null for the defaults or an options array in the format of
+// `jd -opts`, such as `["MERGE",{"precision":0.01}]`. On success `*out`
+// receives a diff to release with [`jd_diff_free`].
+//
+// # Safety
+//
+// `a`, `b`, and a non-null `opts` must be NUL-terminated strings, and
+// `out` must be valid for writes.
+enum JdStatus jd_diff(const char *a, const char *b, const char *opts, struct JdDiff **out);
+
+// Reads a diff written in `format`. On success `*out` receives a diff to
+// release with [`jd_diff_free`].
+//
+// # Safety
+//
+// `input` must be a NUL-terminated string and `out` must be valid for
+// writes.
+enum JdStatus jd_diff_read(const char *input, enum JdFormat format, struct JdDiff **out);
+
+// Reports whether `diff` has no hunks, that is whether the documents it was
+// computed from are equal. Null counts as empty.
+//
+// # Safety
+//
+// `diff` must be null or a live diff.
+bool jd_diff_is_empty(const struct JdDiff *diff);
+
+// Renders `diff` in `format`. On success `*out` receives a string to
+// release with [`jd_string_free`].
+//
+// # Safety
+//
+// `diff` must be a live diff and `out` must be valid for writes.
+enum JdStatus jd_render(const struct JdDiff *diff, enum JdFormat format, char **out);
+
+// Applies `diff` to document `doc`, JSON or YAML. On success `*out`
+// receives the patched document as JSON, to release with
+// [`jd_string_free`]. A diff that removes the whole document leaves no
+// document, which is written as the empty string; a document that is
+// JSON `null` is written as `null`.
+//
+// # Safety
+//
+// `doc` must be a NUL-terminated string, `diff` a live diff, and `out`
+// valid for writes.
+enum JdStatus jd_patch(const char *doc, const struct JdDiff *diff, char **out);
+
+// Describes the last failed call on this thread, or returns null when the
+// last call other than [`jd_diff_free`] and [`jd_string_free`] succeeded,
+// so a diff can be freed before reporting its failure. The string is owned by jd and valid until the next
+// call on the same thread; do not free it.
+const char *jd_last_error(void);
+
+// Releases a diff. Null is ignored.
+//
+// # Safety
+//
+// `diff` must be null or a diff not yet released.
+void jd_diff_free(struct JdDiff *diff);
+
+// Releases a string returned by jd. Null is ignored.
+//
+// # Safety
+//
+// `text` must be null or a string from [`jd_render`] or [`jd_patch`] not
+// yet released.
+void jd_string_free(char *text);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* JD_H */
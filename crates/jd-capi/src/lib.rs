@@ -0,0 +1,340 @@
+//! C bindings for the Rust port of the `jd` tool.
+//!
+//! The functions here embed `jd-core` in C, C++, Swift, and any other
+//! language with a C FFI. `include/jd.h` declares them; it is generated by
+//! cbindgen and checked against this crate by the test suite.
+//!
+//! # Conventions
+//!
+//! - Every fallible function returns a [`JdStatus`], `JD_STATUS_OK` on
+//!   success, and writes its result through an out pointer only on success.
+//!   [`jd_last_error`] describes the most recent failure on the calling
+//!   thread. Every other call except the `_free` functions clears it when
+//!   it succeeds.
+//! - Strings passed in are NUL-terminated UTF-8 and are only borrowed for
+//!   the duration of the call.
+//! - Results are owned by the caller: release strings with
+//!   [`jd_string_free`] and diffs with [`jd_diff_free`]. Both accept null.
+//! - Panics never unwind into C; they are reported as `JD_STATUS_PANIC`.
+//!
+//! ```
+//! use std::ffi::{CStr, CString};
+//! use std::ptr;
+//! use jd_capi::*;
+//!
+//! let (a, b) = (CString::new(r#"{"a":1}"#).unwrap(), CString::new(r#"{"a":2}"#).unwrap());
+//! let mut diff = ptr::null_mut();
+//! let mut out = ptr::null_mut();
+//! unsafe {
+//!     assert_eq!(jd_diff(a.as_ptr(), b.as_ptr(), ptr::null(), &mut diff), JdStatus::Ok);
+//!     assert_eq!(jd_render(diff, JdFormat::Native, &mut out), JdStatus::Ok);
+//!     assert_eq!(CStr::from_ptr(out).to_str().unwrap(), "@ [\"a\"]\n- 1\n+ 2\n");
+//!     jd_string_free(out);
+//!     jd_diff_free(diff);
+//! }
+//! ```
+#![deny(unsafe_op_in_unsafe_fn)]
+#![warn(missing_docs)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use jd_core::{Diff, DiffOptions, Jd, JdError, Node, RenderConfig};
+
+/// Outcome of a call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JdStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null or a string was not UTF-8.
+    InvalidArgument = 1,
+    /// A document is not valid JSON or YAML.
+    Parse = 2,
+    /// The options are invalid.
+    Options = 3,
+    /// The diff could not be computed.
+    Diff = 4,
+    /// A diff could not be read in the given format.
+    Read = 5,
+    /// A diff could not be rendered in the requested format.
+    Render = 6,
+    /// A diff does not apply to the document.
+    Patch = 7,
+    /// jd panicked; this is a bug.
+    Panic = 8,
+}
+
+/// Diff text formats.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JdFormat {
+    /// The native jd format.
+    Native = 0,
+    /// RFC 6902 JSON Patch.
+    Patch = 1,
+    /// RFC 7386 JSON Merge Patch.
+    Merge = 2,
+}
+
+/// An opaque diff, created by [`jd_diff`] or [`jd_diff_read`] and released
+/// with [`jd_diff_free`].
+pub struct JdDiff(Diff);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failed call: its status and message.
+struct Failure(JdStatus, String);
+
+impl Failure {
+    fn new(status: JdStatus, message: impl ToString) -> Self {
+        Self(status, message.to_string())
+    }
+}
+
+impl From<JdError> for Failure {
+    fn from(err: JdError) -> Self {
+        let status = match err {
+            JdError::Canonicalize(_) => JdStatus::Parse,
+            JdError::Options(_) => JdStatus::Options,
+            _ => JdStatus::Diff,
+        };
+        Self::new(status, err)
+    }
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|last| last.borrow_mut().take());
+}
+
+/// Runs `f`, stores its result in `*out` on success, and records the
+/// message of a failure or panic for [`jd_last_error`].
+fn call<T>(out: *mut T, f: impl FnOnce() -> Result<T, Failure>) -> JdStatus {
+    if out.is_null() {
+        return fail(Failure::new(JdStatus::InvalidArgument, "output pointer is null"));
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            // SAFETY: `out` is non-null and the caller guarantees it is
+            // valid for writes.
+            unsafe { out.write(value) };
+            clear_error();
+            JdStatus::Ok
+        }
+        Ok(Err(failure)) => fail(failure),
+        Err(_) => fail(Failure::new(JdStatus::Panic, "jd panicked")),
+    }
+}
+
+fn fail(Failure(status, message): Failure) -> JdStatus {
+    // Interior NULs cannot cross the C boundary; drop them.
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    status
+}
+
+/// Borrows a required C string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives the
+/// returned borrow.
+unsafe fn arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(Failure::new(JdStatus::InvalidArgument, format!("{name} is null")));
+    }
+    // SAFETY: non-null, and valid per the caller's contract.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| Failure::new(JdStatus::InvalidArgument, format!("{name} is not UTF-8")))
+}
+
+/// Borrows a required diff argument.
+///
+/// # Safety
+///
+/// `diff` must be null or a live pointer from [`jd_diff`] or
+/// [`jd_diff_read`].
+unsafe fn diff_arg<'a>(diff: *const JdDiff) -> Result<&'a Diff, Failure> {
+    // SAFETY: null or live per the caller's contract.
+    unsafe { diff.as_ref() }
+        .map(|diff| &diff.0)
+        .ok_or_else(|| Failure::new(JdStatus::InvalidArgument, "diff is null"))
+}
+
+fn into_c_string(text: String) -> Result<*mut c_char, Failure> {
+    CString::new(text)
+        .map(CString::into_raw)
+        .map_err(|_| Failure::new(JdStatus::Render, "output contains a NUL byte"))
+}
+
+/// Parses a document as JSON, or as YAML when it is not JSON, reporting the
+/// JSON error when both fail.
+fn parse_document(input: &str) -> Result<Node, Failure> {
+    Node::from_json_str(input)
+        .or_else(|err| Node::from_yaml_str(input).map_err(|_| err))
+        .map_err(|err| Failure::new(JdStatus::Parse, err))
+}
+
+/// Diffs document `a` against document `b`, each JSON or YAML.
+///
+/// `opts` is null for the defaults or an options array in the format of
+/// `jd -opts`, such as `["MERGE",{"precision":0.01}]`. On success `*out`
+/// receives a diff to release with [`jd_diff_free`].
+///
+/// # Safety
+///
+/// `a`, `b`, and a non-null `opts` must be NUL-terminated strings, and
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jd_diff(
+    a: *const c_char,
+    b: *const c_char,
+    opts: *const c_char,
+    out: *mut *mut JdDiff,
+) -> JdStatus {
+    call(out, || {
+        // SAFETY: guaranteed by the caller.
+        let (a, b) = unsafe { (arg(a, "a")?, arg(b, "b")?) };
+        let options = if opts.is_null() {
+            DiffOptions::default()
+        } else {
+            // SAFETY: non-null, so a NUL-terminated string per the contract.
+            DiffOptions::from_json_opts(unsafe { arg(opts, "opts")? })
+                .map_err(|err| Failure::new(JdStatus::Options, err))?
+        };
+        let diff = Jd::with_options(options).diff(&parse_document(a)?, &parse_document(b)?)?;
+        Ok(Box::into_raw(Box::new(JdDiff(diff))))
+    })
+}
+
+/// Reads a diff written in `format`. On success `*out` receives a diff to
+/// release with [`jd_diff_free`].
+///
+/// # Safety
+///
+/// `input` must be a NUL-terminated string and `out` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn jd_diff_read(
+    input: *const c_char,
+    format: JdFormat,
+    out: *mut *mut JdDiff,
+) -> JdStatus {
+    call(out, || {
+        // SAFETY: guaranteed by the caller.
+        let input = unsafe { arg(input, "input")? };
+        let diff = match format {
+            JdFormat::Native => Diff::from_native_str(input),
+            JdFormat::Patch => Diff::from_patch_str(input),
+            JdFormat::Merge => Diff::from_merge_str(input),
+        }
+        .map_err(|err| Failure::new(JdStatus::Read, err))?;
+        Ok(Box::into_raw(Box::new(JdDiff(diff))))
+    })
+}
+
+/// Reports whether `diff` has no hunks, that is whether the documents it was
+/// computed from are equal. Null counts as empty.
+///
+/// # Safety
+///
+/// `diff` must be null or a live diff.
+#[no_mangle]
+pub unsafe extern "C" fn jd_diff_is_empty(diff: *const JdDiff) -> bool {
+    clear_error();
+    // SAFETY: guaranteed by the caller.
+    unsafe { diff.as_ref() }.is_none_or(|diff| diff.0.is_empty())
+}
+
+/// Renders `diff` in `format`. On success `*out` receives a string to
+/// release with [`jd_string_free`].
+///
+/// # Safety
+///
+/// `diff` must be a live diff and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jd_render(
+    diff: *const JdDiff,
+    format: JdFormat,
+    out: *mut *mut c_char,
+) -> JdStatus {
+    call(out, || {
+        // SAFETY: guaranteed by the caller.
+        let diff = unsafe { diff_arg(diff)? };
+        let text = match format {
+            JdFormat::Native => Ok(diff.render(&RenderConfig::default())),
+            JdFormat::Patch => diff.render_patch(),
+            JdFormat::Merge => diff.render_merge(),
+        }
+        .map_err(|err| Failure::new(JdStatus::Render, err))?;
+        into_c_string(text)
+    })
+}
+
+/// Applies `diff` to document `doc`, JSON or YAML. On success `*out`
+/// receives the patched document as JSON, to release with
+/// [`jd_string_free`]. A diff that removes the whole document leaves no
+/// document, which is written as the empty string; a document that is
+/// JSON `null` is written as `null`.
+///
+/// # Safety
+///
+/// `doc` must be a NUL-terminated string, `diff` a live diff, and `out`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jd_patch(
+    doc: *const c_char,
+    diff: *const JdDiff,
+    out: *mut *mut c_char,
+) -> JdStatus {
+    call(out, || {
+        // SAFETY: guaranteed by the caller.
+        let (doc, diff) = unsafe { (arg(doc, "doc")?, diff_arg(diff)?) };
+        let patched = parse_document(doc)?
+            .apply_patch(diff)
+            .map_err(|err| Failure::new(JdStatus::Patch, err))?;
+        // Only the void node has no JSON value.
+        into_c_string(patched.to_json_value().map_or_else(String::new, |value| value.to_string()))
+    })
+}
+
+/// Describes the last failed call on this thread, or returns null when the
+/// last call other than [`jd_diff_free`] and [`jd_string_free`] succeeded,
+/// so a diff can be freed before reporting its failure. The string is owned by jd and valid until the next
+/// call on the same thread; do not free it.
+#[no_mangle]
+pub extern "C" fn jd_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Releases a diff. Null is ignored.
+///
+/// # Safety
+///
+/// `diff` must be null or a diff not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn jd_diff_free(diff: *mut JdDiff) {
+    if !diff.is_null() {
+        // SAFETY: allocated by `Box::into_raw` and not yet released.
+        drop(unsafe { Box::from_raw(diff) });
+    }
+}
+
+/// Releases a string returned by jd. Null is ignored.
+///
+/// # Safety
+///
+/// `text` must be null or a string from [`jd_render`] or [`jd_patch`] not
+/// yet released.
+#[no_mangle]
+pub unsafe extern "C" fn jd_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        // SAFETY: allocated by `CString::into_raw` and not yet released.
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
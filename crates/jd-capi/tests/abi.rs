@@ -0,0 +1,162 @@
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use jd_capi::*;
+
+fn c(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+/// Takes ownership of a string returned by the API.
+unsafe fn take(text: *mut c_char) -> String {
+    let owned = unsafe { CStr::from_ptr(text) }.to_str().unwrap().to_owned();
+    unsafe { jd_string_free(text) };
+    owned
+}
+
+fn last_error() -> Option<String> {
+    let message = jd_last_error();
+    (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) }.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn discriminants_match_the_header() {
+    let header = include_str!("../include/jd.h");
+    for (name, value) in [
+        ("JD_STATUS_OK", JdStatus::Ok as i32),
+        ("JD_STATUS_INVALID_ARGUMENT", JdStatus::InvalidArgument as i32),
+        ("JD_STATUS_PARSE", JdStatus::Parse as i32),
+        ("JD_STATUS_PANIC", JdStatus::Panic as i32),
+        ("JD_FORMAT_NATIVE", JdFormat::Native as i32),
+        ("JD_FORMAT_MERGE", JdFormat::Merge as i32),
+    ] {
+        assert!(header.contains(&format!("{name} = {value},")), "{name}");
+    }
+    assert_eq!(std::mem::size_of::<JdStatus>(), std::mem::size_of::<i32>());
+    assert_eq!(std::mem::size_of::<JdFormat>(), std::mem::size_of::<i32>());
+}
+
+#[test]
+fn diff_render_and_patch_round_trip() {
+    let (a, b) = (c(r#"{"a":1,"b":[1,2]}"#), c("a: 1\nb: [1, 3]\n"));
+    let mut diff = ptr::null_mut();
+    unsafe {
+        assert_eq!(jd_diff(a.as_ptr(), b.as_ptr(), ptr::null(), &mut diff), JdStatus::Ok);
+        assert!(!jd_diff_is_empty(diff));
+
+        let mut text = ptr::null_mut();
+        assert_eq!(jd_render(diff, JdFormat::Patch, &mut text), JdStatus::Ok);
+        let patch = c(&take(text));
+        let mut read = ptr::null_mut();
+        assert_eq!(jd_diff_read(patch.as_ptr(), JdFormat::Patch, &mut read), JdStatus::Ok);
+
+        assert_eq!(jd_patch(a.as_ptr(), read, &mut text), JdStatus::Ok);
+        assert_eq!(take(text), r#"{"a":1,"b":[1,3]}"#);
+        assert!(last_error().is_none());
+        jd_diff_free(read);
+        jd_diff_free(diff);
+    }
+}
+
+#[test]
+fn removing_the_document_patches_to_the_empty_string() {
+    let mut diff = ptr::null_mut();
+    let mut text = ptr::null_mut();
+    unsafe {
+        let removal = c("@ []\n- {\"a\":1}\n");
+        assert_eq!(jd_diff_read(removal.as_ptr(), JdFormat::Native, &mut diff), JdStatus::Ok);
+        assert_eq!(jd_patch(c(r#"{"a":1}"#).as_ptr(), diff, &mut text), JdStatus::Ok);
+        assert_eq!(take(text), "");
+        jd_diff_free(diff);
+
+        let nulled = c("@ []\n- {\"a\":1}\n+ null\n");
+        assert_eq!(jd_diff_read(nulled.as_ptr(), JdFormat::Native, &mut diff), JdStatus::Ok);
+        assert_eq!(jd_patch(c(r#"{"a":1}"#).as_ptr(), diff, &mut text), JdStatus::Ok);
+        assert_eq!(take(text), "null");
+        jd_diff_free(diff);
+    }
+}
+
+#[test]
+fn options_apply() {
+    let (a, b) = (c(r#"{"pi":3.14}"#), c(r#"{"pi":3.141}"#));
+    let opts = c(r#"[{"precision":0.01}]"#);
+    let mut diff = ptr::null_mut();
+    unsafe {
+        assert_eq!(jd_diff(a.as_ptr(), b.as_ptr(), opts.as_ptr(), &mut diff), JdStatus::Ok);
+        assert!(jd_diff_is_empty(diff));
+        jd_diff_free(diff);
+        let bad = c(r#"["FAST"]"#);
+        assert_eq!(jd_diff(a.as_ptr(), b.as_ptr(), bad.as_ptr(), &mut diff), JdStatus::Options);
+    }
+}
+
+#[test]
+fn failures_set_status_and_message_without_writing_output() {
+    let (doc, bad) = (c(r#"{"a":1}"#), c("{"));
+    let mut diff = ptr::null_mut();
+    unsafe {
+        assert_eq!(jd_diff(bad.as_ptr(), doc.as_ptr(), ptr::null(), &mut diff), JdStatus::Parse);
+        assert!(diff.is_null());
+        assert!(last_error().unwrap().contains("JSON"));
+
+        assert_eq!(
+            jd_diff(ptr::null(), doc.as_ptr(), ptr::null(), &mut diff),
+            JdStatus::InvalidArgument
+        );
+        assert_eq!(last_error().unwrap(), "a is null");
+        assert_eq!(
+            jd_diff(doc.as_ptr(), doc.as_ptr(), ptr::null(), ptr::null_mut()),
+            JdStatus::InvalidArgument
+        );
+
+        let stale = c("@ [\"a\"]\n- 2\n+ 3\n");
+        assert_eq!(jd_diff_read(stale.as_ptr(), JdFormat::Native, &mut diff), JdStatus::Ok);
+        let mut text = ptr::null_mut();
+        assert_eq!(jd_patch(doc.as_ptr(), diff, &mut text), JdStatus::Patch);
+        assert!(text.is_null());
+        assert!(!jd_diff_is_empty(diff));
+        assert!(last_error().is_none());
+        assert_eq!(jd_render(ptr::null(), JdFormat::Native, &mut text), JdStatus::InvalidArgument);
+        jd_diff_free(diff);
+        assert_eq!(last_error().unwrap(), "diff is null");
+
+        let garbage = c("not a diff");
+        assert_eq!(jd_diff_read(garbage.as_ptr(), JdFormat::Native, &mut diff), JdStatus::Read);
+        jd_diff_free(ptr::null_mut());
+        jd_string_free(ptr::null_mut());
+    }
+}
+
+/// Compiles `tests/c/smoke.c` against the header and the static library,
+/// when a C compiler is available, and runs it.
+#[cfg(unix)]
+#[test]
+fn c_program_links_and_runs() {
+    use std::path::Path;
+    use std::process::Command;
+
+    // Cargo builds the static library next to the test binary in `deps`.
+    let exe = std::env::current_exe().unwrap();
+    let lib = exe.with_file_name("libjd_capi.a");
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    if !lib.is_file() || Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("skipping: needs cc and {}", lib.display());
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("smoke");
+    let status = Command::new("cc")
+        .arg(crate_dir.join("tests/c/smoke.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(&lib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling smoke.c failed");
+    let output = Command::new(&program).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"ok\n");
+}
@@ -0,0 +1,48 @@
+/* Exercises the C API through the generated header. Exits non-zero on the
+ * first failed check. */
+#include <stdio.h>
+#include <string.h>
+
+#include "jd.h"
+
+#define CHECK(cond)                                                  \
+  do {                                                               \
+    if (!(cond)) {                                                   \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,         \
+              __LINE__, #cond);                                      \
+      return 1;                                                      \
+    }                                                                \
+  } while (0)
+
+int main(void) {
+  JdDiff *diff = NULL;
+  char *text = NULL;
+
+  CHECK(jd_diff("{\"a\":1}", "{\"a\":2}", NULL, &diff) == JD_STATUS_OK);
+  CHECK(!jd_diff_is_empty(diff));
+  CHECK(jd_last_error() == NULL);
+
+  CHECK(jd_render(diff, JD_FORMAT_NATIVE, &text) == JD_STATUS_OK);
+  CHECK(strcmp(text, "@ [\"a\"]\n- 1\n+ 2\n") == 0);
+  jd_string_free(text);
+
+  CHECK(jd_patch("{\"a\":1}", diff, &text) == JD_STATUS_OK);
+  CHECK(strcmp(text, "{\"a\":2}") == 0);
+  jd_string_free(text);
+  jd_diff_free(diff);
+
+  CHECK(jd_diff("{", "{}", NULL, &diff) == JD_STATUS_PARSE);
+  CHECK(jd_last_error() != NULL && strstr(jd_last_error(), "JSON") != NULL);
+  CHECK(jd_diff(NULL, "{}", NULL, &diff) == JD_STATUS_INVALID_ARGUMENT);
+
+  CHECK(jd_diff_read("{\"a\":null}", JD_FORMAT_MERGE, &diff) == JD_STATUS_OK);
+  CHECK(jd_render(diff, JD_FORMAT_MERGE, &text) == JD_STATUS_OK);
+  CHECK(strcmp(text, "{\"a\":null}") == 0);
+  jd_string_free(text);
+  jd_diff_free(diff);
+
+  jd_diff_free(NULL);
+  jd_string_free(NULL);
+  puts("ok");
+  return 0;
+}
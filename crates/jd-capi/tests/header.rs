@@ -0,0 +1,26 @@
+//! Keeps the committed C header in sync with the exported functions.
+
+use std::path::Path;
+
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("cbindgen parses the crate")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    let path = crate_dir.join("include/jd.h");
+    if std::env::var_os("JD_CAPI_UPDATE_HEADER").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "include/jd.h is stale; run `JD_CAPI_UPDATE_HEADER=1 cargo test -p jd-capi --test header`"
+    );
+}
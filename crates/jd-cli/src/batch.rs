@@ -0,0 +1,100 @@
+//! File helpers for patching documents on disk (`--glob`, `--in-place`).
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::diagnostics::{Diagnostic, ErrorCode};
+
+/// Expands a glob pattern (with `**` support) into a sorted list of files.
+pub(crate) fn expand(pattern: &str) -> Result<Vec<PathBuf>> {
+    let entries = glob::glob(pattern).map_err(|err| {
+        Diagnostic::new(ErrorCode::Usage, format!("invalid --glob pattern {pattern:?}: {err}"))
+    })?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|err| {
+            Diagnostic::new(ErrorCode::Io, format!("failed to read {}", err.path().display()))
+                .with_input(err.path().display().to_string())
+        })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        bail!(Diagnostic::new(
+            ErrorCode::Usage,
+            format!("--glob pattern {pattern:?} matched no files")
+        ));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Path of the backup written next to `path` (`FILE.bak`).
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Replaces `path` with `contents` atomically, keeping its permissions.
+///
+/// The new contents are written to a temporary file in the same directory and
+/// renamed over `path`, so readers never observe a partially written file.
+pub(crate) fn write_in_place(path: &Path, contents: &[u8], backup: bool) -> Result<()> {
+    let failed = || {
+        Diagnostic::new(ErrorCode::Io, format!("failed to write output to {}", path.display()))
+            .with_input(path.display().to_string())
+    };
+    if backup {
+        let backup = backup_path(path);
+        fs::copy(path, &backup).with_context(|| {
+            Diagnostic::new(ErrorCode::Io, format!("failed to write backup {}", backup.display()))
+                .with_input(path.display().to_string())
+        })?;
+    }
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let permissions = fs::metadata(path).with_context(failed)?.permissions();
+    let mut temp = tempfile::NamedTempFile::new_in(dir).with_context(failed)?;
+    temp.write_all(contents).with_context(failed)?;
+    fs::set_permissions(temp.path(), permissions).with_context(failed)?;
+    temp.persist(path).map_err(|err| err.error).with_context(failed)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_in_place_replaces_contents_and_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.json");
+        fs::write(&path, "{\"a\":1}").unwrap();
+
+        write_in_place(&path, b"{\"a\":2}", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\":2}");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "{\"a\":1}");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn expand_sorts_matches_and_rejects_empty_results() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested/b.json"), "{}").unwrap();
+        fs::write(dir.path().join("a.json"), "{}").unwrap();
+
+        let pattern = format!("{}/**/*.json", dir.path().display());
+        let files = expand(&pattern).unwrap();
+        assert_eq!(files, [dir.path().join("a.json"), dir.path().join("nested/b.json")]);
+
+        let pattern = format!("{}/*.yaml", dir.path().display());
+        let err = expand(&pattern).unwrap_err();
+        assert_eq!(err.to_string(), format!("--glob pattern {pattern:?} matched no files"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Default flags from the user config file and the `JD_OPTS` environment variable.
+//!
+//! Defaults are turned into ordinary `--long` arguments and inserted ahead of
+//! the command-line arguments, so clap's "last occurrence wins" rule gives the
+//! precedence config file < `JD_OPTS`. A default only applies to an argument
+//! that is absent from the command line and does not conflict with one given
+//! there, so the command line always wins; `--no-color` and the like turn
+//! switches off again.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, Command};
+
+use crate::diagnostics::{Diagnostic, ErrorCode};
+
+/// Environment variable holding whitespace-separated default flags.
+pub(crate) const ENV_VAR: &str = "JD_OPTS";
+
+/// Flag that skips both the config file and `JD_OPTS`.
+pub(crate) const NO_CONFIG_FLAG: &str = "--no-config";
+
+/// Long flags that make no sense as defaults.
+const RESERVED: [&str; 3] = ["help", "version", "no-config"];
+
+/// Returns `args` with the configured defaults inserted after the program name.
+pub(crate) fn apply_defaults(args: Vec<OsString>, command: &Command) -> Result<Vec<OsString>> {
+    if args.iter().skip(1).any(|arg| arg == NO_CONFIG_FLAG) {
+        return Ok(args);
+    }
+
+    let mut defaults = Vec::new();
+    if let Some(path) = config_path().filter(|path| path.is_file()) {
+        let text = fs::read_to_string(&path)
+            .map_err(|err| config_error(&path, "failed to read", &err.into()))?;
+        defaults.extend(
+            flags_from_toml(&text, command)
+                .map_err(|err| config_error(&path, "invalid config file", &err))?,
+        );
+    }
+    if let Some(opts) = env::var_os(ENV_VAR) {
+        defaults.extend(flags_from_env(&opts));
+    }
+    if defaults.is_empty() {
+        return Ok(args);
+    }
+
+    let mut command = command.clone();
+    command.build();
+    let defaults = yield_to_command_line(&command, &args, defaults);
+    let mut args = args.into_iter();
+    Ok(args.next().into_iter().chain(defaults).chain(args).collect())
+}
+
+/// Drops the defaults for arguments that are given on the command line or
+/// conflict with one that is. Defaults clap does not recognize are kept, so
+/// clap reports them.
+fn yield_to_command_line(
+    command: &Command,
+    args: &[OsString],
+    defaults: Vec<OsString>,
+) -> Vec<OsString> {
+    // Errors such as a missing value surface in the real parse later.
+    let Ok(matches) = command.clone().ignore_errors(true).try_get_matches_from(args) else {
+        return defaults;
+    };
+    let given: Vec<&Arg> = command
+        .get_arguments()
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .collect();
+    let mut kept = Vec::new();
+    let mut defaults = defaults.into_iter();
+    while let Some(flag) = defaults.next() {
+        let Some((arg, inline)) = find_flag(command, &flag) else {
+            kept.push(flag);
+            continue;
+        };
+        let mut flag = vec![flag];
+        if arg.get_action().takes_values() && !inline {
+            flag.extend(defaults.next());
+        }
+        let overridden = given.iter().any(|other| {
+            other.get_id() == arg.get_id()
+                || conflicting(command, arg, other)
+                || conflicting(command, other, arg)
+        });
+        if !overridden {
+            kept.extend(flag);
+        }
+    }
+    kept
+}
+
+/// The argument named by `--long[=value]` or `-s[value]`, and whether the
+/// flag carries its value inline.
+fn find_flag<'a>(command: &'a Command, flag: &OsString) -> Option<(&'a Arg, bool)> {
+    let flag = flag.to_str()?;
+    if let Some(long) = flag.strip_prefix("--") {
+        let (name, value) =
+            long.split_once('=').map_or((long, None), |(name, value)| (name, Some(value)));
+        let arg = command.get_arguments().find(|arg| arg.get_long() == Some(name))?;
+        return Some((arg, value.is_some()));
+    }
+    let mut chars = flag.strip_prefix('-')?.chars();
+    let short = chars.next()?;
+    let arg = command.get_arguments().find(|arg| arg.get_short() == Some(short))?;
+    Some((arg, !chars.as_str().is_empty()))
+}
+
+/// Whether `arg` declares a conflict with `other`, directly or through a
+/// group, or shares a single-choice group with it.
+fn conflicting(command: &Command, arg: &Arg, other: &Arg) -> bool {
+    command.get_arg_conflicts_with(arg).iter().any(|conflict| conflict.get_id() == other.get_id())
+        || command.get_groups().any(|group| {
+            let members: Vec<_> = group.get_args().collect();
+            !group.clone().is_multiple()
+                && members.contains(&arg.get_id())
+                && members.contains(&other.get_id())
+        })
+}
+
+/// `$XDG_CONFIG_HOME/jd/config.toml`, falling back to `~/.config/jd/config.toml`.
+fn config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("jd").join("config.toml"))
+}
+
+/// Config errors are not part of Go `jd`, so the cause is spelled out even in text mode.
+fn config_error(path: &Path, message: &str, cause: &anyhow::Error) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Config, format!("{message} {}: {cause:#}", path.display()))
+        .with_input(path.display().to_string())
+}
+
+fn flags_from_env(opts: &OsString) -> Vec<OsString> {
+    opts.to_string_lossy().split_whitespace().map(OsString::from).collect()
+}
+
+/// Converts `key = value` pairs into `--key=value` arguments.
+///
+/// Keys are the long flag names. `true` enables a switch and `false` leaves it
+/// off; arrays repeat the flag once per element, with nested arrays (such as
+/// `ignore` paths) passed as JSON.
+fn flags_from_toml(text: &str, command: &Command) -> Result<Vec<OsString>> {
+    let table: toml::Table = toml::from_str(text)?;
+    let mut flags = Vec::new();
+    for (key, value) in table {
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .filter(|_| !RESERVED.contains(&key.as_str()))
+        else {
+            bail!("unknown option `{key}`");
+        };
+
+        if !arg.get_action().takes_values() {
+            match value {
+                toml::Value::Boolean(true) => flags.push(OsString::from(format!("--{key}"))),
+                toml::Value::Boolean(false) => {}
+                _ => bail!("option `{key}` expects a boolean"),
+            }
+            continue;
+        }
+
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                toml::Value::Array(_) => serde_json::to_string(&value)?,
+                _ => bail!("option `{key}` has an unsupported value"),
+            };
+            flags.push(OsString::from(format!("--{key}={value}")));
+        }
+    }
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction, ArgGroup};
+
+    fn command() -> Command {
+        Command::new("jd")
+            .arg(Arg::new("color").long("color").action(ArgAction::SetTrue))
+            .arg(Arg::new("precision").long("precision"))
+            .arg(Arg::new("ignore").long("ignore").action(ArgAction::Append))
+            .arg(Arg::new("version").long("version").action(ArgAction::SetTrue))
+    }
+
+    #[test]
+    fn toml_values_become_long_flags() {
+        let flags = flags_from_toml(
+            "color = true\nprecision = 0.5\nignore = [[\"a\", 0], [\"b\"]]\n",
+            &command(),
+        )
+        .unwrap();
+        assert_eq!(
+            flags,
+            ["--color", "--ignore=[\"a\",0]", "--ignore=[\"b\"]", "--precision=0.5"]
+                .map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_reserved_keys() {
+        let err = flags_from_toml("colour = true\n", &command()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown option `colour`");
+        let err = flags_from_toml("version = true\n", &command()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown option `version`");
+        let err = flags_from_toml("color = \"always\"\n", &command()).unwrap_err();
+        assert_eq!(err.to_string(), "option `color` expects a boolean");
+    }
+
+    #[test]
+    fn defaults_yield_to_the_command_line() {
+        let mut command = command()
+            .arg(Arg::new("no-color").long("no-color").action(ArgAction::SetTrue))
+            .arg(Arg::new("output").short('o').long("output"))
+            .arg(
+                Arg::new("quiet").long("quiet").action(ArgAction::SetTrue).conflicts_with("output"),
+            )
+            .arg(Arg::new("yaml").long("yaml").action(ArgAction::SetTrue))
+            .arg(Arg::new("toml").long("toml").action(ArgAction::SetTrue))
+            .group(ArgGroup::new("input").args(["yaml", "toml"]));
+        command.build();
+        let defaults = ["--quiet", "--precision", "1", "-oout", "--yaml", "--color", "--bogus"]
+            .map(OsString::from)
+            .to_vec();
+        let kept = |args: &[&str]| {
+            let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+            yield_to_command_line(&command, &args, defaults.clone())
+        };
+        assert_eq!(kept(&["jd"]), defaults);
+        assert_eq!(
+            kept(&["jd", "-o", "x", "--precision=2", "--toml", "--no-color"]),
+            ["--color", "--bogus"].map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn env_options_split_on_whitespace() {
+        assert_eq!(
+            flags_from_env(&OsString::from(" --color\t--precision=1 ")),
+            ["--color", "--precision=1"].map(OsString::from)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Error reporting for the CLI, including the `--error-format json` mode.
+//!
+//! Call sites attach a [`Diagnostic`] (via `anyhow::Context` or `bail!`) so
+//! the top-level handler can recover a stable error code and the offending
+//! input without parsing free-form messages.
+
+use std::ffi::OsString;
+use std::fmt;
+
+use clap::ValueEnum;
+use jd_core::Location;
+use serde::Serialize;
+
+/// How errors are written to STDERR.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum ErrorFormat {
+    /// Plain text matching Go `jd`.
+    #[default]
+    Text,
+    /// One JSON object per error.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Finds `--error-format` ahead of full argument parsing so that usage
+    /// errors can be reported in the requested format too.
+    pub(crate) fn sniff(args: &[OsString]) -> Self {
+        let mut selected = Self::Text;
+        let mut iter = args.iter().filter_map(|arg| arg.to_str());
+        while let Some(arg) = iter.next() {
+            let value = match arg.strip_prefix("--error-format") {
+                Some("") => iter.next(),
+                Some(rest) => rest.strip_prefix('='),
+                None => None,
+            };
+            if let Some(format) = value.and_then(|value| Self::from_str(value, false).ok()) {
+                selected = format;
+            }
+        }
+        selected
+    }
+}
+
+/// Stable, machine-readable error categories.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorCode {
+    /// Invalid command-line usage.
+    Usage,
+    /// The config file could not be read or contains invalid defaults.
+    Config,
+    /// Reading an input or writing an output failed.
+    Io,
+    /// Fetching a remote input failed.
+    #[cfg(feature = "http")]
+    Http,
+    /// An input could not be parsed.
+    Parse,
+    /// Diff options were rejected.
+    Options,
+    /// A diff could not be rendered in the requested format.
+    Render,
+    /// A translation failed.
+    Translate,
+    /// A patch did not apply to its input.
+    PatchConflict,
+    /// An input or the diff exceeded `--max-input-bytes`, `--max-nodes`,
+    /// `--max-depth`, or `--timeout`.
+    Limit,
+    /// The requested feature is not available.
+    Unsupported,
+    /// Any error without a more specific category.
+    Error,
+}
+
+/// Structured error attached to an `anyhow` chain.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Diagnostic {
+    code: ErrorCode,
+    message: String,
+    path: Option<String>,
+    input: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    /// Extra lines (a source snippet) shown only in text mode.
+    #[serde(skip)]
+    detail: Option<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            path: None,
+            input: None,
+            line: None,
+            column: None,
+            detail: None,
+        }
+    }
+
+    pub(crate) fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Records which input (file path, URL, or `-`) triggered the error.
+    pub(crate) fn with_input(mut self, input: impl Into<String>) -> Self {
+        self.input = Some(input.into());
+        self
+    }
+
+    /// Records the document path (native jd path notation) the error refers to.
+    pub(crate) fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Records where in the input the error occurred, with a snippet for text mode.
+    pub(crate) fn with_location(mut self, location: &Location, snippet: String) -> Self {
+        self.line = Some(location.line());
+        self.column = Some(location.column());
+        self.detail = Some(snippet);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Renders `err` for STDERR in the selected format (without trailing newline).
+pub(crate) fn render(err: &anyhow::Error, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Text => match err.downcast_ref::<Diagnostic>().and_then(|d| d.detail.as_ref())
+        {
+            Some(detail) => format!("{err}\n{detail}"),
+            None => err.to_string(),
+        },
+        ErrorFormat::Json => {
+            let mut diagnostic = err
+                .downcast_ref::<Diagnostic>()
+                .cloned()
+                .unwrap_or_else(|| Diagnostic::new(ErrorCode::Error, ""));
+            diagnostic.message = format!("{err:#}");
+            serde_json::to_string(&diagnostic).expect("diagnostics serialize as JSON")
+        }
+    }
+}
+
+/// Renders the line containing `location` with a caret under the error:
+///
+/// ```text
+///  --> doc.json:2:5
+///   |
+/// 2 |   "a": tru
+///   |        ^
+/// ```
+pub(crate) fn snippet(name: &str, input: &str, location: &Location) -> String {
+    let line = input.lines().nth(location.line() - 1).unwrap_or_default();
+    let number = location.line().to_string();
+    let gutter = " ".repeat(number.len());
+    let pad: String = line
+        .chars()
+        .take(location.column() - 1)
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+    let source = format!("{number} | {line}");
+    format!(
+        "{gutter}--> {name}:{}:{}\n{gutter} |\n{}\n{gutter} | {pad}^",
+        location.line(),
+        location.column(),
+        source.trim_end()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn sniff_accepts_separate_and_inline_values() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(ErrorFormat::sniff(&args(&["jd", "--error-format", "json"])), ErrorFormat::Json);
+        assert_eq!(ErrorFormat::sniff(&args(&["jd", "--error-format=json"])), ErrorFormat::Json);
+        assert_eq!(ErrorFormat::sniff(&args(&["jd", "a.json"])), ErrorFormat::Text);
+    }
+
+    #[test]
+    fn json_rendering_keeps_code_and_full_chain() {
+        let err = Err::<(), _>(anyhow::anyhow!("EOF while parsing"))
+            .context(
+                Diagnostic::new(ErrorCode::Parse, "failed to parse first input")
+                    .with_input("a.json"),
+            )
+            .unwrap_err();
+        assert_eq!(render(&err, ErrorFormat::Text), "failed to parse first input");
+        assert_eq!(
+            render(&err, ErrorFormat::Json),
+            r#"{"code":"parse","message":"failed to parse first input: EOF while parsing","path":null,"input":"a.json"}"#
+        );
+    }
+
+    #[test]
+    fn snippet_points_at_the_error_column() {
+        let input = "{\n\t\"a\": [1,]\n}";
+        let location = jd_core::Node::from_json_str(input).unwrap_err().location(input).unwrap();
+        assert_eq!(
+            snippet("doc.json", input, &location),
+            " --> doc.json:2:10\n  |\n2 | \t\"a\": [1,]\n  | \t        ^"
+        );
+    }
+
+    #[test]
+    fn json_rendering_falls_back_to_generic_code() {
+        let err = anyhow::anyhow!("boom");
+        assert_eq!(
+            render(&err, ErrorFormat::Json),
+            r#"{"code":"error","message":"boom","path":null,"input":null}"#
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Fetching `http://` and `https://` inputs (enabled by the `http` feature).
+
+use std::io::Read;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::diagnostics::{Diagnostic, ErrorCode};
+
+/// Bounds applied to every remote input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct FetchLimits {
+    pub(crate) timeout: Duration,
+    pub(crate) max_bytes: u64,
+}
+
+/// Returns whether a positional argument should be fetched rather than read from disk.
+pub(crate) fn is_url(arg: &str) -> bool {
+    arg.starts_with("https://") || arg.starts_with("http://")
+}
+
+/// Downloads `url`, failing on non-success statuses and oversized bodies.
+pub(crate) fn fetch(url: &str, limits: FetchLimits) -> Result<Vec<u8>> {
+    let agent = ureq::AgentBuilder::new().timeout(limits.timeout).build();
+    let response = match agent.get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, _)) => {
+            bail!(failure(url, format!("failed to fetch {url}: HTTP status {code}")))
+        }
+        Err(err) => {
+            return Err(err).with_context(|| failure(url, format!("failed to fetch {url}")))
+        }
+    };
+
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(limits.max_bytes.saturating_add(1))
+        .read_to_end(&mut body)
+        .with_context(|| failure(url, format!("failed to read response from {url}")))?;
+    if body.len() as u64 > limits.max_bytes {
+        bail!(failure(url, format!("response from {url} exceeds {} bytes", limits.max_bytes)));
+    }
+    Ok(body)
+}
+
+fn failure(url: &str, message: String) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Http, message).with_input(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_url;
+
+    #[test]
+    fn only_http_schemes_are_urls() {
+        assert!(is_url("https://example.com/a.json"));
+        assert!(is_url("http://localhost:8080/"));
+        assert!(!is_url("ftp://example.com/a.json"));
+        assert!(!is_url("a.json"));
+    }
+}
@@ -0,0 +1,183 @@
+//! `-f lsp`: diff hunks as source ranges for editor integrations.
+//!
+//! Each hunk becomes one object naming its jd path, what happened there,
+//! and the range of the affected values in both inputs, in the shape of an
+//! LSP `Range` (0-based lines, UTF-16 characters), so a plugin can
+//! highlight changes in the documents as written:
+//!
+//! ```text
+//! [{"path":["a"],"kind":"replace","lhs":{"file":"a.json","range":{"start":{"line":0,"character":6},"end":...}},
+//!   "rhs":{...},"message":"@ [\"a\"]\n- 1\n+ 2\n"}]
+//! ```
+//!
+//! Ranges cover the removed values in the first input and the added ones
+//! in the second. Where a side has no such values, or the path does not
+//! exist in that input as written (after `--ignore`, `--preset`, or sorting
+//! rewrote it), the range covers the nearest enclosing value instead.
+//! Ranges are `null` for inputs that are not JSON text.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use jd_core::{Diff, DiffElement, Node, ParseIndex, Path, PathSegment, RenderConfig};
+use serde::Serialize;
+
+/// One diffed input: how it was named and, for JSON text, its contents.
+pub(crate) struct Source<'a> {
+    name: String,
+    json: Option<(&'a str, ParseIndex)>,
+}
+
+impl<'a> Source<'a> {
+    /// Describes an input read as `bytes`, which are indexed only if
+    /// `maybe_json` and they are valid JSON.
+    pub(crate) fn new(name: String, bytes: &'a [u8], maybe_json: bool) -> Self {
+        let json = std::str::from_utf8(bytes).ok().filter(|_| maybe_json).and_then(|text| {
+            let (_, index) = Node::from_json_str_indexed(text).ok()?;
+            Some((text, index))
+        });
+        Self { name, json }
+    }
+}
+
+#[derive(Serialize)]
+struct Hunk<'a> {
+    path: &'a Path,
+    kind: Kind,
+    lhs: Site<'a>,
+    rhs: Site<'a>,
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Add,
+    Remove,
+    Replace,
+}
+
+#[derive(Serialize)]
+struct Site<'a> {
+    file: &'a str,
+    range: Option<LspRange>,
+}
+
+#[derive(Serialize)]
+struct LspRange {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Serialize)]
+struct Position {
+    line: usize,
+    character: usize,
+}
+
+/// Renders `diff` as a JSON array of hunk locations in `lhs` and `rhs`.
+pub(crate) fn render(diff: &Diff, lhs: &Source<'_>, rhs: &Source<'_>) -> String {
+    // List hunk indices count positions in the document patched so far, which
+    // are second-input positions; the first input's lag by the elements added
+    // minus those removed earlier in the same array.
+    let mut shifts: HashMap<&[PathSegment], i64> = HashMap::new();
+    let mut hunks = Vec::with_capacity(diff.len());
+    for element in diff {
+        let segments = element.path.segments();
+        let lhs_path: Vec<PathSegment> = segments
+            .iter()
+            .enumerate()
+            .map(|(depth, segment)| match segment {
+                PathSegment::Index(index) => {
+                    PathSegment::Index(index - shifts.get(&segments[..depth]).copied().unwrap_or(0))
+                }
+                other => other.clone(),
+            })
+            .collect();
+        if let Some((PathSegment::Index(_), parent)) = segments.split_last() {
+            *shifts.entry(parent).or_default() +=
+                element.add.len() as i64 - element.remove.len() as i64;
+        }
+        hunks.push(Hunk {
+            path: &element.path,
+            kind: match (element.remove.is_empty(), element.add.is_empty()) {
+                (true, _) => Kind::Add,
+                (false, true) => Kind::Remove,
+                (false, false) => Kind::Replace,
+            },
+            lhs: site(lhs, &lhs_path, element.remove.len()),
+            rhs: site(rhs, segments, element.add.len()),
+            message: hunk_message(element),
+        });
+    }
+    serde_json::to_string(&hunks).expect("hunk locations serialize")
+}
+
+fn hunk_message(element: &DiffElement) -> String {
+    Diff::from_elements(vec![element.clone()]).render(&RenderConfig::default())
+}
+
+fn site<'a>(source: &'a Source<'_>, path: &[PathSegment], count: usize) -> Site<'a> {
+    let range = source.json.as_ref().and_then(|(text, index)| {
+        let bytes = locate(index, path, count)?;
+        Some(LspRange { start: position(text, bytes.start), end: position(text, bytes.end) })
+    });
+    Site { file: &source.name, range }
+}
+
+/// Finds the `count` values starting at `path`, or the value enclosing them.
+fn locate(index: &ParseIndex, path: &[PathSegment], count: usize) -> Option<Range<usize>> {
+    let Some((last, parent)) = path.split_last() else {
+        return value_range(index, path);
+    };
+    if count == 0 {
+        return enclosing(index, parent);
+    }
+    if let (PathSegment::Index(first), true) = (last, count > 1) {
+        let mut end = parent.to_vec();
+        end.push(PathSegment::Index(first + count as i64 - 1));
+        if let (Some(first), Some(last)) = (value_range(index, path), value_range(index, &end)) {
+            return Some(first.start..last.end);
+        }
+    }
+    enclosing(index, path)
+}
+
+fn enclosing(index: &ParseIndex, path: &[PathSegment]) -> Option<Range<usize>> {
+    (0..=path.len()).rev().find_map(|len| value_range(index, &path[..len]))
+}
+
+fn value_range(index: &ParseIndex, path: &[PathSegment]) -> Option<Range<usize>> {
+    let span = index.span_of(&Path::from(path.to_vec()))?;
+    Some(span.start().offset()..span.end().offset())
+}
+
+fn position(text: &str, offset: usize) -> Position {
+    let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: text[..offset].matches('\n').count(),
+        character: text[line_start..offset].encode_utf16().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let text = "[\n  \"😀\", 1]";
+        let start = position(text, text.rfind('1').unwrap());
+        assert_eq!((start.line, start.character), (1, 8));
+    }
+
+    #[test]
+    fn list_hunks_cover_all_removed_elements() {
+        let source = Source::new("a.json".to_string(), b"[1, 2, 3, 4]", true);
+        let (_, index) = source.json.as_ref().unwrap();
+        assert_eq!(locate(index, &[PathSegment::index(1)], 2), Some(4..8));
+        assert_eq!(locate(index, &[PathSegment::index(1)], 0), Some(0..12));
+        assert_eq!(locate(index, &[PathSegment::index(9), PathSegment::key("a")], 1), Some(0..12));
+        assert!(Source::new("a.yaml".to_string(), b"a: 1", true).json.is_none());
+    }
+}
@@ -2,19 +2,36 @@
 //!
 //! This milestone wires the CLI to the renderer APIs implemented in
 //! `jd-core`, supporting diff mode with native, JSON Patch, and JSON
-//! Merge Patch outputs together with color toggling. Future milestones
-//! will extend this binary with patch/translate modes and the remaining
-//! flag surface.
+//! Merge Patch outputs together with color toggling, plus patch and
+//! translate modes. Future milestones will extend this binary with the
+//! remaining flag surface.
+
+mod batch;
+mod config;
+mod diagnostics;
+#[cfg(feature = "http")]
+mod http;
+mod lsp;
+mod progress;
+#[cfg(feature = "serve")]
+mod serve;
+mod translate;
 
-use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsString;
 use std::fs;
-use std::io::{self, Read, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
-use clap::{ArgAction, Parser, ValueEnum};
-use jd_core::{DiffOptions, Node, RenderConfig};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{ArgAction, ArgGroup, CommandFactory, Parser, ValueEnum};
+use diagnostics::{Diagnostic, ErrorCode, ErrorFormat};
+use jd_core::{
+    query::JsonPath, CanonicalizeError, Diff, DiffOptions, Histogram, LimitError, Limits, Node,
+    OptionsError, PathSegment, Preset, RenderConfig,
+};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 const VERSION_NUMBER: &str = env!("CARGO_PKG_VERSION");
 const VERSION_BANNER: &str = concat!("jd version ", env!("CARGO_PKG_VERSION"));
@@ -63,6 +80,19 @@ enum OutputFormat {
     Patch,
     #[value(alias = "merge")]
     Merge,
+    /// Hunk locations in the inputs, for editor plugins; diff mode only.
+    Lsp,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Native => "jd",
+            Self::Patch => "patch",
+            Self::Merge => "merge",
+            Self::Lsp => "lsp.json",
+        }
+    }
 }
 
 impl Default for OutputFormat {
@@ -77,7 +107,10 @@ impl Default for OutputFormat {
     disable_help_flag = true,
     disable_help_subcommand = true,
     disable_version_flag = true,
-    override_usage = "jd [OPTION]... FILE1 [FILE2]"
+    override_usage = "jd [OPTION]... FILE1 [FILE2]",
+    args_override_self = true,
+    group(ArgGroup::new("input_format").multiple(false)),
+    group(ArgGroup::new("rewrite").multiple(false))
 )]
 struct Cli {
     #[arg(long = "help", short = 'h', action = ArgAction::SetTrue, hide = true)]
@@ -87,10 +120,14 @@ struct Cli {
     version: bool,
 
     /// Render diff output using ANSI colors.
-    #[arg(long = "color", action = ArgAction::SetTrue)]
+    #[arg(long = "color", action = ArgAction::SetTrue, overrides_with = "no_color")]
     color: bool,
 
-    /// Select diff output format (`jd`, `patch`, or `merge`).
+    /// Render diff output without colors (the default).
+    #[arg(long = "no-color", action = ArgAction::SetTrue, overrides_with = "color")]
+    no_color: bool,
+
+    /// Select diff output format (`jd`, `patch`, `merge`, or `lsp`).
     #[arg(short = 'f', long = "format", value_enum, default_value = "jd")]
     format: OutputFormat,
 
@@ -98,22 +135,85 @@ struct Cli {
     #[arg(short = 'o', long = "output")]
     output: Option<PathBuf>,
 
+    /// Write several renderings at once to `FILE.<ext>` (or `DIR/diff.<ext>`).
+    #[arg(long = "output-formats", value_enum, value_delimiter = ',', conflicts_with = "format")]
+    output_formats: Vec<OutputFormat>,
+
     /// Enable patch mode (apply FILE1 patch to FILE2/STDIN).
     #[arg(short = 'p', action = ArgAction::SetTrue)]
     patch: bool,
 
-    /// Translate mode (e.g. `jd2patch`).
+    /// In patch mode, patch every file matching PATTERN in place instead of FILE2/STDIN.
+    #[arg(long = "glob", value_name = "PATTERN", requires = "patch", group = "rewrite")]
+    glob: Option<String>,
+
+    /// In patch mode, overwrite FILE2 with the patched document instead of printing it.
+    #[arg(
+        long = "in-place",
+        action = ArgAction::SetTrue,
+        requires = "patch",
+        conflicts_with = "output",
+        group = "rewrite"
+    )]
+    in_place: bool,
+
+    /// Keep a `FILE.bak` copy of every file patched in place.
+    #[arg(
+        long = "backup",
+        action = ArgAction::SetTrue,
+        requires = "rewrite",
+        overrides_with = "no_backup"
+    )]
+    backup: bool,
+
+    /// Keep no backup of files patched in place (the default).
+    #[arg(long = "no-backup", action = ArgAction::SetTrue, overrides_with = "backup")]
+    no_backup: bool,
+
+    /// In patch mode, patch every NDJSON record read from STDIN.
+    #[arg(
+        long = "ndjson",
+        action = ArgAction::SetTrue,
+        requires = "patch",
+        conflicts_with_all = ["rewrite", "input_format", "output_formats"]
+    )]
+    ndjson: bool,
+
+    /// Translate mode (e.g. `jd2patch`, or `auto2json` to detect the source format).
     #[arg(short = 't', long = "translate")]
     translate: Option<String>,
 
     /// Read and write YAML instead of JSON.
-    #[arg(long = "yaml", action = ArgAction::SetTrue)]
+    #[arg(long = "yaml", action = ArgAction::SetTrue, group = "input_format")]
     yaml: bool,
 
+    /// Read and write TOML instead of JSON.
+    #[cfg(feature = "toml")]
+    #[arg(long = "toml", action = ArgAction::SetTrue, group = "input_format")]
+    toml: bool,
+
+    /// Read CBOR inputs instead of JSON.
+    #[cfg(feature = "cbor")]
+    #[arg(long = "cbor", action = ArgAction::SetTrue, group = "input_format")]
+    cbor: bool,
+
+    /// Read MessagePack inputs instead of JSON.
+    #[cfg(feature = "msgpack")]
+    #[arg(long = "msgpack", action = ArgAction::SetTrue, group = "input_format")]
+    msgpack: bool,
+
     /// Numeric precision tolerance.
     #[arg(long = "precision")]
     precision: Option<f64>,
 
+    /// Numeric tolerance relative to the magnitude of compared numbers.
+    #[arg(long = "relative-precision")]
+    relative_precision: Option<f64>,
+
+    /// Maximum distance in units in the last place for numbers to be equal.
+    #[arg(long = "ulps")]
+    ulps: Option<u64>,
+
     /// Treat arrays as sets (not yet implemented).
     #[arg(long = "set", action = ArgAction::SetTrue)]
     set: bool,
@@ -126,14 +226,149 @@ struct Cli {
     #[arg(long = "setkeys")]
     setkeys: Option<String>,
 
+    /// Diff options as a JSON array, like Go's `-opts` (e.g. `[{"@":["a"],"^":["DIFF_OFF"]}]`).
+    #[arg(long = "opts", value_name = "JSON")]
+    opts: Option<String>,
+
+    /// Timeout in seconds for fetching `http(s)://` inputs.
+    #[cfg(feature = "http")]
+    #[arg(long = "http-timeout", default_value_t = 30)]
+    http_timeout: u64,
+
+    /// Maximum size in bytes of a fetched `http(s)://` input.
+    #[cfg(feature = "http")]
+    #[arg(long = "http-max-bytes", default_value_t = 16 * 1024 * 1024)]
+    http_max_bytes: u64,
+
+    /// Print at most N hunks of native output to STDOUT (`-o` keeps the full diff).
+    #[arg(long = "max-hunks", value_name = "N")]
+    max_hunks: Option<usize>,
+
+    /// Elide values longer than N bytes in native output to STDOUT (`-o` keeps the full diff).
+    #[arg(long = "max-value-bytes", value_name = "N")]
+    max_value_bytes: Option<usize>,
+
+    /// Pretty-print arrays and objects in native output to STDOUT, indenting by N spaces.
+    #[arg(long = "indent", value_name = "N")]
+    indent: Option<usize>,
+
+    /// Emit hunks in path order instead of the order the diff engine found them in.
+    #[arg(long = "sort-hunks", action = ArgAction::SetTrue, overrides_with = "no_sort_hunks")]
+    sort_hunks: bool,
+
+    /// Emit hunks in the order the diff engine found them in (the default).
+    #[arg(long = "no-sort-hunks", action = ArgAction::SetTrue, overrides_with = "sort_hunks")]
+    no_sort_hunks: bool,
+
+    /// Refuse inputs larger than N bytes.
+    #[arg(long = "max-input-bytes", value_name = "N")]
+    max_input_bytes: Option<usize>,
+
+    /// Refuse documents with more than N values (arrays, objects, and scalars).
+    #[arg(long = "max-nodes", value_name = "N")]
+    max_nodes: Option<usize>,
+
+    /// Refuse documents with arrays and objects nested more than N levels deep.
+    #[arg(long = "max-depth", value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Give up on a diff that takes longer than SECS seconds (fractions allowed).
+    #[arg(long = "timeout", value_name = "SECS", value_parser = parse_timeout)]
+    timeout: Option<std::time::Duration>,
+
+    /// Print depth, size, and container statistics for each input instead of diffing.
+    #[arg(long = "stat", action = ArgAction::SetTrue, conflicts_with_all = ["patch", "translate", "quiet", "output_formats"])]
+    stat: bool,
+
+    /// Suppress diff output; only report differences through the exit status.
+    #[arg(
+        long = "quiet",
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["output", "output_formats"],
+        overrides_with = "no_quiet"
+    )]
+    quiet: bool,
+
+    /// Print the diff (the default).
+    #[arg(long = "no-quiet", action = ArgAction::SetTrue, overrides_with = "quiet")]
+    no_quiet: bool,
+
+    /// Exit with status 1 when the inputs differ (the default).
+    #[arg(long = "exit-code", action = ArgAction::SetTrue, overrides_with = "no_exit_code")]
+    exit_code: bool,
+
+    /// Exit with status 0 even when the inputs differ.
+    #[arg(long = "no-exit-code", action = ArgAction::SetTrue, overrides_with = "exit_code")]
+    no_exit_code: bool,
+
+    /// Report errors on STDERR as `text` (the default) or one JSON object.
+    #[arg(long = "error-format", value_enum, default_value = "text")]
+    error_format: ErrorFormat,
+
+    /// Leave out the value at PATH (a JSON array such as `["spec",0]` or a JSONPath such as `$.spec[*].id`) in both inputs.
+    #[arg(long = "ignore", value_name = "PATH", action = ArgAction::Append)]
+    ignore: Vec<String>,
+
+    /// Replace the value at PATH (a JSON array such as `["auth","token"]` or a JSONPath such as `$..token`) with `"REDACTED"` in both inputs.
+    #[arg(long = "redact", value_name = "PATH", action = ArgAction::Append)]
+    redact: Vec<String>,
+
+    /// Sort every array of objects by the value of member KEY before diffing.
+    #[arg(long = "sort-arrays-by", value_name = "KEY")]
+    sort_arrays_by: Option<String>,
+
+    /// Derive per-path options from the JSON Schema in FILE (JSON or YAML).
+    #[arg(long = "schema", value_name = "FILE")]
+    schema: Option<OsString>,
+
+    /// Apply ignored paths and array ordering rules for a document family.
+    #[arg(long = "preset", value_name = "NAME", value_parser = preset_parser())]
+    preset: Option<Preset>,
+
+    /// Skip defaults from the config file and `JD_OPTS`.
+    #[arg(long = "no-config", action = ArgAction::SetTrue)]
+    no_config: bool,
+
+    /// Log phase timings to STDERR; repeat for finer-grained spans.
+    #[arg(long = "verbose", action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Draw a progress bar on STDERR, which is otherwise shown only for
+    /// inputs over 8 MiB when STDERR is a terminal.
+    #[arg(long = "progress", action = ArgAction::SetTrue, overrides_with = "no_progress")]
+    progress: bool,
+
+    /// Show the progress bar only for large inputs on a terminal (the default).
+    #[arg(long = "no-progress", action = ArgAction::SetTrue, overrides_with = "progress")]
+    no_progress: bool,
+
+    /// Treat empty arrays, empty objects, and absent keys as equivalent.
+    #[arg(long = "prune-empty", action = ArgAction::SetTrue, overrides_with = "no_prune_empty")]
+    prune_empty: bool,
+
+    /// Tell empty arrays, empty objects, and absent keys apart (the default).
+    #[arg(long = "no-prune-empty", action = ArgAction::SetTrue, overrides_with = "prune_empty")]
+    no_prune_empty: bool,
+
     /// Run as a git diff driver (not yet implemented).
     #[arg(long = "git-diff-driver", action = ArgAction::SetTrue)]
     git_diff_driver: bool,
 
-    /// Serve the web UI on the provided port (not yet implemented).
+    /// Serve the web UI on the provided port (not yet implemented), or the
+    /// JSON API with `serve --api` (8080 by default).
     #[arg(long = "port")]
     port: Option<u16>,
 
+    /// Serve the JSON diff API (`jd serve --api`) instead of diffing.
+    #[cfg(feature = "serve")]
+    #[arg(long = "api", action = ArgAction::SetTrue)]
+    api: bool,
+
+    /// Address the API server listens on.
+    #[cfg(feature = "serve")]
+    #[arg(long = "host", default_value = "127.0.0.1", requires = "api")]
+    host: std::net::IpAddr,
+
     #[arg(long = "v2", action = ArgAction::SetTrue, hide = true)]
     v2: bool,
 
@@ -143,19 +378,65 @@ struct Cli {
 }
 
 fn main() {
-    match try_main() {
+    let args = canonicalize_args(std::env::args_os());
+    let args = match config::apply_defaults(args.clone(), &Cli::command()) {
+        Ok(args) => args,
+        Err(err) => {
+            let _ =
+                writeln!(io::stderr(), "{}", diagnostics::render(&err, ErrorFormat::sniff(&args)));
+            std::process::exit(1);
+        }
+    };
+    let error_format = ErrorFormat::sniff(&args);
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(err) if error_format == ErrorFormat::Json => {
+            let usage = Diagnostic::new(ErrorCode::Usage, err.to_string().trim_end());
+            let _ = writeln!(io::stderr(), "{}", diagnostics::render(&usage.into(), error_format));
+            std::process::exit(err.exit_code());
+        }
+        Err(err) => err.exit(),
+    };
+
+    init_tracing(cli.verbose);
+    match try_main(&cli) {
         Ok(code) => std::process::exit(code),
         Err(err) => {
-            let _ = writeln!(io::stderr(), "{err}");
+            let _ = writeln!(io::stderr(), "{}", diagnostics::render(&err, cli.error_format));
             std::process::exit(1);
         }
     }
 }
 
-fn try_main() -> Result<i32> {
-    let args = canonicalize_args(std::env::args_os());
-    let cli = Cli::parse_from(args);
+/// Logs span timings to STDERR: parse, diff, render, and patch phases at
+/// `--verbose`, plus hashing and LCS at `--verbose --verbose`. Without
+/// `--verbose` only warnings, such as skipped schema keywords, are logged.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => {
+            tracing_subscriber::fmt()
+                .with_writer(io::stderr)
+                .with_max_level(Level::WARN)
+                .without_time()
+                .with_target(false)
+                .with_ansi(false)
+                .init();
+            return;
+        }
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false)
+        .with_ansi(false)
+        .init();
+}
 
+fn try_main(cli: &Cli) -> Result<i32> {
     if cli.help {
         print!("{}", help_text());
         return Ok(0);
@@ -166,17 +447,36 @@ fn try_main() -> Result<i32> {
         return Ok(0);
     }
 
+    #[cfg(feature = "serve")]
+    if cli.api {
+        return run_serve(cli);
+    }
     if cli.port.is_some() {
-        bail!("The web UI (-port) is not supported in this build");
+        bail!(unsupported("The web UI (-port) is not supported in this build"));
     }
     if cli.git_diff_driver {
-        bail!("git diff driver mode is not implemented yet");
+        bail!(unsupported("git diff driver mode is not implemented yet"));
     }
     if cli.patch && cli.translate.is_some() {
-        bail!("Patch and translate modes cannot be used together.");
+        bail!(Diagnostic::new(
+            ErrorCode::Usage,
+            "Patch and translate modes cannot be used together."
+        ));
+    }
+    // Terraform diffs address resources by key rather than by their index in
+    // the state file, so they cannot be applied to it.
+    let patch_format =
+        cli.format == OutputFormat::Patch || cli.output_formats.contains(&OutputFormat::Patch);
+    if cli.preset == Some(Preset::Terraform) && (cli.patch || patch_format) {
+        bail!(Diagnostic::new(
+            ErrorCode::Usage,
+            "--preset terraform keys resources by address, so its diffs cannot be used as patches (-p, -f patch)."
+        ));
     }
 
-    let mode = if cli.patch {
+    let mode = if cli.stat {
+        Mode::Stat
+    } else if cli.patch {
         Mode::Patch
     } else if cli.translate.is_some() {
         Mode::Translate
@@ -185,9 +485,10 @@ fn try_main() -> Result<i32> {
     };
 
     match mode {
-        Mode::Diff => run_diff(&cli),
-        Mode::Patch => bail!("Patch mode is not implemented yet"),
-        Mode::Translate => bail!("Translate mode is not implemented yet"),
+        Mode::Diff => run_diff(cli),
+        Mode::Patch => run_patch(cli),
+        Mode::Translate => run_translate(cli),
+        Mode::Stat => run_stat(cli),
     }
 }
 
@@ -196,161 +497,934 @@ enum Mode {
     Diff,
     Patch,
     Translate,
+    Stat,
 }
 
-fn run_diff(cli: &Cli) -> Result<i32> {
+fn check_array_modes(cli: &Cli) -> Result<()> {
     if cli.set {
-        bail!("-set is not implemented yet");
+        bail!(unsupported("-set is not implemented yet"));
     }
     if cli.multiset {
-        bail!("-mset is not implemented yet");
+        bail!(unsupported("-mset is not implemented yet"));
     }
     if cli.setkeys.is_some() {
-        bail!("-setkeys is not implemented yet");
+        bail!(unsupported("-setkeys is not implemented yet"));
     }
+    Ok(())
+}
+
+fn run_diff(cli: &Cli) -> Result<i32> {
+    check_array_modes(cli)?;
 
     let (first, second) = match cli.inputs.len() {
-        1 => (InputSource::File(path_from(&cli.inputs[0])?), InputSource::Stdin),
-        2 => (
-            InputSource::File(path_from(&cli.inputs[0])?),
-            InputSource::File(path_from(&cli.inputs[1])?),
-        ),
-        _ => {
-            return Err(anyhow!("{}", help_text()));
-        }
+        1 => (input_from(&cli.inputs[0], cli)?, InputSource::Stdin),
+        2 => (input_from(&cli.inputs[0], cli)?, input_from(&cli.inputs[1], cli)?),
+        _ => bail!(usage_help()),
     };
 
-    let lhs_text = read_input(&first)?;
-    let rhs_text = read_input(&second)?;
-    let lhs = parse_node(&lhs_text, cli.yaml).context("failed to parse first input")?;
-    let rhs = parse_node(&rhs_text, cli.yaml).context("failed to parse second input")?;
+    let limits = limits(cli);
+    let lhs_bytes = read_limited("first input", &first, &limits)?;
+    let rhs_bytes = read_limited("second input", &second, &limits)?;
+    // Span logs from `--verbose` would break up the bar's line.
+    let input_bytes = if cli.verbose > 0 { 0 } else { lhs_bytes.len() + rhs_bytes.len() };
+    let _progress = progress::show(cli.progress, input_bytes);
+    let mut lhs = parse_source(&lhs_bytes, &first, cli)
+        .map_err(|err| parse_failed("first input", &first, &err, &lhs_bytes))?;
+    limits.check_node(&lhs).map_err(|err| limit_exceeded("first input", &first, &err))?;
+    let mut rhs = parse_source(&rhs_bytes, &second, cli)
+        .map_err(|err| parse_failed("second input", &second, &err, &rhs_bytes))?;
+    limits.check_node(&rhs).map_err(|err| limit_exceeded("second input", &second, &err))?;
+    for path in &cli.ignore {
+        let selector = PathSelector::parse("--ignore", path)?;
+        for node in [&mut lhs, &mut rhs] {
+            // Later matches go first so that earlier array indices stay valid.
+            for path in selector.resolve(node).iter().rev() {
+                remove_path(node, path.segments());
+            }
+        }
+    }
+    if !cli.redact.is_empty() {
+        let selectors = cli
+            .redact
+            .iter()
+            .map(|path| PathSelector::parse("--redact", path))
+            .collect::<Result<Vec<_>>>()?;
+        let replacement = Node::String(REDACTED.to_string());
+        let redact = |node: &Node| {
+            let paths: Vec<_> =
+                selectors.iter().flat_map(|selector| selector.resolve(node)).collect();
+            node.redact(&paths, &replacement)
+        };
+        lhs = redact(&lhs);
+        rhs = redact(&rhs);
+    }
+    if let Some(key) = cli.sort_arrays_by.as_deref() {
+        lhs = lhs.sort_arrays_by(|_| Some(key));
+        rhs = rhs.sort_arrays_by(|_| Some(key));
+    }
+    if let Some(preset) = cli.preset {
+        lhs = preset.normalize(&lhs);
+        rhs = preset.normalize(&rhs);
+    }
+
+    let schema = cli.schema.as_ref().map(|input| read_schema(input, cli)).transpose()?;
+    let options = build_options(cli, schema.as_ref())
+        .and_then(|options| {
+            let document_options =
+                cli.preset.map(|preset| preset.document_path_options(&lhs, &rhs));
+            Ok(document_options
+                .into_iter()
+                .flatten()
+                .try_fold(options, DiffOptions::with_path_option)?)
+        })
+        .map_err(|err| {
+            let code = err.downcast_ref().map_or(ErrorCode::Options, options_error_code);
+            Diagnostic::new(code, format!("{err:#}"))
+        })?;
+    // `--output-formats` computes the merge diff separately when it needs one.
+    let merge =
+        options.merge() || (cli.format == OutputFormat::Merge && cli.output_formats.is_empty());
+    let diff = compute_diff(&lhs, &rhs, &options.clone().with_merge(merge), &limits)?;
+
+    if cli.quiet {
+        return Ok(diff_exit_code(cli, !diff.is_empty()));
+    }
 
-    let options = build_options(cli)?;
-    let diff = lhs.diff(&rhs, &options);
+    let render_config = render_config(cli);
+    if !cli.output_formats.is_empty() {
+        let merge_diff = || compute_diff(&lhs, &rhs, &options.clone().with_merge(true), &limits);
+        return write_output_formats(cli, &diff, merge_diff, &render_config);
+    }
+
+    let (rendered, have_diff) = if cli.format == OutputFormat::Lsp {
+        // STDIN may have been sniffed as YAML, so check that the text is JSON.
+        let json = !has_input_format(cli);
+        let source = |input: &InputSource, bytes| lsp::Source::new(input.to_string(), bytes, json);
+        let (lhs, rhs) = (source(&first, &lhs_bytes), source(&second, &rhs_bytes));
+        (lsp::render(&diff, &lhs, &rhs), !diff.is_empty())
+    } else {
+        render_diff(cli.format, &diff, &render_config)?
+    };
+    write_output(cli, &rendered)?;
+    Ok(diff_exit_code(cli, have_diff))
+}
 
-    let mut render_config = RenderConfig::default();
-    if cli.color {
-        render_config = render_config.with_color(true);
+/// Maps "the inputs differ" to the process exit status, honoring `--no-exit-code`.
+fn diff_exit_code(cli: &Cli, have_diff: bool) -> i32 {
+    if have_diff && !cli.no_exit_code {
+        1
+    } else {
+        0
     }
+}
+
+fn compute_diff(lhs: &Node, rhs: &Node, options: &DiffOptions, limits: &Limits) -> Result<Diff> {
+    lhs.diff_with_limits(rhs, options, limits)
+        .map_err(|err| Diagnostic::new(ErrorCode::Limit, err.to_string()).into())
+}
 
-    let (rendered, have_diff) = match cli.format {
+/// Renders `diff` in `format`, returning the text and whether it describes any change.
+///
+/// `diff` must have been computed with `DiffOptions::with_merge` for the merge format.
+fn render_diff(
+    format: OutputFormat,
+    diff: &Diff,
+    render_config: &RenderConfig,
+) -> Result<(String, bool)> {
+    let sorted;
+    let diff = if render_config.sorted_hunks() {
+        sorted = diff.sorted();
+        &sorted
+    } else {
+        diff
+    };
+    Ok(match format {
         OutputFormat::Native => {
-            let rendered = diff.render(&render_config);
+            let rendered = diff.render(render_config);
             let have_diff = !rendered.is_empty();
             (rendered, have_diff)
         }
         OutputFormat::Patch => {
-            let rendered = diff.render_patch().context("failed to render JSON Patch")?;
+            let rendered = diff
+                .render_patch()
+                .context(Diagnostic::new(ErrorCode::Render, "failed to render JSON Patch"))?;
             let have_diff = rendered != "[]";
             (rendered, have_diff)
         }
         OutputFormat::Merge => {
-            let patch = merge_patch(&lhs, &rhs).unwrap_or_else(|| Node::Object(BTreeMap::new()));
-            let rendered = patch
-                .to_json_value()
-                .map(|value| serde_json::to_string(&value))
-                .transpose()
-                .context("failed to serialize merge patch")?
-                .unwrap_or_else(|| "{}".to_string());
+            let rendered = diff
+                .render_merge()
+                .context(Diagnostic::new(ErrorCode::Render, "failed to render merge patch"))?;
             let have_diff = rendered != "{}";
             (rendered, have_diff)
         }
+        OutputFormat::Lsp => bail!(Diagnostic::new(
+            ErrorCode::Usage,
+            "the lsp format needs the diffed inputs and cannot be used with --output-formats"
+        )),
+    })
+}
+
+/// Writes one file per `--output-formats` entry next to (or inside) the `-o` target.
+fn write_output_formats(
+    cli: &Cli,
+    diff: &Diff,
+    merge_diff: impl FnOnce() -> Result<Diff>,
+    render_config: &RenderConfig,
+) -> Result<i32> {
+    let Some(output) = &cli.output else {
+        bail!(Diagnostic::new(ErrorCode::Usage, "--output-formats requires -o FILE or -o DIR"));
+    };
+    let merge_diff =
+        if cli.output_formats.contains(&OutputFormat::Merge) { Some(merge_diff()?) } else { None };
+    let mut written = Vec::new();
+    let mut have_diff = false;
+    for &format in &cli.output_formats {
+        if written.contains(&format) {
+            continue;
+        }
+        let diff = match (format, &merge_diff) {
+            (OutputFormat::Merge, Some(merge_diff)) => merge_diff,
+            _ => diff,
+        };
+        let (rendered, changed) = render_diff(format, diff, render_config)?;
+        let path = output_format_path(output, format);
+        fs::write(&path, rendered.as_bytes()).with_context(|| write_failed(&path))?;
+        have_diff |= changed;
+        written.push(format);
+    }
+    Ok(diff_exit_code(cli, have_diff))
+}
+
+fn output_format_path(output: &Path, format: OutputFormat) -> PathBuf {
+    if output.is_dir() {
+        return output.join(format!("diff.{}", format.extension()));
+    }
+    let mut path = output.as_os_str().to_owned();
+    path.push(".");
+    path.push(format.extension());
+    PathBuf::from(path)
+}
+
+/// Applies one or more diffs in order: `jd -p PATCH... [FILE]`.
+///
+/// The last positional argument is the document unless only one is given, in
+/// which case the document is read from STDIN like Go `jd`. With `--glob`, all
+/// positional arguments are patches and every matching file is patched in place.
+fn run_patch(cli: &Cli) -> Result<i32> {
+    check_array_modes(cli)?;
+
+    if let Some(pattern) = &cli.glob {
+        return run_batch_patch(cli, pattern);
+    }
+    if cli.ndjson {
+        return run_ndjson_patch(cli);
+    }
+
+    let (patches, document) = match cli.inputs.as_slice() {
+        [] => bail!(usage_help()),
+        [patch] => (std::slice::from_ref(patch), InputSource::Stdin),
+        [patches @ .., document] => (patches, input_from(document, cli)?),
+    };
+    if cli.in_place && !matches!(document, InputSource::File(_)) {
+        bail!(Diagnostic::new(ErrorCode::Usage, "--in-place requires FILE2 to be a local file"));
+    }
+
+    let (node, original) = read_document_bytes(&document, cli)?;
+    let patches = read_patches(patches, cli)?;
+    let node = apply_patches(node, &patches)?;
+    let rendered = write_patched(&original, &node, &patches, cli)?;
+    match &document {
+        InputSource::File(path) if cli.in_place => {
+            batch::write_in_place(path, rendered.as_bytes(), cli.backup)?;
+        }
+        _ => write_output(cli, &rendered)?,
+    }
+    Ok(0)
+}
+
+/// Patches every file matching `pattern`; nothing is written unless all succeed.
+fn run_batch_patch(cli: &Cli, pattern: &str) -> Result<i32> {
+    if cli.inputs.is_empty() {
+        bail!(usage_help());
+    }
+    let patches = read_patches(&cli.inputs, cli)?;
+
+    let mut updates = Vec::new();
+    for path in batch::expand(pattern)? {
+        let source = InputSource::File(path.clone());
+        let (node, original) = read_document_bytes(&source, cli)?;
+        let patched = apply_patches(node.clone(), &patches).map_err(|err| {
+            Diagnostic::new(ErrorCode::PatchConflict, format!("failed to patch {source}: {err}"))
+                .with_input(source.to_string())
+        })?;
+        if patched != node {
+            updates.push((path, write_patched(&original, &patched, &patches, cli)?));
+        }
+    }
+
+    for (path, contents) in updates {
+        batch::write_in_place(&path, contents.as_bytes(), cli.backup)?;
+    }
+    Ok(0)
+}
+
+/// Applies one patch to every NDJSON record on STDIN: `jd -p --ndjson PATCH`.
+///
+/// Patched records are written as they come, one per line; records the patch
+/// removes entirely are dropped. Lines that do not parse or patch are skipped
+/// and reported on STDERR once the stream ends, with exit code 1.
+fn run_ndjson_patch(cli: &Cli) -> Result<i32> {
+    let [patch] = cli.inputs.as_slice() else {
+        bail!(usage_help());
+    };
+    let patches = read_patches(std::slice::from_ref(patch), cli)?;
+    let diff = &patches[0].1;
+    let limits = limits(cli);
+
+    let mut read_error = None;
+    let mut failures = Vec::new();
+    // Line number of every record handed to the patch stream.
+    let mut record_lines = Vec::new();
+    let records = io::stdin()
+        .lock()
+        .lines()
+        .map_while(|line| line.map_err(|err| read_error = Some(err)).ok())
+        .zip(1..)
+        .filter(|(line, _)| !line.trim().is_empty())
+        .filter_map(|(line, number)| {
+            let failure = match Node::from_json_str(&line) {
+                Ok(node) => match limits.check_node(&node) {
+                    Ok(()) => {
+                        record_lines.push(number);
+                        return Some(node);
+                    }
+                    Err(err) => Diagnostic::new(ErrorCode::Limit, format!("line {number}: {err}"))
+                        .with_input("-"),
+                },
+                Err(err) => Diagnostic::new(ErrorCode::Parse, format!("line {number}: {err}"))
+                    .with_input("-"),
+            };
+            failures.push((number, failure));
+            None
+        });
+
+    let mut stream = diff.apply_to_each(records);
+    let mut out: Box<dyn Write> = match &cli.output {
+        Some(path) => Box::new(io::BufWriter::new(
+            fs::File::create(path).with_context(|| write_failed(path))?,
+        )),
+        None => Box::new(io::BufWriter::new(io::stdout().lock())),
+    };
+    let output_failed = || Diagnostic::new(ErrorCode::Io, "failed to write output");
+    for record in stream.by_ref() {
+        if let Some(value) = record.to_json_value() {
+            writeln!(out, "{value}").with_context(output_failed)?;
+        }
+    }
+    out.flush().with_context(output_failed)?;
+    let patch_failures = stream.into_failures();
+    if let Some(err) = read_error {
+        return Err(anyhow::Error::new(err).context(stdin_failed()));
+    }
+
+    failures.extend(patch_failures.iter().map(|failure| {
+        let number = record_lines[failure.record()];
+        let message = format!("line {number}: {}", failure.error());
+        (number, Diagnostic::new(ErrorCode::PatchConflict, message).with_input("-"))
+    }));
+    failures.sort_by_key(|(number, _)| *number);
+    let mut stderr = io::stderr().lock();
+    for (_, failure) in &failures {
+        let _ =
+            writeln!(stderr, "{}", diagnostics::render(&failure.clone().into(), cli.error_format));
+    }
+    Ok(i32::from(!failures.is_empty()))
+}
+
+/// Prints `Node::metrics` for every input, or for STDIN when none is given.
+fn run_stat(cli: &Cli) -> Result<i32> {
+    let sources = match cli.inputs.as_slice() {
+        [] => vec![InputSource::Stdin],
+        inputs => inputs.iter().map(|input| input_from(input, cli)).collect::<Result<_>>()?,
+    };
+    let mut rendered = String::new();
+    for source in &sources {
+        let metrics = read_document(source, cli)?.metrics();
+        rendered.push_str(&format!(
+            "{}
+  depth: {}
+  nodes: {}
+  string bytes: {}
+  array lengths: {}
+  object sizes: {}
+",
+            source_name(source),
+            metrics.depth(),
+            metrics.nodes(),
+            metrics.string_bytes(),
+            histogram_summary(metrics.array_lengths()),
+            histogram_summary(metrics.object_sizes()),
+        ));
+    }
+    write_output(cli, &rendered)?;
+    Ok(0)
+}
+
+/// Formats a histogram as `0: 3, 2-3: 1`, or `none` when empty.
+fn histogram_summary(histogram: &Histogram) -> String {
+    let buckets: Vec<String> = histogram
+        .buckets()
+        .map(|(range, count)| match (range.start(), range.end()) {
+            (low, high) if low == high => format!("{low}: {count}"),
+            (low, high) => format!("{low}-{high}: {count}"),
+        })
+        .collect();
+    if buckets.is_empty() {
+        "none".to_string()
+    } else {
+        buckets.join(", ")
+    }
+}
+
+fn read_document(source: &InputSource, cli: &Cli) -> Result<Node> {
+    read_document_bytes(source, cli).map(|(node, _)| node)
+}
+
+/// Reads a document, keeping its bytes for editing them in place.
+fn read_document_bytes(source: &InputSource, cli: &Cli) -> Result<(Node, Vec<u8>)> {
+    let limits = limits(cli);
+    let bytes = read_limited("document", source, &limits)?;
+    let node = parse_source(&bytes, source, cli)
+        .map_err(|err| parse_failed("document", source, &err, &bytes))?;
+    limits.check_node(&node).map_err(|err| limit_exceeded("document", source, &err))?;
+    Ok((node, bytes))
+}
+
+fn read_patches(inputs: &[OsString], cli: &Cli) -> Result<Vec<(InputSource, Diff)>> {
+    let count = inputs.len();
+    let mut patches = Vec::with_capacity(count);
+    for (position, input) in inputs.iter().enumerate() {
+        let source = input_from(input, cli)?;
+        let diff = read_patch(&read_input(&source)?, cli.format).map_err(|err| {
+            let label = chain_label(position, count);
+            Diagnostic::new(ErrorCode::Parse, format!("failed to parse {label}: {err:#}"))
+                .with_input(source.to_string())
+        })?;
+        patches.push((source, diff));
+    }
+    Ok(patches)
+}
+
+/// Applies `patches` in sequence, naming the patch that failed.
+fn apply_patches(mut node: Node, patches: &[(InputSource, Diff)]) -> Result<Node> {
+    let count = patches.len();
+    for (position, (source, diff)) in patches.iter().enumerate() {
+        if position > 0 && node == Node::Void {
+            bail!(Diagnostic::new(
+                ErrorCode::PatchConflict,
+                format!("{} has no document to apply to", chain_label(position, count))
+            )
+            .with_input(source.to_string()));
+        }
+        node = node.apply_patch(diff).map_err(|err| {
+            let message = if count == 1 {
+                err.to_string()
+            } else {
+                format!("failed to apply {} ({source}): {err}", chain_label(position, count))
+            };
+            Diagnostic::new(ErrorCode::PatchConflict, message).with_input(source.to_string())
+        })?;
+    }
+    Ok(node)
+}
+
+fn chain_label(position: usize, count: usize) -> String {
+    if count == 1 {
+        "patch".to_string()
+    } else {
+        format!("patch {} of {count}", position + 1)
+    }
+}
+
+fn read_patch(input: &str, format: OutputFormat) -> Result<Diff> {
+    Ok(match format {
+        OutputFormat::Native => Diff::from_native_str(input)?,
+        OutputFormat::Patch => Diff::from_patch_str(input)?,
+        OutputFormat::Merge => Diff::from_merge_str(input)?,
+        OutputFormat::Lsp => {
+            bail!(Diagnostic::new(ErrorCode::Usage, "the lsp format cannot be read as a patch"))
+        }
+    })
+}
+
+/// Serializes a patched document like [`write_document`], except that with
+/// the `yaml-edit` feature YAML is edited in place, keeping comments and
+/// formatting, whenever every patch can be written into the text. JSON files
+/// rewritten by `--in-place` or `--glob` are edited the same way; printed
+/// JSON stays compact, as in Go `jd`.
+fn write_patched(
+    original: &[u8],
+    node: &Node,
+    patches: &[(InputSource, Diff)],
+    cli: &Cli,
+) -> Result<String> {
+    #[cfg(feature = "yaml-edit")]
+    if cli.yaml {
+        if let Some(edited) = edit_yaml(original, patches) {
+            return Ok(edited);
+        }
+    }
+    if (cli.in_place || cli.glob.is_some()) && !has_input_format(cli) {
+        if let Some(edited) = edit_json(original, patches) {
+            return Ok(edited);
+        }
+    }
+    write_document(node, cli)
+}
+
+fn edit_json(original: &[u8], patches: &[(InputSource, Diff)]) -> Option<String> {
+    let mut text = std::str::from_utf8(original).ok()?.to_string();
+    for (source, diff) in patches {
+        text = diff
+            .apply_to_json_str(&text)
+            .map_err(|err| tracing::debug!("rewriting JSON after {source}: {err}"))
+            .ok()?;
+    }
+    Some(text)
+}
+
+#[cfg(feature = "yaml-edit")]
+fn edit_yaml(original: &[u8], patches: &[(InputSource, Diff)]) -> Option<String> {
+    let mut text = std::str::from_utf8(original).ok()?.to_string();
+    for (source, diff) in patches {
+        text = diff
+            .apply_to_yaml_str(&text)
+            .map_err(|err| tracing::debug!("rewriting YAML after {source}: {err}"))
+            .ok()?;
+    }
+    Some(text)
+}
+
+/// Serializes a patched document in the input format (compact JSON by default).
+fn write_document(node: &Node, cli: &Cli) -> Result<String> {
+    #[cfg(feature = "toml")]
+    if cli.toml {
+        return node
+            .to_toml_string()
+            .context(Diagnostic::new(ErrorCode::Render, "failed to write TOML output"));
+    }
+    if cli.yaml {
+        return Ok(node.to_yaml_string().unwrap_or_default());
+    }
+    Ok(node.to_json_value().map(|value| value.to_string()).unwrap_or_default())
+}
+
+fn run_translate(cli: &Cli) -> Result<i32> {
+    let spec = cli.translate.as_deref().unwrap_or_default();
+    let source = match cli.inputs.len() {
+        0 => InputSource::Stdin,
+        1 => input_from(&cli.inputs[0], cli)?,
+        _ => bail!(usage_help()),
     };
+    let input = read_input(&source)?;
+    let rendered = translate::translate(spec, &input, &render_config(cli))?;
+    write_output(cli, &rendered)?;
+    Ok(0)
+}
 
+/// Serves the JSON API: `jd serve --api [--host ADDR] [--port N]`.
+#[cfg(feature = "serve")]
+fn run_serve(cli: &Cli) -> Result<i32> {
+    if !matches!(cli.inputs.as_slice(), [] | [_]) || cli.inputs.iter().any(|arg| arg != "serve") {
+        bail!(Diagnostic::new(ErrorCode::Usage, "usage: jd serve --api [--host ADDR] [--port N]"));
+    }
+    let addr = std::net::SocketAddr::new(cli.host, cli.port.unwrap_or(8080));
+    serve::run(addr, &serve::Api::new(limits(cli)))?;
+    Ok(0)
+}
+
+fn limits(cli: &Cli) -> Limits {
+    let mut limits = Limits::default();
+    if let Some(max) = cli.max_input_bytes {
+        limits = limits.with_max_input_bytes(max);
+    }
+    if let Some(max) = cli.max_nodes {
+        limits = limits.with_max_nodes(max);
+    }
+    if let Some(max) = cli.max_depth {
+        limits = limits.with_max_depth(max);
+    }
+    if let Some(timeout) = cli.timeout {
+        limits = limits.with_timeout(timeout);
+    }
+    limits
+}
+
+fn preset_parser() -> impl TypedValueParser<Value = Preset> {
+    PossibleValuesParser::new(Preset::ALL.map(Preset::name))
+        .map(|name| name.parse().expect("possible values are preset names"))
+}
+
+fn parse_timeout(value: &str) -> Result<std::time::Duration, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("expected a non-negative number of seconds; got {value:?}"))
+}
+
+fn render_config(cli: &Cli) -> RenderConfig {
+    let mut config =
+        RenderConfig::default().with_color(cli.color).with_sorted_hunks(cli.sort_hunks);
+    // Truncation and indentation only keep terminal output readable; files
+    // get the full diff in a form `jd -p` can read back.
+    if cli.output.is_none() {
+        if let Some(max) = cli.max_hunks {
+            config = config.with_max_hunks(max);
+        }
+        if let Some(max) = cli.max_value_bytes {
+            config = config.with_max_value_bytes(max);
+        }
+        if let Some(width) = cli.indent {
+            config = config.with_indent(width);
+        }
+    }
+    config
+}
+
+fn write_output(cli: &Cli, rendered: &str) -> Result<()> {
     if let Some(path) = &cli.output {
-        fs::write(path, rendered.as_bytes())
-            .with_context(|| format!("failed to write output to {}", path.display()))?;
+        fs::write(path, rendered.as_bytes()).with_context(|| write_failed(path))?;
     } else {
         print!("{rendered}");
         io::stdout().flush().ok();
     }
+    Ok(())
+}
+
+fn unsupported(message: &str) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Unsupported, message)
+}
 
-    Ok(if have_diff { 1 } else { 0 })
+fn usage_help() -> Diagnostic {
+    Diagnostic::new(ErrorCode::Usage, help_text())
+}
+
+fn write_failed(path: &Path) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Io, format!("failed to write output to {}", path.display()))
+        .with_input(path.display().to_string())
+}
+
+fn read_failed(path: &Path) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Io, format!("failed to read {}", path.display()))
+        .with_input(path.display().to_string())
 }
 
 #[derive(Debug)]
 enum InputSource {
     File(PathBuf),
     Stdin,
+    #[cfg(feature = "http")]
+    Url(String, http::FetchLimits),
+}
+
+/// Names the input the way it was given on the command line (`-` for STDIN).
+impl std::fmt::Display for InputSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Stdin => f.write_str("-"),
+            #[cfg(feature = "http")]
+            Self::Url(url, _) => f.write_str(url),
+        }
+    }
+}
+
+fn input_from(input: &OsString, cli: &Cli) -> Result<InputSource> {
+    #[cfg(feature = "http")]
+    if let Some(url) = input.to_str().filter(|arg| http::is_url(arg)) {
+        let limits = http::FetchLimits {
+            timeout: std::time::Duration::from_secs(cli.http_timeout),
+            max_bytes: cli.http_max_bytes,
+        };
+        return Ok(InputSource::Url(url.to_string(), limits));
+    }
+    #[cfg(not(feature = "http"))]
+    let _ = cli;
+    Ok(InputSource::File(path_from(input)?))
 }
 
 fn path_from(input: &OsString) -> Result<PathBuf> {
     let path = PathBuf::from(input);
     if path.as_os_str().is_empty() {
-        bail!("expected file path; got empty string");
+        bail!(Diagnostic::new(ErrorCode::Usage, "expected file path; got empty string"));
     }
     Ok(path)
 }
 
 fn read_input(source: &InputSource) -> Result<String> {
+    match source {
+        InputSource::File(path) => fs::read_to_string(path).with_context(|| read_failed(path)),
+        InputSource::Stdin => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer).context(stdin_failed())?;
+            Ok(buffer)
+        }
+        #[cfg(feature = "http")]
+        InputSource::Url(url, limits) => String::from_utf8(http::fetch(url, *limits)?)
+            .with_context(|| format!("response from {url} is not valid UTF-8")),
+    }
+}
+
+fn stdin_failed() -> Diagnostic {
+    Diagnostic::new(ErrorCode::Io, "failed to read STDIN").with_input("-")
+}
+
+/// Reads an input without buffering more than one byte past `--max-input-bytes`.
+fn read_limited(what: &str, source: &InputSource, limits: &Limits) -> Result<Vec<u8>> {
+    let Some(max) = limits.max_input_bytes() else {
+        return read_input_bytes(source);
+    };
+    let cap = u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1);
+    let mut bytes = Vec::new();
     match source {
         InputSource::File(path) => {
-            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+            fs::File::open(path)
+                .and_then(|file| file.take(cap).read_to_end(&mut bytes))
+                .with_context(|| read_failed(path))?;
         }
         InputSource::Stdin => {
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
+            io::stdin().take(cap).read_to_end(&mut bytes).context(stdin_failed())?;
+        }
+        #[cfg(feature = "http")]
+        InputSource::Url(..) => bytes = read_input_bytes(source)?,
+    }
+    limits.check_input_len(bytes.len()).map_err(|err| limit_exceeded(what, source, &err))?;
+    Ok(bytes)
+}
+
+fn limit_exceeded(what: &str, source: &InputSource, err: &LimitError) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Limit, format!("refusing {what} ({}): {err}", source_name(source)))
+        .with_input(source.to_string())
+}
+
+fn read_input_bytes(source: &InputSource) -> Result<Vec<u8>> {
+    match source {
+        InputSource::File(path) => fs::read(path).with_context(|| read_failed(path)),
+        InputSource::Stdin => {
+            let mut buffer = Vec::new();
+            io::stdin().read_to_end(&mut buffer).context(stdin_failed())?;
             Ok(buffer)
         }
+        #[cfg(feature = "http")]
+        InputSource::Url(url, limits) => http::fetch(url, *limits),
+    }
+}
+
+/// Names the input that failed to parse, keeping the parser's line and column
+/// and pointing at the offending text when it can be located.
+/// Names an input in messages, spelling out STDIN rather than `-`.
+fn source_name(source: &InputSource) -> String {
+    match source {
+        InputSource::Stdin => "STDIN".to_string(),
+        source => source.to_string(),
     }
 }
 
-fn parse_node(input: &str, yaml: bool) -> Result<Node> {
-    if yaml {
+fn parse_failed(what: &str, source: &InputSource, err: &anyhow::Error, bytes: &[u8]) -> Diagnostic {
+    let name = source_name(source);
+    let diagnostic =
+        Diagnostic::new(ErrorCode::Parse, format!("failed to parse {what} ({name}): {err}"))
+            .with_input(source.to_string());
+    let located = err.downcast_ref::<CanonicalizeError>().zip(std::str::from_utf8(bytes).ok());
+    let Some((location, text)) =
+        located.and_then(|(err, text)| err.location(text).map(|location| (location, text)))
+    else {
+        return diagnostic;
+    };
+    let diagnostic =
+        diagnostic.with_location(&location, diagnostics::snippet(&name, text, &location));
+    match location.path() {
+        Some(path) => diagnostic.with_path(serde_json::to_string(path).expect("paths serialize")),
+        None => diagnostic,
+    }
+}
+
+fn parse_source(bytes: &[u8], source: &InputSource, cli: &Cli) -> Result<Node> {
+    if matches!(source, InputSource::Stdin) && !has_input_format(cli) {
+        return sniff_json_or_yaml(bytes);
+    }
+    parse_input(bytes, cli)
+}
+
+fn has_input_format(cli: &Cli) -> bool {
+    #[allow(unused_mut)]
+    let mut explicit = cli.yaml;
+    #[cfg(feature = "toml")]
+    {
+        explicit |= cli.toml;
+    }
+    #[cfg(feature = "cbor")]
+    {
+        explicit |= cli.cbor;
+    }
+    #[cfg(feature = "msgpack")]
+    {
+        explicit |= cli.msgpack;
+    }
+    explicit
+}
+
+/// Piped input has no file name to go by, so without a format flag STDIN is
+/// read as JSON (empty input is void) and, failing that, as a YAML mapping or
+/// sequence. YAML scalars are not accepted because almost any text is one.
+/// `--schema` files are read the same way.
+fn sniff_json_or_yaml(bytes: &[u8]) -> Result<Node> {
+    let text = std::str::from_utf8(bytes)?;
+    let json_err = match Node::from_json_str(text) {
+        Ok(node) => return Ok(node),
+        Err(err) => err,
+    };
+    match Node::from_yaml_str(text) {
+        Ok(node @ (Node::Object(_) | Node::Array(_))) => Ok(node),
+        _ => Err(json_err.into()),
+    }
+}
+
+fn parse_input(bytes: &[u8], cli: &Cli) -> Result<Node> {
+    #[cfg(feature = "cbor")]
+    if cli.cbor {
+        return Node::from_cbor_slice(bytes).map_err(|err| anyhow!(err));
+    }
+    #[cfg(feature = "msgpack")]
+    if cli.msgpack {
+        return Node::from_msgpack_slice(bytes).map_err(|err| anyhow!(err));
+    }
+    parse_node(std::str::from_utf8(bytes)?, cli)
+}
+
+fn parse_node(input: &str, cli: &Cli) -> Result<Node> {
+    #[cfg(feature = "toml")]
+    if cli.toml {
+        return Node::from_toml_str(input).map_err(|err| anyhow!(err));
+    }
+    if cli.yaml {
         Node::from_yaml_str(input).map_err(|err| anyhow!(err))
     } else {
         Node::from_json_str(input).map_err(|err| anyhow!(err))
     }
 }
 
-fn build_options(_cli: &Cli) -> Result<DiffOptions> {
-    let options = DiffOptions::default();
+fn build_options(cli: &Cli, schema: Option<&Node>) -> Result<DiffOptions> {
+    let mut options = match &cli.opts {
+        Some(opts) => DiffOptions::from_json_opts(opts)?,
+        None => DiffOptions::default(),
+    };
+    if cli.prune_empty {
+        options = options.with_prune_empty(true);
+    }
+    if let Some(precision) = cli.precision {
+        options = options.with_precision(precision)?;
+    }
+    if let Some(tolerance) = cli.relative_precision {
+        options = options.with_relative_precision(tolerance)?;
+    }
+    if let Some(ulps) = cli.ulps {
+        options = options.with_max_ulps(ulps)?;
+    }
+    if let Some(preset) = cli.preset {
+        options = options.with_preset(preset);
+    }
+    if let Some(schema) = schema {
+        options = options.with_json_schema(schema)?;
+    }
     Ok(options)
 }
 
-fn merge_patch(lhs: &Node, rhs: &Node) -> Option<Node> {
-    match (lhs, rhs) {
-        (Node::Object(a), Node::Object(b)) => {
-            let mut keys: BTreeSet<&String> = BTreeSet::new();
-            keys.extend(a.keys());
-            keys.extend(b.keys());
-
-            let mut map = BTreeMap::new();
-            for key in keys {
-                match (a.get(key), b.get(key)) {
-                    (Some(left), Some(right)) => {
-                        if let Some(child) = merge_patch(left, right) {
-                            match &child {
-                                Node::Object(children) if children.is_empty() => {}
-                                _ => {
-                                    map.insert(key.clone(), child);
-                                }
-                            }
-                        }
-                    }
-                    (Some(_), None) => {
-                        map.insert(key.clone(), Node::Null);
-                    }
-                    (None, Some(value)) => {
-                        map.insert(key.clone(), value.clone());
-                    }
-                    (None, None) => {}
-                }
-            }
+fn read_schema(input: &OsString, cli: &Cli) -> Result<Node> {
+    let source = input_from(input, cli)?;
+    let bytes = read_input_bytes(&source)?;
+    sniff_json_or_yaml(&bytes).map_err(|err| parse_failed("schema", &source, &err, &bytes).into())
+}
 
-            if map.is_empty() {
-                None
-            } else {
-                Some(Node::Object(map))
+/// Set semantics from `--opts` or `--schema` have no diff engine yet, like
+/// `-set`; other option errors are the caller's.
+pub(crate) fn options_error_code(err: &OptionsError) -> ErrorCode {
+    match err {
+        OptionsError::UnsupportedArrayMode(_) | OptionsError::UnsupportedSchema { .. } => {
+            ErrorCode::Unsupported
+        }
+        _ => ErrorCode::Options,
+    }
+}
+
+/// Placeholder written over values selected with `--redact`.
+const REDACTED: &str = "REDACTED";
+
+/// The value of `--ignore` or `--redact`: an exact jd path or a JSONPath query.
+enum PathSelector {
+    Exact(jd_core::Path),
+    Query(JsonPath),
+}
+
+impl PathSelector {
+    fn parse(flag: &str, path: &str) -> Result<Self> {
+        if path.starts_with('$') {
+            return JsonPath::parse(path).map(Self::Query).map_err(|err| {
+                Diagnostic::new(ErrorCode::Usage, format!("invalid {flag} path: {err}")).into()
+            });
+        }
+        let segments: Vec<PathSegment> = serde_json::from_str(path).with_context(|| {
+            Diagnostic::new(
+                ErrorCode::Usage,
+                format!(
+                    "invalid {flag} path {path:?}; expected a JSON array such as [\"a\",0] or a JSONPath such as $.a[0]"
+                ),
+            )
+        })?;
+        Ok(Self::Exact(segments.into()))
+    }
+
+    /// The locations selected in `node`, in document order.
+    fn resolve(&self, node: &Node) -> Vec<jd_core::Path> {
+        match self {
+            Self::Exact(path) => vec![path.clone()],
+            Self::Query(query) => query.paths(node),
+        }
+    }
+}
+
+/// Removes the value at `path` from `node`; missing paths are left alone.
+fn remove_path(node: &mut Node, path: &[PathSegment]) {
+    let Some((last, parents)) = path.split_last() else {
+        *node = Node::Void;
+        return;
+    };
+    let mut current = node;
+    for segment in parents {
+        let child = match (current, segment) {
+            (Node::Object(map), PathSegment::Key(key)) => map.get_mut(key),
+            (Node::Array(items), PathSegment::Index(index)) => {
+                usize::try_from(*index).ok().and_then(|index| items.get_mut(index))
             }
+            _ => None,
+        };
+        match child {
+            Some(child) => current = child,
+            None => return,
         }
-        _ => {
-            if lhs == rhs {
-                None
-            } else {
-                Some(rhs.clone())
+    }
+    match (current, last) {
+        (Node::Object(map), PathSegment::Key(key)) => {
+            map.remove(key);
+        }
+        (Node::Array(items), PathSegment::Index(index)) => {
+            if let Some(index) = usize::try_from(*index).ok().filter(|index| *index < items.len()) {
+                items.remove(index);
             }
         }
+        _ => {}
     }
 }
 
@@ -374,6 +1448,7 @@ where
             Some("-mset") => canonicalized.push(OsString::from("--mset")),
             Some("-precision") => canonicalized.push(OsString::from("--precision")),
             Some("-setkeys") => canonicalized.push(OsString::from("--setkeys")),
+            Some("-opts") => canonicalized.push(OsString::from("--opts")),
             Some("-v2") => canonicalized.push(OsString::from("--v2")),
             Some(other) if other.starts_with("-f=") => {
                 canonicalized.push(OsString::from("-f"));
@@ -387,6 +1462,10 @@ where
                 canonicalized.push(OsString::from("--setkeys"));
                 canonicalized.push(OsString::from(other.trim_start_matches("-setkeys=")));
             }
+            Some(other) if other.starts_with("-opts=") => {
+                canonicalized.push(OsString::from("--opts"));
+                canonicalized.push(OsString::from(other.trim_start_matches("-opts=")));
+            }
             _ => canonicalized.push(arg),
         }
     }
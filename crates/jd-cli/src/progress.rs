@@ -0,0 +1,45 @@
+//! The STDERR progress bar shown while diffing large inputs.
+
+use std::cell::Cell;
+use std::io::{self, IsTerminal, Write};
+
+use jd_core::progress::{self, Phase, ProgressGuard, ProgressSink};
+
+/// Combined input size above which an interactive run shows progress.
+pub(crate) const THRESHOLD_BYTES: usize = 8 * 1024 * 1024;
+
+const WIDTH: usize = 30;
+
+/// Installs a progress bar for the rest of the run when `forced` or when the
+/// inputs total at least [`THRESHOLD_BYTES`] and STDERR is a terminal.
+pub(crate) fn show(forced: bool, input_bytes: usize) -> Option<ProgressGuard> {
+    let terminal = io::stderr().is_terminal();
+    (forced || (terminal && input_bytes >= THRESHOLD_BYTES))
+        .then(|| progress::install(Bar { terminal, drawn: Cell::new(false) }))
+}
+
+/// Redraws one line in place, such as `lcs    [#########.....]  31%`.
+struct Bar {
+    terminal: bool,
+    drawn: Cell<bool>,
+}
+
+impl ProgressSink for Bar {
+    fn report(&self, phase: Phase, percent: u8) {
+        let filled = WIDTH * usize::from(percent) / 100;
+        let bar = format!("{}{}", "#".repeat(filled), ".".repeat(WIDTH - filled));
+        let _ = write!(io::stderr(), "\r{phase:<6} [{bar}] {percent:>3}%");
+        self.drawn.set(true);
+    }
+}
+
+impl Drop for Bar {
+    /// Clears the bar before the diff is printed, or ends its line when
+    /// STDERR is not a terminal that can erase it.
+    fn drop(&mut self) {
+        if self.drawn.get() {
+            let end = if self.terminal { "\r\x1b[K" } else { "\n" };
+            let _ = write!(io::stderr(), "{end}");
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! `jd serve --api`: a JSON diff service (enabled by the `serve` feature).
+//!
+//! Every endpoint takes a JSON body by `POST` and mirrors a CLI mode, with
+//! `lhs` and `rhs` standing for FILE1 and FILE2:
+//!
+//! - `/v1/diff` takes documents `lhs` and `rhs` plus optional `options` (a
+//!   `jd -opts` array) and returns the diff in every format:
+//!   `{"equal":false,"jd":"@ …","patch":[…],"merge":{…}}`.
+//! - `/v1/patch` applies diff `lhs`, written in `format` (`jd` by default,
+//!   `patch`, or `merge`), to document `rhs`: `{"result":…}`, or `{}`
+//!   when the diff removes the whole document.
+//! - `/v1/translate` converts `lhs` by a `jd -t` spec in `format`:
+//!   `{"result":"…"}`.
+//!
+//! Diffs and translation inputs are strings or, for the JSON formats, JSON
+//! values. Failures return the `--error-format json` object with a matching
+//! HTTP status.
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context, Result};
+use jd_core::{DiffOptions, Limits, Node, RenderConfig};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::diagnostics::{self, Diagnostic, ErrorCode, ErrorFormat};
+use crate::{compute_diff, options_error_code, read_patch, render_diff, translate, OutputFormat};
+
+/// Request bodies are refused beyond this size unless `--max-input-bytes`
+/// says otherwise.
+const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Handles API requests under the limits given on the command line.
+#[derive(Clone, Debug)]
+pub(crate) struct Api {
+    limits: Limits,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiffRequest {
+    lhs: JsonValue,
+    rhs: JsonValue,
+    #[serde(default)]
+    options: Option<JsonValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatchRequest {
+    lhs: JsonValue,
+    rhs: JsonValue,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TranslateRequest {
+    lhs: JsonValue,
+    format: String,
+}
+
+impl Api {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+
+    /// Answers one request, returning the HTTP status and JSON body.
+    pub(crate) fn handle(&self, method: &str, path: &str, body: &[u8]) -> (u16, String) {
+        let endpoint = match path.split('?').next().unwrap_or_default() {
+            "/v1/diff" => Self::diff,
+            "/v1/patch" => Self::patch,
+            "/v1/translate" => Self::translate,
+            other => {
+                let message = format!("no such endpoint: {other}");
+                return failure(404, &Diagnostic::new(ErrorCode::Usage, message).into());
+            }
+        };
+        if method != "POST" {
+            let message = format!("{path} only accepts POST");
+            return failure(405, &Diagnostic::new(ErrorCode::Usage, message).into());
+        }
+        match endpoint(self, body) {
+            Ok(response) => (200, response.to_string()),
+            Err(err) => failure(status_for(&err), &err),
+        }
+    }
+
+    fn diff(&self, body: &[u8]) -> Result<JsonValue> {
+        let request: DiffRequest = parse_body(body)?;
+        let lhs = self.document("lhs", request.lhs)?;
+        let rhs = self.document("rhs", request.rhs)?;
+        let options = match request.options {
+            Some(opts) => DiffOptions::from_json_opts(&opts.to_string())
+                .map_err(|err| Diagnostic::new(options_error_code(&err), err.to_string()))?,
+            None => DiffOptions::default(),
+        };
+
+        let config = RenderConfig::default();
+        let diff = compute_diff(&lhs, &rhs, &options, &self.limits)?;
+        let (native, have_diff) = render_diff(OutputFormat::Native, &diff, &config)?;
+        let (patch, _) = render_diff(OutputFormat::Patch, &diff, &config)?;
+        let merge_diff = if options.merge() {
+            diff
+        } else {
+            compute_diff(&lhs, &rhs, &options.with_merge(true), &self.limits)?
+        };
+        let (merge, _) = render_diff(OutputFormat::Merge, &merge_diff, &config)?;
+        Ok(json!({
+            "equal": !have_diff,
+            "jd": native,
+            "patch": serde_json::from_str::<JsonValue>(&patch)?,
+            "merge": serde_json::from_str::<JsonValue>(&merge)?,
+        }))
+    }
+
+    fn patch(&self, body: &[u8]) -> Result<JsonValue> {
+        let request: PatchRequest = parse_body(body)?;
+        let format = match request.format.as_deref() {
+            None | Some("jd") => OutputFormat::Native,
+            Some("patch") => OutputFormat::Patch,
+            Some("merge") => OutputFormat::Merge,
+            Some(other) => bail!(Diagnostic::new(
+                ErrorCode::Usage,
+                format!("unknown format {other:?}; expected \"jd\", \"patch\", or \"merge\"")
+            )),
+        };
+        let diff = read_patch(&text("lhs", request.lhs)?, format).map_err(|err| {
+            Diagnostic::new(ErrorCode::Parse, format!("failed to parse patch: {err:#}"))
+                .with_input("lhs")
+        })?;
+        let patched = self.document("rhs", request.rhs)?.apply_patch(&diff).map_err(|err| {
+            Diagnostic::new(ErrorCode::PatchConflict, err.to_string()).with_input("rhs")
+        })?;
+        // A removed document has no value, not a `null` one.
+        Ok(match patched.to_json_value() {
+            Some(result) => json!({ "result": result }),
+            None => json!({}),
+        })
+    }
+
+    fn translate(&self, body: &[u8]) -> Result<JsonValue> {
+        let request: TranslateRequest = parse_body(body)?;
+        let input = text("lhs", request.lhs)?;
+        self.limits
+            .check_input_len(input.len())
+            .map_err(|err| Diagnostic::new(ErrorCode::Limit, err.to_string()).with_input("lhs"))?;
+        let result = translate::translate(&request.format, &input, &RenderConfig::default())?;
+        Ok(json!({ "result": result }))
+    }
+
+    fn document(&self, field: &str, value: JsonValue) -> Result<Node> {
+        let node = Node::from_json_value(value).map_err(|err| {
+            Diagnostic::new(ErrorCode::Parse, format!("failed to parse {field}: {err}"))
+                .with_input(field)
+        })?;
+        self.limits.check_node(&node).map_err(|err| {
+            Diagnostic::new(ErrorCode::Limit, format!("refusing {field}: {err}")).with_input(field)
+        })?;
+        Ok(node)
+    }
+
+    fn max_body_bytes(&self) -> usize {
+        self.limits.max_input_bytes().unwrap_or(DEFAULT_MAX_BODY_BYTES)
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|err| {
+        Diagnostic::new(ErrorCode::Parse, format!("invalid request body: {err}")).into()
+    })
+}
+
+/// Diffs and translation inputs are text; JSON values stand for their
+/// compact serialization, so JSON Patch and merge patches can be inlined.
+fn text(field: &str, value: JsonValue) -> Result<String> {
+    match value {
+        JsonValue::String(text) => Ok(text),
+        JsonValue::Null => {
+            bail!(Diagnostic::new(ErrorCode::Usage, format!("missing {field}")).with_input(field))
+        }
+        value => Ok(value.to_string()),
+    }
+}
+
+fn status_for(err: &anyhow::Error) -> u16 {
+    let code = err.downcast_ref::<Diagnostic>().map_or(ErrorCode::Error, Diagnostic::code);
+    match code {
+        ErrorCode::Usage | ErrorCode::Parse | ErrorCode::Options | ErrorCode::Translate => 400,
+        ErrorCode::PatchConflict => 409,
+        ErrorCode::Limit => 413,
+        ErrorCode::Render => 422,
+        ErrorCode::Unsupported => 501,
+        _ => 500,
+    }
+}
+
+fn failure(status: u16, err: &anyhow::Error) -> (u16, String) {
+    (status, diagnostics::render(err, ErrorFormat::Json))
+}
+
+/// Serves `api` on `addr` until the process is stopped, answering requests
+/// on one worker thread per available core.
+pub(crate) fn run(addr: SocketAddr, api: &Api) -> Result<()> {
+    let server = Server::http(addr).map_err(|err| {
+        anyhow!(Diagnostic::new(ErrorCode::Io, format!("failed to listen on {addr}: {err}")))
+    })?;
+    let local = server.server_addr().to_ip().unwrap_or(addr);
+    eprintln!("jd API listening on http://{local}");
+    let workers = thread::available_parallelism().map_or(1, usize::from);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    respond(api, request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn respond(api: &Api, mut request: Request) {
+    let limit = api.max_body_bytes();
+    let mut body = Vec::new();
+    let read = request
+        .as_reader()
+        .take(u64::try_from(limit).unwrap_or(u64::MAX).saturating_add(1))
+        .read_to_end(&mut body)
+        .context(Diagnostic::new(ErrorCode::Io, "failed to read request body"));
+    let (status, body) = match read {
+        Err(err) => failure(400, &err),
+        Ok(_) if body.len() > limit => {
+            let message = format!("request body exceeds {limit} bytes");
+            failure(413, &Diagnostic::new(ErrorCode::Limit, message).into())
+        }
+        Ok(_) => api.handle(request.method().as_str(), request.url(), &body),
+    };
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    let _ = request
+        .respond(Response::from_string(body).with_status_code(status).with_header(content_type));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(path: &str, body: JsonValue) -> (u16, JsonValue) {
+        let (status, body) =
+            Api::new(Limits::default()).handle("POST", path, body.to_string().as_bytes());
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn diff_renders_every_format() {
+        let (status, body) =
+            call("/v1/diff", json!({"lhs": {"a": 1, "b": 2}, "rhs": {"a": 2, "b": 2}}));
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            json!({
+                "equal": false,
+                "jd": "@ [\"a\"]\n- 1\n+ 2\n",
+                "patch": [
+                    {"op": "test", "path": "/a", "value": 1},
+                    {"op": "remove", "path": "/a", "value": 1},
+                    {"op": "add", "path": "/a", "value": 2}
+                ],
+                "merge": {"a": 2}
+            })
+        );
+        let (_, body) = call(
+            "/v1/diff",
+            json!({"lhs": [1.0], "rhs": [1.001], "options": [{"precision": 0.01}]}),
+        );
+        assert_eq!(body, json!({"equal": true, "jd": "", "patch": [], "merge": {}}));
+    }
+
+    #[test]
+    fn patch_applies_each_format() {
+        let (status, body) =
+            call("/v1/patch", json!({"lhs": "@ [\"a\"]\n- 1\n+ 2\n", "rhs": {"a": 1}}));
+        assert_eq!((status, body), (200, json!({"result": {"a": 2}})));
+        let ops = json!([{"op": "add", "path": "/b", "value": true}]);
+        let (_, body) = call("/v1/patch", json!({"lhs": ops, "rhs": {"a": 1}, "format": "patch"}));
+        assert_eq!(body, json!({"result": {"a": 1, "b": true}}));
+        let (status, body) =
+            call("/v1/patch", json!({"lhs": "@ [\"a\"]\n- 3\n+ 2\n", "rhs": {"a": 1}}));
+        assert_eq!((status, &body["code"]), (409, &json!("patch_conflict")));
+        let (_, body) = call("/v1/patch", json!({"lhs": "@ []\n- null\n", "rhs": null}));
+        assert_eq!(body, json!({}));
+        let (_, body) = call("/v1/patch", json!({"lhs": "@ []\n- 1\n+ null\n", "rhs": 1}));
+        assert_eq!(body, json!({"result": null}));
+    }
+
+    #[test]
+    fn translate_follows_the_jd_table() {
+        let (status, body) =
+            call("/v1/translate", json!({"lhs": {"a": [1]}, "format": "json2yaml"}));
+        assert_eq!((status, body), (200, json!({"result": "a:\n- 1\n"})));
+        let (status, body) = call("/v1/translate", json!({"lhs": "{}", "format": "patch2merge"}));
+        assert_eq!((status, &body["code"]), (400, &json!("translate")));
+    }
+
+    #[test]
+    fn bad_requests_report_diagnostics() {
+        let api = Api::new(Limits::default());
+        assert_eq!(api.handle("POST", "/v2/diff", b"{}").0, 404);
+        assert_eq!(api.handle("GET", "/v1/diff", b"").0, 405);
+        let (status, body) = api.handle("POST", "/v1/diff", b"{\"lhs\":1}");
+        assert_eq!(status, 400);
+        assert!(body.contains(r#""code":"parse""#), "{body}");
+        let (status, body) = call("/v1/diff", json!({"lhs": 1, "rhs": 2, "options": ["FAST"]}));
+        assert_eq!((status, &body["code"]), (400, &json!("options")));
+
+        let api = Api::new(Limits::default().with_max_nodes(2));
+        let (status, body) = api.handle("POST", "/v1/diff", br#"{"lhs":[1,2,3],"rhs":[]}"#);
+        assert_eq!(status, 413);
+        assert!(body.contains(r#""input":"lhs""#), "{body}");
+    }
+}
@@ -0,0 +1,247 @@
+//! Translate mode (`-t FROM2TO`) conversions between diff and document formats.
+//!
+//! Mirrors the Go translation table (`jd2patch`, `patch2jd`, `jd2merge`,
+//! `merge2jd`, `json2yaml`, `yaml2json`) and adds an `auto` source that sniffs
+//! the input format, plus `json2toml` / `toml2json` with the `toml` feature.
+
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context, Result};
+use jd_core::{Diff, Node, RenderConfig};
+use serde_json::Value as JsonValue;
+
+use crate::diagnostics::{Diagnostic, ErrorCode};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Format {
+    Jd,
+    Patch,
+    Merge,
+    Json,
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "jd" => Some(Self::Jd),
+            "patch" => Some(Self::Patch),
+            "merge" => Some(Self::Merge),
+            "json" => Some(Self::Json),
+            "yaml" => Some(Self::Yaml),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Jd => "jd",
+            Self::Patch => "patch",
+            Self::Merge => "merge",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            #[cfg(feature = "toml")]
+            Self::Toml => "toml",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Translations supported by Go `jd` v2.2.2.
+const SUPPORTED: [(Format, Format); 6] = [
+    (Format::Jd, Format::Patch),
+    (Format::Patch, Format::Jd),
+    (Format::Jd, Format::Merge),
+    (Format::Merge, Format::Jd),
+    (Format::Json, Format::Yaml),
+    (Format::Yaml, Format::Json),
+];
+
+/// Translations beyond the Go table, available with optional features.
+#[cfg(feature = "toml")]
+const EXTENSIONS: [(Format, Format); 2] =
+    [(Format::Json, Format::Toml), (Format::Toml, Format::Json)];
+#[cfg(not(feature = "toml"))]
+const EXTENSIONS: [(Format, Format); 0] = [];
+
+fn is_supported(from: Format, to: Format) -> bool {
+    SUPPORTED.contains(&(from, to)) || EXTENSIONS.contains(&(from, to))
+}
+
+/// Translates `input` according to a `FROM2TO` spec such as `jd2patch` or `auto2json`.
+pub(crate) fn translate(spec: &str, input: &str, render: &RenderConfig) -> Result<String> {
+    let unsupported = || {
+        anyhow!(Diagnostic::new(ErrorCode::Translate, format!("unsupported translation: {spec:?}")))
+    };
+    let (from, to) = spec.split_once('2').ok_or_else(unsupported)?;
+    let to = Format::parse(to).ok_or_else(unsupported)?;
+    let from = if from == "auto" {
+        detect(input, to)?
+    } else {
+        let from = Format::parse(from).ok_or_else(unsupported)?;
+        if !is_supported(from, to) {
+            return Err(unsupported());
+        }
+        from
+    };
+
+    match from {
+        Format::Jd | Format::Patch | Format::Merge => {
+            let mut diff = read_diff(from, input)?;
+            if render.sorted_hunks() {
+                diff = diff.sorted();
+            }
+            match to {
+                Format::Jd => Ok(diff.render(render)),
+                Format::Patch => {
+                    diff.render_patch().context(rendering("failed to render JSON Patch"))
+                }
+                Format::Merge => {
+                    diff.render_merge().context(rendering("failed to render merge patch"))
+                }
+                _ => Err(unsupported()),
+            }
+        }
+        Format::Json => {
+            let node = Node::from_json_str(input).context(parsing("failed to parse JSON input"))?;
+            write_document(&node, to)
+        }
+        Format::Yaml => {
+            let node = Node::from_yaml_str(input).context(parsing("failed to parse YAML input"))?;
+            write_document(&node, to)
+        }
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let node = Node::from_toml_str(input).context(parsing("failed to parse TOML input"))?;
+            write_document(&node, to)
+        }
+    }
+}
+
+fn write_document(node: &Node, format: Format) -> Result<String> {
+    match format {
+        Format::Json => Ok(node.to_json_value().map(|value| value.to_string()).unwrap_or_default()),
+        Format::Yaml => Ok(node.to_yaml_string().unwrap_or_default()),
+        #[cfg(feature = "toml")]
+        Format::Toml => node.to_toml_string().context(rendering("failed to write TOML output")),
+        Format::Jd | Format::Patch | Format::Merge => unreachable!("diffs are not documents"),
+    }
+}
+
+fn read_diff(format: Format, input: &str) -> Result<Diff> {
+    let diff = match format {
+        Format::Jd => Diff::from_native_str(input),
+        Format::Patch => Diff::from_patch_str(input),
+        Format::Merge => Diff::from_merge_str(input),
+        _ => unreachable!("documents are not diffs"),
+    };
+    diff.with_context(|| parsing(&format!("failed to read {format} input")))
+}
+
+fn parsing(message: &str) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Parse, message)
+}
+
+fn rendering(message: &str) -> Diagnostic {
+    Diagnostic::new(ErrorCode::Render, message)
+}
+
+/// Guesses the source format of `input` for a translation targeting `to`.
+///
+/// Some inputs are ambiguous (every merge patch is also a JSON object), so the
+/// candidates are tried in order of specificity and the first one that can be
+/// translated to `to` wins.
+pub(crate) fn detect(input: &str, to: Format) -> Result<Format> {
+    let candidates = candidates(input);
+    let Some(first) = candidates.first() else {
+        bail!(Diagnostic::new(
+            ErrorCode::Translate,
+            "unable to detect input format for translation"
+        ));
+    };
+    candidates.iter().copied().find(|from| is_supported(*from, to)).ok_or_else(|| {
+        anyhow!(Diagnostic::new(
+            ErrorCode::Translate,
+            format!("cannot translate detected {first} input to {to}")
+        ))
+    })
+}
+
+fn candidates(input: &str) -> Vec<Format> {
+    let trimmed = input.trim_start();
+    if trimmed.starts_with('@') || trimmed.starts_with('^') {
+        return vec![Format::Jd];
+    }
+    match serde_json::from_str::<JsonValue>(input) {
+        Ok(JsonValue::Array(ops)) if ops.iter().all(is_patch_op) => {
+            vec![Format::Patch, Format::Json, Format::Yaml]
+        }
+        Ok(JsonValue::Object(_)) => vec![Format::Merge, Format::Json, Format::Yaml],
+        Ok(_) => vec![Format::Json, Format::Yaml],
+        Err(_) => {
+            let mut formats = Vec::new();
+            // TOML goes first: `key = "value"` is also a valid YAML scalar.
+            #[cfg(feature = "toml")]
+            if Node::from_toml_str(input).is_ok() {
+                formats.push(Format::Toml);
+            }
+            if Node::from_yaml_str(input).is_ok() {
+                formats.push(Format::Yaml);
+            }
+            formats
+        }
+    }
+}
+
+fn is_patch_op(value: &JsonValue) -> bool {
+    value.get("op").is_some_and(JsonValue::is_string)
+        && value.get("path").is_some_and(JsonValue::is_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_each_source_format() {
+        assert_eq!(detect("@ [\"a\"]\n+ 1\n", Format::Patch).unwrap(), Format::Jd);
+        assert_eq!(
+            detect(r#"[{"op":"add","path":"/a","value":1}]"#, Format::Jd).unwrap(),
+            Format::Patch
+        );
+        assert_eq!(detect(r#"{"a":null}"#, Format::Jd).unwrap(), Format::Merge);
+        assert_eq!(detect(r#"{"a":null}"#, Format::Yaml).unwrap(), Format::Json);
+        assert_eq!(detect("[1,2]", Format::Yaml).unwrap(), Format::Json);
+        assert_eq!(detect("a: 1\n", Format::Json).unwrap(), Format::Yaml);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn detects_toml_ahead_of_yaml() {
+        assert_eq!(detect("a = 1\n", Format::Json).unwrap(), Format::Toml);
+        assert_eq!(detect("{\"a\":1}", Format::Toml).unwrap(), Format::Json);
+    }
+
+    #[test]
+    fn detection_rejects_untranslatable_inputs() {
+        let err = detect("[1,2]", Format::Jd).unwrap_err();
+        assert_eq!(err.to_string(), "cannot translate detected json input to jd");
+        let err = detect("{", Format::Json).unwrap_err();
+        assert_eq!(err.to_string(), "unable to detect input format for translation");
+    }
+
+    #[test]
+    fn rejects_pairs_outside_the_translation_table() {
+        let err = translate("json2jd", "{}", &RenderConfig::default()).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported translation: \"json2jd\"");
+    }
+}
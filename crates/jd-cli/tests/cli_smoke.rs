@@ -93,6 +93,34 @@ fn diff_patch_matches_fixture() {
         .stderr(predicate::str::is_empty());
 }
 
+#[test]
+fn diff_lsp_reports_ranges_in_both_inputs() {
+    let lhs = write_tempfile("{\n  \"a\": 1,\n  \"b\": [true]\n}");
+    let rhs = write_tempfile("{\"a\": 2, \"b\": [true, null]}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    let output = cmd.args(["-f", "lsp"]).arg(lhs.path()).arg(rhs.path()).output().expect("runs");
+    assert_eq!(output.status.code(), Some(1));
+    let hunks: serde_json::Value = serde_json::from_slice(&output.stdout).expect("JSON output");
+    let range = |line: u64, start: u64, end: u64| serde_json::json!({"start": {"line": line, "character": start}, "end": {"line": line, "character": end}});
+    assert_eq!(hunks[0]["path"], serde_json::json!(["a"]));
+    assert_eq!(hunks[0]["kind"], "replace");
+    assert_eq!(hunks[0]["lhs"]["range"], range(1, 7, 8));
+    assert_eq!(hunks[0]["rhs"]["range"], range(0, 6, 7));
+    assert_eq!(hunks[0]["message"], "@ [\"a\"]\n- 1\n+ 2\n");
+    assert_eq!(hunks[1]["kind"], "add");
+    assert_eq!(hunks[1]["lhs"]["range"], range(2, 7, 13));
+    assert_eq!(hunks[1]["rhs"]["range"], range(0, 21, 25));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "-f", "lsp"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("cannot be read as a patch"));
+}
+
 #[test]
 fn diff_color_output_matches_fixture() {
     let fixture = load_fixture("string_diff_color");
@@ -124,3 +152,1172 @@ fn diff_single_argument_reads_stdin() {
         .stdout(expected)
         .stderr(predicate::str::is_empty());
 }
+
+#[test]
+fn diff_prune_empty_ignores_empty_containers() {
+    let lhs = write_tempfile("{\"a\":1,\"tags\":[]}");
+    let rhs = write_tempfile("{\"a\":1,\"meta\":{}}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--prune-empty")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(0)
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn diff_relative_precision_tolerates_scaled_differences() {
+    let lhs = write_tempfile("{\"mass\":5.972e24,\"charge\":1.602e-19}");
+    let rhs = write_tempfile("{\"mass\":5.9721e24,\"charge\":1.6021e-19}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--relative-precision")
+        .arg("0.001")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(0)
+        .stdout(predicate::str::is_empty());
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--relative-precision=-1")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("numeric tolerance must be a finite"));
+}
+
+#[test]
+fn translate_jd2patch_matches_upstream() {
+    let input = write_tempfile("@ [\"version\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-t").arg("jd2patch").arg(input.path()).assert().code(0).stdout(
+        "[{\"op\":\"test\",\"path\":\"/version\",\"value\":1},{\"op\":\"remove\",\"path\":\"/version\",\"value\":1},{\"op\":\"add\",\"path\":\"/version\",\"value\":2}]",
+    );
+}
+
+#[test]
+fn translate_auto_detects_source_format() {
+    let cases = [
+        ("[{\"op\":\"add\",\"path\":\"/a\",\"value\":1}]", "auto2jd", "@ [\"a\"]\n+ 1\n"),
+        ("{\"a\":null}", "auto2jd", "^ {\"Merge\":true}\n@ [\"a\"]\n+\n"),
+        ("@ [\"a\"]\n+ 1\n", "auto2patch", "[{\"op\":\"add\",\"path\":\"/a\",\"value\":1}]"),
+        ("a: [1, 2]\n", "auto2json", "{\"a\":[1,2]}"),
+        ("{\"a\":[1,2]}", "auto2yaml", "a:\n- 1\n- 2\n"),
+    ];
+    for (input, spec, expected) in cases {
+        let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+        cmd.arg("-t").arg(spec).write_stdin(input).assert().code(0).stdout(expected);
+    }
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-t")
+        .arg("auto2jd")
+        .write_stdin("[1,2]")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("cannot translate detected json input to jd"));
+}
+
+#[test]
+fn diff_toml_inputs() {
+    let lhs = write_tempfile("[package]\nname = \"jd\"\nversion = \"0.1.0\"\n");
+    let rhs = write_tempfile("[package]\nname = \"jd\"\nversion = \"0.2.0\"\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--toml")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"package\",\"version\"]\n- \"0.1.0\"\n+ \"0.2.0\"\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-t")
+        .arg("json2toml")
+        .write_stdin("{\"package\":{\"name\":\"jd\"}}")
+        .assert()
+        .code(0)
+        .stdout("[package]\nname = \"jd\"\n");
+}
+
+#[cfg(all(feature = "cbor", feature = "msgpack"))]
+#[test]
+fn diff_binary_encoded_inputs() {
+    let mut lhs = NamedTempFile::new().expect("create temp file");
+    let mut rhs = NamedTempFile::new().expect("create temp file");
+    // CBOR {"a": 1} and {"a": 2}
+    lhs.write_all(&[0xA1, 0x61, b'a', 0x01]).expect("write temp file");
+    rhs.write_all(&[0xA1, 0x61, b'a', 0x02]).expect("write temp file");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--cbor")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n");
+
+    // MessagePack {"a": 1} and {"a": 2} on STDIN
+    let mut msgpack = NamedTempFile::new().expect("create temp file");
+    msgpack.write_all(&[0x81, 0xA1, b'a', 0x01]).expect("write temp file");
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--msgpack")
+        .arg(msgpack.path())
+        .write_stdin(vec![0x81, 0xA1, b'a', 0x02])
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n");
+}
+
+/// Serves `body` to each of `requests` connections on a local port.
+#[cfg(feature = "http")]
+fn serve(body: &'static str, requests: usize) -> String {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind local port");
+    let url = format!("http://{}/doc.json", listener.local_addr().expect("local addr"));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.expect("accept connection");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+            let mut line = String::new();
+            while reader.read_line(&mut line).expect("read request") > 2 {
+                line.clear();
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).expect("write response");
+        }
+    });
+    url
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn diff_fetches_http_inputs() {
+    let url = serve("{\"status\":\"up\"}", 2);
+    let local = write_tempfile("{\"status\":\"down\"}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(local.path())
+        .arg(&url)
+        .assert()
+        .code(1)
+        .stdout("@ [\"status\"]\n- \"down\"\n+ \"up\"\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--http-max-bytes=4")
+        .arg(local.path())
+        .arg(&url)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("exceeds 4 bytes"));
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_api_answers_diff_requests() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+    use std::process::Stdio;
+
+    let mut server = std::process::Command::new(assert_cmd::cargo::cargo_bin("jd"))
+        .args(["serve", "--api", "--port", "0"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("start jd serve");
+    let mut banner = String::new();
+    BufReader::new(server.stderr.take().expect("stderr piped"))
+        .read_line(&mut banner)
+        .expect("read banner");
+    let addr = banner.trim().rsplit("http://").next().expect("listening address").to_string();
+
+    let body = r#"{"lhs":{"a":1},"rhs":{"a":2}}"#;
+    let mut stream = TcpStream::connect(&addr).expect("connect to jd serve");
+    write!(
+        stream,
+        "POST /v1/diff HTTP/1.1\r\nHost: {addr}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .expect("send request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("read response");
+    server.kill().expect("stop jd serve");
+    server.wait().expect("reap jd serve");
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let json = response.split("\r\n\r\n").nth(1).expect("response body");
+    let json: serde_json::Value = serde_json::from_str(json).expect("JSON body");
+    assert_eq!(json["jd"], "@ [\"a\"]\n- 1\n+ 2\n");
+    assert_eq!(json["merge"], serde_json::json!({"a": 2}));
+}
+
+#[test]
+fn merge_format_nulls_removed_members_and_replaces_arrays() {
+    let lhs = write_tempfile("{\"a\":{\"b\":1,\"c\":2},\"list\":[1,2,3]}");
+    let rhs = write_tempfile("{\"a\":{\"b\":1},\"list\":[1,3]}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-f", "merge"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("{\"a\":{\"c\":null},\"list\":[1,3]}");
+}
+
+#[test]
+fn opts_flag_reads_go_option_arrays() {
+    let lhs = write_tempfile("{\"a\":1,\"version\":\"1.0\"}");
+    let rhs = write_tempfile("{\"a\":2,\"version\":\"2.0\"}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-opts=[{\"@\":[\"version\"],\"^\":[\"DIFF_OFF\"]}]")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--opts", "[\"MERGE\"]"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::starts_with("^ {\"Merge\":true}\n@ [\"a\"]\n+ 2\n"));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--opts", "[\"FAST\"]"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr("invalid options: unsupported option \"FAST\"\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--opts", "[{\"@\":[\"a\"],\"^\":[\"SET\"]}]", "--error-format", "json"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("\"code\":\"unsupported\""));
+}
+
+#[test]
+fn diff_writes_multiple_output_formats() {
+    let lhs = write_tempfile("{\"a\":1}");
+    let rhs = write_tempfile("{\"a\":2}");
+    let dir = tempfile::tempdir().expect("create temp dir");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--output-formats=jd,patch,merge")
+        .arg("-o")
+        .arg(dir.path())
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty());
+    let read = |name: &str| fs::read_to_string(dir.path().join(name)).expect("output written");
+    assert_eq!(read("diff.jd"), "@ [\"a\"]\n- 1\n+ 2\n");
+    assert_eq!(
+        read("diff.patch"),
+        "[{\"op\":\"test\",\"path\":\"/a\",\"value\":1},{\"op\":\"remove\",\"path\":\"/a\",\"value\":1},{\"op\":\"add\",\"path\":\"/a\",\"value\":2}]"
+    );
+    assert_eq!(read("diff.merge"), "{\"a\":2}");
+
+    let base = dir.path().join("changes");
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--output-formats")
+        .arg("patch,jd")
+        .arg("-o")
+        .arg(&base)
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1);
+    assert!(dir.path().join("changes.jd").exists());
+    assert!(dir.path().join("changes.patch").exists());
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--output-formats=jd")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--output-formats requires -o"));
+}
+
+#[test]
+fn quiet_and_exit_code_toggles() {
+    let lhs = write_tempfile("{\"a\":1}");
+    let rhs = write_tempfile("{\"a\":2}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--quiet").arg(lhs.path()).arg(rhs.path()).assert().code(1).stdout("");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--quiet").arg(lhs.path()).arg(lhs.path()).assert().code(0).stdout("");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--no-exit-code")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(0)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--no-exit-code", "--exit-code"]).arg(lhs.path()).arg(rhs.path()).assert().code(1);
+}
+
+#[test]
+fn error_format_json_reports_structured_errors() {
+    let lhs = write_tempfile("{\"a\":");
+    let rhs = write_tempfile("{}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    let output = cmd
+        .args(["--error-format", "json"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("")
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).expect("stderr is JSON");
+    assert_eq!(error["code"], "parse");
+    assert_eq!(error["input"], lhs.path().display().to_string());
+    assert_eq!(error["path"], "[\"a\"]");
+    assert_eq!((error["line"].as_u64(), error["column"].as_u64()), (Some(1), Some(5)));
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("failed to parse first input ({}): ", lhs.path().display())));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    let output = cmd
+        .args(["--error-format=json", "--no-such-flag"])
+        .assert()
+        .code(2)
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).expect("stderr is JSON");
+    assert_eq!(error["code"], "usage");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr(format!(
+        "failed to parse first input ({path}): invalid JSON: EOF while parsing a value at line 1 column 5\n \
+         --> {path}:1:5\n  |\n1 | {{\"a\":\n  |     ^\n",
+        path = lhs.path().display()
+    ));
+}
+
+#[test]
+fn redact_hides_values_in_both_inputs() {
+    let lhs = write_tempfile("{\"auth\":{\"token\":\"old-secret\"},\"n\":1}");
+    let rhs = write_tempfile("{\"auth\":{\"token\":\"new-secret\"},\"n\":2,\"key\":\"k3y\"}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--redact", "[\"auth\",\"token\"]", "--redact", "[\"key\"]"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"n\"]\n- 1\n+ 2\n@ [\"key\"]\n+ \"REDACTED\"\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--redact", "auth.token"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("invalid --redact path \"auth.token\""));
+}
+
+#[test]
+fn jsonpath_selects_ignored_and_redacted_values() {
+    let lhs = write_tempfile(
+        r#"{"items":[{"id":1,"rev":3,"auth":{"token":"a"}},{"id":2,"rev":4}],"token":"x"}"#,
+    );
+    let rhs =
+        write_tempfile(r#"{"items":[{"id":1,"rev":9,"auth":{"token":"b"}},{"id":3}],"token":"y"}"#);
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--ignore", "$.items[*].rev", "--redact", "$..token"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"items\",1,\"id\"]\n- 2\n+ 3\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--ignore", "$.items[-1]", "--ignore", "$.items[*].rev", "--ignore", "$..auth"])
+        .args(["--ignore", "$.token"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--opts", r#"[{"@":"$.items[*].rev","^":["DIFF_OFF"]}]"#, "--ignore", "$.items[1]"])
+        .args(["--redact", "$..token"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--ignore", "$.items["])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("invalid --ignore path: invalid JSONPath \"$.items[\""));
+}
+
+#[test]
+fn sort_hunks_orders_translated_and_computed_diffs() {
+    let diff = write_tempfile("@ [\"b\"]\n+ 1\n@ [\"a\",\"c\"]\n- 2\n@ [\"a\"]\n+ 3\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-t", "jd2patch", "--sort-hunks"])
+        .arg(diff.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(r#"[{"op":"add","path":"/a","value":3}"#));
+
+    let lhs = write_tempfile(r#"{"b":1,"a":[1]}"#);
+    let rhs = write_tempfile(r#"{"b":2,"a":[2]}"#);
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--sort-hunks", "-f", "patch"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(r#""path":"/a/0""#));
+}
+
+#[test]
+fn sort_arrays_by_ignores_element_order() {
+    let lhs = write_tempfile("{\"users\":[{\"id\":2},{\"id\":1}]}");
+    let reordered = write_tempfile("{\"users\":[{\"id\":1},{\"id\":2}]}");
+    let duplicated = write_tempfile("{\"users\":[{\"id\":1},{\"id\":2},{\"id\":1}]}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--sort-arrays-by", "id"]).arg(lhs.path()).arg(reordered.path()).assert().code(0);
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--sort-arrays-by", "id"])
+        .arg(lhs.path())
+        .arg(duplicated.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"users\",1]\n  {\"id\":1}\n+ {\"id\":1}\n  {\"id\":2}\n");
+}
+
+#[test]
+fn schema_multiple_of_sets_precision() {
+    let schema = write_tempfile("properties:\n  price:\n    multipleOf: 0.01\n");
+    let lhs = write_tempfile("{\"price\":0.3,\"qty\":0.3}");
+    let rhs = write_tempfile("{\"price\":0.30000000000000004,\"qty\":0.30000000000000004}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--schema")
+        .arg(schema.path())
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"qty\"]\n- 0.3\n+ 0.30000000000000004\n");
+
+    let schema = write_tempfile(
+        "{\"properties\":{\"tags\":{\"uniqueItems\":true},\"qty\":{\"multipleOf\":0.01}}}",
+    );
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--schema")
+        .arg(schema.path())
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"price\"]\n- 0.3\n+ 0.30000000000000004\n")
+        .stderr(predicate::str::contains("ignoring uniqueItems at #/properties/tags/uniqueItems"));
+}
+
+#[test]
+fn preset_openapi_compares_specs_semantically() {
+    let lhs = write_tempfile(
+        r#"{"info":{"version":"1.0.0"},"tags":[{"name":"pets"},{"name":"admin"}],
+            "paths":{"/pets":{"get":{"parameters":[
+                {"name":"limit","in":"query"},{"name":"id","in":"query"},{"name":"id","in":"header"}]}}}}"#,
+    );
+    let rhs = write_tempfile(
+        r#"{"info":{"version":"1.1.0"},"tags":[{"name":"pets"},{"name":"admin"}],
+            "paths":{"/pets":{"get":{"parameters":[
+                {"name":"limit","in":"query","required":true},
+                {"name":"id","in":"query"},{"name":"id","in":"header"}]}}}}"#,
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--preset", "openapi"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"paths\",\"/pets\",\"get\",\"parameters\",0,\"required\"]\n+ true\n");
+}
+
+#[test]
+fn preset_kubernetes_ignores_server_fields() {
+    let lhs = write_tempfile(
+        r#"{"metadata":{"name":"web","resourceVersion":"41","uid":"a"},"status":{"phase":"Pending"},
+            "spec":{"containers":[{"name":"app","image":"app:1"},{"name":"proxy"}]}}"#,
+    );
+    let rhs = write_tempfile(
+        r#"{"metadata":{"name":"web","resourceVersion":"42","uid":"a"},"status":{"phase":"Running"},
+            "spec":{"containers":[{"name":"app","image":"app:2"},{"name":"proxy"}]}}"#,
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--preset", "kubernetes"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"spec\",\"containers\",0,\"image\"]\n- \"app:1\"\n+ \"app:2\"\n");
+}
+
+#[test]
+fn preset_kubernetes_patches_the_container_that_changed() {
+    let pod = |image: &str| {
+        format!(
+            r#"{{"spec":{{"containers":[{{"image":"{image}","name":"b"}},{{"image":"1","name":"a"}}]}}}}"#
+        )
+    };
+    let lhs = write_tempfile(&pod("1"));
+    let rhs = pod("2");
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let patch = dir.path().join("k.diff");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--preset", "kubernetes", "-o"])
+        .arg(&patch)
+        .arg(lhs.path())
+        .arg(write_tempfile(&rhs).path())
+        .assert()
+        .code(1);
+    assert_eq!(
+        fs::read_to_string(&patch).expect("diff written"),
+        "@ [\"spec\",\"containers\",0,\"image\"]\n- \"1\"\n+ \"2\"\n"
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p").arg(&patch).arg(lhs.path()).assert().code(0).stdout(rhs);
+}
+
+#[test]
+fn preset_terraform_diffs_state_by_resource() {
+    let bucket = |acl: &str| {
+        format!(
+            r#"{{"mode":"managed","type":"aws_s3_bucket","name":"logs","provider":"aws",
+                "instances":[{{"attributes":{{"acl":"{acl}","key":"k-{acl}"}},
+                "sensitive_attributes":[[{{"type":"get_attr","value":"key"}}]]}}]}}"#
+        )
+    };
+    let role =
+        r#"{"mode":"managed","type":"aws_iam_role","name":"ci","instances":[{"attributes":{}}]}"#;
+    let lhs =
+        write_tempfile(&format!(r#"{{"serial":1,"resources":[{},{role}]}}"#, bucket("private")));
+    let rhs =
+        write_tempfile(&format!(r#"{{"serial":2,"resources":[{role},{}]}}"#, bucket("public")));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--preset", "terraform"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout(
+            "@ [\"resources\",\"aws_s3_bucket.logs\",\"attributes\",\"acl\"]\n- \"private\"\n+ \"public\"\n",
+        );
+}
+
+#[test]
+fn preset_terraform_refuses_patches() {
+    let state = write_tempfile(r#"{"serial":1,"resources":[]}"#);
+    for args in [vec!["-f", "patch"], vec!["-p"]] {
+        let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+        cmd.args(["--preset", "terraform"])
+            .args(args)
+            .arg(state.path())
+            .arg(state.path())
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("cannot be used as patches"));
+    }
+}
+
+#[test]
+fn preset_github_ignores_replay_noise() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../jd-benches/fixtures/github/before.json");
+    let payload: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(path).expect("fixture readable"))
+            .expect("fixture parses");
+    let replay = |updated: &str, stars: u64| {
+        let mut payload = payload.clone();
+        payload["issue"]["updated_at"] = updated.into();
+        payload["issue"]["node_id"] = "I_kwDOA".into();
+        payload["repository"]["stargazers_count"] = stars.into();
+        payload["issue"]["labels"].as_array_mut().expect("labels").reverse();
+        write_tempfile(&payload.to_string())
+    };
+    let lhs = replay("2024-07-03T10:12:00Z", 10);
+    let rhs = replay("2024-07-04T08:00:00Z", 11);
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--preset", "github"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(0)
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn preset_github_diffs_apply_to_the_payloads_as_written() {
+    let delivery = |updated: &str, labels: &str| {
+        format!(r#"{{"issue":{{"labels":[{labels}],"number":142,"updated_at":"{updated}"}}}}"#)
+    };
+    let lhs = write_tempfile(&delivery(
+        "2024-07-03T10:12:00Z",
+        r#"{"name":"bug","node_id":"L_1"},{"name":"ui"}"#,
+    ));
+    let rhs = write_tempfile(&delivery(
+        "2024-07-04T08:00:00Z",
+        r#"{"name":"bug","node_id":"L_2"},{"name":"docs","node_id":"L_3"},{"name":"ui"}"#,
+    ));
+    let patched = delivery(
+        "2024-07-03T10:12:00Z",
+        r#"{"name":"bug","node_id":"L_1"},{"name":"docs","node_id":"L_3"},{"name":"ui"}"#,
+    );
+    let dir = tempfile::tempdir().expect("create temp dir");
+
+    for format in ["jd", "patch"] {
+        let patch = dir.path().join(format!("github.{format}"));
+        let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+        cmd.args(["--preset", "github", "-f", format, "-o"])
+            .arg(&patch)
+            .arg(lhs.path())
+            .arg(rhs.path())
+            .assert()
+            .code(1);
+
+        let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+        cmd.args(["-f", format, "-p"])
+            .arg(&patch)
+            .arg(lhs.path())
+            .assert()
+            .code(0)
+            .stdout(patched.clone());
+    }
+}
+
+#[test]
+fn config_file_and_env_defaults() {
+    let config_home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir(config_home.path().join("jd")).expect("config dir");
+    fs::write(
+        config_home.path().join("jd/config.toml"),
+        "format = \"patch\"\nignore = [[\"meta\", \"generation\"]]\n",
+    )
+    .expect("write config");
+    let lhs = write_tempfile("{\"a\":1,\"meta\":{\"generation\":1}}");
+    let rhs = write_tempfile("{\"a\":2,\"meta\":{\"generation\":2}}");
+    let jd = || {
+        let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+        cmd.env("XDG_CONFIG_HOME", config_home.path()).env_remove("JD_OPTS");
+        cmd
+    };
+
+    jd().arg(lhs.path()).arg(rhs.path()).assert().code(1).stdout(
+        "[{\"op\":\"test\",\"path\":\"/a\",\"value\":1},{\"op\":\"remove\",\"path\":\"/a\",\"value\":1},{\"op\":\"add\",\"path\":\"/a\",\"value\":2}]",
+    );
+
+    jd().env("JD_OPTS", "--format=jd --no-exit-code")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(0)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n");
+
+    jd().env("JD_OPTS", "--format=patch")
+        .args(["-f", "merge"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("{\"a\":2}");
+
+    // Defaults conflicting with the command line give way to it.
+    let out = tempfile::tempdir().expect("tempdir");
+    jd().env("JD_OPTS", "--quiet --color")
+        .args(["--no-color", "-o"])
+        .arg(out.path().join("out.jd"))
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(out.path().join("out.jd")).expect("output written"),
+        "[{\"op\":\"test\",\"path\":\"/a\",\"value\":1},{\"op\":\"remove\",\"path\":\"/a\",\"value\":1},{\"op\":\"add\",\"path\":\"/a\",\"value\":2}]"
+    );
+    jd().env("JD_OPTS", "--format=patch")
+        .args(["--output-formats=jd", "-o"])
+        .arg(out.path())
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1);
+    assert_eq!(
+        fs::read_to_string(out.path().join("diff.jd")).expect("output written"),
+        "@ [\"a\"]\n- 1\n+ 2\n"
+    );
+
+    jd().arg("--no-config")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- 1\n+ 2\n@ [\"meta\",\"generation\"]\n- 1\n+ 2\n");
+
+    // Option defaults change what counts as a difference, and the command
+    // line still overrides them.
+    let (lhs, rhs) = (write_tempfile("{\"a\":1}"), write_tempfile("{\"a\":1.05}"));
+    fs::write(config_home.path().join("jd/config.toml"), "precision = 0.1\n")
+        .expect("write config");
+    jd().arg(lhs.path()).arg(rhs.path()).assert().code(0).stdout("");
+    jd().args(["--precision", "0.01"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- 1\n+ 1.05\n");
+    jd().arg("--no-config").arg(lhs.path()).arg(rhs.path()).assert().code(1);
+    jd().env("JD_OPTS", "--precision=0.1")
+        .arg("--no-config")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1);
+    fs::write(config_home.path().join("jd/config.toml"), "").expect("write config");
+    jd().env("JD_OPTS", "--precision=0.1").arg(lhs.path()).arg(rhs.path()).assert().code(0);
+
+    fs::write(config_home.path().join("jd/config.toml"), "colour = true\n").expect("write config");
+    jd().arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr(
+        predicate::str::contains("invalid config file")
+            .and(predicate::str::contains("unknown option `colour`")),
+    );
+}
+
+#[test]
+fn truncation_limits_apply_to_stdout_only() {
+    let lhs = write_tempfile("{\"a\":\"0123456789\",\"b\":1,\"c\":1}");
+    let rhs = write_tempfile("{}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-hunks", "1", "--max-value-bytes", "6"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\"]\n- \"01234... (6 more bytes)\n... 2 more hunks omitted\n");
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let output = dir.path().join("full.jd");
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-hunks", "1", "--max-value-bytes", "6", "-o"])
+        .arg(&output)
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(&output).expect("read output"),
+        "@ [\"a\"]\n- \"0123456789\"\n@ [\"b\"]\n- 1\n@ [\"c\"]\n- 1\n"
+    );
+}
+
+#[test]
+fn indent_pretty_prints_values_on_stdout() {
+    let lhs = write_tempfile("{\"a\":{\"b\":[1,2]}}");
+    let rhs = write_tempfile("{\"a\":{}}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--indent", "2"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [\"a\",\"b\"]\n- [\n-   1,\n-   2\n- ]\n");
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let output = dir.path().join("diff.jd");
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--indent", "2", "-o"]).arg(&output).arg(lhs.path()).arg(rhs.path()).assert().code(1);
+    assert_eq!(fs::read_to_string(&output).expect("read output"), "@ [\"a\",\"b\"]\n- [1,2]\n");
+}
+
+#[test]
+fn patch_mode_applies_chained_patches() {
+    let fix1 = write_tempfile("@ [\"version\"]\n- 1\n+ 2\n");
+    let fix2 = write_tempfile("@ [\"version\"]\n- 2\n+ 3\n");
+    let doc = write_tempfile("{\"name\":\"jd\",\"version\":1}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p")
+        .arg(fix1.path())
+        .write_stdin("{\"version\":1}")
+        .assert()
+        .code(0)
+        .stdout("{\"version\":2}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p")
+        .arg(fix1.path())
+        .arg(fix2.path())
+        .arg(doc.path())
+        .assert()
+        .code(0)
+        .stdout("{\"name\":\"jd\",\"version\":3}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p")
+        .arg(fix2.path())
+        .arg(fix1.path())
+        .arg(doc.path())
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::starts_with(format!(
+            "failed to apply patch 1 of 2 ({}): ",
+            fix2.path().display()
+        )));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    let output = cmd
+        .args(["--error-format", "json", "-p"])
+        .arg(fix1.path())
+        .arg(fix1.path())
+        .arg(doc.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).expect("stderr is JSON");
+    assert_eq!(error["code"], "patch_conflict");
+    assert_eq!(error["input"], fix1.path().display().to_string());
+}
+
+#[test]
+fn patch_mode_reads_json_patch_and_merge_formats() {
+    let doc = write_tempfile("{\"a\":1,\"b\":2}");
+    let patch = write_tempfile(
+        "[{\"op\":\"test\",\"path\":\"/a\",\"value\":1},{\"op\":\"replace\",\"path\":\"/a\",\"value\":5}]",
+    );
+    let merge = write_tempfile("{\"b\":null}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-f", "merge", "-p"])
+        .arg(merge.path())
+        .arg(doc.path())
+        .assert()
+        .code(0)
+        .stdout("{\"a\":1}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-f", "patch", "-p"])
+        .arg(patch.path())
+        .arg(doc.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unsupported JSON Patch operation"));
+}
+
+#[test]
+fn patch_mode_glob_patches_matching_files_in_place() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    fs::create_dir(dir.path().join("prod")).expect("nested dir");
+    let first = dir.path().join("a.json");
+    let second = dir.path().join("prod/b.json");
+    fs::write(&first, "{\"replicas\":1}").expect("write a");
+    fs::write(&second, "{\"name\":\"b\",\"replicas\":1}").expect("write b");
+    fs::write(dir.path().join("notes.txt"), "not json").expect("write notes");
+    let patch = write_tempfile("@ [\"replicas\"]\n- 1\n+ 3\n");
+    let pattern = format!("{}/**/*.json", dir.path().display());
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p")
+        .arg(patch.path())
+        .args(["--glob", &pattern, "--backup"])
+        .assert()
+        .code(0)
+        .stdout("");
+    assert_eq!(fs::read_to_string(&first).unwrap(), "{\"replicas\":3}");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "{\"name\":\"b\",\"replicas\":3}");
+    assert_eq!(fs::read_to_string(dir.path().join("a.json.bak")).unwrap(), "{\"replicas\":1}");
+
+    // The patch no longer applies to any file; nothing may be rewritten.
+    fs::write(&first, "{\"replicas\":1}").expect("reset a");
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("-p")
+        .arg(patch.path())
+        .args(["--glob", &pattern])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with(format!("failed to patch {}: ", second.display())));
+    assert_eq!(fs::read_to_string(&first).unwrap(), "{\"replicas\":1}");
+}
+
+#[test]
+fn patch_mode_ndjson_patches_each_record() {
+    let patch = write_tempfile("@ [\"v\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--ndjson"])
+        .arg(patch.path())
+        .write_stdin("{\"id\":1,\"v\":1}\n\n{\"id\":2,\"v\":3}\nnot json\n{\"id\":4,\"v\":1}\n")
+        .assert()
+        .code(1)
+        .stdout("{\"id\":1,\"v\":2}\n{\"id\":4,\"v\":2}\n")
+        .stderr(predicate::str::starts_with("line 3: found 3 at [v]: expected 1\nline 4: "));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--ndjson"])
+        .arg(patch.path())
+        .write_stdin("{\"v\":1}\n")
+        .assert()
+        .code(0)
+        .stdout("{\"v\":2}\n")
+        .stderr("");
+}
+
+#[test]
+fn patch_mode_keeps_yaml_comments() {
+    let doc =
+        write_tempfile("# replicas are tuned by hand\nreplicas: 2  # was 1\nimage: 'app:1.0'\n");
+    let patch =
+        write_tempfile("@ [\"image\"]\n- \"app:1.0\"\n+ \"app:1.1\"\n@ [\"paused\"]\n+ true\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--yaml"]).arg(patch.path()).arg(doc.path()).assert().code(0).stdout(
+        "# replicas are tuned by hand\nreplicas: 2  # was 1\nimage: 'app:1.1'\npaused: true\n",
+    );
+}
+
+#[test]
+fn patch_mode_in_place_keeps_json_formatting() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let doc = dir.path().join("doc.json");
+    fs::write(&doc, "{\n    \"name\": \"app\",\n    \"version\": 1\n}\n").expect("write doc");
+    let patch = write_tempfile("@ [\"version\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--in-place"]).arg(patch.path()).arg(&doc).assert().code(0).stdout("");
+    assert_eq!(
+        fs::read_to_string(&doc).unwrap(),
+        "{\n    \"name\": \"app\",\n    \"version\": 2\n}\n"
+    );
+}
+
+#[test]
+fn patch_mode_in_place_overwrites_document() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let doc = dir.path().join("doc.json");
+    fs::write(&doc, "{\"version\":1}").expect("write doc");
+    let patch = write_tempfile("@ [\"version\"]\n- 1\n+ 2\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--in-place", "--backup"])
+        .arg(patch.path())
+        .arg(&doc)
+        .assert()
+        .code(0)
+        .stdout("");
+    assert_eq!(fs::read_to_string(&doc).unwrap(), "{\"version\":2}");
+    assert_eq!(fs::read_to_string(dir.path().join("doc.json.bak")).unwrap(), "{\"version\":1}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--in-place"])
+        .arg(patch.path())
+        .arg(&doc)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_empty().not());
+    assert_eq!(fs::read_to_string(&doc).unwrap(), "{\"version\":2}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["-p", "--in-place"])
+        .arg(patch.path())
+        .write_stdin("{\"version\":1}")
+        .assert()
+        .code(1)
+        .stderr("--in-place requires FILE2 to be a local file\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--backup").arg(patch.path()).arg(&doc).assert().code(2);
+}
+
+#[test]
+fn stdin_format_is_sniffed() {
+    let lhs = write_tempfile("{\"a\":1,\"b\":[1,2]}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(lhs.path())
+        .write_stdin("a: 1\nb:\n  - 1\n  - 3\n")
+        .assert()
+        .code(1)
+        .stdout("@ [\"b\",1]\n  1\n- 2\n+ 3\n]\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(lhs.path()).write_stdin("").assert().code(1).stdout("@ []\n- {\"a\":1,\"b\":[1,2]}\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(lhs.path()).write_stdin("{\"a\":\n 1,,}").assert().code(1).stderr(
+        "failed to parse second input (STDIN): invalid JSON: key must be a string at line 2 column 4\n \
+         --> STDIN:2:4\n  |\n2 |  1,,}\n  |    ^\n",
+    );
+}
+
+#[test]
+fn verbose_logs_phase_timings_to_stderr() {
+    let lhs = write_tempfile("[1,2,3]");
+    let rhs = write_tempfile("[1,4,3]");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--verbose")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [1]\n  1\n- 2\n+ 4\n  3\n")
+        .stderr(
+            predicate::str::contains("parse{format=\"json\" bytes=7}: close time.busy=")
+                .and(predicate::str::contains("render{format=\"jd\" hunks=1}: close"))
+                .and(predicate::str::contains("lcs{").not()),
+        );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--verbose", "--verbose"]).arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr(
+        predicate::str::contains("diff:hash{elements=6}: close")
+            .and(predicate::str::contains("diff:lcs{lhs=3 rhs=3}: close")),
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr("");
+}
+
+#[test]
+fn progress_draws_a_bar_on_stderr() {
+    let lhs = write_tempfile("[1,2,3]");
+    let rhs = write_tempfile("[1,4,3]");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--progress")
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [1]\n  1\n- 2\n+ 4\n  3\n")
+        .stderr(
+            predicate::str::starts_with("\rparse  [..............................]   0%")
+                .and(predicate::str::contains("\rlcs    [#########.....................]  33%"))
+                .and(predicate::str::ends_with("\rrender [##############################] 100%\n")),
+        );
+}
+
+#[test]
+fn resource_limits_refuse_oversized_inputs() {
+    let lhs = write_tempfile("[1,2,3]");
+    let rhs = write_tempfile("[1,[4],3]");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-input-bytes", "8"]).arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr(
+        format!(
+            "refusing second input ({}): input exceeds the limit of 8 bytes\n",
+            rhs.path().display()
+        ),
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-nodes", "3"]).arg(lhs.path()).arg(rhs.path()).assert().code(1).stderr(
+        format!(
+            "refusing first input ({}): document has more than 3 nodes\n",
+            lhs.path().display()
+        ),
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-depth", "1"])
+        .arg(lhs.path())
+        .write_stdin("[[1]]")
+        .assert()
+        .code(1)
+        .stderr("refusing second input (STDIN): document is nested deeper than 1 levels\n");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--timeout", "0", "--error-format", "json"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stderr(
+            "{\"code\":\"limit\",\"message\":\"diff did not finish within 0ns\",\"path\":null,\"input\":null}\n",
+        );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--max-input-bytes=9", "--max-nodes=5", "--max-depth=2", "--timeout=30"])
+        .arg(lhs.path())
+        .arg(rhs.path())
+        .assert()
+        .code(1)
+        .stdout("@ [1]\n  1\n- 2\n+ [4]\n  3\n");
+}
+
+#[test]
+fn stat_prints_document_metrics() {
+    let doc = write_tempfile("{\"tags\":[\"a\",\"bc\"],\"meta\":{},\"n\":[[]]}");
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--stat").arg(doc.path()).write_stdin("1").assert().success().stdout(format!(
+        "{}\n  depth: 3\n  nodes: 7\n  string bytes: 12\n  array lengths: 0: 1, 1: 1, 2-3: 1\n  \
+         object sizes: 0: 1, 2-3: 1\n",
+        doc.path().display()
+    ));
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.arg("--stat").write_stdin("1").assert().success().stdout(
+        "STDIN\n  depth: 0\n  nodes: 1\n  string bytes: 0\n  array lengths: none\n  \
+         object sizes: none\n",
+    );
+
+    let mut cmd = Command::cargo_bin("jd").expect("binary jd should be built");
+    cmd.args(["--stat", "-p"]).arg(doc.path()).assert().code(2);
+}
@@ -0,0 +1,463 @@
+//! Hash-consed documents whose identical subtrees are stored once.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::{Diff, DiffOptions, Node};
+
+/// Interns documents so that every distinct subtree is stored once.
+///
+/// Large documents often repeat themselves: the same object in every array
+/// element, the same keys in every record. [`NodeArena::intern`] converts a
+/// [`Node`] into a [`SharedNode`], reusing any subtree the arena has seen
+/// before, from this document or an earlier one. Equal subtrees then share
+/// one allocation, so they cost memory once, clone in constant time, and
+/// compare by pointer. The arena keeps every subtree it has interned
+/// alive, so drop it once no more documents will share them.
+///
+/// ```
+/// use jd_core::{Node, NodeArena, SharedNode};
+///
+/// let mut arena = NodeArena::new();
+/// let doc = Node::from_json_str(r#"[{"port": 80}, {"port": 80}, {"port": 80}]"#).unwrap();
+/// let shared = arena.intern(&doc);
+/// // 80, {"port": 80}, and the array.
+/// assert_eq!(arena.len(), 3);
+/// let items = shared.as_array().unwrap();
+/// assert!(SharedNode::ptr_eq(&items[0], &items[2]));
+/// assert_eq!(shared.to_node(), doc);
+/// ```
+#[derive(Debug, Default)]
+pub struct NodeArena {
+    nodes: HashSet<Interned>,
+    keys: HashSet<Arc<str>>,
+}
+
+impl NodeArena {
+    /// Creates an empty arena.
+    ///
+    /// ```
+    /// assert!(jd_core::NodeArena::new().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts `node` into a [`SharedNode`], sharing every subtree equal
+    /// to one interned before.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena, SharedNode};
+    /// let mut arena = NodeArena::new();
+    /// let a = arena.intern(&Node::from_json_str(r#"{"a": [1, 2]}"#).unwrap());
+    /// let b = arena.intern(&Node::from_json_str(r#"{"a": [1, 2]}"#).unwrap());
+    /// assert!(SharedNode::ptr_eq(&a, &b));
+    /// ```
+    pub fn intern(&mut self, node: &Node) -> SharedNode {
+        let arena = RefCell::new(self);
+        node.fold(
+            |leaf| Some(arena.borrow_mut().insert(Shared::Leaf(leaf.shallow_clone()))),
+            |items| arena.borrow_mut().insert(Shared::Array(items)),
+            |object| {
+                let mut arena = arena.borrow_mut();
+                let object = object.into_iter().map(|(key, value)| (arena.key(key), value));
+                let object = Shared::Object(object.collect());
+                arena.insert(object)
+            },
+        )
+        .expect("interning never aborts")
+    }
+
+    /// Number of distinct subtrees stored.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// arena.intern(&Node::from_json_str("[[1], [1], 1]").unwrap());
+    /// assert_eq!(arena.len(), 3);
+    /// ```
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Reports whether nothing has been interned.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// arena.intern(&Node::Null);
+    /// assert!(!arena.is_empty());
+    /// ```
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn insert(&mut self, shared: Shared) -> SharedNode {
+        let candidate = Interned(SharedNode(Arc::new(shared)));
+        if let Some(existing) = self.nodes.get(&candidate) {
+            return existing.0.clone();
+        }
+        let node = candidate.0.clone();
+        self.nodes.insert(candidate);
+        node
+    }
+
+    fn key(&mut self, key: String) -> Arc<str> {
+        if let Some(existing) = self.keys.get(key.as_str()) {
+            return Arc::clone(existing);
+        }
+        let key: Arc<str> = key.into();
+        self.keys.insert(Arc::clone(&key));
+        key
+    }
+}
+
+/// A document interned by a [`NodeArena`].
+///
+/// Cloning copies a pointer, and subtrees interned by the same arena are
+/// equal exactly when they are the same allocation. Convert back with
+/// [`SharedNode::to_node`] to use the rest of the API.
+#[derive(Clone)]
+pub struct SharedNode(Arc<Shared>);
+
+enum Shared {
+    Leaf(Node),
+    Array(Vec<SharedNode>),
+    Object(BTreeMap<Arc<str>, SharedNode>),
+}
+
+impl SharedNode {
+    /// Reports whether `a` and `b` are the same allocation, which for
+    /// nodes of one arena means they are equal.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena, SharedNode};
+    /// let mut arena = NodeArena::new();
+    /// let a = arena.intern(&Node::from(1));
+    /// assert!(SharedNode::ptr_eq(&a, &a.clone()));
+    /// assert!(!SharedNode::ptr_eq(&a, &arena.intern(&Node::from(2))));
+    /// ```
+    #[must_use]
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+
+    /// Returns the value if it is a scalar or void.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// assert_eq!(arena.intern(&Node::from("x")).as_leaf(), Some(&Node::from("x")));
+    /// assert_eq!(arena.intern(&Node::Array(vec![])).as_leaf(), None);
+    /// ```
+    #[must_use]
+    pub fn as_leaf(&self) -> Option<&Node> {
+        match &*self.0 {
+            Shared::Leaf(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if the value is an array.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// let shared = arena.intern(&Node::from_json_str("[true]").unwrap());
+    /// assert_eq!(shared.as_array().unwrap()[0].as_leaf(), Some(&Node::Bool(true)));
+    /// ```
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[SharedNode]> {
+        match &*self.0 {
+            Shared::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the members if the value is an object.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// let shared = arena.intern(&Node::from_json_str(r#"{"a": null}"#).unwrap());
+    /// assert_eq!(shared.as_object().unwrap()["a"].as_leaf(), Some(&Node::Null));
+    /// ```
+    #[must_use]
+    pub fn as_object(&self) -> Option<&BTreeMap<Arc<str>, SharedNode>> {
+        match &*self.0 {
+            Shared::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Rebuilds an owned [`Node`], without recursion like [`Node::clone`].
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeArena};
+    /// let node = Node::from_json_str(r#"{"a": [1, {"b": null}]}"#).unwrap();
+    /// assert_eq!(NodeArena::new().intern(&node).to_node(), node);
+    /// ```
+    #[must_use]
+    pub fn to_node(&self) -> Node {
+        enum Frame<'a> {
+            Array(Vec<Node>, std::slice::Iter<'a, SharedNode>),
+            Object(
+                BTreeMap<String, Node>,
+                &'a str,
+                std::collections::btree_map::Iter<'a, Arc<str>, SharedNode>,
+            ),
+        }
+        let mut stack = Vec::new();
+        let mut next = self;
+        loop {
+            let mut done = match &*next.0 {
+                Shared::Leaf(node) => Some(node.shallow_clone()),
+                Shared::Array(items) => {
+                    stack.push(Frame::Array(Vec::with_capacity(items.len()), items.iter()));
+                    None
+                }
+                Shared::Object(members) => {
+                    stack.push(Frame::Object(BTreeMap::new(), "", members.iter()));
+                    None
+                }
+            };
+            next = loop {
+                let Some(frame) = stack.last_mut() else {
+                    return done.expect("the root is complete");
+                };
+                let child = match frame {
+                    Frame::Array(items, rest) => {
+                        items.extend(done.take());
+                        rest.next()
+                    }
+                    Frame::Object(members, key, rest) => {
+                        if let Some(value) = done.take() {
+                            members.insert((*key).to_string(), value);
+                        }
+                        rest.next().map(|(next_key, value)| {
+                            *key = next_key;
+                            value
+                        })
+                    }
+                };
+                if let Some(child) = child {
+                    break child;
+                }
+                done = stack.pop().map(|frame| match frame {
+                    Frame::Array(items, _) => Node::Array(items),
+                    Frame::Object(members, ..) => Node::Object(members),
+                });
+            };
+        }
+    }
+
+    /// Computes the diff from `self` to `other`. Subtrees shared by both
+    /// are equal, so the same allocation yields an empty diff without
+    /// comparing anything, and object members shared at any depth are left
+    /// out of the copies handed to the diff engine. Arrays that differ are
+    /// rebuilt whole, since list hunks quote their neighbours as context.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, NodeArena};
+    /// let mut arena = NodeArena::new();
+    /// let a = arena.intern(&Node::from_json_str("[1, 2]").unwrap());
+    /// let b = arena.intern(&Node::from_json_str("[1, 3]").unwrap());
+    /// assert!(a.diff(&a.clone(), &DiffOptions::default()).is_empty());
+    /// assert_eq!(a.diff(&b, &DiffOptions::default()).len(), 1);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Self, options: &DiffOptions) -> Diff {
+        if Self::ptr_eq(self, other) {
+            return Diff::empty();
+        }
+        let (lhs, rhs) = self.differing_parts(other);
+        lhs.diff(&rhs, options)
+    }
+
+    /// Rebuilds `self` and `other` for the diff engine, with the object
+    /// members shared by both replaced by `null` on both sides. Equal
+    /// members yield no hunks under any options, so the diff is unchanged.
+    /// Walks nested objects without recursion, like [`SharedNode::to_node`].
+    fn differing_parts(&self, other: &Self) -> (Node, Node) {
+        type Members = BTreeMap<Arc<str>, SharedNode>;
+        struct Frame<'a> {
+            left: BTreeMap<String, Node>,
+            right: BTreeMap<String, Node>,
+            key: &'a str,
+            rest: std::collections::btree_map::Iter<'a, Arc<str>, SharedNode>,
+            theirs: &'a Members,
+        }
+        fn enter<'a>(lhs: &'a Members, rhs: &'a Members) -> Frame<'a> {
+            let right = rhs
+                .iter()
+                .filter(|(key, _)| !lhs.contains_key(*key))
+                .map(|(key, value)| (key.to_string(), value.to_node()))
+                .collect();
+            Frame { left: BTreeMap::new(), right, key: "", rest: lhs.iter(), theirs: rhs }
+        }
+
+        let (Some(lhs), Some(rhs)) = (self.as_object(), other.as_object()) else {
+            return (self.to_node(), other.to_node());
+        };
+        let mut stack = vec![enter(lhs, rhs)];
+        let mut done = None;
+        loop {
+            let frame = stack.last_mut().expect("a frame is open until the root completes");
+            if let Some((left, right)) = done.take() {
+                frame.left.insert(frame.key.to_string(), left);
+                frame.right.insert(frame.key.to_string(), right);
+            }
+            let Some((key, value)) = frame.rest.next() else {
+                let frame = stack.pop().expect("the frame just inspected");
+                let parts = (Node::Object(frame.left), Node::Object(frame.right));
+                if stack.is_empty() {
+                    return parts;
+                }
+                done = Some(parts);
+                continue;
+            };
+            frame.key = key;
+            done = match frame.theirs.get(key) {
+                None => {
+                    frame.left.insert(key.to_string(), value.to_node());
+                    None
+                }
+                Some(theirs) if Self::ptr_eq(value, theirs) => Some((Node::Null, Node::Null)),
+                Some(theirs) => match (value.as_object(), theirs.as_object()) {
+                    (Some(lhs), Some(rhs)) => {
+                        stack.push(enter(lhs, rhs));
+                        None
+                    }
+                    _ => Some((value.to_node(), theirs.to_node())),
+                },
+            };
+        }
+    }
+}
+
+impl fmt::Debug for SharedNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.0 {
+            Shared::Leaf(node) => node.fmt(f),
+            Shared::Array(items) => f.debug_list().entries(items).finish(),
+            Shared::Object(members) => f.debug_map().entries(members).finish(),
+        }
+    }
+}
+
+/// A [`SharedNode`] hashed and compared by shape: leaves by value,
+/// containers by the addresses of their already interned children.
+struct Interned(SharedNode);
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for Interned {
+    fn eq(&self, other: &Self) -> bool {
+        match (&*self.0 .0, &*other.0 .0) {
+            (Shared::Leaf(a), Shared::Leaf(b)) => identical(a, b),
+            (Shared::Array(a), Shared::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| SharedNode::ptr_eq(a, b))
+            }
+            (Shared::Object(a), Shared::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|((ka, va), (kb, vb))| ka == kb && SharedNode::ptr_eq(va, vb))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Interned {}
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &*self.0 .0 {
+            Shared::Leaf(node) => {
+                state.write_u8(0);
+                node.hash(state);
+            }
+            Shared::Array(items) => {
+                state.write_u8(1);
+                items.iter().for_each(|item| Arc::as_ptr(&item.0).hash(state));
+            }
+            Shared::Object(members) => {
+                state.write_u8(2);
+                for (key, value) in members {
+                    key.hash(state);
+                    Arc::as_ptr(&value.0).hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// Leaves are shared only when they round-trip unchanged, so numbers that
+/// compare equal but print differently, like `0` and `-0`, stay apart.
+fn identical(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::Number(a), Node::Number(b)) => a.get().to_bits() == b.get().to_bits(),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_subtrees_are_stored_once() {
+        let mut arena = NodeArena::new();
+        let record = r#"{"name": "x", "tags": ["a", "b"], "port": 80}"#;
+        let doc = format!("[{}]", vec![record; 1000].join(","));
+        let doc = Node::from_json_str(&doc).unwrap();
+        let shared = arena.intern(&doc);
+        // "x", "a", "b", 80, the tags, the record, and the outer array.
+        assert_eq!(arena.len(), 7);
+        assert_eq!(arena.keys.len(), 3);
+        assert_eq!(shared.to_node(), doc);
+
+        let other = Node::from_json_str(&format!("[{record}, {{\"port\": -0.0}}]")).unwrap();
+        let shared_other = arena.intern(&other);
+        let items = shared_other.as_array().unwrap();
+        assert!(SharedNode::ptr_eq(&items[0], &shared.as_array().unwrap()[999]));
+        assert_eq!(shared_other.to_node(), other);
+        assert_eq!(
+            shared.diff(&shared_other, &DiffOptions::default()),
+            doc.diff(&other, &DiffOptions::default())
+        );
+    }
+
+    #[test]
+    fn diffs_copy_only_unshared_members() {
+        let mut arena = NodeArena::new();
+        let lhs =
+            crate::node!({"big": [1, 2], "o": {"k": {"x": 1}, "n": 1, "gone": 0}, "l": [1, 2]});
+        let rhs =
+            crate::node!({"big": [1, 2], "o": {"k": {"x": 1}, "n": 2, "new": 0}, "l": [1, 3]});
+        let (a, b) = (arena.intern(&lhs), arena.intern(&rhs));
+        let (left, right) = a.differing_parts(&b);
+        assert_eq!(
+            left,
+            crate::node!({"big": null, "o": {"k": null, "n": 1, "gone": 0}, "l": [1, 2]})
+        );
+        assert_eq!(
+            right,
+            crate::node!({"big": null, "o": {"k": null, "n": 2, "new": 0}, "l": [1, 3]})
+        );
+        for options in [DiffOptions::default(), DiffOptions::default().with_merge(true)] {
+            assert_eq!(a.diff(&b, &options), lhs.diff(&rhs, &options));
+        }
+    }
+}
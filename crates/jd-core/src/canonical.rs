@@ -0,0 +1,200 @@
+//! JSON Canonicalization Scheme (RFC 8785) serialization.
+//!
+//! Output has no insignificant whitespace, object members sorted by the
+//! UTF-16 code units of their keys, numbers in ECMAScript `Number.toString`
+//! form, and strings escaped exactly like `JSON.stringify`. Documents are
+//! written with an explicit work stack, so nesting depth is not limited by
+//! the call stack.
+
+use std::fmt::Write;
+
+use crate::{EncodeError, Node};
+
+enum Frame<'a> {
+    Array { rest: std::slice::Iter<'a, Node>, first: bool },
+    Object { rest: std::vec::IntoIter<(&'a String, &'a Node)>, first: bool },
+}
+
+pub(crate) fn to_string(node: &Node) -> Result<String, EncodeError> {
+    let mut out = String::new();
+    let mut stack: Vec<Frame<'_>> = Vec::new();
+    let mut next = Some(node);
+    loop {
+        if let Some(node) = next.take() {
+            match node {
+                Node::Void => return Err(EncodeError::Void),
+                Node::Null => out.push_str("null"),
+                Node::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+                Node::Number(number) => write_number(&mut out, number.get()),
+                Node::String(value) => write_string(&mut out, value),
+                Node::Array(items) => {
+                    out.push('[');
+                    stack.push(Frame::Array { rest: items.iter(), first: true });
+                }
+                Node::Object(map) => {
+                    let mut members: Vec<_> = map.iter().collect();
+                    members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+                    out.push('{');
+                    stack.push(Frame::Object { rest: members.into_iter(), first: true });
+                }
+            }
+        }
+
+        let Some(frame) = stack.last_mut() else {
+            return Ok(out);
+        };
+        match frame {
+            Frame::Array { rest, first } => match rest.next() {
+                Some(item) => {
+                    if !std::mem::take(first) {
+                        out.push(',');
+                    }
+                    next = Some(item);
+                }
+                None => {
+                    out.push(']');
+                    stack.pop();
+                }
+            },
+            Frame::Object { rest, first } => match rest.next() {
+                Some((key, value)) => {
+                    if !std::mem::take(first) {
+                        out.push(',');
+                    }
+                    write_string(&mut out, key);
+                    out.push(':');
+                    next = Some(value);
+                }
+                None => {
+                    out.push('}');
+                    stack.pop();
+                }
+            },
+        }
+    }
+}
+
+/// Writes `value` the way ECMAScript's `Number.prototype.toString` does.
+fn write_number(out: &mut String, value: f64) {
+    if value == 0.0 {
+        out.push('0');
+        return;
+    }
+    if value < 0.0 {
+        out.push('-');
+    }
+    // `{:e}` yields the shortest round-tripping digits, e.g. `1.2345e-7`.
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("LowerExp always has an exponent");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().expect("LowerExp exponent is an integer") + 1;
+
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        let _ = write!(out, "{int}.{frac}");
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-n) as usize));
+        out.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(rest);
+        }
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let _ = write!(out, "e{sign}{}", (n - 1).abs());
+    }
+}
+
+/// Writes a JSON string literal with `JSON.stringify` escaping.
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch < ' ' => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: f64) -> String {
+        let mut out = String::new();
+        write_number(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn numbers_follow_ecmascript_formatting() {
+        // Sample values from RFC 8785, Appendix B.
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (5e-324, "5e-324"),
+            (-5e-324, "-5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740992.0, "9007199254740992"),
+            (-9007199254740992.0, "-9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (9.999999999999997e22, "9.999999999999997e+22"),
+            (333333333.3333332, "333333333.3333332"),
+            (0.000001, "0.000001"),
+            (0.0000001, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (-1.5, "-1.5"),
+            (0.5, "0.5"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(number(value), expected, "formatting {value:e}");
+        }
+    }
+
+    #[test]
+    fn keys_sort_by_utf16_code_units() {
+        // Property sorting example from RFC 8785, Section 3.2.3.
+        let node = Node::from_json_str(
+            r#"{"\u20ac":1,"\r":2,"\ufb33":3,"1":4,"\ud83d\ude00":5,"\u0080":6,"\u00f6":7}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_string(&node).unwrap(),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"\u{f6}\":7,\"\u{20ac}\":1,\"\u{1f600}\":5,\"\u{fb33}\":3}"
+        );
+    }
+
+    #[test]
+    fn strings_escape_like_json_stringify() {
+        let node = Node::String("\"\\/\u{08}\u{0C}\n\r\t\u{01}\u{1f}\u{7f}\u{2028}é".to_string());
+        assert_eq!(
+            to_string(&node).unwrap(),
+            "\"\\\"\\\\/\\b\\f\\n\\r\\t\\u0001\\u001f\u{7f}\u{2028}é\""
+        );
+    }
+
+    #[test]
+    fn void_cannot_be_encoded() {
+        let node = Node::Array(vec![Node::Null, Node::Void]);
+        assert!(matches!(to_string(&node), Err(EncodeError::Void)));
+    }
+}
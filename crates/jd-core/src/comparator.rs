@@ -0,0 +1,138 @@
+//! Equality supplied by an embedder for values at chosen paths.
+
+use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use crate::{Node, PathMatcher};
+
+/// Equality supplied by an embedder for the values at some paths,
+/// registered with [`DiffOptions::with_comparator`](crate::DiffOptions::with_comparator).
+///
+/// [`Node::eq_with_options`] and the diff engine ask the comparator about
+/// the values at its path before comparing them structurally. An answer
+/// decides the comparison: equal values produce no hunks, and unequal ones
+/// are replaced as a whole. `None` leaves the values to the structural
+/// comparison, so a comparator can handle only the values it understands.
+/// An object member present on one side only is removed or added without
+/// asking.
+///
+/// Set and multiset arrays, and the alignment of list elements, match
+/// elements by hash, which does not consult comparators. Comparators must be
+/// unwind safe so that options holding one stay usable across
+/// `catch_unwind`.
+///
+/// ```
+/// use jd_core::{DiffOptions, Node, PathMatcher, ValueComparator};
+///
+/// /// Versions compare equal with or without a leading `v`.
+/// #[derive(Debug)]
+/// struct Version;
+///
+/// impl ValueComparator for Version {
+///     fn equals(&self, lhs: &Node, rhs: &Node) -> Option<bool> {
+///         let (Node::String(lhs), Node::String(rhs)) = (lhs, rhs) else {
+///             return None;
+///         };
+///         Some(lhs.trim_start_matches('v') == rhs.trim_start_matches('v'))
+///     }
+/// }
+///
+/// let opts = DiffOptions::default().with_comparator([PathMatcher::key("version")], Version);
+/// let lhs = Node::from_json_str(r#"{"version":"v1.2.0","name":"v1"}"#).unwrap();
+/// let rhs = Node::from_json_str(r#"{"version":"1.2.0","name":"1"}"#).unwrap();
+/// let diff = lhs.diff(&rhs, &opts);
+/// assert_eq!(diff.render(&Default::default()), "@ [\"name\"]\n- \"v1\"\n+ \"1\"\n");
+/// ```
+pub trait ValueComparator: fmt::Debug + Send + Sync + RefUnwindSafe + UnwindSafe {
+    /// Whether `lhs` and `rhs` are equal, or `None` to compare them
+    /// structurally.
+    fn equals(&self, lhs: &Node, rhs: &Node) -> Option<bool>;
+}
+
+/// A comparator waiting for the traversal to reach its path.
+#[derive(Clone, Debug)]
+pub(crate) struct PathComparator {
+    at: Vec<PathMatcher>,
+    comparator: Arc<dyn ValueComparator>,
+}
+
+impl PathComparator {
+    pub(crate) fn new(at: Vec<PathMatcher>, comparator: Arc<dyn ValueComparator>) -> Self {
+        Self { at, comparator }
+    }
+
+    /// The same comparator one level further down, if `first` matches.
+    pub(crate) fn descend(&self, first: impl Fn(&PathMatcher) -> bool) -> Option<Self> {
+        let (head, rest) = self.at.split_first()?;
+        first(head).then(|| Self { at: rest.to_vec(), comparator: Arc::clone(&self.comparator) })
+    }
+
+    /// The comparator, once the traversal has reached its path.
+    pub(crate) fn reached(&self) -> Option<&Arc<dyn ValueComparator>> {
+        self.at.is_empty().then_some(&self.comparator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, RenderConfig};
+
+    /// Objects are equal when their `id`s are.
+    #[derive(Debug)]
+    struct SameId;
+
+    impl ValueComparator for SameId {
+        fn equals(&self, lhs: &Node, rhs: &Node) -> Option<bool> {
+            let (Node::Object(lhs), Node::Object(rhs)) = (lhs, rhs) else {
+                return None;
+            };
+            Some(lhs.get("id")? == rhs.get("id")?)
+        }
+    }
+
+    fn nodes(lhs: &str, rhs: &str) -> (Node, Node) {
+        (Node::from_json_str(lhs).unwrap(), Node::from_json_str(rhs).unwrap())
+    }
+
+    #[test]
+    fn comparators_decide_at_their_path_only() {
+        let opts = DiffOptions::default().with_comparator([PathMatcher::AnyIndex], SameId);
+        let (lhs, rhs) = nodes(
+            r#"[{"id":1,"x":1,"sub":{"id":1,"y":1}},{"id":2,"x":1},{"x":1}]"#,
+            r#"[{"id":1,"x":2,"sub":{"id":1,"y":2}},{"id":3,"x":1},{"x":2}]"#,
+        );
+        let diff = lhs.diff(&rhs, &opts);
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            concat!(
+                "@ [1]\n- {\"id\":2,\"x\":1}\n+ {\"id\":3,\"x\":1}\n  {\"x\":1}\n",
+                "@ [2,\"x\"]\n- 1\n+ 2\n",
+            )
+        );
+        assert!(!lhs.eq_with_options(&rhs, &opts));
+        assert_eq!(lhs.explain_inequality(&rhs, &opts).unwrap().to_string(), "[1]");
+    }
+
+    #[test]
+    fn later_comparators_replace_earlier_ones() {
+        #[derive(Debug)]
+        struct Never;
+        impl ValueComparator for Never {
+            fn equals(&self, _: &Node, _: &Node) -> Option<bool> {
+                Some(false)
+            }
+        }
+        let (lhs, rhs) = nodes(r#"{"id":1,"x":1}"#, r#"{"id":1,"x":2}"#);
+        let opts = DiffOptions::default().with_comparator([], Never).with_comparator([], SameId);
+        assert!(lhs.diff(&rhs, &opts).is_empty());
+        let opts = DiffOptions::default().with_comparator([], SameId).with_comparator([], Never);
+        let diff = lhs.diff(&rhs, &opts);
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            "@ []\n- {\"id\":1,\"x\":1}\n+ {\"id\":1,\"x\":2}\n"
+        );
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+    }
+}
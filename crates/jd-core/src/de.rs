@@ -0,0 +1,192 @@
+//! Deserializing [`Node`]s into user types.
+
+use std::collections::BTreeMap;
+
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::Node;
+
+/// Errors that can occur while deserializing a [`Node`] into a Rust type.
+///
+/// ```
+/// # use jd_core::node;
+/// let err = node!({"port": "eighty"}).to_t::<std::collections::BTreeMap<String, u16>>();
+/// assert!(err.unwrap_err().to_string().contains("expected u16"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializeError {
+    message: String,
+}
+
+impl DeserializeError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::new(msg.to_string())
+    }
+}
+
+/// A [`Deserializer`](serde::Deserializer) reading from an owned [`Node`].
+///
+/// Nodes deserialize like the JSON they represent. [`Node::Void`] reads as
+/// `None` or `()`, so an absent document can fill an `Option`.
+///
+/// ```
+/// # use jd_core::node;
+/// use serde::Deserialize;
+///
+/// let flags = Vec::<bool>::deserialize(node!([true, false]).into_deserializer()).unwrap();
+/// assert_eq!(flags, [true, false]);
+/// ```
+#[derive(Debug)]
+pub struct NodeDeserializer {
+    node: Node,
+}
+
+impl Node {
+    /// Wraps the node in a [`Deserializer`](serde::Deserializer).
+    ///
+    /// ```
+    /// # use jd_core::node;
+    /// use serde::Deserialize;
+    ///
+    /// let port = u16::deserialize(node!(8080).into_deserializer()).unwrap();
+    /// assert_eq!(port, 8080);
+    /// ```
+    #[must_use]
+    pub fn into_deserializer(self) -> NodeDeserializer {
+        NodeDeserializer { node: self }
+    }
+
+    /// Deserializes a copy of the node into `T`, for example to turn a patched
+    /// document back into a configuration struct.
+    ///
+    /// ```
+    /// # use jd_core::{Node, DiffOptions};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Config {
+    ///     replicas: u32,
+    ///     image: String,
+    /// }
+    ///
+    /// let current = Config { replicas: 1, image: "app:1".into() };
+    /// let target = Config { replicas: 3, image: "app:1".into() };
+    /// let diff = jd_core::diff_values(&current, &target, &DiffOptions::default()).unwrap();
+    /// let patched = Node::from_serialize(&current).unwrap().apply_patch(&diff).unwrap();
+    /// assert_eq!(patched.to_t::<Config>().unwrap(), target);
+    /// ```
+    pub fn to_t<T: DeserializeOwned>(&self) -> Result<T, DeserializeError> {
+        T::deserialize(self.clone().into_deserializer())
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for Node {
+    type Deserializer = NodeDeserializer;
+
+    fn into_deserializer(self) -> NodeDeserializer {
+        NodeDeserializer { node: self }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for NodeDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn map_deserializer(
+    map: BTreeMap<String, Node>,
+) -> MapDeserializer<'static, std::collections::btree_map::IntoIter<String, Node>, DeserializeError>
+{
+    MapDeserializer::new(map.into_iter())
+}
+
+impl<'de> de::Deserializer<'de> for NodeDeserializer {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut node = self.node;
+        match &mut node {
+            Node::Void | Node::Null => visitor.visit_unit(),
+            Node::Bool(value) => visitor.visit_bool(*value),
+            Node::Number(number) => {
+                // Integral values are offered as integers so that integer
+                // fields accept them.
+                let value = number.get();
+                let integral =
+                    value.fract() == 0.0 && (-(2f64.powi(63))..2f64.powi(64)).contains(&value);
+                match value {
+                    _ if !integral => visitor.visit_f64(value),
+                    _ if value < 0.0 => visitor.visit_i64(value as i64),
+                    _ => visitor.visit_u64(value as u64),
+                }
+            }
+            Node::String(value) => visitor.visit_string(std::mem::take(value)),
+            Node::Array(items) => {
+                SeqDeserializer::new(std::mem::take(items).into_iter()).deserialize_any(visitor)
+            }
+            Node::Object(map) => map_deserializer(std::mem::take(map)).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.node {
+            Node::Void | Node::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Reads unit variants from strings and other variants from single-member
+    /// objects, as `serde_json` writes them.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut node = self.node;
+        match &mut node {
+            Node::String(variant) => {
+                visitor.visit_enum(std::mem::take(variant).into_deserializer())
+            }
+            Node::Object(map) if map.len() == 1 => visitor
+                .visit_enum(MapAccessDeserializer::new(map_deserializer(std::mem::take(map)))),
+            Node::Object(_) => {
+                Err(de::Error::invalid_value(Unexpected::Map, &"an object with a single key"))
+            }
+            _ => Self { node }.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
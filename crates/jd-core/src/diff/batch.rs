@@ -0,0 +1,98 @@
+//! Diffing many document pairs across threads.
+
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use super::{diff_nodes_with_scratch, Diff, DiffScratch};
+use crate::{DiffOptions, Node};
+
+/// Computes the diff of every `(lhs, rhs)` pair like [`super::diff_nodes`],
+/// spreading the pairs over one thread per available core.
+///
+/// Diffs are returned in the order of `pairs`, however long each takes.
+/// Threads take the next pair as they finish the previous one, so a few
+/// large documents do not hold up the rest, and each thread reuses its
+/// buffers through a [`DiffScratch`]. A panic while diffing any pair
+/// resumes on the calling thread once the other threads finish.
+///
+/// ```
+/// use jd_core::{diff, node, DiffOptions};
+///
+/// let before = [node!({"a": 1}), node!([1, 2]), node!("x")];
+/// let after = [node!({"a": 2}), node!([1, 2]), node!("y")];
+/// let options = DiffOptions::default();
+/// let diffs = diff::diff_many(before.iter().zip(&after), &options);
+/// assert_eq!(diffs.len(), 3);
+/// assert_eq!(diffs[0], before[0].diff(&after[0], &options));
+/// assert!(diffs[1].is_empty());
+/// ```
+#[must_use]
+pub fn diff_many<'a, I>(pairs: I, options: &DiffOptions) -> Vec<Diff>
+where
+    I: IntoIterator<Item = (&'a Node, &'a Node)>,
+{
+    let pairs: Vec<_> = pairs.into_iter().collect();
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    diff_on_threads(&pairs, options, threads)
+}
+
+/// [`diff_many`] on at most `threads` threads.
+fn diff_on_threads(pairs: &[(&Node, &Node)], options: &DiffOptions, threads: usize) -> Vec<Diff> {
+    let threads = threads.min(pairs.len());
+    if threads <= 1 {
+        let mut scratch = DiffScratch::new();
+        return pairs
+            .iter()
+            .map(|(lhs, rhs)| diff_nodes_with_scratch(lhs, rhs, options, &mut scratch))
+            .collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let worker = || {
+        let mut scratch = DiffScratch::new();
+        let mut diffs = Vec::new();
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(&(lhs, rhs)) = pairs.get(index) else { break };
+            diffs.push((index, diff_nodes_with_scratch(lhs, rhs, options, &mut scratch)));
+        }
+        diffs
+    };
+    let finished: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+        handles.into_iter().map(|handle| handle.join()).collect()
+    });
+
+    let mut ordered: Vec<Option<Diff>> = vec![None; pairs.len()];
+    for diffs in finished {
+        for (index, diff) in diffs.unwrap_or_else(|payload| panic::resume_unwind(payload)) {
+            ordered[index] = Some(diff);
+        }
+    }
+    ordered.into_iter().map(|diff| diff.expect("every pair is diffed once")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_come_back_in_input_order() {
+        let options = DiffOptions::default();
+        let before: Vec<_> = (0..64_i64).map(|i| crate::node!({"n": i})).collect();
+        let after: Vec<_> = (0..64_i64)
+            .map(|i| {
+                // Mix in a few large lists so threads finish out of order.
+                let items: Vec<Node> = (0..i * 20).map(Node::from).collect();
+                crate::node!({"n": (i % 3), "items": items})
+            })
+            .collect();
+        let diffs = diff_many(before.iter().zip(&after), &options);
+        let expected: Vec<_> =
+            before.iter().zip(&after).map(|(l, r)| l.diff(r, &options)).collect();
+        assert_eq!(diffs, expected);
+        assert!(diff_many(std::iter::empty(), &options).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! Summaries of base64-encoded strings in native output.
+
+use std::borrow::Cow;
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine as _;
+
+use super::{Diff, Path, PathSegment};
+use crate::{hash_bytes, DiffOptions, Node};
+
+const CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
+const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
+
+/// Marks the hunks whose path a [`PathSetting::Base64`](crate::PathSetting::Base64)
+/// applies to.
+pub(super) fn mark(diff: &mut Diff, options: &DiffOptions) {
+    if !options.has_base64() {
+        return;
+    }
+    for element in &mut diff.elements {
+        element.base64 = applies(&element.path, options);
+    }
+}
+
+fn applies(path: &Path, options: &DiffOptions) -> bool {
+    let mut options = Cow::Borrowed(options);
+    for segment in path.segments() {
+        if options.base64() {
+            return true;
+        }
+        let refined = match segment {
+            PathSegment::Key(key) => options.refine_key(key).into_owned(),
+            PathSegment::Index(index) => options.refine_index(*index).into_owned(),
+            _ => return false,
+        };
+        options = Cow::Owned(refined);
+    }
+    options.base64()
+}
+
+/// The bytes `text` encodes in standard or URL-safe base64, with or without
+/// padding, ignoring ASCII whitespace such as MIME line breaks.
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let text: Cow<'_, str> = if text.bytes().any(|byte| byte.is_ascii_whitespace()) {
+        Cow::Owned(text.chars().filter(|ch| !ch.is_ascii_whitespace()).collect())
+    } else {
+        Cow::Borrowed(text)
+    };
+    STANDARD.decode(text.as_bytes()).or_else(|_| URL_SAFE.decode(text.as_bytes())).ok()
+}
+
+/// Native lines standing in for a hunk's removed and added values, `None`
+/// for each value that is not a base64 string, or no lines at all when none
+/// of them is.
+///
+/// A single string replacing another also reports the first byte at which
+/// their contents differ.
+pub(super) fn summaries(
+    remove: &[Node],
+    add: &[Node],
+) -> (Vec<Option<String>>, Vec<Option<String>>) {
+    let decode_all = |values: &[Node]| -> Vec<Option<Vec<u8>>> {
+        values
+            .iter()
+            .map(|value| match value {
+                Node::String(text) => decode(text),
+                _ => None,
+            })
+            .collect()
+    };
+    let (old, new) = (decode_all(remove), decode_all(add));
+    if old.iter().chain(&new).all(Option::is_none) {
+        return (Vec::new(), Vec::new());
+    }
+
+    let change = match (&old[..], &new[..]) {
+        ([Some(old)], [Some(new)]) => Some(match old.iter().zip(new).position(|(l, r)| l != r) {
+            Some(at) => format!("first difference at byte {at}"),
+            None if old.len() == new.len() => "same bytes".to_string(),
+            None => format!("first difference at byte {}", old.len().min(new.len())),
+        }),
+        _ => None,
+    };
+    let summarize = |decoded: &[Option<Vec<u8>>], change: Option<&str>| -> Vec<Option<String>> {
+        decoded.iter().map(|bytes| bytes.as_deref().map(|bytes| summary(bytes, change))).collect()
+    };
+    (summarize(&old, None), summarize(&new, change.as_deref()))
+}
+
+fn summary(bytes: &[u8], change: Option<&str>) -> String {
+    let digest = u64::from_le_bytes(hash_bytes(bytes));
+    let unit = if bytes.len() == 1 { "byte" } else { "bytes" };
+    match change {
+        Some(change) => format!("<base64: {} {unit}, fnv {digest:016x}, {change}>", bytes.len()),
+        None => format!("<base64: {} {unit}, fnv {digest:016x}>", bytes.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PathMatcher, PathOption, PathSetting, RenderConfig};
+
+    fn options(at: impl IntoIterator<Item = PathMatcher>) -> DiffOptions {
+        let option = PathOption::new(at).with_setting(PathSetting::Base64);
+        DiffOptions::default().with_path_option(option).unwrap()
+    }
+
+    #[test]
+    fn base64_values_render_as_summaries() {
+        let lhs =
+            crate::node!({"data": {"cert": "aGVsbG8gd29ybGQ=", "name": "aGk="}, "kind": "aGk="});
+        let rhs = crate::node!({"data": {"cert": "aGVsbG8gd29ybGQh", "key": "AA"}, "kind": "aGV5"});
+        let diff = lhs.diff(&rhs, &options([PathMatcher::key("data")]));
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            concat!(
+                "@ [\"data\",\"cert\"]\n",
+                "- <base64: 11 bytes, fnv 779a65e7023cd2e7>\n",
+                "+ <base64: 12 bytes, fnv 782ded88cd5a2672, first difference at byte 11>\n",
+                "@ [\"data\",\"name\"]\n",
+                "- <base64: 2 bytes, fnv 08ba5f07b55ec3da>\n",
+                "@ [\"data\",\"key\"]\n",
+                "+ <base64: 1 byte, fnv af63bd4c8601b7df>\n",
+                "@ [\"kind\"]\n",
+                "- \"aGk=\"\n",
+                "+ \"aGV5\"\n",
+            )
+        );
+        // Patches still carry the values.
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+    }
+
+    #[test]
+    fn strings_that_do_not_decode_render_as_usual() {
+        let lhs = crate::node!(["not base64!", "aGk=", 1]);
+        let rhs = crate::node!(["aGk", "aGk=", 2]);
+        let diff = lhs.diff(&rhs, &options([]));
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            concat!(
+                "@ [0]\n[\n- \"not base64!\"\n+ <base64: 2 bytes, fnv 08ba5f07b55ec3da>\n  \"aGk=\"\n",
+                "@ [2]\n  \"aGk=\"\n- 1\n+ 2\n]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn decoding_accepts_both_alphabets_and_line_breaks() {
+        assert_eq!(decode("aGk=").as_deref(), Some(&b"hi"[..]));
+        assert_eq!(decode("aGk").as_deref(), Some(&b"hi"[..]));
+        assert_eq!(decode("-_8=").as_deref(), Some(&[0xfb, 0xff][..]));
+        assert_eq!(decode("aGVs\nbG8=\n").as_deref(), Some(&b"hello"[..]));
+        assert_eq!(decode("a"), None);
+        let (removed, added) = summaries(&[Node::from("aGk=")], &[Node::from("aGk")]);
+        assert_eq!(removed, [Some("<base64: 2 bytes, fnv 08ba5f07b55ec3da>".to_string())]);
+        assert_eq!(
+            added,
+            [Some("<base64: 2 bytes, fnv 08ba5f07b55ec3da, same bytes>".to_string())]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! The built-in text formats, chosen by name.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Diff, ReadError, RenderConfig, RenderError};
+
+/// A built-in text format for diffs, as named by `jd -f` and the bindings.
+///
+/// [`Jd::render_as`](crate::Jd::render_as) diffs straight into a format.
+///
+/// ```
+/// use jd_core::{DiffFormat, Jd, Node};
+///
+/// let format: DiffFormat = "patch".parse().unwrap();
+/// let lhs = Node::from_json_str(r#"{"a":1}"#).unwrap();
+/// let rhs = Node::from_json_str(r#"{"a":2}"#).unwrap();
+/// let patch = Jd::new().render_as(&lhs, &rhs, format).unwrap();
+/// assert_eq!(lhs.apply_patch(&format.read(&patch).unwrap()).unwrap(), rhs);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DiffFormat {
+    /// The native jd format.
+    Jd,
+    /// JSON Patch (RFC 6902).
+    Patch,
+    /// JSON Merge Patch (RFC 7386), which can only express merge semantics.
+    Merge,
+}
+
+impl DiffFormat {
+    /// The name used by `jd -f` and [`FromStr`].
+    ///
+    /// ```
+    /// assert_eq!(jd_core::DiffFormat::Merge.name(), "merge");
+    /// ```
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Jd => "jd",
+            Self::Patch => "patch",
+            Self::Merge => "merge",
+        }
+    }
+
+    /// Reads a diff written in this format.
+    ///
+    /// ```
+    /// # use jd_core::DiffFormat;
+    /// assert_eq!(DiffFormat::Merge.read(r#"{"a":2}"#).unwrap().len(), 1);
+    /// ```
+    pub fn read(self, input: &str) -> Result<Diff, ReadError> {
+        match self {
+            Self::Jd => Diff::from_native_str(input),
+            Self::Patch => Diff::from_patch_str(input),
+            Self::Merge => Diff::from_merge_str(input),
+        }
+    }
+
+    /// Renders `diff` in this format, using `config` for the native format.
+    ///
+    /// ```
+    /// # use jd_core::{DiffFormat, RenderConfig};
+    /// let diff = DiffFormat::Merge.read(r#"{"a":2}"#).unwrap();
+    /// let rendered = DiffFormat::Jd.render(&diff, &RenderConfig::default()).unwrap();
+    /// assert_eq!(rendered, "^ {\"Merge\":true}\n@ [\"a\"]\n+ 2\n");
+    /// ```
+    pub fn render(self, diff: &Diff, config: &RenderConfig) -> Result<String, RenderError> {
+        match self {
+            Self::Jd => Ok(diff.render(config)),
+            Self::Patch => diff.render_patch(),
+            Self::Merge => diff.render_merge(),
+        }
+    }
+}
+
+impl fmt::Display for DiffFormat {
+    /// Formats the format as its [`name`](DiffFormat::name).
+    ///
+    /// ```
+    /// assert_eq!(jd_core::DiffFormat::Patch.to_string(), "patch");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// The error returned when parsing an unknown diff format name.
+///
+/// ```
+/// let err = "yaml".parse::<jd_core::DiffFormat>().unwrap_err();
+/// assert_eq!(err.to_string(), r#"unknown diff format "yaml"; expected "jd", "patch", or "merge""#);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("unknown diff format {name:?}; expected \"jd\", \"patch\", or \"merge\"")]
+pub struct ParseDiffFormatError {
+    name: String,
+}
+
+impl FromStr for DiffFormat {
+    type Err = ParseDiffFormatError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Self::Jd, Self::Patch, Self::Merge]
+            .into_iter()
+            .find(|format| format.name() == name)
+            .ok_or_else(|| ParseDiffFormatError { name: name.to_string() })
+    }
+}
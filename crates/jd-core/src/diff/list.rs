@@ -1,49 +1,119 @@
-use super::{diff_impl, Diff, DiffElement, Path, PathSegment};
-use crate::hash::HashCode;
-use crate::{DiffOptions, Node};
-
-pub(super) fn diff_lists(lhs: &[Node], rhs: &[Node], path: &Path, options: &DiffOptions) -> Diff {
-    let lhs_hashes: Vec<HashCode> = lhs.iter().map(|node| node.hash_code(options)).collect();
-    let rhs_hashes: Vec<HashCode> = rhs.iter().map(|node| node.hash_code(options)).collect();
-    let common = longest_common_subsequence(&lhs_hashes, &rhs_hashes);
-    let path_with_placeholder = path.clone().with_segment(PathSegment::index(0));
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use super::similarity::Leaves;
+use super::{diff_impl, scratch, Diff, DiffElement, Trail};
+use crate::hash::Digest;
+use crate::limits::Deadline;
+use crate::node::element_hashes;
+use crate::progress::{self, Meter, Phase};
+use crate::{DiffOptions, ListAlignment, Node};
+
+pub(super) fn diff_lists(
+    lhs: &[Node],
+    rhs: &[Node],
+    path: &Trail<'_>,
+    options: &DiffOptions,
+    deadline: &Deadline,
+) -> Diff {
+    let progress = progress::Scope::enter();
+    let hash_span = tracing::debug_span!("hash", elements = lhs.len() + rhs.len()).entered();
+    let hashing = progress.meter(Phase::Hash, lhs.len() + rhs.len());
+    let mut lhs_hashes = scratch::digests(options);
+    lhs_hashes.extend(element_hashes(lhs, options).inspect(|_| hashing.advance()));
+    let mut rhs_hashes = scratch::digests(options);
+    rhs_hashes.extend(element_hashes(rhs, options).inspect(|_| hashing.advance()));
+    let lhs_ids = anchor_ids(lhs, &lhs_hashes, options);
+    let rhs_ids = anchor_ids(rhs, &rhs_hashes, options);
+    hash_span.exit();
+    let common = {
+        let _span = tracing::debug_span!("lcs", lhs = lhs.len(), rhs = rhs.len()).entered();
+        let aligning = progress.meter(Phase::Lcs, lhs.len());
+        let mut common = scratch::digests(options);
+        let mut table = scratch::table(options);
+        let found = match options.list_alignment() {
+            ListAlignment::Lcs => longest_common_subsequence(
+                &lhs_ids,
+                &rhs_ids,
+                deadline,
+                &aligning,
+                &mut table,
+                &mut common,
+            ),
+            ListAlignment::Patience => patience_common_subsequence(
+                &lhs_ids,
+                &rhs_ids,
+                deadline,
+                &aligning,
+                &mut table,
+                &mut common,
+            ),
+        };
+        if !found {
+            common.clear();
+        }
+        aligning.finish();
+        common
+    };
     let elements = diff_rest(
         lhs,
         rhs,
         0,
-        path_with_placeholder,
-        &lhs_hashes,
-        &rhs_hashes,
+        path,
+        Hashes { ids: &lhs_ids, full: &lhs_hashes },
+        Hashes { ids: &rhs_ids, full: &rhs_hashes },
         &common,
         &Node::Void,
         options,
+        deadline,
     );
     Diff::from_elements(elements)
 }
 
+/// The identities elements are aligned by, and their full hashes, which
+/// differ for objects anchored by set keys.
+#[derive(Clone, Copy)]
+struct Hashes<'a> {
+    ids: &'a [Digest],
+    full: &'a [Digest],
+}
+
+impl Hashes<'_> {
+    fn from(&self, cursor: usize) -> Self {
+        Self { ids: &self.ids[cursor..], full: &self.full[cursor..] }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn diff_rest(
     lhs: &[Node],
     rhs: &[Node],
     path_index: i64,
-    path: Path,
-    lhs_hashes: &[HashCode],
-    rhs_hashes: &[HashCode],
-    common: &[HashCode],
+    path: &Trail<'_>,
+    lhs_hashes: Hashes<'_>,
+    rhs_hashes: Hashes<'_>,
+    common: &[Digest],
     previous: &Node,
     options: &DiffOptions,
+    deadline: &Deadline,
 ) -> Vec<DiffElement> {
     let mut a_cursor = 0usize;
     let mut b_cursor = 0usize;
     let mut common_cursor = 0usize;
     let mut path_cursor = path_index;
-    let path_len = path.len();
 
-    let mut diff = vec![DiffElement::new()
-        .with_path(path_now(&path, path_cursor))
-        .with_before(vec![previous.clone()])];
+    // The run's own hunk gets its path once it turns out to have changes.
+    let mut diff = vec![DiffElement::new().with_before(vec![previous.clone()])];
+    // Whether `diff[0]` is this run's own hunk rather than one of a paired
+    // element's.
+    let mut own = true;
+    // Whether the run ended on a common element rather than a paired one.
+    let mut ends_common = false;
 
     loop {
+        let lhs_common = at_common(lhs_hashes.ids, a_cursor, common);
+        let rhs_common = at_common(rhs_hashes.ids, b_cursor, common);
         match () {
             _ if a_cursor == lhs.len() => {
                 while b_cursor < rhs.len() {
@@ -60,43 +130,83 @@ fn diff_rest(
                 }
                 break;
             }
-            _ if at_common(lhs_hashes, a_cursor, common)
-                && at_common(rhs_hashes, b_cursor, common) =>
+            _ if lhs_common
+                && rhs_common
+                && lhs_hashes.full[a_cursor] == rhs_hashes.full[b_cursor] =>
             {
                 a_cursor += 1;
                 b_cursor += 1;
                 common_cursor += 1;
                 path_cursor += 1;
+                ends_common = true;
                 break;
             }
-            _ if at_common(lhs_hashes, a_cursor, common) => {
-                while !at_common(rhs_hashes, b_cursor, common) {
-                    diff[0].add.push(rhs[b_cursor].clone());
-                    b_cursor += 1;
-                    path_cursor += 1;
-                }
-            }
-            _ if at_common(rhs_hashes, b_cursor, common) => {
-                while !at_common(lhs_hashes, a_cursor, common) {
-                    diff[0].remove.push(lhs[a_cursor].clone());
-                    a_cursor += 1;
-                }
-            }
-            _ if same_container_type(&lhs[a_cursor], &rhs[b_cursor]) => {
-                let sub_path = path_now(&path, path_cursor);
+            // Elements paired by set keys differ elsewhere; other containers
+            // are paired where neither side is common.
+            _ if lhs_common && rhs_common
+                || !lhs_common
+                    && !rhs_common
+                    && pairs_with(&lhs[a_cursor], &rhs[b_cursor], options) =>
+            {
+                let sub_path = path.index(path_cursor);
+                let sub_options = options.refine_index(path_cursor);
                 let mut sub_diff =
-                    diff_impl(&lhs[a_cursor], &rhs[b_cursor], &sub_path, options).into_elements();
+                    diff_impl(&lhs[a_cursor], &rhs[b_cursor], &sub_path, &sub_options, deadline)
+                        .into_elements();
                 if has_changes(&diff) {
                     diff[0].after = after_context(lhs, a_cursor, common_cursor);
                     diff.append(&mut sub_diff);
                 } else {
                     diff = sub_diff;
+                    own = false;
+                }
+                if lhs_common {
+                    common_cursor += 1;
                 }
                 a_cursor += 1;
                 b_cursor += 1;
                 path_cursor += 1;
                 break;
             }
+            _ if lhs_common => {
+                while !at_common(rhs_hashes.ids, b_cursor, common) {
+                    diff[0].add.push(rhs[b_cursor].clone());
+                    b_cursor += 1;
+                    path_cursor += 1;
+                }
+            }
+            _ if rhs_common => {
+                while !at_common(lhs_hashes.ids, a_cursor, common) {
+                    diff[0].remove.push(lhs[a_cursor].clone());
+                    a_cursor += 1;
+                }
+            }
+            // An element with a similar counterpart further along waits for it.
+            _ if has_similar(
+                &lhs[a_cursor],
+                rhs,
+                rhs_hashes.ids,
+                b_cursor + 1,
+                common,
+                options,
+            ) =>
+            {
+                diff[0].add.push(rhs[b_cursor].clone());
+                b_cursor += 1;
+                path_cursor += 1;
+            }
+            _ if has_similar(
+                &rhs[b_cursor],
+                lhs,
+                lhs_hashes.ids,
+                a_cursor + 1,
+                common,
+                options,
+            ) =>
+            {
+                diff[0].remove.push(lhs[a_cursor].clone());
+                a_cursor += 1;
+            }
             _ => {
                 diff[0].remove.push(lhs[a_cursor].clone());
                 diff[0].add.push(rhs[b_cursor].clone());
@@ -110,35 +220,74 @@ fn diff_rest(
     if !has_changes(&diff) {
         diff.clear();
     } else {
+        if own {
+            diff[0].path = path.index(path_index).to_path();
+            deadline.found(1);
+        }
         let single = diff.len() < 2;
         if let Some(first) = diff.first_mut() {
-            if first.path.len() <= path_len && single {
+            if first.path.len() <= path.len() + 1 && single {
                 first.after = after_context(lhs, a_cursor, common_cursor);
             }
         }
     }
 
-    if a_cursor == lhs.len() && b_cursor == rhs.len() {
+    if a_cursor == lhs.len() && b_cursor == rhs.len() || deadline.check() {
         return diff;
     }
 
-    let previous_node = if b_cursor == 0 { Node::Void } else { rhs[b_cursor - 1].clone() };
+    // A common element is the one a patch finds in the document it is
+    // applied to, which differs from the `rhs` one where options make
+    // unequal values compare equal.
+    let previous_node = match (b_cursor, ends_common) {
+        (0, _) => Node::Void,
+        (_, true) => lhs[a_cursor - 1].clone(),
+        (_, false) => rhs[b_cursor - 1].clone(),
+    };
     let mut rest = diff_rest(
         &lhs[a_cursor..],
         &rhs[b_cursor..],
         path_cursor,
-        path_now(&path, path_cursor),
-        &lhs_hashes[a_cursor..],
-        &rhs_hashes[b_cursor..],
+        path,
+        lhs_hashes.from(a_cursor),
+        rhs_hashes.from(b_cursor),
         &common[common_cursor..],
         &previous_node,
         options,
+        deadline,
     );
     diff.append(&mut rest);
     diff
 }
 
-fn at_common(hashes: &[HashCode], cursor: usize, common: &[HashCode]) -> bool {
+/// Alignment identities: with set keys, an object holding any of them is
+/// identified by those members alone, so it pairs with the object having
+/// the same key values.
+fn anchor_ids<'a>(
+    values: &[Node],
+    hashes: &'a [Digest],
+    options: &DiffOptions,
+) -> Cow<'a, [Digest]> {
+    let Some(keys) = options.set_keys() else {
+        return Cow::Borrowed(hashes);
+    };
+    let options = options.without_memo();
+    let ids = values.iter().zip(hashes).enumerate().map(|(index, (value, hash))| {
+        let Node::Object(members) = value else {
+            return *hash;
+        };
+        let id: BTreeMap<String, Node> =
+            keys.iter().filter_map(|key| Some((key.clone(), members.get(key)?.clone()))).collect();
+        if id.is_empty() {
+            *hash
+        } else {
+            Node::Object(id).digest(&options.refine_index(index as i64))
+        }
+    });
+    Cow::Owned(ids.collect())
+}
+
+fn at_common(hashes: &[Digest], cursor: usize, common: &[Digest]) -> bool {
     if cursor >= hashes.len() || common.is_empty() {
         return false;
     }
@@ -160,8 +309,43 @@ fn after_context(lhs: &[Node], a_cursor: usize, common_cursor: usize) -> Vec<Nod
     }
 }
 
-fn path_now(path: &Path, path_cursor: i64) -> Path {
-    path.drop_last().with_segment(PathSegment::index(path_cursor))
+/// How far ahead [`has_similar`] looks for a counterpart.
+const SIMILARITY_LOOKAHEAD: usize = 16;
+
+/// Whether two differing elements at the same position are diffed
+/// recursively: containers of the same type, and, with a similarity
+/// threshold, only those similar enough.
+fn pairs_with(lhs: &Node, rhs: &Node, options: &DiffOptions) -> bool {
+    same_container_type(lhs, rhs)
+        && options.similarity_threshold().is_none_or(|threshold| {
+            Leaves::of(lhs, options).similarity(&Leaves::of(rhs, options)) >= threshold
+        })
+}
+
+/// Whether `node` is similar enough to one of `others` from `start` on,
+/// before the next common element, to be diffed with it.
+fn has_similar(
+    node: &Node,
+    others: &[Node],
+    ids: &[Digest],
+    start: usize,
+    common: &[Digest],
+    options: &DiffOptions,
+) -> bool {
+    let Some(threshold) = options.similarity_threshold() else {
+        return false;
+    };
+    if !matches!(node, Node::Array(_) | Node::Object(_)) {
+        return false;
+    }
+    let leaves = Leaves::of(node, options);
+    (start..others.len())
+        .take(SIMILARITY_LOOKAHEAD)
+        .take_while(|&index| !at_common(ids, index, common))
+        .any(|index| {
+            same_container_type(node, &others[index])
+                && leaves.similarity(&Leaves::of(&others[index], options)) >= threshold
+        })
 }
 
 fn same_container_type(lhs: &Node, rhs: &Node) -> bool {
@@ -169,21 +353,39 @@ fn same_container_type(lhs: &Node, rhs: &Node) -> bool {
         || matches!(lhs, Node::Array(_)) && matches!(rhs, Node::Array(_))
 }
 
-fn longest_common_subsequence(lhs: &[HashCode], rhs: &[HashCode]) -> Vec<HashCode> {
+/// Appends the longest common subsequence of `lhs` and `rhs` to `result`,
+/// using `table`, cleared first, for the lengths. Returns `false` if the
+/// deadline expired first.
+fn longest_common_subsequence(
+    lhs: &[Digest],
+    rhs: &[Digest],
+    deadline: &Deadline,
+    meter: &Meter<'_>,
+    table: &mut Vec<usize>,
+    result: &mut Vec<Digest>,
+) -> bool {
     let n = lhs.len();
     let m = rhs.len();
-    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    // Row `i` holds the lengths for `lhs[..i]`, one column per `rhs[..j]`.
+    let width = m + 1;
+    table.clear();
+    table.resize((n + 1) * width, 0);
     for (i, lhs_hash) in lhs.iter().enumerate() {
+        if deadline.check() {
+            return false;
+        }
         for (j, rhs_hash) in rhs.iter().enumerate() {
-            if lhs_hash == rhs_hash {
-                table[i + 1][j + 1] = table[i][j] + 1;
+            table[(i + 1) * width + j + 1] = if lhs_hash == rhs_hash {
+                table[i * width + j] + 1
             } else {
-                table[i + 1][j + 1] = table[i][j + 1].max(table[i + 1][j]);
-            }
+                table[i * width + j + 1].max(table[(i + 1) * width + j])
+            };
         }
+        meter.advance();
     }
 
-    let mut result = Vec::with_capacity(table[n][m]);
+    let start = result.len();
+    result.reserve(table[n * width + m]);
     let mut i = n;
     let mut j = m;
     while i > 0 && j > 0 {
@@ -191,12 +393,129 @@ fn longest_common_subsequence(lhs: &[HashCode], rhs: &[HashCode]) -> Vec<HashCod
             result.push(lhs[i - 1]);
             i -= 1;
             j -= 1;
-        } else if table[i - 1][j] >= table[i][j - 1] {
+        } else if table[(i - 1) * width + j] >= table[i * width + j - 1] {
             i -= 1;
         } else {
             j -= 1;
         }
     }
-    result.reverse();
-    result
+    result[start..].reverse();
+    true
+}
+
+/// A step of the patience alignment: align two ranges, or emit a matched
+/// element. Steps are kept on a stack so nesting never grows the call stack.
+enum Step {
+    Align(Range<usize>, Range<usize>),
+    Keep(Digest),
+}
+
+/// Patience alignment. Common leading and trailing elements match first;
+/// of the rest, elements occurring exactly once on each side are matched
+/// along the longest run in which they appear in the same order, and the
+/// gaps between these anchors are aligned the same way. Gaps without
+/// unique elements fall back to the LCS. Appends to `result` like
+/// [`longest_common_subsequence`].
+fn patience_common_subsequence(
+    lhs: &[Digest],
+    rhs: &[Digest],
+    deadline: &Deadline,
+    meter: &Meter<'_>,
+    table: &mut Vec<usize>,
+    result: &mut Vec<Digest>,
+) -> bool {
+    let mut steps = vec![Step::Align(0..lhs.len(), 0..rhs.len())];
+    while let Some(step) = steps.pop() {
+        let (mut a, mut b) = match step {
+            Step::Keep(digest) => {
+                result.push(digest);
+                continue;
+            }
+            Step::Align(a, b) => (a, b),
+        };
+        if deadline.check() {
+            return false;
+        }
+        while !a.is_empty() && !b.is_empty() && lhs[a.start] == rhs[b.start] {
+            result.push(lhs[a.start]);
+            a.start += 1;
+            b.start += 1;
+            meter.advance();
+        }
+        while !a.is_empty() && !b.is_empty() && lhs[a.end - 1] == rhs[b.end - 1] {
+            a.end -= 1;
+            b.end -= 1;
+            steps.push(Step::Keep(lhs[a.end]));
+            meter.advance();
+        }
+        let anchors = unique_anchors(&lhs[a.clone()], &rhs[b.clone()]);
+        if anchors.is_empty() {
+            if !longest_common_subsequence(
+                &lhs[a.clone()],
+                &rhs[b.clone()],
+                deadline,
+                meter,
+                table,
+                result,
+            ) {
+                return false;
+            }
+            continue;
+        }
+        // Pushed last to first, so they are popped in order.
+        let (mut a_end, mut b_end) = (a.end, b.end);
+        for &(i, j) in anchors.iter().rev() {
+            let (i, j) = (a.start + i, b.start + j);
+            steps.push(Step::Align(i + 1..a_end, j + 1..b_end));
+            steps.push(Step::Keep(lhs[i]));
+            meter.advance();
+            (a_end, b_end) = (i, j);
+        }
+        steps.push(Step::Align(a.start..a_end, b.start..b_end));
+    }
+    true
+}
+
+/// Positions of the elements occurring exactly once in both `lhs` and
+/// `rhs`, restricted to the longest run increasing on both sides.
+fn unique_anchors(lhs: &[Digest], rhs: &[Digest]) -> Vec<(usize, usize)> {
+    // Occurrences and last position on each side.
+    let mut counts: HashMap<Digest, [(usize, usize); 2]> = HashMap::new();
+    for (side, hashes) in [lhs, rhs].into_iter().enumerate() {
+        for (index, digest) in hashes.iter().enumerate() {
+            let entry = &mut counts.entry(*digest).or_default()[side];
+            *entry = (entry.0 + 1, index);
+        }
+    }
+    let pairs: Vec<(usize, usize)> = lhs
+        .iter()
+        .filter_map(|digest| match counts[digest] {
+            [(1, i), (1, j)] => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+
+    // Longest increasing run of `rhs` positions by patience sorting: `tops`
+    // holds, per pile, the pair ending the best run of that length.
+    let mut tops: Vec<usize> = Vec::new();
+    let mut previous = vec![None; pairs.len()];
+    for (index, &(_, j)) in pairs.iter().enumerate() {
+        let pile = tops.partition_point(|&top| pairs[top].1 < j);
+        if pile > 0 {
+            previous[index] = Some(tops[pile - 1]);
+        }
+        if pile == tops.len() {
+            tops.push(index);
+        } else {
+            tops[pile] = index;
+        }
+    }
+    let mut run = Vec::with_capacity(tops.len());
+    let mut cursor = tops.last().copied();
+    while let Some(index) = cursor {
+        run.push(pairs[index]);
+        cursor = previous[index];
+    }
+    run.reverse();
+    run
 }
@@ -0,0 +1,243 @@
+//! Coalescing pass that shrinks diffs assembled from several sources.
+//!
+//! Every rewrite keeps the document produced by applying the diff in order:
+//! a hunk is only folded into an earlier one when it edits what that hunk
+//! wrote, or the list right next to it, and every hunk in between touches a
+//! different object member.
+
+use super::{DiffElement, DiffMetadata, Path, PathSegment};
+use crate::Node;
+
+/// Repeats [`minimize_once`] until nothing changes, since dropping a hunk can
+/// let the hunks around it fold together.
+pub(super) fn minimize(elements: &[DiffElement]) -> Vec<DiffElement> {
+    let mut minimized = minimize_once(elements);
+    loop {
+        let again = minimize_once(&minimized);
+        if again == minimized {
+            return minimized;
+        }
+        minimized = again;
+    }
+}
+
+fn minimize_once(elements: &[DiffElement]) -> Vec<DiffElement> {
+    let mut inherited: Option<DiffMetadata> = None;
+    let mut minimized: Vec<(DiffElement, Option<DiffMetadata>)> = Vec::new();
+    for element in elements {
+        if let Some(metadata) = element.metadata.as_ref().filter(|meta| meta.is_effective()) {
+            match inherited.as_mut() {
+                Some(existing) => existing.absorb(metadata),
+                None => inherited = Some(metadata.clone()),
+            }
+        }
+        let mut element = DiffElement { metadata: None, ..element.clone() };
+        // Merge patches replace whole values; leave them as they are.
+        if inherited.as_ref().is_some_and(|meta| meta.merge) {
+            minimized.push((element, inherited.clone()));
+            continue;
+        }
+        trim(&mut element);
+        if is_noop(&element) {
+            continue;
+        }
+        let mut folded = false;
+        for candidate in (0..minimized.len()).rev() {
+            let (previous, metadata) = &mut minimized[candidate];
+            if *metadata == inherited && coalesce(previous, &element) {
+                trim(previous);
+                if is_noop(previous) {
+                    minimized.remove(candidate);
+                }
+                folded = true;
+                break;
+            }
+            if !independent(&previous.path, &element.path) {
+                break;
+            }
+        }
+        if !folded {
+            minimized.push((element, inherited.clone()));
+        }
+    }
+
+    // Metadata applies to all following hunks, so it is only written where
+    // it changes.
+    let mut emitted: Option<DiffMetadata> = None;
+    minimized
+        .into_iter()
+        .map(|(mut element, metadata)| {
+            if metadata != emitted {
+                element.metadata.clone_from(&metadata);
+                emitted = metadata;
+            }
+            element
+        })
+        .collect()
+}
+
+/// Whether hunks at `a` and `b` can be applied in either order: their paths
+/// part at different members of the same object.
+fn independent(a: &Path, b: &Path) -> bool {
+    a.segments()
+        .iter()
+        .zip(b.segments())
+        .find(|(a, b)| a != b)
+        .is_some_and(|pair| matches!(pair, (PathSegment::Key(_), PathSegment::Key(_))))
+}
+
+fn is_noop(element: &DiffElement) -> bool {
+    element.remove.is_empty() && element.add.is_empty()
+}
+
+/// The list index a hunk edits, if it edits a list.
+fn list_index(element: &DiffElement) -> Option<i64> {
+    match element.path.segments().last() {
+        Some(PathSegment::Index(index)) => Some(*index),
+        _ => None,
+    }
+}
+
+/// Drops values that are removed and added back unchanged, sliding the
+/// context over them, and keeps one line of context on each side like the
+/// hunks jd produces.
+fn trim(element: &mut DiffElement) {
+    let Some(index) = list_index(element).filter(|index| *index >= 0) else {
+        // Context is only checked for list edits.
+        element.before.clear();
+        element.after.clear();
+        if element.remove == element.add {
+            element.remove.clear();
+            element.add.clear();
+        }
+        return;
+    };
+
+    let prefix = common_len(element.remove.iter(), element.add.iter());
+    if prefix > 0 {
+        element.add.drain(..prefix);
+        let mut kept = element.remove.drain(..prefix);
+        if !element.before.is_empty() {
+            element.before = kept.next_back().into_iter().collect();
+        }
+        element.path.pop();
+        element.path.push(PathSegment::Index(index + prefix as i64));
+    }
+    let suffix = common_len(element.remove.iter().rev(), element.add.iter().rev());
+    if suffix > 0 {
+        element.add.truncate(element.add.len() - suffix);
+        let kept = element.remove.split_off(element.remove.len() - suffix);
+        if !element.after.is_empty() {
+            element.after = kept.into_iter().take(1).collect();
+        }
+    }
+
+    if element.before.len() > 1 {
+        element.before.drain(..element.before.len() - 1);
+    }
+    element.after.truncate(1);
+}
+
+fn common_len<'a>(
+    lhs: impl Iterator<Item = &'a Node>,
+    rhs: impl Iterator<Item = &'a Node>,
+) -> usize {
+    lhs.zip(rhs).take_while(|(a, b)| a == b).count()
+}
+
+/// Folds `next` into `previous` when applying the result in place of both
+/// yields the same document.
+fn coalesce(previous: &mut DiffElement, next: &DiffElement) -> bool {
+    if previous.path.drop_last() != next.path.drop_last() {
+        return false;
+    }
+    match (list_index(previous), list_index(next)) {
+        // `next` edits the list inside or right after the values `previous`
+        // inserted; whatever it removes beyond them are the original values
+        // that followed `previous`'s removals.
+        (Some(start), Some(index)) if start >= 0 && index >= start => {
+            let Some(offset) =
+                usize::try_from(index - start).ok().filter(|offset| *offset <= previous.add.len())
+            else {
+                return false;
+            };
+            let overlap = next.remove.len().min(previous.add.len() - offset);
+            if previous.add[offset..offset + overlap] != next.remove[..overlap] {
+                return false;
+            }
+            if offset + next.remove.len() >= previous.add.len() {
+                previous.after.clone_from(&next.after);
+            }
+            previous.remove.extend(next.remove[overlap..].iter().cloned());
+            previous.add.splice(offset..offset + overlap, next.add.iter().cloned());
+            true
+        }
+        // `next` replaces the value `previous` wrote at the same key.
+        (None, None) if previous.path == next.path && previous.add == next.remove => {
+            previous.add.clone_from(&next.add);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Diff, DiffOptions};
+
+    fn parse(json: &str) -> Node {
+        Node::from_json_str(json).unwrap()
+    }
+
+    #[test]
+    fn adjacent_list_hunks_merge_and_shared_values_become_context() {
+        let a = parse("[1,2,3,4]");
+        let b = parse("[1,9,3,4]");
+        let c = parse("[1,9,7,4]");
+        let mut elements = a.diff(&b, &DiffOptions::default()).into_elements();
+        elements.extend(b.diff(&c, &DiffOptions::default()));
+        let composed = Diff::from_elements(elements);
+        assert_eq!(composed.len(), 2);
+
+        let minimized = composed.minimize();
+        assert_eq!(minimized, a.diff(&c, &DiffOptions::default()));
+        assert_eq!(a.apply_patch(&minimized).unwrap(), c);
+
+        // Reverting part of an insertion keeps the rest of it.
+        let d = parse("[1,9,3,4]");
+        let inserted = a.diff(&parse("[1,5,6,2,3,4]"), &DiffOptions::default());
+        let steps: Vec<_> = inserted
+            .into_iter()
+            .chain(parse("[1,5,6,2,3,4]").diff(&d, &DiffOptions::default()))
+            .collect();
+        let minimized = Diff::from_elements(steps).minimize();
+        assert_eq!(minimized, a.diff(&d, &DiffOptions::default()));
+    }
+
+    #[test]
+    fn replacements_of_the_same_key_fold_and_round_trips_vanish() {
+        let a = parse(r#"{"x":1,"y":[1]}"#);
+        let b = parse(r#"{"x":2,"y":[2]}"#);
+        let mut elements = a.diff(&b, &DiffOptions::default()).into_elements();
+        elements.extend(b.diff(&a, &DiffOptions::default()));
+        assert!(Diff::from_elements(elements).minimize().is_empty());
+
+        let c = parse(r#"{"x":3,"y":[1]}"#);
+        let mut elements = a.diff(&b, &DiffOptions::default()).into_elements();
+        elements.extend(b.diff(&c, &DiffOptions::default()));
+        assert_eq!(Diff::from_elements(elements).minimize(), a.diff(&c, &DiffOptions::default()));
+    }
+
+    #[test]
+    fn metadata_moves_to_the_first_remaining_hunk() {
+        let noop = DiffElement::new()
+            .with_metadata(DiffMetadata { color: Some(true), ..DiffMetadata::default() })
+            .with_path(PathSegment::key("a"))
+            .with_remove(vec![Node::Null])
+            .with_add(vec![Node::Null]);
+        let change = DiffElement::new().with_path(PathSegment::key("b")).with_add(vec![Node::Null]);
+        let minimized = Diff::from_elements(vec![noop.clone(), change.clone()]).minimize();
+        assert_eq!(minimized.into_elements(), [change.with_metadata(noop.metadata.unwrap())]);
+    }
+}
@@ -5,16 +5,41 @@
 //! The current milestone implements list-mode diffing and object traversal,
 //! mirroring the upstream Go implementation.
 
+mod batch;
+mod binary;
+mod format;
 mod list;
+mod minimize;
 mod object;
 mod path;
 mod primitives;
-
+mod read;
+mod rebase;
+mod renderer;
+mod scratch;
+mod similarity;
+mod strings;
+mod validate;
+
+pub use batch::diff_many;
+pub use format::{DiffFormat, ParseDiffFormatError};
+use path::Trail;
 pub use path::{path_from_segments, root_path, Path, PathSegment};
+pub use read::ReadError;
+pub use rebase::RebaseError;
+pub use renderer::{DiffRenderer, MergeRenderer, PatchRenderer, RawRenderer};
+pub(crate) use scratch::Buffers;
+pub use scratch::{diff_nodes_with_scratch, DiffScratch};
+pub use validate::DiffBuilder;
+
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Number as JsonNumber, Value as JsonValue};
 
+use crate::hashed::DigestMemo;
+use crate::limits::Deadline;
+use crate::progress::{self, Phase};
 use crate::{ArrayMode, DiffOptions, Node, Number, PatchError};
 
 /// Metadata associated with a diff element.
@@ -25,6 +50,7 @@ use crate::{ArrayMode, DiffOptions, Node, Number, PatchError};
 /// assert!(meta.merge);
 /// ```
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct DiffMetadata {
     /// Indicates that merge patch semantics should be used.
     #[serde(default)]
@@ -50,6 +76,35 @@ impl DiffMetadata {
         Self { merge: true, set_keys: None, color: None }
     }
 
+    /// Identifies set elements by these object keys.
+    ///
+    /// ```
+    /// # use jd_core::DiffMetadata;
+    /// let meta = DiffMetadata::default().with_set_keys(["id"]);
+    /// assert_eq!(meta.set_keys.as_deref(), Some(&["id".to_string()][..]));
+    /// ```
+    #[must_use]
+    pub fn with_set_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.set_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the color rendering hint.
+    ///
+    /// ```
+    /// # use jd_core::DiffMetadata;
+    /// assert_eq!(DiffMetadata::merge().with_color(true).color, Some(true));
+    /// ```
+    #[must_use]
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = Some(enabled);
+        self
+    }
+
     pub(crate) fn is_effective(&self) -> bool {
         self.merge || self.set_keys.is_some() || self.color.is_some()
     }
@@ -92,6 +147,7 @@ impl DiffMetadata {
 /// # assert_eq!(diff.len(), 1);
 /// ```
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct DiffElement {
     /// Optional metadata for this hunk.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -111,6 +167,10 @@ pub struct DiffElement {
     /// Context after the change (list diffs only).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub after: Vec<Node>,
+    /// Set by the diff engine where a [`PathSetting::Base64`](crate::PathSetting::Base64)
+    /// applies. Not serialized.
+    #[serde(skip)]
+    base64: bool,
 }
 
 impl DiffElement {
@@ -219,12 +279,34 @@ impl DiffElement {
 #[serde(transparent)]
 pub struct Diff {
     elements: Vec<DiffElement>,
+    /// Not serialized; the hunks themselves do not record it.
+    #[serde(skip)]
+    truncated: bool,
 }
 
 /// Configuration toggles for diff rendering.
-#[derive(Clone, Copy, Debug, Default)]
+///
+/// Serializes with the same field names as the builder methods, so frontends
+/// can store or send rendering settings; missing fields take their defaults.
+///
+/// ```
+/// # use jd_core::RenderConfig;
+/// let config: RenderConfig = serde_json::from_str(r#"{"color":true,"indent":2}"#).unwrap();
+/// assert!(config.color_enabled());
+/// let json = serde_json::to_string(&config).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"color":true,"max_hunks":null,"max_value_bytes":null,"indent":2,"sorted_hunks":false}"#
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenderConfig {
     color: bool,
+    max_hunks: Option<usize>,
+    max_value_bytes: Option<usize>,
+    indent: usize,
+    sorted_hunks: bool,
 }
 
 impl RenderConfig {
@@ -264,6 +346,129 @@ impl RenderConfig {
     pub fn color_enabled(self) -> bool {
         self.color
     }
+
+    /// Limits native rendering to the first `max` hunks, followed by a line
+    /// counting the omitted ones.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str("{\"a\":1,\"b\":1}").expect("valid JSON");
+    /// let rhs = Node::from_json_str("{\"a\":2,\"b\":2}").expect("valid JSON");
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default());
+    /// let rendered = diff.render(&RenderConfig::new().with_max_hunks(1));
+    /// assert_eq!(rendered, "@ [\"a\"]\n- 1\n+ 2\n... 1 more hunk omitted\n");
+    /// ```
+    #[must_use]
+    pub fn with_max_hunks(mut self, max: usize) -> Self {
+        self.max_hunks = Some(max);
+        self
+    }
+
+    /// Returns the hunk limit, if any.
+    ///
+    /// ```
+    /// # use jd_core::RenderConfig;
+    /// assert_eq!(RenderConfig::new().max_hunks(), None);
+    /// assert_eq!(RenderConfig::new().with_max_hunks(3).max_hunks(), Some(3));
+    /// ```
+    #[must_use]
+    pub fn max_hunks(self) -> Option<usize> {
+        self.max_hunks
+    }
+
+    /// Truncates rendered values longer than `max` bytes in native output,
+    /// replacing the tail with an elision marker.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str("{\"a\":\"0123456789\"}").expect("valid JSON");
+    /// let rhs = Node::from_json_str("{}").expect("valid JSON");
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default());
+    /// let rendered = diff.render(&RenderConfig::new().with_max_value_bytes(4));
+    /// assert_eq!(rendered, "@ [\"a\"]\n- \"012... (8 more bytes)\n");
+    /// ```
+    #[must_use]
+    pub fn with_max_value_bytes(mut self, max: usize) -> Self {
+        self.max_value_bytes = Some(max);
+        self
+    }
+
+    /// Returns the per-value byte limit, if any.
+    ///
+    /// ```
+    /// # use jd_core::RenderConfig;
+    /// assert_eq!(RenderConfig::new().with_max_value_bytes(64).max_value_bytes(), Some(64));
+    /// ```
+    #[must_use]
+    pub fn max_value_bytes(self) -> Option<usize> {
+        self.max_value_bytes
+    }
+
+    /// Pretty-prints non-empty arrays and objects in native output across
+    /// several lines, indenting nested levels by `width` spaces. Every
+    /// continuation line repeats the `- `, `+ `, or context prefix. `0` (the
+    /// default) keeps each value on a single line.
+    ///
+    /// Indented output is meant for reading; [`Diff::from_native_str`] only
+    /// accepts single-line values.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str("{\"a\":{\"b\":[1]}}").expect("valid JSON");
+    /// let rhs = Node::from_json_str("{\"a\":null}").expect("valid JSON");
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default());
+    /// let rendered = diff.render(&RenderConfig::new().with_indent(2));
+    /// assert_eq!(
+    ///     rendered,
+    ///     "@ [\"a\"]\n- {\n-   \"b\": [\n-     1\n-   ]\n- }\n+ null\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_indent(mut self, width: usize) -> Self {
+        self.indent = width;
+        self
+    }
+
+    /// Returns the indentation width for pretty-printed values (`0` when
+    /// values stay on one line).
+    ///
+    /// ```
+    /// # use jd_core::RenderConfig;
+    /// assert_eq!(RenderConfig::new().indent(), 0);
+    /// assert_eq!(RenderConfig::new().with_indent(4).indent(), 4);
+    /// ```
+    #[must_use]
+    pub fn indent(self) -> usize {
+        self.indent
+    }
+
+    /// Renders hunks in path order, as [`Diff::sorted`] arranges them, instead
+    /// of the order the diff engine produced them in.
+    ///
+    /// ```
+    /// # use jd_core::{diff::DiffElement, diff::PathSegment, Diff, Node, RenderConfig};
+    /// let hunk = |key: &str| DiffElement::new().with_path(PathSegment::key(key)).with_add(vec![Node::Null]);
+    /// let diff = Diff::from_elements(vec![hunk("b"), hunk("a")]);
+    /// let rendered = diff.render(&RenderConfig::new().with_sorted_hunks(true));
+    /// assert_eq!(rendered, "@ [\"a\"]\n+ null\n@ [\"b\"]\n+ null\n");
+    /// ```
+    #[must_use]
+    pub fn with_sorted_hunks(mut self, enabled: bool) -> Self {
+        self.sorted_hunks = enabled;
+        self
+    }
+
+    /// Indicates whether hunks are rendered in path order.
+    ///
+    /// ```
+    /// # use jd_core::RenderConfig;
+    /// assert!(!RenderConfig::new().sorted_hunks());
+    /// assert!(RenderConfig::new().with_sorted_hunks(true).sorted_hunks());
+    /// ```
+    #[must_use]
+    pub fn sorted_hunks(self) -> bool {
+        self.sorted_hunks
+    }
 }
 
 impl RenderConfig {
@@ -294,7 +499,15 @@ pub struct RenderError {
 }
 
 impl RenderError {
-    fn new(message: impl Into<String>) -> Self {
+    /// Creates an error with `message`, for a [`DiffRenderer`] that cannot
+    /// render a diff.
+    ///
+    /// ```
+    /// # use jd_core::RenderError;
+    /// assert_eq!(RenderError::new("no hunks").to_string(), "no hunks");
+    /// ```
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
         Self { message: message.into() }
     }
 }
@@ -313,6 +526,12 @@ impl From<serde_json::Error> for RenderError {
     }
 }
 
+impl From<std::io::Error> for RenderError {
+    fn from(err: std::io::Error) -> Self {
+        Self::new(err.to_string())
+    }
+}
+
 impl From<PatchError> for RenderError {
     fn from(err: PatchError) -> Self {
         Self::new(err.to_string())
@@ -329,7 +548,7 @@ impl Diff {
     /// ```
     #[must_use]
     pub fn empty() -> Self {
-        Self { elements: Vec::new() }
+        Self { elements: Vec::new(), truncated: false }
     }
 
     /// Builds a diff from the provided elements.
@@ -342,7 +561,7 @@ impl Diff {
     /// ```
     #[must_use]
     pub fn from_elements(elements: Vec<DiffElement>) -> Self {
-        Self { elements }
+        Self { elements, truncated: false }
     }
 
     /// Returns the number of elements in the diff.
@@ -368,6 +587,23 @@ impl Diff {
         self.elements.is_empty()
     }
 
+    /// Whether diffing stopped at [`DiffOptions::max_hunks`], so hunks
+    /// after these may be missing. Only diffs computed under a hunk limit
+    /// are truncated, and sorting keeps the mark.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let lhs = Node::from_json_str("[1, 2]").unwrap();
+    /// let rhs = Node::from_json_str("[3, 4]").unwrap();
+    /// assert!(!lhs.diff(&rhs, &DiffOptions::default()).is_truncated());
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default().with_max_hunks(1).unwrap());
+    /// assert!(diff.is_truncated());
+    /// ```
+    #[must_use]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
     /// Returns an iterator over the elements.
     ///
     /// ```
@@ -380,6 +616,77 @@ impl Diff {
         self.elements.iter()
     }
 
+    /// Returns the metadata in effect for each element, in order.
+    ///
+    /// Metadata attached to an element stays in effect for the elements after
+    /// it and later metadata adds to it, the same way patching reads it.
+    /// Elements before any metadata get [`DiffMetadata::default`], which
+    /// means a strict hunk.
+    ///
+    /// ```
+    /// # use jd_core::{diff::DiffElement, diff::PathSegment, Diff, DiffMetadata};
+    /// let diff = Diff::from_elements(vec![
+    ///     DiffElement::new().with_path(PathSegment::key("a")),
+    ///     DiffElement::new().with_metadata(DiffMetadata::merge()).with_path(PathSegment::key("b")),
+    ///     DiffElement::new().with_path(PathSegment::key("c")),
+    /// ]);
+    /// let merge: Vec<bool> = diff.effective_metadata().iter().map(|meta| meta.merge).collect();
+    /// assert_eq!(merge, [false, true, true]);
+    /// ```
+    #[must_use]
+    pub fn effective_metadata(&self) -> Vec<DiffMetadata> {
+        let mut inherited = DiffMetadata::default();
+        self.elements
+            .iter()
+            .map(|element| {
+                if let Some(metadata) = element.metadata.as_ref() {
+                    inherited.absorb(metadata);
+                }
+                inherited.clone()
+            })
+            .collect()
+    }
+
+    /// Returns the diff with hunks in path order, so that equivalent diffs
+    /// render identically regardless of the order the engine emits them in.
+    ///
+    /// Paths compare segment by segment, with keys before indices, keys in
+    /// byte order, and indices numerically; a path sorts before its
+    /// extensions. Hunks are only moved past others under the same metadata,
+    /// and hunks at the same path keep their relative order, so the sorted
+    /// diff patches the same way.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str(r#"{"b":[1,2],"a":1}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"b":[1,3],"a":2}"#).unwrap();
+    /// let sorted = lhs.diff(&rhs, &DiffOptions::default()).sorted();
+    /// let paths: Vec<String> = sorted.iter().map(|element| element.path.to_string()).collect();
+    /// assert_eq!(paths, ["[a]", "[b 1]"]);
+    /// assert_eq!(lhs.apply_patch(&sorted).unwrap(), rhs);
+    /// ```
+    #[must_use]
+    pub fn sorted(&self) -> Self {
+        let mut elements = self.elements.clone();
+        let effective = self.effective_metadata();
+        // Hunks are only reordered within runs under the same metadata. The
+        // hunk that moves to the front of a run carries that metadata.
+        let mut bounds: Vec<usize> =
+            (1..elements.len()).filter(|&index| effective[index] != effective[index - 1]).collect();
+        bounds.insert(0, 0);
+        bounds.push(elements.len());
+        for run in bounds.windows(2) {
+            let (start, end) = (run[0], run[1]);
+            let Some(first) = elements.get(start) else { continue };
+            let announced = first.metadata.is_some();
+            elements[start..end].sort_by(|a, b| a.path.cmp(&b.path));
+            if announced {
+                elements[start].metadata = Some(effective[start].clone());
+            }
+        }
+        Self { elements, truncated: self.truncated }
+    }
+
     /// Consumes the diff and returns the elements.
     ///
     /// ```
@@ -406,16 +713,44 @@ impl Diff {
     #[must_use]
     pub fn render(&self, config: &RenderConfig) -> String {
         let mut output = String::new();
-        let mut inherited = DiffMetadata::default();
-        for element in &self.elements {
-            if let Some(metadata) = element.metadata.as_ref() {
+        self.render_into(config, &mut output);
+        output
+    }
+
+    /// Appends the native rendering of the diff to `output`, so callers
+    /// rendering many diffs can reuse one buffer.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str("{\"a\":1}").expect("valid JSON");
+    /// let mut output = String::from("# changes\n");
+    /// lhs.diff(&Node::from_json_str("{\"a\":2}").unwrap(), &DiffOptions::default())
+    ///     .render_into(&RenderConfig::default(), &mut output);
+    /// assert_eq!(output, "# changes\n@ [\"a\"]\n- 1\n+ 2\n");
+    /// ```
+    pub fn render_into(&self, config: &RenderConfig, output: &mut String) {
+        if config.sorted_hunks {
+            return self.sorted().render_into(&config.with_sorted_hunks(false), output);
+        }
+        let _span = tracing::info_span!("render", format = "jd", hunks = self.len()).entered();
+        let shown = config.max_hunks.unwrap_or(usize::MAX).min(self.elements.len());
+        let progress = progress::Scope::enter();
+        let rendering = progress.meter(Phase::Render, shown);
+        let mut merge = false;
+        for (element, metadata) in self.elements[..shown].iter().zip(self.effective_metadata()) {
+            // One header covers every following hunk, as in Go `jd` v2.
+            if metadata.merge != merge {
                 output.push_str(&metadata.render_header());
-                inherited = metadata.clone();
+                merge = metadata.merge;
             }
-            let is_merge = element.metadata.as_ref().map_or(inherited.merge, |meta| meta.merge);
-            output.push_str(&render_element_native(element, config, is_merge));
+            render_element_native(element, config, metadata.merge, output);
+            rendering.advance();
+        }
+        match self.elements.len() - shown {
+            0 => {}
+            1 => output.push_str("... 1 more hunk omitted\n"),
+            omitted => output.push_str(&format!("... {omitted} more hunks omitted\n")),
         }
-        output
     }
 
     /// Renders the diff as a JSON Patch (RFC 6902).
@@ -429,13 +764,17 @@ impl Diff {
     /// assert!(patch.starts_with("[{\"op\":\"test\""));
     /// ```
     pub fn render_patch(&self) -> Result<String, RenderError> {
+        let _span = tracing::info_span!("render", format = "patch", hunks = self.len()).entered();
         if self.is_empty() {
             return Ok("[]".to_string());
         }
 
         let mut operations = Vec::new();
+        let progress = progress::Scope::enter();
+        let rendering = progress.meter(Phase::Render, self.elements.len());
 
         for element in &self.elements {
+            rendering.advance();
             if element.remove.is_empty() && element.add.is_empty() {
                 return Err(RenderError::new("cannot render empty diff element as JSON Patch op"));
             }
@@ -529,19 +868,17 @@ impl Diff {
     /// assert_eq!(diff.render_merge().unwrap(), "{\"name\":\"jd\"}");
     /// ```
     pub fn render_merge(&self) -> Result<String, RenderError> {
+        let _span = tracing::info_span!("render", format = "merge", hunks = self.len()).entered();
         if self.is_empty() {
             return Ok("{}".to_string());
         }
 
-        let mut inherited = DiffMetadata::default();
+        let progress = progress::Scope::enter();
+        let rendering = progress.meter(Phase::Render, self.elements.len());
         let mut normalized = Vec::with_capacity(self.elements.len());
-
-        for element in &self.elements {
-            if let Some(metadata) = element.metadata.as_ref() {
-                inherited = metadata.clone();
-            }
-            let is_merge = element.metadata.as_ref().map_or(inherited.merge, |meta| meta.merge);
-            if !is_merge {
+        for (element, metadata) in self.elements.iter().zip(self.effective_metadata()) {
+            rendering.advance();
+            if !metadata.merge {
                 return Err(RenderError::new("cannot render non-merge element as merge"));
             }
             let mut clone = element.clone();
@@ -636,6 +973,32 @@ impl Diff {
 
         Ok(Diff::from_elements(reversed))
     }
+
+    /// Returns an equivalent diff with as few hunks and values as possible.
+    ///
+    /// Diffs concatenated from several steps, edited by hand, or read from
+    /// another format often touch the same place twice. Adjacent list hunks
+    /// are merged, a value replaced twice is replaced once, values removed
+    /// and added back unchanged are dropped along with hunks that no longer
+    /// change anything, and list context is trimmed to one value on each side
+    /// of the change. Applying the result gives the same document as applying
+    /// the original. Hunks under merge metadata are kept as they are.
+    ///
+    /// ```
+    /// # use jd_core::{Diff, DiffOptions, Node};
+    /// let a = Node::from_json_str("[1,2,3]").unwrap();
+    /// let b = Node::from_json_str("[1,4,3]").unwrap();
+    /// let c = Node::from_json_str("[1,4,5]").unwrap();
+    /// let opts = DiffOptions::default();
+    /// let steps: Vec<_> = a.diff(&b, &opts).into_iter().chain(b.diff(&c, &opts)).collect();
+    /// let minimized = Diff::from_elements(steps).minimize();
+    /// assert_eq!(minimized.len(), 1);
+    /// assert_eq!(a.apply_patch(&minimized).unwrap(), c);
+    /// ```
+    #[must_use]
+    pub fn minimize(&self) -> Diff {
+        Diff::from_elements(minimize::minimize(&self.elements))
+    }
 }
 
 impl IntoIterator for Diff {
@@ -692,22 +1055,44 @@ fn is_void(node: &Node) -> bool {
     matches!(node, Node::Void)
 }
 
-fn render_element_native(element: &DiffElement, config: &RenderConfig, is_merge: bool) -> String {
-    let mut output = String::new();
+/// Appends the native rendering of one hunk to `output`.
+fn render_element_native(
+    element: &DiffElement,
+    config: &RenderConfig,
+    is_merge: bool,
+    output: &mut String,
+) {
     output.push_str("@ ");
     output.push_str(&path_to_json(&element.path));
     output.push('\n');
 
     struct SingleStringDiff<'a> {
-        common: Vec<char>,
+        common: Vec<strings::Segment<'a>>,
         old: &'a str,
         new: &'a str,
     }
 
-    let string_diff = if element.remove.len() == 1 && element.add.len() == 1 {
+    let (removed, added) = if element.base64 {
+        binary::summaries(&element.remove, &element.add)
+    } else {
+        Default::default()
+    };
+
+    // Only colored output highlights the changed characters.
+    let string_diff = if config.color_enabled()
+        && removed.is_empty()
+        && element.remove.len() == 1
+        && element.add.len() == 1
+    {
         match (&element.remove[0], &element.add[0]) {
-            (Node::String(old), Node::String(new)) => {
-                Some(SingleStringDiff { common: lcs_chars(old, new), old, new })
+            (Node::String(old), Node::String(new))
+                if config.max_value_bytes.is_none_or(|max| old.len().max(new.len()) <= max) =>
+            {
+                strings::common_segments(old, new).map(|common| SingleStringDiff {
+                    common,
+                    old,
+                    new,
+                })
             }
             _ => None,
         }
@@ -719,36 +1104,38 @@ fn render_element_native(element: &DiffElement, config: &RenderConfig, is_merge:
         if is_void(before) {
             output.push_str("[\n");
         } else {
-            output.push_str("  ");
-            output.push_str(&node_to_json(before));
-            output.push('\n');
+            push_native_value(output, "  ", before, config);
         }
     }
 
-    for value in &element.remove {
+    for (index, value) in element.remove.iter().enumerate() {
         if is_void(value) {
             continue;
         }
+        if let Some(summary) = removed.get(index).and_then(Option::as_deref) {
+            push_summary(output, "- ", summary, COLOR_RED, config);
+            continue;
+        }
         if let Some(diff) = &string_diff {
-            if config.color_enabled() {
-                output.push_str("- \"");
-                output.push_str(&color_string_diff(diff.old, &diff.common, COLOR_RED));
-                output.push_str("\"\n");
-                continue;
-            }
+            output.push_str("- \"");
+            output.push_str(&strings::highlight(diff.old, &diff.common, COLOR_RED));
+            output.push_str("\"\n");
+            continue;
         }
         if config.color_enabled() {
             output.push_str(COLOR_RED);
         }
-        output.push_str("- ");
-        output.push_str(&node_to_json(value));
-        output.push('\n');
+        push_native_value(output, "- ", value, config);
         if config.color_enabled() {
             output.push_str(COLOR_RESET);
         }
     }
 
-    for value in &element.add {
+    for (index, value) in element.add.iter().enumerate() {
+        if let Some(summary) = added.get(index).and_then(Option::as_deref) {
+            push_summary(output, "+ ", summary, COLOR_GREEN, config);
+            continue;
+        }
         if is_void(value) {
             if is_merge {
                 if config.color_enabled() {
@@ -762,19 +1149,15 @@ fn render_element_native(element: &DiffElement, config: &RenderConfig, is_merge:
             continue;
         }
         if let Some(diff) = &string_diff {
-            if config.color_enabled() {
-                output.push_str("+ \"");
-                output.push_str(&color_string_diff(diff.new, &diff.common, COLOR_GREEN));
-                output.push_str("\"\n");
-                continue;
-            }
+            output.push_str("+ \"");
+            output.push_str(&strings::highlight(diff.new, &diff.common, COLOR_GREEN));
+            output.push_str("\"\n");
+            continue;
         }
         if config.color_enabled() {
             output.push_str(COLOR_GREEN);
         }
-        output.push_str("+ ");
-        output.push_str(&node_to_json(value));
-        output.push('\n');
+        push_native_value(output, "+ ", value, config);
         if config.color_enabled() {
             output.push_str(COLOR_RESET);
         }
@@ -784,13 +1167,57 @@ fn render_element_native(element: &DiffElement, config: &RenderConfig, is_merge:
         if is_void(after) {
             output.push_str("]\n");
         } else {
-            output.push_str("  ");
-            output.push_str(&node_to_json(after));
-            output.push('\n');
+            push_native_value(output, "  ", after, config);
         }
     }
+}
 
-    output
+/// Writes `node` as one or more native lines, each starting with `prefix`.
+fn push_native_value(output: &mut String, prefix: &str, node: &Node, config: &RenderConfig) {
+    for line in native_value(node, config).split('\n') {
+        output.push_str(prefix);
+        output.push_str(line);
+        output.push('\n');
+    }
+}
+
+/// Writes a line that stands in for a value, in `color` when enabled.
+fn push_summary(
+    output: &mut String,
+    prefix: &str,
+    summary: &str,
+    color: &str,
+    config: &RenderConfig,
+) {
+    if config.color_enabled() {
+        output.push_str(color);
+    }
+    output.push_str(prefix);
+    output.push_str(summary);
+    output.push('\n');
+    if config.color_enabled() {
+        output.push_str(COLOR_RESET);
+    }
+}
+
+/// Renders `node` for native output, eliding bytes beyond the configured limit.
+fn native_value(node: &Node, config: &RenderConfig) -> String {
+    let mut text = match node {
+        Node::Array(_) | Node::Object(_) if config.indent > 0 => {
+            node_to_pretty_json(node, config.indent)
+        }
+        _ => node_to_json(node),
+    };
+    if let Some(max) = config.max_value_bytes.filter(|max| text.len() > *max) {
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let elided = text.len() - end;
+        text.truncate(end);
+        text.push_str(&format!("... ({elided} more bytes)"));
+    }
+    text
 }
 
 fn node_to_json(node: &Node) -> String {
@@ -804,6 +1231,16 @@ fn node_to_json(node: &Node) -> String {
     }
 }
 
+fn node_to_pretty_json(node: &Node, width: usize) -> String {
+    let value = node_to_json_value(node).expect("serializing node");
+    let indent = " ".repeat(width);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer).expect("serializing node");
+    String::from_utf8(out).expect("serde_json writes UTF-8")
+}
+
 fn node_to_json_value(node: &Node) -> Result<JsonValue, RenderError> {
     match node {
         Node::Void => Err(RenderError::new("cannot encode void value in JSON Patch")),
@@ -823,6 +1260,7 @@ fn path_to_json(path: &Path) -> String {
                 let number = json_number_from_f64(*index as f64);
                 values.push(JsonValue::Number(number));
             }
+            marker => values.push(marker.marker_json().unwrap_or_default()),
         }
     }
     serde_json::to_string(&JsonValue::Array(values)).expect("serialize path")
@@ -833,13 +1271,15 @@ fn path_to_pointer(path: &Path) -> Result<String, RenderError> {
     for segment in path.segments() {
         pointer.push('/');
         match segment {
-            PathSegment::Index(index) => {
-                if *index == -1 {
-                    pointer.push('-');
-                } else {
-                    pointer.push_str(&index.to_string());
+            PathSegment::Index(index) => match *index {
+                -1 => pointer.push('-'),
+                index if index < 0 => {
+                    return Err(RenderError::new(format!(
+                        "JSON Pointer cannot address negative index {index}"
+                    )));
                 }
-            }
+                index => pointer.push_str(&index.to_string()),
+            },
             PathSegment::Key(key) => {
                 if key.parse::<i64>().is_ok() {
                     return Err(RenderError::new(format!(
@@ -851,6 +1291,11 @@ fn path_to_pointer(path: &Path) -> Result<String, RenderError> {
                 }
                 pointer.push_str(&escape_pointer_segment(key));
             }
+            marker => {
+                return Err(RenderError::new(format!(
+                    "JSON Pointer cannot address set or multiset path element {marker}"
+                )));
+            }
         }
     }
     Ok(pointer)
@@ -864,90 +1309,151 @@ fn json_number_from_f64(value: f64) -> JsonNumber {
     Number::new(value).expect("finite number").to_json_number()
 }
 
-fn color_string_diff(text: &str, common: &[char], color: &str) -> String {
-    let mut result = String::new();
-    let mut common_iter = common.iter();
-    let mut current = common_iter.next();
-    for ch in text.chars() {
-        if let Some(expected) = current {
-            if ch == *expected {
-                result.push(ch);
-                current = common_iter.next();
-                continue;
-            }
-        }
-        result.push_str(color);
-        result.push(ch);
-        result.push_str(COLOR_RESET);
-    }
-    result
+/// Computes the structural diff between two nodes.
+#[must_use]
+pub fn diff_nodes(lhs: &Node, rhs: &Node, options: &DiffOptions) -> Diff {
+    diff_nodes_until(lhs, rhs, options, &Deadline::unbounded())
 }
 
-fn lcs_chars(lhs: &str, rhs: &str) -> Vec<char> {
-    let left: Vec<char> = lhs.chars().collect();
-    let right: Vec<char> = rhs.chars().collect();
-    let n = left.len();
-    let m = right.len();
-    let mut table = vec![vec![0usize; m + 1]; n + 1];
-    for (i, lhs_char) in left.iter().enumerate() {
-        for (j, rhs_char) in right.iter().enumerate() {
-            if lhs_char == rhs_char {
-                table[i + 1][j + 1] = table[i][j] + 1;
-            } else {
-                table[i + 1][j + 1] = table[i][j + 1].max(table[i + 1][j]);
-            }
-        }
-    }
-
-    let mut result = Vec::with_capacity(table[n][m]);
-    let mut i = n;
-    let mut j = m;
-    while i > 0 && j > 0 {
-        if left[i - 1] == right[j - 1] {
-            result.push(left[i - 1]);
-            i -= 1;
-            j -= 1;
-        } else if table[i - 1][j] >= table[i][j - 1] {
-            i -= 1;
-        } else {
-            j -= 1;
+/// Computes the structural diff, giving up with a partial result once
+/// `deadline` expires.
+pub(crate) fn diff_nodes_until(
+    lhs: &Node,
+    rhs: &Node,
+    options: &DiffOptions,
+    deadline: &Deadline,
+) -> Diff {
+    let _span = tracing::info_span!("diff").entered();
+    deadline.limit_hunks(options.max_hunks());
+    let mut diff = match DigestMemo::for_diff(options) {
+        Some(memo) => {
+            let options = options.clone().with_memo(Arc::new(memo));
+            diff_impl(lhs, rhs, &Trail::Root, &options, deadline)
         }
+        None => diff_impl(lhs, rhs, &Trail::Root, options, deadline),
+    };
+    if let Some(max) = options.max_hunks().filter(|_| deadline.hunks_exhausted()) {
+        // Hunks come out in the order they are completed, so the first
+        // `max` were finished before the diff was cut short.
+        diff.elements.truncate(max);
+        diff.truncated = true;
     }
-    result.reverse();
-    result
+    binary::mark(&mut diff, options);
+    diff
 }
 
-/// Computes the structural diff between two nodes.
-#[must_use]
-pub fn diff_nodes(lhs: &Node, rhs: &Node, options: &DiffOptions) -> Diff {
-    diff_impl(lhs, rhs, &Path::new(), options)
-}
-
-pub(super) fn diff_impl(lhs: &Node, rhs: &Node, path: &Path, options: &DiffOptions) -> Diff {
-    if lhs.eq_with_options(rhs, options) {
+fn diff_impl(
+    lhs: &Node,
+    rhs: &Node,
+    path: &Trail<'_>,
+    options: &DiffOptions,
+    deadline: &Deadline,
+) -> Diff {
+    // A comparator judges the values as a whole, so unequal ones are
+    // replaced whole.
+    let custom = options.custom_equality(lhs, rhs);
+    if deadline.check()
+        || custom.unwrap_or_else(|| {
+            !digests_differ(lhs, rhs, options) && lhs.eq_with_options(rhs, options)
+        })
+    {
         return Diff::empty();
     }
 
     match (lhs, rhs) {
-        (Node::Object(left), Node::Object(right)) => {
-            object::diff_objects(left, right, path, options)
+        (Node::Object(left), Node::Object(right)) if custom.is_none() => {
+            object::diff_objects(left, right, path, options, deadline)
+        }
+        // Merge patches cannot edit arrays or change types in place; the
+        // new value replaces the old one.
+        _ if options.merge() => {
+            deadline.found(1);
+            Diff::from_elements(vec![read::merge_element(path.to_path(), rhs.clone())])
         }
-        (Node::Array(left), Node::Array(right)) => match options.array_mode() {
-            ArrayMode::List => list::diff_lists(left, right, path, options),
+        (Node::Array(left), Node::Array(right)) if options.diff_off() => {
+            // Elements are only compared where diffing is turned back on, so
+            // they are matched by position.
+            let elements = left.iter().zip(right).enumerate().flat_map(|(index, (a, b))| {
+                let index = index as i64;
+                diff_impl(a, b, &path.index(index), &options.refine_index(index), deadline)
+            });
+            Diff::from_elements(elements.collect())
+        }
+        (Node::Array(left), Node::Array(right)) if custom.is_none() => match options.array_mode() {
+            ArrayMode::List => list::diff_lists(left, right, path, options, deadline),
             mode => {
                 panic!("array mode {mode:?} not implemented in diff engine");
             }
         },
-        _ => primitives::diff_primitives(lhs, rhs, path),
+        _ => {
+            deadline.found(1);
+            primitives::diff_primitives(lhs, rhs, path)
+        }
     }
 }
 
+/// Whether the memoized digests of `lhs` and `rhs` prove them unequal, which
+/// spares walking both subtrees before recursing into them. Equal digests
+/// prove nothing, since distinct values can collide, and without a memo
+/// hashing would cost as much as the walk.
+fn digests_differ(lhs: &Node, rhs: &Node, options: &DiffOptions) -> bool {
+    (options.memo().is_some() || options.digests().is_some())
+        && options.digests_decide_inequality()
+        && lhs.digest(options) != rhs.digest(options)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::DiffOptions;
     use proptest::prelude::*;
 
+    #[test]
+    fn value_truncation_respects_char_boundaries_and_skips_string_coloring() {
+        let lhs = Node::from_json_str("[\"ééé\"]").unwrap();
+        let rhs = Node::from_json_str("[\"ééx\"]").unwrap();
+        let diff = diff_nodes(&lhs, &rhs, &DiffOptions::default());
+        let config = RenderConfig::color(true).with_max_value_bytes(4);
+        assert_eq!(
+            diff.render(&config),
+            "@ [0]\n[\n\u{1b}[31m- \"é... (5 more bytes)\n\u{1b}[0m\u{1b}[32m+ \"é... (4 more bytes)\n\u{1b}[0m]\n"
+        );
+    }
+
+    #[test]
+    fn sorting_keeps_hunks_under_their_metadata() {
+        let hunk = |key: &str| {
+            DiffElement::new().with_path(PathSegment::key(key)).with_add(vec![Node::Null])
+        };
+        let diff = Diff::from_elements(vec![
+            hunk("b"),
+            hunk("a"),
+            hunk("z").with_metadata(DiffMetadata::merge()),
+            hunk("c"),
+        ]);
+        let sorted = diff.sorted();
+        let paths: Vec<String> = sorted.iter().map(|element| element.path.to_string()).collect();
+        assert_eq!(paths, ["[a]", "[b]", "[c]", "[z]"]);
+        assert_eq!(sorted.effective_metadata(), diff.effective_metadata());
+        // The hunk moved to the front of the merge run announces it; the
+        // moved hunk keeps its own metadata.
+        assert_eq!(sorted.elements[2].metadata, Some(DiffMetadata::merge()));
+        assert_eq!(sorted.elements[3].metadata, Some(DiffMetadata::merge()));
+    }
+
+    #[test]
+    fn hunk_limit_counts_all_omitted_hunks() {
+        let lhs = Node::from_json_str("{\"a\":1,\"b\":1,\"c\":1}").unwrap();
+        let rhs = Node::from_json_str("{}").unwrap();
+        let diff = diff_nodes(&lhs, &rhs, &DiffOptions::default());
+        let rendered = diff.render(&RenderConfig::new().with_max_hunks(0));
+        assert_eq!(rendered, "... 3 more hunks omitted\n");
+        assert_eq!(
+            diff.render(&RenderConfig::new().with_max_hunks(3)),
+            diff.render(&RenderConfig::new())
+        );
+    }
+
     #[test]
     fn diff_of_numbers_produces_replacement_hunk() {
         let lhs = Node::from_json_str("1").unwrap();
@@ -1015,6 +1521,92 @@ mod tests {
         assert_eq!(diff, expected);
     }
 
+    #[test]
+    fn diff_with_prune_empty_skips_empty_containers() {
+        let lhs = Node::from_json_str("{\"a\":[],\"b\":{\"c\":{}},\"d\":1}").unwrap();
+        let rhs = Node::from_json_str("{\"d\":2,\"e\":{}}").unwrap();
+        let options = DiffOptions::default().with_prune_empty(true);
+        let diff = diff_nodes(&lhs, &rhs, &options);
+        let expected = Diff::from_elements(vec![DiffElement::new()
+            .with_path(PathSegment::key("d"))
+            .with_remove(vec![Node::from_json_str("1").unwrap()])
+            .with_add(vec![Node::from_json_str("2").unwrap()])]);
+        assert_eq!(diff, expected);
+        assert_eq!(lhs.apply_patch(&diff).unwrap().prune_empty(), rhs.prune_empty());
+    }
+
+    #[test]
+    fn patience_alignment_anchors_on_unique_elements() {
+        let lhs = crate::node!([{}, {"id": 1}, {}, {"id": 2}, {}, {"id": 3}]);
+        let rhs = crate::node!([{"id": 1}, {}, {"id": 3}, {}, {"id": 2}, {}]);
+        let render = |options: &DiffOptions| {
+            let diff = diff_nodes(&lhs, &rhs, options);
+            assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+            diff.render(&RenderConfig::default())
+        };
+        // The LCS aligns the empty objects and rewrites an id in place.
+        assert!(render(&DiffOptions::default()).contains("@ [2,\"id\"]\n- 1\n+ 3\n"));
+        let patience = DiffOptions::default().with_list_alignment(crate::ListAlignment::Patience);
+        assert_eq!(
+            render(&patience),
+            "@ [0]\n[\n- {}\n  {\"id\":1}\n\
+             @ [2]\n  {}\n- {\"id\":2}\n- {}\n  {\"id\":3}\n\
+             @ [3]\n  {\"id\":3}\n+ {}\n+ {\"id\":2}\n+ {}\n]\n"
+        );
+    }
+
+    #[test]
+    fn set_keys_anchor_list_alignment() {
+        let lhs = crate::node!([{"id": 1, "v": 1}, {"id": 2, "v": 2}, {"id": 3, "v": 3}, 4]);
+        let rhs = crate::node!([{"id": 2, "v": 5}, {"id": 3, "v": 3}, {"v": 1}, 4]);
+        let anchored = DiffOptions::default()
+            .with_set_keys(["id"])
+            .and_then(|options| options.with_array_mode(ArrayMode::List))
+            .unwrap();
+        let diff = diff_nodes(&lhs, &rhs, &anchored);
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            "@ [0]\n[\n- {\"id\":1,\"v\":1}\n  {\"id\":2,\"v\":2}\n\
+             @ [0,\"v\"]\n- 2\n+ 5\n\
+             @ [2]\n  {\"id\":3,\"v\":3}\n+ {\"v\":1}\n  4\n"
+        );
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+        // Without anchors the changed element is replaced whole.
+        let plain =
+            diff_nodes(&lhs, &rhs, &DiffOptions::default()).render(&RenderConfig::default());
+        assert!(plain.contains("- {\"id\":2,\"v\":2}\n"));
+    }
+
+    #[test]
+    fn string_scalars_align_with_the_values_they_spell() {
+        let lhs = crate::node!(["42", 1, "true"]);
+        let rhs = crate::node!([42, 2, true]);
+        let options = DiffOptions::default().with_string_scalars(true);
+        let diff = diff_nodes(&lhs, &rhs, &options);
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            "@ [1]\n  \"42\"\n- 1\n+ 2\n  \"true\"\n"
+        );
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), crate::node!(["42", 2, "true"]));
+        let loose = options.with_precision(0.1).unwrap();
+        assert_eq!(diff_nodes(&lhs, &rhs, &loose), diff);
+    }
+
+    #[test]
+    fn digests_only_skip_comparisons_under_exact_options() {
+        let lhs = crate::node!({"a": [{"x": -0.0, "y": "1"}], "b": 1.0});
+        let rhs = crate::node!({"a": [{"x": 0, "y": 1}], "b": 1.05});
+        let render = |options: &DiffOptions| {
+            diff_nodes(&lhs, &rhs, options).render(&RenderConfig::default())
+        };
+        assert_eq!(
+            render(&DiffOptions::default()),
+            "@ [\"a\",0,\"y\"]\n- \"1\"\n+ 1\n@ [\"b\"]\n- 1\n+ 1.05\n"
+        );
+        let loose = DiffOptions::default().with_string_scalars(true).with_precision(0.1).unwrap();
+        assert_eq!(render(&loose), "");
+    }
+
     fn arb_json_value() -> impl Strategy<Value = serde_json::Value> {
         use proptest::{collection::btree_map, collection::vec, string::string_regex};
 
@@ -1045,6 +1637,101 @@ mod tests {
     }
 
     proptest! {
+        #[test]
+        fn computed_diffs_validate(a in arb_json_value(), b in arb_json_value()) {
+            let (a, b) = (Node::from_json_value(a).unwrap(), Node::from_json_value(b).unwrap());
+            prop_assert_eq!(diff_nodes(&a, &b, &DiffOptions::default()).validate(), Ok(()));
+            let merge = DiffOptions::default().with_merge(true);
+            prop_assert_eq!(diff_nodes(&a, &b, &merge).validate(), Ok(()));
+        }
+
+        #[test]
+        fn patience_diffs_apply(
+            lhs in proptest::collection::vec(0u8..4, 0..12),
+            rhs in proptest::collection::vec(0u8..4, 0..12),
+        ) {
+            let lhs = Node::from_json_str(&format!("{lhs:?}")).unwrap();
+            let rhs = Node::from_json_str(&format!("{rhs:?}")).unwrap();
+            let options = DiffOptions::default().with_list_alignment(crate::ListAlignment::Patience);
+            let diff = diff_nodes(&lhs, &rhs, &options);
+            prop_assert_eq!(diff.validate(), Ok(()));
+            prop_assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+        }
+
+        #[test]
+        fn anchored_diffs_apply(
+            lhs in proptest::collection::vec((0u8..4, 0u8..3), 0..8),
+            rhs in proptest::collection::vec((0u8..4, 0u8..3), 0..8),
+            patience in any::<bool>(),
+        ) {
+            let objects = |pairs: Vec<(u8, u8)>| {
+                let items: Vec<String> =
+                    pairs.iter().map(|(id, v)| format!(r#"{{"id":{id},"v":{v}}}"#)).collect();
+                Node::from_json_str(&format!("[{}]", items.join(","))).unwrap()
+            };
+            let (lhs, rhs) = (objects(lhs), objects(rhs));
+            let alignment =
+                if patience { crate::ListAlignment::Patience } else { crate::ListAlignment::Lcs };
+            let options = DiffOptions::default()
+                .with_set_keys(["id"])
+                .and_then(|options| options.with_array_mode(ArrayMode::List))
+                .unwrap()
+                .with_list_alignment(alignment);
+            let diff = diff_nodes(&lhs, &rhs, &options);
+            prop_assert_eq!(diff.validate(), Ok(()));
+            prop_assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+        }
+
+        #[test]
+        fn similarity_paired_diffs_apply(
+            a in arb_json_value(),
+            b in arb_json_value(),
+            threshold in 0.05f64..=1.0,
+        ) {
+            let (a, b) = (Node::from_json_value(a).unwrap(), Node::from_json_value(b).unwrap());
+            let options = DiffOptions::default().with_similarity_threshold(threshold).unwrap();
+            for (lhs, rhs) in [(&a, &b), (&Node::Array(vec![a.clone(), b.clone()]), &Node::Array(vec![b.clone(), a.clone()]))] {
+                let diff = diff_nodes(lhs, rhs, &options);
+                prop_assert_eq!(diff.validate(), Ok(()));
+                prop_assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs.clone());
+            }
+        }
+
+        #[test]
+        fn bounded_diffs_are_prefixes(
+            a in arb_json_value(),
+            b in arb_json_value(),
+            max in 1usize..4,
+            merge in any::<bool>(),
+        ) {
+            let (a, b) = (Node::from_json_value(a).unwrap(), Node::from_json_value(b).unwrap());
+            let options = DiffOptions::default().with_merge(merge);
+            let full = diff_nodes(&a, &b, &options);
+            let bounded = diff_nodes(&a, &b, &options.with_max_hunks(max).unwrap());
+            prop_assert_eq!(bounded.is_truncated(), full.len() >= max);
+            prop_assert_eq!(bounded.iter().as_slice(), &full.iter().as_slice()[..full.len().min(max)]);
+            prop_assert!(a.apply_patch(&bounded).is_ok());
+        }
+
+        #[test]
+        fn equal_agrees_with_empty_diffs(
+            a in arb_json_value(),
+            b in arb_json_value(),
+            precision in prop_oneof![Just(0.0), 0.0f64..10.0],
+            merge in any::<bool>(),
+            prune in any::<bool>(),
+        ) {
+            let (a, b) = (Node::from_json_value(a).unwrap(), Node::from_json_value(b).unwrap());
+            let options = DiffOptions::default()
+                .with_precision(precision)
+                .unwrap()
+                .with_merge(merge)
+                .with_prune_empty(prune);
+            for (lhs, rhs) in [(&a, &b), (&a, &a.clone())] {
+                prop_assert_eq!(crate::equal(lhs, rhs, &options), diff_nodes(lhs, rhs, &options).is_empty());
+            }
+        }
+
         #[test]
         fn identical_nodes_produce_empty_diff(json in arb_json_value()) {
             let node = Node::from_json_value(json.clone()).unwrap();
@@ -1052,5 +1739,64 @@ mod tests {
             let diff = diff_nodes(&node, &other, &DiffOptions::default());
             prop_assert!(diff.is_empty());
         }
+
+        #[test]
+        fn minimize_preserves_composed_list_edits(
+            lists in proptest::collection::vec(proptest::collection::vec(0u8..4, 0..8), 2..5)
+        ) {
+            let nodes: Vec<Node> = lists
+                .iter()
+                .map(|list| Node::from_json_str(&format!("{list:?}")).unwrap())
+                .collect();
+            let composed: Diff = nodes
+                .windows(2)
+                .flat_map(|pair| diff_nodes(&pair[0], &pair[1], &DiffOptions::default()))
+                .collect::<Vec<_>>()
+                .into();
+            let minimized = composed.minimize();
+            prop_assert!(minimized.len() <= composed.len());
+            prop_assert_eq!(nodes[0].apply_patch(&minimized).unwrap(), nodes.last().unwrap().clone());
+            prop_assert_eq!(minimized.minimize(), minimized);
+        }
+
+        #[test]
+        fn minimize_preserves_composed_edits(
+            a in arb_json_value(),
+            b in arb_json_value(),
+            c in arb_json_value(),
+        ) {
+            let nodes = [a, b, c].map(|json| Node::from_json_value(json).unwrap());
+            let mut elements = diff_nodes(&nodes[0], &nodes[1], &DiffOptions::default()).into_elements();
+            elements.extend(diff_nodes(&nodes[1], &nodes[2], &DiffOptions::default()));
+            let minimized = Diff::from_elements(elements).minimize();
+            prop_assert_eq!(nodes[0].apply_patch(&minimized).unwrap(), nodes[2].clone());
+            prop_assert_eq!(minimized.minimize(), minimized);
+            prop_assert!(diff_nodes(&nodes[0], &nodes[0], &DiffOptions::default()).minimize().is_empty());
+        }
+
+        #[test]
+        fn merge_diffs_apply_and_render(lhs in arb_json_value(), rhs in arb_json_value()) {
+            let lhs = Node::from_json_value(lhs).unwrap();
+            let rhs = Node::from_json_value(rhs).unwrap();
+            let diff = diff_nodes(&lhs, &rhs, &DiffOptions::default().with_merge(true));
+            prop_assert!(diff.iter().all(|element| element.metadata == Some(DiffMetadata::merge())));
+            prop_assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+            prop_assert!(diff.render_merge().is_ok());
+        }
+
+        #[test]
+        fn sorted_diffs_apply_and_are_ordered(
+            lhs in arb_json_value(),
+            rhs in arb_json_value(),
+            merge in any::<bool>(),
+        ) {
+            let lhs = Node::from_json_value(lhs).unwrap();
+            let rhs = Node::from_json_value(rhs).unwrap();
+            let sorted = diff_nodes(&lhs, &rhs, &DiffOptions::default().with_merge(merge)).sorted();
+            let paths: Vec<&Path> = sorted.iter().map(|element| &element.path).collect();
+            prop_assert!(paths.windows(2).all(|pair| pair[0] <= pair[1]));
+            prop_assert_eq!(lhs.apply_patch(&sorted).unwrap(), rhs);
+            prop_assert_eq!(sorted.sorted(), sorted);
+        }
     }
 }
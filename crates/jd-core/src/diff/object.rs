@@ -1,42 +1,61 @@
 use std::collections::BTreeMap;
 
-use super::{diff_impl, Diff, DiffElement, Path, PathSegment};
+use super::read::merge_element;
+use super::{diff_impl, Diff, DiffElement, Trail};
+use crate::limits::Deadline;
 use crate::{DiffOptions, Node};
 
 pub(super) fn diff_objects(
     lhs: &BTreeMap<String, Node>,
     rhs: &BTreeMap<String, Node>,
-    path: &Path,
+    path: &Trail<'_>,
     options: &DiffOptions,
+    deadline: &Deadline,
 ) -> Diff {
     let mut elements = Vec::new();
 
-    let mut lhs_keys: Vec<_> = lhs.keys().cloned().collect();
-    lhs_keys.sort();
-    for key in lhs_keys {
-        let value = &lhs[&key];
-        if let Some(other) = rhs.get(&key) {
-            let sub_path = path.clone().with_segment(PathSegment::key(key));
-            let diff = diff_impl(value, other, &sub_path, options);
+    for (key, value) in lhs {
+        if deadline.check() {
+            break;
+        }
+        if let Some(other) = rhs.get(key) {
+            let sub_options = options.refine_key(key);
+            let diff = diff_impl(value, other, &path.key(key), &sub_options, deadline);
             elements.extend(diff.into_iter());
+        } else if options.refine_key(key).diff_off()
+            || (options.prune_empty() && value.is_prunable())
+        {
+            continue;
+        } else if options.merge() {
+            elements.push(merge_element(path.key(key).to_path(), Node::Void));
+            deadline.found(1);
         } else {
             let element = DiffElement::new()
-                .with_path(path.clone().with_segment(PathSegment::key(key)))
+                .with_path(path.key(key).to_path())
                 .with_remove(vec![value.clone()]);
             elements.push(element);
+            deadline.found(1);
         }
     }
 
-    let mut rhs_keys: Vec<_> = rhs.keys().cloned().collect();
-    rhs_keys.sort();
-    for key in rhs_keys {
-        if lhs.contains_key(&key) {
+    for (key, value) in rhs {
+        if deadline.check() {
+            break;
+        }
+        if lhs.contains_key(key)
+            || options.refine_key(key).diff_off()
+            || (options.prune_empty() && value.is_prunable())
+        {
             continue;
         }
-        let element = DiffElement::new()
-            .with_path(path.clone().with_segment(PathSegment::key(key.clone())))
-            .with_add(vec![rhs[&key].clone()]);
-        elements.push(element);
+        let sub_path = path.key(key).to_path();
+        let value = value.clone();
+        elements.push(if options.merge() {
+            merge_element(sub_path, value)
+        } else {
+            DiffElement::new().with_path(sub_path).with_add(vec![value])
+        });
+        deadline.found(1);
     }
 
     Diff::from_elements(elements)
@@ -1,25 +1,48 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value as JsonValue;
+
+use crate::Node;
 
 /// Represents a single element within a diff path.
 ///
-/// A segment can either refer to an object key or an array index. Future
-/// milestones will add set and multiset markers.
+/// A segment either refers to an object key or an array index, or, like
+/// Go `jd` v2, marks an array that is compared as a set or multiset. The
+/// markers serialize as `{}` (set), `[]` (multiset), `{"id":1}` (the set
+/// element whose set keys have these values), and `[{"id":1}]` (the same for
+/// a multiset).
 ///
 /// ```
-/// # use jd_core::diff::PathSegment;
+/// # use std::collections::BTreeMap;
+/// # use jd_core::{diff::PathSegment, Node};
 /// let key = PathSegment::key("name");
 /// let index = PathSegment::index(2);
 /// assert!(matches!(key, PathSegment::Key(_)));
 /// assert!(matches!(index, PathSegment::Index(_)));
+///
+/// let element = PathSegment::SetKeys(BTreeMap::from([("id".to_string(), Node::from(1))]));
+/// assert_eq!(serde_json::to_string(&element).unwrap(), r#"{"id":1}"#);
+/// let parsed: Vec<PathSegment> = serde_json::from_str(r#"["tags",{},[{"id":1}]]"#).unwrap();
+/// assert_eq!(parsed[1], PathSegment::Set);
+/// assert_eq!(parsed[2].to_string(), r#"[{"id":1}]"#);
 /// ```
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PathSegment {
     /// Object key lookup.
     Key(String),
     /// Array index lookup.
     Index(i64),
+    /// An array compared as a set (`{}`).
+    Set,
+    /// An array compared as a multiset (`[]`).
+    MultiSet,
+    /// The set element whose set keys have these values (`{"id":1}`).
+    SetKeys(BTreeMap<String, Node>),
+    /// The multiset element whose set keys have these values (`[{"id":1}]`).
+    MultiSetKeys(BTreeMap<String, Node>),
 }
 
 impl PathSegment {
@@ -40,6 +63,37 @@ impl PathSegment {
     {
         Self::Index(value.into())
     }
+
+    /// Indicates whether the segment is one of the set or multiset markers.
+    ///
+    /// ```
+    /// # use jd_core::diff::PathSegment;
+    /// assert!(PathSegment::MultiSet.is_set_marker());
+    /// assert!(!PathSegment::index(0).is_set_marker());
+    /// ```
+    #[must_use]
+    pub fn is_set_marker(&self) -> bool {
+        !matches!(self, Self::Key(_) | Self::Index(_))
+    }
+
+    /// The JSON form of a set or multiset marker. Void set-key values stand
+    /// for absent keys and are left out.
+    pub(crate) fn marker_json(&self) -> Option<JsonValue> {
+        let keys = |keys: &BTreeMap<String, Node>| {
+            JsonValue::Object(
+                keys.iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.to_json_value()?)))
+                    .collect(),
+            )
+        };
+        match self {
+            Self::Key(_) | Self::Index(_) => None,
+            Self::Set => Some(JsonValue::Object(serde_json::Map::new())),
+            Self::MultiSet => Some(JsonValue::Array(Vec::new())),
+            Self::SetKeys(values) => Some(keys(values)),
+            Self::MultiSetKeys(values) => Some(JsonValue::Array(vec![keys(values)])),
+        }
+    }
 }
 
 impl fmt::Display for PathSegment {
@@ -47,6 +101,7 @@ impl fmt::Display for PathSegment {
         match self {
             Self::Key(key) => f.write_str(key),
             Self::Index(index) => write!(f, "{index}"),
+            marker => write!(f, "{}", marker.marker_json().unwrap_or_default()),
         }
     }
 }
@@ -59,6 +114,7 @@ impl Serialize for PathSegment {
         match self {
             Self::Key(key) => serializer.serialize_str(key),
             Self::Index(index) => serializer.serialize_i64(*index),
+            marker => marker.marker_json().unwrap_or_default().serialize(serializer),
         }
     }
 }
@@ -70,11 +126,19 @@ impl<'de> Deserialize<'de> for PathSegment {
     {
         struct Visitor;
 
+        fn set_keys<E: serde::de::Error>(
+            keys: serde_json::Map<String, JsonValue>,
+        ) -> Result<BTreeMap<String, Node>, E> {
+            keys.into_iter()
+                .map(|(key, value)| Ok((key, Node::from_json_value(value).map_err(E::custom)?)))
+                .collect()
+        }
+
         impl<'de> serde::de::Visitor<'de> for Visitor {
             type Value = PathSegment;
 
             fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.write_str("a string key or integer index")
+                f.write_str("a string key, an integer index, or a set marker")
             }
 
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -105,6 +169,34 @@ impl<'de> Deserialize<'de> for PathSegment {
                 let value = i64::try_from(v).map_err(|_| E::custom("index exceeds i64"))?;
                 Ok(PathSegment::Index(value))
             }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let keys = serde_json::Map::deserialize(MapAccessDeserializer::new(map))?;
+                if keys.is_empty() {
+                    return Ok(PathSegment::Set);
+                }
+                set_keys(keys).map(PathSegment::SetKeys)
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let items = Vec::<JsonValue>::deserialize(SeqAccessDeserializer::new(seq))?;
+                let mut items = items.into_iter();
+                match (items.next(), items.next()) {
+                    (None, _) => Ok(PathSegment::MultiSet),
+                    (Some(JsonValue::Object(keys)), None) => {
+                        set_keys(keys).map(PathSegment::MultiSetKeys)
+                    }
+                    _ => Err(serde::de::Error::custom(
+                        "expected [] or a single object of set keys in a path array",
+                    )),
+                }
+            }
         }
 
         deserializer.deserialize_any(Visitor)
@@ -119,7 +211,7 @@ impl<'de> Deserialize<'de> for PathSegment {
 ///     .with_segment(PathSegment::index(0));
 /// assert_eq!(path.len(), 2);
 /// ```
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Path(Vec<PathSegment>);
 
@@ -277,6 +369,57 @@ where
     Path(segments.into_iter().collect())
 }
 
+/// The path to the value being diffed, as a chain of segments borrowed
+/// from the frames of the values above it. Descending into a child builds
+/// its trail on the stack and borrows keys from the document, so the diff
+/// only allocates a [`Path`] for the hunks it emits.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Trail<'a> {
+    Root,
+    Key(&'a Trail<'a>, &'a str),
+    Index(&'a Trail<'a>, i64),
+}
+
+impl<'a> Trail<'a> {
+    pub(super) fn key(&'a self, key: &'a str) -> Self {
+        Self::Key(self, key)
+    }
+
+    pub(super) fn index(&'a self, index: i64) -> Self {
+        Self::Index(self, index)
+    }
+
+    pub(super) fn len(&self) -> usize {
+        let mut len = 0;
+        let mut trail = self;
+        while let Self::Key(parent, _) | Self::Index(parent, _) = trail {
+            len += 1;
+            trail = parent;
+        }
+        len
+    }
+
+    pub(super) fn to_path(self) -> Path {
+        let mut segments = Vec::with_capacity(self.len());
+        let mut trail = &self;
+        loop {
+            trail = match trail {
+                Self::Root => break,
+                Self::Key(parent, key) => {
+                    segments.push(PathSegment::key(*key));
+                    parent
+                }
+                Self::Index(parent, index) => {
+                    segments.push(PathSegment::Index(*index));
+                    parent
+                }
+            };
+        }
+        segments.reverse();
+        Path(segments)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -289,4 +432,40 @@ mod tests {
         let decoded: Path = serde_json::from_str(&json).unwrap();
         assert_eq!(decoded, path);
     }
+
+    #[test]
+    fn serde_round_trip_for_set_markers() {
+        let keys =
+            BTreeMap::from([("id".to_string(), Node::from("a")), ("n".to_string(), Node::Null)]);
+        let path = path_from_segments([
+            PathSegment::Set,
+            PathSegment::MultiSet,
+            PathSegment::SetKeys(keys.clone()),
+            PathSegment::MultiSetKeys(keys),
+        ]);
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#"[{},[],{"id":"a","n":null},[{"id":"a","n":null}]]"#);
+        assert_eq!(serde_json::from_str::<Path>(&json).unwrap(), path);
+        assert_eq!(path.to_string(), r#"[{} [] {"id":"a","n":null} [{"id":"a","n":null}]]"#);
+        assert!(serde_json::from_str::<PathSegment>("[1]").is_err());
+        assert!(serde_json::from_str::<PathSegment>("[{},{}]").is_err());
+    }
+
+    #[test]
+    fn trails_build_the_paths_they_spell() {
+        let root = Trail::Root;
+        assert!(root.to_path().is_empty());
+        let key = root.key("a");
+        let index = key.index(2);
+        let leaf = index.key("b");
+        assert_eq!(leaf.len(), 3);
+        assert_eq!(
+            leaf.to_path(),
+            path_from_segments([
+                PathSegment::key("a"),
+                PathSegment::index(2),
+                PathSegment::key("b")
+            ])
+        );
+    }
 }
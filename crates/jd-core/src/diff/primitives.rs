@@ -1,10 +1,10 @@
-use super::{Diff, DiffElement, Path};
+use super::{Diff, DiffElement, Trail};
 use crate::Node;
 
 /// Produces a replacement diff element for non-container nodes.
 #[allow(clippy::needless_pass_by_value)]
-pub(super) fn diff_primitives(lhs: &Node, rhs: &Node, path: &Path) -> Diff {
-    let mut element = DiffElement::new().with_path(path.clone());
+pub(super) fn diff_primitives(lhs: &Node, rhs: &Node, path: &Trail<'_>) -> Diff {
+    let mut element = DiffElement::new().with_path(path.to_path());
     if !matches!(lhs, Node::Void) {
         element.remove.push(lhs.clone());
     }
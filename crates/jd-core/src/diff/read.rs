@@ -0,0 +1,507 @@
+//! Readers turning serialized diffs back into [`Diff`] values.
+//!
+//! The native jd format, RFC 6902 JSON Patch, and RFC 7386 JSON Merge Patch
+//! are all accepted. Readers are the inverse of the renderers in the parent
+//! module: rendering a diff and reading it back yields an equivalent diff.
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use super::{is_void, Diff, DiffElement, DiffMetadata, Path, PathSegment};
+use crate::Node;
+
+/// Errors that can occur while reading a serialized diff.
+///
+/// ```
+/// # use jd_core::Diff;
+/// let err = Diff::from_native_str("- 1\n").unwrap_err();
+/// assert!(err.to_string().contains("line 1"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadError {
+    message: String,
+}
+
+impl ReadError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+
+    fn at_line(line: usize, message: impl std::fmt::Display) -> Self {
+        Self::new(format!("invalid diff at line {line}: {message}"))
+    }
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl Diff {
+    /// Parses a diff written in the native jd format.
+    ///
+    /// ```
+    /// # use jd_core::{Diff, RenderConfig};
+    /// let text = "@ [\"a\"]\n- 1\n+ 2\n";
+    /// let diff = Diff::from_native_str(text).expect("valid diff");
+    /// assert_eq!(diff.render(&RenderConfig::default()), text);
+    /// ```
+    pub fn from_native_str(input: &str) -> Result<Self, ReadError> {
+        let mut elements = Vec::new();
+        let mut current: Option<DiffElement> = None;
+        let mut pending_metadata: Option<DiffMetadata> = None;
+
+        for (index, raw_line) in input.lines().enumerate() {
+            let line_no = index + 1;
+            let line = raw_line.strip_suffix('\r').unwrap_or(raw_line);
+            if line.is_empty() {
+                continue;
+            }
+            let (header, rest) = line.split_at(1);
+            let rest = rest.strip_prefix(' ').unwrap_or(rest);
+
+            match header {
+                "^" => {
+                    if let Some(element) = current.take() {
+                        elements.push(finish_native_element(element, line_no)?);
+                    }
+                    let metadata =
+                        parse_metadata(rest).map_err(|e| ReadError::at_line(line_no, e))?;
+                    pending_metadata.get_or_insert_with(DiffMetadata::default).absorb(&metadata);
+                }
+                "@" => {
+                    if let Some(element) = current.take() {
+                        elements.push(finish_native_element(element, line_no)?);
+                    }
+                    let path =
+                        parse_native_path(rest).map_err(|e| ReadError::at_line(line_no, e))?;
+                    let mut element = DiffElement::new().with_path(path);
+                    element.metadata = pending_metadata.take();
+                    current = Some(element);
+                }
+                _ => {
+                    let Some(element) = current.as_mut() else {
+                        return Err(ReadError::at_line(line_no, "expected @ or ^ header"));
+                    };
+                    let changed = !element.remove.is_empty() || !element.add.is_empty();
+                    match header {
+                        "[" if rest.is_empty() && !changed && element.before.is_empty() => {
+                            element.before.push(Node::Void);
+                        }
+                        "]" if rest.is_empty() && changed && element.after.is_empty() => {
+                            element.after.push(Node::Void);
+                        }
+                        " " => {
+                            let value = parse_value(rest, line_no)?;
+                            if element.after.first().is_some_and(is_void) {
+                                return Err(ReadError::at_line(line_no, "context after ]"));
+                            }
+                            if changed {
+                                element.after.push(value);
+                            } else if element.before.first().is_some_and(is_void) {
+                                return Err(ReadError::at_line(line_no, "context after ["));
+                            } else {
+                                element.before.push(value);
+                            }
+                        }
+                        "-" if element.add.is_empty() && element.after.is_empty() => {
+                            element.remove.push(parse_value(rest, line_no)?);
+                        }
+                        "+" if element.after.is_empty() => {
+                            let value = if rest.is_empty() {
+                                Node::Void
+                            } else {
+                                parse_value(rest, line_no)?
+                            };
+                            element.add.push(value);
+                        }
+                        _ => {
+                            return Err(ReadError::at_line(
+                                line_no,
+                                format!("unexpected line {line:?}"),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let line_count = input.lines().count();
+        if let Some(element) = current.take() {
+            elements.push(finish_native_element(element, line_count)?);
+        }
+        if pending_metadata.is_some() {
+            return Err(ReadError::at_line(line_count, "metadata without a following hunk"));
+        }
+        Ok(Diff::from_elements(elements))
+    }
+
+    /// Parses a JSON Patch (RFC 6902) document produced by [`Diff::render_patch`].
+    ///
+    /// Only `test`, `remove`, and `add` operations are supported. Removals
+    /// must be preceded by a `test` of the removed value, and `test`
+    /// operations on neighbouring list indices become hunk context.
+    ///
+    /// ```
+    /// # use jd_core::{Diff, RenderConfig};
+    /// let patch = r#"[{"op":"test","path":"/a","value":1},
+    ///                 {"op":"remove","path":"/a","value":1},
+    ///                 {"op":"add","path":"/a","value":2}]"#;
+    /// let diff = Diff::from_patch_str(patch).expect("valid patch");
+    /// assert_eq!(diff.render(&RenderConfig::default()), "@ [\"a\"]\n- 1\n+ 2\n");
+    /// ```
+    pub fn from_patch_str(input: &str) -> Result<Self, ReadError> {
+        let raw: Vec<RawPatchOp> = serde_json::from_str(input)
+            .map_err(|err| ReadError::new(format!("invalid JSON Patch: {err}")))?;
+        let ops = raw.into_iter().map(PatchOp::try_from).collect::<Result<Vec<_>, _>>()?;
+
+        let mut elements = Vec::new();
+        let mut cursor = 0;
+        while cursor < ops.len() {
+            let mut context = Vec::new();
+            while cursor < ops.len()
+                && ops[cursor].kind == OpKind::Test
+                && !starts_removal(&ops, cursor)
+            {
+                context.push(&ops[cursor]);
+                cursor += 1;
+            }
+
+            let mut path: Option<&Path> = None;
+            let mut remove = Vec::new();
+            while starts_removal(&ops, cursor) && path.is_none_or(|p| p == &ops[cursor].path) {
+                path = Some(&ops[cursor].path);
+                remove.push(ops[cursor].value.clone());
+                cursor += 2;
+            }
+
+            let mut add = Vec::new();
+            while cursor < ops.len()
+                && ops[cursor].kind == OpKind::Add
+                && path.is_none_or(|p| p == &ops[cursor].path)
+            {
+                path = Some(&ops[cursor].path);
+                add.push(ops[cursor].value.clone());
+                cursor += 1;
+            }
+            add.reverse();
+
+            let Some(path) = path.cloned() else {
+                let op = ops.get(cursor).or(context.last().copied()).expect("non-empty patch");
+                return Err(ReadError::new(format!(
+                    "unexpected JSON Patch {} operation at {}",
+                    op.kind.name(),
+                    op.pointer
+                )));
+            };
+
+            let (before, after) = patch_context(&path, &context, remove.len())?;
+            elements.push(DiffElement {
+                metadata: None,
+                path,
+                before,
+                remove,
+                add,
+                after,
+                base64: false,
+            });
+        }
+
+        Ok(Diff::from_elements(elements))
+    }
+
+    /// Parses a JSON Merge Patch (RFC 7386) into a merge-mode diff.
+    ///
+    /// Nested objects produce one hunk per leaf; `null` values become
+    /// deletions.
+    ///
+    /// ```
+    /// # use jd_core::{Diff, RenderConfig};
+    /// let diff = Diff::from_merge_str(r#"{"a":{"b":1},"c":null}"#).expect("valid merge patch");
+    /// assert_eq!(
+    ///     diff.render(&RenderConfig::default()),
+    ///     "^ {\"Merge\":true}\n@ [\"a\",\"b\"]\n+ 1\n@ [\"c\"]\n+\n"
+    /// );
+    /// assert_eq!(diff.render_merge().unwrap(), r#"{"a":{"b":1},"c":null}"#);
+    /// ```
+    pub fn from_merge_str(input: &str) -> Result<Self, ReadError> {
+        let node = Node::from_json_str(input)
+            .map_err(|err| ReadError::new(format!("invalid merge patch: {err}")))?;
+        let mut elements = Vec::new();
+        match node {
+            Node::Object(_) => collect_merge_elements(node, Path::new(), &mut elements),
+            Node::Void => {}
+            other => elements.push(merge_element(Path::new(), other)),
+        }
+        Ok(Diff::from_elements(elements))
+    }
+}
+
+fn finish_native_element(element: DiffElement, line_no: usize) -> Result<DiffElement, ReadError> {
+    if element.remove.is_empty() && element.add.is_empty() {
+        return Err(ReadError::at_line(
+            line_no,
+            format!("hunk at {} has no changes", super::path_to_json(&element.path)),
+        ));
+    }
+    Ok(element)
+}
+
+fn parse_value(text: &str, line_no: usize) -> Result<Node, ReadError> {
+    match Node::from_json_str(text) {
+        Ok(Node::Void) => Err(ReadError::at_line(line_no, "missing value")),
+        Ok(node) => Ok(node),
+        Err(err) => Err(ReadError::at_line(line_no, err)),
+    }
+}
+
+fn parse_metadata(text: &str) -> Result<DiffMetadata, String> {
+    let value: JsonValue = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    let JsonValue::Object(map) = value else {
+        return Err(format!("unsupported metadata {text}"));
+    };
+    let mut metadata = DiffMetadata::default();
+    for (key, value) in map {
+        match (key.as_str(), value) {
+            ("Merge", JsonValue::Bool(merge)) => metadata.merge = merge,
+            (key, _) => return Err(format!("unsupported metadata key {key:?}")),
+        }
+    }
+    Ok(metadata)
+}
+
+fn parse_native_path(text: &str) -> Result<Path, String> {
+    let value: JsonValue = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    let JsonValue::Array(segments) = value else {
+        return Err(format!("path must be a JSON array. got {text}"));
+    };
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            JsonValue::String(key) => Ok(PathSegment::Key(key)),
+            JsonValue::Number(number) => number
+                .as_i64()
+                .map(PathSegment::Index)
+                .ok_or_else(|| format!("path index must be an integer. got {number}")),
+            marker @ (JsonValue::Object(_) | JsonValue::Array(_)) => {
+                let text = marker.to_string();
+                serde_json::from_value(marker)
+                    .map_err(|err| format!("unsupported path element {text}: {err}"))
+            }
+            other => Err(format!("unsupported path element {other}")),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Path::from)
+}
+
+#[derive(Deserialize)]
+struct RawPatchOp {
+    op: String,
+    path: String,
+    #[serde(default, deserialize_with = "present_value")]
+    value: Option<JsonValue>,
+}
+
+/// Distinguishes `"value": null` from an absent `value` member.
+fn present_value<'de, D>(deserializer: D) -> Result<Option<JsonValue>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    JsonValue::deserialize(deserializer).map(Some)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OpKind {
+    Test,
+    Remove,
+    Add,
+}
+
+impl OpKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Test => "test",
+            Self::Remove => "remove",
+            Self::Add => "add",
+        }
+    }
+}
+
+struct PatchOp {
+    kind: OpKind,
+    pointer: String,
+    path: Path,
+    value: Node,
+    has_value: bool,
+}
+
+impl TryFrom<RawPatchOp> for PatchOp {
+    type Error = ReadError;
+
+    fn try_from(raw: RawPatchOp) -> Result<Self, Self::Error> {
+        let kind = match raw.op.as_str() {
+            "test" => OpKind::Test,
+            "remove" => OpKind::Remove,
+            "add" => OpKind::Add,
+            other => {
+                return Err(ReadError::new(format!("unsupported JSON Patch operation {other:?}")))
+            }
+        };
+        let path = pointer_to_path(&raw.path)?;
+        let has_value = raw.value.is_some();
+        let value = match raw.value {
+            Some(value) => Node::from_json_value(value)
+                .map_err(|err| ReadError::new(format!("invalid value at {}: {err}", raw.path)))?,
+            None if kind == OpKind::Remove => Node::Void,
+            None => {
+                return Err(ReadError::new(format!(
+                    "JSON Patch {} operation at {} requires a value",
+                    kind.name(),
+                    raw.path
+                )))
+            }
+        };
+        Ok(Self { kind, pointer: raw.path, path, value, has_value })
+    }
+}
+
+/// Returns whether `ops[index..]` starts with a `test` + `remove` pair.
+fn starts_removal(ops: &[PatchOp], index: usize) -> bool {
+    let (Some(test), Some(remove)) = (ops.get(index), ops.get(index + 1)) else {
+        return false;
+    };
+    test.kind == OpKind::Test
+        && remove.kind == OpKind::Remove
+        && test.path == remove.path
+        && (!remove.has_value || test.value == remove.value)
+}
+
+fn patch_context(
+    path: &Path,
+    context: &[&PatchOp],
+    removed: usize,
+) -> Result<(Vec<Node>, Vec<Node>), ReadError> {
+    let Some(PathSegment::Index(index)) = path.segments().last() else {
+        if let Some(op) = context.first() {
+            return Err(ReadError::new(format!(
+                "unexpected JSON Patch test operation at {}",
+                op.pointer
+            )));
+        }
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let parent = path.drop_last();
+    let after_index = index + i64::try_from(removed).unwrap_or(i64::MAX);
+
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    for op in context {
+        let neighbour = match op.path.segments().split_last() {
+            Some((PathSegment::Index(i), rest)) if rest == parent.segments() => Some(*i),
+            _ => None,
+        };
+        match neighbour {
+            Some(i) if i == index - 1 && before.is_empty() => before.push(op.value.clone()),
+            Some(i) if i == after_index && after.is_empty() => after.push(op.value.clone()),
+            _ => {
+                return Err(ReadError::new(format!(
+                    "unexpected JSON Patch test operation at {}",
+                    op.pointer
+                )))
+            }
+        }
+    }
+
+    if before.is_empty() && *index == 0 {
+        before.push(Node::Void);
+    }
+    if after.is_empty() {
+        after.push(Node::Void);
+    }
+    Ok((before, after))
+}
+
+fn pointer_to_path(pointer: &str) -> Result<Path, ReadError> {
+    if pointer.is_empty() {
+        return Ok(Path::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(ReadError::new(format!("JSON Pointer must start with '/'. got {pointer:?}")));
+    };
+    let segments = rest
+        .split('/')
+        .map(|segment| {
+            if segment == "-" {
+                return PathSegment::Index(-1);
+            }
+            let is_index = segment == "0"
+                || (!segment.starts_with('0') && segment.bytes().all(|b| b.is_ascii_digit()));
+            match segment.parse::<i64>() {
+                Ok(index) if is_index => PathSegment::Index(index),
+                _ => PathSegment::Key(segment.replace("~1", "/").replace("~0", "~")),
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(Path::from(segments))
+}
+
+fn collect_merge_elements(mut node: Node, path: Path, elements: &mut Vec<DiffElement>) {
+    match node {
+        Node::Object(ref mut map) if !map.is_empty() => {
+            for (key, value) in std::mem::take(map) {
+                collect_merge_elements(
+                    value,
+                    path.clone().with_segment(PathSegment::Key(key)),
+                    elements,
+                );
+            }
+        }
+        Node::Null => elements.push(merge_element(path, Node::Void)),
+        other => elements.push(merge_element(path, other)),
+    }
+}
+
+pub(super) fn merge_element(path: Path, value: Node) -> DiffElement {
+    DiffElement::new().with_metadata(DiffMetadata::merge()).with_path(path).with_add(vec![value])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointer_segments_unescape_and_detect_indices() {
+        let path = pointer_to_path("/a~1b/0/-/01/m~0n").unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::key("a/b"),
+                PathSegment::index(0),
+                PathSegment::index(-1),
+                PathSegment::key("01"),
+                PathSegment::key("m~n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn native_reader_rejects_hunks_without_changes() {
+        let err = Diff::from_native_str("@ [\"a\"]\n  1\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid diff at line 2: hunk at [\"a\"] has no changes");
+    }
+
+    #[test]
+    fn patch_reader_requires_test_before_remove() {
+        let err = Diff::from_patch_str(r#"[{"op":"remove","path":"/a"}]"#).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected JSON Patch remove operation at /a");
+    }
+
+    #[test]
+    fn patch_reader_rejects_unsupported_operations() {
+        let err = Diff::from_patch_str(r#"[{"op":"replace","path":"/a","value":1}]"#).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported JSON Patch operation \"replace\"");
+    }
+}
@@ -0,0 +1,322 @@
+//! Rebasing a diff over another diff of the same document.
+//!
+//! Each hunk of the rebased diff is transformed past every hunk of the diff
+//! it is rebased onto, and those hunks are in turn transformed past it before
+//! the next hunk is handled, as in operational transformation. Hunks that
+//! touch different object members or list positions only have their indices
+//! shifted and their context refreshed; hunks that change the same value
+//! conflict unless they make the same change.
+
+use super::{Diff, DiffElement, Path, PathSegment};
+use crate::Node;
+
+/// Error returned when a diff cannot be rebased onto another.
+///
+/// ```
+/// # use jd_core::{DiffOptions, Node};
+/// let base = Node::from_json_str(r#"{"a":1}"#).unwrap();
+/// let ours = base.diff(&Node::from_json_str(r#"{"a":2}"#).unwrap(), &DiffOptions::default());
+/// let theirs = base.diff(&Node::from_json_str(r#"{"a":3}"#).unwrap(), &DiffOptions::default());
+/// let err = ours.rebase(&theirs).unwrap_err();
+/// assert_eq!(err.to_string(), "conflicting changes at [\"a\"] and [\"a\"]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebaseError {
+    message: String,
+}
+
+impl RebaseError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+
+    fn conflict(ours: &DiffElement, theirs: &DiffElement) -> Self {
+        Self::new(format!(
+            "conflicting changes at {} and {}",
+            super::path_to_json(&ours.path),
+            super::path_to_json(&theirs.path)
+        ))
+    }
+}
+
+impl std::fmt::Display for RebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RebaseError {}
+
+impl Diff {
+    /// Adjusts this diff so it applies after `onto`, where both diffs were
+    /// computed against the same base document.
+    ///
+    /// List indices move past the values `onto` inserted or removed, and
+    /// list context is updated to the values `onto` left next to each
+    /// change, so independent edits can be queued up like patches in a
+    /// patch series. Changes that `onto` already made are dropped. A hunk
+    /// fails with a [`RebaseError`] when `onto` changed the same value
+    /// differently, changed a value this diff removes, or replaced a value
+    /// this diff edits inside of. Merge diffs cannot be rebased.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let base = Node::from_json_str(r#"{"tags":["a","b"],"n":1}"#).unwrap();
+    /// let ours = Node::from_json_str(r#"{"tags":["a","b","c"],"n":1}"#).unwrap();
+    /// let theirs = Node::from_json_str(r#"{"tags":["z","a","b"],"n":2}"#).unwrap();
+    /// let opts = DiffOptions::default();
+    ///
+    /// let rebased = base.diff(&ours, &opts).rebase(&base.diff(&theirs, &opts)).unwrap();
+    /// let merged = theirs.apply_patch(&rebased).unwrap();
+    /// assert_eq!(merged, Node::from_json_str(r#"{"tags":["z","a","b","c"],"n":2}"#).unwrap());
+    /// ```
+    pub fn rebase(&self, onto: &Diff) -> Result<Diff, RebaseError> {
+        for element in self.iter().chain(onto) {
+            if element.metadata.as_ref().is_some_and(|meta| meta.merge) {
+                return Err(RebaseError::new(format!(
+                    "cannot rebase merge diff element at {}",
+                    super::path_to_json(&element.path)
+                )));
+            }
+        }
+
+        let mut theirs: Vec<Option<DiffElement>> = onto.iter().cloned().map(Some).collect();
+        let mut rebased = Vec::with_capacity(self.len());
+        for ours in self {
+            let mut ours = Some(ours.clone());
+            for theirs in theirs.iter_mut() {
+                let (Some(a), Some(b)) = (ours.as_ref(), theirs.as_ref()) else {
+                    continue;
+                };
+                let a_after_b = transform(a, b, true)?;
+                let b_after_a = transform(b, a, false)?;
+                ours = a_after_b;
+                *theirs = b_after_a;
+            }
+            rebased.extend(ours);
+        }
+        Ok(Diff::from_elements(rebased))
+    }
+}
+
+/// The part of a list a hunk touches.
+#[derive(Clone, Copy)]
+enum Span {
+    /// A list edit removing `removed` values at `index` and adding `added`;
+    /// `index` is -1 for appends.
+    Hunk { index: i64, removed: usize, added: usize },
+    /// An edit inside the element at `index`.
+    Inside { index: i64 },
+}
+
+impl Span {
+    fn of(element: &DiffElement, depth: usize, index: i64) -> Self {
+        if element.path.len() == depth + 1 {
+            Self::Hunk { index, removed: element.remove.len(), added: element.add.len() }
+        } else {
+            Self::Inside { index }
+        }
+    }
+
+    fn start(self) -> i64 {
+        match self {
+            Self::Hunk { index, .. } | Self::Inside { index } => index,
+        }
+    }
+
+    /// Number of values of the original list the edit covers.
+    fn len(self) -> i64 {
+        match self {
+            Self::Hunk { removed, .. } => removed as i64,
+            Self::Inside { .. } => 1,
+        }
+    }
+
+    fn delta(self) -> i64 {
+        match self {
+            Self::Hunk { removed, added, .. } => added as i64 - removed as i64,
+            Self::Inside { .. } => 0,
+        }
+    }
+
+    fn is_insertion(self) -> bool {
+        matches!(self, Self::Hunk { removed: 0, .. })
+    }
+}
+
+/// Rewrites `x` to apply after `y`, both taken against the same document.
+/// `Ok(None)` means `y` already made the change. Insertions at the same
+/// position keep `y`'s values first when `y_first` is set.
+fn transform(
+    x: &DiffElement,
+    y: &DiffElement,
+    y_first: bool,
+) -> Result<Option<DiffElement>, RebaseError> {
+    let (xs, ys) = (x.path.segments(), y.path.segments());
+    let mut depth = 0;
+    loop {
+        match (xs.get(depth), ys.get(depth)) {
+            (Some(PathSegment::Key(a)), Some(PathSegment::Key(b))) if a == b => depth += 1,
+            (Some(PathSegment::Key(_)), Some(PathSegment::Key(_))) => return Ok(Some(x.clone())),
+            // Set elements are addressed by content, not position, so edits
+            // to different elements commute like edits to different keys.
+            (Some(a), Some(b)) if a.is_set_marker() && a == b => depth += 1,
+            (Some(PathSegment::SetKeys(_)), Some(PathSegment::SetKeys(_)))
+            | (Some(PathSegment::MultiSetKeys(_)), Some(PathSegment::MultiSetKeys(_))) => {
+                return Ok(Some(x.clone()))
+            }
+            (Some(PathSegment::Index(a)), Some(PathSegment::Index(b))) => {
+                let (x_span, y_span) = (Span::of(x, depth, *a), Span::of(y, depth, *b));
+                match (x_span, y_span) {
+                    (Span::Inside { .. }, Span::Inside { .. }) if a == b => depth += 1,
+                    _ => return transform_list(x, y, depth, x_span, y_span, y_first),
+                }
+            }
+            // The same value is replaced by both.
+            (None, None) if x.remove == y.remove && x.add == y.add => return Ok(None),
+            _ => return Err(RebaseError::conflict(x, y)),
+        }
+    }
+}
+
+/// Handles `x` and `y` editing the same list at `depth`, at least one of
+/// them with a list hunk.
+fn transform_list(
+    x: &DiffElement,
+    y: &DiffElement,
+    depth: usize,
+    x_span: Span,
+    y_span: Span,
+    y_first: bool,
+) -> Result<Option<DiffElement>, RebaseError> {
+    if let (Span::Hunk { .. }, Span::Hunk { .. }) = (x_span, y_span) {
+        if x_span.start() == y_span.start() && x.remove == y.remove && x.add == y.add {
+            return Ok(None);
+        }
+    }
+    let mut x = x.clone();
+
+    // Appends land after everything else, so they leave other edits alone,
+    // except that the end of the list is no longer right after them.
+    if y_span.start() < 0 {
+        if matches!(x_span, Span::Hunk { .. }) && x.after == [Node::Void] {
+            if let Some(first) = y.add.first() {
+                x.after = vec![first.clone()];
+            }
+        }
+        return Ok(Some(x));
+    }
+    if x_span.start() < 0 {
+        return Ok(Some(x));
+    }
+
+    let (x_start, y_start) = (x_span.start(), y_span.start());
+    let y_before_x = if x_span.is_insertion() && y_span.is_insertion() && x_start == y_start {
+        y_first
+    } else if y_start + y_span.len() <= x_start {
+        true
+    } else if x_start + x_span.len() <= y_start {
+        false
+    } else {
+        return Err(RebaseError::conflict(&x, y));
+    };
+
+    let is_hunk = matches!(x_span, Span::Hunk { .. });
+    if y_before_x {
+        set_index(&mut x.path, depth, x_start + y_span.delta());
+        if is_hunk && !x.before.is_empty() && y_start + y_span.len() == x_start {
+            x.before = match y_span {
+                Span::Hunk { .. } => match y.add.last() {
+                    Some(last) => vec![last.clone()],
+                    None => y.before.clone(),
+                },
+                Span::Inside { .. } => vec![apply_inside(&x.before[0], y, depth, &x)?],
+            };
+        }
+    } else if is_hunk && !x.after.is_empty() && x_start + x_span.len() == y_start {
+        x.after = match y_span {
+            Span::Hunk { .. } => match y.add.first() {
+                Some(first) => vec![first.clone()],
+                None => y.after.clone(),
+            },
+            Span::Inside { .. } => vec![apply_inside(&x.after[0], y, depth, &x)?],
+        };
+    }
+    Ok(Some(x))
+}
+
+/// Applies the part of `y` below the list element at `depth` to `value`, a
+/// copy of that element kept as context.
+fn apply_inside(
+    value: &Node,
+    y: &DiffElement,
+    depth: usize,
+    x: &DiffElement,
+) -> Result<Node, RebaseError> {
+    let inner = DiffElement {
+        metadata: None,
+        path: Path::from(y.path.segments()[depth + 1..].to_vec()),
+        ..y.clone()
+    };
+    value.apply_patch(&Diff::from_elements(vec![inner])).map_err(|_| RebaseError::conflict(x, y))
+}
+
+fn set_index(path: &mut Path, depth: usize, index: i64) {
+    let mut segments = std::mem::take(path).into_segments();
+    segments[depth] = PathSegment::Index(index);
+    *path = Path::from(segments);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiffOptions;
+
+    fn parse(json: &str) -> Node {
+        Node::from_json_str(json).unwrap()
+    }
+
+    /// Rebases `base -> ours` onto `base -> theirs` and applies it to `theirs`.
+    fn rebase(base: &str, ours: &str, theirs: &str) -> Result<Node, RebaseError> {
+        let (base, ours, theirs) = (parse(base), parse(ours), parse(theirs));
+        let opts = DiffOptions::default();
+        let rebased = base.diff(&ours, &opts).rebase(&base.diff(&theirs, &opts))?;
+        Ok(theirs.apply_patch(&rebased).expect("rebased diff applies"))
+    }
+
+    #[test]
+    fn list_edits_shift_past_each_other() {
+        assert_eq!(rebase("[1,2,3,4]", "[1,9,3,4]", "[0,1,2,3,4,5]"), Ok(parse("[0,1,9,3,4,5]")));
+        assert_eq!(rebase("[1,2,3,4]", "[1,2,3]", "[2,3,4]"), Ok(parse("[2,3]")));
+        assert_eq!(rebase("[1,2]", "[1,2,8]", "[1,2,9]"), Ok(parse("[1,2,9,8]")));
+        assert_eq!(rebase("[1,2]", "[1,7,2]", "[1,9,2]"), Ok(parse("[1,9,7,2]")));
+    }
+
+    #[test]
+    fn context_follows_edits_inside_neighbours() {
+        assert_eq!(
+            rebase(r#"[{"a":1},2]"#, r#"[{"a":1},3]"#, r#"[{"a":5},2]"#),
+            Ok(parse(r#"[{"a":5},3]"#))
+        );
+        assert_eq!(
+            rebase(r#"[[1],{"b":[1,2]},3]"#, r#"[[1],{"b":[1,2,4]},3]"#, r#"[[1,0],{"b":[2]},3]"#),
+            Ok(parse(r#"[[1,0],{"b":[2,4]},3]"#))
+        );
+    }
+
+    #[test]
+    fn identical_changes_are_dropped_and_different_ones_conflict() {
+        assert_eq!(
+            rebase(r#"{"a":1,"b":1}"#, r#"{"a":2,"b":1}"#, r#"{"a":2,"b":3}"#),
+            Ok(parse(r#"{"a":2,"b":3}"#))
+        );
+        assert_eq!(
+            rebase("[1,2,3]", "[1,3]", "[1,4,3]").unwrap_err().to_string(),
+            "conflicting changes at [1] and [1]"
+        );
+        assert_eq!(
+            rebase(r#"{"a":{"b":1}}"#, r#"{"a":{"b":2}}"#, r#"{"a":7}"#).unwrap_err().to_string(),
+            "conflicting changes at [\"a\",\"b\"] and [\"a\"]"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Output formats for diffs behind one trait.
+
+use std::io;
+
+use super::{Diff, RenderConfig, RenderError};
+
+/// An output format for diffs.
+///
+/// The built-in formats implement it: [`RenderConfig`] for the native jd
+/// text, [`PatchRenderer`] for JSON Patch, [`MergeRenderer`] for JSON Merge
+/// Patch, and [`RawRenderer`] for the debugging JSON. Other crates can add
+/// formats, text or binary, and pick among them at run time as
+/// `&dyn DiffRenderer`.
+///
+/// ```
+/// use std::io;
+/// use jd_core::{Diff, DiffOptions, DiffRenderer, Node, PatchRenderer, RenderConfig, RenderError};
+///
+/// /// One line per changed path.
+/// struct Paths;
+///
+/// impl DiffRenderer for Paths {
+///     fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError> {
+///         for element in diff.iter() {
+///             writeln!(output, "{}", element.path)?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let lhs = Node::from_json_str(r#"{"a":1,"b":[1,2]}"#).unwrap();
+/// let rhs = Node::from_json_str(r#"{"a":2,"b":[1,3]}"#).unwrap();
+/// let diff = lhs.diff(&rhs, &DiffOptions::default());
+/// let renderers: [&dyn DiffRenderer; 3] = [&Paths, &RenderConfig::default(), &PatchRenderer];
+/// let rendered: Vec<String> =
+///     renderers.iter().map(|renderer| renderer.render(&diff).unwrap()).collect();
+/// assert_eq!(rendered[0], "[a]\n[b 1]\n");
+/// assert_eq!(rendered[1], diff.render(&RenderConfig::default()));
+/// assert_eq!(rendered[2], diff.render_patch().unwrap());
+/// ```
+pub trait DiffRenderer {
+    /// Writes `diff` in this format to `output`.
+    fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError>;
+
+    /// Renders `diff` in this format as text.
+    ///
+    /// Fails for output that is not UTF-8.
+    fn render(&self, diff: &Diff) -> Result<String, RenderError> {
+        let mut output = Vec::new();
+        self.render_to(diff, &mut output)?;
+        String::from_utf8(output).map_err(|err| RenderError::new(err.to_string()))
+    }
+}
+
+/// Renders the native jd text format; see [`Diff::render`].
+impl DiffRenderer for RenderConfig {
+    fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError> {
+        Ok(output.write_all(diff.render(self).as_bytes())?)
+    }
+
+    fn render(&self, diff: &Diff) -> Result<String, RenderError> {
+        Ok(diff.render(self))
+    }
+}
+
+/// Renders JSON Patch (RFC 6902); see [`Diff::render_patch`].
+///
+/// ```
+/// # use jd_core::{DiffOptions, DiffRenderer, Node, PatchRenderer};
+/// let lhs = Node::from_json_str(r#"{"a":1}"#).unwrap();
+/// let diff = lhs.diff(&Node::from_json_str(r#"{"a":2}"#).unwrap(), &DiffOptions::default());
+/// assert_eq!(PatchRenderer.render(&diff).unwrap(), diff.render_patch().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PatchRenderer;
+
+impl DiffRenderer for PatchRenderer {
+    fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError> {
+        Ok(output.write_all(diff.render_patch()?.as_bytes())?)
+    }
+
+    fn render(&self, diff: &Diff) -> Result<String, RenderError> {
+        diff.render_patch()
+    }
+}
+
+/// Renders JSON Merge Patch (RFC 7386); see [`Diff::render_merge`].
+///
+/// ```
+/// # use jd_core::{DiffOptions, DiffRenderer, MergeRenderer, Node};
+/// let lhs = Node::from_json_str(r#"{"a":1}"#).unwrap();
+/// let opts = DiffOptions::default().with_merge(true);
+/// let diff = lhs.diff(&Node::from_json_str(r#"{"a":2}"#).unwrap(), &opts);
+/// assert_eq!(MergeRenderer.render(&diff).unwrap(), r#"{"a":2}"#);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeRenderer;
+
+impl DiffRenderer for MergeRenderer {
+    fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError> {
+        Ok(output.write_all(diff.render_merge()?.as_bytes())?)
+    }
+
+    fn render(&self, diff: &Diff) -> Result<String, RenderError> {
+        diff.render_merge()
+    }
+}
+
+/// Renders the diff structure as JSON for debugging; see
+/// [`Diff::render_raw`].
+///
+/// ```
+/// # use jd_core::{Diff, DiffRenderer, RawRenderer};
+/// assert_eq!(RawRenderer.render(&Diff::empty()).unwrap(), "[]");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RawRenderer;
+
+impl DiffRenderer for RawRenderer {
+    fn render_to(&self, diff: &Diff, output: &mut dyn io::Write) -> Result<(), RenderError> {
+        Ok(serde_json::to_writer(output, &diff.elements)?)
+    }
+
+    fn render(&self, diff: &Diff) -> Result<String, RenderError> {
+        diff.render_raw()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, Node};
+
+    #[test]
+    fn built_in_renderers_write_what_they_render() {
+        let lhs = Node::from_json_str(r#"{"a":[1,2],"b":true}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"a":[1,3],"c":null}"#).unwrap();
+        let diff = lhs.diff(&rhs, &DiffOptions::default());
+        let merge = lhs.diff(&rhs, &DiffOptions::default().with_merge(true));
+        let color = RenderConfig::color(true);
+        let cases: [(&dyn DiffRenderer, &Diff); 4] = [
+            (&color, &diff),
+            (&PatchRenderer, &diff),
+            (&MergeRenderer, &merge),
+            (&RawRenderer, &diff),
+        ];
+        for (renderer, diff) in cases {
+            let mut written = Vec::new();
+            renderer.render_to(diff, &mut written).unwrap();
+            assert_eq!(String::from_utf8(written).unwrap(), renderer.render(diff).unwrap());
+        }
+        assert!(MergeRenderer.render(&diff).is_err());
+    }
+}
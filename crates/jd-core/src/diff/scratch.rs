@@ -0,0 +1,174 @@
+//! Buffers reused across diffs.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use super::{diff_nodes_until, Diff, RenderConfig};
+use crate::hash::Digest;
+use crate::limits::Deadline;
+use crate::{DiffOptions, Node};
+
+/// Scratch space for computing many diffs in a row, such as a service
+/// diffing thousands of small documents per second.
+///
+/// Each diff allocates the hash vectors and alignment tables of every list
+/// it compares, and rendering allocates the output. Passing the same
+/// scratch to [`diff_nodes_with_scratch`] and [`DiffScratch::render`] keeps
+/// those buffers between calls, so after the first few diffs they reuse
+/// memory instead of allocating. Results are the same as without scratch.
+///
+/// ```
+/// use jd_core::diff::{diff_nodes_with_scratch, DiffScratch};
+/// use jd_core::{DiffOptions, Node, RenderConfig};
+///
+/// let mut scratch = DiffScratch::new();
+/// let options = DiffOptions::default();
+/// for (lhs, rhs) in [("[1, 2]", "[1, 3]"), ("[4]", "[5]")] {
+///     let lhs = Node::from_json_str(lhs).unwrap();
+///     let rhs = Node::from_json_str(rhs).unwrap();
+///     let diff = diff_nodes_with_scratch(&lhs, &rhs, &options, &mut scratch);
+///     assert_eq!(diff, lhs.diff(&rhs, &options));
+///     let rendered = scratch.render(&diff, &RenderConfig::default());
+///     assert_eq!(rendered, diff.render(&RenderConfig::default()));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct DiffScratch {
+    buffers: Arc<Buffers>,
+    output: String,
+}
+
+impl DiffScratch {
+    /// Creates empty scratch space; buffers are allocated as diffs need them.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders `diff` like [`Diff::render`] into the scratch's output
+    /// buffer, which the next call overwrites.
+    pub fn render(&mut self, diff: &Diff, config: &RenderConfig) -> &str {
+        self.output.clear();
+        diff.render_into(config, &mut self.output);
+        &self.output
+    }
+}
+
+/// Computes the diff between two nodes like [`super::diff_nodes`], reusing
+/// the buffers in `scratch`.
+#[must_use]
+pub fn diff_nodes_with_scratch(
+    lhs: &Node,
+    rhs: &Node,
+    options: &DiffOptions,
+    scratch: &mut DiffScratch,
+) -> Diff {
+    let options = options.clone().with_scratch(Arc::clone(&scratch.buffers));
+    diff_nodes_until(lhs, rhs, &options, &Deadline::unbounded())
+}
+
+/// The buffers of a [`DiffScratch`], shared with the options of the diff
+/// using them. Nested lists take several at once, so each kind is a pool.
+#[derive(Default)]
+pub(crate) struct Buffers {
+    digests: Pool<Digest>,
+    table: Pool<usize>,
+}
+
+impl fmt::Debug for Buffers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Buffers")
+            .field("digests", &self.digests.lock().len())
+            .field("table", &self.table.lock().len())
+            .finish()
+    }
+}
+
+/// An empty vector of digests, from the scratch in `options` if any.
+pub(crate) fn digests(options: &DiffOptions) -> Pooled<'_, Digest> {
+    Pooled::take(options.scratch().map(|buffers| &buffers.digests))
+}
+
+/// An empty table for the list aligner, from the scratch in `options` if
+/// any.
+pub(crate) fn table(options: &DiffOptions) -> Pooled<'_, usize> {
+    Pooled::take(options.scratch().map(|buffers| &buffers.table))
+}
+
+#[derive(Default)]
+struct Pool<T>(Mutex<Vec<Vec<T>>>);
+
+impl<T> Pool<T> {
+    /// Buffers are only ever moved in and out whole, so a poisoned lock is
+    /// still consistent.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<T>>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// A vector on loan from a pool, returned empty when dropped.
+pub(crate) struct Pooled<'a, T> {
+    buffer: Vec<T>,
+    pool: Option<&'a Pool<T>>,
+}
+
+impl<'a, T> Pooled<'a, T> {
+    fn take(pool: Option<&'a Pool<T>>) -> Self {
+        let buffer = pool.and_then(|pool| pool.lock().pop()).unwrap_or_default();
+        Self { buffer, pool }
+    }
+}
+
+impl<T> Deref for Pooled<'_, T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.buffer
+    }
+}
+
+impl<T> DerefMut for Pooled<'_, T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.buffer
+    }
+}
+
+impl<T> Drop for Pooled<'_, T> {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool {
+            let mut buffer = std::mem::take(&mut self.buffer);
+            buffer.clear();
+            pool.lock().push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_buffers_give_the_same_diffs() {
+        let options = DiffOptions::default();
+        let mut scratch = DiffScratch::new();
+        let pairs = [
+            (crate::node!([1, [2, 3], {"a": [4, 5]}]), crate::node!([1, [3], {"a": [5, 4]}, 6])),
+            (crate::node!({"x": [1, 2, 3]}), crate::node!({"x": [3, 2, 1]})),
+            (crate::node!([]), crate::node!(["new"])),
+        ];
+        for _ in 0..2 {
+            for (lhs, rhs) in &pairs {
+                let diff = diff_nodes_with_scratch(lhs, rhs, &options, &mut scratch);
+                assert_eq!(diff, lhs.diff(rhs, &options));
+                let config = RenderConfig::default();
+                assert_eq!(scratch.render(&diff, &config), diff.render(&config));
+            }
+        }
+        // Nested lists held several vectors at once; all were returned.
+        let digests = scratch.buffers.digests.lock();
+        assert!(digests.len() >= 3);
+        assert!(digests.iter().all(|buffer| buffer.is_empty() && buffer.capacity() > 0));
+        assert!(!scratch.buffers.table.lock().is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Structural similarity of values, for pairing list elements.
+
+use std::collections::HashMap;
+
+use super::{Path, PathSegment};
+use crate::hash::Digest;
+use crate::{DiffOptions, Node};
+
+/// The leaves of a value, scalars and empty containers, counted by their
+/// path within the value and their hash.
+pub(super) struct Leaves {
+    counts: HashMap<(Path, Digest), usize>,
+    total: usize,
+}
+
+impl Leaves {
+    pub(super) fn of(node: &Node, options: &DiffOptions) -> Self {
+        let mut counts = HashMap::new();
+        let mut total = 0;
+        let mut stack = vec![(Path::new(), node)];
+        while let Some((path, node)) = stack.pop() {
+            match node {
+                Node::Array(values) if !values.is_empty() => {
+                    for (index, value) in values.iter().enumerate() {
+                        stack.push((
+                            path.clone().with_segment(PathSegment::index(index as i64)),
+                            value,
+                        ));
+                    }
+                }
+                Node::Object(members) if !members.is_empty() => {
+                    for (key, value) in members {
+                        stack.push((
+                            path.clone().with_segment(PathSegment::key(key.as_str())),
+                            value,
+                        ));
+                    }
+                }
+                leaf => {
+                    *counts.entry((path, leaf.digest(options))).or_default() += 1;
+                    total += 1;
+                }
+            }
+        }
+        Self { counts, total }
+    }
+
+    /// The share of leaves the two values have in common: twice the shared
+    /// leaves over the leaves of both, from 0 for none to 1 for all.
+    pub(super) fn similarity(&self, other: &Self) -> f64 {
+        let shared: usize = self
+            .counts
+            .iter()
+            .map(|(leaf, count)| other.counts.get(leaf).map_or(0, |other| (*count).min(*other)))
+            .sum();
+        (2 * shared) as f64 / (self.total + other.total) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node;
+
+    #[test]
+    fn similarity_counts_leaves_at_the_same_path() {
+        let options = DiffOptions::default();
+        let leaves = |node: Node| Leaves::of(&node, &options);
+        let base = leaves(node!({"a": 1, "b": [1, 2], "c": {}}));
+        assert_eq!(base.similarity(&leaves(node!({"a": 1, "b": [1, 2], "c": {}}))), 1.0);
+        assert_eq!(base.similarity(&leaves(node!({"a": 1, "b": [1, 3], "c": {}}))), 0.75);
+        // Moved leaves do not count.
+        assert_eq!(base.similarity(&leaves(node!({"x": 1, "b": [2, 1]}))), 0.0);
+        assert_eq!(leaves(node!([])).similarity(&leaves(node!({}))), 0.0);
+    }
+}
@@ -0,0 +1,255 @@
+//! Character alignment for highlighting the edits in a replaced string.
+//!
+//! Strings are compared in segments: characters by default, or extended
+//! grapheme clusters with the `unicode-segmentation` feature, so an emoji
+//! or a letter with combining marks is highlighted whole.
+
+#[cfg(feature = "unicode-segmentation")]
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::COLOR_RESET;
+
+/// Strings whose LCS table has at most this many cells are aligned with
+/// the table, which picks the same characters as Go `jd`.
+const TABLE_CELLS: usize = 1 << 16;
+
+/// Strings longer than this in total, in segments, are rendered as a
+/// plain replacement without highlighting.
+const MAX_SEGMENTS: usize = 1 << 20;
+
+/// Character comparisons the O(ND) alignment may spend, at most
+/// `(n + m) * d` for `d` edits.
+const BUDGET: usize = 1 << 26;
+
+/// Most edits the O(ND) alignment looks for; its backtracking memory grows
+/// with their square.
+const MAX_EDITS: usize = 1024;
+
+/// A unit of comparison: an extended grapheme cluster.
+#[cfg(feature = "unicode-segmentation")]
+pub(super) type Segment<'a> = &'a str;
+
+/// A unit of comparison: a character.
+#[cfg(not(feature = "unicode-segmentation"))]
+pub(super) type Segment<'a> = char;
+
+/// The segments `text` is compared in.
+#[cfg(feature = "unicode-segmentation")]
+fn segments(text: &str) -> impl Iterator<Item = Segment<'_>> {
+    text.graphemes(true)
+}
+
+/// The segments `text` is compared in.
+#[cfg(not(feature = "unicode-segmentation"))]
+fn segments(text: &str) -> impl Iterator<Item = Segment<'_>> {
+    text.chars()
+}
+
+#[cfg(feature = "unicode-segmentation")]
+fn push_segment(output: &mut String, segment: Segment<'_>) {
+    output.push_str(segment);
+}
+
+#[cfg(not(feature = "unicode-segmentation"))]
+fn push_segment(output: &mut String, segment: Segment<'_>) {
+    output.push(segment);
+}
+
+/// The segments `lhs` and `rhs` have in common, in order, or `None` when
+/// aligning them would take too long and they should be rendered whole.
+pub(super) fn common_segments<'a>(lhs: &'a str, rhs: &'a str) -> Option<Vec<Segment<'a>>> {
+    let left: Vec<_> = segments(lhs).collect();
+    let right: Vec<_> = segments(rhs).collect();
+    if (left.len() + 1).saturating_mul(right.len() + 1) <= TABLE_CELLS {
+        return Some(lcs(&left, &right));
+    }
+    let total = left.len() + right.len();
+    if total > MAX_SEGMENTS {
+        return None;
+    }
+    myers(&left, &right, (BUDGET / total).min(MAX_EDITS))
+}
+
+/// `text` with every segment outside `common` wrapped in `color`.
+pub(super) fn highlight(text: &str, common: &[Segment<'_>], color: &str) -> String {
+    let mut result = String::new();
+    let mut common_iter = common.iter();
+    let mut current = common_iter.next();
+    for segment in segments(text) {
+        if current == Some(&segment) {
+            push_segment(&mut result, segment);
+            current = common_iter.next();
+            continue;
+        }
+        result.push_str(color);
+        push_segment(&mut result, segment);
+        result.push_str(COLOR_RESET);
+    }
+    result
+}
+
+fn lcs<T: Copy + PartialEq>(left: &[T], right: &[T]) -> Vec<T> {
+    let n = left.len();
+    let m = right.len();
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for (i, lhs_char) in left.iter().enumerate() {
+        for (j, rhs_char) in right.iter().enumerate() {
+            if lhs_char == rhs_char {
+                table[i + 1][j + 1] = table[i][j] + 1;
+            } else {
+                table[i + 1][j + 1] = table[i][j + 1].max(table[i + 1][j]);
+            }
+        }
+    }
+
+    let mut result = Vec::with_capacity(table[n][m]);
+    let mut i = n;
+    let mut j = m;
+    while i > 0 && j > 0 {
+        if left[i - 1] == right[j - 1] {
+            result.push(left[i - 1]);
+            i -= 1;
+            j -= 1;
+        } else if table[i - 1][j] >= table[i][j - 1] {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+    result.reverse();
+    result
+}
+
+/// A longest common subsequence by Myers' O(ND) algorithm, or `None` when
+/// the strings need more than `max_edits` insertions and deletions.
+fn myers<T: Copy + PartialEq>(left: &[T], right: &[T], max_edits: usize) -> Option<Vec<T>> {
+    let (n, m) = (left.len() as isize, right.len() as isize);
+    let max = max_edits as isize;
+    // `reach[k + offset]` is the furthest x reached on diagonal k = x - y.
+    let offset = max + 1;
+    let mut reach = vec![0isize; 2 * max_edits + 3];
+    // The reach of diagonals -d..=d before step d, for backtracking.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max {
+        trace.push(reach[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| reach[(k + offset) as usize];
+            let mut x = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+                at(k + 1)
+            } else {
+                at(k - 1) + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && left[x as usize] == right[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            reach[(k + offset) as usize] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(left, &trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+/// Walks the edits recorded in `trace` back from `(n, m)`, collecting the
+/// characters on the diagonal runs between them.
+fn backtrack<T: Copy>(left: &[T], trace: &[Vec<isize>], n: isize, m: isize) -> Vec<T> {
+    let mut common = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, reach) in trace.iter().enumerate().skip(1).rev() {
+        let d = d as isize;
+        let at = |k: isize| reach[(k + d) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && at(k - 1) < at(k + 1));
+        let previous_k = if down { k + 1 } else { k - 1 };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        let (start_x, start_y) =
+            if down { (previous_x, previous_y + 1) } else { (previous_x + 1, previous_y) };
+        while x > start_x && y > start_y {
+            common.push(left[x as usize - 1]);
+            x -= 1;
+            y -= 1;
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+    while x > 0 && y > 0 {
+        common.push(left[x as usize - 1]);
+        x -= 1;
+        y -= 1;
+    }
+    common.reverse();
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn is_subsequence(common: &[char], text: &[char]) -> bool {
+        let mut rest = text.iter();
+        common.iter().all(|ch| rest.any(|other| other == ch))
+    }
+
+    #[test]
+    fn myers_finds_a_longest_common_subsequence() {
+        let pairs = [
+            ("kitten", "sitting"),
+            ("", "abc"),
+            ("abc", ""),
+            ("abcabba", "cbabac"),
+            ("the quick brown fox", "a quick brown dog jumps"),
+            ("aaaa", "aaaa"),
+            ("héllo wörld", "hello world!"),
+        ];
+        for (lhs, rhs) in pairs {
+            let (left, right) = (chars(lhs), chars(rhs));
+            let common = myers(&left, &right, MAX_EDITS).expect("within the edit limit");
+            assert_eq!(common.len(), lcs(&left, &right).len(), "{lhs:?} / {rhs:?}");
+            assert!(is_subsequence(&common, &left) && is_subsequence(&common, &right));
+        }
+        assert_eq!(myers(&chars("abcd"), &chars("wxyz"), 7), None);
+        assert!(myers(&chars("abcd"), &chars("wxyz"), 8).is_some());
+    }
+
+    #[test]
+    fn long_strings_use_the_budgeted_alignment() {
+        let old: String = (0..5_000).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+        let mut new = old.clone();
+        new.replace_range(2_500..2_501, "#");
+        let common = common_segments(&old, &new).expect("few edits");
+        assert_eq!(common.len(), old.len() - 1);
+
+        let unrelated: String = "#".repeat(5_000);
+        assert_eq!(common_segments(&old, &unrelated), None);
+        let huge = "a".repeat(MAX_SEGMENTS);
+        assert_eq!(common_segments(&huge, "a"), None);
+    }
+
+    #[test]
+    fn highlight_wraps_the_segments_left_out() {
+        let common = common_segments("kitten", "sitting").unwrap();
+        assert_eq!(
+            highlight("kitten", &common, "<"),
+            format!("<k{COLOR_RESET}itt<e{COLOR_RESET}n")
+        );
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn graphemes_are_kept_whole() {
+        // The flags share their first regional indicator and the last
+        // letters their base `e`, but no grapheme is in common.
+        let (old, new) = ("🇵🇱 cafe\u{301}", "🇵🇹 cafe");
+        let common = common_segments(old, new).unwrap();
+        assert_eq!(common, [" ", "c", "a", "f"]);
+        let highlighted = highlight(old, &common, "<");
+        assert_eq!(highlighted, format!("<🇵🇱{COLOR_RESET} caf<e\u{301}{COLOR_RESET}"));
+    }
+}
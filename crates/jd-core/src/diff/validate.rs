@@ -0,0 +1,157 @@
+//! Structural checks for diff elements and a builder that applies them.
+
+use super::{Diff, DiffElement, DiffMetadata, PathSegment};
+use crate::{ElementError, Node};
+
+impl DiffElement {
+    /// Checks the element against the invariants of the native diff format,
+    /// reading merge semantics from the element's own metadata.
+    ///
+    /// Strict elements must change something, may not remove or add void
+    /// values, carry at most one before and one after context value and only
+    /// at array index paths, and change several values only at an index or
+    /// set path. Merge elements address object members and add exactly one
+    /// value, void to delete the member.
+    ///
+    /// ```
+    /// # use jd_core::{diff::PathSegment, DiffElement, DiffMetadata, ElementError, Node};
+    /// let element = DiffElement::new().with_path(PathSegment::key("a")).with_add(vec![Node::Null]);
+    /// assert_eq!(element.validate(), Ok(()));
+    ///
+    /// let merge = element.with_metadata(DiffMetadata::merge()).with_remove(vec![Node::Null]);
+    /// assert!(matches!(merge.validate(), Err(ElementError::MergeRemoval { .. })));
+    /// ```
+    pub fn validate(&self) -> Result<(), ElementError> {
+        self.validate_as(self.metadata.as_ref().is_some_and(|metadata| metadata.merge))
+    }
+
+    fn validate_as(&self, merge: bool) -> Result<(), ElementError> {
+        let path = || self.path.clone();
+        let last = self.path.segments().last();
+        if !self.before.is_empty() || !self.after.is_empty() {
+            if !matches!(last, Some(PathSegment::Index(_))) {
+                return Err(ElementError::ContextWithoutIndex { path: path() });
+            }
+            if self.before.len() > 1 || self.after.len() > 1 {
+                return Err(ElementError::ExcessContext { path: path() });
+            }
+        }
+        if merge {
+            if !self.path.segments().iter().all(|segment| matches!(segment, PathSegment::Key(_))) {
+                return Err(ElementError::MergePath { path: path() });
+            }
+            if !self.remove.is_empty() {
+                return Err(ElementError::MergeRemoval { path: path() });
+            }
+            if self.add.len() != 1 {
+                return Err(ElementError::MergeAddition { path: path() });
+            }
+            return Ok(());
+        }
+        if self.remove.is_empty() && self.add.is_empty() {
+            return Err(ElementError::Empty { path: path() });
+        }
+        if self.remove.iter().chain(&self.add).any(|value| matches!(value, Node::Void)) {
+            return Err(ElementError::VoidValue { path: path() });
+        }
+        let multiple = self.remove.len() > 1 || self.add.len() > 1;
+        let holds_many =
+            matches!(last, Some(PathSegment::Index(_) | PathSegment::Set | PathSegment::MultiSet));
+        if multiple && !holds_many {
+            return Err(ElementError::MultipleValues { path: path() });
+        }
+        Ok(())
+    }
+}
+
+impl Diff {
+    /// Validates every element under the metadata in effect for it; see
+    /// [`DiffElement::validate`].
+    ///
+    /// ```
+    /// # use jd_core::{Diff, ElementError};
+    /// let diff = Diff::from_native_str("^ {\"Merge\":true}\n@ [\"a\"]\n+ 1\n@ [\"b\"]\n- 2\n").unwrap();
+    /// let err = diff.validate().unwrap_err();
+    /// assert_eq!(err.to_string(), "merge diff element at [b] removes values");
+    /// ```
+    pub fn validate(&self) -> Result<(), ElementError> {
+        self.iter()
+            .zip(self.effective_metadata())
+            .try_for_each(|(element, metadata)| element.validate_as(metadata.merge))
+    }
+}
+
+/// Builds a [`Diff`] one element at a time, rejecting elements that break
+/// the native format's invariants as they are added.
+///
+/// Metadata on an element stays in effect for the elements after it, so a
+/// merge header makes later elements merge elements too.
+///
+/// ```
+/// # use jd_core::{diff::PathSegment, DiffBuilder, DiffElement, DiffMetadata, ElementError, Node};
+/// let builder = DiffBuilder::new()
+///     .with_element(
+///         DiffElement::new()
+///             .with_metadata(DiffMetadata::merge())
+///             .with_path(PathSegment::key("a"))
+///             .with_add(vec![Node::Void]),
+///     )
+///     .unwrap();
+/// let err = builder
+///     .clone()
+///     .with_element(DiffElement::new().with_path(PathSegment::index(0)).with_add(vec![Node::Null]))
+///     .unwrap_err();
+/// assert!(matches!(err, ElementError::MergePath { .. }));
+/// assert_eq!(builder.build().render_merge().unwrap(), r#"{"a":null}"#);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DiffBuilder {
+    elements: Vec<DiffElement>,
+    metadata: DiffMetadata,
+}
+
+impl DiffBuilder {
+    /// Creates a builder for an empty diff.
+    ///
+    /// ```
+    /// # use jd_core::DiffBuilder;
+    /// assert!(DiffBuilder::new().build().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `element` after validating it under the metadata in effect.
+    ///
+    /// ```
+    /// # use jd_core::{DiffBuilder, DiffElement, ElementError};
+    /// let err = DiffBuilder::new().with_element(DiffElement::new()).unwrap_err();
+    /// assert_eq!(err, ElementError::Empty { path: Default::default() });
+    /// ```
+    pub fn with_element(mut self, element: DiffElement) -> Result<Self, ElementError> {
+        let mut metadata = self.metadata.clone();
+        if let Some(own) = &element.metadata {
+            metadata.absorb(own);
+        }
+        element.validate_as(metadata.merge)?;
+        self.metadata = metadata;
+        self.elements.push(element);
+        Ok(self)
+    }
+
+    /// Returns the diff built so far.
+    ///
+    /// ```
+    /// # use jd_core::{diff::PathSegment, DiffBuilder, DiffElement, Node, RenderConfig};
+    /// let diff = DiffBuilder::new()
+    ///     .with_element(DiffElement::new().with_path(PathSegment::key("a")).with_add(vec![Node::Null]))
+    ///     .unwrap()
+    ///     .build();
+    /// assert_eq!(diff.render(&RenderConfig::default()), "@ [\"a\"]\n+ null\n");
+    /// ```
+    #[must_use]
+    pub fn build(self) -> Diff {
+        Diff::from_elements(self.elements)
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::{Location, Path};
+
 /// Errors that can occur while canonicalizing external data into [`Node`](crate::Node).
 ///
 /// ```
@@ -8,6 +10,7 @@ use thiserror::Error;
 /// assert!(matches!(err, jd_core::CanonicalizeError::Json(_)));
 /// ```
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CanonicalizeError {
     /// The provided JSON input was invalid.
     #[error("invalid JSON: {0}")]
@@ -15,6 +18,20 @@ pub enum CanonicalizeError {
     /// The provided YAML input was invalid.
     #[error("invalid YAML: {0}")]
     Yaml(#[from] serde_yaml::Error),
+    /// The provided TOML input was invalid.
+    #[cfg(feature = "toml")]
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    /// The provided CBOR input was invalid or used types outside the JSON
+    /// data model (byte strings, non-string map keys, tags).
+    #[cfg(feature = "cbor")]
+    #[error("invalid CBOR: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+    /// The provided MessagePack input was invalid or used types outside the
+    /// JSON data model (binary, extensions, non-string map keys).
+    #[cfg(feature = "msgpack")]
+    #[error("invalid MessagePack: {0}")]
+    MessagePack(#[from] rmp_serde::decode::Error),
     /// Encountered a number that cannot be represented as an IEEE-754 f64.
     #[error("number {value} cannot be represented as f64")]
     NumberOutOfRange {
@@ -40,6 +57,71 @@ pub enum CanonicalizeError {
         /// The offending numeric value.
         value: f64,
     },
+    /// The input exceeded a configured [`Limits`](crate::Limits) bound.
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+}
+
+impl CanonicalizeError {
+    /// Locates a syntax error within `input`, the text that failed to parse.
+    ///
+    /// Returns `None` for errors without a position (such as out-of-range
+    /// numbers) and for binary formats. JSON locations also carry a
+    /// best-effort [`path`](crate::Location::path) to the enclosing value.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let input = "{\n  \"a\": [1, oops]\n}";
+    /// let location = Node::from_json_str(input).unwrap_err().location(input).unwrap();
+    /// assert_eq!((location.line(), location.column()), (2, 12));
+    /// assert_eq!(location.path().unwrap().to_string(), "[a 1]");
+    /// ```
+    #[must_use]
+    pub fn location(&self, input: &str) -> Option<Location> {
+        match self {
+            Self::Json(err) if err.line() > 0 => {
+                let location = Location::from_line_column(input, err.line(), err.column());
+                let end = if err.is_eof() { input.len() } else { location.offset() };
+                Some(location.with_json_path(input, end))
+            }
+            Self::Yaml(err) => err.location().map(|loc| Location::from_offset(input, loc.index())),
+            #[cfg(feature = "toml")]
+            Self::Toml(err) => err.span().map(|span| Location::from_offset(input, span.start)),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can occur while encoding a [`Node`](crate::Node) into an external format.
+///
+/// ```
+/// # use jd_core::{EncodeError, Node};
+/// # #[cfg(feature = "toml")]
+/// # {
+/// let err = Node::from_json_str("[1]").unwrap().to_toml_string().unwrap_err();
+/// assert!(matches!(err, EncodeError::Toml(_)));
+/// # }
+/// assert!(matches!(Node::Void.to_yaml_string(), None));
+/// ```
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The void sentinel has no representation outside jd.
+    #[error("cannot encode void value")]
+    Void,
+    /// The node cannot be represented as TOML (for example a non-table root
+    /// or a `null` value).
+    #[cfg(feature = "toml")]
+    #[error("cannot encode as TOML: {0}")]
+    Toml(#[from] toml::ser::Error),
+    /// CBOR serialization failed.
+    #[cfg(feature = "cbor")]
+    #[error("cannot encode as CBOR: {0}")]
+    Cbor(#[from] ciborium::ser::Error<std::io::Error>),
+    /// MessagePack serialization failed.
+    #[cfg(feature = "msgpack")]
+    #[error("cannot encode as MessagePack: {0}")]
+    MessagePack(#[from] rmp_serde::encode::Error),
 }
 
 /// Errors emitted when constructing [`DiffOptions`](crate::DiffOptions).
@@ -52,15 +134,243 @@ pub enum CanonicalizeError {
 ///     .unwrap_err();
 /// assert!(matches!(err, jd_core::OptionsError::PrecisionIncompatible));
 /// ```
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum OptionsError {
     /// Precision tolerance is incompatible with set or multiset semantics.
     #[error("precision tolerance cannot be combined with set or multiset array modes")]
     PrecisionIncompatible,
-    /// Set keys require arrays to operate in set mode.
-    #[error("set keys require array mode to be set")]
+    /// Set keys require arrays to operate in set or list mode.
+    #[error("set keys require array mode to be set or list")]
     SetKeysRequireSetMode,
+    /// Numeric tolerances must be finite and non-negative.
+    #[error("numeric tolerance must be a finite, non-negative number")]
+    InvalidTolerance,
     /// Set keys must be non-empty strings.
     #[error("set keys must be non-empty strings")]
     EmptySetKey,
+    /// Similarity thresholds must lie in `(0, 1]`.
+    #[error("similarity threshold must be greater than 0 and at most 1")]
+    InvalidSimilarity,
+    /// Hunk limits must allow at least one hunk.
+    #[error("hunk limit must be at least 1")]
+    InvalidMaxHunks,
+    /// A JSON Schema keyword used to derive options is malformed.
+    #[error("invalid JSON Schema at {pointer}: {reason}")]
+    InvalidSchema {
+        /// JSON Pointer to the offending keyword, relative to the schema root.
+        pointer: String,
+        /// What is wrong with it.
+        reason: String,
+    },
+    /// The options ask for an array mode the diff engine does not implement
+    /// yet.
+    #[error("array mode {0} is not supported by the diff engine yet")]
+    UnsupportedArrayMode(crate::ArrayMode),
+    /// A JSON Schema keyword asks for semantics the diff engine does not
+    /// implement yet.
+    #[error("unsupported JSON Schema keyword at {pointer}: {reason}")]
+    UnsupportedSchema {
+        /// JSON Pointer to the keyword, relative to the schema root.
+        pointer: String,
+        /// What the keyword would need.
+        reason: String,
+    },
+    /// An option list in the format of Go `jd`'s `-opts` flag is malformed.
+    #[error("invalid options: {reason}")]
+    InvalidOpts {
+        /// What is wrong with it.
+        reason: String,
+    },
+}
+
+/// A [`DiffElement`](crate::DiffElement) breaks an invariant of the native
+/// diff format, so it would fail to render or apply.
+///
+/// ```
+/// # use jd_core::{diff::PathSegment, DiffElement, ElementError, Node};
+/// let element = DiffElement::new()
+///     .with_path(PathSegment::key("a"))
+///     .with_before(vec![Node::Null])
+///     .with_add(vec![Node::Null]);
+/// assert!(matches!(element.validate(), Err(ElementError::ContextWithoutIndex { .. })));
+/// ```
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ElementError {
+    /// The element neither removes nor adds values.
+    #[error("diff element at {path} has no changes")]
+    Empty {
+        /// Path of the element.
+        path: Path,
+    },
+    /// A removed or added value is void; only context may be void.
+    #[error("diff element at {path} removes or adds a void value")]
+    VoidValue {
+        /// Path of the element.
+        path: Path,
+    },
+    /// The element has context but its path does not end in an array index.
+    #[error("diff element at {path} has list context but does not end in an array index")]
+    ContextWithoutIndex {
+        /// Path of the element.
+        path: Path,
+    },
+    /// The element has more than one before or after context value.
+    #[error("diff element at {path} has more than one before or after context value")]
+    ExcessContext {
+        /// Path of the element.
+        path: Path,
+    },
+    /// The element removes or adds several values at a path that holds one.
+    #[error(
+        "diff element at {path} changes multiple values but does not end in an array index or set"
+    )]
+    MultipleValues {
+        /// Path of the element.
+        path: Path,
+    },
+    /// A merge element addresses something other than object members.
+    #[error("merge diff element at {path} must only address object members")]
+    MergePath {
+        /// Path of the element.
+        path: Path,
+    },
+    /// A merge element removes values; merge patches only set or delete.
+    #[error("merge diff element at {path} removes values")]
+    MergeRemoval {
+        /// Path of the element.
+        path: Path,
+    },
+    /// A merge element does not add exactly one value (void to delete).
+    #[error("merge diff element at {path} must add exactly one value")]
+    MergeAddition {
+        /// Path of the element.
+        path: Path,
+    },
+}
+
+/// A [`Limits`](crate::Limits) bound was exceeded.
+///
+/// ```
+/// # use jd_core::{LimitError, Limits};
+/// let err = Limits::default().with_max_input_bytes(8).check_input_len(9).unwrap_err();
+/// assert_eq!(err.to_string(), "input exceeds the limit of 8 bytes");
+/// ```
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LimitError {
+    /// The input is longer than the maximum input size.
+    #[error("input exceeds the limit of {limit} bytes")]
+    InputTooLarge {
+        /// The configured maximum in bytes.
+        limit: usize,
+    },
+    /// The document holds more values than allowed.
+    #[error("document has more than {limit} nodes")]
+    TooManyNodes {
+        /// The configured maximum node count.
+        limit: usize,
+    },
+    /// Arrays and objects are nested deeper than allowed.
+    #[error("document is nested deeper than {limit} levels")]
+    TooDeep {
+        /// The configured maximum depth.
+        limit: usize,
+    },
+    /// The diff ran out of time and was abandoned.
+    #[error("diff did not finish within {timeout:?}")]
+    Timeout {
+        /// The configured wall-clock budget.
+        timeout: std::time::Duration,
+    },
+}
+
+/// Errors returned by the [`Jd`](crate::Jd) facade.
+///
+/// ```
+/// # use jd_core::{Jd, JdError};
+/// let err = Jd::new().diff_str("[1,", "[]").unwrap_err();
+/// assert!(matches!(err, JdError::Canonicalize(_)));
+/// ```
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum JdError {
+    /// One of the inputs could not be canonicalized.
+    #[error(transparent)]
+    Canonicalize(#[from] CanonicalizeError),
+    /// The configured options are invalid.
+    #[error(transparent)]
+    Options(#[from] OptionsError),
+    /// A configured resource limit was exceeded while diffing.
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+    /// The requested array mode has no diff engine yet.
+    #[error("array mode {0} is not supported by the diff engine yet")]
+    UnsupportedArrayMode(crate::ArrayMode),
+    /// The diff could not be rendered in the requested format.
+    #[error(transparent)]
+    Render(#[from] crate::RenderError),
+}
+
+/// Errors returned by [`Diff::apply_to_json_str`](crate::Diff::apply_to_json_str).
+///
+/// ```
+/// # use jd_core::{DiffOptions, JsonEditError, Node};
+/// let input = r#"{"a": 1, "b": 0, "a": 2}"#;
+/// let target = Node::from_json_str(r#"{"b": 0}"#).unwrap();
+/// let diff = Node::from_json_str(input).unwrap().diff(&target, &DiffOptions::default());
+/// let err = diff.apply_to_json_str(input).unwrap_err();
+/// assert!(matches!(err, JsonEditError::Unsupported { .. }));
+/// ```
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum JsonEditError {
+    /// The document could not be canonicalized.
+    #[error(transparent)]
+    Canonicalize(#[from] CanonicalizeError),
+    /// The diff does not apply to the document.
+    #[error(transparent)]
+    Patch(#[from] crate::PatchError),
+    /// The change cannot be written into the text in place; patch the
+    /// parsed node and serialize it instead.
+    #[error("cannot edit JSON in place at {path}: {reason}")]
+    Unsupported {
+        /// The value whose edit failed, or the root for whole-document problems.
+        path: Path,
+        /// Why the edit failed.
+        reason: String,
+    },
+}
+
+/// Errors returned by [`Diff::apply_to_yaml_str`](crate::Diff::apply_to_yaml_str).
+/// Requires the `yaml-edit` feature.
+///
+/// ```
+/// # use jd_core::{DiffOptions, Node, YamlEditError};
+/// let input = "base: &b 1\ncopy: *b\n";
+/// let target = Node::from_json_str(r#"{"base": 2, "copy": 1}"#).unwrap();
+/// let diff = Node::from_yaml_str(input).unwrap().diff(&target, &DiffOptions::default());
+/// let err = diff.apply_to_yaml_str(input).unwrap_err();
+/// assert!(matches!(err, YamlEditError::Unsupported { .. }));
+/// ```
+#[cfg(feature = "yaml-edit")]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum YamlEditError {
+    /// The document could not be canonicalized.
+    #[error(transparent)]
+    Canonicalize(#[from] CanonicalizeError),
+    /// The diff does not apply to the document.
+    #[error(transparent)]
+    Patch(#[from] crate::PatchError),
+    /// The change cannot be written into the text in place; patch the
+    /// parsed node and re-render it instead.
+    #[error("cannot edit YAML in place at {path}: {reason}")]
+    Unsupported {
+        /// The value whose edit failed, or the root for whole-document problems.
+        path: Path,
+        /// Why the edit failed.
+        reason: String,
+    },
 }
@@ -0,0 +1,122 @@
+//! Helpers for GitHub webhook payloads and REST API responses.
+//!
+//! Replaying a webhook, or fetching the same issue twice, yields payloads
+//! that differ in bookkeeping GitHub rewrites on every read: timestamps
+//! such as `updated_at`, GraphQL `node_id`s, and cache validators.
+//! [`volatile_path_options`] turns diffing off for those members wherever
+//! they appear, so diffs show what actually changed and still apply to the
+//! payloads as written. [`Preset::GitHub`](crate::Preset::GitHub) adds
+//! them. [`strip_volatile`] drops the members instead, as
+//! `jd --ignore '$..updated_at'` would.
+//!
+//! ```
+//! use jd_core::{github, node, DiffOptions};
+//!
+//! let delivery = |updated: &str, title: &str| node!({"action": "edited",
+//!     "issue": {"node_id": "I_kw1", "title": title, "updated_at": updated,
+//!         "user": {"login": "octocat", "node_id": "U_kg1"}}});
+//! let first = delivery("2024-07-03T10:12:00Z", "Crash");
+//! let replay = delivery("2024-07-04T08:00:00Z", "Crash");
+//! let options = github::volatile_path_options([&first, &replay])
+//!     .into_iter()
+//!     .try_fold(DiffOptions::default(), DiffOptions::with_path_option)
+//!     .unwrap();
+//! assert!(first.diff(&replay, &options).is_empty());
+//! ```
+
+use crate::{Node, PathMatcher, PathOption, PathSetting};
+
+/// Members [`volatile_path_options`] and [`strip_volatile`] leave out
+/// wherever they appear.
+///
+/// `updated_at` and `pushed_at` move on every write, `node_id` is an
+/// opaque GraphQL id that says nothing `id` does not, and `etag` /
+/// `last_modified` are HTTP cache validators copied into some API bodies.
+pub const VOLATILE_FIELDS: [&str; 5] =
+    ["etag", "last_modified", "node_id", "pushed_at", "updated_at"];
+
+/// Returns a copy of the payload without the [`VOLATILE_FIELDS`] in any
+/// object, however deeply nested.
+///
+/// ```
+/// # use jd_core::{github, node};
+/// let payload = node!({"repository": {"name": "jd", "pushed_at": 1720000000},
+///     "commits": [{"id": "abc", "node_id": "C_1"}]});
+/// let stripped = node!({"repository": {"name": "jd"}, "commits": [{"id": "abc"}]});
+/// assert_eq!(github::strip_volatile(&payload), stripped);
+/// ```
+#[must_use]
+pub fn strip_volatile(payload: &Node) -> Node {
+    let mut stripped = payload.clone();
+    let mut stack = vec![&mut stripped];
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Array(items) => stack.extend(items.iter_mut()),
+            Node::Object(map) => {
+                map.retain(|key, _| !VOLATILE_FIELDS.contains(&key.as_str()));
+                stack.extend(map.values_mut());
+            }
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Returns `DiffOff` path options for every location of the
+/// [`VOLATILE_FIELDS`] in any of `payloads`.
+///
+/// Array indices along the way match any index, so a member is skipped in
+/// every element of an array once it appears in one, and elements that
+/// differ only in volatile members compare equal.
+///
+/// ```
+/// # use jd_core::{github, node, PathMatcher};
+/// let payload = node!({"labels": [{"name": "bug", "node_id": "L_1"}], "updated_at": 1});
+/// let options = github::volatile_path_options([&payload]);
+/// let label_id = [PathMatcher::key("labels"), PathMatcher::AnyIndex, PathMatcher::key("node_id")];
+/// assert_eq!(options.len(), 2);
+/// assert!(options.iter().any(|option| option.at() == label_id));
+/// ```
+#[must_use]
+pub fn volatile_path_options<'a>(payloads: impl IntoIterator<Item = &'a Node>) -> Vec<PathOption> {
+    let mut paths: Vec<Vec<PathMatcher>> = Vec::new();
+    let mut stack: Vec<(&Node, Vec<PathMatcher>)> =
+        payloads.into_iter().map(|payload| (payload, Vec::new())).collect();
+    while let Some((node, at)) = stack.pop() {
+        match node {
+            Node::Array(items) => {
+                let mut at = at;
+                at.push(PathMatcher::AnyIndex);
+                stack.extend(items.iter().map(|item| (item, at.clone())));
+            }
+            Node::Object(map) => {
+                for (key, value) in map.iter() {
+                    let mut at = at.clone();
+                    at.push(PathMatcher::key(key.as_str()));
+                    if !VOLATILE_FIELDS.contains(&key.as_str()) {
+                        stack.push((value, at));
+                    } else if !paths.contains(&at) {
+                        paths.push(at);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    paths.into_iter().map(|at| PathOption::new(at).with_setting(PathSetting::DiffOff)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node;
+
+    #[test]
+    fn keeps_volatile_names_as_values_and_other_members() {
+        let payload = node!({"changes": {"title": {"from": "updated_at"}}, "etag": "W/\"1\"",
+            "pull_request": {"head": {"sha": "abc", "repo": {"updated_at": "x", "forks": 1}}}});
+        let stripped = node!({"changes": {"title": {"from": "updated_at"}},
+            "pull_request": {"head": {"sha": "abc", "repo": {"forks": 1}}}});
+        assert_eq!(strip_volatile(&payload), stripped);
+    }
+}
@@ -1,3 +1,9 @@
+use std::fmt;
+use std::hash::{BuildHasher, RandomState};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+use serde::{Deserialize, Serialize};
+
 /// Type alias representing the 64-bit hash code used throughout the diff engine.
 ///
 /// ```
@@ -17,15 +23,33 @@ pub type HashCode = [u8; 8];
 /// ```
 #[must_use]
 pub fn hash_bytes(input: &[u8]) -> HashCode {
+    let mut hasher = Fnv::new();
+    hasher.write(input);
+    hasher.finish()
+}
+
+/// FNV-1a over input fed in pieces, for hashing streams that are never
+/// held in one buffer.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
     const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
     const PRIME: u64 = 0x100000001b3;
 
-    let mut hash = OFFSET_BASIS;
-    for byte in input {
-        hash ^= u64::from(*byte);
-        hash = hash.wrapping_mul(PRIME);
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    pub(crate) fn write(&mut self, input: &[u8]) {
+        for byte in input {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> HashCode {
+        self.0.to_le_bytes()
     }
-    hash.to_le_bytes()
 }
 
 /// Combine a collection of hash codes into a single aggregate hash.
@@ -45,3 +69,462 @@ pub fn combine(mut codes: Vec<HashCode>) -> HashCode {
     }
     hash_bytes(&bytes)
 }
+
+/// Hash function used to match array elements in set and multiset
+/// comparisons and in list alignment, selected with
+/// [`DiffOptions::with_hash_algorithm`](crate::DiffOptions::with_hash_algorithm).
+///
+/// Matching trusts hashes: two elements with the same hash are treated as
+/// equal. The default 64-bit FNV-1a is fast and identical to Go `jd`, but
+/// collisions can be found on purpose, so a crafted input could make
+/// different elements match and corrupt a diff. When diffing untrusted data,
+/// use [`HashAlgorithm::sip_hash_128`], which breaks hash parity with Go
+/// `jd` (diffs of ordinary documents are unaffected).
+///
+/// ```
+/// # use jd_core::{DiffOptions, HashAlgorithm, Node};
+/// let opts = DiffOptions::default().with_hash_algorithm(HashAlgorithm::sip_hash_128());
+/// let node = Node::from_json_str("[1, 2]").unwrap();
+/// assert_ne!(node.hash_code(&opts), node.hash_code(&DiffOptions::default()));
+/// assert!(node.diff(&Node::from_json_str("[1, 3]").unwrap(), &opts).len() == 1);
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    /// 64-bit FNV-1a, bit-for-bit compatible with Go `jd` (default).
+    #[default]
+    Fnv64,
+    /// 128-bit SipHash-2-4 under a secret key, so collisions cannot be
+    /// precomputed and are negligibly likely by chance.
+    SipHash128 {
+        /// The SipHash key. It is never serialized or formatted with `{:?}`,
+        /// so options written out or logged do not leak it; deserializing
+        /// draws a fresh random key.
+        #[serde(skip, default = "random_key")]
+        key: [u64; 2],
+    },
+    /// 64-bit XXH3 for strings, numbers, and keys, which it hashes several
+    /// bytes at a time with SIMD instructions where the target has them,
+    /// and FNV-1a over the digests of array elements and object members.
+    /// Faster than FNV-1a alone, but unkeyed, so not for untrusted input.
+    #[cfg(feature = "simd")]
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// SipHash-2-4 with 128-bit output under a key drawn at random, different
+    /// in every call. Hash codes are then only comparable between nodes
+    /// hashed with the same options.
+    ///
+    /// ```
+    /// # use jd_core::HashAlgorithm;
+    /// assert_ne!(HashAlgorithm::sip_hash_128(), HashAlgorithm::sip_hash_128());
+    /// ```
+    #[must_use]
+    pub fn sip_hash_128() -> Self {
+        Self::SipHash128 { key: random_key() }
+    }
+
+    /// Bytes of each digest that carry the hash.
+    pub(crate) fn width(self) -> usize {
+        match self {
+            Self::Fnv64 => 8,
+            Self::SipHash128 { .. } => 16,
+            #[cfg(feature = "simd")]
+            Self::Xxh3 => 8,
+        }
+    }
+
+    /// Starts hashing a value of the kind `tag`. FNV ignores the tag so its
+    /// digests stay those of Go `jd`; SipHash mixes it in so that, say, the
+    /// string `"null"` and `null` differ. XXH3 only hashes whole scalars,
+    /// so containers stream their children's digests through FNV, which
+    /// needs no buffer.
+    pub(crate) fn hasher(self, tag: u8) -> StreamHasher<'static> {
+        match self {
+            Self::Fnv64 => StreamHasher::Fnv(Fnv::new()),
+            Self::SipHash128 { key } => {
+                let mut hasher = Sip128::new(key);
+                hasher.write(&[tag]);
+                StreamHasher::Sip(hasher)
+            }
+            #[cfg(feature = "simd")]
+            Self::Xxh3 => StreamHasher::Fnv(Fnv::new()),
+        }
+    }
+
+    pub(crate) fn digest(self, tag: u8, input: &[u8]) -> Digest {
+        #[cfg(feature = "simd")]
+        if self == Self::Xxh3 {
+            return xxh3(tag, input);
+        }
+        let mut hasher = self.hasher(tag);
+        hasher.write(input);
+        hasher.finish()
+    }
+
+    /// Digest of a value without content: the Go constant `code` under FNV.
+    pub(crate) fn constant(self, code: HashCode, tag: u8) -> Digest {
+        match self {
+            Self::Fnv64 => widen(code),
+            Self::SipHash128 { .. } => self.digest(tag, &[]),
+            #[cfg(feature = "simd")]
+            Self::Xxh3 => self.digest(tag, &[]),
+        }
+    }
+}
+
+impl fmt::Debug for HashAlgorithm {
+    /// Formats the algorithm with the SipHash key redacted, since options
+    /// end up in logs and panic messages.
+    ///
+    /// ```
+    /// # use jd_core::HashAlgorithm;
+    /// assert_eq!(format!("{:?}", HashAlgorithm::Fnv64), "Fnv64");
+    /// assert_eq!(format!("{:?}", HashAlgorithm::sip_hash_128()), "SipHash128 { key: <redacted> }");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fnv64 => f.write_str("Fnv64"),
+            Self::SipHash128 { .. } => {
+                f.debug_struct("SipHash128").field("key", &format_args!("<redacted>")).finish()
+            }
+            #[cfg(feature = "simd")]
+            Self::Xxh3 => f.write_str("Xxh3"),
+        }
+    }
+}
+
+impl NodeHasher for HashAlgorithm {
+    fn width(&self) -> usize {
+        HashAlgorithm::width(*self)
+    }
+
+    fn digest(&self, tag: u8, input: &[u8]) -> Digest {
+        HashAlgorithm::digest(*self, tag, input)
+    }
+}
+
+fn random_key() -> [u64; 2] {
+    let state = RandomState::new();
+    [state.hash_one(0u8), state.hash_one(1u8)]
+}
+
+/// A hash of a value as used for matching. Only the first
+/// [`NodeHasher::width`] bytes are significant; the rest are zero.
+pub type Digest = [u8; 16];
+
+/// Hashing supplied by an embedder for matching array elements in set and
+/// multiset comparisons and in list alignment, installed with
+/// [`DiffOptions::with_hasher`](crate::DiffOptions::with_hasher).
+///
+/// Nodes are hashed bottom up. Scalars are hashed from their bytes (UTF-8
+/// for strings, little-endian IEEE-754 for numbers, nothing for `null`,
+/// booleans, and void); arrays and objects from a seed followed by the
+/// first [`width`](NodeHasher::width) bytes of their elements' digests,
+/// and of their keys' and values' digests in key order. `tag` names the
+/// kind of value so different kinds with the same bytes can hash apart:
+/// `b'z'` null, `b't'`/`b'f'` booleans, `b'v'` void, `b'n'` numbers,
+/// `b's'` strings, `b'k'` object keys, `b'l'` lists, `b'o'` objects, and
+/// `b'c'` the sorted digests of sets and multisets.
+///
+/// Elements whose digests are equal are treated as equal, so a hasher
+/// trades speed against the chance of such collisions. [`HashAlgorithm`]
+/// implements the built-in choices. Hashers must be unwind safe so that
+/// options holding one stay usable across `catch_unwind`.
+///
+/// ```
+/// use std::hash::{DefaultHasher, Hasher};
+/// use jd_core::{ArrayMode, Digest, DiffOptions, Node, NodeHasher};
+///
+/// /// SipHash-1-3 with a fixed key, stable across processes.
+/// #[derive(Debug)]
+/// struct StdHasher;
+///
+/// impl NodeHasher for StdHasher {
+///     fn width(&self) -> usize {
+///         8
+///     }
+///
+///     fn digest(&self, tag: u8, input: &[u8]) -> Digest {
+///         let mut hasher = DefaultHasher::new();
+///         hasher.write_u8(tag);
+///         hasher.write(input);
+///         let mut digest = [0; 16];
+///         digest[..8].copy_from_slice(&hasher.finish().to_le_bytes());
+///         digest
+///     }
+/// }
+///
+/// let opts = DiffOptions::default().with_array_mode(ArrayMode::Set).unwrap().with_hasher(StdHasher);
+/// let lhs = Node::from_json_str("[1, 2, 2]").unwrap();
+/// assert!(lhs.eq_with_options(&Node::from_json_str("[2, 1]").unwrap(), &opts));
+/// assert!(!lhs.eq_with_options(&Node::from_json_str("[2, 3]").unwrap(), &opts));
+/// ```
+pub trait NodeHasher: fmt::Debug + Send + Sync + RefUnwindSafe + UnwindSafe {
+    /// Number of leading bytes of each digest that carry the hash, from 1
+    /// to 16. Wider digests collide less but cost more to combine.
+    fn width(&self) -> usize;
+
+    /// Hashes `input`, the bytes of a value of kind `tag`.
+    fn digest(&self, tag: u8, input: &[u8]) -> Digest;
+}
+
+/// The hashing configured in some options: a built-in algorithm, hashed
+/// incrementally, or an embedder's [`NodeHasher`].
+#[derive(Clone, Copy)]
+pub(crate) enum Hashing<'a> {
+    Builtin(HashAlgorithm),
+    Custom(&'a dyn NodeHasher),
+}
+
+impl<'a> Hashing<'a> {
+    pub(crate) fn width(self) -> usize {
+        match self {
+            Self::Builtin(algorithm) => algorithm.width(),
+            Self::Custom(hasher) => hasher.width().clamp(1, 16),
+        }
+    }
+
+    pub(crate) fn hasher(self, tag: u8) -> StreamHasher<'a> {
+        match self {
+            Self::Builtin(algorithm) => algorithm.hasher(tag),
+            Self::Custom(hasher) => StreamHasher::Buffered { hasher, tag, bytes: Vec::new() },
+        }
+    }
+
+    pub(crate) fn digest(self, tag: u8, input: &[u8]) -> Digest {
+        match self {
+            Self::Builtin(algorithm) => algorithm.digest(tag, input),
+            Self::Custom(hasher) => truncate(hasher.digest(tag, input), self.width()),
+        }
+    }
+
+    pub(crate) fn constant(self, code: HashCode, tag: u8) -> Digest {
+        match self {
+            Self::Builtin(algorithm) => algorithm.constant(code, tag),
+            Self::Custom(_) => self.digest(tag, &[]),
+        }
+    }
+}
+
+/// Zeroes the bytes past `width`, so digests compare on their hash alone.
+fn truncate(mut digest: Digest, width: usize) -> Digest {
+    digest[width..].fill(0);
+    digest
+}
+
+pub(crate) fn widen(code: HashCode) -> Digest {
+    let mut digest = [0; 16];
+    digest[..8].copy_from_slice(&code);
+    digest
+}
+
+pub(crate) enum StreamHasher<'a> {
+    Fnv(Fnv),
+    Sip(Sip128),
+    /// A [`NodeHasher`] sees its whole input at once.
+    Buffered {
+        hasher: &'a dyn NodeHasher,
+        tag: u8,
+        bytes: Vec<u8>,
+    },
+}
+
+impl StreamHasher<'_> {
+    pub(crate) fn write(&mut self, input: &[u8]) {
+        match self {
+            Self::Fnv(hasher) => hasher.write(input),
+            Self::Sip(hasher) => hasher.write(input),
+            Self::Buffered { bytes, .. } => bytes.extend_from_slice(input),
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Digest {
+        match self {
+            Self::Fnv(hasher) => widen(hasher.finish()),
+            Self::Sip(hasher) => hasher.finish(),
+            Self::Buffered { hasher, tag, bytes } => {
+                truncate(hasher.digest(*tag, bytes), hasher.width().clamp(1, 16))
+            }
+        }
+    }
+}
+
+/// 64-bit XXH3 seeded with the tag, so kinds hash apart without copying
+/// the input behind a prefix. One-shot XXH3 beats FNV-1a from 8 bytes up,
+/// while its streaming state, which would have to buffer containers'
+/// input, does not.
+#[cfg(feature = "simd")]
+fn xxh3(tag: u8, input: &[u8]) -> Digest {
+    widen(xxhash_rust::xxh3::xxh3_64_with_seed(input, tag.into()).to_le_bytes())
+}
+
+/// SipHash-2-4 with 128-bit output, over input fed in pieces.
+#[derive(Clone)]
+pub(crate) struct Sip128 {
+    v: [u64; 4],
+    /// Bytes not yet forming a whole word, in the low end.
+    tail: u64,
+    length: usize,
+}
+
+impl Sip128 {
+    fn new([k0, k1]: [u64; 2]) -> Self {
+        Self {
+            v: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d ^ 0xee,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            length: 0,
+        }
+    }
+
+    fn write(&mut self, input: &[u8]) {
+        for &byte in input {
+            self.tail |= u64::from(byte) << (8 * (self.length % 8));
+            self.length += 1;
+            if self.length % 8 == 0 {
+                self.compress(self.tail);
+                self.tail = 0;
+            }
+        }
+    }
+
+    fn finish(&self) -> Digest {
+        let mut state = self.clone();
+        state.compress(state.tail | ((self.length as u64) << 56));
+        state.v[2] ^= 0xee;
+        let low = state.rounds(4);
+        state.v[1] ^= 0xdd;
+        let high = state.rounds(4);
+        let mut digest = [0; 16];
+        digest[..8].copy_from_slice(&low.to_le_bytes());
+        digest[8..].copy_from_slice(&high.to_le_bytes());
+        digest
+    }
+
+    fn compress(&mut self, word: u64) {
+        self.v[3] ^= word;
+        self.rounds(2);
+        self.v[0] ^= word;
+    }
+
+    /// Runs `count` SipRounds and returns the XOR of the state.
+    fn rounds(&mut self, count: usize) -> u64 {
+        let [v0, v1, v2, v3] = &mut self.v;
+        for _ in 0..count {
+            *v0 = v0.wrapping_add(*v1);
+            *v1 = v1.rotate_left(13) ^ *v0;
+            *v0 = v0.rotate_left(32);
+            *v2 = v2.wrapping_add(*v3);
+            *v3 = v3.rotate_left(16) ^ *v2;
+            *v0 = v0.wrapping_add(*v3);
+            *v3 = v3.rotate_left(21) ^ *v0;
+            *v2 = v2.wrapping_add(*v1);
+            *v1 = v1.rotate_left(17) ^ *v2;
+            *v2 = v2.rotate_left(32);
+        }
+        *v0 ^ *v1 ^ *v2 ^ *v3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_output_redacts_the_sip_hash_key() {
+        let key = [0x0123_4567_89ab_cdef, 0x0fed_cba9_8765_4321];
+        let algorithm = HashAlgorithm::SipHash128 { key };
+        let options = crate::DiffOptions::default().with_hash_algorithm(algorithm);
+        let jd = crate::Jd::with_options(options.clone());
+        for debug in [format!("{algorithm:?}"), format!("{options:?}"), format!("{jd:?}")] {
+            assert!(debug.contains("<redacted>"), "{debug}");
+            for part in key {
+                assert!(!debug.contains(&part.to_string()), "{debug}");
+                assert!(!debug.contains(&format!("{part:x}")), "{debug}");
+            }
+        }
+    }
+
+    #[test]
+    fn sip_hash_128_matches_reference_vectors() {
+        // From the SipHash reference implementation, key 00 01 .. 0f.
+        let key = [0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908];
+        let algorithm = HashAlgorithm::SipHash128 { key };
+        let mut empty = Sip128::new(key);
+        empty.write(&[]);
+        assert_eq!(
+            empty.finish(),
+            [
+                0xa3, 0x81, 0x7f, 0x04, 0xba, 0x25, 0xa8, 0xe6, 0x6d, 0xf6, 0x72, 0x14, 0xc7, 0x55,
+                0x02, 0x93
+            ]
+        );
+        // Tagged digests hash the tag byte first.
+        assert_eq!(
+            algorithm.digest(0x00, &[]),
+            [
+                0xda, 0x87, 0xc1, 0xd8, 0x6b, 0x99, 0xaf, 0x44, 0x34, 0x76, 0x59, 0x11, 0x9b, 0x22,
+                0xfc, 0x45
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_digests_are_truncated_to_their_width() {
+        #[derive(Debug)]
+        struct Ones;
+        impl NodeHasher for Ones {
+            fn width(&self) -> usize {
+                3
+            }
+            fn digest(&self, _: u8, _: &[u8]) -> Digest {
+                [1; 16]
+            }
+        }
+        let hashing = Hashing::Custom(&Ones);
+        assert_eq!(hashing.digest(b's', b"x"), [1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(hashing.hasher(b'l').finish(), hashing.digest(b'l', &[]));
+    }
+
+    #[test]
+    fn sip_hash_keys_are_not_serialized() {
+        let algorithm = HashAlgorithm::sip_hash_128();
+        let json = serde_json::to_string(&algorithm).unwrap();
+        assert_eq!(json, r#"{"SipHash128":{}}"#);
+        let read: HashAlgorithm = serde_json::from_str(&json).unwrap();
+        assert!(matches!(read, HashAlgorithm::SipHash128 { .. }));
+        assert_ne!(read, algorithm);
+    }
+
+    #[test]
+    fn fnv_digests_are_go_hash_codes() {
+        assert_eq!(HashAlgorithm::Fnv64.digest(b's', b"diff")[..8], hash_bytes(b"diff"));
+        assert_eq!(HashAlgorithm::Fnv64.digest(b's', b"diff")[8..], [0; 8]);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn xxh3_hashes_scalars_and_streams_containers() {
+        let long = "x".repeat(1000);
+        for input in [&b""[..], b"diff", long.as_bytes()] {
+            let digest = HashAlgorithm::Xxh3.digest(b's', input);
+            assert_eq!(
+                digest[..8],
+                xxhash_rust::xxh3::xxh3_64_with_seed(input, b's'.into()).to_le_bytes()
+            );
+            assert_eq!(digest[8..], [0; 8]);
+            let mut hasher = HashAlgorithm::Xxh3.hasher(b'l');
+            for chunk in input.chunks(7) {
+                hasher.write(chunk);
+            }
+            assert_eq!(hasher.finish(), HashAlgorithm::Fnv64.digest(b'l', input));
+        }
+        assert_ne!(HashAlgorithm::Xxh3.digest(b's', b""), HashAlgorithm::Xxh3.digest(b'k', b""));
+    }
+}
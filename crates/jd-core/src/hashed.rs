@@ -0,0 +1,349 @@
+//! Documents whose subtree hashes are computed once and reused.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::hash::{Digest, HashCode};
+use crate::{Diff, DiffOptions, Node};
+
+/// A document paired with the hash of every value in it, so it can be
+/// compared against many others without hashing it again.
+///
+/// Diffing arrays and comparing sets hashes every element, and nested
+/// arrays are hashed once per level they are matched at. When one base
+/// document is diffed against many candidates, as a server checking
+/// configs against a golden copy does, wrap the base once; each diff then
+/// only hashes the candidate.
+///
+/// Hashes depend on the options, so they are fixed at construction and
+/// every comparison uses them.
+///
+/// ```
+/// use jd_core::{DiffOptions, HashedNode, Node};
+///
+/// let golden = Node::from_json_str(r#"{"servers": [{"host": "a"}, {"host": "b"}]}"#).unwrap();
+/// let golden = HashedNode::new(golden, &DiffOptions::default());
+/// for candidate in [r#"{"servers": [{"host": "b"}]}"#, r#"{"servers": []}"#] {
+///     let candidate = Node::from_json_str(candidate).unwrap();
+///     assert_eq!(golden.diff(&candidate), golden.node().diff(&candidate, &DiffOptions::default()));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HashedNode {
+    node: Node,
+    digest: Digest,
+    /// Carries the digests of every value below the root.
+    options: DiffOptions,
+}
+
+impl HashedNode {
+    /// Hashes every value of `node` under `options`.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashedNode, Node};
+    /// let options = DiffOptions::default();
+    /// let node = Node::from_json_str("[[1, 2], {\"a\": [3]}]").unwrap();
+    /// let hashed = HashedNode::new(node.clone(), &options);
+    /// assert_eq!(hashed.hash_code(), node.hash_code(&options));
+    /// ```
+    #[must_use]
+    pub fn new(node: Node, options: &DiffOptions) -> Self {
+        let cache = Arc::new(DigestCache::for_descendants(&node));
+        let options = options.clone().with_digests(Arc::clone(&cache));
+        fill(&node, &options, &cache);
+        let digest = node.digest(&options);
+        Self { node, digest, options }
+    }
+
+    /// Returns the wrapped document.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashedNode, Node};
+    /// let hashed = HashedNode::new(Node::from(1), &DiffOptions::default());
+    /// assert_eq!(hashed.node(), &Node::from(1));
+    /// ```
+    #[must_use]
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Unwraps the document, dropping its hashes.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashedNode, Node};
+    /// let hashed = HashedNode::new(Node::Null, &DiffOptions::default());
+    /// assert_eq!(hashed.into_node(), Node::Null);
+    /// ```
+    #[must_use]
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+
+    /// Returns the document's hash code, as [`Node::hash_code`] computes it
+    /// under the options given at construction.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashedNode, Node};
+    /// let hashed = HashedNode::new(Node::from("x"), &DiffOptions::default());
+    /// assert_eq!(hashed.hash_code(), Node::from("x").hash_code(&DiffOptions::default()));
+    /// ```
+    #[must_use]
+    pub fn hash_code(&self) -> HashCode {
+        let mut code = [0; 8];
+        code.copy_from_slice(&self.digest[..8]);
+        code
+    }
+
+    /// Computes the diff from this document to `other`, like
+    /// [`Node::diff`] with the options given at construction.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashedNode, Node, RenderConfig};
+    /// let hashed = HashedNode::new(Node::from_json_str("[1, 2]").unwrap(), &DiffOptions::default());
+    /// let diff = hashed.diff(&Node::from_json_str("[1, 3]").unwrap());
+    /// assert_eq!(diff.render(&RenderConfig::default()), "@ [1]\n  1\n- 2\n+ 3\n]\n");
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Node) -> Diff {
+        crate::diff::diff_nodes(&self.node, other, &self.options)
+    }
+
+    /// Compares this document with `other`, like [`Node::eq_with_options`]
+    /// with the options given at construction.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, DiffOptions, HashedNode, Node};
+    /// let options = DiffOptions::default().with_array_mode(ArrayMode::Set).unwrap();
+    /// let hashed = HashedNode::new(Node::from_json_str("[1, 2, 1]").unwrap(), &options);
+    /// assert!(hashed.equals(&Node::from_json_str("[2, 1]").unwrap()));
+    /// ```
+    #[must_use]
+    pub fn equals(&self, other: &Node) -> bool {
+        self.node.eq_with_options(other, &self.options)
+    }
+}
+
+/// Digests of the values below a [`HashedNode`]'s root, keyed by address.
+///
+/// The values live in the heap buffers of their parents' arrays and maps,
+/// which the `HashedNode` owns and never mutates, so their addresses are
+/// stable and no other live node can share them. The root is excluded
+/// because it moves with its wrapper.
+pub(crate) struct DigestCache {
+    digests: HashMap<usize, OnceLock<Digest>>,
+}
+
+impl DigestCache {
+    fn for_descendants(root: &Node) -> Self {
+        let mut digests = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let children: Box<dyn Iterator<Item = &Node>> = match node {
+                Node::Array(values) => Box::new(values.iter()),
+                Node::Object(map) => Box::new(map.values()),
+                _ => continue,
+            };
+            for child in children {
+                digests.insert(address(child), OnceLock::new());
+                stack.push(child);
+            }
+        }
+        Self { digests }
+    }
+
+    pub(crate) fn get(&self, node: &Node) -> Option<Digest> {
+        self.digests.get(&address(node))?.get().copied()
+    }
+
+    fn set(&self, node: &Node, digest: Digest) {
+        if let Some(slot) = self.digests.get(&address(node)) {
+            let _ = slot.set(digest);
+        }
+    }
+}
+
+impl fmt::Debug for DigestCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestCache").field("len", &self.digests.len()).finish()
+    }
+}
+
+/// Digests of the arrays and objects hashed during one diff, keyed by
+/// address, so nested arrays are hashed once rather than once per level.
+/// Equal digests never stand in for equality, since distinct values can
+/// collide, but under options without tolerances or `DIFF_OFF` unequal
+/// digests prove a difference, so the engine recurses into such values
+/// without comparing them first.
+///
+/// Both documents stay borrowed, and so in place, for the whole diff. A
+/// digest depends on the options at the value's path, so the memo is only
+/// used when no path option names a specific index: the engine refines the
+/// options of list elements by their position in the diff, not in the
+/// array.
+#[derive(Default)]
+pub(crate) struct DigestMemo {
+    digests: Mutex<HashMap<usize, Digest>>,
+}
+
+impl DigestMemo {
+    /// A memo for a diff under `options`, if it can use one.
+    pub(crate) fn for_diff(options: &DiffOptions) -> Option<Self> {
+        let by_index = options.path_options().iter().any(|option| {
+            option.at().iter().any(|matcher| matches!(matcher, crate::PathMatcher::Index(_)))
+        });
+        (options.memo().is_none() && !by_index).then(Self::default)
+    }
+
+    pub(crate) fn get(&self, node: &Node) -> Option<Digest> {
+        self.lock().get(&address(node)).copied()
+    }
+
+    pub(crate) fn insert(&self, node: &Node, digest: Digest) {
+        self.lock().insert(address(node), digest);
+    }
+
+    /// Entries are only ever inserted whole, so a poisoned lock is still
+    /// consistent.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<usize, Digest>> {
+        self.digests.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for DigestMemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestMemo").field("len", &self.lock().len()).finish()
+    }
+}
+
+fn address(node: &Node) -> usize {
+    std::ptr::from_ref(node) as usize
+}
+
+/// Caches the digests below `node`, children first, so each value is
+/// hashed once from its children's cached digests.
+fn fill(node: &Node, options: &DiffOptions, cache: &DigestCache) {
+    match node {
+        Node::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let options = options.refine_index(index as i64);
+                fill(value, &options, cache);
+                cache.set(value, value.digest(&options));
+            }
+        }
+        Node::Object(map) => {
+            for (key, value) in map {
+                let options = options.refine_key(key);
+                fill(value, &options, cache);
+                cache.set(value, value.digest(&options));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Deadline;
+    use crate::{ArrayMode, PathMatcher, PathOption, PathSetting};
+
+    #[test]
+    fn cached_hashes_match_fresh_ones() {
+        let options = DiffOptions::default()
+            .with_path_option(
+                PathOption::new([PathMatcher::key("tags")])
+                    .with_setting(PathSetting::ArrayMode(ArrayMode::MultiSet)),
+            )
+            .unwrap();
+        let base = Node::from_json_str(
+            r#"{"tags": ["a", "b", "a"], "rows": [[1, [2]], {"x": [3, 4]}, [1, [2]]]}"#,
+        )
+        .unwrap();
+        let hashed = HashedNode::new(base.clone(), &options);
+        assert_eq!(hashed.cache().digests.len(), 17);
+        assert!(hashed.cache().digests.values().all(|slot| slot.get().is_some()));
+        assert_eq!(hashed.hash_code(), base.hash_code(&options));
+
+        for candidate in [
+            r#"{"tags": ["b", "a", "a"], "rows": [[1, [2]], {"x": [3, 4]}, [1, [2]]]}"#,
+            r#"{"tags": ["a", "a", "b"], "rows": [[1, [2]], [1, [5]], {"x": [4]}]}"#,
+            r#"[[1, [2]]]"#,
+        ] {
+            let candidate = Node::from_json_str(candidate).unwrap();
+            assert_eq!(hashed.diff(&candidate), base.diff(&candidate, &options));
+            assert_eq!(hashed.equals(&candidate), base.eq_with_options(&candidate, &options));
+        }
+        // The diff engine does not diff multisets yet, so only compare these.
+        let candidate = Node::from_json_str(r#"{"tags": ["b", "a"], "rows": []}"#).unwrap();
+        assert!(!hashed.equals(&candidate));
+    }
+
+    #[test]
+    fn moving_the_wrapper_keeps_hits_valid() {
+        let base = Node::from_json_str(r#"[[1], [2]]"#).unwrap();
+        let hashed = vec![HashedNode::new(base.clone(), &DiffOptions::default())];
+        let hashed = hashed.into_iter().next().unwrap();
+        let Node::Array(values) = hashed.node() else { unreachable!() };
+        assert!(values.iter().all(|value| hashed.cache().get(value).is_some()));
+        assert_eq!(hashed.cache().get(hashed.node()), None);
+        let candidate = Node::from_json_str(r#"[[2], [1]]"#).unwrap();
+        assert_eq!(hashed.diff(&candidate), base.diff(&candidate, &DiffOptions::default()));
+    }
+
+    #[test]
+    fn memo_keeps_digests_of_paired_elements() {
+        let lhs = crate::node!([{"big": [[1, 2], {"x": [3]}], "v": 1}, 9]);
+        let rhs = crate::node!([{"big": [[1, 2], {"x": [3]}], "v": 2}, 8]);
+        let options = DiffOptions::default();
+        let memo = Arc::new(DigestMemo::for_diff(&options).unwrap());
+        let memoized = options.clone().with_memo(Arc::clone(&memo));
+        let diff = crate::diff::diff_nodes_until(&lhs, &rhs, &memoized, &Deadline::unbounded());
+        assert_eq!(diff, lhs.diff(&rhs, &DiffOptions::default()));
+        let (Node::Array(lhs), Node::Array(rhs)) = (&lhs, &rhs) else { unreachable!() };
+        let (Node::Object(lhs), Node::Object(rhs)) = (&lhs[0], &rhs[0]) else { unreachable!() };
+        assert!(memo.get(&lhs["big"]).is_some());
+        assert_eq!(memo.get(&lhs["big"]), memo.get(&rhs["big"]));
+        assert_eq!(memo.get(&lhs["v"]), None);
+
+        let by_index = options
+            .with_path_option(
+                PathOption::new([PathMatcher::Index(0)]).with_setting(PathSetting::DiffOff),
+            )
+            .unwrap();
+        assert!(DigestMemo::for_diff(&by_index).is_none());
+    }
+
+    #[test]
+    fn colliding_digests_do_not_hide_changes() {
+        /// FNV-1a, except that every array hashes alike.
+        #[derive(Debug)]
+        struct ArraysCollide;
+        impl crate::NodeHasher for ArraysCollide {
+            fn width(&self) -> usize {
+                8
+            }
+            fn digest(&self, tag: u8, input: &[u8]) -> Digest {
+                match tag {
+                    b'l' => [7; 16],
+                    _ => crate::NodeHasher::digest(&crate::HashAlgorithm::Fnv64, tag, input),
+                }
+            }
+        }
+        let lhs = crate::node!([{"a": [1], "v": 1}]);
+        let rhs = crate::node!([{"a": [2], "v": 2}]);
+        let options = DiffOptions::default().with_hasher(ArraysCollide);
+        let memo = Arc::new(DigestMemo::for_diff(&options).unwrap());
+        let memoized = options.clone().with_memo(Arc::clone(&memo));
+        let diff = crate::diff::diff_nodes_until(&lhs, &rhs, &memoized, &Deadline::unbounded());
+        let (Node::Array(lhs), Node::Array(rhs)) = (&lhs, &rhs) else { unreachable!() };
+        assert_eq!(memo.get(&lhs[0]["a"]), memo.get(&rhs[0]["a"]));
+        assert_eq!(diff.len(), 2);
+    }
+
+    impl HashedNode {
+        fn cache(&self) -> &DigestCache {
+            self.options.digests().expect("cached")
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Fluent facade over canonicalization, options, diffing, and rendering.
+//!
+//! [`Jd`] bundles the pieces most callers need into a single builder so that
+//! embedding jd does not require learning [`Node`], [`DiffOptions`],
+//! [`Diff`], and [`RenderConfig`] up front. Option validation errors are
+//! deferred until a terminal operation such as [`Jd::diff_str`] runs.
+//! Optional [`Limits`] make the facade safe to use on untrusted input.
+
+use std::borrow::Cow;
+
+use crate::{
+    ArrayMode, CanonicalizeError, Diff, DiffFormat, DiffOptions, JdError, Limits, Node,
+    OptionsError, Preset, RenderConfig,
+};
+
+/// Builder-style entry point for diffing documents.
+///
+/// ```
+/// use jd_core::Jd;
+///
+/// let rendered = Jd::new()
+///     .precision(0.01)
+///     .render_str(r#"{"pi":3.14,"e":2.7}"#, r#"{"pi":3.141,"e":2.8}"#)
+///     .expect("diff documents");
+/// assert_eq!(rendered, "@ [\"e\"]\n- 2.7\n+ 2.8\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Jd {
+    options: Result<DiffOptions, OptionsError>,
+    render: RenderConfig,
+    limits: Limits,
+    yaml: bool,
+}
+
+impl Default for Jd {
+    fn default() -> Self {
+        Self {
+            options: Ok(DiffOptions::default()),
+            render: RenderConfig::default(),
+            limits: Limits::default(),
+            yaml: false,
+        }
+    }
+}
+
+impl Jd {
+    /// Creates a facade using default options (list mode, JSON input, no color).
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let diff = Jd::new().diff_str("[1,2]", "[1,3]").unwrap();
+    /// assert_eq!(diff.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a facade from pre-built options.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Jd};
+    /// let options = DiffOptions::default().with_prune_empty(true);
+    /// let jd = Jd::with_options(options);
+    /// assert!(jd.options().unwrap().prune_empty());
+    /// ```
+    #[must_use]
+    pub fn with_options(options: DiffOptions) -> Self {
+        Self { options: Ok(options), ..Self::default() }
+    }
+
+    /// Treats arrays as sets.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, Jd};
+    /// assert_eq!(Jd::new().set().options().unwrap().array_mode(), ArrayMode::Set);
+    /// ```
+    #[must_use]
+    pub fn set(self) -> Self {
+        self.map_options(|opts| opts.with_array_mode(ArrayMode::Set))
+    }
+
+    /// Treats arrays as multisets.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, Jd};
+    /// assert_eq!(Jd::new().multiset().options().unwrap().array_mode(), ArrayMode::MultiSet);
+    /// ```
+    #[must_use]
+    pub fn multiset(self) -> Self {
+        self.map_options(|opts| opts.with_array_mode(ArrayMode::MultiSet))
+    }
+
+    /// Sets the keys identifying objects within set semantics.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let jd = Jd::new().set().set_keys(["id"]);
+    /// assert_eq!(jd.options().unwrap().set_keys().unwrap(), ["id"]);
+    /// ```
+    #[must_use]
+    pub fn set_keys<I, S>(self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.map_options(|opts| opts.with_set_keys(keys))
+    }
+
+    /// Applies a [`Preset`]: its path options, and normalizing both
+    /// documents with [`Preset::normalize`] before each diff.
+    ///
+    /// ```
+    /// # use jd_core::{Jd, Preset};
+    /// let jd = Jd::new().preset(Preset::OpenApi);
+    /// let diff = jd.diff_str(r#"{"info":{"version":"1.0.0"}}"#, r#"{"info":{"version":"1.1.0"}}"#);
+    /// assert!(diff.unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn preset(self, preset: Preset) -> Self {
+        self.map_options(|opts| Ok(opts.with_preset(preset)))
+    }
+
+    /// Sets the absolute numeric tolerance.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// assert!(Jd::new().precision(0.5).diff_str("1", "1.4").unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn precision(self, precision: f64) -> Self {
+        self.map_options(|opts| opts.with_precision(precision))
+    }
+
+    /// Sets the numeric tolerance relative to operand magnitude.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// assert!(Jd::new().relative_precision(1e-3).diff_str("1e9", "1.0001e9").unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn relative_precision(self, tolerance: f64) -> Self {
+        self.map_options(|opts| opts.with_relative_precision(tolerance))
+    }
+
+    /// Sets the maximum ULP distance for numbers to compare equal.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// assert!(Jd::new().max_ulps(1).diff_str("0.30000000000000004", "0.3").unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn max_ulps(self, max_ulps: u64) -> Self {
+        self.map_options(|opts| opts.with_max_ulps(max_ulps))
+    }
+
+    /// Treats `[]`, `{}`, and absent keys as equivalent.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// assert!(Jd::new().prune_empty().diff_str(r#"{"a":[]}"#, "{}").unwrap().is_empty());
+    /// ```
+    #[must_use]
+    pub fn prune_empty(self) -> Self {
+        self.map_options(|opts| Ok(opts.with_prune_empty(true)))
+    }
+
+    /// Parses string inputs as YAML instead of JSON.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let diff = Jd::new().yaml().diff_str("a: 1\n", "a: 2\n").unwrap();
+    /// assert_eq!(diff.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn yaml(mut self) -> Self {
+        self.yaml = true;
+        self
+    }
+
+    /// Enables ANSI color in rendered output.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let rendered = Jd::new().color(true).render_str("1", "2").unwrap();
+    /// assert!(rendered.contains("\u{1b}[31m"));
+    /// ```
+    #[must_use]
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.render = self.render.with_color(enabled);
+        self
+    }
+
+    /// Enforces resource limits when parsing and diffing.
+    ///
+    /// Input size, node count, and depth are checked by [`Jd::parse`]; the
+    /// node limits and timeout are also enforced by [`Jd::diff`].
+    ///
+    /// ```
+    /// # use jd_core::{Jd, JdError, LimitError, Limits};
+    /// let jd = Jd::new().limits(Limits::default().with_max_input_bytes(16));
+    /// let err = jd.diff_str(r#"{"key":"a long value"}"#, "{}").unwrap_err();
+    /// assert!(matches!(
+    ///     err,
+    ///     JdError::Canonicalize(jd_core::CanonicalizeError::Limit(LimitError::InputTooLarge { .. }))
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the accumulated options, or the first validation error.
+    ///
+    /// ```
+    /// # use jd_core::{Jd, OptionsError};
+    /// let err = Jd::new().set().precision(0.1).options().unwrap_err();
+    /// assert_eq!(err, OptionsError::PrecisionIncompatible);
+    /// ```
+    pub fn options(&self) -> Result<&DiffOptions, OptionsError> {
+        self.options.as_ref().map_err(Clone::clone)
+    }
+
+    /// Returns the render configuration used by [`Jd::render_str`].
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// assert!(Jd::new().color(true).render_config().color_enabled());
+    /// ```
+    #[must_use]
+    pub fn render_config(&self) -> RenderConfig {
+        self.render
+    }
+
+    /// Returns the resource limits applied by [`Jd::parse`] and [`Jd::diff`].
+    ///
+    /// ```
+    /// # use jd_core::{Jd, Limits};
+    /// let jd = Jd::new().limits(Limits::default().with_max_depth(32));
+    /// assert_eq!(jd.resource_limits().max_depth(), Some(32));
+    /// ```
+    #[must_use]
+    pub fn resource_limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Canonicalizes an input string using the configured format.
+    ///
+    /// ```
+    /// # use jd_core::{Jd, Node};
+    /// let node = Jd::new().yaml().parse("- 1\n- 2\n").unwrap();
+    /// assert_eq!(node, Node::from_json_str("[1,2]").unwrap());
+    /// ```
+    pub fn parse(&self, input: &str) -> Result<Node, CanonicalizeError> {
+        self.limits.check_input_len(input.len())?;
+        let node =
+            if self.yaml { Node::from_yaml_str(input)? } else { Node::from_json_str(input)? };
+        self.limits.check_node(&node)?;
+        Ok(node)
+    }
+
+    /// Diffs two already canonicalized nodes, normalizing them first when
+    /// the options carry a [`Preset`].
+    ///
+    /// ```
+    /// # use jd_core::{Jd, Node};
+    /// let lhs = Node::from_json_str("{\"a\":1}").unwrap();
+    /// let rhs = Node::from_json_str("{\"a\":2}").unwrap();
+    /// assert_eq!(Jd::new().diff(&lhs, &rhs).unwrap().len(), 1);
+    /// ```
+    pub fn diff(&self, lhs: &Node, rhs: &Node) -> Result<Diff, JdError> {
+        self.diff_with(self.options()?, lhs, rhs)
+    }
+
+    fn diff_with(&self, options: &DiffOptions, lhs: &Node, rhs: &Node) -> Result<Diff, JdError> {
+        if let Some(mode) = options.unsupported_array_mode() {
+            return Err(JdError::UnsupportedArrayMode(mode));
+        }
+        let (lhs, rhs) = match options.applied_preset() {
+            Some(preset) => (Cow::Owned(preset.normalize(lhs)), Cow::Owned(preset.normalize(rhs))),
+            None => (Cow::Borrowed(lhs), Cow::Borrowed(rhs)),
+        };
+        let document_options = options
+            .applied_preset()
+            .map(|preset| preset.document_path_options(&lhs, &rhs))
+            .unwrap_or_default();
+        if document_options.is_empty() {
+            return Ok(lhs.diff_with_limits(&rhs, options, &self.limits)?);
+        }
+        let options = document_options
+            .into_iter()
+            .try_fold(options.clone(), DiffOptions::with_path_option)?;
+        Ok(lhs.diff_with_limits(&rhs, &options, &self.limits)?)
+    }
+
+    /// Parses both inputs and diffs them.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let diff = Jd::new().diff_str("[1,2,3]", "[1,4,3]").unwrap();
+    /// assert_eq!(diff.render_patch().unwrap().matches("\"op\"").count(), 5);
+    /// ```
+    pub fn diff_str(&self, lhs: &str, rhs: &str) -> Result<Diff, JdError> {
+        let lhs = self.parse(lhs)?;
+        let rhs = self.parse(rhs)?;
+        self.diff(&lhs, &rhs)
+    }
+
+    /// Parses, diffs, and renders both inputs in the native jd format.
+    ///
+    /// ```
+    /// # use jd_core::Jd;
+    /// let rendered = Jd::new().render_str("{\"a\":1}", "{\"a\":2}").unwrap();
+    /// assert_eq!(rendered, "@ [\"a\"]\n- 1\n+ 2\n");
+    /// ```
+    pub fn render_str(&self, lhs: &str, rhs: &str) -> Result<String, JdError> {
+        Ok(self.diff_str(lhs, rhs)?.render(&self.render))
+    }
+
+    /// Diffs two already canonicalized nodes and renders the diff as
+    /// `format`, using [`Jd::render_config`] for the native format.
+    ///
+    /// Merge patches can only express merge semantics, so
+    /// [`DiffFormat::Merge`] diffs with merge options whatever the
+    /// configured ones say, as `jd -f merge` does.
+    ///
+    /// ```
+    /// # use jd_core::{DiffFormat, Jd, Node};
+    /// let lhs = Node::from_json_str(r#"{"a":1,"b":2}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"a":3}"#).unwrap();
+    /// let merge = Jd::new().render_as(&lhs, &rhs, DiffFormat::Merge).unwrap();
+    /// assert_eq!(merge, r#"{"a":3,"b":null}"#);
+    /// ```
+    pub fn render_as(&self, lhs: &Node, rhs: &Node, format: DiffFormat) -> Result<String, JdError> {
+        let diff = match format {
+            DiffFormat::Merge => {
+                self.diff_with(&self.options()?.clone().with_merge(true), lhs, rhs)?
+            }
+            _ => self.diff(lhs, rhs)?,
+        };
+        Ok(format.render(&diff, &self.render)?)
+    }
+
+    fn map_options<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(DiffOptions) -> Result<DiffOptions, OptionsError>,
+    {
+        self.options = self.options.and_then(f);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_option_error_is_preserved() {
+        let jd = Jd::new().set().precision(0.1).set_keys([" "]);
+        let err = jd.diff_str("[]", "[]").unwrap_err();
+        assert!(matches!(err, JdError::Options(OptionsError::PrecisionIncompatible)));
+    }
+
+    #[test]
+    fn parse_errors_surface_as_canonicalize_errors() {
+        let err = Jd::new().diff_str("{", "{}").unwrap_err();
+        assert!(matches!(err, JdError::Canonicalize(CanonicalizeError::Json(_))));
+    }
+
+    #[test]
+    fn limits_apply_to_parse_and_diff() {
+        let jd = Jd::new().limits(Limits::default().with_max_nodes(2));
+        let err = jd.diff_str("[1]", "[1,2]").unwrap_err();
+        assert!(matches!(
+            err,
+            JdError::Canonicalize(CanonicalizeError::Limit(crate::LimitError::TooManyNodes {
+                limit: 2
+            }))
+        ));
+
+        let lhs = Node::from_json_str("[1,2]").unwrap();
+        let err = jd.diff(&lhs, &Node::Null).unwrap_err();
+        assert!(matches!(err, JdError::Limit(crate::LimitError::TooManyNodes { limit: 2 })));
+    }
+
+    #[test]
+    fn set_modes_report_unsupported_until_engine_lands() {
+        let err = Jd::new().set().set_keys(["id"]).diff_str("[]", "[1]").unwrap_err();
+        assert!(matches!(err, JdError::UnsupportedArrayMode(ArrayMode::Set)));
+    }
+
+    #[test]
+    fn only_the_merge_format_implies_merge_options() {
+        let lhs = Node::from_json_str(r#"{"a":[1,2]}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"a":[1,3]}"#).unwrap();
+        let jd = Jd::new().color(true);
+        let native = jd.render_as(&lhs, &rhs, DiffFormat::Jd).unwrap();
+        assert_eq!(native, jd.diff(&lhs, &rhs).unwrap().render(&jd.render_config()));
+        assert_eq!(jd.render_as(&lhs, &rhs, DiffFormat::Merge).unwrap(), r#"{"a":[1,3]}"#);
+        let merge = Jd::with_options(DiffOptions::default().with_merge(true));
+        let patch = merge.render_as(&lhs, &rhs, DiffFormat::Patch).unwrap();
+        assert_eq!(patch, merge.diff(&lhs, &rhs).unwrap().render_patch().unwrap());
+    }
+}
@@ -0,0 +1,384 @@
+//! Patching JSON text in place, keeping formatting and key order.
+//!
+//! [`Diff::apply_to_json_str`] patches the parsed document as usual, then
+//! writes only the difference between the old and new values back into the
+//! original text, located with a [`ParseIndex`]. Changed values are replaced
+//! where they stand, removed members and elements take their separators with
+//! them, and new ones are appended after their siblings using the separator
+//! already found between them. Anything else is left byte for byte as it was.
+//!
+//! New values are written compactly in single-line documents and indented
+//! like the surrounding text otherwise. Any edit whose result does not parse
+//! back to the patched value, as when an object repeats a key, is refused.
+
+use serde_json::ser::PrettyFormatter;
+use serde_json::Serializer;
+
+use crate::diff::{Path, PathSegment};
+use crate::{Diff, JsonEditError, Node, ParseIndex};
+
+impl Diff {
+    /// Applies this diff to the JSON document `input`, returning the edited
+    /// text.
+    ///
+    /// Unlike patching the parsed node and serializing it again, only the
+    /// affected values are rewritten, so indentation, spacing, and the
+    /// author's key order survive in hand-maintained files.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let input = "{\n  \"name\": \"app\",\n  \"replicas\": 2\n}\n";
+    /// let target = Node::from_json_str(r#"{"replicas": 3, "name": "app", "paused": true}"#).unwrap();
+    /// let diff = Node::from_json_str(input).unwrap().diff(&target, &DiffOptions::default());
+    /// assert_eq!(
+    ///     diff.apply_to_json_str(input).unwrap(),
+    ///     "{\n  \"name\": \"app\",\n  \"replicas\": 3,\n  \"paused\": true\n}\n"
+    /// );
+    /// ```
+    pub fn apply_to_json_str(&self, input: &str) -> Result<String, JsonEditError> {
+        let (before, index) = Node::from_json_str_indexed(input)?;
+        let after = before.apply_patch(self)?;
+        if after == before {
+            return Ok(input.to_string());
+        }
+        if after == Node::Void || before == Node::Void {
+            return Err(unsupported(Path::new(), "the patch adds or removes the whole document"));
+        }
+        let document = Document::new(input, &index);
+        let mut edits = document.edits(&before, &after)?;
+        // Later edits first, so earlier offsets stay valid; at one offset the
+        // outer value's insertion goes in first and ends up last.
+        edits.sort_by(|a, b| b.start.cmp(&a.start).then(a.depth.cmp(&b.depth)));
+        let mut output = input.to_string();
+        let mut limit = input.len();
+        for edit in edits {
+            if edit.end > limit {
+                return Err(unsupported(Path::new(), "edits overlap"));
+            }
+            limit = edit.start;
+            output.replace_range(edit.start..edit.end, &edit.text);
+        }
+        match Node::from_json_str(&output) {
+            Ok(edited) if edited == after => Ok(output),
+            _ => Err(unsupported(
+                Path::new(),
+                "the edited text does not read back as the patch result",
+            )),
+        }
+    }
+}
+
+fn unsupported(path: Path, reason: &str) -> JsonEditError {
+    JsonEditError::Unsupported { path, reason: reason.to_string() }
+}
+
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+    depth: usize,
+}
+
+/// A member or element as written: from its key (or value) to the end of
+/// its value. Offsets are in bytes.
+#[derive(Clone, Copy)]
+struct Child {
+    start: usize,
+    end: usize,
+}
+
+struct Document<'a> {
+    input: &'a str,
+    index: &'a ParseIndex,
+    /// One level of indentation, or `None` for single-line documents.
+    unit: Option<String>,
+}
+
+impl<'a> Document<'a> {
+    fn new(input: &'a str, index: &'a ParseIndex) -> Self {
+        let body = input.trim();
+        let unit = body.contains('\n').then(|| {
+            let indent = body
+                .lines()
+                .skip(1)
+                .map(|line| &line[..line.len() - line.trim_start().len()])
+                .find(|indent| !indent.is_empty())
+                .unwrap_or("  ");
+            indent.to_string()
+        });
+        Self { input, index, unit }
+    }
+
+    /// Plans the edits turning the text of `before` into that of `after`.
+    fn edits(&self, before: &Node, after: &Node) -> Result<Vec<Edit>, JsonEditError> {
+        let mut edits = Vec::new();
+        let mut stack = vec![(Path::new(), before, after)];
+        while let Some((path, before, after)) = stack.pop() {
+            if before == after {
+                continue;
+            }
+            let Some(value) = self.value(&path) else {
+                return Err(unsupported(path, "the value was not found in the text"));
+            };
+            let depth = path.segments().len();
+            let planned = match (before, after) {
+                (Node::Object(old), Node::Object(new)) if !old.is_empty() && !new.is_empty() => {
+                    if old.len() == new.len() && old.keys().eq(new.keys()) {
+                        for (key, value) in old {
+                            stack.push((key_path(&path, key), value, &new[key]));
+                        }
+                        Some(Vec::new())
+                    } else {
+                        self.members(&path, value, old, new, &mut stack)
+                    }
+                }
+                (Node::Array(old), Node::Array(new)) if !old.is_empty() && !new.is_empty() => {
+                    if old.len() == new.len() {
+                        for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                            stack.push((index_path(&path, index), old, new));
+                        }
+                        Some(Vec::new())
+                    } else {
+                        self.items(&path, value, old, new, &mut stack)
+                    }
+                }
+                _ => None,
+            };
+            let planned = match planned {
+                Some(planned) => planned,
+                None => {
+                    let indent = self.indent_at(value.start);
+                    let Some(text) = self.render(after, indent) else {
+                        return Err(unsupported(path, "a value is void"));
+                    };
+                    vec![Edit { start: value.start, end: value.end, text, depth }]
+                }
+            };
+            edits.extend(planned.into_iter().map(|edit| Edit { depth, ..edit }));
+        }
+        Ok(edits)
+    }
+
+    /// Removes and appends members of an object, queueing the members both
+    /// sides share; `None` when the text does not allow it.
+    fn members<'n>(
+        &self,
+        path: &Path,
+        object: Child,
+        old: &'n std::collections::BTreeMap<String, Node>,
+        new: &'n std::collections::BTreeMap<String, Node>,
+        stack: &mut Vec<(Path, &'n Node, &'n Node)>,
+    ) -> Option<Vec<Edit>> {
+        let mut written: Vec<(&String, Child)> = old
+            .keys()
+            .map(|key| {
+                let child = key_path(path, key);
+                let start = self.index.key_offset(&child)?;
+                Some((key, Child { start, end: self.value(&child)?.end }))
+            })
+            .collect::<Option<_>>()?;
+        written.sort_by_key(|(_, child)| child.start);
+        let children: Vec<Child> = written.iter().map(|(_, child)| *child).collect();
+
+        let mut edits = self.remove_runs(
+            &children,
+            &written.iter().map(|(key, _)| !new.contains_key(*key)).collect::<Vec<_>>(),
+        )?;
+        for (key, _) in &written {
+            if let Some(value) = new.get(*key) {
+                stack.push((key_path(path, key), &old[*key], value));
+            }
+        }
+
+        let separator = self.separator(object, &children);
+        let indent = self.indent_at(children[0].start);
+        // Whatever follows the first key up to its value, such as `": "`.
+        let first = &written[0];
+        let value_start = self.value(&key_path(path, first.0))?.start;
+        let colon = self.input[first.1.start..value_start].rfind(':')? + first.1.start;
+        let key_end = first.1.start + self.input[first.1.start..colon].trim_end().len();
+        let colon = &self.input[key_end..value_start];
+        let mut text = String::new();
+        for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+            text.push_str(&separator);
+            text.push_str(&serde_json::to_string(key).ok()?);
+            text.push_str(colon);
+            text.push_str(&self.render(value, indent)?);
+        }
+        if !text.is_empty() {
+            let at = children.last()?.end;
+            edits.push(Edit { start: at, end: at, text, depth: 0 });
+        }
+        Some(edits)
+    }
+
+    /// Removes and inserts elements of an array around its unchanged ends,
+    /// queueing the elements paired up in between.
+    fn items<'n>(
+        &self,
+        path: &Path,
+        array: Child,
+        old: &'n [Node],
+        new: &'n [Node],
+        stack: &mut Vec<(Path, &'n Node, &'n Node)>,
+    ) -> Option<Vec<Edit>> {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (removed, added) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+        let children: Vec<Child> = (0..old.len())
+            .map(|index| self.value(&index_path(path, index)))
+            .collect::<Option<_>>()?;
+        let paired = removed.len().min(added.len());
+        for (offset, (old, new)) in removed.iter().zip(added).enumerate() {
+            stack.push((index_path(path, prefix + offset), old, new));
+        }
+
+        let gone = prefix + paired..prefix + removed.len();
+        let mut edits = self.remove_runs(
+            &children,
+            &(0..old.len()).map(|index| gone.contains(&index)).collect::<Vec<_>>(),
+        )?;
+        if added.len() > paired {
+            let separator = self.separator(array, &children);
+            let indent = self.indent_at(children[0].start);
+            let items = added[paired..]
+                .iter()
+                .map(|item| self.render(item, indent))
+                .collect::<Option<Vec<_>>>()?;
+            let next = prefix + removed.len();
+            edits.push(match children.get(next) {
+                Some(child) => {
+                    let mut text = items.join(&separator);
+                    text.push_str(&separator);
+                    Edit { start: child.start, end: child.start, text, depth: 0 }
+                }
+                None => {
+                    let at = children.last()?.end;
+                    let text = format!("{separator}{}", items.join(&separator));
+                    Edit { start: at, end: at, text, depth: 0 }
+                }
+            });
+        }
+        Some(edits)
+    }
+
+    /// Deletes each run of consecutive `removed` children together with the
+    /// separator after it, or before it for a trailing run.
+    fn remove_runs(&self, children: &[Child], removed: &[bool]) -> Option<Vec<Edit>> {
+        let mut edits = Vec::new();
+        let mut index = 0;
+        while index < children.len() {
+            if !removed[index] {
+                index += 1;
+                continue;
+            }
+            let run = index;
+            while index < children.len() && removed[index] {
+                index += 1;
+            }
+            let (start, end) = match (children.get(index), run.checked_sub(1)) {
+                (Some(next), _) => (children[run].start, next.start),
+                (None, Some(previous)) => (children[previous].end, children[index - 1].end),
+                (None, None) => return None,
+            };
+            edits.push(Edit { start, end, text: String::new(), depth: 0 });
+        }
+        Some(edits)
+    }
+
+    /// The text between two children of `container`, comma included.
+    fn separator(&self, container: Child, children: &[Child]) -> String {
+        match children {
+            [first, second, ..] => self.input[first.end..second.start].to_string(),
+            [first, ..] => format!(",{}", &self.input[container.start + 1..first.start]),
+            [] => ",".to_string(),
+        }
+    }
+
+    fn value(&self, path: &Path) -> Option<Child> {
+        let span = self.index.span_of(path)?;
+        Some(Child { start: span.start().offset(), end: span.end().offset() })
+    }
+
+    /// Leading whitespace of the line on which `offset` lies.
+    fn indent_at(&self, offset: usize) -> &str {
+        let line = &self.input[self.input[..offset].rfind('\n').map_or(0, |newline| newline + 1)..];
+        &line[..line.len() - line.trim_start().len()]
+    }
+
+    /// Writes `value` to stand on a line indented by `indent`.
+    fn render(&self, value: &Node, indent: &str) -> Option<String> {
+        let json = value.to_json_value()?;
+        let Some(unit) = &self.unit else {
+            return Some(json.to_string());
+        };
+        let mut buffer = Vec::new();
+        let mut serializer =
+            Serializer::with_formatter(&mut buffer, PrettyFormatter::with_indent(unit.as_bytes()));
+        serde::Serialize::serialize(&json, &mut serializer).ok()?;
+        let pretty = String::from_utf8(buffer).ok()?;
+        Some(pretty.replace('\n', &format!("\n{indent}")))
+    }
+}
+
+fn key_path(path: &Path, key: &str) -> Path {
+    path.clone().with_segment(PathSegment::Key(key.to_string()))
+}
+
+fn index_path(path: &Path, index: usize) -> Path {
+    path.clone().with_segment(PathSegment::Index(index as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DiffOptions, JsonEditError, Node};
+
+    fn edit(input: &str, target: &str) -> Result<String, JsonEditError> {
+        let target = Node::from_json_str(target).unwrap();
+        let diff = Node::from_json_str(input).unwrap().diff(&target, &DiffOptions::default());
+        diff.apply_to_json_str(input)
+    }
+
+    #[test]
+    fn replaces_values_keeping_key_order_and_spacing() {
+        let input = "{\"z\": 1, \"a\" : [1, 2, 3], \"m\": {\"x\": \"y\"}}";
+        let target = r#"{"a": [1, 5, 3], "m": {"x": [true]}, "z": 2}"#;
+        assert_eq!(
+            edit(input, target).unwrap(),
+            "{\"z\": 2, \"a\" : [1, 5, 3], \"m\": {\"x\": [true]}}"
+        );
+    }
+
+    #[test]
+    fn removes_and_appends_members() {
+        let input =
+            "{\n    \"keep\": 1,\n    \"drop\": 2,\n    \"nested\": {\"a\": 1, \"b\": 2}\n}\n";
+        let target = r#"{"keep": 1, "nested": {"a": 1}, "new": {"c": [1]}}"#;
+        assert_eq!(
+            edit(input, target).unwrap(),
+            "{\n    \"keep\": 1,\n    \"nested\": {\"a\": 1},\n    \"new\": {\n        \"c\": [\n            1\n        ]\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn inserts_and_removes_elements() {
+        assert_eq!(edit("[1, 2, 3, 4]", "[1, 4]").unwrap(), "[1, 4]");
+        assert_eq!(edit("[1, 2, 3, 4]", "[1, 2]").unwrap(), "[1, 2]");
+        assert_eq!(edit("[1, 4]", "[1, 2, 3, 4]").unwrap(), "[1, 2, 3, 4]");
+        assert_eq!(edit("[ 1 ]", "[1, 2]").unwrap(), "[ 1, 2 ]");
+        assert_eq!(edit("{\"a\": []}", "{\"a\": [1]}").unwrap(), "{\"a\": [1]}");
+    }
+
+    #[test]
+    fn refuses_edits_hidden_by_repeated_keys() {
+        assert!(matches!(
+            edit(r#"{"a": 1, "b": 0, "a": 2}"#, r#"{"b": 0}"#),
+            Err(JsonEditError::Unsupported { .. })
+        ));
+    }
+}
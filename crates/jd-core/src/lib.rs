@@ -22,24 +22,182 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! Callers that only need a quick comparison can use the [`Jd`] facade,
+//! which wraps canonicalization, option validation, diffing, and rendering:
+//!
+//! ```
+//! use jd_core::Jd;
+//!
+//! let rendered = Jd::new().render_str("{\"a\":1}", "{\"a\":2}").unwrap();
+//! assert_eq!(rendered, "@ [\"a\"]\n- 1\n+ 2\n");
+//! ```
+//!
+//! # Stability
+//!
+//! `jd-core` follows semantic versioning and builds on Rust 1.88 and later;
+//! raising that minimum is a minor release. `tests/public_api.rs` keeps a
+//! snapshot of every public signature so API changes are reviewed as such.
+//!
+//! Error enums, [`PathSetting`], [`Preset`], [`progress::Phase`], and the public-field
+//! structs [`DiffElement`] and [`DiffMetadata`] are `#[non_exhaustive]`, so
+//! new variants and fields are not breaking changes. Match the enums with a
+//! wildcard arm:
+//!
+//! ```compile_fail,E0004
+//! use jd_core::LimitError;
+//!
+//! fn describe(err: &LimitError) -> &'static str {
+//!     match err {
+//!         LimitError::InputTooLarge { .. } => "input",
+//!         LimitError::TooManyNodes { .. } => "nodes",
+//!         LimitError::TooDeep { .. } => "depth",
+//!         LimitError::Timeout { .. } => "time",
+//!     }
+//! }
+//! ```
+//!
+//! and build the structs through their constructors:
+//!
+//! ```compile_fail,E0639
+//! let meta = jd_core::DiffMetadata { merge: true, set_keys: None, color: None };
+//! ```
+//!
+//! Behavior is extended through traits rather than new variants:
+//! [`Visitor`] walks documents and [`progress::ProgressSink`] receives
+//! progress reports. Methods added to these traits come with default
+//! implementations.
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+mod arena;
+mod canonical;
+mod comparator;
+mod de;
 pub mod diff;
 mod error;
+pub mod github;
 mod hash;
+mod hashed;
+mod jd;
+mod json_edit;
+mod limits;
+mod location;
+mod macros;
+mod metrics;
 mod node;
+mod node_ref;
 mod number;
 mod options;
+mod opts;
+mod parse_index;
 mod patch;
+mod path_options;
+mod preset;
+pub mod progress;
+pub mod query;
+mod schema;
+#[cfg(feature = "simd")]
+mod simd;
+mod spill;
+pub mod task;
+pub mod terraform;
+mod timestamp;
+mod visit;
+#[cfg(feature = "yaml-edit")]
+mod yaml_edit;
 
-pub use diff::{Diff, DiffElement, DiffMetadata, Path, PathSegment, RenderConfig, RenderError};
-pub use error::{CanonicalizeError, OptionsError};
-pub use hash::{combine, hash_bytes, HashCode};
+pub use arena::{NodeArena, SharedNode};
+pub use comparator::ValueComparator;
+pub use de::{DeserializeError, NodeDeserializer};
+pub use diff::{
+    Diff, DiffBuilder, DiffElement, DiffFormat, DiffMetadata, DiffRenderer, DiffScratch,
+    MergeRenderer, ParseDiffFormatError, PatchRenderer, Path, PathSegment, RawRenderer, ReadError,
+    RebaseError, RenderConfig, RenderError,
+};
+#[cfg(feature = "yaml-edit")]
+pub use error::YamlEditError;
+pub use error::{
+    CanonicalizeError, ElementError, EncodeError, JdError, JsonEditError, LimitError, OptionsError,
+};
+pub use hash::{combine, hash_bytes, Digest, HashAlgorithm, HashCode, NodeHasher};
+pub use hashed::HashedNode;
+pub use jd::Jd;
+pub use limits::Limits;
+pub use location::Location;
+pub use metrics::{Histogram, Metrics};
 pub use node::Node;
+pub use node_ref::NodeRef;
 pub use number::Number;
-pub use options::{ArrayMode, DiffOptions};
-pub use patch::PatchError;
+pub use options::{ArrayMode, DiffOptions, ListAlignment};
+pub use parse_index::{ParseIndex, SourceSpan};
+pub use patch::{PatchError, PatchStream, RecordFailure};
+pub use path_options::{PathMatcher, PathOption, PathSetting};
+pub use preset::{ParsePresetError, Preset};
+pub use visit::Visitor;
+
+/// Diffs two serializable values, such as application structs, without
+/// converting them to JSON by hand.
+///
+/// Both values go through [`Node::from_serialize`] and are then diffed by
+/// [`Jd::diff`], so presets apply and array modes the diff engine cannot
+/// handle yet are reported as [`JdError::UnsupportedArrayMode`].
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     host: &'static str,
+///     load: f64,
+/// }
+///
+/// let before = Server { host: "a", load: 0.50 };
+/// let after = Server { host: "b", load: 0.51 };
+/// let options = jd_core::DiffOptions::default().with_precision(0.1).unwrap();
+/// let diff = jd_core::diff_values(&before, &after, &options).unwrap();
+/// assert_eq!(diff.render(&Default::default()), "@ [\"host\"]\n- \"a\"\n+ \"b\"\n");
+/// ```
+pub fn diff_values<A, B>(lhs: &A, rhs: &B, options: &DiffOptions) -> Result<Diff, JdError>
+where
+    A: serde::Serialize + ?Sized,
+    B: serde::Serialize + ?Sized,
+{
+    let (lhs, rhs) = (Node::from_serialize(lhs)?, Node::from_serialize(rhs)?);
+    Jd::with_options(options.clone()).diff(&lhs, &rhs)
+}
+
+/// Reports whether two documents are equal under `options`. In list mode
+/// this is the same answer as `lhs.diff(rhs, options).is_empty()` at a
+/// fraction of the cost.
+///
+/// This is the cheap path for gate checks such as "did the rendered
+/// config drift?": it stops at the first difference, builds no hunks, and
+/// allocates nothing in list mode without path options. Precision,
+/// pruning, and path options apply as they do for [`Node::diff`]. Set and
+/// multiset array modes are honored here as well, although [`Node::diff`]
+/// cannot diff them until set diffing lands. Use
+/// [`Node::explain_inequality`] to also learn where the documents differ.
+///
+/// ```
+/// use jd_core::{ArrayMode, DiffOptions, Node};
+///
+/// let lhs = Node::from_json_str(r#"{"tags": ["a", "b"], "cpu": 0.51}"#).unwrap();
+/// let rhs = Node::from_json_str(r#"{"tags": ["b", "a"], "cpu": 0.5}"#).unwrap();
+/// assert!(!jd_core::equal(&lhs, &rhs, &DiffOptions::default()));
+/// let options = DiffOptions::default()
+///     .with_precision(0.05)
+///     .unwrap()
+///     .with_path_option(jd_core::PathOption::new([jd_core::PathMatcher::key("tags")])
+///         .with_setting(jd_core::PathSetting::ArrayMode(ArrayMode::Set)))
+///     .unwrap();
+/// assert!(jd_core::equal(&lhs, &rhs, &options));
+/// ```
+#[must_use]
+pub fn equal(lhs: &Node, rhs: &Node, options: &DiffOptions) -> bool {
+    // A value equals itself under any options.
+    std::ptr::eq(lhs, rhs) || lhs.eq_with_options(rhs, options)
+}
 
 /// Returns the semantic version of the `jd-core` crate.
 ///
@@ -0,0 +1,268 @@
+//! Resource limits for diffing untrusted documents.
+
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+use crate::{LimitError, Node};
+
+/// Optional bounds on input size, document shape, and diff time.
+///
+/// Every limit is off by default. Services that diff documents they do not
+/// control can enable them to fail with a [`LimitError`] instead of running
+/// out of memory, overflowing the stack, or stalling on a quadratic diff.
+///
+/// ```
+/// use std::time::Duration;
+/// use jd_core::{DiffOptions, LimitError, Limits, Node};
+///
+/// let limits = Limits::default().with_max_depth(2).with_timeout(Duration::from_secs(1));
+/// let lhs = Node::from_json_str("[[[1]]]").unwrap();
+/// let rhs = Node::from_json_str("[]").unwrap();
+/// let err = lhs.diff_with_limits(&rhs, &DiffOptions::default(), &limits).unwrap_err();
+/// assert_eq!(err, LimitError::TooDeep { limit: 2 });
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    max_input_bytes: Option<usize>,
+    max_nodes: Option<usize>,
+    max_depth: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl Limits {
+    /// Rejects inputs longer than `bytes`.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().with_max_input_bytes(1024).max_input_bytes(), Some(1024));
+    /// ```
+    #[must_use]
+    pub fn with_max_input_bytes(mut self, bytes: usize) -> Self {
+        self.max_input_bytes = Some(bytes);
+        self
+    }
+
+    /// Maximum input length in bytes, if limited.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().max_input_bytes(), None);
+    /// ```
+    #[must_use]
+    pub fn max_input_bytes(&self) -> Option<usize> {
+        self.max_input_bytes
+    }
+
+    /// Rejects documents with more than `nodes` values, counting every
+    /// array, object, and scalar.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().with_max_nodes(10_000).max_nodes(), Some(10_000));
+    /// ```
+    #[must_use]
+    pub fn with_max_nodes(mut self, nodes: usize) -> Self {
+        self.max_nodes = Some(nodes);
+        self
+    }
+
+    /// Maximum number of values per document, if limited.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().max_nodes(), None);
+    /// ```
+    #[must_use]
+    pub fn max_nodes(&self) -> Option<usize> {
+        self.max_nodes
+    }
+
+    /// Rejects documents with arrays and objects nested more than `depth`
+    /// levels deep. Scalars have depth 0; `[1]` and `{}` have depth 1.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().with_max_depth(64).max_depth(), Some(64));
+    /// ```
+    #[must_use]
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Maximum nesting depth, if limited.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().max_depth(), None);
+    /// ```
+    #[must_use]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Abandons a diff that runs longer than `timeout` of wall-clock time.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use jd_core::Limits;
+    /// let limits = Limits::default().with_timeout(Duration::from_millis(500));
+    /// assert_eq!(limits.timeout(), Some(Duration::from_millis(500)));
+    /// ```
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Wall-clock budget for a diff, if limited.
+    ///
+    /// ```
+    /// # use jd_core::Limits;
+    /// assert_eq!(Limits::default().timeout(), None);
+    /// ```
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Checks the length of an input before it is parsed.
+    ///
+    /// ```
+    /// # use jd_core::{LimitError, Limits};
+    /// let limits = Limits::default().with_max_input_bytes(4);
+    /// assert!(limits.check_input_len(4).is_ok());
+    /// assert_eq!(limits.check_input_len(5), Err(LimitError::InputTooLarge { limit: 4 }));
+    /// ```
+    pub fn check_input_len(&self, len: usize) -> Result<(), LimitError> {
+        match self.max_input_bytes {
+            Some(limit) if len > limit => Err(LimitError::InputTooLarge { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the node count and nesting depth of a parsed document.
+    ///
+    /// The walk stops at the first violation, so oversized documents are
+    /// rejected without being traversed in full.
+    ///
+    /// ```
+    /// # use jd_core::{LimitError, Limits, Node};
+    /// let node = Node::from_json_str(r#"{"a":[1,2]}"#).unwrap();
+    /// assert!(Limits::default().with_max_nodes(4).check_node(&node).is_ok());
+    /// assert_eq!(
+    ///     Limits::default().with_max_nodes(3).check_node(&node),
+    ///     Err(LimitError::TooManyNodes { limit: 3 })
+    /// );
+    /// ```
+    pub fn check_node(&self, node: &Node) -> Result<(), LimitError> {
+        if self.max_nodes.is_none() && self.max_depth.is_none() {
+            return Ok(());
+        }
+        let mut count = 0usize;
+        let outcome = node.walk_depths(|_, depth| {
+            count += 1;
+            if let Some(limit) = self.max_nodes.filter(|&limit| count > limit) {
+                return ControlFlow::Break(LimitError::TooManyNodes { limit });
+            }
+            if let Some(limit) = self.max_depth.filter(|&limit| depth > limit) {
+                return ControlFlow::Break(LimitError::TooDeep { limit });
+            }
+            ControlFlow::Continue(())
+        });
+        match outcome {
+            ControlFlow::Break(err) => Err(err),
+            ControlFlow::Continue(()) => Ok(()),
+        }
+    }
+}
+
+/// Wall-clock and hunk budgets checked while diffing.
+///
+/// Once either budget runs out every check fails, so the diff unwinds
+/// quickly with a partial result: the caller discards it after a timeout
+/// and keeps the hunks found so far after running out of hunks.
+pub(crate) struct Deadline {
+    at: Option<Instant>,
+    expired: Cell<bool>,
+    hunks_left: Cell<Option<usize>>,
+}
+
+impl Deadline {
+    pub(crate) fn after(timeout: Option<Duration>) -> Self {
+        Self {
+            at: timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+            expired: Cell::new(false),
+            hunks_left: Cell::new(None),
+        }
+    }
+
+    pub(crate) fn unbounded() -> Self {
+        Self::after(None)
+    }
+
+    /// Stops the diff once `max` hunks have been found.
+    pub(crate) fn limit_hunks(&self, max: Option<usize>) {
+        self.hunks_left.set(max);
+    }
+
+    /// Counts hunks added to the diff.
+    pub(crate) fn found(&self, hunks: usize) {
+        if let Some(left) = self.hunks_left.get() {
+            self.hunks_left.set(Some(left.saturating_sub(hunks)));
+        }
+    }
+
+    /// Returns `true` once the deadline has passed or the hunk limit has
+    /// been reached.
+    pub(crate) fn check(&self) -> bool {
+        if self.expired.get() || self.hunks_exhausted() {
+            return true;
+        }
+        let expired = self.at.is_some_and(|at| Instant::now() >= at);
+        self.expired.set(expired);
+        expired
+    }
+
+    /// Whether a check failed because time ran out, i.e. the diff was cut
+    /// short.
+    pub(crate) fn expired(&self) -> bool {
+        self.expired.get()
+    }
+
+    /// Whether the hunk limit has been reached.
+    pub(crate) fn hunks_exhausted(&self) -> bool {
+        self.hunks_left.get() == Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiffOptions;
+
+    #[test]
+    fn depth_counts_containers_only() {
+        let node = Node::from_json_str(r#"{"a":[{"b":1}]}"#).unwrap();
+        assert!(Limits::default().with_max_depth(3).check_node(&node).is_ok());
+        assert_eq!(
+            Limits::default().with_max_depth(2).check_node(&node),
+            Err(LimitError::TooDeep { limit: 2 })
+        );
+        assert!(Limits::default().with_max_depth(0).check_node(&Node::Null).is_ok());
+    }
+
+    #[test]
+    fn expired_deadline_aborts_diff() {
+        let lhs = Node::from_json_str(&format!("{:?}", (0..200).collect::<Vec<_>>())).unwrap();
+        let rhs = Node::from_json_str(&format!("{:?}", (100..300).collect::<Vec<_>>())).unwrap();
+        let limits = Limits::default().with_timeout(Duration::ZERO);
+        let err = lhs.diff_with_limits(&rhs, &DiffOptions::default(), &limits).unwrap_err();
+        assert_eq!(err, LimitError::Timeout { timeout: Duration::ZERO });
+
+        let limits = Limits::default().with_timeout(Duration::from_secs(60));
+        let diff = lhs.diff_with_limits(&rhs, &DiffOptions::default(), &limits).unwrap();
+        assert_eq!(diff, lhs.diff(&rhs, &DiffOptions::default()));
+    }
+}
@@ -0,0 +1,210 @@
+//! Source positions for canonicalization errors and parsed values.
+
+use crate::diff::{Path, PathSegment};
+
+/// Where in the input text a parse error occurred.
+///
+/// Obtained from [`CanonicalizeError::location`](crate::CanonicalizeError::location).
+///
+/// ```
+/// # use jd_core::Node;
+/// let input = "{\"a\": [1, 2,, 3]}";
+/// let location = Node::from_json_str(input).unwrap_err().location(input).unwrap();
+/// assert_eq!((location.line(), location.column(), location.offset()), (1, 13, 12));
+/// assert_eq!(location.path().unwrap().to_string(), "[a 2]");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    offset: usize,
+    line: usize,
+    column: usize,
+    path: Option<Path>,
+}
+
+impl Location {
+    /// Builds a location from already known coordinates.
+    pub(crate) fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self { offset, line, column, path: None }
+    }
+
+    /// Builds a location from a byte offset, clamped to the input.
+    pub(crate) fn from_offset(input: &str, offset: usize) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = input[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        Self {
+            offset,
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            path: None,
+        }
+    }
+
+    /// Builds a location from a 1-based line and 1-based byte column.
+    pub(crate) fn from_line_column(input: &str, line: usize, column: usize) -> Self {
+        let line_start =
+            input.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum::<usize>();
+        Self::from_offset(input, line_start + column.saturating_sub(1))
+    }
+
+    /// Adds the path of the value open at byte `end` (the error offset, or the
+    /// end of input when parsing ran out of data).
+    pub(crate) fn with_json_path(mut self, input: &str, end: usize) -> Self {
+        self.path = Some(json_path_at(&input[..end]));
+        self
+    }
+
+    /// Byte offset of the error within the input.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let input = "[1,]";
+    /// let err = Node::from_json_str(input).unwrap_err();
+    /// assert_eq!(err.location(input).unwrap().offset(), 3);
+    /// ```
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// 1-based line number.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let input = "{\n  \"a\": [1,]\n}";
+    /// let err = Node::from_json_str(input).unwrap_err();
+    /// assert_eq!(err.location(input).unwrap().line(), 2);
+    /// ```
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based column, counted in characters.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let input = "[\"é\", x]";
+    /// let err = Node::from_json_str(input).unwrap_err();
+    /// assert_eq!(err.location(input).unwrap().column(), 7);
+    /// ```
+    #[must_use]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Best-effort path of the value being parsed when the error occurred.
+    ///
+    /// Only available for JSON input; `None` for other formats.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let input = "{\"spec\": {\"replicas\": +1}}";
+    /// let err = Node::from_json_str(input).unwrap_err();
+    /// assert_eq!(err.location(input).unwrap().path().unwrap().to_string(), "[spec replicas]");
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+}
+
+enum Frame {
+    Object { key: Option<String>, expect_key: bool },
+    Array { index: i64 },
+}
+
+/// Reconstructs the path of the innermost value open at the end of `prefix`.
+fn json_path_at(prefix: &str) -> Path {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut chars = prefix.char_indices();
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '{' => stack.push(Frame::Object { key: None, expect_key: true }),
+            '[' => stack.push(Frame::Array { index: 0 }),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(Frame::Object { expect_key, .. }) = stack.last_mut() {
+                    *expect_key = false;
+                }
+            }
+            ',' => match stack.last_mut() {
+                Some(Frame::Object { key, expect_key }) => {
+                    *key = None;
+                    *expect_key = true;
+                }
+                Some(Frame::Array { index }) => *index += 1,
+                None => {}
+            },
+            '"' => {
+                let mut end = None;
+                let mut escaped = false;
+                for (idx, ch) in chars.by_ref() {
+                    match ch {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => {
+                            end = Some(idx);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                let Some(end) = end else { break };
+                if let Some(Frame::Object { key, expect_key: true }) = stack.last_mut() {
+                    *key = serde_json::from_str(&prefix[start..=end]).ok();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut path = Path::new();
+    for frame in stack {
+        match frame {
+            Frame::Object { key: Some(key), expect_key: false } => path.push(PathSegment::Key(key)),
+            Frame::Object { .. } => break,
+            Frame::Array { index } => path.push(PathSegment::Index(index)),
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_path_tracks_keys_indices_and_escapes() {
+        assert_eq!(json_path_at("{\"a\": [1, {\"b\\\"]\": ").to_string(), "[a 1 b\"]]");
+        assert_eq!(json_path_at("{\"a\": 1, ").to_string(), "[]");
+        assert_eq!(json_path_at("[[1], [2, ").to_string(), "[1 1]");
+    }
+
+    #[test]
+    fn yaml_and_toml_errors_have_locations_without_paths() {
+        let input = "a: 1\nb: [1, 2\n";
+        let location = crate::Node::from_yaml_str(input).unwrap_err().location(input).unwrap();
+        assert_eq!(location.line(), 3);
+        assert_eq!(location.path(), None);
+
+        #[cfg(feature = "toml")]
+        {
+            let input = "a = 1\nb = = 2\n";
+            let location = crate::Node::from_toml_str(input).unwrap_err().location(input).unwrap();
+            assert_eq!((location.line(), location.column()), (2, 5));
+        }
+    }
+
+    #[test]
+    fn offsets_clamp_to_char_boundaries() {
+        let location = Location::from_offset("\"é", 2);
+        assert_eq!((location.offset(), location.line(), location.column()), (1, 1, 2));
+        let location = Location::from_line_column("a\nbc", 2, 9);
+        assert_eq!((location.offset(), location.line(), location.column()), (4, 2, 3));
+    }
+}
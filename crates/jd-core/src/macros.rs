@@ -0,0 +1,67 @@
+/// Builds a [`Node`](crate::Node) from JSON-like syntax.
+///
+/// Arrays and objects nest as in JSON, `null` is [`Node::Null`](crate::Node::Null),
+/// and any other value is an expression converted with [`From`], so variables
+/// and existing nodes can be spliced in. Object keys must be string literals.
+///
+/// ```
+/// # use jd_core::{node, Node};
+/// let id = 7;
+/// let name = String::from("jd");
+/// let node = node!({"id": id, "name": name, "tags": ["a", -1.5, null, true], "meta": {}});
+/// assert_eq!(
+///     node,
+///     Node::from_json_str(r#"{"id":7,"name":"jd","tags":["a",-1.5,null,true],"meta":{}}"#)
+///         .unwrap()
+/// );
+/// assert_eq!(node!([node["id"].clone(), []]), Node::from_json_str("[7,[]]").unwrap());
+/// ```
+#[macro_export]
+macro_rules! node {
+    (null) => {
+        $crate::Node::Null
+    };
+    ([$($elements:tt)*]) => {
+        $crate::Node::Array($crate::node!(@array [] $($elements)*))
+    };
+    ({$($members:tt)*}) => {
+        $crate::Node::Object($crate::node!(@object [] $($members)*))
+    };
+    (@array [$($done:expr,)*]) => {
+        ::std::vec![$($done,)*]
+    };
+    (@array [$($done:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::node!(@array [$($done,)* $crate::Node::Null,] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::node!(@array [$($done,)* $crate::node!([$($inner)*]),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::node!(@array [$($done,)* $crate::node!({$($inner)*}),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::node!(@array [$($done,)* $crate::Node::from($next),] $($($rest)*)?)
+    };
+    (@object [$($done:expr,)*]) => {
+        ::std::collections::BTreeMap::<::std::string::String, $crate::Node>::from([$($done,)*])
+    };
+    (@object [$($done:expr,)*] $key:literal : null $(, $($rest:tt)*)?) => {
+        $crate::node!(@object [$($done,)* ($key.to_string(), $crate::Node::Null),] $($($rest)*)?)
+    };
+    (@object [$($done:expr,)*] $key:literal : [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::node!(
+            @object [$($done,)* ($key.to_string(), $crate::node!([$($inner)*])),] $($($rest)*)?
+        )
+    };
+    (@object [$($done:expr,)*] $key:literal : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::node!(
+            @object [$($done,)* ($key.to_string(), $crate::node!({$($inner)*})),] $($($rest)*)?
+        )
+    };
+    (@object [$($done:expr,)*] $key:literal : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::node!(@object [$($done,)* ($key.to_string(), $crate::Node::from($value)),] $($($rest)*)?)
+    };
+    ($other:expr) => {
+        $crate::Node::from($other)
+    };
+}
@@ -0,0 +1,212 @@
+//! Size and shape statistics for documents.
+
+use std::ops::{ControlFlow, RangeInclusive};
+
+use crate::Node;
+
+/// Depth, size, and container-cardinality statistics for a [`Node`].
+///
+/// Obtained from [`Node::metrics`].
+///
+/// ```
+/// # use jd_core::Node;
+/// let node = Node::from_json_str(r#"{"tags":["a","bc"],"meta":{}}"#).unwrap();
+/// let metrics = node.metrics();
+/// assert_eq!(metrics.depth(), 2);
+/// assert_eq!(metrics.nodes(), 5);
+/// assert_eq!(metrics.string_bytes(), 11);
+/// assert_eq!(metrics.array_lengths().total(), 1);
+/// assert_eq!(metrics.object_sizes().max(), Some(2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    depth: usize,
+    nodes: usize,
+    string_bytes: usize,
+    array_lengths: Histogram,
+    object_sizes: Histogram,
+}
+
+impl Metrics {
+    pub(crate) fn collect(node: &Node) -> Self {
+        let mut metrics = Self::default();
+        let _ = node.walk_depths(|node, depth| {
+            metrics.nodes += 1;
+            metrics.depth = metrics.depth.max(depth);
+            match node {
+                Node::String(s) => metrics.string_bytes += s.len(),
+                Node::Array(items) => metrics.array_lengths.record(items.len()),
+                Node::Object(map) => {
+                    metrics.object_sizes.record(map.len());
+                    metrics.string_bytes += map.keys().map(String::len).sum::<usize>();
+                }
+                _ => {}
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        metrics
+    }
+
+    /// Deepest nesting of arrays and objects. Scalars have depth 0; `[1]`
+    /// and `{}` have depth 1.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert_eq!(Node::from_json_str("[[1],2]").unwrap().metrics().depth(), 2);
+    /// ```
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of values, counting every array, object, and scalar.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert_eq!(Node::from_json_str("[[1],2]").unwrap().metrics().nodes(), 4);
+    /// ```
+    #[must_use]
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Total UTF-8 length of string values and object keys.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert_eq!(Node::from_json_str(r#"{"k":"é"}"#).unwrap().metrics().string_bytes(), 3);
+    /// ```
+    #[must_use]
+    pub fn string_bytes(&self) -> usize {
+        self.string_bytes
+    }
+
+    /// Histogram of array lengths.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let metrics = Node::from_json_str("[[],[1,2,3]]").unwrap().metrics();
+    /// let buckets: Vec<_> = metrics.array_lengths().buckets().collect();
+    /// assert_eq!(buckets, [(0..=0, 1), (2..=3, 2)]);
+    /// ```
+    #[must_use]
+    pub fn array_lengths(&self) -> &Histogram {
+        &self.array_lengths
+    }
+
+    /// Histogram of object sizes (number of keys).
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let metrics = Node::from_json_str(r#"[{"a":1},{}]"#).unwrap().metrics();
+    /// assert_eq!(metrics.object_sizes().buckets().collect::<Vec<_>>(), [(0..=0, 1), (1..=1, 1)]);
+    /// ```
+    #[must_use]
+    pub fn object_sizes(&self) -> &Histogram {
+        &self.object_sizes
+    }
+}
+
+/// Counts of container sizes in power-of-two buckets: `0`, `1`, `2..=3`,
+/// `4..=7`, and so on.
+///
+/// ```
+/// # use jd_core::Node;
+/// let metrics = Node::from_json_str("[[1,2,3,4,5],[1],[]]").unwrap().metrics();
+/// let lengths = metrics.array_lengths();
+/// assert_eq!(lengths.total(), 4);
+/// assert_eq!(lengths.max(), Some(5));
+/// assert_eq!(lengths.buckets().last(), Some((4..=7, 1)));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: Vec<usize>,
+    max: Option<usize>,
+}
+
+impl Histogram {
+    fn record(&mut self, size: usize) {
+        let bucket = (usize::BITS - size.leading_zeros()) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+        self.max = self.max.max(Some(size));
+    }
+
+    /// Number of containers recorded.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert_eq!(Node::from_json_str("1").unwrap().metrics().array_lengths().total(), 0);
+    /// ```
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    /// Largest size recorded, if any.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert_eq!(Node::from_json_str("[[1,2],[]]").unwrap().metrics().array_lengths().max(), Some(2));
+    /// ```
+    #[must_use]
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    /// Non-empty buckets in ascending order, as `(size range, count)`.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let metrics = Node::from_json_str("[1,2,3,4]").unwrap().metrics();
+    /// assert_eq!(metrics.array_lengths().buckets().collect::<Vec<_>>(), [(4..=7, 1)]);
+    /// ```
+    pub fn buckets(&self) -> impl Iterator<Item = (RangeInclusive<usize>, usize)> + '_ {
+        self.buckets.iter().enumerate().filter(|(_, count)| **count > 0).map(|(bucket, count)| {
+            let range = match bucket {
+                0 => 0..=0,
+                _ => {
+                    let low = 1usize << (bucket - 1);
+                    low..=low.saturating_sub(1).saturating_add(low)
+                }
+            };
+            (range, *count)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_cover_powers_of_two() {
+        let mut histogram = Histogram::default();
+        for size in [0, 1, 2, 3, 4, 7, 8, usize::MAX] {
+            histogram.record(size);
+        }
+        let buckets: Vec<_> = histogram.buckets().collect();
+        assert_eq!(
+            buckets,
+            [
+                (0..=0, 1),
+                (1..=1, 1),
+                (2..=3, 2),
+                (4..=7, 2),
+                (8..=15, 1),
+                (1 << (usize::BITS - 1)..=usize::MAX, 1)
+            ]
+        );
+        assert_eq!(histogram.max(), Some(usize::MAX));
+    }
+
+    #[test]
+    fn void_and_scalars_count_as_nodes() {
+        let metrics = Node::Void.metrics();
+        assert_eq!((metrics.nodes(), metrics.depth()), (1, 0));
+        let metrics = Node::from_json_str(r#"{"a":{"b":[null,true,"xy"]}}"#).unwrap().metrics();
+        assert_eq!((metrics.nodes(), metrics.depth(), metrics.string_bytes()), (6, 3, 4));
+    }
+}
@@ -1,12 +1,18 @@
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::ops::ControlFlow;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 
 use crate::{
-    hash::{combine, hash_bytes, HashCode},
-    ArrayMode, CanonicalizeError, DiffOptions, Number, PatchError,
+    hash::{Digest, HashCode},
+    limits::Deadline,
+    progress::{self, MeteredReader, Phase},
+    ArrayMode, CanonicalizeError, DiffOptions, LimitError, Limits, Metrics, Number, PatchError,
+    Path, PathSegment,
 };
 
 const VOID_HASH: HashCode = [0xF3, 0x97, 0x6B, 0x21, 0x91, 0x26, 0x8D, 0x96];
@@ -17,7 +23,25 @@ const LIST_SEED: [u8; 8] = [0xF5, 0x18, 0x0A, 0x71, 0xA4, 0xC4, 0x03, 0xF3];
 const OBJECT_SEED: [u8; 8] = [0x00, 0x5D, 0x39, 0xA4, 0x18, 0x10, 0xEA, 0xD5];
 
 /// Represents the canonical JSON data model used by the diff engine.
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+///
+/// Nodes are [`Eq`], [`Ord`], and [`Hash`], so they can key maps and sets.
+/// [`Number`] is never NaN, which makes equality total. The ordering sorts by
+/// type (void, `null`, booleans, numbers, strings, arrays, objects), then by
+/// value; arrays and objects compare element by element, member keys before
+/// values.
+///
+/// ```
+/// # use std::collections::BTreeSet;
+/// # use jd_core::Node;
+/// let nodes: BTreeSet<Node> = ["[1]", "\"b\"", "2", "null", "1.0"]
+///     .into_iter()
+///     .map(|json| Node::from_json_str(json).unwrap())
+///     .collect();
+/// let sorted: Vec<String> =
+///     nodes.iter().map(|node| node.to_json_value().unwrap().to_string()).collect();
+/// assert_eq!(sorted, ["null", "1", "2", "\"b\"", "[1]"]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
 pub enum Node {
     /// Sentinel representing the absence of a value.
@@ -36,6 +60,106 @@ pub enum Node {
     Object(BTreeMap<String, Node>),
 }
 
+/// Partially rebuilt container on the work stack of [`Node::fold`].
+enum FoldFrame<'a, T> {
+    Array {
+        items: Vec<T>,
+        rest: std::slice::Iter<'a, Node>,
+    },
+    Object {
+        object: BTreeMap<String, T>,
+        key: &'a str,
+        rest: std::collections::btree_map::Iter<'a, String, Node>,
+    },
+}
+
+/// Clones without recursion so that patching deeply nested documents cannot
+/// overflow the call stack.
+impl Clone for Node {
+    fn clone(&self) -> Self {
+        self.fold(|leaf| Some(leaf.shallow_clone()), Self::Array, Self::Object)
+            .expect("cloning never aborts")
+    }
+}
+
+/// Drops without recursion for the same reason: nested containers are
+/// moved onto a heap stack and emptied one at a time, so each drops with no
+/// containers left inside.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_nested(&mut stack);
+        while let Some(mut node) = stack.pop() {
+            node.take_nested(&mut stack);
+        }
+    }
+}
+
+impl Node {
+    /// Moves the non-empty arrays and objects directly inside `self` onto
+    /// `stack` and drops the other children.
+    fn take_nested(&mut self, stack: &mut Vec<Node>) {
+        let nested = |node: &Node| match node {
+            Self::Array(items) => !items.is_empty(),
+            Self::Object(members) => !members.is_empty(),
+            _ => false,
+        };
+        match self {
+            Self::Array(items) => stack.extend(items.drain(..).filter(nested)),
+            Self::Object(members) => {
+                stack.extend(std::mem::take(members).into_values().filter(nested));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Total order used to sort arrays and ordered collections of nodes.
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn rank(node: &Node) -> u8 {
+            match node {
+                Node::Void => 0,
+                Node::Null => 1,
+                Node::Bool(_) => 2,
+                Node::Number(_) => 3,
+                Node::String(_) => 4,
+                Node::Array(_) => 5,
+                Node::Object(_) => 6,
+            }
+        }
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Number(a), Self::Number(b)) => a.cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Array(a), Self::Array(b)) => a.cmp(b),
+            (Self::Object(a), Self::Object(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Partially converted container on the work stack of [`Node::from_json_value`].
+enum FromJsonFrame {
+    Array { items: Vec<Node>, rest: std::vec::IntoIter<JsonValue> },
+    Object { object: BTreeMap<String, Node>, key: String, rest: serde_json::map::IntoIter },
+}
+
+impl FromJsonFrame {
+    fn finish(self) -> Node {
+        match self {
+            Self::Array { items, .. } => Node::Array(items),
+            Self::Object { object, .. } => Node::Object(object),
+        }
+    }
+}
+
 impl Node {
     /// Parses a JSON string into the canonical node representation.
     ///
@@ -45,10 +169,75 @@ impl Node {
     /// assert!(matches!(node, Node::Object(_)));
     /// ```
     pub fn from_json_str(input: &str) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "json", bytes = input.len()).entered();
         if input.trim().is_empty() {
             return Ok(Self::Void);
         }
-        let value: JsonValue = serde_json::from_str(input)?;
+        let scope = progress::Scope::enter();
+        let value: JsonValue = if scope.is_reporting() {
+            // Reading through a meter is slower than parsing the string
+            // directly, so only pay for it when someone is listening.
+            let meter = scope.meter(Phase::Parse, input.len());
+            let reader = MeteredReader { inner: input.as_bytes(), meter: &meter };
+            let value = serde_json::from_reader(io::BufReader::new(reader));
+            meter.finish();
+            value?
+        } else {
+            #[cfg(feature = "simd")]
+            if let Some(node) = crate::simd::parse(input) {
+                return node;
+            }
+            serde_json::from_str(input)?
+        };
+        Self::from_json_value(value)
+    }
+
+    /// Parses a JSON string like [`Node::from_json_str`], also recording the
+    /// source span of every value in a [`ParseIndex`](crate::ParseIndex).
+    ///
+    /// Indexing costs an extra pass over the text and a path per value, so
+    /// it is only done on request.
+    ///
+    /// ```
+    /// # use jd_core::{Node, Path, PathSegment};
+    /// let input = "{\"a\": 1,\n \"b\": 2}";
+    /// let (node, index) = Node::from_json_str_indexed(input).unwrap();
+    /// assert_eq!(node, Node::from_json_str(input).unwrap());
+    /// assert_eq!(index.span_of(&Path::from(PathSegment::key("b"))).unwrap().start().line(), 2);
+    /// ```
+    pub fn from_json_str_indexed(
+        input: &str,
+    ) -> Result<(Self, crate::ParseIndex), CanonicalizeError> {
+        let node = Self::from_json_str(input)?;
+        Ok((node, crate::ParseIndex::build(input)))
+    }
+
+    /// Parses JSON read from `reader` into the canonical node representation.
+    ///
+    /// Reads incrementally instead of buffering the whole input as a string,
+    /// which matters for documents of hundreds of megabytes. Wrap unbuffered
+    /// readers such as [`std::fs::File`] in a [`std::io::BufReader`]. Read
+    /// failures are reported as [`CanonicalizeError::Json`].
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_reader(&b"{\"hello\":\"world\"}"[..]).expect("valid JSON");
+    /// assert_eq!(node, Node::from_json_str("{\"hello\":\"world\"}").unwrap());
+    /// assert_eq!(Node::from_json_reader(&b" \n"[..]).unwrap(), Node::Void);
+    /// assert!(Node::from_json_reader(&b"1 2"[..]).is_err());
+    /// ```
+    pub fn from_json_reader(reader: impl std::io::Read) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "json").entered();
+        let mut values = serde_json::Deserializer::from_reader(reader).into_iter::<JsonValue>();
+        let Some(value) = values.next().transpose()? else {
+            return Ok(Self::Void);
+        };
+        if let Some(trailing) = values.next() {
+            // A second value is as invalid as trailing garbage; reject both.
+            trailing?;
+            let err: serde_json::Error = serde::de::Error::custom("trailing characters");
+            return Err(err.into());
+        }
         Self::from_json_value(value)
     }
 
@@ -60,11 +249,93 @@ impl Node {
     /// assert!(matches!(node, Node::Object(_)));
     /// ```
     pub fn from_yaml_str(input: &str) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "yaml", bytes = input.len()).entered();
         if input.trim().is_empty() {
             return Ok(Self::Void);
         }
-        let value: YamlValue = serde_yaml::from_str(input)?;
-        Self::from_yaml_value(value)
+        let scope = progress::Scope::enter();
+        let meter = scope.meter(Phase::Parse, 1);
+        let value = serde_yaml::from_str(input);
+        meter.finish();
+        Self::from_yaml_value(value?)
+    }
+
+    /// Parses a TOML document into the canonical node representation.
+    ///
+    /// Datetimes become strings in their RFC 3339 form. Requires the `toml`
+    /// feature.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_toml_str("[package]\nname = \"jd\"\n").expect("valid TOML");
+    /// assert_eq!(node, Node::from_json_str(r#"{"package":{"name":"jd"}}"#).unwrap());
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(input: &str) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "toml", bytes = input.len()).entered();
+        if input.trim().is_empty() {
+            return Ok(Self::Void);
+        }
+        let table: toml::Table = input.parse()?;
+        Self::from_toml_value(toml::Value::Table(table))
+    }
+
+    /// Decodes a CBOR item into the canonical node representation.
+    ///
+    /// Byte strings, tags, and non-string map keys have no JSON equivalent
+    /// and are rejected. Requires the `cbor` feature.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// // {"a": 1}
+    /// let node = Node::from_cbor_slice(&[0xA1, 0x61, b'a', 0x01]).expect("valid CBOR");
+    /// assert_eq!(node, Node::from_json_str(r#"{"a":1}"#).unwrap());
+    /// ```
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor_slice(input: &[u8]) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "cbor", bytes = input.len()).entered();
+        if input.is_empty() {
+            return Ok(Self::Void);
+        }
+        let value: JsonValue = ciborium::from_reader(input)?;
+        Self::from_json_value(value)
+    }
+
+    /// Decodes a MessagePack value into the canonical node representation.
+    ///
+    /// Binary, extension types, and non-string map keys have no JSON
+    /// equivalent and are rejected. Requires the `msgpack` feature.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// // {"a": 1}
+    /// let node = Node::from_msgpack_slice(&[0x81, 0xA1, b'a', 0x01]).expect("valid MessagePack");
+    /// assert_eq!(node, Node::from_json_str(r#"{"a":1}"#).unwrap());
+    /// ```
+    #[cfg(feature = "msgpack")]
+    pub fn from_msgpack_slice(input: &[u8]) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "msgpack", bytes = input.len()).entered();
+        if input.is_empty() {
+            return Ok(Self::Void);
+        }
+        let value: JsonValue = rmp_serde::from_slice(input)?;
+        Self::from_json_value(value)
+    }
+
+    /// Converts any [`Serialize`] value into a [`Node`] through its JSON
+    /// representation.
+    ///
+    /// Fails with [`CanonicalizeError::Json`] when the value has no JSON form,
+    /// such as a map with non-string keys. Non-finite floats become `null`.
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use jd_core::{node, Node};
+    /// let config = BTreeMap::from([("retries", vec![1, 2])]);
+    /// assert_eq!(Node::from_serialize(&config).unwrap(), node!({"retries": [1, 2]}));
+    /// ```
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self, CanonicalizeError> {
+        Self::from_json_value(serde_json::to_value(value)?)
     }
 
     /// Converts a serde JSON value into a [`Node`].
@@ -76,6 +347,51 @@ impl Node {
     /// assert!(matches!(node, Node::Object(_)));
     /// ```
     pub fn from_json_value(value: JsonValue) -> Result<Self, CanonicalizeError> {
+        // Containers are converted with an explicit work stack so that deeply
+        // nested values cannot overflow the call stack.
+        let mut stack: Vec<FromJsonFrame> = Vec::new();
+        let mut value = value;
+        loop {
+            let mut done = match value {
+                JsonValue::Array(values) => {
+                    let items = Vec::with_capacity(values.len());
+                    stack.push(FromJsonFrame::Array { items, rest: values.into_iter() });
+                    None
+                }
+                JsonValue::Object(map) => {
+                    let (object, key) = (BTreeMap::new(), String::new());
+                    stack.push(FromJsonFrame::Object { object, key, rest: map.into_iter() });
+                    None
+                }
+                scalar => Some(Self::from_json_scalar(scalar)?),
+            };
+            value = loop {
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(done.expect("the root value completes last"));
+                };
+                match frame {
+                    FromJsonFrame::Array { items, rest } => {
+                        items.extend(done.take());
+                        if let Some(next) = rest.next() {
+                            break next;
+                        }
+                    }
+                    FromJsonFrame::Object { object, key, rest } => {
+                        if let Some(node) = done.take() {
+                            object.insert(std::mem::take(key), node);
+                        }
+                        if let Some((next_key, next)) = rest.next() {
+                            *key = next_key;
+                            break next;
+                        }
+                    }
+                }
+                done = stack.pop().map(FromJsonFrame::finish);
+            };
+        }
+    }
+
+    fn from_json_scalar(value: JsonValue) -> Result<Self, CanonicalizeError> {
         match value {
             JsonValue::Null => Ok(Self::Null),
             JsonValue::Bool(v) => Ok(Self::Bool(v)),
@@ -87,19 +403,8 @@ impl Node {
                 Ok(Self::Number(Number::new(as_f64)?))
             }
             JsonValue::String(s) => Ok(Self::String(s)),
-            JsonValue::Array(values) => {
-                let mut items = Vec::with_capacity(values.len());
-                for value in values {
-                    items.push(Self::from_json_value(value)?);
-                }
-                Ok(Self::Array(items))
-            }
-            JsonValue::Object(map) => {
-                let mut object = BTreeMap::new();
-                for (key, value) in map {
-                    object.insert(key, Self::from_json_value(value)?);
-                }
-                Ok(Self::Object(object))
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                unreachable!("containers are converted by from_json_value")
             }
         }
     }
@@ -149,6 +454,29 @@ impl Node {
         }
     }
 
+    #[cfg(feature = "toml")]
+    fn from_toml_value(value: toml::Value) -> Result<Self, CanonicalizeError> {
+        match value {
+            toml::Value::String(s) => Ok(Self::String(s)),
+            toml::Value::Integer(i) => Ok(Self::Number(Number::new(i as f64)?)),
+            toml::Value::Float(f) => Ok(Self::Number(Number::new(f)?)),
+            toml::Value::Boolean(b) => Ok(Self::Bool(b)),
+            toml::Value::Datetime(datetime) => Ok(Self::String(datetime.to_string())),
+            toml::Value::Array(values) => values
+                .into_iter()
+                .map(Self::from_toml_value)
+                .collect::<Result<_, _>>()
+                .map(Self::Array),
+            toml::Value::Table(table) => {
+                let mut object = BTreeMap::new();
+                for (key, value) in table {
+                    object.insert(key, Self::from_toml_value(value)?);
+                }
+                Ok(Self::Object(object))
+            }
+        }
+    }
+
     /// Converts the node into a serde JSON value when representable.
     ///
     /// Returns `None` when the node contains the `Void` sentinel (either at the
@@ -165,29 +493,159 @@ impl Node {
     /// ```
     #[must_use]
     pub fn to_json_value(&self) -> Option<JsonValue> {
-        match self {
-            Self::Void => None,
-            Self::Null => Some(JsonValue::Null),
-            Self::Bool(v) => Some(JsonValue::Bool(*v)),
-            Self::Number(n) => Some(JsonValue::Number(n.to_json_number())),
-            Self::String(s) => Some(JsonValue::String(s.clone())),
-            Self::Array(values) => {
-                let mut result = Vec::with_capacity(values.len());
-                for value in values {
-                    result.push(value.to_json_value()?);
+        self.fold(
+            |leaf| match leaf {
+                Self::Void => None,
+                Self::Null => Some(JsonValue::Null),
+                Self::Bool(v) => Some(JsonValue::Bool(*v)),
+                Self::Number(n) => Some(JsonValue::Number(n.to_json_number())),
+                Self::String(s) => Some(JsonValue::String(s.clone())),
+                Self::Array(_) | Self::Object(_) => unreachable!("fold only passes leaves"),
+            },
+            JsonValue::Array,
+            |object| JsonValue::Object(object.into_iter().collect()),
+        )
+    }
+
+    /// Rebuilds the node bottom-up with an explicit work stack instead of
+    /// recursion, so deeply nested documents cannot overflow the call stack.
+    ///
+    /// `leaf` converts scalars and `Void` (returning `None` aborts the fold);
+    /// `array` and `object` assemble converted children.
+    pub(crate) fn fold<T>(
+        &self,
+        mut leaf: impl FnMut(&Self) -> Option<T>,
+        mut array: impl FnMut(Vec<T>) -> T,
+        mut object: impl FnMut(BTreeMap<String, T>) -> T,
+    ) -> Option<T> {
+        let mut stack: Vec<FoldFrame<'_, T>> = Vec::new();
+        let mut node = self;
+        loop {
+            let mut done = match node {
+                Self::Array(values) => {
+                    let items = Vec::with_capacity(values.len());
+                    stack.push(FoldFrame::Array { items, rest: values.iter() });
+                    None
                 }
-                Some(JsonValue::Array(result))
-            }
-            Self::Object(map) => {
-                let mut object = serde_json::Map::new();
-                for (key, value) in map {
-                    object.insert(key.clone(), value.to_json_value()?);
+                Self::Object(map) => {
+                    let rest = map.iter();
+                    stack.push(FoldFrame::Object { object: BTreeMap::new(), key: "", rest });
+                    None
                 }
-                Some(JsonValue::Object(object))
-            }
+                leaf_node => Some(leaf(leaf_node)?),
+            };
+            node = loop {
+                let Some(frame) = stack.last_mut() else {
+                    return done;
+                };
+                match frame {
+                    FoldFrame::Array { items, rest } => {
+                        items.extend(done.take());
+                        if let Some(next) = rest.next() {
+                            break next;
+                        }
+                    }
+                    FoldFrame::Object { object, key, rest } => {
+                        if let Some(value) = done.take() {
+                            object.insert((*key).to_string(), value);
+                        }
+                        if let Some((next_key, next)) = rest.next() {
+                            *key = next_key;
+                            break next;
+                        }
+                    }
+                }
+                done = stack.pop().map(|frame| match frame {
+                    FoldFrame::Array { items, .. } => array(items),
+                    FoldFrame::Object { object: map, .. } => object(map),
+                });
+            };
         }
     }
 
+    /// Clones a scalar or `Void`; containers are handled by [`Node::fold`].
+    pub(crate) fn shallow_clone(&self) -> Self {
+        match self {
+            Self::Void => Self::Void,
+            Self::Null => Self::Null,
+            Self::Bool(v) => Self::Bool(*v),
+            Self::Number(n) => Self::Number(*n),
+            Self::String(s) => Self::String(s.clone()),
+            Self::Array(_) | Self::Object(_) => unreachable!("fold only passes leaves"),
+        }
+    }
+
+    /// Serializes the node as a YAML document, returning `None` for void.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str("{\"b\":[1,2],\"a\":\"x\"}").expect("valid JSON");
+    /// assert_eq!(node.to_yaml_string().unwrap(), "a: x\nb:\n- 1\n- 2\n");
+    /// ```
+    #[must_use]
+    pub fn to_yaml_string(&self) -> Option<String> {
+        let value = self.to_json_value()?;
+        Some(serde_yaml::to_string(&value).expect("JSON values always serialize as YAML"))
+    }
+
+    /// Serializes the node as canonical JSON per RFC 8785 (JCS).
+    ///
+    /// Object keys are sorted by UTF-16 code units, numbers use the shortest
+    /// ECMAScript form, and there is no whitespace, so equal documents always
+    /// produce identical bytes, suitable for hashing or signing.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str(r#"{"b": 1e21, "a": [0.50, "\u00e9"]}"#).unwrap();
+    /// assert_eq!(node.to_canonical_json_string().unwrap(), r#"{"a":[0.5,"é"],"b":1e+21}"#);
+    /// ```
+    pub fn to_canonical_json_string(&self) -> Result<String, crate::EncodeError> {
+        crate::canonical::to_string(self)
+    }
+
+    /// Serializes the node as a TOML document. Requires the `toml` feature.
+    ///
+    /// Only objects can be encoded at the root, and TOML has no `null`.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str(r#"{"package":{"name":"jd"},"edition":2021}"#).unwrap();
+    /// assert_eq!(node.to_toml_string().unwrap(), "edition = 2021\n\n[package]\nname = \"jd\"\n");
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, crate::EncodeError> {
+        let value = self.to_json_value().ok_or(crate::EncodeError::Void)?;
+        Ok(toml::to_string(&value)?)
+    }
+
+    /// Encodes the node as a CBOR item. Requires the `cbor` feature.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str(r#"{"a":1}"#).unwrap();
+    /// assert_eq!(node.to_cbor_vec().unwrap(), [0xA1, 0x61, b'a', 0x01]);
+    /// ```
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor_vec(&self) -> Result<Vec<u8>, crate::EncodeError> {
+        let value = self.to_json_value().ok_or(crate::EncodeError::Void)?;
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&value, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Encodes the node as a MessagePack value. Requires the `msgpack` feature.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str(r#"{"a":1}"#).unwrap();
+    /// assert_eq!(node.to_msgpack_vec().unwrap(), [0x81, 0xA1, b'a', 0x01]);
+    /// ```
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack_vec(&self) -> Result<Vec<u8>, crate::EncodeError> {
+        let value = self.to_json_value().ok_or(crate::EncodeError::Void)?;
+        Ok(rmp_serde::to_vec(&value)?)
+    }
+
     /// Structural equality that respects [`DiffOptions`].
     ///
     /// ```
@@ -201,35 +659,69 @@ impl Node {
     /// ```
     #[must_use]
     pub fn eq_with_options(&self, other: &Self, options: &DiffOptions) -> bool {
+        if options.diff_off() {
+            // Only paths below that turn diffing back on are compared.
+            return match (self, other) {
+                _ if options.ignores_all() => true,
+                (Self::Array(a), Self::Array(b)) => {
+                    a.iter().zip(b).enumerate().all(|(index, (a, b))| {
+                        a.eq_with_options(b, &options.refine_index(index as i64))
+                    })
+                }
+                (Self::Object(a), Self::Object(b)) => object_equals(a, b, options),
+                _ => true,
+            };
+        }
+        if let Some(equal) = options.custom_equality(self, other) {
+            return equal;
+        }
+        if options.prune_empty() && self.is_prunable() && other.is_prunable() {
+            return true;
+        }
         match (self, other) {
             (Self::Void, Self::Void) => true,
             (Self::Null, Self::Null) => true,
             (Self::Bool(a), Self::Bool(b)) => a == b,
-            (Self::Number(a), Self::Number(b)) => a.equals_with_precision(*b, options.precision()),
-            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Number(a), Self::Number(b)) => options.numbers_equal(*a, *b),
+            (Self::String(a), Self::String(b)) => options.strings_equal(a, b),
             (Self::Array(a), Self::Array(b)) => match options.array_mode() {
                 ArrayMode::List => list_equals(a, b, options),
                 ArrayMode::Set => set_equals(a, b, options),
                 ArrayMode::MultiSet => multiset_equals(a, b, options),
             },
-            (Self::Object(a), Self::Object(b)) => {
-                if a.len() != b.len() {
-                    return false;
-                }
-                for (key, value_a) in a {
-                    let Some(value_b) = b.get(key) else {
-                        return false;
-                    };
-                    if !value_a.eq_with_options(value_b, options) {
-                        return false;
-                    }
-                }
-                true
+            (Self::Object(a), Self::Object(b)) => object_equals(a, b, options),
+            (Self::String(text), scalar) | (scalar, Self::String(text))
+                if options.string_scalars() =>
+            {
+                spells_scalar(text, scalar, options)
             }
             _ => false,
         }
     }
 
+    /// Returns the path of the first value that differs under `options`, or
+    /// `None` when the nodes are equal.
+    ///
+    /// Unlike [`Node::diff`] this stops at the first difference and builds no
+    /// hunks, which makes it cheap enough for assertion messages. Objects are
+    /// walked in key order and lists by position; when one list is longer the
+    /// path points at its first extra element. Arrays compared as sets or
+    /// multisets are reported as a whole.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let lhs = Node::from_json_str(r#"{"a":{"b":[1,2,3]},"c":1}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"a":{"b":[1,5,3]},"c":2}"#).unwrap();
+    /// let opts = DiffOptions::default();
+    /// assert_eq!(lhs.explain_inequality(&rhs, &opts).unwrap().to_string(), "[a b 1]");
+    /// assert_eq!(lhs.explain_inequality(&lhs, &opts), None);
+    /// ```
+    #[must_use]
+    pub fn explain_inequality(&self, other: &Self, options: &DiffOptions) -> Option<Path> {
+        let mut path = Path::new();
+        first_difference(self, other, options, &mut path).then_some(path)
+    }
+
     /// Computes the structural diff between two nodes.
     ///
     /// ```
@@ -244,6 +736,42 @@ impl Node {
         crate::diff::diff_nodes(self, other, options)
     }
 
+    /// Computes the structural diff, enforcing the node count, nesting depth,
+    /// and timeout of `limits`.
+    ///
+    /// Both documents are checked with [`Limits::check_node`] before diffing.
+    /// A diff that exceeds the timeout is abandoned with
+    /// [`LimitError::Timeout`].
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use jd_core::{DiffOptions, LimitError, Limits, Node};
+    /// let lhs = Node::from_json_str("[1,2,3]").unwrap();
+    /// let rhs = Node::from_json_str("[1,4,3]").unwrap();
+    /// let limits = Limits::default().with_timeout(Duration::from_secs(5));
+    /// let diff = lhs.diff_with_limits(&rhs, &DiffOptions::default(), &limits).unwrap();
+    /// assert_eq!(diff.len(), 1);
+    ///
+    /// let limits = Limits::default().with_max_nodes(3);
+    /// let err = lhs.diff_with_limits(&rhs, &DiffOptions::default(), &limits).unwrap_err();
+    /// assert_eq!(err, LimitError::TooManyNodes { limit: 3 });
+    /// ```
+    pub fn diff_with_limits(
+        &self,
+        other: &Self,
+        options: &DiffOptions,
+        limits: &Limits,
+    ) -> Result<crate::Diff, LimitError> {
+        limits.check_node(self)?;
+        limits.check_node(other)?;
+        let deadline = Deadline::after(limits.timeout());
+        let diff = crate::diff::diff_nodes_until(self, other, options, &deadline);
+        match limits.timeout() {
+            Some(timeout) if deadline.expired() => Err(LimitError::Timeout { timeout }),
+            _ => Ok(diff),
+        }
+    }
+
     /// Applies a diff to this node, returning the patched node on success.
     ///
     /// ```
@@ -260,6 +788,9 @@ impl Node {
 
     /// Computes the Go-compatible hash code for this node.
     ///
+    /// With [`HashAlgorithm::SipHash128`](crate::HashAlgorithm::SipHash128)
+    /// this is the first half of the 128-bit digest used for matching.
+    ///
     /// ```
     /// # use jd_core::{DiffOptions, Node};
     /// let node = Node::from_json_str("{\"x\":true}").expect("valid JSON");
@@ -269,13 +800,54 @@ impl Node {
     /// ```
     #[must_use]
     pub fn hash_code(&self, options: &DiffOptions) -> HashCode {
+        let digest = self.digest(options);
+        let mut code = [0; 8];
+        code.copy_from_slice(&digest[..8]);
+        code
+    }
+
+    /// Hashes the node with the options' [`hasher`](DiffOptions::hasher).
+    pub(crate) fn digest(&self, options: &DiffOptions) -> Digest {
+        if let Some(digest) = options.digests().and_then(|digests| digests.get(self)) {
+            return digest;
+        }
+        // Scalars are cheaper to hash again than to look up.
+        let memo = options.memo().filter(|_| matches!(self, Self::Array(_) | Self::Object(_)));
+        if let Some(digest) = memo.and_then(|memo| memo.get(self)) {
+            return digest;
+        }
+        let digest = self.digest_uncached(options);
+        if let Some(memo) = memo {
+            memo.insert(self, digest);
+        }
+        digest
+    }
+
+    fn digest_uncached(&self, options: &DiffOptions) -> Digest {
+        let hashing = options.hashing();
+        // Values that are not diffed are all equal to each other.
+        let container = matches!(self, Self::Array(_) | Self::Object(_));
+        if options.diff_off() && (options.ignores_all() || !container) {
+            return hashing.constant(VOID_HASH, b'v');
+        }
+        if options.prune_empty() && self.is_prunable() {
+            return hash_object(&BTreeMap::new(), options);
+        }
         match self {
-            Self::Void => VOID_HASH,
-            Self::Null => NULL_HASH,
-            Self::Bool(true) => BOOL_TRUE_HASH,
-            Self::Bool(false) => BOOL_FALSE_HASH,
-            Self::Number(n) => n.hash_code(),
-            Self::String(s) => hash_bytes(s.as_bytes()),
+            Self::Void => hashing.constant(VOID_HASH, b'v'),
+            Self::Null => hashing.constant(NULL_HASH, b'z'),
+            Self::Bool(true) => hashing.constant(BOOL_TRUE_HASH, b't'),
+            Self::Bool(false) => hashing.constant(BOOL_FALSE_HASH, b'f'),
+            // `0.0` and `-0.0` are equal, so they share a digest.
+            Self::Number(n) => hashing.digest(b'n', &(n.get() + 0.0).to_le_bytes()),
+            // A string hashes as the scalar it spells, so list alignment
+            // pairs it with that scalar as `eq_with_options` does.
+            Self::String(s) => {
+                match options.string_scalars().then(|| spelled_scalar(s)).flatten() {
+                    Some(scalar) => scalar.digest_uncached(options),
+                    None => hashing.digest(b's', s.as_bytes()),
+                }
+            }
             Self::Array(values) => match options.array_mode() {
                 ArrayMode::List => hash_list(values, options),
                 ArrayMode::Set => hash_set(values, options),
@@ -284,6 +856,208 @@ impl Node {
             Self::Object(map) => hash_object(map, options),
         }
     }
+
+    /// Returns a copy of the node with the value at each of `paths` replaced
+    /// by `replacement`.
+    ///
+    /// Use it to hide secrets such as tokens or passwords before a diff is
+    /// rendered and shared. Paths that do not exist in the document are
+    /// skipped, so the same list can be applied to both sides of a diff.
+    ///
+    /// ```
+    /// # use jd_core::{Node, Path, PathSegment};
+    /// let node = Node::from_json_str(r#"{"auth":{"token":"s3cr3t","user":"jd"}}"#).unwrap();
+    /// let token = Path::new().with_segment(PathSegment::key("auth")).with_segment(PathSegment::key("token"));
+    /// let missing = Path::from(PathSegment::key("password"));
+    /// let redacted = node.redact([&token, &missing], &Node::String("***".into()));
+    /// assert_eq!(redacted, Node::from_json_str(r#"{"auth":{"token":"***","user":"jd"}}"#).unwrap());
+    /// ```
+    #[must_use]
+    pub fn redact<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a Path>,
+        replacement: &Self,
+    ) -> Self {
+        let mut redacted = self.clone();
+        for path in paths {
+            if let Some(value) = redacted.get_mut(path.segments()) {
+                *value = replacement.clone();
+            }
+        }
+        redacted
+    }
+
+    /// Returns a copy of the node with arrays of objects sorted by a member.
+    ///
+    /// `key` is called with the path of every array and returns the object
+    /// key to sort that array by, or `None` to leave it alone. Elements are
+    /// ordered by the value of that key (`null`, then booleans, numbers,
+    /// strings, arrays, and objects); elements without it keep their relative
+    /// order after the rest. The sort is stable and keeps duplicates, so it
+    /// makes list diffs independent of element order without switching to set
+    /// semantics.
+    ///
+    /// ```
+    /// # use jd_core::{Node, PathSegment};
+    /// let node = Node::from_json_str(
+    ///     r#"{"users":[{"id":2},{"id":1},{"name":"x"},{"id":1,"dup":true}],"tags":[{"id":9},{"id":3}]}"#,
+    /// )
+    /// .unwrap();
+    /// let sorted = node.sort_arrays_by(|path| {
+    ///     (path.segments() == [PathSegment::key("users")]).then_some("id")
+    /// });
+    /// let expected = Node::from_json_str(
+    ///     r#"{"users":[{"id":1},{"id":1,"dup":true},{"id":2},{"name":"x"}],"tags":[{"id":9},{"id":3}]}"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(sorted, expected);
+    /// ```
+    #[must_use]
+    pub fn sort_arrays_by<K: AsRef<str>>(&self, mut key: impl FnMut(&Path) -> Option<K>) -> Self {
+        let mut sorted = self.clone();
+        let mut stack = vec![(&mut sorted, Path::new())];
+        while let Some((node, path)) = stack.pop() {
+            match node {
+                Self::Array(items) => {
+                    if let Some(key) = key(&path) {
+                        let key = key.as_ref();
+                        items.sort_by(|a, b| match (a.member(key), b.member(key)) {
+                            (Some(a), Some(b)) => a.cmp(b),
+                            (Some(_), None) => Ordering::Less,
+                            (None, Some(_)) => Ordering::Greater,
+                            (None, None) => Ordering::Equal,
+                        });
+                    }
+                    for (index, item) in items.iter_mut().enumerate() {
+                        let path = path.clone().with_segment(PathSegment::Index(index as i64));
+                        stack.push((item, path));
+                    }
+                }
+                Self::Object(map) => {
+                    for (name, value) in map.iter_mut() {
+                        stack.push((value, path.clone().with_segment(PathSegment::key(name))));
+                    }
+                }
+                _ => {}
+            }
+        }
+        sorted
+    }
+
+    /// Returns the value of member `key` if this is an object that has it.
+    fn member(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Finds the value at `path`, ignoring segments that do not match.
+    fn get_mut(&mut self, path: &[PathSegment]) -> Option<&mut Self> {
+        path.iter().try_fold(self, |node, segment| match (node, segment) {
+            (Self::Object(map), PathSegment::Key(key)) => map.get_mut(key),
+            (Self::Array(items), PathSegment::Index(index)) => {
+                items.get_mut(usize::try_from(*index).ok()?)
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns a copy of the node with empty containers removed from objects.
+    ///
+    /// Object entries whose value is `[]`, `{}`, or an object that becomes
+    /// empty after pruning are dropped. Array elements are pruned recursively
+    /// but never removed, so list positions are preserved. This is the
+    /// normalization applied implicitly by
+    /// [`DiffOptions::with_prune_empty`].
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let node = Node::from_json_str(r#"{"a":[],"b":{"c":{}},"d":[{}],"e":1}"#).unwrap();
+    /// let expected = Node::from_json_str(r#"{"d":[{}],"e":1}"#).unwrap();
+    /// assert_eq!(node.prune_empty(), expected);
+    /// ```
+    #[must_use]
+    pub fn prune_empty(&self) -> Self {
+        match self {
+            Self::Array(values) => Self::Array(values.iter().map(Self::prune_empty).collect()),
+            Self::Object(map) => Self::Object(
+                map.iter()
+                    .filter(|(_, value)| !value.is_prunable())
+                    .map(|(key, value)| (key.clone(), value.prune_empty()))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Computes depth, node count, string size, and container-size
+    /// histograms in a single non-recursive pass.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let metrics = Node::from_json_str(r#"{"a":[1,2,3]}"#).unwrap().metrics();
+    /// assert_eq!((metrics.depth(), metrics.nodes()), (2, 5));
+    /// ```
+    #[must_use]
+    pub fn metrics(&self) -> Metrics {
+        Metrics::collect(self)
+    }
+
+    /// Walks the document depth-first, calling `visitor` on entering and
+    /// leaving every value with its path from the root.
+    ///
+    /// ```
+    /// use jd_core::{Node, Path, Visitor};
+    ///
+    /// struct Deepest(usize);
+    ///
+    /// impl Visitor for Deepest {
+    ///     fn enter(&mut self, path: &Path, _: &Node) {
+    ///         self.0 = self.0.max(path.len());
+    ///     }
+    /// }
+    ///
+    /// let node = Node::from_json_str(r#"{"a":[[1]],"b":2}"#).unwrap();
+    /// let mut deepest = Deepest(0);
+    /// node.walk(&mut deepest);
+    /// assert_eq!(deepest.0, 3);
+    /// ```
+    pub fn walk(&self, visitor: &mut impl crate::Visitor) {
+        crate::visit::walk(self, visitor);
+    }
+
+    /// Visits every node with an explicit work stack, passing the number of
+    /// arrays and objects enclosing it (itself included). Stops at the first
+    /// `Break`.
+    pub(crate) fn walk_depths<B>(
+        &self,
+        mut visit: impl FnMut(&Self, usize) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let mut stack = vec![(self, 0usize)];
+        while let Some((node, depth)) = stack.pop() {
+            let depth = match node {
+                Self::Array(_) | Self::Object(_) => depth + 1,
+                _ => depth,
+            };
+            visit(node, depth)?;
+            match node {
+                Self::Array(items) => stack.extend(items.iter().map(|item| (item, depth))),
+                Self::Object(map) => stack.extend(map.values().map(|value| (value, depth))),
+                _ => {}
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Reports whether the node is an empty container once pruned.
+    pub(crate) fn is_prunable(&self) -> bool {
+        match self {
+            Self::Array(values) => values.is_empty(),
+            Self::Object(map) => map.values().all(Self::is_prunable),
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<JsonValue> for Node {
@@ -294,16 +1068,249 @@ impl TryFrom<JsonValue> for Node {
     }
 }
 
+impl From<bool> for Node {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for Node {
+    fn from(value: i64) -> Self {
+        Self::Number(Number::new(value as f64).expect("integers are finite"))
+    }
+}
+
+/// Non-finite values become `null`, as in `serde_json`.
+impl From<f64> for Node {
+    fn from(value: f64) -> Self {
+        Number::new(value).map_or(Self::Null, Self::Number)
+    }
+}
+
+impl From<Number> for Node {
+    fn from(value: Number) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for Node {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for Node {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<Vec<Node>> for Node {
+    fn from(value: Vec<Node>) -> Self {
+        Self::Array(value)
+    }
+}
+
+impl From<BTreeMap<String, Node>> for Node {
+    fn from(value: BTreeMap<String, Node>) -> Self {
+        Self::Object(value)
+    }
+}
+
+static VOID: Node = Node::Void;
+
+/// Looks up an object member, yielding [`Node::Void`] when `self` is not an
+/// object or has no such member.
+///
+/// ```
+/// # use jd_core::Node;
+/// let node = Node::from_json_str(r#"{"a":{"b":[true]}}"#).unwrap();
+/// assert_eq!(node["a"]["b"][0], Node::Bool(true));
+/// assert_eq!(node["missing"]["b"], Node::Void);
+/// ```
+impl std::ops::Index<&str> for Node {
+    type Output = Self;
+
+    fn index(&self, key: &str) -> &Self {
+        self.member(key).unwrap_or(&VOID)
+    }
+}
+
+/// Looks up an array element, yielding [`Node::Void`] when `self` is not an
+/// array or `index` is out of bounds.
+///
+/// ```
+/// # use jd_core::Node;
+/// let node = Node::from_json_str("[1, 2]").unwrap();
+/// assert_eq!(node[1], Node::from(2));
+/// assert_eq!(node[2], Node::Void);
+/// ```
+impl std::ops::Index<usize> for Node {
+    type Output = Self;
+
+    fn index(&self, index: usize) -> &Self {
+        match self {
+            Self::Array(items) => items.get(index).unwrap_or(&VOID),
+            _ => &VOID,
+        }
+    }
+}
+
+/// Extends `path` to the first value where `lhs` and `rhs` differ and
+/// reports whether they differ, following [`Node::eq_with_options`].
+fn first_difference(lhs: &Node, rhs: &Node, options: &DiffOptions, path: &mut Path) -> bool {
+    if options.diff_off() && options.ignores_all() {
+        return false;
+    }
+    if let Some(equal) = options.custom_equality(lhs, rhs) {
+        return !equal;
+    }
+    if !options.diff_off() && options.prune_empty() && lhs.is_prunable() && rhs.is_prunable() {
+        return false;
+    }
+    match (lhs, rhs) {
+        (Node::Array(a), Node::Array(b))
+            if options.diff_off() || options.array_mode() == ArrayMode::List =>
+        {
+            for (index, (a, b)) in a.iter().zip(b).enumerate() {
+                path.push(PathSegment::Index(index as i64));
+                if first_difference(a, b, &options.refine_index(index as i64), path) {
+                    return true;
+                }
+                path.pop();
+            }
+            if options.diff_off() || a.len() == b.len() {
+                return false;
+            }
+            path.push(PathSegment::Index(a.len().min(b.len()) as i64));
+            true
+        }
+        (Node::Object(a), Node::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let options = options.refine_key(key);
+                path.push(PathSegment::key(key.clone()));
+                let differs = match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => first_difference(a, b, &options, path),
+                    (Some(value), None) | (None, Some(value)) => {
+                        !(options.diff_off() || options.prune_empty() && value.is_prunable())
+                    }
+                    (None, None) => false,
+                };
+                if differs {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        _ if options.diff_off() => false,
+        _ => !lhs.eq_with_options(rhs, options),
+    }
+}
+
 fn list_equals(lhs: &[Node], rhs: &[Node], options: &DiffOptions) -> bool {
     if lhs.len() != rhs.len() {
         return false;
     }
-    lhs.iter().zip(rhs.iter()).all(|(a, b)| a.eq_with_options(b, options))
+    lhs.iter()
+        .zip(rhs.iter())
+        .enumerate()
+        .all(|(index, (a, b))| a.eq_with_options(b, &options.refine_index(index as i64)))
+}
+
+/// Hashes array elements, each under the options for its index.
+pub(crate) fn hash_elements(values: &[Node], options: &DiffOptions) -> Vec<Digest> {
+    element_hashes(values, options).collect()
+}
+
+pub(crate) fn element_hashes<'a>(
+    values: &'a [Node],
+    options: &'a DiffOptions,
+) -> impl Iterator<Item = Digest> + 'a {
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| value.digest(&options.refine_index(index as i64)))
+}
+
+/// Whether `text` is the JSON spelling of the number or boolean `scalar`.
+fn spells_scalar(text: &str, scalar: &Node, options: &DiffOptions) -> bool {
+    match (spelled_scalar(text), scalar) {
+        (Some(Node::Bool(a)), Node::Bool(b)) => a == *b,
+        (Some(Node::Number(a)), Node::Number(b)) => options.numbers_equal(a, *b),
+        _ => false,
+    }
+}
+
+/// Reads `text` as a JSON number or `true`/`false`.
+fn spelled_scalar(text: &str) -> Option<Node> {
+    match text {
+        "true" => Some(Node::Bool(true)),
+        "false" => Some(Node::Bool(false)),
+        _ => {
+            let number = text.parse::<serde_json::Number>().ok()?;
+            Number::new(number.as_f64()?).ok().map(Node::Number)
+        }
+    }
+}
+
+fn object_equals(
+    lhs: &BTreeMap<String, Node>,
+    rhs: &BTreeMap<String, Node>,
+    options: &DiffOptions,
+) -> bool {
+    // Without pruning or path options a missing member is always a difference.
+    if !options.prune_empty() && options.path_options().is_empty() {
+        return lhs.len() == rhs.len()
+            && lhs
+                .iter()
+                .all(|(key, a)| rhs.get(key).is_some_and(|b| a.eq_with_options(b, options)));
+    }
+    // Both maps are sorted, so walk their keys in step rather than
+    // collecting the union.
+    let (mut lhs, mut rhs) = (lhs.iter().peekable(), rhs.iter().peekable());
+    loop {
+        let (key, members) = match (lhs.peek(), rhs.peek()) {
+            (None, None) => return true,
+            (Some((a, _)), Some((b, _))) if a == b => {
+                let (key, a) = lhs.next().expect("peeked");
+                let (_, b) = rhs.next().expect("peeked");
+                (key, (Some(a), Some(b)))
+            }
+            (Some((a, _)), Some((b, _))) if a > b => {
+                let (key, b) = rhs.next().expect("peeked");
+                (key, (None, Some(b)))
+            }
+            (Some(_), _) => {
+                let (key, a) = lhs.next().expect("peeked");
+                (key, (Some(a), None))
+            }
+            (None, Some(_)) => {
+                let (key, b) = rhs.next().expect("peeked");
+                (key, (None, Some(b)))
+            }
+        };
+        let options = options.refine_key(key);
+        let equal = match members {
+            (Some(a), Some(b)) => a.eq_with_options(b, &options),
+            (Some(value), None) | (None, Some(value)) => {
+                options.diff_off() || (options.prune_empty() && value.is_prunable())
+            }
+            (None, None) => true,
+        };
+        if !equal {
+            return false;
+        }
+    }
 }
 
 fn set_equals(lhs: &[Node], rhs: &[Node], options: &DiffOptions) -> bool {
-    let lhs_hashes: BTreeSet<HashCode> = lhs.iter().map(|n| n.hash_code(options)).collect();
-    let rhs_hashes: BTreeSet<HashCode> = rhs.iter().map(|n| n.hash_code(options)).collect();
+    if let Some(equal) = crate::spill::equal(lhs, rhs, options, true) {
+        return equal;
+    }
+    let lhs_hashes: BTreeSet<Digest> = hash_elements(lhs, options).into_iter().collect();
+    let rhs_hashes: BTreeSet<Digest> = hash_elements(rhs, options).into_iter().collect();
     lhs_hashes == rhs_hashes
 }
 
@@ -311,11 +1318,14 @@ fn multiset_equals(lhs: &[Node], rhs: &[Node], options: &DiffOptions) -> bool {
     if lhs.len() != rhs.len() {
         return false;
     }
+    if let Some(equal) = crate::spill::equal(lhs, rhs, options, false) {
+        return equal;
+    }
     let mut counts = BTreeMap::new();
-    for hash in lhs.iter().map(|n| n.hash_code(options)) {
+    for hash in hash_elements(lhs, options) {
         *counts.entry(hash).or_insert(0usize) += 1;
     }
-    for hash in rhs.iter().map(|n| n.hash_code(options)) {
+    for hash in hash_elements(rhs, options) {
         match counts.get_mut(&hash) {
             Some(count) if *count > 0 => *count -= 1,
             _ => return false,
@@ -324,36 +1334,60 @@ fn multiset_equals(lhs: &[Node], rhs: &[Node], options: &DiffOptions) -> bool {
     counts.values().all(|count| *count == 0)
 }
 
-fn hash_list(values: &[Node], options: &DiffOptions) -> HashCode {
-    let mut bytes = Vec::with_capacity(8 + values.len() * 8);
-    bytes.extend_from_slice(&LIST_SEED);
-    for value in values {
-        bytes.extend_from_slice(&value.hash_code(options));
+fn hash_list(values: &[Node], options: &DiffOptions) -> Digest {
+    let hashing = options.hashing();
+    let mut hasher = hashing.hasher(b'l');
+    hasher.write(&LIST_SEED);
+    for hash in element_hashes(values, options) {
+        hasher.write(&hash[..hashing.width()]);
+    }
+    hasher.finish()
+}
+
+fn hash_set(values: &[Node], options: &DiffOptions) -> Digest {
+    if let Some(hash) = crate::spill::combine(values, options, true) {
+        return hash;
     }
-    hash_bytes(&bytes)
+    let unique: BTreeSet<Digest> = hash_elements(values, options).into_iter().collect();
+    combine_digests(unique, options)
 }
 
-fn hash_set(values: &[Node], options: &DiffOptions) -> HashCode {
-    let mut unique = BTreeSet::new();
-    for value in values {
-        unique.insert(value.hash_code(options));
+fn hash_multiset(values: &[Node], options: &DiffOptions) -> Digest {
+    if let Some(hash) = crate::spill::combine(values, options, false) {
+        return hash;
     }
-    combine(unique.into_iter().collect())
+    let mut hashes = hash_elements(values, options);
+    hashes.sort_unstable();
+    combine_digests(hashes, options)
 }
 
-fn hash_multiset(values: &[Node], options: &DiffOptions) -> HashCode {
-    let hashes: Vec<_> = values.iter().map(|n| n.hash_code(options)).collect();
-    combine(hashes)
+/// Hashes sorted digests like [`crate::combine`] does hash codes.
+pub(crate) fn combine_digests(
+    sorted: impl IntoIterator<Item = Digest>,
+    options: &DiffOptions,
+) -> Digest {
+    let hashing = options.hashing();
+    let mut hasher = hashing.hasher(b'c');
+    for hash in sorted {
+        hasher.write(&hash[..hashing.width()]);
+    }
+    hasher.finish()
 }
 
-fn hash_object(map: &BTreeMap<String, Node>, options: &DiffOptions) -> HashCode {
-    let mut bytes = Vec::with_capacity(OBJECT_SEED.len() + map.len() * 16);
-    bytes.extend_from_slice(&OBJECT_SEED);
+fn hash_object(map: &BTreeMap<String, Node>, options: &DiffOptions) -> Digest {
+    let hashing = options.hashing();
+    let width = hashing.width();
+    let mut hasher = hashing.hasher(b'o');
+    hasher.write(&OBJECT_SEED);
     for (key, value) in map {
-        bytes.extend_from_slice(&hash_bytes(key.as_bytes()));
-        bytes.extend_from_slice(&value.hash_code(options));
+        let options = options.refine_key(key);
+        if options.ignores_all() || (options.prune_empty() && value.is_prunable()) {
+            continue;
+        }
+        hasher.write(&hashing.digest(b'k', key.as_bytes())[..width]);
+        hasher.write(&value.digest(&options)[..width]);
     }
-    hash_bytes(&bytes)
+    hasher.finish()
 }
 
 #[cfg(test)]
@@ -365,6 +1399,54 @@ mod tests {
         string::string_regex,
     };
 
+    /// Deep enough to overflow recursive conversion on a 2 MiB test thread.
+    const DEEP: usize = 100_000;
+
+    /// Alternates arrays and objects: `[{"k":[{"k":...}]}]`.
+    fn deeply_nested(depth: usize) -> JsonValue {
+        let mut value = JsonValue::from(1);
+        for level in 0..depth {
+            value = if level % 2 == 0 {
+                JsonValue::Array(vec![value])
+            } else {
+                JsonValue::Object(serde_json::Map::from_iter([("k".to_string(), value)]))
+            };
+        }
+        value
+    }
+
+    #[test]
+    fn deeply_nested_values_convert_and_clone_without_recursion() {
+        let node = Node::from_json_value(deeply_nested(DEEP)).unwrap();
+        let copy = node.clone();
+        let mut value = node.to_json_value().unwrap();
+        drop(node);
+
+        let mut depth = 0;
+        let mut current = &copy;
+        while let Some(child) = match current {
+            Node::Array(items) => items.first(),
+            Node::Object(map) => map.get("k"),
+            _ => None,
+        } {
+            current = child;
+            depth += 1;
+        }
+        assert_eq!((depth, current), (DEEP, &Node::from_json_str("1").unwrap()));
+        drop(copy);
+
+        depth = 0;
+        while let Some(child) = match &mut value {
+            JsonValue::Array(items) => items.pop(),
+            JsonValue::Object(map) => map.remove("k"),
+            _ => None,
+        } {
+            value = child;
+            depth += 1;
+        }
+        assert_eq!((depth, value), (DEEP, JsonValue::from(1)));
+    }
+
     fn arb_json_value() -> impl Strategy<Value = JsonValue> {
         let leaf = prop_oneof![
             Just(JsonValue::Null),
@@ -444,6 +1526,34 @@ mod tests {
         assert!(lhs.eq_with_options(&rhs, &loose));
     }
 
+    #[test]
+    fn string_scalars_equal_the_values_they_spell() {
+        let opts = DiffOptions::default().with_string_scalars(true);
+        let equal = |lhs: &str, rhs: &str, opts: &DiffOptions| {
+            let (lhs, rhs) = (Node::from_json_str(lhs).unwrap(), Node::from_json_str(rhs).unwrap());
+            lhs.eq_with_options(&rhs, opts) && rhs.eq_with_options(&lhs, opts)
+        };
+        assert!(equal(r#""42""#, "42", &opts));
+        assert!(equal(r#""-4.20e1""#, "-42", &opts));
+        assert!(equal(r#""true""#, "true", &opts));
+        assert!(equal(r#""false""#, "false", &opts));
+        for (text, value) in [
+            (r#"" 42""#, "42"),
+            (r#""+42""#, "42"),
+            (r#""0x2a""#, "42"),
+            (r#""NaN""#, "0"),
+            (r#""TRUE""#, "true"),
+            (r#""1""#, "true"),
+            (r#""null""#, "null"),
+            (r#""42""#, "[42]"),
+        ] {
+            assert!(!equal(text, value, &opts), "{text} {value}");
+        }
+        assert!(!equal(r#""42""#, "42", &DiffOptions::default()));
+        let loose = opts.with_precision(0.1).unwrap();
+        assert!(equal(r#""1.05""#, "1", &loose));
+    }
+
     #[test]
     fn array_mode_list_respects_order() {
         let lhs = Node::from_json_str("[1,2]").unwrap();
@@ -460,7 +1570,160 @@ mod tests {
         assert!(lhs.eq_with_options(&rhs, &opts));
     }
 
+    #[test]
+    fn prune_empty_equates_missing_and_empty_containers() {
+        let lhs = Node::from_json_str(r#"{"a":1,"b":[],"c":{"d":{}}}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"a":1,"b":{}}"#).unwrap();
+        assert!(!lhs.eq_with_options(&rhs, &DiffOptions::default()));
+        let opts = DiffOptions::default().with_prune_empty(true);
+        assert!(lhs.eq_with_options(&rhs, &opts));
+        assert_eq!(lhs.hash_code(&opts), rhs.hash_code(&opts));
+        assert_eq!(lhs.prune_empty(), rhs.prune_empty());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_scalars_map_onto_json_model() {
+        let node = Node::from_toml_str("n = 3\nf = 1.5\nwhen = 1979-05-27T07:32:00Z\n").unwrap();
+        let expected =
+            Node::from_json_str(r#"{"n":3,"f":1.5,"when":"1979-05-27T07:32:00Z"}"#).unwrap();
+        assert_eq!(node, expected);
+        assert_eq!(Node::from_toml_str(&node.to_toml_string().unwrap()).unwrap(), node);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trips_and_rejects_byte_strings() {
+        let node = Node::from_json_str(r#"{"a":[1,2.5,null,"x"],"b":{"c":true}}"#).unwrap();
+        assert_eq!(Node::from_cbor_slice(&node.to_cbor_vec().unwrap()).unwrap(), node);
+        // h'01'
+        let err = Node::from_cbor_slice(&[0x41, 0x01]).unwrap_err();
+        assert!(matches!(err, CanonicalizeError::Cbor(_)));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_round_trips_and_rejects_non_string_keys() {
+        let node = Node::from_json_str(r#"{"a":[1,2.5,null,"x"],"b":{"c":true}}"#).unwrap();
+        assert_eq!(Node::from_msgpack_slice(&node.to_msgpack_vec().unwrap()).unwrap(), node);
+        // {1: 2}
+        let err = Node::from_msgpack_slice(&[0x81, 0x01, 0x02]).unwrap_err();
+        assert!(matches!(err, CanonicalizeError::MessagePack(_)));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_cannot_encode_null() {
+        let node = Node::from_json_str(r#"{"a":null}"#).unwrap();
+        assert!(matches!(node.to_toml_string(), Err(crate::EncodeError::Toml(_))));
+        assert!(matches!(Node::Void.to_toml_string(), Err(crate::EncodeError::Void)));
+    }
+
+    #[test]
+    fn explain_inequality_points_at_extra_and_missing_values() {
+        let opts = DiffOptions::default();
+        let explain = |lhs: &str, rhs: &str| {
+            let (lhs, rhs) = (Node::from_json_str(lhs).unwrap(), Node::from_json_str(rhs).unwrap());
+            lhs.explain_inequality(&rhs, &opts).map(|path| path.to_string())
+        };
+        assert_eq!(explain("[1,2]", "[1,2,3]"), Some("[2]".to_string()));
+        assert_eq!(explain(r#"{"a":1}"#, r#"{"a":1,"b":null}"#), Some("[b]".to_string()));
+        assert_eq!(explain(r#"{"a":[1]}"#, r#"{"a":{}}"#), Some("[a]".to_string()));
+        assert_eq!(explain("1", "1.0"), None);
+
+        let pruned = opts.with_prune_empty(true);
+        let lhs = Node::from_json_str(r#"{"a":[],"b":{"c":1}}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"b":{"c":2}}"#).unwrap();
+        assert_eq!(lhs.explain_inequality(&rhs, &pruned).unwrap().to_string(), "[b c]");
+    }
+
+    #[test]
+    fn sort_arrays_by_orders_mixed_key_types() {
+        let node = Node::from_json_str(
+            r#"[[{"k":"b"},{"k":{}},{"k":[1]},{"k":2},{"k":"a"},{"k":true},{"k":null},{"k":-1.5},7]]"#,
+        )
+        .unwrap();
+        let sorted = node.sort_arrays_by(|path| (!path.is_empty()).then_some("k"));
+        assert_eq!(
+            sorted,
+            Node::from_json_str(
+                r#"[[{"k":null},{"k":true},{"k":-1.5},{"k":2},{"k":"a"},{"k":"b"},{"k":[1]},{"k":{}},7]]"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn node_macro_matches_parsed_json() {
+        let inner = crate::node!([1, 2,]);
+        let built = crate::node!({
+            "a": [null, [], {}, {"b": -0.5},],
+            "c": inner,
+            "d": i64::MAX,
+            "e": f64::NAN,
+        });
+        let parsed = Node::from_json_str(
+            r#"{"a":[null,[],{},{"b":-0.5}],"c":[1,2],"d":9223372036854775807,"e":null}"#,
+        )
+        .unwrap();
+        assert_eq!(built, parsed);
+        assert_eq!(built["a"][3]["b"], Node::from(-0.5));
+        assert_eq!(built["c"]["b"], Node::Void);
+        assert_eq!(crate::node!(null), Node::Null);
+        assert_eq!(crate::node!("x"), Node::String("x".to_string()));
+    }
+
+    #[test]
+    fn custom_hasher_decides_set_membership() {
+        #[derive(Debug)]
+        struct Constant;
+        impl crate::NodeHasher for Constant {
+            fn width(&self) -> usize {
+                1
+            }
+            fn digest(&self, _: u8, _: &[u8]) -> crate::Digest {
+                [7; 16]
+            }
+        }
+        let opts = DiffOptions::default().with_array_mode(ArrayMode::Set).unwrap();
+        let lhs = Node::from_json_str(r#"[1, "a", {"b": null}]"#).unwrap();
+        let rhs = Node::from_json_str("[[true]]").unwrap();
+        assert!(!lhs.eq_with_options(&rhs, &opts));
+        let colliding = opts.with_hasher(Constant);
+        assert!(lhs.eq_with_options(&rhs, &colliding));
+        assert_eq!(lhs.hash_code(&colliding), rhs.hash_code(&colliding));
+        // Options refined for nested paths keep the hasher.
+        let nested = DiffOptions::default()
+            .with_hasher(Constant)
+            .with_path_option(
+                crate::PathOption::new([crate::PathMatcher::key("x")])
+                    .with_setting(crate::PathSetting::ArrayMode(ArrayMode::Set)),
+            )
+            .unwrap();
+        let lhs = Node::from_json_str(r#"{"x": [1]}"#).unwrap();
+        assert!(lhs.eq_with_options(&Node::from_json_str(r#"{"x": [2]}"#).unwrap(), &nested));
+    }
+
+    #[test]
+    fn signed_zeros_collapse_in_hashed_sets() {
+        let nodes: std::collections::HashSet<Node> =
+            [r#"{"a":[0]}"#, r#"{"a":[-0.0]}"#, r#"{"a":[0.5]}"#]
+                .into_iter()
+                .map(|json| Node::from_json_str(json).unwrap())
+                .collect();
+        assert_eq!(nodes.len(), 2);
+    }
+
     proptest! {
+        #[test]
+        fn ordering_agrees_with_equality(lhs in arb_json_value(), rhs in arb_json_value()) {
+            let lhs = Node::from_json_value(lhs).unwrap();
+            let rhs = Node::from_json_value(rhs).unwrap();
+            prop_assert_eq!(lhs.cmp(&rhs).is_eq(), lhs == rhs);
+            prop_assert_eq!(lhs.cmp(&rhs), rhs.cmp(&lhs).reverse());
+            prop_assert_eq!(lhs.cmp(&lhs.clone()), Ordering::Equal);
+        }
+
         #[test]
         fn json_roundtrips_through_node(value in arb_json_value()) {
             let node = Node::from_json_value(value.clone()).unwrap();
@@ -470,5 +1733,27 @@ mod tests {
             let reconstructed_again = node_again.to_json_value().unwrap();
             prop_assert_eq!(reconstructed_again, reconstructed);
         }
+
+        #[test]
+        fn explain_inequality_agrees_with_equality_and_diff(
+            lhs in arb_json_value(),
+            rhs in arb_json_value(),
+            prune_empty in any::<bool>(),
+        ) {
+            let lhs = Node::from_json_value(lhs).unwrap();
+            let rhs = Node::from_json_value(rhs).unwrap();
+            let opts = DiffOptions::default().with_prune_empty(prune_empty);
+            let explained = lhs.explain_inequality(&rhs, &opts);
+            prop_assert_eq!(explained.is_none(), lhs.eq_with_options(&rhs, &opts));
+            if let Some(path) = explained {
+                // Every difference shows up in the diff at or above that path.
+                let diff = lhs.diff(&rhs, &opts);
+                let related = |element: &crate::DiffElement| {
+                    path.segments().starts_with(element.path.segments())
+                        || element.path.segments().starts_with(path.segments())
+                };
+                prop_assert!(diff.iter().any(related));
+            }
+        }
     }
 }
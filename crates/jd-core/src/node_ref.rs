@@ -0,0 +1,391 @@
+//! Documents that borrow their strings from the input text.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{CanonicalizeError, Diff, DiffOptions, Node, Number};
+
+/// A parsed JSON document whose strings and object keys point into the
+/// input where they can.
+///
+/// [`Node::from_json_str`] copies every string out of the input. Strings
+/// without escape sequences appear verbatim in the text, so
+/// [`NodeRef::from_json_str`] borrows them instead and only allocates for
+/// the rest, which keeps huge, string-heavy documents from being held in
+/// memory twice. Convert to a [`Node`] with [`NodeRef::into_owned`] for the
+/// rest of the API.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use jd_core::{Node, NodeRef};
+///
+/// let input = r#"{"name": "jd", "quote": "\"hi\""}"#;
+/// let doc = NodeRef::from_json_str(input).unwrap();
+/// let NodeRef::Object(members) = &doc else { unreachable!() };
+/// assert!(matches!(members["name"], NodeRef::String(Cow::Borrowed("jd"))));
+/// assert!(matches!(members["quote"], NodeRef::String(Cow::Owned(_))));
+/// assert_eq!(doc.into_owned(), Node::from_json_str(input).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeRef<'a> {
+    /// Sentinel representing the absence of a value.
+    Void,
+    /// JSON `null`.
+    Null,
+    /// JSON boolean.
+    Bool(bool),
+    /// JSON number represented as IEEE-754 double precision.
+    Number(Number),
+    /// JSON string, borrowed unless it contained escapes.
+    String(Cow<'a, str>),
+    /// JSON array.
+    Array(Vec<NodeRef<'a>>),
+    /// JSON object with deterministic key ordering.
+    Object(BTreeMap<Cow<'a, str>, NodeRef<'a>>),
+}
+
+impl<'a> NodeRef<'a> {
+    /// Parses a JSON string like [`Node::from_json_str`], borrowing strings
+    /// from `input`. Blank input is [`NodeRef::Void`].
+    ///
+    /// ```
+    /// # use jd_core::NodeRef;
+    /// assert_eq!(NodeRef::from_json_str(" ").unwrap(), NodeRef::Void);
+    /// assert!(NodeRef::from_json_str("[1,").is_err());
+    /// ```
+    pub fn from_json_str(input: &'a str) -> Result<Self, CanonicalizeError> {
+        let _span = tracing::info_span!("parse", format = "json", bytes = input.len()).entered();
+        if input.trim().is_empty() {
+            return Ok(Self::Void);
+        }
+        Ok(serde_json::from_str(input)?)
+    }
+
+    /// Converts into an owned [`Node`], moving strings that were already
+    /// owned and copying borrowed ones.
+    ///
+    /// ```
+    /// # use jd_core::{Node, NodeRef};
+    /// let doc = NodeRef::from_json_str(r#"[null, true, 1.5, "x"]"#).unwrap();
+    /// assert_eq!(doc.into_owned(), Node::from_json_str(r#"[null, true, 1.5, "x"]"#).unwrap());
+    /// ```
+    #[must_use]
+    pub fn into_owned(self) -> Node {
+        match self {
+            Self::Void => Node::Void,
+            Self::Null => Node::Null,
+            Self::Bool(v) => Node::Bool(v),
+            Self::Number(n) => Node::Number(n),
+            Self::String(s) => Node::String(s.into_owned()),
+            Self::Array(items) => Node::Array(items.into_iter().map(Self::into_owned).collect()),
+            Self::Object(members) => Node::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Computes the diff from `self` to `other` like [`Node::diff`].
+    ///
+    /// Identical documents, the common case when checking for drift, yield
+    /// an empty diff straight from the borrowed form. Otherwise the diff
+    /// engine runs on [`Node`] copies in which object members identical on
+    /// both sides are `null` on both sides, so only the keys along the way
+    /// and the members that differ are copied. Arrays that differ are
+    /// copied whole, since list hunks quote their neighbours as context.
+    /// A [`ValueComparator`](crate::ValueComparator) sees the values it is
+    /// registered for as written, so with comparators both documents are
+    /// copied whole.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, NodeRef, RenderConfig};
+    /// let lhs = NodeRef::from_json_str(r#"{"a": "x"}"#).unwrap();
+    /// let rhs = NodeRef::from_json_str(r#"{"a": "y"}"#).unwrap();
+    /// assert!(lhs.diff(&lhs.clone(), &DiffOptions::default()).is_empty());
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default());
+    /// assert_eq!(diff.render(&RenderConfig::default()), "@ [\"a\"]\n- \"x\"\n+ \"y\"\n");
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &NodeRef<'_>, options: &DiffOptions) -> Diff {
+        if options.has_comparators() {
+            return self.to_node().diff(&other.to_node(), options);
+        }
+        if self.identical(other) {
+            return Diff::empty();
+        }
+        let (lhs, rhs) = self.differing_parts(other);
+        lhs.diff(&rhs, options)
+    }
+
+    /// Copies `self` and `other` for the diff engine, with the members
+    /// identical on both sides replaced by `null`. Without comparators,
+    /// which could see an object that holds such members, equal members
+    /// yield no hunks, so the diff is the same as the full copies' diff.
+    fn differing_parts(&self, other: &NodeRef<'_>) -> (Node, Node) {
+        let (Self::Object(lhs), NodeRef::Object(rhs)) = (self, other) else {
+            return (self.to_node(), other.to_node());
+        };
+        let mut left = BTreeMap::new();
+        let mut right = BTreeMap::new();
+        for (key, value) in lhs {
+            let (l, r) = match rhs.get(key.as_ref()) {
+                Some(theirs) if value.identical(theirs) => (Node::Null, Node::Null),
+                Some(theirs) => value.differing_parts(theirs),
+                None => {
+                    left.insert(key.to_string(), value.to_node());
+                    continue;
+                }
+            };
+            left.insert(key.to_string(), l);
+            right.insert(key.to_string(), r);
+        }
+        for (key, value) in rhs {
+            if !lhs.contains_key(key.as_ref()) {
+                right.insert(key.to_string(), value.to_node());
+            }
+        }
+        (Node::Object(left), Node::Object(right))
+    }
+
+    /// Copies `self` into a [`Node`] without cloning it first.
+    fn to_node(&self) -> Node {
+        match self {
+            Self::Void => Node::Void,
+            Self::Null => Node::Null,
+            Self::Bool(v) => Node::Bool(*v),
+            Self::Number(n) => Node::Number(*n),
+            Self::String(s) => Node::String(s.to_string()),
+            Self::Array(items) => Node::Array(items.iter().map(Self::to_node).collect()),
+            Self::Object(members) => Node::Object(
+                members.iter().map(|(key, value)| (key.to_string(), value.to_node())).collect(),
+            ),
+        }
+    }
+
+    /// Structural equality across lifetimes; equal values are equal under
+    /// any options.
+    fn identical(&self, other: &NodeRef<'_>) -> bool {
+        match (self, other) {
+            (Self::Void, NodeRef::Void) | (Self::Null, NodeRef::Null) => true,
+            (Self::Bool(a), NodeRef::Bool(b)) => a == b,
+            (Self::Number(a), NodeRef::Number(b)) => a == b,
+            (Self::String(a), NodeRef::String(b)) => a == b,
+            (Self::Array(a), NodeRef::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.identical(b))
+            }
+            (Self::Object(a), NodeRef::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|((ka, va), (kb, vb))| ka == kb && va.identical(vb))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeRef<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeRefVisitor)
+    }
+}
+
+struct NodeRefVisitor;
+
+impl<'de> Visitor<'de> for NodeRefVisitor {
+    type Value = NodeRef<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(NodeRef::Null)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(NodeRef::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_f64(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_f64(v as f64)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Number::new(v).map(NodeRef::Number).map_err(E::custom)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(NodeRef::String(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(NodeRef::String(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(NodeRef::String(Cow::Owned(v)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(NodeRef::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut members = BTreeMap::new();
+        // A repeated key keeps its last value, as in `Node::from_json_str`.
+        while let Some(Key(key)) = map.next_key()? {
+            members.insert(key, map.next_value()?);
+        }
+        Ok(NodeRef::Object(members))
+    }
+}
+
+/// An object key, borrowed like string values.
+struct Key<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = Key<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an object key")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(Key(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(Key(Cow::Owned(v.to_owned())))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(Key(Cow::Owned(v)))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_owned_parsing() {
+        for input in [
+            r#"{"a\nb": ["é", "plain", -0.0, 1e300, 18446744073709551615], "a": {}, "a": [true, null]}"#,
+            r#""only""#,
+            "[]",
+        ] {
+            assert_eq!(
+                NodeRef::from_json_str(input).unwrap().into_owned(),
+                Node::from_json_str(input).unwrap()
+            );
+        }
+        assert!(NodeRef::from_json_str("1e999").is_err());
+    }
+
+    #[test]
+    fn only_escaped_strings_are_copied() {
+        let doc = NodeRef::from_json_str(r#"{"k": ["v", "e\\scaped"], "k\"2": 1}"#).unwrap();
+        let NodeRef::Object(members) = &doc else { panic!("object") };
+        let keys: Vec<_> = members.keys().map(|key| matches!(key, Cow::Borrowed(_))).collect();
+        assert_eq!(keys, [true, false]);
+        let NodeRef::Array(items) = &members["k"] else { panic!("array") };
+        assert!(matches!(items[0], NodeRef::String(Cow::Borrowed("v"))));
+        assert!(matches!(&items[1], NodeRef::String(Cow::Owned(s)) if s == "e\\scaped"));
+    }
+
+    #[test]
+    fn diffs_match_owned_diffs() {
+        let options = DiffOptions::default();
+        let lhs = r#"{"a": [1, 2, 3], "b": "x"}"#;
+        let rhs = r#"{"a": [1, 3], "c": "x"}"#;
+        let borrowed = NodeRef::from_json_str(lhs)
+            .unwrap()
+            .diff(&NodeRef::from_json_str(rhs).unwrap(), &options);
+        let owned =
+            Node::from_json_str(lhs).unwrap().diff(&Node::from_json_str(rhs).unwrap(), &options);
+        assert_eq!(borrowed, owned);
+    }
+
+    #[test]
+    fn only_differing_members_are_copied() {
+        let lhs = r#"{"same": {"big": [1, 2]}, "o": {"k": "v", "n": 1, "gone": []}, "l": [1, 2]}"#;
+        let rhs = r#"{"same": {"big": [1, 2]}, "o": {"k": "v", "n": 2, "new": {}}, "l": [1, 3]}"#;
+        let (left, right) = NodeRef::from_json_str(lhs)
+            .unwrap()
+            .differing_parts(&NodeRef::from_json_str(rhs).unwrap());
+        assert_eq!(
+            left,
+            crate::node!({"same": null, "o": {"k": null, "n": 1, "gone": []}, "l": [1, 2]})
+        );
+        assert_eq!(
+            right,
+            crate::node!({"same": null, "o": {"k": null, "n": 2, "new": {}}, "l": [1, 3]})
+        );
+        for options in [
+            DiffOptions::default(),
+            DiffOptions::default().with_merge(true),
+            DiffOptions::default().with_precision(0.5).unwrap(),
+        ] {
+            let borrowed = NodeRef::from_json_str(lhs)
+                .unwrap()
+                .diff(&NodeRef::from_json_str(rhs).unwrap(), &options);
+            let owned = Node::from_json_str(lhs)
+                .unwrap()
+                .diff(&Node::from_json_str(rhs).unwrap(), &options);
+            assert_eq!(borrowed, owned);
+        }
+    }
+
+    #[test]
+    fn comparators_see_the_whole_values() {
+        /// Records with the same revision are the same record.
+        #[derive(Debug)]
+        struct SameRevision;
+
+        impl crate::ValueComparator for SameRevision {
+            fn equals(&self, lhs: &Node, rhs: &Node) -> Option<bool> {
+                match (lhs, rhs) {
+                    (Node::Object(lhs), Node::Object(rhs)) => {
+                        match (lhs.get("rev"), rhs.get("rev")) {
+                            (Some(Node::String(lhs)), Some(Node::String(rhs))) => Some(lhs == rhs),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        let lhs = r#"{"o": {"rev": "7", "cached": 1}, "p": 1}"#;
+        let rhs = r#"{"o": {"rev": "7", "cached": 2}, "p": 2}"#;
+        let options =
+            DiffOptions::default().with_comparator([crate::PathMatcher::key("o")], SameRevision);
+        let borrowed = NodeRef::from_json_str(lhs)
+            .unwrap()
+            .diff(&NodeRef::from_json_str(rhs).unwrap(), &options);
+        let owned =
+            Node::from_json_str(lhs).unwrap().diff(&Node::from_json_str(rhs).unwrap(), &options);
+        assert_eq!(borrowed, owned);
+        assert_eq!(borrowed.render(&crate::RenderConfig::default()), "@ [\"p\"]\n- 1\n+ 2\n");
+    }
+}
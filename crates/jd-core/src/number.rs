@@ -1,11 +1,18 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
 use serde::{Deserialize, Serialize};
 use serde_json::Number as JsonNumber;
 
 use crate::{hash::hash_bytes, CanonicalizeError};
 
 /// Represents a JSON number using IEEE-754 double precision, mirroring Go's `float64`.
-#[derive(Clone, Copy, Debug, PartialOrd, Serialize, Deserialize)]
-#[serde(transparent)]
+///
+/// Every constructor, including deserialization, rejects NaN and infinities,
+/// so numbers are totally ordered and implement [`Eq`], [`Ord`], and [`Hash`].
+/// `0.0` and `-0.0` compare and hash as equal.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(into = "f64", try_from = "f64")]
 pub struct Number(f64);
 
 impl Number {
@@ -49,6 +56,55 @@ impl Number {
         (self.0 - other.0).abs() <= precision
     }
 
+    /// Compares two numbers using a tolerance relative to their magnitude.
+    ///
+    /// The numbers are equal when `|a - b| <= tolerance * max(|a|, |b|)`, which
+    /// keeps the comparison meaningful for both very large and very small
+    /// values.
+    ///
+    /// ```
+    /// # use jd_core::Number;
+    /// let lhs = Number::new(1.0e9).expect("finite");
+    /// let rhs = Number::new(1.0e9 + 50.0).expect("finite");
+    /// assert!(lhs.equals_with_relative_precision(rhs, 1e-6));
+    /// let tiny = Number::new(1.0e-9).expect("finite");
+    /// let other = Number::new(2.0e-9).expect("finite");
+    /// assert!(!tiny.equals_with_relative_precision(other, 1e-6));
+    /// ```
+    #[must_use]
+    pub fn equals_with_relative_precision(self, other: Self, tolerance: f64) -> bool {
+        let scale = self.0.abs().max(other.0.abs());
+        (self.0 - other.0).abs() <= tolerance * scale
+    }
+
+    /// Compares two numbers by counting the representable doubles between them.
+    ///
+    /// `0.0` and `-0.0` are zero ULPs apart.
+    ///
+    /// ```
+    /// # use jd_core::Number;
+    /// let lhs = Number::new(0.1 + 0.2).expect("finite");
+    /// let rhs = Number::new(0.3).expect("finite");
+    /// assert!(lhs.equals_within_ulps(rhs, 1));
+    /// assert!(!lhs.equals_within_ulps(rhs, 0));
+    /// ```
+    #[must_use]
+    pub fn equals_within_ulps(self, other: Self, max_ulps: u64) -> bool {
+        self.ulps_from(other) <= u128::from(max_ulps)
+    }
+
+    fn ulps_from(self, other: Self) -> u128 {
+        fn ordered(value: f64) -> i128 {
+            let bits = i128::from(value.to_bits() as i64);
+            if bits < 0 {
+                i128::from(i64::MIN) - bits
+            } else {
+                bits
+            }
+        }
+        (ordered(self.0) - ordered(other.0)).unsigned_abs()
+    }
+
     /// Computes the hash code following the Go implementation's strategy.
     ///
     /// ```
@@ -84,8 +140,93 @@ impl Number {
     }
 }
 
+impl TryFrom<f64> for Number {
+    type Error = CanonicalizeError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<Number> for f64 {
+    fn from(number: Number) -> Self {
+        number.0
+    }
+}
+
 impl PartialEq for Number {
     fn eq(&self, other: &Self) -> bool {
         self.0 == other.0
     }
 }
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).expect("numbers are finite")
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Adding zero folds `-0.0` into `0.0`, matching `PartialEq`.
+        (self.0 + 0.0).to_bits().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(value: f64) -> Number {
+        Number::new(value).unwrap()
+    }
+
+    #[test]
+    fn ulps_distance_spans_zero() {
+        assert!(num(0.0).equals_within_ulps(num(-0.0), 0));
+        let smallest = f64::from_bits(1);
+        assert!(num(smallest).equals_within_ulps(num(-smallest), 2));
+        assert!(!num(smallest).equals_within_ulps(num(-smallest), 1));
+    }
+
+    #[test]
+    fn ulps_distance_handles_extremes() {
+        assert!(!num(f64::MAX).equals_within_ulps(num(f64::MIN), 0));
+        assert!(num(f64::MAX).equals_within_ulps(num(f64::MIN), u64::MAX));
+    }
+
+    #[test]
+    fn signed_zeros_are_one_key() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |number: Number| {
+            let mut hasher = DefaultHasher::new();
+            number.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(num(0.0).cmp(&num(-0.0)), Ordering::Equal);
+        assert_eq!(hash(num(0.0)), hash(num(-0.0)));
+        assert!(num(-1.5) < num(0.0) && num(0.0) < num(f64::MIN_POSITIVE));
+    }
+
+    #[test]
+    fn deserializing_rejects_non_finite_values() {
+        assert_eq!(serde_json::from_str::<Number>("2.5").unwrap(), num(2.5));
+        assert_eq!(serde_json::to_string(&num(2.5)).unwrap(), "2.5");
+        let yaml: Result<Number, _> = serde_yaml::from_str(".nan");
+        assert!(yaml.is_err());
+    }
+
+    #[test]
+    fn relative_precision_of_zero_requires_exact_match() {
+        assert!(num(0.0).equals_with_relative_precision(num(0.0), 0.5));
+        assert!(!num(0.0).equals_with_relative_precision(num(1e-300), 0.5));
+    }
+}
@@ -1,8 +1,19 @@
+use std::borrow::Cow;
 use std::fmt;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::OptionsError;
+use crate::comparator::PathComparator;
+use crate::diff::Buffers;
+use crate::hash::Hashing;
+use crate::hashed::{DigestCache, DigestMemo};
+use crate::path_options::Descent;
+use crate::timestamp;
+use crate::{
+    HashAlgorithm, Node, NodeHasher, Number, OptionsError, PathMatcher, PathOption, PathSetting,
+    Preset, ValueComparator,
+};
 
 /// Controls how arrays are interpreted during equality and diff operations.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -21,17 +32,116 @@ impl Default for ArrayMode {
     }
 }
 
+/// Controls how the elements of two arrays diffed as lists are aligned.
+///
+/// ```
+/// # use jd_core::{DiffOptions, ListAlignment, Node};
+/// let lhs = Node::from_json_str(r#"[{}, "a", {}, "b", {}]"#).unwrap();
+/// let rhs = Node::from_json_str(r#"["b", {}, "a", {}]"#).unwrap();
+/// let opts = DiffOptions::default().with_list_alignment(ListAlignment::Patience);
+/// let diff = lhs.diff(&rhs, &opts);
+/// assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ListAlignment {
+    /// A longest common subsequence of the elements, like Go `jd` (default).
+    #[default]
+    Lcs,
+    /// Patience alignment: elements that occur exactly once in each array
+    /// are matched first, in order, and the runs between them are aligned
+    /// the same way, falling back to a longest common subsequence where no
+    /// element is unique. Repeated values such as `{}` or `null` then cannot
+    /// pull the alignment away from distinctive elements, which usually
+    /// gives fewer, more readable hunks.
+    Patience,
+}
+
 /// Configuration knobs passed to equality and diff operations.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DiffOptions {
     array_mode: ArrayMode,
     precision: f64,
     set_keys: Option<Vec<String>>,
+    #[serde(default)]
+    relative_precision: f64,
+    #[serde(default)]
+    max_ulps: u64,
+    #[serde(default)]
+    prune_empty: bool,
+    #[serde(default)]
+    string_scalars: bool,
+    #[serde(default)]
+    path_options: Vec<PathOption>,
+    #[serde(default)]
+    diff_off: bool,
+    #[serde(default)]
+    base64: bool,
+    #[serde(default)]
+    timestamp_tolerance: Option<f64>,
+    #[serde(default)]
+    merge: bool,
+    #[serde(default)]
+    preset: Option<Preset>,
+    #[serde(default)]
+    spill_threshold: Option<usize>,
+    #[serde(default)]
+    hash_algorithm: HashAlgorithm,
+    #[serde(default)]
+    list_alignment: ListAlignment,
+    #[serde(default)]
+    similarity_threshold: Option<f64>,
+    #[serde(default)]
+    max_hunks: Option<usize>,
+    /// Not serialized; deserialized options use `hash_algorithm`.
+    #[serde(skip)]
+    hasher: Option<Arc<dyn NodeHasher>>,
+    /// Not serialized, like `hasher`.
+    #[serde(skip)]
+    comparators: Vec<PathComparator>,
+    /// The comparator registered for exactly this value, if any.
+    #[serde(skip)]
+    comparator: Option<Arc<dyn ValueComparator>>,
+    /// Set only on the options inside a [`HashedNode`](crate::HashedNode).
+    #[serde(skip)]
+    digests: Option<Arc<DigestCache>>,
+    /// Set only for the duration of a diff.
+    #[serde(skip)]
+    memo: Option<Arc<DigestMemo>>,
+    /// Set only for the duration of a diff with a
+    /// [`DiffScratch`](crate::diff::DiffScratch).
+    #[serde(skip)]
+    scratch: Option<Arc<Buffers>>,
 }
 
 impl Default for DiffOptions {
     fn default() -> Self {
-        Self { array_mode: ArrayMode::List, precision: 0.0, set_keys: None }
+        Self {
+            array_mode: ArrayMode::List,
+            precision: 0.0,
+            set_keys: None,
+            relative_precision: 0.0,
+            max_ulps: 0,
+            prune_empty: false,
+            string_scalars: false,
+            path_options: Vec::new(),
+            diff_off: false,
+            base64: false,
+            timestamp_tolerance: None,
+            merge: false,
+            preset: None,
+            spill_threshold: None,
+            hash_algorithm: HashAlgorithm::Fnv64,
+            list_alignment: ListAlignment::Lcs,
+            similarity_threshold: None,
+            max_hunks: None,
+            hasher: None,
+            comparators: Vec::new(),
+            comparator: None,
+            digests: None,
+            memo: None,
+            scratch: None,
+        }
     }
 }
 
@@ -64,7 +174,95 @@ impl DiffOptions {
         self.precision
     }
 
-    /// Returns the keys used to identify objects within set semantics.
+    /// Returns the numeric equality tolerance relative to operand magnitude.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default()
+    ///     .with_relative_precision(1e-6)
+    ///     .expect("set relative precision");
+    /// assert!((opts.relative_precision() - 1e-6).abs() < f64::EPSILON);
+    /// ```
+    #[must_use]
+    pub fn relative_precision(&self) -> f64 {
+        self.relative_precision
+    }
+
+    /// Returns the maximum distance in units in the last place (ULPs) for
+    /// numbers to compare equal.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default().with_max_ulps(4).expect("set ulps");
+    /// assert_eq!(opts.max_ulps(), 4);
+    /// ```
+    #[must_use]
+    pub fn max_ulps(&self) -> u64 {
+        self.max_ulps
+    }
+
+    /// Compares two numbers using every configured tolerance.
+    ///
+    /// Numbers are equal when they are within the absolute precision, the
+    /// relative precision, or the ULP distance, whichever is most lenient.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Number};
+    /// let opts = DiffOptions::default()
+    ///     .with_relative_precision(0.01)
+    ///     .expect("set relative precision");
+    /// let lhs = Number::new(1000.0).unwrap();
+    /// let rhs = Number::new(1005.0).unwrap();
+    /// assert!(opts.numbers_equal(lhs, rhs));
+    /// ```
+    #[must_use]
+    pub fn numbers_equal(&self, lhs: Number, rhs: Number) -> bool {
+        lhs.equals_with_precision(rhs, self.precision)
+            || (self.relative_precision > 0.0
+                && lhs.equals_with_relative_precision(rhs, self.relative_precision))
+            || (self.max_ulps > 0 && lhs.equals_within_ulps(rhs, self.max_ulps))
+    }
+
+    /// Returns how many seconds apart ISO-8601 timestamps may be and still
+    /// compare equal, or `None` when strings only equal identical strings.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default()
+    ///     .with_timestamp_tolerance(1.0)
+    ///     .expect("set timestamp tolerance");
+    /// assert_eq!(opts.timestamp_tolerance(), Some(1.0));
+    /// ```
+    #[must_use]
+    pub fn timestamp_tolerance(&self) -> Option<f64> {
+        self.timestamp_tolerance
+    }
+
+    /// Compares two strings using the configured timestamp tolerance.
+    ///
+    /// Strings are equal when they are identical or, with a timestamp
+    /// tolerance, when both are ISO-8601 date-times no further apart than
+    /// the tolerance. Times with a UTC offset compare as instants; times
+    /// without one only equal other times without one.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default()
+    ///     .with_timestamp_tolerance(0.0)
+    ///     .expect("set timestamp tolerance");
+    /// assert!(opts.strings_equal("2024-03-01T12:00:00Z", "2024-03-01T13:00:00+01:00"));
+    /// assert!(!opts.strings_equal("2024-03-01T12:00:00Z", "2024-03-01T12:00:00"));
+    /// ```
+    #[must_use]
+    pub fn strings_equal(&self, lhs: &str, rhs: &str) -> bool {
+        lhs == rhs
+            || self
+                .timestamp_tolerance
+                .is_some_and(|tolerance| timestamp::within(lhs, rhs, tolerance))
+    }
+
+    /// Returns the keys used to identify objects within set semantics, or
+    /// to pair objects when aligning lists.
     ///
     /// ```
     /// # use jd_core::DiffOptions;
@@ -78,6 +276,161 @@ impl DiffOptions {
         self.set_keys.as_deref()
     }
 
+    /// Indicates whether empty arrays, empty objects, and absent object keys
+    /// are treated as equivalent.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default().with_prune_empty(true);
+    /// assert!(opts.prune_empty());
+    /// ```
+    #[must_use]
+    pub fn prune_empty(&self) -> bool {
+        self.prune_empty
+    }
+
+    /// Indicates whether strings compare equal to the numbers and booleans
+    /// they spell.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default().with_string_scalars(true);
+    /// assert!(opts.string_scalars());
+    /// ```
+    #[must_use]
+    pub fn string_scalars(&self) -> bool {
+        self.string_scalars
+    }
+
+    /// Indicates whether diffs are produced as JSON Merge Patches.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert!(DiffOptions::default().with_merge(true).merge());
+    /// ```
+    #[must_use]
+    pub fn merge(&self) -> bool {
+        self.merge
+    }
+
+    /// Returns the size in bytes above which an array's element hashes are
+    /// sorted on disk, if set.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert_eq!(DiffOptions::default().spill_threshold(), None);
+    /// assert_eq!(DiffOptions::default().with_spill_threshold(1 << 20).spill_threshold(), Some(1 << 20));
+    /// ```
+    #[must_use]
+    pub fn spill_threshold(&self) -> Option<usize> {
+        self.spill_threshold
+    }
+
+    /// Returns the hash function used to match array elements.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, HashAlgorithm};
+    /// assert_eq!(DiffOptions::default().hash_algorithm(), HashAlgorithm::Fnv64);
+    /// ```
+    #[must_use]
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Returns the hashing used to match array elements: the hasher set
+    /// with [`with_hasher`](Self::with_hasher), or else the
+    /// [`hash_algorithm`](Self::hash_algorithm).
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert_eq!(DiffOptions::default().hasher().width(), 8);
+    /// ```
+    #[must_use]
+    pub fn hasher(&self) -> &dyn NodeHasher {
+        match &self.hasher {
+            Some(hasher) => hasher.as_ref(),
+            None => &self.hash_algorithm,
+        }
+    }
+
+    /// Returns how arrays diffed as lists are aligned.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, ListAlignment};
+    /// assert_eq!(DiffOptions::default().list_alignment(), ListAlignment::Lcs);
+    /// ```
+    #[must_use]
+    pub fn list_alignment(&self) -> ListAlignment {
+        self.list_alignment
+    }
+
+    /// Returns the similarity above which differing list elements are
+    /// diffed recursively, if set.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert_eq!(DiffOptions::default().similarity_threshold(), None);
+    /// ```
+    #[must_use]
+    pub fn similarity_threshold(&self) -> Option<f64> {
+        self.similarity_threshold
+    }
+
+    /// Returns the number of hunks after which diffing stops, if limited.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert_eq!(DiffOptions::default().max_hunks(), None);
+    /// assert_eq!(DiffOptions::default().with_max_hunks(1).unwrap().max_hunks(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn max_hunks(&self) -> Option<usize> {
+        self.max_hunks
+    }
+
+    pub(crate) fn digests(&self) -> Option<&DigestCache> {
+        self.digests.as_deref()
+    }
+
+    pub(crate) fn with_digests(mut self, digests: Arc<DigestCache>) -> Self {
+        self.digests = Some(digests);
+        self
+    }
+
+    pub(crate) fn memo(&self) -> Option<&DigestMemo> {
+        self.memo.as_deref()
+    }
+
+    pub(crate) fn with_memo(mut self, memo: Arc<DigestMemo>) -> Self {
+        self.memo = Some(memo);
+        self
+    }
+
+    pub(crate) fn scratch(&self) -> Option<&Buffers> {
+        self.scratch.as_deref()
+    }
+
+    pub(crate) fn with_scratch(mut self, buffers: Arc<Buffers>) -> Self {
+        self.scratch = Some(buffers);
+        self
+    }
+
+    /// Options for hashing values built during a diff, whose addresses
+    /// must not enter the memo.
+    pub(crate) fn without_memo(&self) -> Cow<'_, Self> {
+        match self.memo {
+            Some(_) => Cow::Owned(Self { memo: None, ..self.clone() }),
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    pub(crate) fn hashing(&self) -> Hashing<'_> {
+        match &self.hasher {
+            Some(hasher) => Hashing::Custom(hasher.as_ref()),
+            None => Hashing::Builtin(self.hash_algorithm),
+        }
+    }
+
     /// Sets the array interpretation mode.
     ///
     /// ```
@@ -108,14 +461,90 @@ impl DiffOptions {
         Ok(self)
     }
 
+    /// Sets a numeric tolerance relative to the magnitude of the compared numbers.
+    ///
+    /// Two numbers `a` and `b` compare equal when
+    /// `|a - b| <= tolerance * max(|a|, |b|)`. Like the absolute precision,
+    /// relative tolerance is only supported in list array mode.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let opts = DiffOptions::default()
+    ///     .with_relative_precision(1e-3)
+    ///     .expect("set relative precision");
+    /// let lhs = Node::from_json_str("[6.02214e23, 1.6e-19]").unwrap();
+    /// let rhs = Node::from_json_str("[6.0222e23, 1.6001e-19]").unwrap();
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    /// ```
+    pub fn with_relative_precision(mut self, tolerance: f64) -> Result<Self, OptionsError> {
+        if !tolerance.is_finite() || tolerance < 0.0 {
+            return Err(OptionsError::InvalidTolerance);
+        }
+        self.relative_precision = tolerance;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Sets the maximum ULP distance for numbers to compare equal.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let opts = DiffOptions::default().with_max_ulps(1).expect("set ulps");
+    /// let lhs = Node::from_json_str("0.30000000000000004").unwrap();
+    /// let rhs = Node::from_json_str("0.3").unwrap();
+    /// assert!(lhs.eq_with_options(&rhs, &opts));
+    /// ```
+    pub fn with_max_ulps(mut self, max_ulps: u64) -> Result<Self, OptionsError> {
+        self.max_ulps = max_ulps;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Compares strings holding ISO-8601 date-times as instants that may be
+    /// up to `seconds` apart, so the same moment written in another time
+    /// zone or rounded differently compares equal. Like the numeric
+    /// tolerances, it is only supported in list array mode.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let opts = DiffOptions::default()
+    ///     .with_timestamp_tolerance(1.0)
+    ///     .expect("set timestamp tolerance");
+    /// let lhs = Node::from_json_str(r#"{"at":"2024-03-01T12:00:00.4Z"}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"at":"2024-03-01T07:00:01-05:00"}"#).unwrap();
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    /// ```
+    pub fn with_timestamp_tolerance(mut self, seconds: f64) -> Result<Self, OptionsError> {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return Err(OptionsError::InvalidTolerance);
+        }
+        self.timestamp_tolerance = Some(seconds);
+        self.validate()?;
+        Ok(self)
+    }
+
     /// Sets the object identity keys used when arrays behave as sets.
     ///
+    /// This switches to set mode. Switching back to list mode afterwards
+    /// keeps the keys as alignment anchors: objects with the same values
+    /// for the keys are paired and diffed member by member, even where they
+    /// differ elsewhere, instead of being removed and added whole.
+    ///
     /// ```
-    /// # use jd_core::DiffOptions;
+    /// # use jd_core::{ArrayMode, DiffOptions, Node, RenderConfig};
     /// let opts = DiffOptions::default()
     ///     .with_set_keys(["name", "id"])
     ///     .expect("set keys");
     /// assert_eq!(opts.set_keys().unwrap(), ["id", "name"]);
+    ///
+    /// let anchored = DiffOptions::default()
+    ///     .with_set_keys(["id"])
+    ///     .and_then(|opts| opts.with_array_mode(ArrayMode::List))
+    ///     .unwrap();
+    /// let lhs = Node::from_json_str(r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"[{"id": 1, "v": "x"}, {"id": 2, "v": "b"}]"#).unwrap();
+    /// let diff = lhs.diff(&rhs, &anchored);
+    /// assert_eq!(diff.render(&RenderConfig::default()), "@ [0,\"v\"]\n- \"a\"\n+ \"x\"\n");
     /// ```
     pub fn with_set_keys<I, S>(mut self, keys: I) -> Result<Self, OptionsError>
     where
@@ -141,11 +570,565 @@ impl DiffOptions {
         Ok(self)
     }
 
+    /// Treats `[]`, `{}`, and absent object keys as equivalent.
+    ///
+    /// Useful when comparing API responses that omit empty collections
+    /// inconsistently. Containers whose members are all empty after pruning
+    /// (e.g. `{"a":{"b":[]}}`) are considered empty as well. See
+    /// [`Node::prune_empty`](crate::Node::prune_empty) for the matching
+    /// normalization helper.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let lhs = Node::from_json_str(r#"{"name":"jd","tags":[]}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"name":"jd"}"#).unwrap();
+    /// let opts = DiffOptions::default().with_prune_empty(true);
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    /// ```
+    #[must_use]
+    pub fn with_prune_empty(mut self, enabled: bool) -> Self {
+        self.prune_empty = enabled;
+        self
+    }
+
+    /// Treats a string holding a JSON number or `true`/`false` as equal to
+    /// that number or boolean, so `"42"` equals `42` and `"true"` equals
+    /// `true`.
+    ///
+    /// Useful when comparing documents from systems that stringify scalars
+    /// inconsistently, such as configs derived from environment variables.
+    /// Numbers are compared with the configured tolerances; strings with
+    /// surrounding whitespace or other spellings (`"+1"`, `"TRUE"`) still
+    /// differ. Such a string also hashes as the value it spells, so list
+    /// alignment pairs `"42"` with `42` instead of removing one and adding
+    /// the other.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let lhs = Node::from_json_str(r#"{"port":"8080","debug":"false","ratio":"1e-1"}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"port":8080,"debug":false,"ratio":0.1}"#).unwrap();
+    /// let opts = DiffOptions::default().with_string_scalars(true);
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    /// assert!(!lhs.diff(&rhs, &DiffOptions::default()).is_empty());
+    /// ```
+    #[must_use]
+    pub fn with_string_scalars(mut self, enabled: bool) -> Self {
+        self.string_scalars = enabled;
+        self
+    }
+
+    /// Produces diffs with JSON Merge Patch (RFC 7386) semantics, like Go's
+    /// `jd.MERGE` option.
+    ///
+    /// Every hunk carries [`DiffMetadata::merge`](crate::DiffMetadata::merge)
+    /// and only adds values: removed members become void additions, and
+    /// arrays and values of different types are replaced as a whole. The
+    /// result can be rendered with [`Diff::render_merge`](crate::Diff::render_merge).
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let lhs = Node::from_json_str(r#"{"a":1,"b":[1,2],"c":true}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"a":2,"b":[1]}"#).unwrap();
+    /// let diff = lhs.diff(&rhs, &DiffOptions::default().with_merge(true));
+    /// assert_eq!(diff.render_merge().unwrap(), r#"{"a":2,"b":[1],"c":null}"#);
+    /// assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+    /// ```
+    #[must_use]
+    pub fn with_merge(mut self, enabled: bool) -> Self {
+        self.merge = enabled;
+        self
+    }
+
+    /// Sorts the element hashes of arrays compared as sets or multisets on
+    /// disk once they take more than `bytes`.
+    ///
+    /// Unordered comparison sorts the hash of every element. When an
+    /// array's hashes (16 bytes each) would take more than `bytes`, they are
+    /// sorted in runs, spilled to anonymous temporary files, and merged from
+    /// disk. A run holds `bytes` of hashes, but never fewer than 4096
+    /// hashes (64 KiB), so that small thresholds do not open a file for
+    /// every few elements. This is not a memory limit: only the hash
+    /// buffers spill, both documents stay parsed in memory, and list-mode
+    /// diffs keep their alignment tables there too. If a temporary file
+    /// cannot be written or read the hashes are sorted in memory instead.
+    /// The diff engine does not support set and multiset diffs yet, so the
+    /// threshold applies to equality and hashing.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, DiffOptions, Node};
+    /// let opts = DiffOptions::default()
+    ///     .with_array_mode(ArrayMode::MultiSet)
+    ///     .unwrap()
+    ///     .with_spill_threshold(64 * 1024);
+    /// let lhs = Node::Array((0..10_000).map(Node::from).collect());
+    /// let rhs = Node::Array((0..10_000).rev().map(Node::from).collect());
+    /// assert!(lhs.eq_with_options(&rhs, &opts));
+    /// ```
+    #[must_use]
+    pub fn with_spill_threshold(mut self, bytes: usize) -> Self {
+        self.spill_threshold = Some(bytes);
+        self
+    }
+
+    /// Selects the hash function used to match elements in set and
+    /// multiset comparisons and in list alignment.
+    ///
+    /// The default [`HashAlgorithm::Fnv64`] reproduces Go `jd`'s hash codes.
+    /// Choose [`HashAlgorithm::sip_hash_128`] for untrusted input, where
+    /// crafted FNV collisions could pair up different elements; hash codes
+    /// then no longer match Go `jd`.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, DiffOptions, HashAlgorithm, Node};
+    /// let opts = DiffOptions::default()
+    ///     .with_array_mode(ArrayMode::Set)
+    ///     .unwrap()
+    ///     .with_hash_algorithm(HashAlgorithm::sip_hash_128());
+    /// let lhs = Node::from_json_str(r#"["a", "b", "a"]"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"["b", "a"]"#).unwrap();
+    /// assert!(lhs.eq_with_options(&rhs, &opts));
+    /// ```
+    #[must_use]
+    pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = algorithm;
+        self
+    }
+
+    /// Hashes array elements with an embedder's [`NodeHasher`] instead of
+    /// the [`hash_algorithm`](Self::hash_algorithm), for set and multiset
+    /// comparisons and list alignment.
+    ///
+    /// The hasher is shared by clones of these options but is not
+    /// serialized. See [`NodeHasher`] for an example.
+    #[must_use]
+    pub fn with_hasher(mut self, hasher: impl NodeHasher + 'static) -> Self {
+        self.hasher = Some(Arc::new(hasher));
+        self
+    }
+
+    /// Compares the values at `at` with an embedder's [`ValueComparator`]
+    /// before comparing them structurally.
+    ///
+    /// The comparator applies to the values the path matches, not to the
+    /// values below them; use [`PathMatcher::AnyKey`] and
+    /// [`PathMatcher::AnyIndex`] to reach several values. An empty path
+    /// selects the whole document. A later comparator for the same values
+    /// replaces an earlier one. Comparators are shared by clones of these
+    /// options but are not serialized. See [`ValueComparator`] for an
+    /// example.
+    #[must_use]
+    pub fn with_comparator(
+        mut self,
+        at: impl IntoIterator<Item = PathMatcher>,
+        comparator: impl ValueComparator + 'static,
+    ) -> Self {
+        let comparator = PathComparator::new(at.into_iter().collect(), Arc::new(comparator));
+        match comparator.reached() {
+            Some(reached) => self.comparator = Some(Arc::clone(reached)),
+            None => self.comparators.push(comparator),
+        }
+        self
+    }
+
+    /// Selects how arrays diffed as lists are aligned. See [`ListAlignment`].
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, ListAlignment, Node, RenderConfig};
+    /// let lhs = Node::from_json_str(r#"[{}, {"id": 1}, {}]"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"[{"id": 1}, {}, {}]"#).unwrap();
+    /// let opts = DiffOptions::default().with_list_alignment(ListAlignment::Patience);
+    /// assert_eq!(
+    ///     lhs.diff(&rhs, &opts).render(&RenderConfig::default()),
+    ///     "@ [0]\n[\n- {}\n  {\"id\":1}\n@ [2]\n  {}\n+ {}\n]\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_list_alignment(mut self, alignment: ListAlignment) -> Self {
+        self.list_alignment = alignment;
+        self
+    }
+
+    /// Pairs differing list elements by structural similarity.
+    ///
+    /// By default, arrays or objects left over between aligned elements are
+    /// diffed recursively with whichever element of the same type is at the
+    /// same position, however little they share. With a threshold, only
+    /// elements sharing at least that fraction of their leaves (scalars and
+    /// empty containers, at the same path inside each element) are diffed
+    /// recursively; an element with a similar counterpart a little further
+    /// along, up to 16 elements ahead, waits for it; and the rest are
+    /// removed and added whole. A threshold of `0.7` reports an object with
+    /// one of four fields changed as modified, like the "modified element"
+    /// detection of other differs.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str(r#"[{"a": 1, "b": 2, "c": 3}]"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"[{"z": 0}, {"a": 1, "b": 2, "c": 4}]"#).unwrap();
+    /// let opts = DiffOptions::default().with_similarity_threshold(0.6).unwrap();
+    /// assert_eq!(
+    ///     lhs.diff(&rhs, &opts).render(&RenderConfig::default()),
+    ///     "@ [0]\n[\n+ {\"z\":0}\n  {\"a\":1,\"b\":2,\"c\":3}\n@ [1,\"c\"]\n- 3\n+ 4\n"
+    /// );
+    /// assert!(DiffOptions::default().with_similarity_threshold(1.5).is_err());
+    /// ```
+    pub fn with_similarity_threshold(mut self, threshold: f64) -> Result<Self, OptionsError> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(OptionsError::InvalidSimilarity);
+        }
+        self.similarity_threshold = Some(threshold);
+        Ok(self)
+    }
+
+    /// Stops diffing once `max` hunks have been found, for callers that
+    /// only need to know whether, or roughly how, huge documents differ.
+    ///
+    /// The diff keeps the first `max` hunks, in the order a full diff would
+    /// list them, and [`Diff::is_truncated`](crate::Diff::is_truncated)
+    /// reports that later hunks may be missing. The hunks patch the left
+    /// document part of the way to the right one.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, RenderConfig};
+    /// let lhs = Node::from_json_str(r#"{"a": 1, "b": [1, 2], "c": 3}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"a": 2, "b": [1, 3], "c": 4}"#).unwrap();
+    /// let opts = DiffOptions::default().with_max_hunks(2).unwrap();
+    /// let diff = lhs.diff(&rhs, &opts);
+    /// assert!(diff.is_truncated());
+    /// assert_eq!(
+    ///     diff.render(&RenderConfig::default()),
+    ///     "@ [\"a\"]\n- 1\n+ 2\n@ [\"b\",1]\n  1\n- 2\n+ 3\n]\n"
+    /// );
+    /// assert!(!lhs.diff(&lhs, &opts).is_truncated());
+    /// assert!(DiffOptions::default().with_max_hunks(0).is_err());
+    /// ```
+    pub fn with_max_hunks(mut self, max: usize) -> Result<Self, OptionsError> {
+        if max == 0 {
+            return Err(OptionsError::InvalidMaxHunks);
+        }
+        self.max_hunks = Some(max);
+        Ok(self)
+    }
+
+    /// Adds settings that apply only to the values at a path and below.
+    ///
+    /// An option with an empty path changes the global settings directly.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node, PathMatcher, PathOption, PathSetting};
+    /// let option = PathOption::new([PathMatcher::key("metrics"), PathMatcher::AnyKey])
+    ///     .with_setting(PathSetting::Precision(0.1));
+    /// let opts = DiffOptions::default().with_path_option(option).unwrap();
+    /// assert_eq!(opts.path_options().len(), 1);
+    ///
+    /// let lhs = Node::from_json_str(r#"{"metrics":{"cpu":0.51},"version":1.01}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"metrics":{"cpu":0.48},"version":1.0}"#).unwrap();
+    /// assert_eq!(lhs.diff(&rhs, &opts).len(), 1);
+    /// ```
+    pub fn with_path_option(mut self, option: PathOption) -> Result<Self, OptionsError> {
+        option.validate()?;
+        if option.at().is_empty() {
+            self.apply(option.settings());
+        } else {
+            self.path_options.push(option);
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Creates default options with the path options of `preset`.
+    ///
+    /// [`Jd::diff`](crate::Jd::diff) also sorts both documents with
+    /// [`Preset::normalize`]; callers diffing with [`Node::diff`](crate::Node::diff)
+    /// normalize them themselves.
+    ///
+    /// ```
+    /// # use jd_core::{node, DiffOptions, Preset};
+    /// let opts = DiffOptions::preset(Preset::OpenApi);
+    /// let lhs = node!({"info": {"title": "Pets", "version": "1.0.0"}});
+    /// let rhs = node!({"info": {"title": "Pets", "version": "1.1.0"}});
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    /// ```
+    #[must_use]
+    pub fn preset(preset: Preset) -> Self {
+        Self::default().with_preset(preset)
+    }
+
+    /// Adds the path options of `preset`, after any already configured.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Preset};
+    /// let opts = DiffOptions::default().with_prune_empty(true).with_preset(Preset::Terraform);
+    /// assert!(opts.prune_empty());
+    /// assert_eq!(opts.path_options(), Preset::Terraform.path_options());
+    /// ```
+    #[must_use]
+    pub fn with_preset(mut self, preset: Preset) -> Self {
+        // Preset options only switch diffing off below non-empty paths and
+        // anchor lists there by set keys, which never conflicts with other
+        // settings.
+        self.path_options.extend(preset.path_options());
+        self.preset = Some(preset);
+        self
+    }
+
+    pub(crate) fn applied_preset(&self) -> Option<Preset> {
+        self.preset
+    }
+
+    /// Adds path options derived from a JSON Schema.
+    ///
+    /// Numbers with `multipleOf: step` compare equal within `step / 2`.
+    /// Settings are placed at the paths described by `properties`,
+    /// `additionalProperties`, `items`, and `prefixItems`, following `allOf`,
+    /// `anyOf`, `oneOf`, and local `$ref`s (recursive references are expanded
+    /// once). An `x-jd-set-keys` array of member names anchors the list
+    /// alignment of the array it annotates, as set keys followed by list mode
+    /// do. `uniqueItems: true` would ask for set semantics, which the diff
+    /// engine does not implement yet, so it is skipped with a warning; list
+    /// mode still reports every change to such an array. Other keywords are
+    /// ignored.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let schema = Node::from_json_str(
+    ///     r#"{"properties":{"price":{"type":"number","multipleOf":0.01}}}"#,
+    /// )
+    /// .unwrap();
+    /// let opts = DiffOptions::default().with_json_schema(&schema).unwrap();
+    /// let lhs = Node::from_json_str(r#"{"price":0.3}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"price":0.30000000000000004}"#).unwrap();
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    ///
+    /// let sets = Node::from_json_str(r#"{"properties":{"tags":{"uniqueItems":true}}}"#).unwrap();
+    /// assert!(DiffOptions::default().with_json_schema(&sets).is_ok());
+    /// ```
+    pub fn with_json_schema(self, schema: &crate::Node) -> Result<Self, OptionsError> {
+        crate::schema::path_options(schema)?
+            .into_iter()
+            .try_fold(self, |options, option| options.with_path_option(option))
+    }
+
+    /// Parses options in the format of Go `jd`'s `-opts` flag, a JSON array
+    /// such as `["MERGE",{"precision":0.01},{"@":["tags"],"^":["DIFF_OFF"]}]`.
+    ///
+    /// Besides Go's options, `"LIST"`, `"PRUNE_EMPTY"`, `"STRING_SCALARS"`, `"BASE64"`,
+    /// `{"relative_precision":N}`, `{"ulps":N}`, and
+    /// `{"timestamp_tolerance":N}` set the matching
+    /// extensions, and `[]` and `{}` in a path match any array element and
+    /// any object member. `"COLOR"` only affects rendering and is ignored.
+    /// `"SET"`, `"MULTISET"`, and set keys outside list mode fail with
+    /// [`OptionsError::UnsupportedArrayMode`] until the diff engine supports
+    /// sets.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let opts = DiffOptions::from_json_opts(r#"[{"@":["price"],"^":[{"precision":0.01}]}]"#).unwrap();
+    /// let lhs = Node::from_json_str(r#"{"price":9.99}"#).unwrap();
+    /// let rhs = Node::from_json_str(r#"{"price":9.991}"#).unwrap();
+    /// assert!(lhs.diff(&rhs, &opts).is_empty());
+    ///
+    /// assert!(DiffOptions::from_json_opts(r#"["FAST"]"#).is_err());
+    /// assert!(DiffOptions::from_json_opts(r#"["SET"]"#).is_err());
+    /// ```
+    pub fn from_json_opts(input: &str) -> Result<Self, OptionsError> {
+        crate::opts::parse(input)
+    }
+
+    /// Formats these options as a Go `jd` `-opts` array that
+    /// [`DiffOptions::from_json_opts`] reads back into the same options.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// let opts = DiffOptions::default().with_merge(true).with_precision(0.5).unwrap();
+    /// assert_eq!(opts.to_json_opts(), r#"[{"precision":0.5},"MERGE"]"#);
+    /// assert_eq!(DiffOptions::from_json_opts(&opts.to_json_opts()).unwrap().to_json_opts(), opts.to_json_opts());
+    /// ```
+    #[must_use]
+    pub fn to_json_opts(&self) -> String {
+        crate::opts::render(self)
+    }
+
+    /// Returns the path-scoped options, in the order they were added.
+    ///
+    /// ```
+    /// # use jd_core::DiffOptions;
+    /// assert!(DiffOptions::default().path_options().is_empty());
+    /// ```
+    #[must_use]
+    pub fn path_options(&self) -> &[PathOption] {
+        &self.path_options
+    }
+
+    /// Reports an array mode the diff engine cannot handle yet, whether set
+    /// globally or by a path option.
+    pub(crate) fn unsupported_array_mode(&self) -> Option<ArrayMode> {
+        if self.array_mode != ArrayMode::List {
+            return Some(self.array_mode);
+        }
+        // Set keys followed by list mode only anchor the list alignment.
+        self.path_options.iter().find_map(|option| {
+            let mode = option.settings().iter().fold(None, |mode, setting| match setting {
+                PathSetting::ArrayMode(mode) => Some(*mode),
+                PathSetting::SetKeys(_) => Some(ArrayMode::Set),
+                PathSetting::Precision(_)
+                | PathSetting::TimestampTolerance(_)
+                | PathSetting::DiffOff
+                | PathSetting::DiffOn
+                | PathSetting::Base64 => mode,
+            });
+            mode.filter(|mode| *mode != ArrayMode::List)
+        })
+    }
+
+    /// Whether a [`PathSetting::DiffOff`] applies here.
+    pub(crate) fn diff_off(&self) -> bool {
+        self.diff_off
+    }
+
+    /// Whether a [`PathSetting::DiffOff`] applies and no [`PathSetting::DiffOn`]
+    /// further down turns diffing back on, so all values compare equal.
+    pub(crate) fn ignores_all(&self) -> bool {
+        self.diff_off
+            && !self
+                .path_options
+                .iter()
+                .any(|option| option.settings().contains(&PathSetting::DiffOn))
+    }
+
+    /// Whether a [`PathSetting::Base64`] applies here.
+    pub(crate) fn base64(&self) -> bool {
+        self.base64
+    }
+
+    /// Whether a [`PathSetting::Base64`] applies here or anywhere below.
+    pub(crate) fn has_base64(&self) -> bool {
+        self.base64
+            || self
+                .path_options
+                .iter()
+                .any(|option| option.settings().contains(&PathSetting::Base64))
+    }
+
+    /// What the [`ValueComparator`] registered for these values makes of
+    /// them, or `None` to compare them structurally.
+    pub(crate) fn custom_equality(&self, lhs: &Node, rhs: &Node) -> Option<bool> {
+        if self.diff_off {
+            return None;
+        }
+        self.comparator.as_ref()?.equals(lhs, rhs)
+    }
+
+    /// Whether a [`ValueComparator`] applies here or below.
+    pub(crate) fn has_comparators(&self) -> bool {
+        !self.comparators.is_empty() || self.comparator.is_some()
+    }
+
+    /// Whether values equal under these options, here and below, always
+    /// share a digest, so that unequal digests prove a difference.
+    /// Tolerances, string scalars, comparators, and `DIFF_OFF` equate values
+    /// whose digests differ.
+    pub(crate) fn digests_decide_inequality(&self) -> bool {
+        !self.diff_off
+            && self.precision == 0.0
+            && self.relative_precision == 0.0
+            && self.max_ulps == 0
+            && self.timestamp_tolerance.is_none()
+            && !self.string_scalars
+            && !self.has_comparators()
+            && self.path_options.iter().all(|option| {
+                !option.settings().iter().any(|setting| {
+                    matches!(
+                        setting,
+                        PathSetting::Precision(_)
+                            | PathSetting::TimestampTolerance(_)
+                            | PathSetting::DiffOff
+                    )
+                })
+            })
+    }
+
+    /// Options for the member `key` of an object compared under `self`.
+    pub(crate) fn refine_key(&self, key: &str) -> Cow<'_, Self> {
+        self.refine(|matcher| matcher.matches_key(key))
+    }
+
+    /// Options for the element at `index` of an array compared under `self`.
+    pub(crate) fn refine_index(&self, index: i64) -> Cow<'_, Self> {
+        self.refine(|matcher| matcher.matches_index(index))
+    }
+
+    fn refine(&self, matches: impl Fn(&PathMatcher) -> bool) -> Cow<'_, Self> {
+        if self.path_options.is_empty() && !self.has_comparators() {
+            return Cow::Borrowed(self);
+        }
+        let mut child = Self {
+            set_keys: self.set_keys.clone(),
+            path_options: Vec::new(),
+            hasher: self.hasher.clone(),
+            comparators: Vec::new(),
+            comparator: None,
+            digests: self.digests.clone(),
+            memo: self.memo.clone(),
+            scratch: self.scratch.clone(),
+            ..*self
+        };
+        for option in &self.path_options {
+            match option.descend(&matches) {
+                Some(Descent::Reached(settings)) => child.apply(settings),
+                Some(Descent::Pending(option)) => child.path_options.push(option),
+                None => {}
+            }
+        }
+        for comparator in &self.comparators {
+            if let Some(comparator) = comparator.descend(&matches) {
+                match comparator.reached() {
+                    Some(reached) => child.comparator = Some(Arc::clone(reached)),
+                    None => child.comparators.push(comparator),
+                }
+            }
+        }
+        Cow::Owned(child)
+    }
+
+    /// Applies path settings without re-validating the combination; a subtree
+    /// may legitimately use set semantics under a global precision.
+    pub(crate) fn apply(&mut self, settings: &[PathSetting]) {
+        for setting in settings {
+            match setting {
+                PathSetting::ArrayMode(mode) => {
+                    self.array_mode = *mode;
+                    if *mode == ArrayMode::MultiSet {
+                        self.set_keys = None;
+                    }
+                }
+                PathSetting::SetKeys(keys) => {
+                    let mut keys = keys.clone();
+                    keys.sort();
+                    keys.dedup();
+                    self.set_keys = Some(keys);
+                    self.array_mode = ArrayMode::Set;
+                }
+                PathSetting::Precision(precision) => self.precision = *precision,
+                PathSetting::TimestampTolerance(seconds) => {
+                    self.timestamp_tolerance = Some(*seconds);
+                }
+                PathSetting::DiffOff => self.diff_off = true,
+                PathSetting::DiffOn => self.diff_off = false,
+                PathSetting::Base64 => self.base64 = true,
+            }
+        }
+    }
+
     fn validate(&self) -> Result<(), OptionsError> {
-        if !matches!(self.array_mode, ArrayMode::List) && self.precision > 0.0 {
+        let tolerant = self.precision > 0.0
+            || self.relative_precision > 0.0
+            || self.max_ulps > 0
+            || self.timestamp_tolerance.is_some();
+        if !matches!(self.array_mode, ArrayMode::List) && tolerant {
             return Err(OptionsError::PrecisionIncompatible);
         }
-        if self.set_keys.is_some() && !matches!(self.array_mode, ArrayMode::Set) {
+        if self.set_keys.is_some() && matches!(self.array_mode, ArrayMode::MultiSet) {
             return Err(OptionsError::SetKeysRequireSetMode);
         }
         Ok(())
@@ -166,6 +1149,22 @@ impl fmt::Display for ArrayMode {
 mod tests {
     use super::*;
 
+    #[test]
+    fn options_are_unwind_safe() {
+        fn check<T: Send + Sync + std::panic::RefUnwindSafe + std::panic::UnwindSafe>(_: &T) {}
+        #[derive(Debug)]
+        struct Zero;
+        impl NodeHasher for Zero {
+            fn width(&self) -> usize {
+                1
+            }
+            fn digest(&self, _: u8, _: &[u8]) -> crate::Digest {
+                [0; 16]
+            }
+        }
+        check(&DiffOptions::default().with_hasher(Zero));
+    }
+
     #[test]
     fn precision_and_set_mode_conflict() {
         let err = DiffOptions::default()
@@ -181,6 +1180,58 @@ mod tests {
         assert_eq!(err, OptionsError::EmptySetKey);
     }
 
+    #[test]
+    fn relative_precision_rejects_invalid_values() {
+        let err = DiffOptions::default().with_relative_precision(-1.0).unwrap_err();
+        assert_eq!(err, OptionsError::InvalidTolerance);
+        let err = DiffOptions::default().with_relative_precision(f64::NAN).unwrap_err();
+        assert_eq!(err, OptionsError::InvalidTolerance);
+    }
+
+    #[test]
+    fn relative_precision_and_ulps_conflict_with_set_mode() {
+        let err = DiffOptions::default()
+            .with_relative_precision(0.1)
+            .and_then(|opts| opts.with_array_mode(ArrayMode::MultiSet))
+            .unwrap_err();
+        assert_eq!(err, OptionsError::PrecisionIncompatible);
+        let err = DiffOptions::default()
+            .with_array_mode(ArrayMode::Set)
+            .and_then(|opts| opts.with_max_ulps(2))
+            .unwrap_err();
+        assert_eq!(err, OptionsError::PrecisionIncompatible);
+    }
+
+    #[test]
+    fn timestamp_tolerance_applies_at_its_path() {
+        let err = DiffOptions::default().with_timestamp_tolerance(f64::INFINITY).unwrap_err();
+        assert_eq!(err, OptionsError::InvalidTolerance);
+        let err = DiffOptions::default()
+            .with_timestamp_tolerance(1.0)
+            .and_then(|opts| opts.with_array_mode(ArrayMode::Set))
+            .unwrap_err();
+        assert_eq!(err, OptionsError::PrecisionIncompatible);
+
+        let opts = DiffOptions::default()
+            .with_path_option(
+                PathOption::new([PathMatcher::key("at")])
+                    .with_setting(PathSetting::TimestampTolerance(1.0)),
+            )
+            .unwrap();
+        let lhs = crate::Node::from_json_str(
+            r#"{"at":"2024-03-01T12:00:00Z","id":"2024-03-01T12:00:00Z"}"#,
+        )
+        .unwrap();
+        let rhs = crate::Node::from_json_str(
+            r#"{"at":"2024-03-01T13:00:01+01:00","id":"2024-03-01T13:00:00+01:00"}"#,
+        )
+        .unwrap();
+        let diff = lhs.diff(&rhs, &opts);
+        assert_eq!(diff.len(), 1);
+        assert!(!lhs.eq_with_options(&rhs, &opts));
+        assert!(lhs.eq_with_options(&rhs, &opts.with_timestamp_tolerance(1.0).unwrap()));
+    }
+
     #[test]
     fn set_keys_force_set_mode() {
         let opts = DiffOptions::default().with_set_keys(["id"]).unwrap();
@@ -0,0 +1,304 @@
+//! The JSON option list of Go `jd`'s `-opts` flag.
+//!
+//! Each entry is an option name (`"SET"`, `"MULTISET"`, `"MERGE"`,
+//! `"COLOR"`, `"DIFF_ON"`, `"DIFF_OFF"`), a one-member object
+//! (`{"precision":N}`, `{"setkeys":[...]}`), or a path option
+//! `{"@":PATH,"^":[...]}` whose settings apply at `PATH`. Options this crate
+//! adds beyond Go use the same shapes: `"LIST"`, `"PRUNE_EMPTY"`,
+//! `"STRING_SCALARS"`, `"BASE64"`,
+//! `{"relative_precision":N}`, `{"ulps":N}`, `{"timestamp_tolerance":N}`,
+//! and the path wildcards `[]`
+//! (any array element) and `{}` (any object member). `PATH` may also be a
+//! JSONPath string such as `"$.spec[*].ports"`; see
+//! [`JsonPath::to_matchers`]. Options that leave arrays in set or multiset
+//! mode are read but refused, since the diff engine cannot diff sets yet.
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::query::JsonPath;
+use crate::{ArrayMode, DiffOptions, OptionsError, PathMatcher, PathOption, PathSetting};
+
+pub(crate) fn parse(input: &str) -> Result<DiffOptions, OptionsError> {
+    let entries: JsonValue = serde_json::from_str(input).map_err(|err| invalid(err.to_string()))?;
+    let JsonValue::Array(entries) = entries else {
+        return Err(invalid("expected a JSON array"));
+    };
+    let options = entries.iter().try_fold(DiffOptions::default(), apply)?;
+    match options.unsupported_array_mode() {
+        Some(mode) => Err(OptionsError::UnsupportedArrayMode(mode)),
+        None => Ok(options),
+    }
+}
+
+fn apply(options: DiffOptions, entry: &JsonValue) -> Result<DiffOptions, OptionsError> {
+    match entry {
+        // Color only affects rendering; see `RenderConfig::with_color`.
+        JsonValue::String(name) if name == "COLOR" => Ok(options),
+        JsonValue::String(name) if name == "MERGE" => Ok(options.with_merge(true)),
+        JsonValue::String(name) if name == "PRUNE_EMPTY" => Ok(options.with_prune_empty(true)),
+        JsonValue::String(name) if name == "STRING_SCALARS" => {
+            Ok(options.with_string_scalars(true))
+        }
+        JsonValue::Object(members) if members.contains_key("@") => {
+            options.with_path_option(path_option(members)?)
+        }
+        JsonValue::Object(members) => match single_member(members, entry)? {
+            ("relative_precision", value) => options.with_relative_precision(number(value, entry)?),
+            ("ulps", value) => options.with_max_ulps(
+                value.as_u64().ok_or_else(|| invalid(format!("expected an integer in {entry}")))?,
+            ),
+            _ => options.with_path_option(PathOption::new([]).with_setting(setting(entry)?)),
+        },
+        _ => options.with_path_option(PathOption::new([]).with_setting(setting(entry)?)),
+    }
+}
+
+fn path_option(members: &Map<String, JsonValue>) -> Result<PathOption, OptionsError> {
+    let entry = JsonValue::Object(members.clone());
+    if let Some(key) = members.keys().find(|key| *key != "@" && *key != "^") {
+        return Err(invalid(format!("unexpected key {key:?} in {entry}")));
+    }
+    let path = match members.get("@") {
+        Some(JsonValue::Array(path)) => path,
+        Some(JsonValue::String(query)) => {
+            let at = JsonPath::parse(query).and_then(|query| query.to_matchers());
+            return settings(members, &entry, at.map_err(|err| invalid(err.to_string()))?);
+        }
+        _ => return Err(invalid(format!("expected a path array or JSONPath in {entry}"))),
+    };
+    let at = path
+        .iter()
+        .map(|segment| match segment {
+            JsonValue::String(key) => Ok(PathMatcher::key(key.clone())),
+            JsonValue::Number(index) => index
+                .as_i64()
+                .map(PathMatcher::Index)
+                .ok_or_else(|| invalid(format!("invalid index {index} in {entry}"))),
+            JsonValue::Array(any) if any.is_empty() => Ok(PathMatcher::AnyIndex),
+            JsonValue::Object(any) if any.is_empty() => Ok(PathMatcher::AnyKey),
+            other => Err(invalid(format!("unsupported path element {other} in {entry}"))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    settings(members, &entry, at)
+}
+
+fn settings(
+    members: &Map<String, JsonValue>,
+    entry: &JsonValue,
+    at: Vec<PathMatcher>,
+) -> Result<PathOption, OptionsError> {
+    let settings = match members.get("^") {
+        Some(JsonValue::Array(settings)) => settings,
+        None => return Err(invalid(format!("missing settings in {entry}"))),
+        Some(_) => return Err(invalid(format!("expected a settings array in {entry}"))),
+    };
+    settings.iter().try_fold(PathOption::new(at), |option, setting_entry| {
+        Ok(option.with_setting(setting(setting_entry)?))
+    })
+}
+
+/// Parses an option that can also appear inside a path option.
+fn setting(entry: &JsonValue) -> Result<PathSetting, OptionsError> {
+    match entry {
+        JsonValue::String(name) => match name.as_str() {
+            "LIST" => Ok(PathSetting::ArrayMode(ArrayMode::List)),
+            "SET" => Ok(PathSetting::ArrayMode(ArrayMode::Set)),
+            "MULTISET" => Ok(PathSetting::ArrayMode(ArrayMode::MultiSet)),
+            "DIFF_ON" => Ok(PathSetting::DiffOn),
+            "DIFF_OFF" => Ok(PathSetting::DiffOff),
+            "BASE64" => Ok(PathSetting::Base64),
+            _ => Err(invalid(format!("unsupported option {entry}"))),
+        },
+        JsonValue::Object(members) => match single_member(members, entry)? {
+            ("precision", value) => Ok(PathSetting::Precision(number(value, entry)?)),
+            ("timestamp_tolerance", value) => {
+                Ok(PathSetting::TimestampTolerance(number(value, entry)?))
+            }
+            ("setkeys", JsonValue::Array(keys)) => keys
+                .iter()
+                .map(|key| match key {
+                    JsonValue::String(key) => Ok(key.clone()),
+                    _ => Err(invalid(format!("expected strings in {entry}"))),
+                })
+                .collect::<Result<_, _>>()
+                .map(PathSetting::SetKeys),
+            _ => Err(invalid(format!("unsupported option {entry}"))),
+        },
+        _ => Err(invalid(format!("unsupported option {entry}"))),
+    }
+}
+
+fn single_member<'a>(
+    members: &'a Map<String, JsonValue>,
+    entry: &JsonValue,
+) -> Result<(&'a str, &'a JsonValue), OptionsError> {
+    let mut iter = members.iter();
+    match (iter.next(), iter.next()) {
+        (Some((key, value)), None) => Ok((key.as_str(), value)),
+        _ => Err(invalid(format!("expected exactly one key in {entry}"))),
+    }
+}
+
+fn number(value: &JsonValue, entry: &JsonValue) -> Result<f64, OptionsError> {
+    value.as_f64().ok_or_else(|| invalid(format!("expected a number in {entry}")))
+}
+
+fn invalid(reason: impl Into<String>) -> OptionsError {
+    OptionsError::InvalidOpts { reason: reason.into() }
+}
+
+pub(crate) fn render(options: &DiffOptions) -> String {
+    let mut entries = Vec::new();
+    match (options.array_mode(), options.set_keys()) {
+        (ArrayMode::List, None) => {}
+        (ArrayMode::List, Some(keys)) => {
+            entries.extend([json!({ "setkeys": keys }), array_mode_name(ArrayMode::List)]);
+        }
+        (ArrayMode::Set, Some(keys)) => entries.push(json!({ "setkeys": keys })),
+        (mode, _) => entries.push(array_mode_name(mode)),
+    }
+    if options.precision() > 0.0 {
+        entries.push(json!({ "precision": options.precision() }));
+    }
+    if options.relative_precision() > 0.0 {
+        entries.push(json!({ "relative_precision": options.relative_precision() }));
+    }
+    if options.max_ulps() > 0 {
+        entries.push(json!({ "ulps": options.max_ulps() }));
+    }
+    if let Some(seconds) = options.timestamp_tolerance() {
+        entries.push(json!({ "timestamp_tolerance": seconds }));
+    }
+    if options.prune_empty() {
+        entries.push(json!("PRUNE_EMPTY"));
+    }
+    if options.string_scalars() {
+        entries.push(json!("STRING_SCALARS"));
+    }
+    if options.merge() {
+        entries.push(json!("MERGE"));
+    }
+    if options.diff_off() {
+        entries.push(json!("DIFF_OFF"));
+    }
+    if options.base64() {
+        entries.push(json!("BASE64"));
+    }
+    for option in options.path_options() {
+        let at: Vec<JsonValue> = option
+            .at()
+            .iter()
+            .map(|matcher| match matcher {
+                PathMatcher::Key(key) => json!(key),
+                PathMatcher::Index(index) => json!(index),
+                PathMatcher::AnyIndex => json!([]),
+                PathMatcher::AnyKey => json!({}),
+            })
+            .collect();
+        let settings: Vec<JsonValue> = option
+            .settings()
+            .iter()
+            .map(|setting| match setting {
+                PathSetting::ArrayMode(mode) => array_mode_name(*mode),
+                PathSetting::SetKeys(keys) => json!({ "setkeys": keys }),
+                PathSetting::Precision(precision) => json!({ "precision": precision }),
+                PathSetting::TimestampTolerance(seconds) => {
+                    json!({ "timestamp_tolerance": seconds })
+                }
+                PathSetting::DiffOff => json!("DIFF_OFF"),
+                PathSetting::DiffOn => json!("DIFF_ON"),
+                PathSetting::Base64 => json!("BASE64"),
+            })
+            .collect();
+        entries.push(json!({ "@": at, "^": settings }));
+    }
+    JsonValue::Array(entries).to_string()
+}
+
+fn array_mode_name(mode: ArrayMode) -> JsonValue {
+    json!(match mode {
+        ArrayMode::List => "LIST",
+        ArrayMode::Set => "SET",
+        ArrayMode::MultiSet => "MULTISET",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn go_options_parse() {
+        let options =
+            parse(r#"["MERGE","COLOR",{"@":["foo",[]],"^":[{"setkeys":["id"]},"LIST"]}]"#).unwrap();
+        assert!(options.merge());
+        assert_eq!(
+            options.path_options(),
+            [PathOption::new([PathMatcher::key("foo"), PathMatcher::AnyIndex])
+                .with_setting(PathSetting::SetKeys(vec!["id".to_string()]))
+                .with_setting(PathSetting::ArrayMode(ArrayMode::List))]
+        );
+
+        let from_query = parse(r#"[{"@":"$.foo[*]","^":[{"setkeys":["id"]},"LIST"]}]"#).unwrap();
+        assert_eq!(from_query.path_options(), options.path_options());
+
+        let options = parse(r#"[{"setkeys":["b","a"]},"LIST"]"#).unwrap();
+        assert_eq!(options.set_keys().unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn set_modes_are_refused() {
+        for (input, mode) in [
+            (r#"["SET"]"#, ArrayMode::Set),
+            (r#"["MULTISET"]"#, ArrayMode::MultiSet),
+            (r#"[{"setkeys":["id"]}]"#, ArrayMode::Set),
+            (r#"[{"@":["a"],"^":["SET"]}]"#, ArrayMode::Set),
+            (r#"[{"@":["a"],"^":[{"setkeys":["id"]}]}]"#, ArrayMode::Set),
+        ] {
+            assert_eq!(parse(input).unwrap_err(), OptionsError::UnsupportedArrayMode(mode));
+        }
+    }
+
+    #[test]
+    fn rendered_options_parse_back() {
+        let input = concat!(
+            r#"[{"precision":0.5},{"relative_precision":0.001},{"ulps":4},{"timestamp_tolerance":1.0},"#,
+            r#""PRUNE_EMPTY","STRING_SCALARS","MERGE","#,
+            r#""BASE64",{"@":[{},2],"^":["DIFF_OFF"]},"#,
+            r#"{"@":[{},2,"id"],"^":["DIFF_ON",{"precision":0.0}]},{"@":["data",{}],"^":["BASE64"]},"#,
+            r#"{"@":["at"],"^":[{"timestamp_tolerance":0.5}]}]"#
+        );
+        let rendered = render(&parse(input).unwrap());
+        assert_eq!(rendered, input);
+        assert_eq!(render(&parse(&rendered).unwrap()), rendered);
+        let multiset = DiffOptions::default().with_array_mode(ArrayMode::MultiSet).unwrap();
+        assert_eq!(render(&multiset), r#"["MULTISET"]"#);
+        let anchored = parse(r#"[{"setkeys":["id"]},"LIST"]"#).unwrap();
+        assert_eq!(anchored.array_mode(), ArrayMode::List);
+        assert_eq!(render(&anchored), r#"[{"setkeys":["id"]},"LIST"]"#);
+        assert_eq!(render(&DiffOptions::default()), "[]");
+    }
+
+    #[test]
+    fn malformed_options_are_rejected() {
+        let reason = |input: &str| match parse(input).unwrap_err() {
+            OptionsError::InvalidOpts { reason } => reason,
+            other => panic!("unexpected error {other:?}"),
+        };
+        assert_eq!(reason(r#"{"precision":1}"#), "expected a JSON array");
+        assert_eq!(reason(r#"["SETS"]"#), r#"unsupported option "SETS""#);
+        assert_eq!(reason(r#"[{"@":["a"]}]"#), r#"missing settings in {"@":["a"]}"#);
+        assert_eq!(
+            reason(r#"[{"@":[true],"^":[]}]"#),
+            r#"unsupported path element true in {"@":[true],"^":[]}"#
+        );
+        assert_eq!(
+            reason(r#"[{"@":"$..a","^":[]}]"#),
+            "$..a cannot be a path option location: `..` is not supported"
+        );
+        assert_eq!(
+            parse(r#"["SET",{"precision":1}]"#).unwrap_err(),
+            OptionsError::PrecisionIncompatible
+        );
+    }
+}
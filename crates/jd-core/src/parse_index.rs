@@ -0,0 +1,307 @@
+//! Source spans of parsed JSON values.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::diff::{Path, PathSegment};
+use crate::Location;
+
+/// Where every value of a parsed JSON document appears in its text.
+///
+/// Built by [`Node::from_json_str_indexed`](crate::Node::from_json_str_indexed)
+/// alongside the node, as a side table keyed by [`Path`], so editors and
+/// error messages can point from a diff hunk back to the source. When an
+/// object repeats a key, the last occurrence is indexed, matching the value
+/// the parser keeps.
+///
+/// ```
+/// # use jd_core::{Node, Path, PathSegment};
+/// let input = "{\n  \"a\": [1, \"two\"]\n}";
+/// let (_, index) = Node::from_json_str_indexed(input).unwrap();
+/// let path = Path::from(vec![PathSegment::key("a"), PathSegment::index(1)]);
+/// let span = index.span_of(&path).unwrap();
+/// assert_eq!(&input[span.start().offset()..span.end().offset()], "\"two\"");
+/// assert_eq!((span.start().line(), span.start().column()), (2, 12));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseIndex {
+    spans: HashMap<Path, SourceSpan>,
+    /// Byte offset of the opening quote of each object member's key.
+    keys: HashMap<Path, usize>,
+}
+
+impl ParseIndex {
+    /// Returns the span of the value at `path`, or `None` if the document
+    /// has no value there.
+    ///
+    /// ```
+    /// # use jd_core::{Node, Path, PathSegment};
+    /// let (_, index) = Node::from_json_str_indexed("[true]").unwrap();
+    /// assert_eq!(index.span_of(&Path::new()).unwrap().end().offset(), 6);
+    /// assert!(index.span_of(&Path::from(PathSegment::index(1))).is_none());
+    /// ```
+    #[must_use]
+    pub fn span_of(&self, path: &Path) -> Option<&SourceSpan> {
+        self.spans.get(path)
+    }
+
+    /// Number of indexed values.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// let (_, index) = Node::from_json_str_indexed(r#"{"a": [1, 2]}"#).unwrap();
+    /// assert_eq!(index.len(), 4);
+    /// ```
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Reports whether no value was indexed, as for blank input.
+    ///
+    /// ```
+    /// # use jd_core::Node;
+    /// assert!(Node::from_json_str_indexed(" ").unwrap().1.is_empty());
+    /// ```
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Byte offset of the key of the object member at `path`.
+    pub(crate) fn key_offset(&self, path: &Path) -> Option<usize> {
+        self.keys.get(path).copied()
+    }
+
+    /// Indexes valid JSON `input` in one pass over its characters.
+    pub(crate) fn build(input: &str) -> Self {
+        let mut spans = HashMap::new();
+        let mut keys = HashMap::new();
+        let mut scanner =
+            Scanner { input, chars: input.char_indices().peekable(), line: 1, column: 1 };
+        let mut stack: Vec<Frame> = Vec::new();
+        while let Some(ch) = scanner.peek() {
+            match ch {
+                '{' | '[' => {
+                    let path = value_path(&stack);
+                    shadow(&mut spans, &path);
+                    let start = scanner.here();
+                    scanner.bump();
+                    let kind = if ch == '{' {
+                        Container::Object { key: None, expect_key: true }
+                    } else {
+                        Container::Array { index: 0 }
+                    };
+                    stack.push(Frame { path, start, kind });
+                }
+                '}' | ']' => {
+                    scanner.bump();
+                    if let Some(frame) = stack.pop() {
+                        spans.insert(
+                            frame.path,
+                            SourceSpan { start: frame.start, end: scanner.here() },
+                        );
+                    }
+                }
+                ',' => {
+                    scanner.bump();
+                    match stack.last_mut().map(|frame| &mut frame.kind) {
+                        Some(Container::Object { key, expect_key }) => {
+                            *key = None;
+                            *expect_key = true;
+                        }
+                        Some(Container::Array { index }) => *index += 1,
+                        None => {}
+                    }
+                }
+                ':' => {
+                    scanner.bump();
+                    if let Some(Container::Object { expect_key, .. }) =
+                        stack.last_mut().map(|frame| &mut frame.kind)
+                    {
+                        *expect_key = false;
+                    }
+                }
+                ' ' | '\t' | '\n' | '\r' => scanner.bump(),
+                '"' => {
+                    let start = scanner.here();
+                    scanner.bump();
+                    while let Some(ch) = scanner.peek() {
+                        scanner.bump();
+                        match ch {
+                            '\\' => scanner.bump(),
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                    let end = scanner.here();
+                    match stack.last_mut() {
+                        Some(Frame {
+                            path,
+                            kind: Container::Object { key, expect_key: true },
+                            ..
+                        }) => {
+                            *key = serde_json::from_str(&input[start.offset()..end.offset()]).ok();
+                            let name = key.clone().unwrap_or_default();
+                            keys.insert(
+                                path.clone().with_segment(PathSegment::Key(name)),
+                                start.offset(),
+                            );
+                        }
+                        _ => {
+                            let path = value_path(&stack);
+                            shadow(&mut spans, &path);
+                            spans.insert(path, SourceSpan { start, end });
+                        }
+                    }
+                }
+                _ => {
+                    let start = scanner.here();
+                    while scanner
+                        .peek()
+                        .is_some_and(|ch| !matches!(ch, ',' | ']' | '}' | ' ' | '\t' | '\n' | '\r'))
+                    {
+                        scanner.bump();
+                    }
+                    let path = value_path(&stack);
+                    shadow(&mut spans, &path);
+                    spans.insert(path, SourceSpan { start, end: scanner.here() });
+                }
+            }
+        }
+        Self { spans, keys }
+    }
+}
+
+/// The text of one value, from its first character to just past its last.
+///
+/// ```
+/// # use jd_core::{Node, Path, PathSegment};
+/// let (_, index) = Node::from_json_str_indexed("{\"a\": {\n}}").unwrap();
+/// let span = index.span_of(&Path::from(PathSegment::key("a"))).unwrap();
+/// assert_eq!((span.start().line(), span.start().column()), (1, 7));
+/// assert_eq!((span.end().line(), span.end().column()), (2, 2));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceSpan {
+    start: Location,
+    end: Location,
+}
+
+impl SourceSpan {
+    /// Location of the value's first character.
+    #[must_use]
+    pub fn start(&self) -> &Location {
+        &self.start
+    }
+
+    /// Location just past the value's last character.
+    #[must_use]
+    pub fn end(&self) -> &Location {
+        &self.end
+    }
+}
+
+struct Frame {
+    path: Path,
+    start: Location,
+    kind: Container,
+}
+
+enum Container {
+    Object { key: Option<String>, expect_key: bool },
+    Array { index: i64 },
+}
+
+/// Forgets an earlier value at `path`, and everything in it, when a
+/// repeated object key replaces it.
+fn shadow(spans: &mut HashMap<Path, SourceSpan>, path: &Path) {
+    if spans.contains_key(path) {
+        spans.retain(|indexed, _| !indexed.segments().starts_with(path.segments()));
+    }
+}
+
+/// Path of the value starting at the scanner's position.
+fn value_path(stack: &[Frame]) -> Path {
+    let Some(frame) = stack.last() else {
+        return Path::new();
+    };
+    let segment = match &frame.kind {
+        Container::Object { key, .. } => PathSegment::Key(key.clone().unwrap_or_default()),
+        Container::Array { index } => PathSegment::Index(*index),
+    };
+    frame.path.clone().with_segment(segment)
+}
+
+/// Walks the input tracking the line and column of the next character.
+struct Scanner<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, ch)| ch)
+    }
+
+    fn bump(&mut self) {
+        match self.chars.next() {
+            Some((_, '\n')) => {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => {}
+        }
+    }
+
+    fn here(&mut self) -> Location {
+        let offset = self.chars.peek().map_or(self.input.len(), |&(offset, _)| offset);
+        Location::new(offset, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text<'a>(input: &'a str, index: &ParseIndex, path: Vec<PathSegment>) -> &'a str {
+        let span = index.span_of(&Path::from(path)).expect("indexed");
+        &input[span.start().offset()..span.end().offset()]
+    }
+
+    #[test]
+    fn indexes_every_value_with_escapes_and_duplicates() {
+        let input = r#" {"a": [1, {"b\"": "x,]"}, [ ], -2.5e3], "a": {"c": null}, "é": "ü"} "#;
+        let index = ParseIndex::build(input);
+        assert_eq!(text(input, &index, vec![]), input.trim());
+        assert_eq!(text(input, &index, vec![PathSegment::key("a")]), r#"{"c": null}"#);
+        assert_eq!(text(input, &index, vec![PathSegment::key("a"), PathSegment::key("c")]), "null");
+        assert_eq!(text(input, &index, vec![PathSegment::key("é")]), "\"ü\"");
+        // The first `a` and its elements are shadowed by the second.
+        assert!(index
+            .span_of(&Path::from(vec![PathSegment::key("a"), PathSegment::index(1)]))
+            .is_none());
+        assert_eq!(index.len(), 4);
+
+        let input = r#"[1, {"b\"": "x,]"}, [ ], -2.5e3]"#;
+        let index = ParseIndex::build(input);
+        let b = vec![PathSegment::index(1), PathSegment::key("b\"")];
+        assert_eq!(text(input, &index, b), r#""x,]""#);
+        assert_eq!(text(input, &index, vec![PathSegment::index(2)]), "[ ]");
+        assert_eq!(text(input, &index, vec![PathSegment::index(3)]), "-2.5e3");
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let input = "[\"é\",\n 1]";
+        let index = ParseIndex::build(input);
+        let span = index.span_of(&Path::from(PathSegment::index(0))).unwrap();
+        assert_eq!((span.end().column(), span.end().offset()), (5, 5));
+        let span = index.span_of(&Path::from(PathSegment::index(1))).unwrap();
+        assert_eq!((span.start().line(), span.start().column()), (2, 2));
+    }
+}
@@ -2,14 +2,14 @@
 //!
 //! The implementation follows the semantics of the upstream Go version by
 //! interpreting `DiffElement` metadata, enforcing list context validation, and
-//! recursing through objects and arrays using strict or merge strategies.
+//! descending through objects and arrays using strict or merge strategies.
 
 use std::collections::BTreeMap;
 use std::fmt;
 
 use crate::{
     diff::{Path, PathSegment},
-    Diff, DiffMetadata, Node,
+    ArrayMode, Diff, DiffElement, DiffMetadata, DiffOptions, Node,
 };
 
 /// Errors that can occur while applying a diff.
@@ -41,6 +41,124 @@ impl fmt::Display for PatchError {
 
 impl std::error::Error for PatchError {}
 
+/// A record of a stream that a diff could not be applied to.
+///
+/// ```
+/// # use jd_core::{DiffOptions, Node};
+/// let diff = Node::from_json_str(r#"{"a":1}"#)
+///     .unwrap()
+///     .diff(&Node::from_json_str(r#"{"a":2}"#).unwrap(), &DiffOptions::default());
+/// let mut stream = diff.apply_to_each([Node::from_json_str(r#"{"a":3}"#).unwrap()]);
+/// assert_eq!(stream.next(), None);
+/// let failure = &stream.failures()[0];
+/// assert_eq!(failure.record(), 0);
+/// assert_eq!(failure.to_string(), "record 0: found 3 at [a]: expected 1");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordFailure {
+    record: usize,
+    error: PatchError,
+}
+
+impl RecordFailure {
+    /// Zero-based position of the record in the stream.
+    #[must_use]
+    pub fn record(&self) -> usize {
+        self.record
+    }
+
+    /// Why the diff did not apply.
+    #[must_use]
+    pub fn error(&self) -> &PatchError {
+        &self.error
+    }
+}
+
+impl fmt::Display for RecordFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record {}: {}", self.record, self.error)
+    }
+}
+
+impl std::error::Error for RecordFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Iterator returned by [`Diff::apply_to_each`].
+///
+/// Yields the patched records in order and skips the ones the diff does not
+/// apply to, keeping their errors in [`PatchStream::failures`].
+#[derive(Debug)]
+pub struct PatchStream<'a, I> {
+    diff: &'a Diff,
+    records: I,
+    position: usize,
+    failures: Vec<RecordFailure>,
+}
+
+impl<I> PatchStream<'_, I> {
+    /// Records skipped so far.
+    #[must_use]
+    pub fn failures(&self) -> &[RecordFailure] {
+        &self.failures
+    }
+
+    /// Consumes the stream, returning the records skipped so far.
+    #[must_use]
+    pub fn into_failures(self) -> Vec<RecordFailure> {
+        self.failures
+    }
+}
+
+impl<I: Iterator<Item = Node>> Iterator for PatchStream<'_, I> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        for record in self.records.by_ref() {
+            let position = self.position;
+            self.position += 1;
+            match apply_patch(&record, self.diff) {
+                Ok(patched) => return Some(patched),
+                Err(error) => self.failures.push(RecordFailure { record: position, error }),
+            }
+        }
+        None
+    }
+}
+
+impl Diff {
+    /// Applies this diff to every record of `records`, such as the lines of
+    /// an NDJSON file, without collecting them first.
+    ///
+    /// The returned iterator yields the patched records; records the diff
+    /// does not apply to are skipped and reported by
+    /// [`PatchStream::failures`], so one bad record does not stop a
+    /// migration. Records the diff removes entirely come out as
+    /// [`Node::Void`].
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let diff = Node::from_json_str(r#"{"v":1}"#)
+    ///     .unwrap()
+    ///     .diff(&Node::from_json_str(r#"{"v":2}"#).unwrap(), &DiffOptions::default());
+    /// let records = ["{\"v\":1,\"id\":1}", "{\"v\":2,\"id\":2}", "{\"v\":1,\"id\":3}"]
+    ///     .map(|line| Node::from_json_str(line).unwrap());
+    ///
+    /// let mut stream = diff.apply_to_each(records);
+    /// let ids: Vec<_> = stream.by_ref().map(|record| record.to_json_value().unwrap()["id"].clone()).collect();
+    /// assert_eq!(ids, [1, 3]);
+    /// assert_eq!(stream.failures()[0].record(), 1);
+    /// ```
+    pub fn apply_to_each<I>(&self, records: I) -> PatchStream<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = Node>,
+    {
+        PatchStream { diff: self, records: records.into_iter(), position: 0, failures: Vec::new() }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum PatchStrategy {
     Strict,
@@ -67,18 +185,31 @@ impl fmt::Display for PatchStrategy {
 }
 
 pub(crate) fn apply_patch(node: &Node, diff: &Diff) -> Result<Node, PatchError> {
-    let mut current = node.clone();
+    let _span = tracing::info_span!("patch", hunks = diff.len()).entered();
+    let elements = diff.iter().as_slice();
     let mut inherited_metadata: Option<DiffMetadata> = None;
-    for element in diff.iter() {
-        if let Some(meta) = element.metadata.as_ref().filter(|metadata| metadata.is_effective()) {
-            if let Some(existing) = inherited_metadata.as_mut() {
-                existing.absorb(meta);
-            } else {
-                inherited_metadata = Some(meta.clone());
+    let strategies: Vec<_> = elements
+        .iter()
+        .map(|element| {
+            if let Some(meta) = element.metadata.as_ref().filter(|metadata| metadata.is_effective())
+            {
+                if let Some(existing) = inherited_metadata.as_mut() {
+                    existing.absorb(meta);
+                } else {
+                    inherited_metadata = Some(meta.clone());
+                }
             }
-        }
-        let metadata = inherited_metadata.as_ref().filter(|metadata| metadata.is_effective());
-        let strategy = PatchStrategy::from_metadata(metadata);
+            let metadata = inherited_metadata.as_ref().filter(|metadata| metadata.is_effective());
+            PatchStrategy::from_metadata(metadata)
+        })
+        .collect();
+
+    let mut current = node.clone();
+    let mut start = 0;
+    while start < elements.len() {
+        let element = &elements[start];
+        let strategy = strategies[start];
+        let end = start + list_run_len(&elements[start..], &strategies[start..]);
         current = patch_element(
             current,
             Vec::new(),
@@ -88,11 +219,75 @@ pub(crate) fn apply_patch(node: &Node, diff: &Diff) -> Result<Node, PatchError>
             &element.add,
             &element.after,
             strategy,
+            &elements[start + 1..end],
         )?;
+        start = end;
     }
     Ok(current)
 }
 
+/// Counts the hunks at the start of `elements` that edit the same array by
+/// index under the strict strategy, which [`patch_list`] applies in one
+/// pass. Paths through set keys end a run, since an earlier hunk could
+/// change the keys a later one looks up.
+fn list_run_len(elements: &[DiffElement], strategies: &[PatchStrategy]) -> usize {
+    fn parent(element: &DiffElement) -> Option<&[PathSegment]> {
+        match element.path.segments() {
+            [parent @ .., PathSegment::Index(_)]
+                if parent.iter().all(|segment| {
+                    matches!(segment, PathSegment::Key(_) | PathSegment::Index(_))
+                }) =>
+            {
+                Some(parent)
+            }
+            _ => None,
+        }
+    }
+    let Some(first) = parent(&elements[0]).filter(|_| strategies[0] == PatchStrategy::Strict)
+    else {
+        return 1;
+    };
+    1 + elements[1..]
+        .iter()
+        .zip(&strategies[1..])
+        .take_while(|(element, strategy)| {
+            **strategy == PatchStrategy::Strict && parent(element) == Some(first)
+        })
+        .count()
+}
+
+/// Container taken apart on the way down to a patch target, reassembled with
+/// the patched child on the way back up.
+enum Frame {
+    /// Object entry at `key`. A void result removes the entry unless
+    /// `keep_void` is set, which mirrors Go's merge handling of
+    /// intermediate path segments.
+    Object { map: BTreeMap<String, Node>, key: String, keep_void: bool },
+    /// Array element at `index`.
+    Array { list: Vec<Node>, index: usize },
+}
+
+impl Frame {
+    fn reassemble(self, patched: Node) -> Node {
+        match self {
+            Self::Object { mut map, key, keep_void } => {
+                if keep_void || !is_void(&patched) {
+                    map.insert(key, patched);
+                }
+                Node::Object(map)
+            }
+            Self::Array { mut list, index } => {
+                list[index] = patched;
+                Node::Array(list)
+            }
+        }
+    }
+}
+
+/// Walks `path_ahead` with an explicit stack of [`Frame`]s instead of
+/// recursing per segment, so deeply nested documents cannot overflow the
+/// call stack. `following` holds the rest of a run of hunks on the same
+/// array (see [`list_run_len`]), applied by [`patch_list`] in the same pass.
 // Mirrors the Go implementation signature for parity with the CLI contract.
 #[allow(clippy::too_many_arguments)]
 fn patch_element(
@@ -104,62 +299,106 @@ fn patch_element(
     add: &[Node],
     after: &[Node],
     strategy: PatchStrategy,
+    following: &[DiffElement],
 ) -> Result<Node, PatchError> {
-    if !path_ahead.is_empty() && strategy == PatchStrategy::Merge {
-        let (segment, rest) = path_ahead.split_first().unwrap();
-        let PathSegment::Key(key) = segment else {
-            return Err(expected_collection_error(&node, segment));
-        };
+    let mut frames = Vec::new();
+    let (mut node, mut path_behind, mut path_ahead) = (node, path_behind, path_ahead);
 
-        match node {
-            Node::Object(mut map) => {
-                let existing = map.remove(key).unwrap_or_else(|| {
-                    if rest.is_empty() {
-                        Node::Void
-                    } else {
-                        Node::Object(BTreeMap::new())
-                    }
-                });
-                let mut new_path = path_behind.clone();
-                new_path.push(PathSegment::Key(key.clone()));
-                let patched =
-                    patch_element(existing, new_path, rest, before, remove, add, after, strategy)?;
-                if is_void(&patched) && rest.is_empty() {
-                    // Removal handled via map.remove above.
-                } else if !is_void(&patched) || !rest.is_empty() {
-                    map.insert(key.clone(), patched);
+    while let Some((segment, rest)) = path_ahead.split_first() {
+        if strategy == PatchStrategy::Merge {
+            let PathSegment::Key(key) = segment else {
+                return Err(expected_collection_error(&node, segment));
+            };
+            let seed = || if rest.is_empty() { Node::Void } else { Node::Object(BTreeMap::new()) };
+            let (map, child) = match node {
+                Node::Object(ref mut map) => {
+                    let mut map = std::mem::take(map);
+                    let child = map.remove(key).unwrap_or_else(seed);
+                    (map, child)
                 }
-                return Ok(Node::Object(map));
-            }
-            _other => {
-                let seed = if rest.is_empty() { Node::Void } else { Node::Object(BTreeMap::new()) };
-                let mut new_path = path_behind.clone();
-                new_path.push(PathSegment::Key(key.clone()));
-                let patched =
-                    patch_element(seed, new_path, rest, before, remove, add, after, strategy)?;
-                let mut map = BTreeMap::new();
-                if !is_void(&patched) || !rest.is_empty() {
-                    map.insert(key.clone(), patched);
+                _ => (BTreeMap::new(), seed()),
+            };
+            frames.push(Frame::Object { map, key: key.clone(), keep_void: !rest.is_empty() });
+            node = child;
+        } else {
+            match node {
+                Node::Object(ref mut map) => {
+                    let mut map = std::mem::take(map);
+                    let PathSegment::Key(key) = segment else {
+                        return Err(PatchError::new(format!(
+                            "found {} at {}: expected JSON object",
+                            node_json(&Node::Object(map)),
+                            path_to_string(&path_behind)
+                        )));
+                    };
+                    let child = map.remove(key).unwrap_or(Node::Void);
+                    frames.push(Frame::Object { map, key: key.clone(), keep_void: false });
+                    node = child;
+                }
+                Node::Array(ref mut list) if !rest.is_empty() => {
+                    let mut list = std::mem::take(list);
+                    let index = match segment {
+                        PathSegment::Index(raw_index) => usize::try_from(*raw_index)
+                            .ok()
+                            .filter(|&i| i < list.len())
+                            .ok_or_else(|| {
+                                PatchError::new(format!("patch index out of bounds: {raw_index}"))
+                            })?,
+                        PathSegment::SetKeys(keys) | PathSegment::MultiSetKeys(keys) => {
+                            set_keys_index(&list, keys).ok_or_else(|| {
+                                PatchError::new(format!(
+                                    "invalid patch. no object matching {segment} at {}",
+                                    path_to_string(&path_behind)
+                                ))
+                            })?
+                        }
+                        PathSegment::Set | PathSegment::MultiSet => {
+                            return Err(PatchError::new(format!(
+                                "invalid path. {segment} at {} must be the last path element",
+                                path_to_string(&path_behind)
+                            )));
+                        }
+                        PathSegment::Key(_) => return Err(invalid_path_element_error(segment)),
+                    };
+                    let child = std::mem::replace(&mut list[index], Node::Void);
+                    frames.push(Frame::Array { list, index });
+                    node = child;
                 }
-                return Ok(Node::Object(map));
+                // Scalars report the path error; list edits happen at the
+                // final index segment.
+                _ => break,
             }
         }
+        path_behind.push(segment.clone());
+        path_ahead = rest;
     }
 
-    match node {
-        Node::Array(values) => {
-            patch_list(values, path_behind, path_ahead, before, remove, add, after, strategy)
-        }
-        Node::Object(map) => {
-            patch_object(map, path_behind, path_ahead, before, remove, add, after, strategy)
+    let mut patched = match node {
+        Node::Array(ref mut values) => patch_list(
+            std::mem::take(values),
+            path_behind,
+            path_ahead,
+            before,
+            remove,
+            add,
+            after,
+            strategy,
+            following,
+        )?,
+        Node::Object(ref mut map) => {
+            patch_object(std::mem::take(map), path_behind, remove, add, strategy)?
         }
         other => {
             if let Some(segment) = path_ahead.first() {
                 return Err(expected_collection_error(&other, segment));
             }
-            patch_scalar(other, path_behind, path_ahead, before, remove, add, after, strategy)
+            patch_scalar(other, path_behind, path_ahead, before, remove, add, after, strategy)?
         }
+    };
+    while let Some(frame) = frames.pop() {
+        patched = frame.reassemble(patched);
     }
+    Ok(patched)
 }
 
 // Mirrors the Go implementation signature for parity with the CLI contract.
@@ -203,67 +442,28 @@ fn patch_scalar(
     Ok(new_value)
 }
 
-// Mirrors the Go implementation signature for parity with the CLI contract.
-#[allow(clippy::too_many_arguments)]
+/// Replaces a whole object; descending into its keys happens in
+/// [`patch_element`].
 fn patch_object(
-    mut map: BTreeMap<String, Node>,
+    map: BTreeMap<String, Node>,
     path_behind: Vec<PathSegment>,
-    path_ahead: &[PathSegment],
-    _before: &[Node],
     old_values: &[Node],
     new_values: &[Node],
-    _after: &[Node],
     strategy: PatchStrategy,
 ) -> Result<Node, PatchError> {
-    if path_ahead.is_empty() {
-        if old_values.len() > 1 || new_values.len() > 1 {
-            return Err(non_set_diff_error(old_values, new_values, &path_behind));
-        }
-        let new_value = single_value(new_values);
-        if strategy == PatchStrategy::Merge {
-            return Ok(new_value);
-        }
-        let old_value = single_value(old_values);
-        if !node_equals(&Node::Object(map.clone()), &old_value) {
-            return Err(expect_value_error(&old_value, &Node::Object(map), &path_behind));
-        }
-        return Ok(new_value);
+    if old_values.len() > 1 || new_values.len() > 1 {
+        return Err(non_set_diff_error(old_values, new_values, &path_behind));
     }
-
-    let (segment, rest) = path_ahead.split_first().unwrap();
-    let PathSegment::Key(key) = segment else {
-        return Err(PatchError::new(format!(
-            "found {} at {}: expected JSON object",
-            node_json(&Node::Object(map.clone())),
-            path_to_string(&path_behind)
-        )));
-    };
-
-    let mut next = map.get(key).cloned();
-    if next.is_none() {
-        next = Some(match strategy {
-            PatchStrategy::Merge => {
-                if rest.is_empty() {
-                    Node::Void
-                } else {
-                    Node::Object(BTreeMap::new())
-                }
-            }
-            PatchStrategy::Strict => Node::Void,
-        });
+    let new_value = single_value(new_values);
+    if strategy == PatchStrategy::Merge {
+        return Ok(new_value);
     }
-
-    let mut new_path = path_behind.clone();
-    new_path.push(PathSegment::Key(key.clone()));
-    let patched =
-        patch_element(next.unwrap(), new_path, rest, &[], old_values, new_values, &[], strategy)?;
-
-    if is_void(&patched) {
-        map.remove(key);
-    } else {
-        map.insert(key.clone(), patched);
+    let old_value = single_value(old_values);
+    let current = Node::Object(map);
+    if !node_equals(&current, &old_value) {
+        return Err(expect_value_error(&old_value, &current, &path_behind));
     }
-    Ok(Node::Object(map))
+    Ok(new_value)
 }
 
 // Mirrors the Go implementation signature for parity with the CLI contract.
@@ -277,6 +477,7 @@ fn patch_list(
     add: &[Node],
     after: &[Node],
     strategy: PatchStrategy,
+    following: &[DiffElement],
 ) -> Result<Node, PatchError> {
     if strategy == PatchStrategy::Merge {
         return patch_scalar(
@@ -313,111 +514,221 @@ fn patch_list(
         return Ok(add[0].clone());
     }
 
-    let (segment, rest) = path_ahead.split_first().unwrap();
-    let PathSegment::Index(raw_index) = segment else {
-        return Err(invalid_path_element_error(segment));
+    // Deeper paths were resolved by `patch_element`; only the target index
+    // or set marker remains.
+    let segment = &path_ahead[0];
+    let raw_index = match segment {
+        PathSegment::Index(raw_index) => raw_index,
+        PathSegment::Set => return patch_set(list, &path_behind, segment, remove, add, false),
+        PathSegment::MultiSet => return patch_set(list, &path_behind, segment, remove, add, true),
+        PathSegment::SetKeys(_) | PathSegment::MultiSetKeys(_) => {
+            return Err(PatchError::new(format!(
+                "invalid path. {segment} at {} must be followed by a path into the object",
+                path_to_string(&path_behind)
+            )));
+        }
+        PathSegment::Key(_) => return Err(invalid_path_element_error(segment)),
     };
 
-    if !rest.is_empty() {
-        if *raw_index < 0 || (*raw_index as usize) >= list.len() {
-            return Err(PatchError::new(format!("patch index out of bounds: {raw_index}")));
-        }
-        let mut new_path = path_behind.clone();
-        new_path.push(PathSegment::Index(*raw_index));
-        let mut list_clone = list.clone();
-        let child = list_clone[*raw_index as usize].clone();
-        let patched = patch_element(child, new_path, rest, &[], remove, add, &[], strategy)?;
-        list_clone[*raw_index as usize] = patched;
-        return Ok(Node::Array(list_clone));
+    let mut splice = Splice::new(list);
+    splice.apply(*raw_index, before, remove, add, after)?;
+    for element in following {
+        let Some(PathSegment::Index(raw_index)) = element.path.segments().last() else {
+            unreachable!("runs only hold hunks ending in an index");
+        };
+        splice.apply(*raw_index, &element.before, &element.remove, &element.add, &element.after)?;
     }
+    Ok(Node::Array(splice.finish()))
+}
 
-    if *raw_index == -1 {
-        if !remove.is_empty() {
-            return Err(PatchError::new(
-                "invalid patch. appending to -1 index. but want to remove values",
-            ));
-        }
-        let mut list_clone = list.clone();
-        list_clone.extend(add.iter().cloned());
-        return Ok(Node::Array(list_clone));
+/// An array rebuilt by a run of hunks in one pass. Values the hunks have
+/// moved past are in `done`, followed by the untouched `rest`; together
+/// they are the array as patched so far, which hunk indices refer to.
+struct Splice {
+    done: Vec<Node>,
+    rest: std::vec::IntoIter<Node>,
+}
+
+impl Splice {
+    fn new(list: Vec<Node>) -> Self {
+        Self { done: Vec::with_capacity(list.len()), rest: list.into_iter() }
     }
 
-    if *raw_index < 0 {
-        return Err(PatchError::new(format!("patch index out of bounds: {raw_index}")));
+    fn len(&self) -> usize {
+        self.done.len() + self.rest.len()
     }
 
-    let insertion_index = *raw_index as usize;
-    let original = list.clone();
+    fn get(&self, index: usize) -> Option<&Node> {
+        self.done.get(index).or_else(|| self.rest.as_slice().get(index - self.done.len()))
+    }
+
+    /// Moves values to `done` until it holds the first `index` of them, or
+    /// all of them. A hunk before the previous one starts the pass over.
+    fn seek(&mut self, index: usize) {
+        if index < self.done.len() {
+            self.done.extend(self.rest.by_ref());
+            let list = std::mem::take(&mut self.done);
+            self.done.reserve(list.len());
+            self.rest = list.into_iter();
+        }
+        let count = (index - self.done.len()).min(self.rest.len());
+        self.done.extend(self.rest.by_ref().take(count));
+    }
 
-    for (offset, context) in before.iter().enumerate() {
-        let distance = before.len() - offset;
-        let check_index = (*raw_index as isize) - (distance as isize);
-        if check_index < 0 {
-            if check_index == -1 && is_void(context) {
-                continue;
+    /// Checks a hunk's context and removals against the array and applies
+    /// it, with the same errors as applying it on its own.
+    fn apply(
+        &mut self,
+        raw_index: i64,
+        before: &[Node],
+        remove: &[Node],
+        add: &[Node],
+        after: &[Node],
+    ) -> Result<(), PatchError> {
+        if raw_index == -1 {
+            if !remove.is_empty() {
+                return Err(PatchError::new(
+                    "invalid patch. appending to -1 index. but want to remove values",
+                ));
             }
-            return Err(PatchError::new(format!(
-                "invalid patch. before context {} out of bounds: {check_index}",
-                node_json(context)
-            )));
+            self.seek(self.len());
+            self.done.extend(add.iter().cloned());
+            return Ok(());
         }
-        let check_index = check_index as usize;
-        if !node_equals(&original[check_index], context) {
-            return Err(PatchError::new(format!(
-                "invalid patch. expected {} before. got {}",
-                node_json(context),
-                node_json(&original[check_index])
-            )));
+
+        if raw_index < 0 {
+            return Err(PatchError::new(format!("patch index out of bounds: {raw_index}")));
+        }
+
+        let insertion_index = raw_index as usize;
+        self.seek(insertion_index);
+
+        for (offset, context) in before.iter().enumerate() {
+            let distance = before.len() - offset;
+            let check_index = (raw_index as isize) - (distance as isize);
+            if check_index < 0 {
+                if check_index == -1 && is_void(context) {
+                    continue;
+                }
+                return Err(PatchError::new(format!(
+                    "invalid patch. before context {} out of bounds: {check_index}",
+                    node_json(context)
+                )));
+            }
+            let Some(found) = self.get(check_index as usize) else {
+                return Err(PatchError::new(format!(
+                    "invalid patch. before context {} out of bounds: {check_index}",
+                    node_json(context)
+                )));
+            };
+            if !node_equals(found, context) {
+                return Err(PatchError::new(format!(
+                    "invalid patch. expected {} before. got {}",
+                    node_json(context),
+                    node_json(found)
+                )));
+            }
+        }
+
+        if !remove.is_empty() {
+            if insertion_index >= self.len() {
+                return Err(PatchError::new(format!("remove values out bounds: {raw_index}")));
+            }
+            for (offset, expected) in remove.iter().enumerate() {
+                let Some(found) = self.get(insertion_index + offset) else {
+                    return Err(PatchError::new(format!("remove values out bounds: {raw_index}")));
+                };
+                if !node_equals(found, expected) {
+                    return Err(PatchError::new(format!(
+                        "invalid patch. wanted {}. found {}",
+                        node_json(expected),
+                        node_json(found)
+                    )));
+                }
+            }
         }
-    }
 
-    let mut working = original.clone();
-    if !remove.is_empty() {
-        if insertion_index >= working.len() {
+        if insertion_index + remove.len() > self.len() {
             return Err(PatchError::new(format!("remove values out bounds: {raw_index}")));
         }
-        for expected in remove {
-            if !node_equals(&working[insertion_index], expected) {
+        // `done` now ends at the hunk, so the values after the removed ones,
+        // which the after context describes, start `remove.len()` into `rest`.
+        let rest = &self.rest.as_slice()[remove.len()..];
+        for (offset, context) in after.iter().enumerate() {
+            let check_index = insertion_index + offset;
+            let Some(found) = rest.get(offset) else {
+                if offset == rest.len() && is_void(context) {
+                    continue;
+                }
                 return Err(PatchError::new(format!(
-                    "invalid patch. wanted {}. found {}",
-                    node_json(expected),
-                    node_json(&working[insertion_index])
+                    "invalid patch. after context {} out of bounds: {check_index}",
+                    node_json(context)
+                )));
+            };
+            if !node_equals(found, context) {
+                return Err(PatchError::new(format!(
+                    "invalid patch. expected {} after. got {}",
+                    node_json(context),
+                    node_json(found)
                 )));
             }
-            working.remove(insertion_index);
         }
-    }
 
-    if insertion_index > working.len() {
-        return Err(PatchError::new(format!("remove values out bounds: {raw_index}")));
+        self.rest.by_ref().take(remove.len()).for_each(drop);
+        self.done.extend(add.iter().cloned());
+        Ok(())
     }
 
-    let mut result = Vec::with_capacity(working.len() + add.len());
-    result.extend(working.iter().take(insertion_index).cloned());
-    result.extend(add.iter().cloned());
-    result.extend(working.iter().skip(insertion_index).cloned());
+    fn finish(mut self) -> Vec<Node> {
+        self.done.extend(self.rest);
+        self.done
+    }
+}
 
-    for (offset, context) in after.iter().enumerate() {
-        let check_index = insertion_index + offset;
-        if check_index >= working.len() {
-            if check_index == working.len() && is_void(context) {
-                continue;
-            }
-            return Err(PatchError::new(format!(
-                "invalid patch. after context {} out of bounds: {check_index}",
-                node_json(context)
-            )));
+/// Removes `remove` from and adds `add` to an array patched as a set or, with
+/// `multiset`, as a multiset. Members are matched by value under the array's
+/// own mode, the way Go `jd` matches them by hash; added members go last.
+fn patch_set(
+    mut list: Vec<Node>,
+    path_behind: &[PathSegment],
+    marker: &PathSegment,
+    remove: &[Node],
+    add: &[Node],
+    multiset: bool,
+) -> Result<Node, PatchError> {
+    let mode = if multiset { ArrayMode::MultiSet } else { ArrayMode::Set };
+    let options =
+        DiffOptions::default().with_array_mode(mode).expect("default options accept any mode");
+    let mut path = path_behind.to_vec();
+    path.push(marker.clone());
+    for value in remove {
+        let Some(index) = list.iter().position(|member| member.eq_with_options(value, &options))
+        else {
+            return Err(expect_value_error(value, &Node::Void, &path));
+        };
+        if multiset {
+            list.remove(index);
+        } else {
+            list.retain(|member| !member.eq_with_options(value, &options));
         }
-        if !node_equals(&working[check_index], context) {
-            return Err(PatchError::new(format!(
-                "invalid patch. expected {} after. got {}",
-                node_json(context),
-                node_json(&working[check_index])
-            )));
+    }
+    for value in add {
+        if multiset || !list.iter().any(|member| member.eq_with_options(value, &options)) {
+            list.push(value.clone());
         }
     }
+    Ok(Node::Array(list))
+}
 
-    Ok(Node::Array(result))
+/// Position of the first object whose members match every set key, with
+/// void standing for an absent member.
+fn set_keys_index(list: &[Node], keys: &BTreeMap<String, Node>) -> Option<usize> {
+    list.iter().position(|member| {
+        let Node::Object(map) = member else {
+            return false;
+        };
+        keys.iter().all(|(key, value)| map.get(key).unwrap_or(&Node::Void) == value)
+    })
 }
 
 fn non_set_diff_error(
@@ -449,7 +760,7 @@ fn expect_value_error(expected: &Node, found: &Node, path: &[PathSegment]) -> Pa
 fn expected_collection_error(node: &Node, segment: &PathSegment) -> PatchError {
     let expected = match segment {
         PathSegment::Key(_) => "JSON object",
-        PathSegment::Index(_) => "JSON array",
+        _ => "JSON array",
     };
     PatchError::new(format!("found {} at {segment}: expected {expected}", node_json(node)))
 }
@@ -458,6 +769,10 @@ fn invalid_path_element_error(segment: &PathSegment) -> PatchError {
     let type_name = match segment {
         PathSegment::Key(_) => "string",
         PathSegment::Index(_) => "float64",
+        PathSegment::Set => "jd.PathSet",
+        PathSegment::MultiSet => "jd.PathMultiset",
+        PathSegment::SetKeys(_) => "jd.PathSetKeys",
+        PathSegment::MultiSetKeys(_) => "jd.PathMultisetKeys",
     };
     PatchError::new(format!("invalid path element {type_name}: expected float64"))
 }
@@ -0,0 +1,273 @@
+//! Diff settings scoped to parts of a document.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ArrayMode, OptionsError};
+
+/// One segment of the location a [`PathOption`] applies to.
+///
+/// ```
+/// # use jd_core::PathMatcher;
+/// let at = [PathMatcher::key("spec"), PathMatcher::AnyIndex, PathMatcher::key("ports")];
+/// assert_eq!(at[1], PathMatcher::AnyIndex);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathMatcher {
+    /// An object member with this key.
+    Key(String),
+    /// The array element at this index.
+    Index(i64),
+    /// Any object member.
+    AnyKey,
+    /// Any array element.
+    AnyIndex,
+}
+
+impl PathMatcher {
+    /// Matches the object member `key`.
+    ///
+    /// ```
+    /// # use jd_core::PathMatcher;
+    /// assert_eq!(PathMatcher::key("id"), PathMatcher::Key("id".to_string()));
+    /// ```
+    #[must_use]
+    pub fn key(key: impl Into<String>) -> Self {
+        Self::Key(key.into())
+    }
+
+    pub(crate) fn matches_key(&self, key: &str) -> bool {
+        match self {
+            Self::Key(expected) => expected == key,
+            Self::AnyKey => true,
+            Self::Index(_) | Self::AnyIndex => false,
+        }
+    }
+
+    pub(crate) fn matches_index(&self, index: i64) -> bool {
+        match self {
+            Self::Index(expected) => *expected == index,
+            Self::AnyIndex => true,
+            Self::Key(_) | Self::AnyKey => false,
+        }
+    }
+}
+
+/// A diff setting applied by a [`PathOption`].
+///
+/// ```
+/// # use jd_core::{ArrayMode, PathSetting};
+/// let setting = PathSetting::ArrayMode(ArrayMode::MultiSet);
+/// assert_ne!(setting, PathSetting::Precision(0.5));
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum PathSetting {
+    /// Interpret arrays as lists, sets, or multisets.
+    ArrayMode(ArrayMode),
+    /// Identify set elements by these object keys (implies set mode). A
+    /// later `ArrayMode(ArrayMode::List)` keeps the keys as list alignment
+    /// anchors.
+    SetKeys(Vec<String>),
+    /// Maximum absolute difference for numbers to compare equal.
+    Precision(f64),
+    /// Maximum number of seconds between ISO-8601 date-times for them to
+    /// compare equal as instants; see [`DiffOptions::strings_equal`].
+    TimestampTolerance(f64),
+    /// Report no differences here, including added and removed members, like
+    /// Go's `DIFF_OFF`.
+    DiffOff,
+    /// Report differences again below a [`PathSetting::DiffOff`], like Go's
+    /// `DIFF_ON`.
+    DiffOn,
+    /// Strings here hold base64-encoded bytes: native rendering shows each
+    /// one that decodes by its size and digest instead of its text.
+    Base64,
+}
+
+/// Diff settings for the value at a path and everything below it, like the
+/// path options of Go `jd` v2.
+///
+/// Settings from several options accumulate in the order the options were
+/// added to [`DiffOptions::with_path_option`], on top of the global settings.
+///
+/// ```
+/// use jd_core::{DiffOptions, Node, PathMatcher, PathOption, PathSetting};
+///
+/// let option = PathOption::new([PathMatcher::key("price")]).with_setting(PathSetting::Precision(0.01));
+/// let opts = DiffOptions::default().with_path_option(option).unwrap();
+/// let lhs = Node::from_json_str(r#"{"price":9.99,"qty":1.001}"#).unwrap();
+/// let rhs = Node::from_json_str(r#"{"price":9.991,"qty":1.0}"#).unwrap();
+/// assert_eq!(lhs.diff(&rhs, &opts).render(&Default::default()), "@ [\"qty\"]\n- 1.001\n+ 1\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathOption {
+    at: Vec<PathMatcher>,
+    settings: Vec<PathSetting>,
+}
+
+impl PathOption {
+    /// Creates an option for the values at `at`, with no settings yet. An
+    /// empty path selects the whole document.
+    ///
+    /// ```
+    /// # use jd_core::{PathMatcher, PathOption};
+    /// let option = PathOption::new([PathMatcher::key("tags")]);
+    /// assert!(option.settings().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new(at: impl IntoIterator<Item = PathMatcher>) -> Self {
+        Self { at: at.into_iter().collect(), settings: Vec::new() }
+    }
+
+    /// Adds a setting.
+    ///
+    /// ```
+    /// # use jd_core::{ArrayMode, PathMatcher, PathOption, PathSetting};
+    /// let option = PathOption::new([PathMatcher::key("tags")])
+    ///     .with_setting(PathSetting::ArrayMode(ArrayMode::Set));
+    /// assert_eq!(option.settings().len(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_setting(mut self, setting: PathSetting) -> Self {
+        self.settings.push(setting);
+        self
+    }
+
+    /// The location this option applies to.
+    ///
+    /// ```
+    /// # use jd_core::{PathMatcher, PathOption};
+    /// let option = PathOption::new([PathMatcher::AnyKey]);
+    /// assert_eq!(option.at(), [PathMatcher::AnyKey]);
+    /// ```
+    #[must_use]
+    pub fn at(&self) -> &[PathMatcher] {
+        &self.at
+    }
+
+    /// The settings applied at [`PathOption::at`].
+    ///
+    /// ```
+    /// # use jd_core::{PathOption, PathSetting};
+    /// let option = PathOption::new([]).with_setting(PathSetting::Precision(0.1));
+    /// assert_eq!(option.settings(), [PathSetting::Precision(0.1)]);
+    /// ```
+    #[must_use]
+    pub fn settings(&self) -> &[PathSetting] {
+        &self.settings
+    }
+
+    /// Checks each setting in isolation.
+    pub(crate) fn validate(&self) -> Result<(), OptionsError> {
+        for setting in &self.settings {
+            match setting {
+                PathSetting::Precision(precision) if !precision.is_finite() || *precision < 0.0 => {
+                    return Err(OptionsError::InvalidTolerance);
+                }
+                PathSetting::TimestampTolerance(seconds)
+                    if !seconds.is_finite() || *seconds < 0.0 =>
+                {
+                    return Err(OptionsError::InvalidTolerance);
+                }
+                PathSetting::SetKeys(keys) if keys.is_empty() => {
+                    return Err(OptionsError::EmptySetKey);
+                }
+                PathSetting::SetKeys(keys) if keys.iter().any(|key| key.trim().is_empty()) => {
+                    return Err(OptionsError::EmptySetKey);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The same option one level further down, if `first` matches.
+    pub(crate) fn descend(&self, first: impl Fn(&PathMatcher) -> bool) -> Option<Descent<'_>> {
+        let (head, rest) = self.at.split_first()?;
+        if !first(head) {
+            return None;
+        }
+        Some(if rest.is_empty() {
+            Descent::Reached(&self.settings)
+        } else {
+            Descent::Pending(Self { at: rest.to_vec(), settings: self.settings.clone() })
+        })
+    }
+}
+
+/// Outcome of following a [`PathOption`] one segment down.
+pub(crate) enum Descent<'a> {
+    /// The option's path ends here; its settings apply from this value on.
+    Reached(&'a [PathSetting]),
+    /// The option applies further down.
+    Pending(PathOption),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, Node};
+
+    #[test]
+    fn settings_apply_below_the_path_only() {
+        let option = PathOption::new([PathMatcher::AnyKey, PathMatcher::Index(1)])
+            .with_setting(PathSetting::Precision(0.5));
+        let opts = DiffOptions::default().with_path_option(option).unwrap();
+        let lhs = Node::from_json_str(r#"{"a":[1,2,[3]],"b":[1,2]}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"a":[1.2,2.2,[3]],"b":[1,2.4]}"#).unwrap();
+        let paths: Vec<_> = lhs.diff(&rhs, &opts).iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["[a 0]"]);
+        assert!(!lhs.eq_with_options(&rhs, &opts));
+    }
+
+    #[test]
+    fn later_options_override_earlier_ones() {
+        let coarse = PathOption::new([]).with_setting(PathSetting::Precision(1.0));
+        let fine =
+            PathOption::new([PathMatcher::key("x")]).with_setting(PathSetting::Precision(0.0));
+        let opts = DiffOptions::default()
+            .with_path_option(coarse)
+            .and_then(|opts| opts.with_path_option(fine))
+            .unwrap();
+        assert!((opts.precision() - 1.0).abs() < f64::EPSILON);
+        let lhs = Node::from_json_str(r#"{"x":1,"y":1}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"x":1.5,"y":1.5}"#).unwrap();
+        assert_eq!(lhs.diff(&rhs, &opts).len(), 1);
+    }
+
+    #[test]
+    fn diff_off_hides_changes_until_diff_on() {
+        let meta = [PathMatcher::AnyIndex, PathMatcher::key("meta")];
+        let off = PathOption::new(meta.clone()).with_setting(PathSetting::DiffOff);
+        let on = PathOption::new(meta.into_iter().chain([PathMatcher::key("owner")]))
+            .with_setting(PathSetting::DiffOn);
+        let opts = DiffOptions::default()
+            .with_path_option(off)
+            .and_then(|opts| opts.with_path_option(on))
+            .unwrap();
+        let lhs = Node::from_json_str(r#"[{"meta":{"at":1,"owner":"a"}},{"meta":{}}]"#).unwrap();
+        let rhs = Node::from_json_str(r#"[{"meta":{"at":2,"owner":"b"}},{}]"#).unwrap();
+        let paths: Vec<_> = lhs.diff(&rhs, &opts).iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["[0 meta owner]"]);
+
+        let rhs =
+            Node::from_json_str(r#"[{"meta":{"owner":"a","new":true}},{"meta":[]}]"#).unwrap();
+        assert!(lhs.eq_with_options(&rhs, &opts));
+        assert!(lhs.diff(&rhs, &opts).is_empty());
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let option =
+            PathOption::new([PathMatcher::AnyIndex]).with_setting(PathSetting::Precision(-1.0));
+        assert_eq!(
+            DiffOptions::default().with_path_option(option).unwrap_err(),
+            OptionsError::InvalidTolerance
+        );
+        let option = PathOption::new([]).with_setting(PathSetting::SetKeys(Vec::new()));
+        assert_eq!(
+            DiffOptions::default().with_path_option(option).unwrap_err(),
+            OptionsError::EmptySetKey
+        );
+    }
+}
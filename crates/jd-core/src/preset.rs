@@ -0,0 +1,457 @@
+//! Option bundles for common document families.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ArrayMode, Node, PathMatcher, PathOption, PathSetting};
+
+/// A document family with known volatile fields.
+///
+/// A preset contributes two things. Its [`path_options`](Self::path_options)
+/// stop diffing fields that change on every write, such as Kubernetes
+/// `metadata.resourceVersion`; [`DiffOptions::preset`](crate::DiffOptions::preset)
+/// bundles them. Fields that can appear anywhere, such as GitHub's
+/// `node_id`s, are found in the documents themselves by
+/// [`document_path_options`](Self::document_path_options). Its
+/// [`normalize`](Self::normalize) step rewrites Terraform documents into a
+/// comparable form. [`Jd::diff`](crate::Jd::diff) applies both to the
+/// documents when the options carry a preset. Arrays of elements identified by a key, such as
+/// OpenAPI parameters by `name` and `in`, are still lists, but the key
+/// anchors their alignment: an element that changes in place is diffed
+/// member by member even when others around it move. Moving an element is
+/// still a removal and an addition until the diff engine can match elements
+/// through set semantics.
+///
+/// ```
+/// use jd_core::{node, Jd, Preset};
+///
+/// let before = node!({"metadata": {"name": "web", "resourceVersion": "41"},
+///     "spec": {"containers": [{"name": "app"}, {"name": "proxy"}]}});
+/// let after = node!({"metadata": {"name": "web", "resourceVersion": "42"},
+///     "spec": {"containers": [{"name": "app"}, {"name": "proxy"}]}});
+/// assert!(Jd::new().preset(Preset::Kubernetes).diff(&before, &after).unwrap().is_empty());
+/// assert_eq!("openapi".parse::<Preset>().unwrap(), Preset::OpenApi);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Preset {
+    /// GitHub webhook payloads and REST API responses, with volatile
+    /// members skipped by [`github::volatile_path_options`](crate::github::volatile_path_options).
+    GitHub,
+    /// Kubernetes manifests and `kubectl get -o json` output, alone or as the
+    /// `items` of a `List`.
+    Kubernetes,
+    /// OpenAPI 3.x and Swagger 2.0 API descriptions.
+    OpenApi,
+    /// Terraform state files and `terraform show -json` plans, normalized
+    /// with [`terraform::normalize`](crate::terraform::normalize).
+    Terraform,
+}
+
+impl Preset {
+    /// Every preset, in the order of their names.
+    ///
+    /// ```
+    /// # use jd_core::Preset;
+    /// let names: Vec<_> = Preset::ALL.iter().map(|preset| preset.name()).collect();
+    /// assert_eq!(names, ["github", "kubernetes", "openapi", "terraform"]);
+    /// ```
+    pub const ALL: [Self; 4] = [Self::GitHub, Self::Kubernetes, Self::OpenApi, Self::Terraform];
+
+    /// The name used by `jd --preset` and [`FromStr`].
+    ///
+    /// ```
+    /// assert_eq!(jd_core::Preset::OpenApi.name(), "openapi");
+    /// ```
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::Kubernetes => "kubernetes",
+            Self::OpenApi => "openapi",
+            Self::Terraform => "terraform",
+        }
+    }
+
+    /// Path options that stop diffing the family's volatile fields and
+    /// anchor its keyed arrays by their keys.
+    ///
+    /// ```
+    /// # use jd_core::Preset;
+    /// assert!(!Preset::Terraform.path_options().is_empty());
+    /// ```
+    #[must_use]
+    pub fn path_options(self) -> Vec<PathOption> {
+        let paths: Vec<Vec<PathMatcher>> = match self {
+            // Repository counters move with every star, fork, and push, and
+            // are repeated in the repositories of a pull request's branches.
+            Self::GitHub => [
+                vec![PathMatcher::key("repository")],
+                vec![
+                    PathMatcher::key("pull_request"),
+                    PathMatcher::AnyKey,
+                    PathMatcher::key("repo"),
+                ],
+            ]
+            .into_iter()
+            .flat_map(|prefix| {
+                [
+                    "forks",
+                    "forks_count",
+                    "open_issues",
+                    "open_issues_count",
+                    "size",
+                    "stargazers_count",
+                    "watchers",
+                    "watchers_count",
+                ]
+                .map(|key| {
+                    let mut path = prefix.clone();
+                    path.push(PathMatcher::key(key));
+                    path
+                })
+            })
+            .collect(),
+            // Server-managed bookkeeping that changes on every write. A
+            // `List` holds the same fields in each of its items.
+            Self::Kubernetes => [vec![], vec![PathMatcher::key("items"), PathMatcher::AnyIndex]]
+                .into_iter()
+                .flat_map(|prefix| {
+                    let metadata = |key: &str| {
+                        let mut path = prefix.clone();
+                        path.extend([PathMatcher::key("metadata"), PathMatcher::key(key)]);
+                        path
+                    };
+                    let annotation = |key: &str| {
+                        let mut path = metadata("annotations");
+                        path.push(PathMatcher::key(key));
+                        path
+                    };
+                    let mut status = prefix.clone();
+                    status.push(PathMatcher::key("status"));
+                    [
+                        metadata("creationTimestamp"),
+                        metadata("generation"),
+                        metadata("managedFields"),
+                        metadata("resourceVersion"),
+                        metadata("selfLink"),
+                        metadata("uid"),
+                        annotation("deployment.kubernetes.io/revision"),
+                        annotation("kubectl.kubernetes.io/last-applied-configuration"),
+                        status,
+                    ]
+                })
+                .collect(),
+            // Version bumps come with every release and say nothing about
+            // how the API changed.
+            Self::OpenApi => vec![vec![PathMatcher::key("info"), PathMatcher::key("version")]],
+            // Every apply bumps the serial, and the tool version and plan
+            // timestamp describe the run rather than the infrastructure.
+            // `private` is opaque provider data of each keyed instance.
+            Self::Terraform => ["serial", "lineage", "terraform_version", "timestamp"]
+                .into_iter()
+                .map(|key| vec![PathMatcher::key(key)])
+                .chain([vec![
+                    PathMatcher::key("resources"),
+                    PathMatcher::AnyKey,
+                    PathMatcher::key("private"),
+                ]])
+                .collect(),
+        };
+        paths
+            .into_iter()
+            .map(|at| PathOption::new(at).with_setting(PathSetting::DiffOff))
+            .chain(self.anchors())
+            .collect()
+    }
+
+    /// List-mode set-key anchors for arrays whose elements carry their own
+    /// identity.
+    fn anchors(self) -> Vec<PathOption> {
+        let anchored = |at: Vec<PathMatcher>, keys: &[&str]| {
+            PathOption::new(at)
+                .with_setting(PathSetting::SetKeys(
+                    keys.iter().map(|key| key.to_string()).collect(),
+                ))
+                .with_setting(PathSetting::ArrayMode(ArrayMode::List))
+        };
+        match self {
+            // A parameter is identified by its name and location, both at the
+            // path level and in each operation. Tags are named.
+            Self::OpenApi => vec![
+                anchored(
+                    vec![
+                        PathMatcher::key("paths"),
+                        PathMatcher::AnyKey,
+                        PathMatcher::key("parameters"),
+                    ],
+                    &["name", "in"],
+                ),
+                anchored(
+                    vec![
+                        PathMatcher::key("paths"),
+                        PathMatcher::AnyKey,
+                        PathMatcher::AnyKey,
+                        PathMatcher::key("parameters"),
+                    ],
+                    &["name", "in"],
+                ),
+                anchored(vec![PathMatcher::key("tags")], &["name"]),
+            ],
+            // Containers, init containers, their `env`, ports, and mounts,
+            // and volumes are all named, in pods and in the pod templates
+            // of workloads alike. Set keys reach every array below `spec`.
+            Self::Kubernetes => [vec![], vec![PathMatcher::key("items"), PathMatcher::AnyIndex]]
+                .into_iter()
+                .map(|mut at| {
+                    at.push(PathMatcher::key("spec"));
+                    anchored(at, &["name"])
+                })
+                .collect(),
+            Self::GitHub | Self::Terraform => Vec::new(),
+        }
+    }
+
+    /// Path options for the volatile fields found in `lhs` and `rhs`, in
+    /// addition to the fixed [`path_options`](Self::path_options).
+    ///
+    /// GitHub payloads repeat `updated_at`, `node_id`, and similar members
+    /// at any depth, so their locations come from
+    /// [`github::volatile_path_options`](crate::github::volatile_path_options).
+    /// Diffing is switched off there rather than the members being removed,
+    /// so the diff still applies to the payloads as written. Other families
+    /// have none.
+    ///
+    /// ```
+    /// use jd_core::{node, Preset};
+    ///
+    /// let issue = node!({"issue": {"title": "Crash", "updated_at": "2024-07-03T10:12:00Z"}});
+    /// assert_eq!(Preset::GitHub.document_path_options(&issue, &issue).len(), 1);
+    /// assert!(Preset::Kubernetes.document_path_options(&issue, &issue).is_empty());
+    /// ```
+    #[must_use]
+    pub fn document_path_options(self, lhs: &Node, rhs: &Node) -> Vec<PathOption> {
+        match self {
+            Self::GitHub => crate::github::volatile_path_options([lhs, rhs]),
+            Self::Kubernetes | Self::OpenApi | Self::Terraform => Vec::new(),
+        }
+    }
+
+    /// Rewrites a document of the family into the form its diffs describe.
+    ///
+    /// Terraform resources are keyed by address, and sensitive values
+    /// masked, by [`terraform::normalize`](crate::terraform::normalize).
+    /// Other families are returned unchanged, and their arrays are never
+    /// reordered, so the indices in their diffs point at the elements as
+    /// written. A Terraform diff does not: its `resources` array becomes an
+    /// object keyed by address, so the diff describes the normalized
+    /// document and cannot be applied to the state file as a patch.
+    ///
+    /// ```
+    /// use jd_core::{node, Preset};
+    ///
+    /// let spec = node!({"tags": [{"name": "pets"}, {"name": "admin"}]});
+    /// assert_eq!(Preset::OpenApi.normalize(&spec), spec);
+    /// ```
+    #[must_use]
+    pub fn normalize(self, node: &Node) -> Node {
+        match self {
+            Self::Terraform => crate::terraform::normalize(node),
+            Self::GitHub | Self::Kubernetes | Self::OpenApi => node.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    /// Formats the preset as its [`name`](Preset::name).
+    ///
+    /// ```
+    /// assert_eq!(jd_core::Preset::Kubernetes.to_string(), "kubernetes");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// The error returned when parsing an unknown preset name.
+///
+/// ```
+/// let err = "helm".parse::<jd_core::Preset>().unwrap_err();
+/// assert_eq!(err.to_string(), "unknown preset \"helm\"; expected github, kubernetes, openapi, or terraform");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("unknown preset {name:?}; expected github, kubernetes, openapi, or terraform")]
+pub struct ParsePresetError {
+    name: String,
+}
+
+impl FromStr for Preset {
+    type Err = ParsePresetError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| ParsePresetError { name: name.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{node, DiffOptions, Jd};
+
+    #[test]
+    fn github_replays_compare_equal() {
+        let delivery = |updated: &str, stars: i64, labels: Node| {
+            node!({"action": "labeled", "issue": {"number": 142, "updated_at": updated, "labels": labels},
+                "repository": {"name": "jd", "node_id": "R_1", "stargazers_count": stars}})
+        };
+        let first =
+            delivery("2024-07-03T10:12:00Z", 10, node!([{"name": "bug"}, {"name": "parity"}]));
+        let replay =
+            delivery("2024-07-04T08:00:00Z", 12, node!([{"name": "bug"}, {"name": "parity"}]));
+        let jd = Jd::new().preset(Preset::GitHub);
+        assert!(jd.diff(&first, &replay).unwrap().is_empty());
+        let relabeled = delivery("2024-07-03T10:12:00Z", 10, node!([{"name": "bug"}]));
+        assert_eq!(jd.diff(&first, &relabeled).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn github_patches_apply_to_the_payload_as_written() {
+        let delivery = |updated: &str, labels: Node| node!({"issue": {"number": 142, "updated_at": updated, "labels": labels}});
+        let lhs = delivery("1", node!([{"name": "bug", "node_id": "L_1"}, {"name": "ui"}]));
+        let rhs = delivery(
+            "2",
+            node!([{"name": "bug", "node_id": "L_2"}, {"name": "docs", "node_id": "L_3"},
+                {"name": "ui"}]),
+        );
+        let diff = Jd::new().preset(Preset::GitHub).diff(&lhs, &rhs).unwrap();
+        assert_eq!(diff.len(), 1);
+        let patched = lhs.apply_patch(&diff).unwrap();
+        let expected = delivery(
+            "1",
+            node!([{"name": "bug", "node_id": "L_1"}, {"name": "docs", "node_id": "L_3"},
+                {"name": "ui"}]),
+        );
+        assert_eq!(patched, expected);
+    }
+
+    #[test]
+    fn kubernetes_ignores_bookkeeping_in_lists_too() {
+        let item = |version: &str, ready: i64| {
+            node!({"metadata": {"name": "web", "resourceVersion": version,
+                "annotations": {"deployment.kubernetes.io/revision": version, "team": "a"}},
+                "status": {"readyReplicas": ready}})
+        };
+        let jd = Jd::new().preset(Preset::Kubernetes);
+        assert!(jd.diff(&item("1", 1), &item("2", 3)).unwrap().is_empty());
+        let (lhs, rhs) = (node!({"items": [item("1", 1)]}), node!({"items": [item("2", 0)]}));
+        assert!(jd.diff(&lhs, &rhs).unwrap().is_empty());
+        let renamed = node!({"metadata": {"name": "api", "annotations": {"team": "a"}}});
+        assert_eq!(jd.diff(&item("1", 1), &renamed).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn kubernetes_patches_apply_to_the_manifest_as_written() {
+        let pod = |image: &str| node!({"spec": {"containers": [{"name": "b", "image": image}, {"name": "a", "image": "1"}]}});
+        let (lhs, rhs) = (pod("1"), pod("2"));
+        let diff = Jd::new().preset(Preset::Kubernetes).diff(&lhs, &rhs).unwrap();
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+    }
+
+    #[test]
+    fn kubernetes_anchors_containers_and_env_by_name() {
+        let deployment = |containers: Node| node!({"items": [{"spec": {"template": {"spec": {"containers": containers}}}}]});
+        let app = |image: &str, env: Node| node!({"name": "app", "image": image, "env": env});
+        let lhs = deployment(node!([app("a:1", node!([{"name": "LEVEL", "value": "info"}]))]));
+        let rhs = deployment(node!([
+            {"name": "proxy", "image": "p:1"},
+            app("a:2", node!([{"name": "MODE", "value": "prod"}, {"name": "LEVEL", "value": "debug"}]))
+        ]));
+        let jd = Jd::new().preset(Preset::Kubernetes);
+        let diff = jd.diff(&lhs, &rhs).unwrap();
+        let app = r#"["items",0,"spec","template","spec","containers",1,"#;
+        assert_eq!(
+            diff.render(&Default::default()),
+            format!(
+                "@ [\"items\",0,\"spec\",\"template\",\"spec\",\"containers\",0]\n[\n\
+                 + {{\"image\":\"p:1\",\"name\":\"proxy\"}}\n  \
+                 {{\"env\":[{{\"name\":\"LEVEL\",\"value\":\"info\"}}],\"image\":\"a:1\",\"name\":\"app\"}}\n\
+                 @ {app}\"env\",0]\n[\n+ {{\"name\":\"MODE\",\"value\":\"prod\"}}\n  \
+                 {{\"name\":\"LEVEL\",\"value\":\"info\"}}\n\
+                 @ {app}\"env\",1,\"value\"]\n- \"info\"\n+ \"debug\"\n\
+                 @ {app}\"image\"]\n- \"a:1\"\n+ \"a:2\"\n"
+            )
+        );
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+        // Without the anchors the new container is diffed against `app`.
+        let plain = lhs.diff(&rhs, &DiffOptions::default()).render(&Default::default());
+        assert!(plain.contains("- \"app\"\n+ \"proxy\"\n"), "{plain}");
+    }
+
+    #[test]
+    fn openapi_patches_apply_to_the_spec_as_written() {
+        let spec = |required: bool| {
+            node!({"info": {"version": "1.0.0"}, "paths": {"/pets": {"get": {"parameters": [
+                {"name": "limit", "in": "query", "required": required},
+                {"name": "id", "in": "query"}
+            ]}}}})
+        };
+        let (lhs, rhs) = (spec(false), spec(true));
+        let diff = Jd::new().preset(Preset::OpenApi).diff(&lhs, &rhs).unwrap();
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+    }
+
+    #[test]
+    fn openapi_anchors_reordered_parameters_by_name_and_location() {
+        let limit = |required: bool| node!({"name": "limit", "in": "query", "required": required});
+        let spec = |parameters: Node| {
+            node!({"paths": {"/pets": {"get": {"parameters": parameters}}},
+                "tags": [{"name": "pets"}, {"name": "admin"}]})
+        };
+        let lhs = spec(
+            node!([limit(false), {"name": "id", "in": "query"}, {"name": "id", "in": "header"}]),
+        );
+        let rhs = spec(
+            node!([{"name": "id", "in": "header"}, limit(true), {"name": "id", "in": "query"}]),
+        );
+        let jd = Jd::new().preset(Preset::OpenApi);
+        let diff = jd.diff(&lhs, &rhs).unwrap();
+        // The moved parameter is one addition and one removal; the one that
+        // changed is diffed member by member.
+        let parameters = r#"["paths","/pets","get","parameters","#;
+        assert_eq!(
+            diff.render(&Default::default()),
+            format!(
+                "@ {parameters}0]\n[\n+ {{\"in\":\"header\",\"name\":\"id\"}}\n  \
+                 {{\"in\":\"query\",\"name\":\"limit\",\"required\":false}}\n\
+                 @ {parameters}1,\"required\"]\n- false\n+ true\n\
+                 @ {parameters}3]\n  {{\"in\":\"query\",\"name\":\"id\"}}\n\
+                 - {{\"in\":\"header\",\"name\":\"id\"}}\n]\n"
+            )
+        );
+        assert_eq!(lhs.apply_patch(&diff).unwrap(), rhs);
+        // Without the anchors the parameters are rewritten in place.
+        let plain = lhs.diff(&rhs, &DiffOptions::default()).render(&Default::default());
+        assert!(plain.contains("- \"limit\"\n+ \"id\"\n"), "{plain}");
+    }
+
+    #[test]
+    fn terraform_ignores_run_metadata_and_keys_resources() {
+        let state = |serial: i64, resources: Node| node!({"version": 4, "serial": serial, "lineage": "x", "resources": resources});
+        let resource = |name: &str, private: &str| {
+            node!({"mode": "managed", "type": "aws_s3_bucket", "name": name,
+                "instances": [{"attributes": {"bucket": name}, "private": private}]})
+        };
+        let lhs = state(1, node!([resource("logs", "a"), resource("assets", "a")]));
+        let rhs = state(2, node!([resource("assets", "b"), resource("logs", "b")]));
+        let options = DiffOptions::preset(Preset::Terraform);
+        assert!(!lhs.diff(&rhs, &options).is_empty());
+        let normalize = |node| Preset::Terraform.normalize(node);
+        assert!(normalize(&lhs).diff(&normalize(&rhs), &options).is_empty());
+    }
+}
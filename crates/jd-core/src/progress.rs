@@ -0,0 +1,277 @@
+//! Progress reporting for long parses, diffs, and renders.
+//!
+//! Install a [`ProgressSink`] on the current thread and jd reports how far
+//! each phase of the work has got: parsing JSON, hashing list elements,
+//! aligning lists, and rendering. Without a sink installed, reporting costs
+//! one thread-local check per phase.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use jd_core::progress::{self, Phase};
+//! use jd_core::{DiffOptions, Node};
+//!
+//! let seen = Rc::new(RefCell::new(Vec::new()));
+//! let log = Rc::clone(&seen);
+//! let guard = progress::install(move |phase: Phase, percent: u8| {
+//!     log.borrow_mut().push((phase, percent));
+//! });
+//! let lhs = Node::from_json_str("[1, 2, 3]").unwrap();
+//! let rhs = Node::from_json_str("[1, 4, 3]").unwrap();
+//! lhs.diff(&rhs, &DiffOptions::default());
+//! drop(guard);
+//!
+//! let seen = seen.borrow();
+//! assert_eq!(seen.first(), Some(&(Phase::Parse, 0)));
+//! assert!(seen.contains(&(Phase::Hash, 100)));
+//! assert_eq!(seen.last(), Some(&(Phase::Lcs, 100)));
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// A stage of work that reports progress.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Phase {
+    /// Parsing a JSON document, measured in bytes consumed. YAML documents
+    /// report only when parsing finishes.
+    Parse,
+    /// Hashing the elements of the outermost list being diffed.
+    Hash,
+    /// Aligning the elements of the outermost list being diffed.
+    Lcs,
+    /// Rendering a diff, measured in hunks.
+    Render,
+}
+
+impl fmt::Display for Phase {
+    /// Formats the phase as its lowercase name, matching the `--verbose`
+    /// span names.
+    ///
+    /// ```
+    /// assert_eq!(jd_core::progress::Phase::Lcs.to_string(), "lcs");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Parse => "parse",
+            Self::Hash => "hash",
+            Self::Lcs => "lcs",
+            Self::Render => "render",
+        })
+    }
+}
+
+/// Receives progress reports.
+///
+/// Each phase reports 0 when it starts, then each whole percentage it
+/// passes, ending with 100. Closures taking `(Phase, u8)` are sinks.
+pub trait ProgressSink {
+    /// Records that `phase` is `percent` (0 to 100) complete.
+    fn report(&self, phase: Phase, percent: u8);
+}
+
+impl<F: Fn(Phase, u8)> ProgressSink for F {
+    fn report(&self, phase: Phase, percent: u8) {
+        self(phase, percent);
+    }
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Rc<dyn ProgressSink>>> = const { RefCell::new(None) };
+    static METERING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Reports progress on the current thread to `sink` until the returned
+/// guard is dropped, which restores the previously installed sink.
+///
+/// Work moved to other threads, such as [`task`](crate::task) futures, does
+/// not report to the sink.
+pub fn install(sink: impl ProgressSink + 'static) -> ProgressGuard {
+    let previous = SINK.with(|current| current.borrow_mut().replace(Rc::new(sink)));
+    ProgressGuard { previous }
+}
+
+/// Keeps a sink installed by [`install`]; dropping it uninstalls the sink.
+#[must_use = "the sink is uninstalled as soon as the guard is dropped"]
+pub struct ProgressGuard {
+    previous: Option<Rc<dyn ProgressSink>>,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        SINK.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+impl fmt::Debug for ProgressGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressGuard").finish_non_exhaustive()
+    }
+}
+
+/// Claims progress reporting for one piece of work. Only the outermost
+/// scope on a thread reports, so the lists nested inside a large list do
+/// not restart the bar for every element.
+pub(crate) struct Scope {
+    sink: Option<Rc<dyn ProgressSink>>,
+}
+
+impl Scope {
+    pub(crate) fn enter() -> Self {
+        let sink = SINK.with(|current| current.borrow().clone());
+        let sink = sink.filter(|_| !METERING.with(|metering| metering.replace(true)));
+        Self { sink }
+    }
+
+    pub(crate) fn is_reporting(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Starts reporting `phase` over `total` units of work.
+    pub(crate) fn meter(&self, phase: Phase, total: usize) -> Meter<'_> {
+        let meter = Meter {
+            sink: self.sink.as_deref(),
+            phase,
+            total,
+            done: Cell::new(0),
+            last: Cell::new(0),
+        };
+        meter.send(if total == 0 { 100 } else { 0 });
+        meter
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if self.sink.is_some() {
+            METERING.with(|metering| metering.set(false));
+        }
+    }
+}
+
+/// Counts completed units of one phase, reporting each new percentage.
+pub(crate) struct Meter<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    phase: Phase,
+    total: usize,
+    done: Cell<usize>,
+    last: Cell<u8>,
+}
+
+impl Meter<'_> {
+    pub(crate) fn advance(&self) {
+        self.advance_by(1);
+    }
+
+    pub(crate) fn advance_by(&self, units: usize) {
+        if self.sink.is_none() || self.total == 0 {
+            return;
+        }
+        let done = self.done.get().saturating_add(units).min(self.total);
+        self.done.set(done);
+        let percent = (done as u128 * 100 / self.total as u128) as u8;
+        if percent > self.last.get() {
+            self.last.set(percent);
+            self.send(percent);
+        }
+    }
+
+    /// Reports completion, for work that ends before counting every unit,
+    /// such as a parse that fails or a phase without a count.
+    pub(crate) fn finish(&self) {
+        if self.last.get() < 100 {
+            self.last.set(100);
+            self.send(100);
+        }
+    }
+
+    fn send(&self, percent: u8) {
+        if let Some(sink) = self.sink {
+            sink.report(self.phase, percent);
+        }
+    }
+}
+
+/// Counts the bytes a parser reads.
+pub(crate) struct MeteredReader<'a, R> {
+    pub(crate) inner: R,
+    pub(crate) meter: &'a Meter<'a>,
+}
+
+impl<R: Read> Read for MeteredReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.meter.advance_by(read);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{node, DiffOptions, Node, RenderConfig};
+
+    type Reports = Rc<RefCell<Vec<(Phase, u8)>>>;
+
+    fn record() -> (Reports, ProgressGuard) {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&seen);
+        let guard = install(move |phase, percent| log.borrow_mut().push((phase, percent)));
+        (seen, guard)
+    }
+
+    #[test]
+    fn percentages_rise_to_completion_once() {
+        let (seen, _guard) = record();
+        let lhs: Vec<_> = (0..250).map(|i| node!(i)).collect();
+        let rhs: Vec<_> = (0..250).map(|i| node!((i * 7) % 250)).collect();
+        let _ = Node::Array(lhs).diff(&Node::Array(rhs), &DiffOptions::default());
+        for phase in [Phase::Hash, Phase::Lcs] {
+            let percents: Vec<u8> =
+                seen.borrow().iter().filter(|(p, _)| *p == phase).map(|(_, n)| *n).collect();
+            assert_eq!(percents.first(), Some(&0));
+            assert_eq!(percents.last(), Some(&100));
+            assert!(percents.windows(2).all(|pair| pair[0] < pair[1]), "{percents:?}");
+        }
+    }
+
+    #[test]
+    fn only_the_outermost_list_reports() {
+        let (seen, _guard) = record();
+        let lhs = node!([[1, 2], [3, 4], 5]);
+        let rhs = node!([[1, 9], [3, 4], 6]);
+        let _ = lhs.diff(&rhs, &DiffOptions::default());
+        let starts = seen.borrow().iter().filter(|(_, percent)| *percent == 0).count();
+        assert_eq!(starts, 2, "{:?}", seen.borrow());
+    }
+
+    #[test]
+    fn rendering_reports_per_hunk() {
+        let (seen, _guard) = record();
+        let diff = node!({"a": [1, 2]}).diff(&node!({"a": [1, 3]}), &DiffOptions::default());
+        seen.borrow_mut().clear();
+        let _ = diff.render(&RenderConfig::default());
+        assert_eq!(*seen.borrow(), [(Phase::Render, 0), (Phase::Render, 100)]);
+    }
+
+    #[test]
+    fn failed_parses_still_complete() {
+        let (seen, _guard) = record();
+        assert!(Node::from_json_str("[1,").is_err());
+        assert_eq!(seen.borrow().last(), Some(&(Phase::Parse, 100)));
+    }
+
+    #[test]
+    fn dropping_the_guard_restores_the_previous_sink() {
+        let (outer, _outer_guard) = record();
+        let (inner, inner_guard) = record();
+        Node::from_json_str("1").unwrap();
+        drop(inner_guard);
+        Node::from_json_str("2").unwrap();
+        assert_eq!(inner.borrow().len(), 2);
+        assert_eq!(outer.borrow().len(), 2);
+    }
+}
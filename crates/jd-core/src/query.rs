@@ -0,0 +1,445 @@
+//! JSONPath queries over [`Node`]s.
+//!
+//! [`JsonPath`] implements the subset of RFC 9535 that selects locations
+//! rather than filtering on values:
+//!
+//! - `$`, the root, which every query starts with;
+//! - `.name` and `['name']` (or `["name"]`) for object members;
+//! - `[0]` for array elements, with `[-1]` counting from the end;
+//! - `.*` and `[*]` for every member or element;
+//! - `..` before any of the above to search all descendants.
+//!
+//! ```
+//! use jd_core::{node, query::JsonPath};
+//!
+//! let doc = node!({"spec": {"containers": [{"image": "a:1"}, {"image": "b:2"}]}});
+//! let query: JsonPath = "$.spec.containers[*].image".parse().unwrap();
+//! let images: Vec<String> = query.paths(&doc).iter().map(ToString::to_string).collect();
+//! assert_eq!(images, ["[spec containers 0 image]", "[spec containers 1 image]"]);
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+use crate::{Node, Path, PathMatcher, PathSegment};
+
+/// A parsed JSONPath query. See the [module documentation](self) for the
+/// supported syntax.
+///
+/// ```
+/// # use jd_core::query::JsonPath;
+/// let query = JsonPath::parse("$..['id']").unwrap();
+/// assert_eq!(query.to_string(), "$..id");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonPath {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Step {
+    descendants: bool,
+    selector: Selector,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    /// `.*`, which path options read as "any object member".
+    AnyMember,
+    /// `[*]`, which path options read as "any array element".
+    AnyElement,
+}
+
+/// Errors that can occur while parsing a [`JsonPath`] or converting it to
+/// path option matchers.
+///
+/// ```
+/// # use jd_core::query::JsonPath;
+/// let err = JsonPath::parse("$.a[").unwrap_err();
+/// assert_eq!(err.to_string(), "invalid JSONPath \"$.a[\": unexpected end at offset 4");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    message: String,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl JsonPath {
+    /// Parses a query such as `$.spec.containers[*].image`.
+    ///
+    /// ```
+    /// # use jd_core::query::JsonPath;
+    /// assert!(JsonPath::parse("$.items[-1]['display name']").is_ok());
+    /// assert!(JsonPath::parse("items[0]").is_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        Parser { input, chars: input.char_indices().peekable() }.parse()
+    }
+
+    /// Returns the location and value of every match in `node`, in document
+    /// order and without duplicates.
+    ///
+    /// ```
+    /// # use jd_core::{node, query::JsonPath};
+    /// let doc = node!({"a": [1, 2, 3]});
+    /// let matches = JsonPath::parse("$.a[-1]").unwrap().select(&doc);
+    /// assert_eq!(matches[0].0.to_string(), "[a 2]");
+    /// assert_eq!(*matches[0].1, node!(3));
+    /// ```
+    #[must_use]
+    pub fn select<'a>(&self, node: &'a Node) -> Vec<(Path, &'a Node)> {
+        let mut current = vec![(Path::new(), node)];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for (path, node) in current {
+                if !step.descendants {
+                    step.selector.select(&path, node, &mut next);
+                    continue;
+                }
+                // Visit the node and its descendants in document order
+                // without recursion.
+                let mut stack = vec![(path, node)];
+                while let Some((path, node)) = stack.pop() {
+                    step.selector.select(&path, node, &mut next);
+                    let first = stack.len();
+                    children(&path, node, &mut stack);
+                    stack[first..].reverse();
+                }
+            }
+            let mut seen = HashSet::new();
+            next.retain(|(path, _)| seen.insert(path.clone()));
+            current = next;
+        }
+        current
+    }
+
+    /// Returns the location of every match in `node`; see [`JsonPath::select`].
+    ///
+    /// ```
+    /// # use jd_core::{node, query::JsonPath};
+    /// let doc = node!({"a": {"id": 1}, "b": [{"id": 2}]});
+    /// let paths = JsonPath::parse("$..id").unwrap().paths(&doc);
+    /// assert_eq!(paths.len(), 2);
+    /// ```
+    #[must_use]
+    pub fn paths(&self, node: &Node) -> Vec<Path> {
+        self.select(node).into_iter().map(|(path, _)| path).collect()
+    }
+
+    /// Converts the query into the location of a
+    /// [`PathOption`](crate::PathOption). `.*` becomes
+    /// [`PathMatcher::AnyKey`] and `[*]` becomes [`PathMatcher::AnyIndex`].
+    /// Descendant searches and negative indices have no matcher equivalent.
+    ///
+    /// ```
+    /// # use jd_core::{query::JsonPath, PathMatcher};
+    /// let matchers = JsonPath::parse("$.spec[*].*").unwrap().to_matchers().unwrap();
+    /// assert_eq!(matchers, [PathMatcher::key("spec"), PathMatcher::AnyIndex, PathMatcher::AnyKey]);
+    /// assert!(JsonPath::parse("$..spec").unwrap().to_matchers().is_err());
+    /// ```
+    pub fn to_matchers(&self) -> Result<Vec<PathMatcher>, QueryError> {
+        self.steps
+            .iter()
+            .map(|step| match &step.selector {
+                _ if step.descendants => Err(QueryError::new(format!(
+                    "{self} cannot be a path option location: `..` is not supported"
+                ))),
+                Selector::Index(index) if *index < 0 => Err(QueryError::new(format!(
+                    "{self} cannot be a path option location: negative index {index}"
+                ))),
+                Selector::Name(name) => Ok(PathMatcher::key(name.clone())),
+                Selector::Index(index) => Ok(PathMatcher::Index(*index)),
+                Selector::AnyMember => Ok(PathMatcher::AnyKey),
+                Selector::AnyElement => Ok(PathMatcher::AnyIndex),
+            })
+            .collect()
+    }
+}
+
+impl Selector {
+    fn select<'a>(&self, path: &Path, node: &'a Node, out: &mut Vec<(Path, &'a Node)>) {
+        match (self, node) {
+            (Self::Name(name), Node::Object(map)) => {
+                if let Some(value) = map.get(name) {
+                    out.push((path.clone().with_segment(PathSegment::key(name.clone())), value));
+                }
+            }
+            (Self::Index(index), Node::Array(items)) => {
+                let len = items.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    let value = &items[index as usize];
+                    out.push((path.clone().with_segment(PathSegment::Index(index)), value));
+                }
+            }
+            (Self::AnyMember | Self::AnyElement, _) => children(path, node, out),
+            _ => {}
+        }
+    }
+}
+
+fn children<'a>(path: &Path, node: &'a Node, out: &mut Vec<(Path, &'a Node)>) {
+    match node {
+        Node::Array(items) => out.extend(items.iter().enumerate().map(|(index, value)| {
+            (path.clone().with_segment(PathSegment::Index(index as i64)), value)
+        })),
+        Node::Object(map) => out.extend(
+            map.iter()
+                .map(|(key, value)| (path.clone().with_segment(PathSegment::key(key)), value)),
+        ),
+        _ => {}
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+/// Writes the query in its shortest form, which parses back to the same query.
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("$")?;
+        for step in &self.steps {
+            let dot = if step.descendants {
+                f.write_str("..")?;
+                ""
+            } else {
+                "."
+            };
+            match &step.selector {
+                Selector::Name(name) if is_shorthand(name) => write!(f, "{dot}{name}")?,
+                Selector::Name(name) => {
+                    f.write_str("['")?;
+                    for c in name.chars() {
+                        match c {
+                            '\'' | '\\' => write!(f, "\\{c}")?,
+                            _ => write!(f, "{c}")?,
+                        }
+                    }
+                    f.write_str("']")?;
+                }
+                Selector::Index(index) => write!(f, "[{index}]")?,
+                Selector::AnyMember => write!(f, "{dot}*")?,
+                Selector::AnyElement => f.write_str("[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `name` can be written as `.name`.
+fn is_shorthand(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_name_start) && chars.all(is_name_char)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<JsonPath, QueryError> {
+        self.expect('$')?;
+        let mut steps = Vec::new();
+        while let Some((offset, c)) = self.chars.next() {
+            let step = match c {
+                '.' if self.eat('.') => {
+                    let selector = match self.chars.peek() {
+                        Some((_, '[')) => {
+                            self.chars.next();
+                            self.bracket()?
+                        }
+                        _ => self.dotted()?,
+                    };
+                    Step { descendants: true, selector }
+                }
+                '.' => Step { descendants: false, selector: self.dotted()? },
+                '[' => Step { descendants: false, selector: self.bracket()? },
+                _ => return Err(self.error(format!("unexpected {c:?} at offset {offset}"))),
+            };
+            steps.push(step);
+        }
+        Ok(JsonPath { steps })
+    }
+
+    /// Parses what follows a `.`: a member name or `*`.
+    fn dotted(&mut self) -> Result<Selector, QueryError> {
+        match self.chars.peek().copied() {
+            Some((_, '*')) => {
+                self.chars.next();
+                Ok(Selector::AnyMember)
+            }
+            Some((start, c)) if is_name_start(c) => {
+                let mut end = start;
+                while let Some((offset, c)) = self.chars.next_if(|(_, c)| is_name_char(*c)) {
+                    end = offset + c.len_utf8();
+                }
+                Ok(Selector::Name(self.input[start..end].to_string()))
+            }
+            Some((offset, c)) => {
+                Err(self.error(format!("expected a member name at offset {offset}, found {c:?}")))
+            }
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    /// Parses what follows a `[` up to and including the closing `]`.
+    fn bracket(&mut self) -> Result<Selector, QueryError> {
+        self.skip_whitespace();
+        let selector = match self.chars.next() {
+            Some((_, '*')) => Selector::AnyElement,
+            Some((_, quote @ ('\'' | '"'))) => Selector::Name(self.quoted(quote)?),
+            Some((start, c)) if c == '-' || c.is_ascii_digit() => {
+                let mut end = start + 1;
+                while let Some((offset, _)) = self.chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    end = offset + 1;
+                }
+                let digits = &self.input[start..end];
+                let index = digits.parse().map_err(|_| {
+                    self.error(format!("invalid index {digits:?} at offset {start}"))
+                })?;
+                Selector::Index(index)
+            }
+            Some((offset, c)) => {
+                return Err(self.error(format!("unexpected {c:?} at offset {offset}")));
+            }
+            None => return Err(self.unexpected_end()),
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(selector)
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, QueryError> {
+        let mut name = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(name),
+                Some((offset, '\\')) => match self.chars.next() {
+                    Some((_, c @ ('\'' | '"' | '\\' | '/'))) => name.push(c),
+                    Some((_, 'n')) => name.push('\n'),
+                    Some((_, 't')) => name.push('\t'),
+                    Some(_) => {
+                        return Err(self.error(format!("unsupported escape at offset {offset}")));
+                    }
+                    None => return Err(self.unexpected_end()),
+                },
+                Some((_, c)) => name.push(c),
+                None => return Err(self.unexpected_end()),
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), QueryError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((offset, c)) => {
+                Err(self.error(format!("expected {expected:?} at offset {offset}, found {c:?}")))
+            }
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    fn unexpected_end(&self) -> QueryError {
+        self.error(format!("unexpected end at offset {}", self.input.len()))
+    }
+
+    fn error(&self, reason: String) -> QueryError {
+        QueryError::new(format!("invalid JSONPath {:?}: {reason}", self.input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node;
+
+    fn paths(query: &str, node: &Node) -> Vec<String> {
+        JsonPath::parse(query).unwrap().paths(node).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn selectors_follow_document_order() {
+        let doc = node!({"b": [{"id": 1}, {"id": 2, "sub": {"id": 3}}], "a": {"id": 0}});
+        assert_eq!(paths("$", &doc), ["[]"]);
+        assert_eq!(paths("$.*", &doc), ["[a]", "[b]"]);
+        assert_eq!(paths("$.b[*].id", &doc), ["[b 0 id]", "[b 1 id]"]);
+        assert_eq!(paths("$..id", &doc), ["[a id]", "[b 0 id]", "[b 1 id]", "[b 1 sub id]"]);
+        assert_eq!(paths("$..[0]", &doc), ["[b 0]"]);
+        assert_eq!(paths("$.b[-2]['id']", &doc), ["[b 0 id]"]);
+        assert!(paths("$.b[2]", &doc).is_empty());
+        assert!(paths("$.b[-3]", &doc).is_empty());
+        assert!(paths("$.a[0]", &doc).is_empty());
+    }
+
+    #[test]
+    fn overlapping_descendant_matches_are_reported_once() {
+        let doc = node!({"a": {"a": {"x": 1}}});
+        assert_eq!(paths("$..a..x", &doc), ["[a a x]"]);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for query in
+            ["$", "$.a.b-c[0][*].*", "$..x..*..[1]", "$['a b']['it\\'s']['\\\\']", "$['0']"]
+        {
+            let parsed = JsonPath::parse(query).unwrap();
+            assert_eq!(parsed.to_string(), query);
+            assert_eq!(JsonPath::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+        assert_eq!(JsonPath::parse(r#"$[ "a" ]['b']"#).unwrap().to_string(), "$.a.b");
+    }
+
+    #[test]
+    fn malformed_queries_are_rejected() {
+        let error = |query: &str| JsonPath::parse(query).unwrap_err().to_string();
+        assert_eq!(error(""), r#"invalid JSONPath "": unexpected end at offset 0"#);
+        assert_eq!(error("a"), r#"invalid JSONPath "a": expected '$' at offset 0, found 'a'"#);
+        assert_eq!(error("$."), r#"invalid JSONPath "$.": unexpected end at offset 2"#);
+        assert_eq!(error("$.a]"), r#"invalid JSONPath "$.a]": unexpected ']' at offset 3"#);
+        assert_eq!(
+            error("$[1:2]"),
+            r#"invalid JSONPath "$[1:2]": expected ']' at offset 3, found ':'"#
+        );
+        assert_eq!(error("$['a"), r#"invalid JSONPath "$['a": unexpected end at offset 4"#);
+        assert_eq!(error("$[-]"), r#"invalid JSONPath "$[-]": invalid index "-" at offset 2"#);
+    }
+}
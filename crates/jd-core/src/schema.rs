@@ -0,0 +1,282 @@
+//! Path options derived from JSON Schema annotations.
+//!
+//! Only the keywords that say something about how values should be compared
+//! are read: `multipleOf` sets a numeric precision, and `properties`,
+//! `additionalProperties`, `items`, `prefixItems`, `allOf`, `anyOf`,
+//! `oneOf`, and local `$ref`s are followed to find where it applies.
+//! `x-jd-set-keys` names the members identifying an array's elements; until
+//! the diff engine implements set semantics they anchor the list alignment.
+//! `uniqueItems: true` is skipped with a warning: list mode still reports
+//! every change to such an array, only not as a set difference.
+
+use crate::{ArrayMode, Node, OptionsError, PathMatcher, PathOption, PathSetting};
+
+/// Keyword marking the object keys that identify set elements.
+const SET_KEYS: &str = "x-jd-set-keys";
+
+pub(crate) fn path_options(schema: &Node) -> Result<Vec<PathOption>, OptionsError> {
+    let mut deriver = Deriver { root: schema, options: Vec::new(), refs: Vec::new() };
+    deriver.visit(schema, &mut Vec::new(), "#")?;
+    Ok(deriver.options)
+}
+
+struct Deriver<'a> {
+    root: &'a Node,
+    options: Vec<PathOption>,
+    /// `$ref`s being expanded, to stop at recursive schemas.
+    refs: Vec<&'a str>,
+}
+
+impl<'a> Deriver<'a> {
+    fn visit(
+        &mut self,
+        schema: &'a Node,
+        at: &mut Vec<PathMatcher>,
+        pointer: &str,
+    ) -> Result<(), OptionsError> {
+        let Node::Object(keywords) = schema else {
+            return Ok(());
+        };
+        let invalid = |keyword: &str, reason: &str| OptionsError::InvalidSchema {
+            pointer: format!("{pointer}/{keyword}"),
+            reason: reason.to_string(),
+        };
+
+        match keywords.get("uniqueItems") {
+            Some(Node::Bool(true)) => tracing::warn!(
+                "ignoring uniqueItems at {pointer}/uniqueItems: \
+                 set semantics are not implemented in the diff engine yet"
+            ),
+            Some(Node::Bool(false)) | None => {}
+            Some(_) => return Err(invalid("uniqueItems", "expected a boolean")),
+        }
+        let mut settings = Vec::new();
+        match keywords.get(SET_KEYS) {
+            Some(Node::Array(keys)) => {
+                let keys = keys
+                    .iter()
+                    .map(|key| match key {
+                        Node::String(key) => Ok(key.clone()),
+                        _ => Err(invalid(SET_KEYS, "expected an array of member names")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                // Set keys followed by list mode only anchor the alignment.
+                settings.push(PathSetting::SetKeys(keys));
+                settings.push(PathSetting::ArrayMode(ArrayMode::List));
+            }
+            Some(_) => return Err(invalid(SET_KEYS, "expected an array of member names")),
+            None => {}
+        }
+        match keywords.get("multipleOf") {
+            Some(Node::Number(step)) if step.get() > 0.0 => {
+                // Values on the grid are at least one step apart; anything
+                // closer is floating-point noise around the same value.
+                settings.push(PathSetting::Precision(step.get() / 2.0));
+            }
+            Some(_) => return Err(invalid("multipleOf", "expected a positive number")),
+            None => {}
+        }
+        if !settings.is_empty() {
+            let option = settings
+                .into_iter()
+                .fold(PathOption::new(at.iter().cloned()), PathOption::with_setting);
+            self.options.push(option);
+        }
+
+        if let Some(reference) = keywords.get("$ref") {
+            let Node::String(reference) = reference else {
+                return Err(invalid("$ref", "expected a string"));
+            };
+            if let Some(target) = reference.strip_prefix('#') {
+                if !self.refs.contains(&reference.as_str()) {
+                    let resolved = resolve(self.root, target)
+                        .ok_or_else(|| invalid("$ref", "reference does not resolve"))?;
+                    self.refs.push(reference);
+                    self.visit(resolved, at, reference)?;
+                    self.refs.pop();
+                }
+            }
+        }
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            match keywords.get(keyword) {
+                Some(Node::Array(branches)) => {
+                    for (index, branch) in branches.iter().enumerate() {
+                        self.visit(branch, at, &format!("{pointer}/{keyword}/{index}"))?;
+                    }
+                }
+                Some(_) => return Err(invalid(keyword, "expected an array of schemas")),
+                None => {}
+            }
+        }
+
+        // Catch-all keywords first, so options for specific members and
+        // positions are added later and take precedence.
+        if let Some(schema) = keywords.get("additionalProperties") {
+            let pointer = format!("{pointer}/additionalProperties");
+            self.visit_child(schema, at, PathMatcher::AnyKey, &pointer)?;
+        }
+        match keywords.get("properties") {
+            Some(Node::Object(properties)) => {
+                for (name, schema) in properties {
+                    let pointer = format!("{pointer}/properties/{}", escape(name));
+                    self.visit_child(schema, at, PathMatcher::key(name), &pointer)?;
+                }
+            }
+            Some(_) => return Err(invalid("properties", "expected an object")),
+            None => {}
+        }
+        match keywords.get("items") {
+            // Draft 4 to 2019-09 tuple validation.
+            Some(Node::Array(items)) => self.visit_tuple(items, at, pointer, "items")?,
+            Some(schema) => {
+                let pointer = format!("{pointer}/items");
+                self.visit_child(schema, at, PathMatcher::AnyIndex, &pointer)?;
+            }
+            None => {}
+        }
+        match keywords.get("prefixItems") {
+            Some(Node::Array(items)) => self.visit_tuple(items, at, pointer, "prefixItems")?,
+            Some(_) => return Err(invalid("prefixItems", "expected an array of schemas")),
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn visit_child(
+        &mut self,
+        schema: &'a Node,
+        at: &mut Vec<PathMatcher>,
+        matcher: PathMatcher,
+        pointer: &str,
+    ) -> Result<(), OptionsError> {
+        at.push(matcher);
+        let result = self.visit(schema, at, pointer);
+        at.pop();
+        result
+    }
+
+    fn visit_tuple(
+        &mut self,
+        items: &'a [Node],
+        at: &mut Vec<PathMatcher>,
+        pointer: &str,
+        keyword: &str,
+    ) -> Result<(), OptionsError> {
+        for (index, schema) in items.iter().enumerate() {
+            let pointer = format!("{pointer}/{keyword}/{index}");
+            self.visit_child(schema, at, PathMatcher::Index(index as i64), &pointer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves the JSON Pointer fragment of a local `$ref` such as `/$defs/item`.
+fn resolve<'a>(root: &'a Node, pointer: &str) -> Option<&'a Node> {
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    pointer.strip_prefix('/')?.split('/').try_fold(root, |node, token| {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match node {
+            Node::Object(map) => map.get(&token),
+            Node::Array(items) => items.get(token.parse::<usize>().ok()?),
+            _ => None,
+        }
+    })
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiffOptions;
+
+    fn derive(schema: &str) -> Result<Vec<PathOption>, OptionsError> {
+        path_options(&Node::from_json_str(schema).unwrap())
+    }
+
+    #[test]
+    fn multiple_of_maps_to_precision_at_its_path() {
+        let options = derive(
+            r##"{
+                "properties": {
+                    "tags": {"type": "array", "uniqueItems": false},
+                    "users": {"$ref": "#/$defs/users"},
+                    "price": {"multipleOf": 0.01}
+                },
+                "$defs": {"users": {"items": {"$ref": "#/$defs/user"}},
+                          "user": {"properties": {"friends": {"$ref": "#/$defs/users"},
+                                                  "score": {"multipleOf": 1}}}}
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            [
+                PathOption::new([PathMatcher::key("price")])
+                    .with_setting(PathSetting::Precision(0.005)),
+                PathOption::new([
+                    PathMatcher::key("users"),
+                    PathMatcher::AnyIndex,
+                    PathMatcher::key("score")
+                ])
+                .with_setting(PathSetting::Precision(0.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_keywords_anchor_lists_and_unique_items_is_skipped() {
+        let schema = r#"{"properties": {
+            "tags": {"uniqueItems": true, "items": {"multipleOf": 0.5}},
+            "users": {"uniqueItems": true, "x-jd-set-keys": ["id"]}
+        }}"#;
+        let options = derive(schema).unwrap();
+        assert_eq!(
+            options,
+            [
+                PathOption::new([PathMatcher::key("tags"), PathMatcher::AnyIndex])
+                    .with_setting(PathSetting::Precision(0.25)),
+                PathOption::new([PathMatcher::key("users")])
+                    .with_setting(PathSetting::SetKeys(vec!["id".to_string()]))
+                    .with_setting(PathSetting::ArrayMode(ArrayMode::List)),
+            ]
+        );
+        // The derived options diff in list mode.
+        let options =
+            DiffOptions::default().with_json_schema(&Node::from_json_str(schema).unwrap()).unwrap();
+        let lhs = crate::node!({"tags": [1.0], "users": [{"id": 1, "v": 1}, {"id": 2}]});
+        let rhs = crate::node!({"tags": [1.2], "users": [{"id": 0}, {"id": 1, "v": 2}, {"id": 2}]});
+        let paths: Vec<_> = lhs.diff(&rhs, &options).iter().map(|e| e.path.to_string()).collect();
+        assert_eq!(paths, ["[users 0]", "[users 1 v]"]);
+        let err = derive(r#"{"items": {"x-jd-set-keys": "id"}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid JSON Schema at #/items/x-jd-set-keys: expected an array of member names"
+        );
+    }
+
+    #[test]
+    fn tuples_and_composition_are_followed() {
+        let options = derive(
+            r#"{"allOf": [{"prefixItems": [{}, {"multipleOf": 2}]}, {"items": [{"multipleOf": 4}]}]}"#,
+        )
+        .unwrap();
+        let at: Vec<_> = options.iter().map(|option| option.at().to_vec()).collect();
+        assert_eq!(at, [[PathMatcher::Index(1)], [PathMatcher::Index(0)]]);
+    }
+
+    #[test]
+    fn malformed_keywords_are_reported_with_their_location() {
+        let err = derive(r#"{"properties": {"a/b": {"multipleOf": 0}}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid JSON Schema at #/properties/a~1b/multipleOf: expected a positive number"
+        );
+        let err = derive(r##"{"$ref": "#/definitions/missing"}"##).unwrap_err();
+        assert_eq!(err.to_string(), "invalid JSON Schema at #/$ref: reference does not resolve");
+    }
+}
@@ -0,0 +1,135 @@
+//! JSON parsing with simd-json.
+
+use std::collections::BTreeMap;
+
+use simd_json::{Node as TapeNode, StaticNode};
+
+use crate::{CanonicalizeError, Node, Number};
+
+/// Parses `input` with simd-json, building the node straight from its tape
+/// of values. `None` when simd-json rejects the input, so the caller parses
+/// it again with serde_json, which reports errors with the usual messages
+/// and reads integers too large for 64 bits as doubles.
+pub(crate) fn parse(input: &str) -> Option<Result<Node, CanonicalizeError>> {
+    if has_negative_integer_zero(input.as_bytes()) {
+        return None;
+    }
+    // simd-json parses in place, unescaping strings into its own buffer.
+    let mut bytes = input.as_bytes().to_vec();
+    let tape = simd_json::to_tape(&mut bytes).ok()?;
+    Some(from_tape(&tape.0))
+}
+
+/// Containers being filled, with the number of values they still expect.
+enum Frame {
+    Array { items: Vec<Node>, left: usize },
+    Object { object: BTreeMap<String, Node>, key: Option<String>, left: usize },
+}
+
+/// Rebuilds a node from a tape, where each container is followed by its
+/// values, and each object value by its key, in document order.
+fn from_tape(tape: &[TapeNode<'_>]) -> Result<Node, CanonicalizeError> {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut nodes = tape.iter();
+    loop {
+        let Some(node) = nodes.next() else {
+            unreachable!("a tape from simd-json holds a complete value");
+        };
+        if let Some(Frame::Object { key: key @ None, .. }) = stack.last_mut() {
+            let TapeNode::String(name) = node else {
+                unreachable!("object keys are strings");
+            };
+            *key = Some((*name).to_owned());
+            continue;
+        }
+        let mut done = match *node {
+            TapeNode::String(s) => Some(Node::String(s.to_owned())),
+            TapeNode::Static(value) => Some(scalar(value)?),
+            TapeNode::Array { len: 0, .. } => Some(Node::Array(Vec::new())),
+            TapeNode::Object { len: 0, .. } => Some(Node::Object(BTreeMap::new())),
+            TapeNode::Array { len, .. } => {
+                stack.push(Frame::Array { items: Vec::with_capacity(len), left: len });
+                None
+            }
+            TapeNode::Object { len, .. } => {
+                stack.push(Frame::Object { object: BTreeMap::new(), key: None, left: len });
+                None
+            }
+        };
+        while let Some(value) = done.take() {
+            let left = match stack.last_mut() {
+                None => return Ok(value),
+                Some(Frame::Array { items, left }) => {
+                    items.push(value);
+                    left
+                }
+                // A repeated key keeps its last value, as with serde_json.
+                Some(Frame::Object { object, key, left }) => {
+                    object.insert(key.take().expect("key precedes value"), value);
+                    left
+                }
+            };
+            *left -= 1;
+            if *left == 0 {
+                done = stack.pop().map(|frame| match frame {
+                    Frame::Array { items, .. } => Node::Array(items),
+                    Frame::Object { object, .. } => Node::Object(object),
+                });
+            }
+        }
+    }
+}
+
+/// simd-json reads the integer `-0` as `0`, dropping the sign that
+/// serde_json keeps. Strings are not skipped, so text such as `" -0"` also
+/// takes the slower path, which is still correct.
+fn has_negative_integer_zero(input: &[u8]) -> bool {
+    input.windows(2).enumerate().any(|(at, window)| {
+        window == b"-0"
+            && !matches!(input.get(at.wrapping_sub(1)), Some(b) if b.is_ascii_digit())
+            && !matches!(input.get(at + 2), Some(b'0'..=b'9' | b'.' | b'e' | b'E'))
+    })
+}
+
+fn scalar(value: StaticNode) -> Result<Node, CanonicalizeError> {
+    Ok(match value {
+        StaticNode::Null => Node::Null,
+        StaticNode::Bool(v) => Node::Bool(v),
+        StaticNode::I64(v) => Node::Number(Number::new(v as f64)?),
+        StaticNode::U64(v) => Node::Number(Number::new(v as f64)?),
+        StaticNode::F64(v) => Node::Number(Number::new(v)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_serde_json() {
+        for input in [
+            r#"{"a": [0.1, -0.0, 1e-300, 2.2250738585072014e-308, 123456789.123456789], "a": "dup"}"#,
+            r#"["é\n\"", 9007199254740993, -9223372036854775808, 18446744073709551615]"#,
+            r#"{"b": {"c": [[], {}, [null, true, {"d": false}]]}, "a": {}}"#,
+            r#"[-0.0, -0e1, "2024-01-02"]"#,
+            " 3 ",
+        ] {
+            let expected = Node::from_json_value(serde_json::from_str(input).unwrap()).unwrap();
+            assert_eq!(parse(input).expect("simd-json parses").unwrap(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn rejected_input_falls_back_to_serde_json() {
+        assert!(parse("[1, -0]").is_none());
+        assert_eq!(Node::from_json_str("-0").unwrap(), Node::from(-0.0));
+
+        let big = "[100000000000000000000000000]";
+        assert!(parse(big).is_none());
+        assert_eq!(Node::from_json_str(big).unwrap(), Node::Array(vec![Node::from(1e26)]));
+
+        let err = Node::from_json_str("[1,\n 2,]").unwrap_err();
+        let expected = serde_json::from_str::<serde_json::Value>("[1,\n 2,]").unwrap_err();
+        assert_eq!(err.to_string(), CanonicalizeError::Json(expected).to_string());
+    }
+}
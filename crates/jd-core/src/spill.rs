@@ -0,0 +1,231 @@
+//! Sorting element hashes on disk for arrays compared as sets or multisets.
+//!
+//! Unordered comparison only needs each array's element hashes in sorted
+//! order. Once those hashes take more than
+//! [`DiffOptions::with_spill_threshold`] bytes, they are sorted in runs of
+//! that size, each run is written to an anonymous temporary file, and the runs are merged back as
+//! one sorted stream. Only these hash buffers leave memory: the documents
+//! are borrowed throughout, and list-mode diffs, including their alignment
+//! tables, never spill. The files have no name to guess or leak and vanish
+//! when the stream is dropped. Any I/O failure makes the caller fall back
+//! to sorting in memory.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use crate::hash::Digest;
+use crate::node::{combine_digests, element_hashes};
+use crate::{DiffOptions, Node};
+
+const HASH_LEN: usize = std::mem::size_of::<Digest>();
+
+/// Runs never hold fewer hashes than this, so tiny thresholds do not open
+/// a file per element.
+const MIN_RUN: usize = 4096;
+
+/// Compares the sorted element hashes of two arrays, ignoring repeats when
+/// `unique` is set. `None` when the arrays are under the threshold or
+/// spilling fails.
+pub(crate) fn equal(
+    lhs: &[Node],
+    rhs: &[Node],
+    options: &DiffOptions,
+    unique: bool,
+) -> Option<bool> {
+    let threshold = over_threshold(lhs.len() + rhs.len(), options)?;
+    let mut left = sort(lhs, options, threshold / 2)?;
+    let mut right = sort(rhs, options, threshold / 2)?;
+    let equal = if unique {
+        dedup(&mut left).eq(dedup(&mut right))
+    } else {
+        left.by_ref().eq(right.by_ref())
+    };
+    (!left.failed && !right.failed).then_some(equal)
+}
+
+/// Hashes the sorted element hashes of an array like [`combine_digests`].
+/// `None` when the array is under the threshold or spilling fails.
+pub(crate) fn combine(values: &[Node], options: &DiffOptions, unique: bool) -> Option<Digest> {
+    let threshold = over_threshold(values.len(), options)?;
+    let mut sorted = sort(values, options, threshold)?;
+    let hash = if unique {
+        combine_digests(dedup(&mut sorted), options)
+    } else {
+        combine_digests(&mut sorted, options)
+    };
+    (!sorted.failed).then_some(hash)
+}
+
+fn over_threshold(elements: usize, options: &DiffOptions) -> Option<usize> {
+    options.spill_threshold().filter(|&threshold| elements.saturating_mul(HASH_LEN) > threshold)
+}
+
+fn dedup(hashes: impl Iterator<Item = Digest>) -> impl Iterator<Item = Digest> {
+    let mut last = None;
+    hashes.filter(move |hash| last.replace(*hash) != Some(*hash))
+}
+
+fn sort(values: &[Node], options: &DiffOptions, run_bytes: usize) -> Option<Sorted> {
+    let capacity = (run_bytes / HASH_LEN).max(MIN_RUN);
+    let mut runs = Vec::new();
+    let mut buffer = Vec::with_capacity(capacity.min(values.len()));
+    for hash in element_hashes(values, options) {
+        buffer.push(hash);
+        if buffer.len() == capacity {
+            buffer.sort_unstable();
+            runs.push(
+                Run::write(&buffer)
+                    .map_err(|err| tracing::warn!("cannot spill element hashes: {err}"))
+                    .ok()?,
+            );
+            buffer.clear();
+        }
+    }
+    buffer.sort_unstable();
+    tracing::debug!(elements = values.len(), runs = runs.len(), "spilled element hashes");
+    Some(Sorted::new(buffer, runs))
+}
+
+/// A sorted run of hashes in an anonymous temporary file, which the
+/// operating system reclaims once it is closed.
+struct Run {
+    reader: BufReader<File>,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Runs written by this thread, so tests can tell that hashes spilled.
+    static RUNS_WRITTEN: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl Run {
+    fn write(hashes: &[Digest]) -> io::Result<Self> {
+        #[cfg(test)]
+        RUNS_WRITTEN.with(|runs| runs.set(runs.get() + 1));
+        let mut file = tempfile::tempfile()?;
+        let mut writer = BufWriter::new(&mut file);
+        hashes.iter().try_for_each(|hash| writer.write_all(hash))?;
+        writer.flush()?;
+        drop(writer);
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self { reader: BufReader::new(file) })
+    }
+
+    fn read(&mut self) -> io::Result<Option<Digest>> {
+        let mut hash = [0; HASH_LEN];
+        match self.reader.read_exact(&mut hash) {
+            Ok(()) => Ok(Some(hash)),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Merges the spilled runs and the hashes left in memory into one sorted
+/// stream. A read error ends the stream early and sets `failed`.
+struct Sorted {
+    memory: std::vec::IntoIter<Digest>,
+    runs: Vec<Run>,
+    /// The next hash of each source; source `runs.len()` is `memory`.
+    heads: BinaryHeap<Reverse<(Digest, usize)>>,
+    failed: bool,
+}
+
+impl Sorted {
+    fn new(memory: Vec<Digest>, runs: Vec<Run>) -> Self {
+        let mut sorted =
+            Self { memory: memory.into_iter(), runs, heads: BinaryHeap::new(), failed: false };
+        for source in 0..=sorted.runs.len() {
+            sorted.advance(source);
+        }
+        sorted
+    }
+
+    fn advance(&mut self, source: usize) {
+        let next = match self.runs.get_mut(source) {
+            Some(run) => run.read().unwrap_or_else(|err| {
+                tracing::warn!("cannot read spilled element hashes: {err}");
+                self.failed = true;
+                None
+            }),
+            None => self.memory.next(),
+        };
+        if let Some(hash) = next {
+            self.heads.push(Reverse((hash, source)));
+        }
+    }
+}
+
+impl Iterator for Sorted {
+    type Item = Digest;
+
+    fn next(&mut self) -> Option<Digest> {
+        if self.failed {
+            return None;
+        }
+        let Reverse((hash, source)) = self.heads.pop()?;
+        self.advance(source);
+        Some(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayMode, PathMatcher, PathOption, PathSetting};
+
+    fn numbers(values: impl Iterator<Item = i64>) -> Vec<Node> {
+        values.map(Node::from).collect()
+    }
+
+    #[test]
+    fn merged_runs_match_in_memory_results() {
+        let options = DiffOptions::default().with_array_mode(ArrayMode::MultiSet).unwrap();
+        let spilled = options.clone().with_spill_threshold(1);
+        let lhs = numbers((0..10_000).map(|n| n % 7_000));
+        let mut rhs = lhs.clone();
+        rhs.reverse();
+
+        assert_eq!(equal(&lhs, &rhs, &spilled, false), Some(true));
+        assert_eq!(equal(&lhs, &rhs[1..], &spilled, true), Some(true));
+        assert_eq!(equal(&lhs, &numbers(0..10_000), &spilled, false), Some(false));
+        assert_eq!(combine(&lhs, &spilled, false), Some(Node::Array(lhs.clone()).digest(&options)));
+        assert_eq!(equal(&lhs, &rhs, &options, false), None);
+    }
+
+    #[test]
+    fn threshold_applies_to_nested_arrays_through_equality_and_hashing() {
+        let tags = PathOption::new([PathMatcher::key("tags")])
+            .with_setting(PathSetting::ArrayMode(ArrayMode::MultiSet));
+        let options = DiffOptions::default().with_path_option(tags).unwrap();
+        let spilled = options.clone().with_spill_threshold(1024);
+        let document = |tags: Vec<Node>| {
+            Node::Object(
+                [("name".to_string(), Node::from("a")), ("tags".to_string(), Node::Array(tags))]
+                    .into(),
+            )
+        };
+        let lhs = document(numbers(0..20_000));
+        let rhs = document(numbers((0..20_000).rev()));
+        let changed = document(numbers(1..20_001));
+
+        let before = RUNS_WRITTEN.with(std::cell::Cell::get);
+        assert!(crate::equal(&lhs, &rhs, &spilled));
+        assert!(!crate::equal(&lhs, &changed, &spilled));
+        assert_eq!(lhs.hash_code(&spilled), rhs.hash_code(&options));
+        assert_ne!(lhs.hash_code(&spilled), changed.hash_code(&spilled));
+        assert!(RUNS_WRITTEN.with(std::cell::Cell::get) > before);
+    }
+
+    #[test]
+    fn spilled_runs_merge_with_memory() {
+        let runs = vec![
+            Run::write(&[[1; HASH_LEN], [4; HASH_LEN]]).unwrap(),
+            Run::write(&[[2; HASH_LEN]]).unwrap(),
+        ];
+        let sorted = Sorted::new(vec![[0; HASH_LEN], [3; HASH_LEN]], runs);
+        assert_eq!(sorted.map(|hash| hash[0]).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    }
+}
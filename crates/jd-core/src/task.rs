@@ -0,0 +1,213 @@
+//! Futures for diffing and patching off the caller's thread.
+//!
+//! Diffing large documents is CPU-bound and can take long enough to stall
+//! an async executor. The functions here move the work to a dedicated
+//! thread and return a [`Task`], a future that resolves with the result.
+//! Tasks work under any executor, and `jd-core` takes no runtime
+//! dependency. Callers that already run on tokio can equally wrap the
+//! synchronous API in `tokio::task::spawn_blocking`.
+//!
+//! ```
+//! use jd_core::{node, task, Jd};
+//!
+//! async fn handler(jd: &Jd) -> String {
+//!     let diff = task::diff(jd, node!({"a": 1}), node!({"a": 2})).await.unwrap();
+//!     diff.render(&jd.render_config())
+//! }
+//!
+//! // Outside async code, `Task::wait` blocks until the result is ready.
+//! let diff = task::diff(&Jd::new(), node!([1, 2]), node!([1, 3])).wait().unwrap();
+//! let patched = task::patch(node!([1, 2]), diff).wait().unwrap();
+//! assert_eq!(patched, node!([1, 3]));
+//! ```
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::{Diff, Jd, JdError, Node, PatchError};
+
+/// Work running on its own thread. Awaiting the task yields the work's
+/// result; a panic in the work resumes in the awaiting task.
+///
+/// Dropping a task detaches it: the work runs to completion and its result
+/// is discarded.
+#[must_use = "a task does nothing useful unless awaited or waited on"]
+#[derive(Debug)]
+pub struct Task<T> {
+    shared: Arc<Shared<T>>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    done: Condvar,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Runs `work` on a new thread.
+    ///
+    /// ```
+    /// let task = jd_core::task::Task::spawn(|| 6 * 7);
+    /// assert_eq!(task.wait(), 42);
+    /// ```
+    pub fn spawn<F>(work: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State { result: None, waker: None }),
+            done: Condvar::new(),
+        });
+        let worker = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("jd-task".to_string())
+            .spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(work));
+                let mut state = lock(&worker.state);
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+                worker.done.notify_all();
+            })
+            .expect("failed to spawn a jd task thread");
+        Self { shared }
+    }
+}
+
+impl<T> Task<T> {
+    /// Reports whether the work has finished, so that awaiting the task
+    /// would not wait.
+    pub fn is_finished(&self) -> bool {
+        lock(&self.shared.state).result.is_some()
+    }
+
+    /// Blocks the current thread until the work finishes and returns its
+    /// result, for callers outside async code.
+    pub fn wait(self) -> T {
+        let mut state = lock(&self.shared.state);
+        loop {
+            if let Some(result) = state.result.take() {
+                return unwrap(result);
+            }
+            state = self.shared.done.wait(state).unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = lock(&self.shared.state);
+        match state.result.take() {
+            Some(result) => Poll::Ready(unwrap(result)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The state is only written while the lock is held and never left half
+/// updated, so a poisoned lock is still consistent.
+fn lock<T>(state: &Mutex<State<T>>) -> MutexGuard<'_, State<T>> {
+    state.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn unwrap<T>(result: thread::Result<T>) -> T {
+    result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+/// Diffs `lhs` against `rhs` with [`Jd::diff`] on a new thread, under the
+/// facade's options and limits.
+///
+/// ```
+/// use jd_core::{node, task, Jd, Limits};
+///
+/// let jd = Jd::new().limits(Limits::default().with_max_nodes(2));
+/// assert!(task::diff(&jd, node!([1, 2, 3]), node!([])).wait().is_err());
+/// ```
+pub fn diff(jd: &Jd, lhs: Node, rhs: Node) -> Task<Result<Diff, JdError>> {
+    let jd = jd.clone();
+    Task::spawn(move || jd.diff(&lhs, &rhs))
+}
+
+/// Applies `diff` to `node` with [`Node::apply_patch`] on a new thread.
+///
+/// ```
+/// use jd_core::{node, task, Diff};
+///
+/// let diff = Diff::from_native_str("@ [\"a\"]\n- 1\n+ 2\n").unwrap();
+/// assert_eq!(task::patch(node!({"a": 1}), diff).wait().unwrap(), node!({"a": 2}));
+/// ```
+pub fn patch(node: Node, diff: Diff) -> Task<Result<Node, PatchError>> {
+    Task::spawn(move || node.apply_patch(&diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::task::Wake;
+
+    struct Notify(mpsc::Sender<()>);
+
+    impl Wake for Notify {
+        fn wake(self: Arc<Self>) {
+            let _ = self.0.send(());
+        }
+    }
+
+    /// Polls `future` to completion, sleeping until each wake-up.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let (sender, woken) = mpsc::channel();
+        let waker = Waker::from(Arc::new(Notify(sender)));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            woken.recv().unwrap();
+        }
+    }
+
+    #[test]
+    fn awaiting_yields_the_result_after_a_wake() {
+        let (release, gate) = mpsc::channel::<()>();
+        let task = Task::spawn(move || {
+            gate.recv().unwrap();
+            "done"
+        });
+        assert!(!task.is_finished());
+        release.send(()).unwrap();
+        assert_eq!(block_on(task), "done");
+    }
+
+    #[test]
+    fn diff_and_patch_round_trip() {
+        let lhs = crate::node!({"a": [1, 2, 3]});
+        let rhs = crate::node!({"a": [1, 4, 3]});
+        let diff = block_on(diff(&Jd::new(), lhs.clone(), rhs.clone())).unwrap();
+        assert_eq!(block_on(patch(lhs, diff)).unwrap(), rhs);
+    }
+
+    #[test]
+    fn panics_resume_in_the_waiting_caller() {
+        let task = Task::spawn(|| -> u8 { panic!("boom") });
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| task.wait())).unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Helpers for Terraform state files and `terraform show -json` plans.
+//!
+//! Terraform lists resources in arrays, so adding one resource shifts the
+//! position of every resource after it and a list diff reports them all.
+//! [`key_by_address`] turns those arrays into objects keyed by resource
+//! address, so diffs name the resource that changed. [`mask_sensitive`]
+//! hides the values Terraform marks sensitive before a diff can print them.
+//! [`Preset::Terraform`](crate::Preset::Terraform) applies both.
+//!
+//! ```
+//! use jd_core::{node, terraform, DiffOptions, RenderConfig};
+//!
+//! let state = |acl: &str| node!({"resources": [{
+//!     "mode": "managed", "type": "aws_s3_bucket", "name": "logs",
+//!     "instances": [{"attributes": {"acl": acl, "password": "hunter2"},
+//!         "sensitive_attributes": [[{"type": "get_attr", "value": "password"}]]}]
+//! }]});
+//! let (before, after) = (terraform::normalize(&state("private")), terraform::normalize(&state("public")));
+//! let diff = before.diff(&after, &DiffOptions::default()).render(&RenderConfig::default());
+//! assert_eq!(diff, "@ [\"resources\",\"aws_s3_bucket.logs\",\"attributes\",\"acl\"]\n- \"private\"\n+ \"public\"\n");
+//! assert_eq!(after["resources"]["aws_s3_bucket.logs"]["attributes"]["password"], node!("(sensitive value)"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{Node, Path, PathSegment};
+
+/// The string that replaces sensitive values, as Terraform prints them.
+pub const SENSITIVE_VALUE: &str = "(sensitive value)";
+
+/// Masks sensitive values, then keys resources by address.
+///
+/// ```
+/// # use jd_core::{node, terraform};
+/// let plan = node!({"resource_changes": [{"address": "random_password.db",
+///     "change": {"after": {"result": "s3cr3t"}, "after_sensitive": {"result": true}}}]});
+/// let normalized = terraform::normalize(&plan);
+/// assert_eq!(normalized["resource_changes"]["random_password.db"]["change"]["after"]["result"], node!("(sensitive value)"));
+/// ```
+#[must_use]
+pub fn normalize(document: &Node) -> Node {
+    key_by_address(&mask_sensitive(document))
+}
+
+/// Returns a copy of a state or plan with the values Terraform marks
+/// sensitive replaced by [`SENSITIVE_VALUE`].
+///
+/// Masked are state `outputs` with `"sensitive": true`, the instance
+/// `attributes` listed in `sensitive_attributes`, and the parts of a plan's
+/// `resource_changes` and `resource_drift` marked in `before_sensitive` and
+/// `after_sensitive`. Changed secrets therefore compare equal.
+///
+/// ```
+/// # use jd_core::{node, terraform};
+/// let state = node!({"outputs": {"token": {"value": "abc", "sensitive": true},
+///     "url": {"value": "https://example.com", "sensitive": false}}});
+/// let masked = terraform::mask_sensitive(&state);
+/// assert_eq!(masked["outputs"]["token"]["value"], node!("(sensitive value)"));
+/// assert_eq!(masked["outputs"]["url"]["value"], node!("https://example.com"));
+/// ```
+#[must_use]
+pub fn mask_sensitive(document: &Node) -> Node {
+    let mut masked = document.clone();
+    let Node::Object(root) = &mut masked else {
+        return masked;
+    };
+    if let Some(Node::Object(outputs)) = root.get_mut("outputs") {
+        for output in outputs.values_mut() {
+            if let Node::Object(output) = output {
+                if output.get("sensitive") == Some(&Node::Bool(true)) {
+                    if let Some(value) = output.get_mut("value") {
+                        *value = sensitive();
+                    }
+                }
+            }
+        }
+    }
+    if let Some(Node::Array(resources)) = root.get_mut("resources") {
+        let instances = resources.iter_mut().filter_map(|resource| match resource {
+            Node::Object(resource) => match resource.get_mut("instances") {
+                Some(Node::Array(instances)) => Some(instances),
+                _ => None,
+            },
+            _ => None,
+        });
+        for instance in instances.flatten() {
+            let Node::Object(instance) = instance else { continue };
+            let paths: Vec<Path> = match instance.get("sensitive_attributes") {
+                Some(Node::Array(paths)) => paths.iter().filter_map(attribute_path).collect(),
+                _ => continue,
+            };
+            if let Some(attributes) = instance.get_mut("attributes") {
+                *attributes = attributes.redact(&paths, &sensitive());
+            }
+        }
+    }
+    for key in ["resource_changes", "resource_drift"] {
+        let Some(Node::Array(changes)) = root.get_mut(key) else { continue };
+        for change in changes {
+            let Node::Object(change) = change else { continue };
+            let Some(Node::Object(change)) = change.get_mut("change") else { continue };
+            for (value, marks) in [("before", "before_sensitive"), ("after", "after_sensitive")] {
+                let Some(marks) = change.get(marks).cloned() else { continue };
+                if let Some(value) = change.get_mut(value) {
+                    mask_marked(value, &marks);
+                }
+            }
+        }
+    }
+    masked
+}
+
+/// Returns a copy of a state or plan with its resource arrays replaced by
+/// objects keyed by resource address.
+///
+/// Each instance in a state's `resources` becomes a member named by
+/// [`instance_address`], holding the instance without `index_key` and
+/// `deposed` plus the resource's `provider`. A plan's `resource_changes`
+/// and `resource_drift` are keyed by their `address` member. An array with
+/// an element that has no address, or with two elements at one address,
+/// is left as it is.
+///
+/// ```
+/// # use jd_core::{node, terraform};
+/// let state = node!({"resources": [{"module": "module.web", "mode": "managed", "type": "aws_instance",
+///     "name": "app", "provider": "aws", "instances": [{"index_key": 0, "attributes": {"ami": "ami-1"}}]}]});
+/// let keyed = node!({"resources": {"module.web.aws_instance.app[0]": {"attributes": {"ami": "ami-1"},
+///     "provider": "aws"}}});
+/// assert_eq!(terraform::key_by_address(&state), keyed);
+/// ```
+#[must_use]
+pub fn key_by_address(document: &Node) -> Node {
+    let mut keyed = document.clone();
+    let Node::Object(root) = &mut keyed else {
+        return keyed;
+    };
+    if let Some(resources) = root.get_mut("resources") {
+        if let Some(by_address) = state_instances(resources) {
+            *resources = by_address;
+        }
+    }
+    for key in ["resource_changes", "resource_drift"] {
+        if let Some(changes) = root.get_mut(key) {
+            if let Some(by_address) = plan_changes(changes) {
+                *changes = by_address;
+            }
+        }
+    }
+    keyed
+}
+
+/// Returns the address of an `instance` of a state `resource`, such as
+/// `module.db.data.aws_ami.base["eu"]`, or `None` if the resource lacks
+/// its `mode`, `type`, or `name`.
+///
+/// Deposed instances, left behind by `create_before_destroy`, are told
+/// apart by a ` (deposed object KEY)` suffix, as in Terraform's output.
+///
+/// ```
+/// # use jd_core::{node, terraform};
+/// let resource = node!({"mode": "data", "type": "aws_ami", "name": "base"});
+/// let address = terraform::instance_address(&resource, &node!({"index_key": "eu"}));
+/// assert_eq!(address.as_deref(), Some("data.aws_ami.base[\"eu\"]"));
+/// ```
+#[must_use]
+pub fn instance_address(resource: &Node, instance: &Node) -> Option<String> {
+    let mut address = String::new();
+    if let Some(module) = text(resource, "module") {
+        address.push_str(module);
+        address.push('.');
+    }
+    if text(resource, "mode")? == "data" {
+        address.push_str("data.");
+    }
+    address.push_str(text(resource, "type")?);
+    address.push('.');
+    address.push_str(text(resource, "name")?);
+    match member(instance, "index_key") {
+        Some(Node::String(key)) => {
+            address.push_str(&format!("[{}]", serde_json::Value::from(key.as_str())));
+        }
+        Some(Node::Number(index)) => address.push_str(&format!("[{}]", index.to_json_number())),
+        Some(_) => return None,
+        None => {}
+    }
+    Some(with_deposed(address, instance))
+}
+
+fn state_instances(resources: &Node) -> Option<Node> {
+    let Node::Array(resources) = resources else {
+        return None;
+    };
+    let mut by_address = BTreeMap::new();
+    for resource in resources {
+        let Some(Node::Array(instances)) = member(resource, "instances") else {
+            return None;
+        };
+        for instance in instances {
+            let address = instance_address(resource, instance)?;
+            let Node::Object(entry) = instance else {
+                return None;
+            };
+            let mut entry = entry.clone();
+            entry.remove("index_key");
+            entry.remove("deposed");
+            if let Some(provider) = member(resource, "provider") {
+                entry.insert("provider".to_string(), provider.clone());
+            }
+            if by_address.insert(address, Node::Object(entry)).is_some() {
+                return None;
+            }
+        }
+    }
+    Some(Node::Object(by_address))
+}
+
+fn plan_changes(changes: &Node) -> Option<Node> {
+    let Node::Array(changes) = changes else {
+        return None;
+    };
+    let mut by_address = BTreeMap::new();
+    for change in changes {
+        let address = with_deposed(text(change, "address")?.to_string(), change);
+        let Node::Object(entry) = change else {
+            return None;
+        };
+        let mut entry = entry.clone();
+        entry.remove("address");
+        entry.remove("deposed");
+        if by_address.insert(address, Node::Object(entry)).is_some() {
+            return None;
+        }
+    }
+    Some(Node::Object(by_address))
+}
+
+fn with_deposed(mut address: String, instance: &Node) -> String {
+    if let Some(key) = text(instance, "deposed") {
+        address.push_str(&format!(" (deposed object {key})"));
+    }
+    address
+}
+
+/// Converts a `sensitive_attributes` entry, a list of `get_attr` and
+/// `index` steps, to the path of the attribute it marks.
+fn attribute_path(steps: &Node) -> Option<Path> {
+    let Node::Array(steps) = steps else {
+        return None;
+    };
+    steps.iter().try_fold(Path::new(), |path, step| {
+        let segment = match (text(step, "type")?, member(step, "value")?) {
+            ("get_attr", Node::String(name)) => PathSegment::key(name.as_str()),
+            // Index values are typed: `{"value": 0, "type": "number"}`.
+            ("index", index) => match member(index, "value")? {
+                Node::String(key) => PathSegment::key(key.as_str()),
+                Node::Number(index) => PathSegment::index(index.get() as i64),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(path.with_segment(segment))
+    })
+}
+
+/// Masks the parts of `value` marked `true` in `marks`, a plan's
+/// `before_sensitive` or `after_sensitive` mirror of it.
+fn mask_marked(value: &mut Node, marks: &Node) {
+    let mut stack = vec![(value, marks)];
+    while let Some((value, marks)) = stack.pop() {
+        match (value, marks) {
+            (value, Node::Bool(true)) => *value = sensitive(),
+            (Node::Object(values), Node::Object(marks)) => stack.extend(
+                values.iter_mut().filter_map(|(key, value)| Some((value, marks.get(key)?))),
+            ),
+            (Node::Array(values), Node::Array(marks)) => stack.extend(values.iter_mut().zip(marks)),
+            _ => {}
+        }
+    }
+}
+
+fn sensitive() -> Node {
+    Node::String(SENSITIVE_VALUE.to_string())
+}
+
+fn member<'a>(node: &'a Node, key: &str) -> Option<&'a Node> {
+    match node {
+        Node::Object(map) => map.get(key),
+        _ => None,
+    }
+}
+
+fn text<'a>(node: &'a Node, key: &str) -> Option<&'a str> {
+    match member(node, key)? {
+        Node::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node;
+
+    #[test]
+    fn masks_sensitive_attributes_by_path() {
+        let state = node!({"resources": [{"mode": "managed", "type": "aws_db_instance", "name": "db",
+        "instances": [{"attributes": {"password": "a", "tags": {"secret": "b", "team": "c"}, "ports": [1, 2]},
+            "sensitive_attributes": [
+                [{"type": "get_attr", "value": "password"}],
+                [{"type": "get_attr", "value": "tags"}, {"type": "index", "value": {"value": "secret", "type": "string"}}],
+                [{"type": "get_attr", "value": "ports"}, {"type": "index", "value": {"value": 1, "type": "number"}}],
+                [{"type": "get_attr", "value": "missing"}]
+            ]}]}]});
+        let masked = mask_sensitive(&state);
+        let attributes = &masked["resources"][0]["instances"][0]["attributes"];
+        assert_eq!(
+            attributes,
+            &node!({"password": "(sensitive value)", "tags": {"secret": "(sensitive value)", "team": "c"},
+                "ports": [1, "(sensitive value)"]})
+        );
+    }
+
+    #[test]
+    fn masks_plan_values_marked_sensitive() {
+        let plan = node!({"resource_changes": [{"address": "aws_instance.a", "change": {
+            "before": {"tags": {"k": "v"}, "user_data": "x"},
+            "after": {"tags": {"k": "w"}, "user_data": "y", "ami": "ami-1"},
+            "before_sensitive": {"tags": true},
+            "after_sensitive": {"tags": {"k": true}, "user_data": false}
+        }}]});
+        let change = &mask_sensitive(&plan)["resource_changes"][0]["change"];
+        assert_eq!(change["before"], node!({"tags": "(sensitive value)", "user_data": "x"}));
+        assert_eq!(
+            change["after"],
+            node!({"tags": {"k": "(sensitive value)"}, "user_data": "y", "ami": "ami-1"})
+        );
+    }
+
+    #[test]
+    fn keys_instances_and_deposed_objects_apart() {
+        let state = node!({"resources": [{"mode": "managed", "type": "aws_instance", "name": "web", "instances": [
+            {"attributes": {"id": "i-1"}},
+            {"deposed": "00000001", "attributes": {"id": "i-0"}}
+        ]}]});
+        let keyed = key_by_address(&state);
+        assert_eq!(keyed["resources"]["aws_instance.web"]["attributes"]["id"], node!("i-1"));
+        assert_eq!(
+            keyed["resources"]["aws_instance.web (deposed object 00000001)"]["attributes"]["id"],
+            node!("i-0")
+        );
+    }
+
+    #[test]
+    fn leaves_arrays_without_unique_addresses_alone() {
+        let clash = node!({"resource_changes": [{"address": "a.b"}, {"address": "a.b"}], "resources": [{"type": "x"}]});
+        assert_eq!(key_by_address(&clash), clash);
+        assert_eq!(key_by_address(&node!([1])), node!([1]));
+    }
+}
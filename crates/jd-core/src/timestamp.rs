@@ -0,0 +1,211 @@
+//! ISO-8601 timestamps compared as instants.
+
+/// A date and time of day read from an ISO-8601 string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Timestamp {
+    /// Nanoseconds since the Unix epoch of the local date and time, read as
+    /// if it were UTC.
+    local_nanos: i128,
+    /// Offset from UTC in seconds, `None` for a local time without one.
+    offset: Option<i32>,
+}
+
+impl Timestamp {
+    /// Nanoseconds since the Unix epoch, or the local reading for a time
+    /// without an offset.
+    fn nanos(self) -> i128 {
+        self.local_nanos - i128::from(self.offset.unwrap_or(0)) * 1_000_000_000
+    }
+}
+
+/// Whether `lhs` and `rhs` are ISO-8601 date-times at most `tolerance`
+/// seconds apart.
+///
+/// Times with a UTC offset are compared as instants, so the same moment
+/// written in different time zones is equal. Times without one only equal
+/// other times without one, since their zone is unknown.
+pub(crate) fn within(lhs: &str, rhs: &str, tolerance: f64) -> bool {
+    let (Some(lhs), Some(rhs)) = (parse(lhs), parse(rhs)) else {
+        return false;
+    };
+    if lhs.offset.is_some() != rhs.offset.is_some() {
+        return false;
+    }
+    (lhs.nanos() - rhs.nanos()).unsigned_abs() as f64 <= tolerance * 1e9
+}
+
+/// Reads `YYYY-MM-DDTHH:MM[:SS[.fraction]][Z|±HH[:MM]]`. The separator may
+/// also be `t` or a space, `Z` may be lowercase, the fraction may follow a
+/// comma, and digits past nanoseconds are ignored.
+fn parse(text: &str) -> Option<Timestamp> {
+    let mut cursor = Cursor { bytes: text.as_bytes(), at: 0 };
+    let year = cursor.number(4)?;
+    cursor.expect(b'-')?;
+    let month = cursor.number(2)?;
+    cursor.expect(b'-')?;
+    let day = cursor.number(2)?;
+    if !matches!(cursor.next()?, b'T' | b't' | b' ') {
+        return None;
+    }
+    let hour = cursor.number(2)?;
+    cursor.expect(b':')?;
+    let minute = cursor.number(2)?;
+    let mut second = 0;
+    let mut nanos = 0;
+    if cursor.eat(b':') {
+        second = cursor.number(2)?;
+        if cursor.eat(b'.') || cursor.eat(b',') {
+            let start = cursor.at;
+            let mut scale = 100_000_000;
+            while let Some(digit) = cursor.digit() {
+                nanos += digit * scale;
+                scale /= 10;
+            }
+            if cursor.at == start {
+                return None;
+            }
+        }
+    }
+    let offset = match cursor.next() {
+        None => None,
+        Some(b'Z' | b'z') => Some(0),
+        Some(sign @ (b'+' | b'-')) => {
+            let hours = cursor.number(2)?;
+            let minutes = match cursor.peek() {
+                None => 0,
+                Some(b':') => {
+                    cursor.at += 1;
+                    cursor.number(2)?
+                }
+                Some(_) => cursor.number(2)?,
+            };
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let seconds = (hours * 3600 + minutes * 60) as i32;
+            Some(if sign == b'-' { -seconds } else { seconds })
+        }
+        Some(_) => return None,
+    };
+    let valid = (1..=12).contains(&month)
+        && (1..=days_in_month(year, month)).contains(&day)
+        && hour <= 23
+        && minute <= 59
+        // A leap second reads as the first second of the next minute.
+        && second <= 60;
+    if !valid || cursor.at != text.len() {
+        return None;
+    }
+    let days = days_from_civil(i64::from(year), month, day);
+    let seconds = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second);
+    Some(Timestamp { local_nanos: i128::from(seconds) * 1_000_000_000 + i128::from(nanos), offset })
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.at += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.eat(byte).then_some(())
+    }
+
+    fn digit(&mut self) -> Option<u32> {
+        let digit = char::from(self.peek()?).to_digit(10)?;
+        self.at += 1;
+        Some(digit)
+    }
+
+    /// Exactly `count` decimal digits.
+    fn number(&mut self, count: usize) -> Option<u32> {
+        (0..count).try_fold(0, |value, _| Some(value * 10 + self.digit()?))
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((i64::from(month) + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instants_compare_across_time_zones() {
+        assert!(within("2024-03-01T12:00:00Z", "2024-03-01T13:00:00+01:00", 0.0));
+        assert!(within("2024-03-01T12:00:00Z", "2024-03-01 07:00:00-0500", 0.0));
+        assert!(within("2024-03-01t12:00z", "2024-03-01T14:00:00.000+02", 0.0));
+        assert!(within("2024-02-29T23:30:00-01:00", "2024-03-01T00:30:00Z", 0.0));
+        assert!(!within("2024-03-01T12:00:00Z", "2024-03-01T12:00:00+01:00", 0.0));
+    }
+
+    #[test]
+    fn tolerance_covers_fractions_and_seconds() {
+        let (lhs, rhs) = ("2024-03-01T12:00:00.250Z", "2024-03-01T12:00:01,1Z");
+        assert!(!within(lhs, rhs, 0.5));
+        assert!(within(lhs, rhs, 0.85));
+        assert!(within("1999-12-31T23:59:59Z", "2000-01-01T00:00:00Z", 1.0));
+        assert!(within("2024-03-01T12:00:00.123456789123Z", "2024-03-01T12:00:00.123456789Z", 0.0));
+    }
+
+    #[test]
+    fn local_times_only_equal_local_times() {
+        assert!(within("2024-03-01T12:00:00", "2024-03-01T12:00:00.4", 0.5));
+        assert!(!within("2024-03-01T12:00:00", "2024-03-01T12:00:00Z", 1.0));
+    }
+
+    #[test]
+    fn malformed_timestamps_are_rejected() {
+        for text in [
+            "2024-03-01",
+            "2024-3-01T12:00Z",
+            "2024-02-30T12:00:00Z",
+            "2023-02-29T12:00:00Z",
+            "2024-03-01T24:00:00Z",
+            "2024-03-01T12:00:00.Z",
+            "2024-03-01T12:00:00+1",
+            "2024-03-01T12:00:00+24:00",
+            "2024-03-01T12:00:00Zjunk",
+            "12:00:00",
+        ] {
+            assert_eq!(parse(text), None, "{text}");
+        }
+        assert!(parse("1900-02-28T00:00:00Z").is_some());
+        assert_eq!(parse("1970-01-01T00:00:00Z").map(Timestamp::nanos), Some(0));
+        assert_eq!(parse("1969-12-31T23:59:59.5Z").map(Timestamp::nanos), Some(-500_000_000));
+    }
+}
@@ -0,0 +1,120 @@
+//! Depth-first traversal of documents with path tracking.
+
+use crate::{Node, Path, PathSegment};
+
+/// Callbacks invoked by [`Node::walk`] for every value in a document.
+///
+/// `enter` sees a value before its children and `exit` after them, both with
+/// the path from the root. Children are visited in document order: array
+/// items by index and object members by key. Both methods default to doing
+/// nothing, so implementors override only what they need.
+///
+/// ```
+/// use jd_core::{Node, Path, Visitor};
+///
+/// /// Collects the paths of all string values.
+/// #[derive(Default)]
+/// struct Strings(Vec<String>);
+///
+/// impl Visitor for Strings {
+///     fn enter(&mut self, path: &Path, node: &Node) {
+///         if let Node::String(_) = node {
+///             self.0.push(path.to_string());
+///         }
+///     }
+/// }
+///
+/// let node = Node::from_json_str(r#"{"user":{"name":"a","tags":["x",1]}}"#).unwrap();
+/// let mut strings = Strings::default();
+/// node.walk(&mut strings);
+/// assert_eq!(strings.0, ["[user name]", "[user tags 0]"]);
+/// ```
+pub trait Visitor {
+    /// Called before the children of `node` are visited.
+    fn enter(&mut self, path: &Path, node: &Node) {
+        let _ = (path, node);
+    }
+
+    /// Called after the children of `node` have been visited.
+    fn exit(&mut self, path: &Path, node: &Node) {
+        let _ = (path, node);
+    }
+}
+
+enum Step<'a> {
+    Enter(&'a Node, Option<PathSegment>),
+    Exit(&'a Node),
+}
+
+/// Drives `visitor` over `root` with an explicit work stack, so nesting depth
+/// is not limited by the call stack.
+pub(crate) fn walk(root: &Node, visitor: &mut (impl Visitor + ?Sized)) {
+    let mut path = Path::new();
+    let mut stack = vec![Step::Enter(root, None)];
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(node, segment) => {
+                if let Some(segment) = segment {
+                    path.push(segment);
+                }
+                visitor.enter(&path, node);
+                stack.push(Step::Exit(node));
+                match node {
+                    Node::Array(items) => {
+                        stack.extend(items.iter().enumerate().rev().map(|(index, item)| {
+                            Step::Enter(item, Some(PathSegment::Index(index as i64)))
+                        }))
+                    }
+                    Node::Object(map) => stack.extend(
+                        map.iter()
+                            .rev()
+                            .map(|(key, value)| Step::Enter(value, Some(PathSegment::key(key)))),
+                    ),
+                    _ => {}
+                }
+            }
+            Step::Exit(node) => {
+                visitor.exit(&path, node);
+                path.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl Visitor for Trace {
+        fn enter(&mut self, path: &Path, _: &Node) {
+            self.0.push(format!("enter {path}"));
+        }
+
+        fn exit(&mut self, path: &Path, _: &Node) {
+            self.0.push(format!("exit {path}"));
+        }
+    }
+
+    #[test]
+    fn enter_and_exit_nest_in_document_order() {
+        let node = Node::from_json_str(r#"{"b":[1],"a":2}"#).unwrap();
+        let mut trace = Trace::default();
+        node.walk(&mut trace);
+        assert_eq!(
+            trace.0,
+            [
+                "enter []",
+                "enter [a]",
+                "exit [a]",
+                "enter [b]",
+                "enter [b 0]",
+                "exit [b 0]",
+                "exit [b]",
+                "exit []",
+            ]
+        );
+    }
+}
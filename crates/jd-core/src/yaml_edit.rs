@@ -0,0 +1,563 @@
+//! Patching YAML text in place, keeping comments and formatting.
+//!
+//! [`Diff::apply_to_yaml_str`] patches the parsed document as usual, then
+//! writes only the difference between the old and new values back into the
+//! original text, located with `saphyr-parser`'s spans. Changed scalars keep
+//! their quoting style, members and items that are removed take their lines
+//! with them, and new ones are appended in block style at the indentation of
+//! their siblings. Anything else is left byte for byte as it was.
+//!
+//! Changes that cannot be made surgically fall back to the smallest
+//! enclosing value written as flow YAML (compact JSON). Documents using
+//! aliases are refused, since editing an anchored value would change every
+//! alias of it, and so is any edit whose result does not parse back to the
+//! patched value.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use saphyr_parser::{Event, Parser, ScalarStyle};
+
+use crate::diff::{Path, PathSegment};
+use crate::{Diff, Node, YamlEditError};
+
+impl Diff {
+    /// Applies this diff to the YAML document `input`, returning the edited
+    /// text. Requires the `yaml-edit` feature.
+    ///
+    /// Unlike patching the parsed node and writing it with
+    /// [`Node::to_yaml_string`], only the affected values are rewritten, so
+    /// comments, quoting, and key order survive in human-maintained files.
+    ///
+    /// ```
+    /// # use jd_core::{DiffOptions, Node};
+    /// let input = "# Deployment settings\nreplicas: 2 # scaled by hand\nimage: 'app:1.0'\n";
+    /// let target = Node::from_yaml_str("replicas: 3\nimage: app:1.1\n").unwrap();
+    /// let diff = Node::from_yaml_str(input).unwrap().diff(&target, &DiffOptions::default());
+    /// assert_eq!(
+    ///     diff.apply_to_yaml_str(input).unwrap(),
+    ///     "# Deployment settings\nreplicas: 3 # scaled by hand\nimage: 'app:1.1'\n"
+    /// );
+    /// ```
+    pub fn apply_to_yaml_str(&self, input: &str) -> Result<String, YamlEditError> {
+        let before = Node::from_yaml_str(input)?;
+        let after = before.apply_patch(self)?;
+        if after == before {
+            return Ok(input.to_string());
+        }
+        if after == Node::Void {
+            return Err(unsupported(Path::new(), "the patch removes the whole document"));
+        }
+        let document = Document::index(input)?;
+        let mut edits = document.edits(&before, &after)?;
+        // Later edits first, so earlier offsets stay valid; at one offset the
+        // outer value's insertion goes in first and ends up last.
+        edits.sort_by(|a, b| b.range.start.cmp(&a.range.start).then(a.depth.cmp(&b.depth)));
+        let mut output = input.to_string();
+        let mut limit = input.len();
+        for edit in edits {
+            if edit.range.end > limit {
+                return Err(unsupported(Path::new(), "edits overlap"));
+            }
+            limit = edit.range.start;
+            output.replace_range(edit.range, &edit.text);
+        }
+        match Node::from_yaml_str(&output) {
+            Ok(edited) if edited == after => Ok(output),
+            _ => Err(unsupported(
+                Path::new(),
+                "the edited text does not read back as the patch result",
+            )),
+        }
+    }
+}
+
+fn unsupported(path: Path, reason: &str) -> YamlEditError {
+    YamlEditError::Unsupported { path, reason: reason.to_string() }
+}
+
+/// A value as written in the source text. Offsets are in bytes.
+struct Entry {
+    /// The value, from its first character to just past its last. Block
+    /// sequences start at their first `-`, block scalars at their `|` or `>`
+    /// header, and both end after their last line break.
+    span: Range<usize>,
+    /// The key, for mapping members.
+    key: Option<Range<usize>>,
+    style: Style,
+    /// Whether the value sits inside a flow collection, where plain scalars
+    /// cannot contain `,[]{}`.
+    in_flow: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Style {
+    Scalar(ScalarStyle),
+    /// A literal or folded scalar, spanning its header and every line.
+    BlockScalar,
+    Block,
+    Flow,
+}
+
+struct Edit {
+    range: Range<usize>,
+    text: String,
+    depth: usize,
+}
+
+struct Document<'a> {
+    input: &'a str,
+    entries: HashMap<Path, Entry>,
+}
+
+/// A collection being read from the event stream.
+struct Open {
+    path: Path,
+    start: usize,
+    key: Option<Range<usize>>,
+    flow: bool,
+    end: usize,
+    next: Next,
+}
+
+enum Next {
+    Key,
+    Value(String, Range<usize>),
+    Item(i64),
+}
+
+impl<'a> Document<'a> {
+    /// Locates every value of `input`, which must already parse as YAML.
+    fn index(input: &'a str) -> Result<Self, YamlEditError> {
+        // saphyr-parser counts characters; edits need bytes.
+        let mut bytes: Vec<usize> = input.char_indices().map(|(offset, _)| offset).collect();
+        bytes.push(input.len());
+        let mut entries = HashMap::new();
+        let mut stack: Vec<Open> = Vec::new();
+        // End of the previous event, before which a block scalar's header lies.
+        let mut previous = 0;
+        for event in Parser::new_from_str(input) {
+            let (event, span) = event.map_err(|err| unsupported(Path::new(), err.info()))?;
+            let start = bytes[span.start.index()];
+            let mut end = bytes[span.end.index()];
+            match event {
+                Event::Alias(_) => {
+                    let path = stack.last().map_or_else(Path::new, |open| open.path.clone());
+                    return Err(unsupported(path, "the document uses aliases"));
+                }
+                Event::Scalar(value, style, ..) => {
+                    if let Some(Open { next: next @ Next::Key, .. }) = stack.last_mut() {
+                        if matches!(style, ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted) {
+                            end = quoted_end(input, start);
+                        }
+                        *next = Next::Value(value.into_owned(), start..end);
+                        previous = end;
+                        continue;
+                    }
+                    let (span, style) = match style {
+                        ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted => {
+                            (start..quoted_end(input, start), Style::Scalar(style))
+                        }
+                        ScalarStyle::Literal | ScalarStyle::Folded => {
+                            let header = input[previous..start]
+                                .find(['|', '>'])
+                                .map_or(start, |offset| previous + offset);
+                            let text_end = input[..end].trim_end_matches([' ', '\t']).len();
+                            (header..text_end.max(start), Style::BlockScalar)
+                        }
+                        ScalarStyle::Plain => (start..end, Style::Scalar(style)),
+                    };
+                    end = span.end;
+                    place(&mut stack, &mut entries, span, style);
+                }
+                Event::MappingStart(..) | Event::SequenceStart(..) => {
+                    let flow = input[start..].starts_with(['{', '[']);
+                    let sequence = matches!(event, Event::SequenceStart(..));
+                    let start = if sequence && !flow { dash_before(input, start) } else { start };
+                    let (path, key) = match stack.last_mut() {
+                        None => (Path::new(), None),
+                        Some(open) => match std::mem::replace(&mut open.next, Next::Key) {
+                            Next::Key => {
+                                return Err(unsupported(open.path.clone(), "a key is a collection"))
+                            }
+                            Next::Value(name, key) => {
+                                (open.path.clone().with_segment(PathSegment::Key(name)), Some(key))
+                            }
+                            Next::Item(index) => {
+                                open.next = Next::Item(index);
+                                (open.path.clone().with_segment(PathSegment::Index(index)), None)
+                            }
+                        },
+                    };
+                    let next = if sequence { Next::Item(0) } else { Next::Key };
+                    stack.push(Open { path, start, key, flow, end: start, next });
+                }
+                Event::MappingEnd | Event::SequenceEnd => {
+                    let Some(open) = stack.pop() else { continue };
+                    let style = if open.flow { Style::Flow } else { Style::Block };
+                    let span = open.start..if open.flow { start + 1 } else { open.end };
+                    end = span.end;
+                    entries.insert(
+                        open.path,
+                        Entry {
+                            span: span.clone(),
+                            key: open.key,
+                            style,
+                            in_flow: stack.iter().any(|open| open.flow),
+                        },
+                    );
+                    finish_value(&mut stack, span.end);
+                }
+                _ => {}
+            }
+            previous = end;
+        }
+        Ok(Self { input, entries })
+    }
+
+    /// Plans the edits turning the text of `before` into that of `after`.
+    fn edits(&self, before: &Node, after: &Node) -> Result<Vec<Edit>, YamlEditError> {
+        let mut edits = Vec::new();
+        let mut stack = vec![(Path::new(), before, after)];
+        while let Some((path, before, after)) = stack.pop() {
+            if before == after {
+                continue;
+            }
+            let Some(entry) = self.entries.get(&path) else {
+                return Err(unsupported(path, "the value was not found in the text"));
+            };
+            let depth = path.segments().len();
+            let planned = match (before, after, entry.style) {
+                (Node::Object(old), Node::Object(new), style @ (Style::Block | Style::Flow)) => {
+                    if old.len() == new.len() && old.keys().eq(new.keys()) {
+                        for (key, value) in old {
+                            let child = path.clone().with_segment(PathSegment::Key(key.clone()));
+                            stack.push((child, value, &new[key]));
+                        }
+                        Some(Vec::new())
+                    } else if matches!(style, Style::Block) && !new.is_empty() {
+                        self.members(&path, old, new, &mut stack)
+                    } else {
+                        None
+                    }
+                }
+                (Node::Array(old), Node::Array(new), style @ (Style::Block | Style::Flow)) => {
+                    if old.len() == new.len() {
+                        for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                            stack.push((index_path(&path, index), old, new));
+                        }
+                        Some(Vec::new())
+                    } else if matches!(style, Style::Block) && !new.is_empty() {
+                        self.items(&path, old, new, &mut stack)
+                    } else {
+                        None
+                    }
+                }
+                (_, Node::String(_) | Node::Number(_) | Node::Bool(_) | Node::Null, style) => {
+                    let text = match style {
+                        Style::Scalar(style) => scalar(after, style, entry.in_flow),
+                        _ => scalar(after, ScalarStyle::Plain, entry.in_flow),
+                    };
+                    Some(vec![self.replace(entry, text, depth)])
+                }
+                _ => None,
+            };
+            let planned = match planned {
+                Some(planned) => planned,
+                None => {
+                    let Some(json) = after.to_json_value() else {
+                        return Err(unsupported(path, "a value is void"));
+                    };
+                    vec![self.replace(entry, json.to_string(), depth)]
+                }
+            };
+            edits.extend(planned.into_iter().map(|edit| Edit { depth, ..edit }));
+        }
+        Ok(edits)
+    }
+
+    /// Removes and appends members of a block mapping, queueing the members
+    /// both sides share; `None` when the text does not allow it.
+    fn members<'n>(
+        &self,
+        path: &Path,
+        old: &'n std::collections::BTreeMap<String, Node>,
+        new: &'n std::collections::BTreeMap<String, Node>,
+        stack: &mut Vec<(Path, &'n Node, &'n Node)>,
+    ) -> Option<Vec<Edit>> {
+        let mut written: Vec<(&String, &Entry)> = old
+            .keys()
+            .map(|key| Some((key, self.entries.get(&key_path(path, key))?)))
+            .collect::<Option<_>>()?;
+        written.sort_by_key(|(_, entry)| entry.key.as_ref().map(|key| key.start));
+        let first_key = written.first()?.1.key.clone()?;
+        let last_end = written.iter().map(|(_, entry)| entry.span.end).max()?;
+
+        let mut edits = Vec::new();
+        for (position, (key, entry)) in written.iter().enumerate() {
+            match new.get(*key) {
+                Some(value) => stack.push((key_path(path, key), &old[*key], value)),
+                None => {
+                    let key_start = entry.key.as_ref()?.start;
+                    let line = self.line_start(key_start);
+                    let range = if self.input[line..key_start].trim().is_empty() {
+                        line..self.line_end(entry.span.end)
+                    } else {
+                        // The first member of a sequence item shares the dash's line.
+                        key_start..written.get(position + 1)?.1.key.as_ref()?.start
+                    };
+                    edits.push(Edit { range, text: String::new(), depth: 0 });
+                }
+            }
+        }
+
+        let indent = " "
+            .repeat(self.input[self.line_start(first_key.start)..first_key.start].chars().count());
+        let mut text = String::new();
+        for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+            let member = Node::Object([(key.clone(), value.clone())].into_iter().collect());
+            text.push_str(&indented(&member.to_yaml_string()?, &indent));
+        }
+        if !text.is_empty() {
+            edits.push(self.insert_after(last_end, text));
+        }
+        Some(edits)
+    }
+
+    /// Removes and inserts items of a block sequence around its unchanged
+    /// ends, queueing the items paired up in between.
+    fn items<'n>(
+        &self,
+        path: &Path,
+        old: &'n [Node],
+        new: &'n [Node],
+        stack: &mut Vec<(Path, &'n Node, &'n Node)>,
+    ) -> Option<Vec<Edit>> {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (removed, added) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+        let paired = removed.len().min(added.len());
+        for (offset, (old, new)) in removed.iter().zip(added).enumerate() {
+            stack.push((index_path(path, prefix + offset), old, new));
+        }
+
+        let dashes: Vec<usize> = (0..old.len())
+            .map(|index| {
+                let entry = self.entries.get(&index_path(path, index))?;
+                let dash = dash_before(self.input, entry.span.start);
+                let line = self.line_start(dash);
+                self.input[line..dash].trim().is_empty().then_some(dash)
+            })
+            .collect::<Option<_>>()?;
+        let mut edits = Vec::new();
+        let gone = prefix + paired..prefix + removed.len();
+        for (index, &dash) in dashes.iter().enumerate().take(gone.end).skip(gone.start) {
+            let end = self.entries.get(&index_path(path, index))?.span.end;
+            let range = self.line_start(dash)..self.line_end(end);
+            edits.push(Edit { range, text: String::new(), depth: 0 });
+        }
+        if added.len() > paired {
+            let indent =
+                " ".repeat(self.input[self.line_start(dashes[0])..dashes[0]].chars().count());
+            let items = Node::Array(added[paired..].to_vec());
+            let text = indented(&items.to_yaml_string()?, &indent);
+            let next = prefix + removed.len();
+            edits.push(match dashes.get(next) {
+                Some(&dash) => {
+                    Edit { range: self.line_start(dash)..self.line_start(dash), text, depth: 0 }
+                }
+                None => {
+                    let last = self.entries.get(&index_path(path, old.len() - 1))?.span.end;
+                    self.insert_after(last, text)
+                }
+            });
+        }
+        Some(edits)
+    }
+
+    fn replace(&self, entry: &Entry, mut text: String, depth: usize) -> Edit {
+        if matches!(entry.style, Style::BlockScalar) && self.input[..entry.span.end].ends_with('\n')
+        {
+            text.push('\n');
+        }
+        Edit { range: entry.span.clone(), text, depth }
+    }
+
+    /// Inserts whole lines after the line on which `offset` lies.
+    fn insert_after(&self, offset: usize, mut text: String) -> Edit {
+        let at = self.line_end(offset);
+        if !self.input[..at].ends_with('\n') {
+            text.insert(0, '\n');
+        }
+        Edit { range: at..at, text, depth: 0 }
+    }
+
+    fn line_start(&self, offset: usize) -> usize {
+        self.input[..offset].rfind('\n').map_or(0, |newline| newline + 1)
+    }
+
+    /// Offset just past the line break ending the line on which the text
+    /// before `offset` ends, trailing comment included.
+    fn line_end(&self, offset: usize) -> usize {
+        if self.input[..offset].ends_with('\n') {
+            return offset;
+        }
+        self.input[offset..].find('\n').map_or(self.input.len(), |newline| offset + newline + 1)
+    }
+}
+
+/// Records a finished value in the collection that holds it.
+fn place(stack: &mut [Open], entries: &mut HashMap<Path, Entry>, span: Range<usize>, style: Style) {
+    let in_flow = stack.iter().any(|open| open.flow);
+    let (path, key) = match stack.last_mut() {
+        None => (Path::new(), None),
+        Some(open) => match std::mem::replace(&mut open.next, Next::Key) {
+            Next::Value(name, key) => (key_path(&open.path, &name), Some(key)),
+            Next::Item(index) => {
+                open.next = Next::Item(index);
+                (open.path.clone().with_segment(PathSegment::Index(index)), None)
+            }
+            Next::Key => unreachable!("keys are recorded before their values"),
+        },
+    };
+    let end = span.end;
+    entries.insert(path, Entry { span, key, style, in_flow });
+    finish_value(stack, end);
+}
+
+fn finish_value(stack: &mut [Open], end: usize) {
+    if let Some(open) = stack.last_mut() {
+        open.end = end;
+        if let Next::Item(index) = &mut open.next {
+            *index += 1;
+        }
+    }
+}
+
+fn key_path(path: &Path, key: &str) -> Path {
+    path.clone().with_segment(PathSegment::Key(key.to_string()))
+}
+
+fn index_path(path: &Path, index: usize) -> Path {
+    path.clone().with_segment(PathSegment::Index(index as i64))
+}
+
+/// End of the quoted scalar starting at `start`; saphyr's spans run on
+/// over any trailing comment.
+fn quoted_end(input: &str, start: usize) -> usize {
+    let quote = input[start..].chars().next().unwrap_or('"');
+    let mut chars = input[start + 1..].char_indices();
+    while let Some((offset, ch)) = chars.next() {
+        match ch {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            ch if ch == quote => {
+                if quote == '\'' && input[start + 1 + offset + 1..].starts_with('\'') {
+                    chars.next();
+                } else {
+                    return start + 1 + offset + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+/// Offset of the `-` introducing the sequence item that starts at `start`.
+fn dash_before(input: &str, start: usize) -> usize {
+    let trimmed = input[..start].trim_end();
+    if trimmed.ends_with('-') {
+        trimmed.len() - 1
+    } else {
+        start
+    }
+}
+
+/// Writes a scalar in `style` where the value still reads back the same.
+fn scalar(value: &Node, style: ScalarStyle, in_flow: bool) -> String {
+    let json = value.to_json_value().unwrap_or_default().to_string();
+    let Node::String(text) = value else {
+        return json;
+    };
+    let printable = !text.chars().any(char::is_control);
+    match style {
+        ScalarStyle::SingleQuoted if printable => format!("'{}'", text.replace('\'', "''")),
+        ScalarStyle::Plain if printable && plain_safe(text, in_flow) => text.clone(),
+        _ => json,
+    }
+}
+
+fn plain_safe(text: &str, in_flow: bool) -> bool {
+    !text.is_empty()
+        && text.trim() == text
+        && !(in_flow && text.contains([',', '[', ']', '{', '}']))
+        && matches!(Node::from_yaml_str(text), Ok(Node::String(ref read)) if read == text)
+}
+
+/// Prefixes every line of `text` with `indent`.
+fn indented(text: &str, indent: &str) -> String {
+    text.lines().map(|line| format!("{indent}{line}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DiffOptions, Node, YamlEditError};
+
+    fn edit(input: &str, target: &str) -> Result<String, YamlEditError> {
+        let target = Node::from_yaml_str(target).unwrap();
+        let diff = Node::from_yaml_str(input).unwrap().diff(&target, &DiffOptions::default());
+        diff.apply_to_yaml_str(input)
+    }
+
+    #[test]
+    fn replaces_scalars_keeping_their_style() {
+        let input =
+            "a: \"x\"  # note\nb: 'it''s'\nc: plain # why\nd: |\n  one\n  two\ne: [1, {f: x}]\n";
+        let target = "a: y\nb: it is\nc: 'needs: quotes'\nd: 3\ne: [1, {f: 'y,z'}]\n";
+        assert_eq!(
+            edit(input, target).unwrap(),
+            "a: \"y\"  # note\nb: 'it is'\nc: \"needs: quotes\" # why\nd: 3\ne: [1, {f: \"y,z\"}]\n"
+        );
+    }
+
+    #[test]
+    fn removes_and_appends_members() {
+        let input = "# config\nkeep: 1  # stays\ndrop: 2\nnested:\n  a: 1 # first\n  b: [1, 2]\n";
+        let target = "keep: 1\nnested: {a: 1, c: {d: [x]}}\nnew: true\n";
+        assert_eq!(
+            edit(input, target).unwrap(),
+            "# config\nkeep: 1  # stays\nnested:\n  a: 1 # first\n  c:\n    d:\n    - x\nnew: true\n"
+        );
+    }
+
+    #[test]
+    fn edits_sequence_items_and_their_first_members() {
+        let input =
+            "env:\n- name: A # a\n  value: '1'\n- name: B\n  value: '2'\nports:\n  - 80\n  - 443";
+        let target = "env:\n- value: '1'\n- name: C\n  value: '3'\n- name: B\n  value: '2'\nports: [443, 8080]\n";
+        assert_eq!(
+            edit(input, target).unwrap(),
+            "env:\n- value: '1'\n- name: C\n  value: '3'\n- name: B\n  value: '2'\nports:\n  - 443\n  - 8080"
+        );
+    }
+
+    #[test]
+    fn rewrites_unsplittable_values_as_flow_and_refuses_aliases() {
+        assert_eq!(
+            edit("a: {b: 1} # c\n", "a: {b: 1, c: 2}").unwrap(),
+            "a: {\"b\":1,\"c\":2} # c\n"
+        );
+        assert_eq!(edit("a: 1\nb:\n  c: 1\n", "a: 1\nb: []").unwrap(), "a: 1\nb:\n  []\n");
+        assert!(matches!(
+            edit("a: &x 1\nb: *x\n", "a: 2\nb: 1"),
+            Err(YamlEditError::Unsupported { .. })
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use jd_core::{diff::PathSegment, Diff, DiffElement, DiffMetadata, DiffOptions, Node};
+use jd_core::{diff::PathSegment, ArrayMode, Diff, DiffElement, DiffMetadata, DiffOptions, Node};
 use proptest::prop_assert_eq;
 
 #[test]
@@ -53,6 +53,142 @@ fn apply_patch_rejects_merge_old_value() {
     assert_eq!(err.to_string(), "patch with merge strategy at [a] has unnecessary old value 1");
 }
 
+/// Deep enough to overflow a recursive patch traversal on a 2 MiB test thread.
+const DEEP: usize = 100_000;
+
+/// Alternates arrays and objects (`[{"k":[{"k":...1}]}]`), returning the
+/// document and the path to its innermost value.
+fn deeply_nested(depth: usize) -> (Node, Vec<PathSegment>) {
+    let mut value = serde_json::Value::from(1);
+    let mut path = Vec::with_capacity(depth);
+    for level in 0..depth {
+        if level % 2 == 0 {
+            value = serde_json::Value::Array(vec![value]);
+            path.push(PathSegment::index(0));
+        } else {
+            value = serde_json::Value::Object(serde_json::Map::from_iter([("k".into(), value)]));
+            path.push(PathSegment::key("k"));
+        }
+    }
+    path.reverse();
+    (Node::from_json_value(value).unwrap(), path)
+}
+
+/// Follows `path` without recursion.
+fn innermost<'a>(node: &'a Node, path: &[PathSegment]) -> &'a Node {
+    let mut current = node;
+    for segment in path {
+        current = match (current, segment) {
+            (Node::Array(items), PathSegment::Index(index)) => &items[*index as usize],
+            (Node::Object(map), PathSegment::Key(key)) => &map[key],
+            (other, _) => panic!("unexpected node {other:?} at {segment}"),
+        };
+    }
+    current
+}
+
+#[test]
+fn apply_patch_handles_deeply_nested_documents() {
+    let (base, path) = deeply_nested(DEEP);
+    let element = DiffElement::new()
+        .with_path(path.clone())
+        .with_remove(vec![Node::from_json_str("1").unwrap()])
+        .with_add(vec![Node::from_json_str("2").unwrap()]);
+    let patched = base.apply_patch(&Diff::from_elements(vec![element])).unwrap();
+
+    assert_eq!(innermost(&patched, &path), &Node::from_json_str("2").unwrap());
+    assert_eq!(innermost(&base, &path), &Node::from_json_str("1").unwrap());
+}
+
+#[test]
+fn out_of_range_context_and_removals_are_errors() {
+    let patch = |diff: &str| {
+        let diff = Diff::from_native_str(diff).unwrap();
+        Node::from_json_str("[1]").unwrap().apply_patch(&diff).unwrap_err().to_string()
+    };
+    assert_eq!(patch("@ [3]\n  2\n+ 3\n"), "invalid patch. before context 2 out of bounds: 2");
+    assert_eq!(patch("@ [0]\n- 1\n- 2\n"), "remove values out bounds: 0");
+    assert_eq!(patch("@ [2]\n+ 3\n"), "remove values out bounds: 2");
+    assert_eq!(patch("@ [0]\n- 1\n  2\n"), "invalid patch. after context 2 out of bounds: 0");
+    assert_eq!(patch("@ [1]\n  1\n+ 2\n  3\n"), "invalid patch. after context 3 out of bounds: 1");
+}
+
+#[test]
+fn list_hunks_apply_one_after_another() {
+    let diff = Diff::from_native_str(
+        "@ [0]\n[\n- 1\n+ 0\n  2\n@ [3]\n  3\n+ 4\n  5\n@ [6]\n  6\n- 7\n+ 8\n]\n",
+    )
+    .unwrap();
+    let patched = Node::from_json_str("[1,2,3,5,6,7]").unwrap().apply_patch(&diff).unwrap();
+    assert_eq!(patched, Node::from_json_str("[0,2,3,4,5,6,8]").unwrap());
+}
+
+#[test]
+fn nested_list_context_is_checked() {
+    let diff = Diff::from_native_str("@ [\"a\",0,1]\n  1\n+ 2\n  3\n").unwrap();
+    let patch = |doc: &str| Node::from_json_str(doc).unwrap().apply_patch(&diff);
+    assert_eq!(
+        patch(r#"{"a":[[1,3]]}"#).unwrap(),
+        Node::from_json_str(r#"{"a":[[1,2,3]]}"#).unwrap()
+    );
+    let err = patch(r#"{"a":[[1,2,3]]}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid patch. expected 3 after. got 2");
+    let err = patch(r#"{"a":[[0,3]]}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid patch. expected 1 before. got 0");
+}
+
+const PARITY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/parity/upstream/jd-v2.2.2");
+
+fn parity_file(relative: &str) -> String {
+    std::fs::read_to_string(format!("{PARITY}/{relative}")).expect("parity fixture")
+}
+
+#[test]
+fn set_diffs_patch_upstream_fixtures() {
+    for (case, mode) in [
+        ("arrays-set", ArrayMode::Set),
+        ("arrays-multiset", ArrayMode::MultiSet),
+        ("arrays-multiset-nested", ArrayMode::MultiSet),
+        ("arrays-setkeys", ArrayMode::Set),
+        ("arrays-setkeys-nested", ArrayMode::Set),
+    ] {
+        let diff = Diff::from_native_str(&parity_file(&format!("{case}/diff.jd"))).unwrap();
+        let before = Node::from_json_str(&parity_file(&format!("{case}/before.json"))).unwrap();
+        let after = Node::from_json_str(&parity_file(&format!("{case}/after.json"))).unwrap();
+        let patched = before.apply_patch(&diff).unwrap();
+        let options = DiffOptions::default().with_array_mode(mode).unwrap();
+        assert!(patched.eq_with_options(&after, &options), "{case}: {patched:?}");
+    }
+}
+
+#[test]
+fn set_patches_match_members_by_value() {
+    let patch = |base: &str, diff: &str| {
+        let diff = Diff::from_native_str(diff).unwrap();
+        Node::from_json_str(base).unwrap().apply_patch(&diff).map_err(|err| err.to_string())
+    };
+    let json = |text: &str| Ok(Node::from_json_str(text).unwrap());
+    assert_eq!(patch("[1,2,1,3]", "@ [{}]\n- 1\n+ 3\n+ 4\n"), json("[2,3,4]"));
+    assert_eq!(patch("[1,2,1]", "@ [[]]\n- 1\n+ 2\n"), json("[2,1,2]"));
+    assert_eq!(patch("[[2,1]]", "@ [[]]\n- [1,2]\n"), json("[]"));
+    assert_eq!(
+        patch("[{\"id\":1,\"n\":1}]", "@ [{\"id\":1},\"n\"]\n- 1\n+ 2\n"),
+        json("[{\"id\":1,\"n\":2}]")
+    );
+    assert_eq!(
+        patch("{\"a\":[2]}", "@ [\"a\",{}]\n- 1\n"),
+        Err("found  at [a {}]: expected 1".into())
+    );
+    assert_eq!(
+        patch("[{\"id\":1}]", "@ [{\"id\":2},\"n\"]\n+ 1\n"),
+        Err("invalid patch. no object matching {\"id\":2} at []".into())
+    );
+    assert_eq!(
+        patch("[[1]]", "@ [{},0]\n- 1\n"),
+        Err("invalid path. {} at [] must be the last path element".into())
+    );
+}
+
 fn arb_json_value() -> impl proptest::strategy::Strategy<Value = serde_json::Value> {
     use proptest::{collection::btree_map, collection::vec, prelude::*, string::string_regex};
 
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+
+use jd_core::{Diff, Node};
+use serde::Deserialize;
+
+/// A patch and its outcome: the patched document or the error message.
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    base: String,
+    diff: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+fn load_fixture(path: &Path) -> Fixture {
+    let data = fs::read_to_string(path).expect("fixture should be readable");
+    serde_json::from_str(&data).expect("fixture should deserialize")
+}
+
+fn outcome(result: Result<Node, jd_core::PatchError>) -> Result<Node, String> {
+    result.map_err(|err| err.to_string())
+}
+
+/// Runs of hunks on one array are applied in a single pass; every fixture
+/// also applies its hunks one at a time, which never forms a run, and
+/// requires the same outcome.
+#[test]
+fn list_patch_golden() {
+    let fixtures_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/patch/list");
+    let mut entries: Vec<_> = fs::read_dir(&fixtures_root)
+        .expect("fixtures directory must exist")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    entries.sort();
+
+    assert!(
+        !entries.is_empty(),
+        "expected at least one patch fixture under tests/fixtures/patch/list",
+    );
+
+    for path in entries {
+        let fixture = load_fixture(&path);
+        let base = Node::from_json_str(&fixture.base).expect("base parses");
+        let diff = Diff::from_native_str(&fixture.diff).expect("diff parses");
+        let expected = match (&fixture.result, fixture.error) {
+            (Some(result), None) => Ok(Node::from_json_str(result).expect("result parses")),
+            (None, Some(error)) => Err(error),
+            _ => panic!("fixture {path:?} needs exactly one of result and error"),
+        };
+
+        assert_eq!(outcome(base.apply_patch(&diff)), expected, "fixture {path:?}");
+
+        let one_at_a_time = diff.iter().try_fold(base, |node, element| {
+            node.apply_patch(&Diff::from_elements(vec![element.clone()]))
+        });
+        assert_eq!(outcome(one_at_a_time), expected, "fixture {path:?}, one hunk at a time");
+    }
+}
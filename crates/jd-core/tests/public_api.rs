@@ -0,0 +1,337 @@
+//! Snapshot of the public API, so that changes to it show up in review.
+//!
+//! The test reduces every `pub` item under `src/` to its signature and
+//! compares the result with `tests/snapshots/public-api.rs`, ignoring
+//! formatting. After an intended change, regenerate the snapshot with
+//!
+//! ```text
+//! UPDATE_PUBLIC_API=1 cargo test -p jd-core --test public_api
+//! ```
+//!
+//! and check its diff for breaking changes before committing it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use quote::ToTokens;
+use syn::{
+    parse_quote, Attribute, Block, Fields, File, ImplItem, Item, ItemImpl, TraitItem, Type,
+    Visibility,
+};
+
+const SNAPSHOT: &str = "tests/snapshots/public-api.rs";
+
+/// Attributes that change what callers may do with an item; documentation
+/// and lint attributes are dropped.
+const KEPT_ATTRIBUTES: [&str; 5] = ["cfg", "derive", "macro_export", "non_exhaustive", "repr"];
+
+#[test]
+fn public_api_matches_snapshot() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let items = load_module(&root.join("src/lib.rs"), &root.join("src"));
+    let mut types = BTreeSet::new();
+    public_types(&items, &mut types);
+    let api = File { shebang: None, attrs: Vec::new(), items: reduce(items, &types) };
+
+    let path = root.join(SNAPSHOT);
+    if std::env::var_os("UPDATE_PUBLIC_API").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format_source(&api)).unwrap();
+        return;
+    }
+    let snapshot = fs::read_to_string(&path).expect("public API snapshot is readable");
+    let snapshot: File = syn::parse_str(&snapshot).expect("public API snapshot parses");
+
+    let (actual, expected) = (signatures(&api.items), signatures(&snapshot.items));
+    let added: Vec<_> = actual
+        .iter()
+        .filter(|(key, _)| !expected.contains_key(*key))
+        .map(|(_, item)| item)
+        .collect();
+    let removed: Vec<_> = expected
+        .iter()
+        .filter(|(key, _)| !actual.contains_key(*key))
+        .map(|(_, item)| item)
+        .collect();
+    assert!(
+        added.is_empty() && removed.is_empty(),
+        "the public API changed; review it and rerun with UPDATE_PUBLIC_API=1\n\
+         added: {added:#?}\nremoved: {removed:#?}"
+    );
+}
+
+/// Parses `file` with its out-of-line modules inlined, looking for them in `dir`.
+fn load_module(file: &Path, dir: &Path) -> Vec<Item> {
+    let source = fs::read_to_string(file).unwrap_or_else(|err| panic!("{}: {err}", file.display()));
+    let parsed: File =
+        syn::parse_file(&source).unwrap_or_else(|err| panic!("{}: {err}", file.display()));
+    let mut items = parsed.items;
+    for item in &mut items {
+        let Item::Mod(module) = item else { continue };
+        if module.content.is_some() || is_test_only(&module.attrs) {
+            continue;
+        }
+        let name = module.ident.to_string();
+        let child = dir.join(&name);
+        let file = match dir.join(format!("{name}.rs")) {
+            flat if flat.exists() => flat,
+            _ => child.join("mod.rs"),
+        };
+        module.content = Some((Default::default(), load_module(&file, &child)));
+    }
+    items
+}
+
+fn public_types(items: &[Item], types: &mut BTreeSet<String>) {
+    for item in items {
+        let ident = match item {
+            Item::Struct(item) if is_public(&item.vis) => &item.ident,
+            Item::Enum(item) if is_public(&item.vis) => &item.ident,
+            Item::Trait(item) if is_public(&item.vis) => &item.ident,
+            Item::Type(item) if is_public(&item.vis) => &item.ident,
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    public_types(items, types);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        types.insert(ident.to_string());
+    }
+}
+
+/// Keeps the public items in `items`, stripped down to their signatures.
+fn reduce(items: Vec<Item>, types: &BTreeSet<String>) -> Vec<Item> {
+    let mut kept = Vec::new();
+    for item in items {
+        let item = match item {
+            Item::Mod(mut module) if !is_test_only(&module.attrs) => {
+                let Some((brace, items)) = module.content.take() else { continue };
+                let items = reduce(items, types);
+                if items.is_empty() {
+                    continue;
+                }
+                module.attrs = kept_attributes(module.attrs);
+                module.content = Some((brace, items));
+                Item::Mod(module)
+            }
+            Item::Fn(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                item.block = Box::new(empty_block());
+                Item::Fn(item)
+            }
+            Item::Struct(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                // Private fields keep callers from building the struct.
+                if item.fields.iter().any(|field| !is_public(&field.vis)) {
+                    item.attrs.push(parse_quote!(#[private_fields]));
+                }
+                match &mut item.fields {
+                    Fields::Named(fields) => {
+                        fields.named = std::mem::take(&mut fields.named)
+                            .into_pairs()
+                            .filter(|pair| is_public(&pair.value().vis))
+                            .collect();
+                    }
+                    Fields::Unnamed(fields) => {
+                        fields.unnamed = std::mem::take(&mut fields.unnamed)
+                            .into_pairs()
+                            .filter(|pair| is_public(&pair.value().vis))
+                            .collect();
+                    }
+                    Fields::Unit => {}
+                }
+                for field in item.fields.iter_mut() {
+                    field.attrs = kept_attributes(std::mem::take(&mut field.attrs));
+                }
+                Item::Struct(item)
+            }
+            Item::Enum(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                for variant in &mut item.variants {
+                    variant.attrs = kept_attributes(std::mem::take(&mut variant.attrs));
+                    for field in variant.fields.iter_mut() {
+                        field.attrs = kept_attributes(std::mem::take(&mut field.attrs));
+                    }
+                }
+                Item::Enum(item)
+            }
+            Item::Trait(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                for member in &mut item.items {
+                    if let TraitItem::Fn(method) = member {
+                        method.attrs = kept_attributes(std::mem::take(&mut method.attrs));
+                        // An empty body marks a provided method, which
+                        // implementors need not write.
+                        if method.default.is_some() {
+                            method.default = Some(empty_block());
+                        }
+                    }
+                }
+                Item::Trait(item)
+            }
+            Item::Impl(item) => match reduce_impl(item, types) {
+                Some(item) => Item::Impl(item),
+                None => continue,
+            },
+            Item::Const(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                Item::Const(item)
+            }
+            Item::Static(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                Item::Static(item)
+            }
+            Item::Type(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                Item::Type(item)
+            }
+            Item::Use(mut item) if is_public(&item.vis) => {
+                item.attrs = kept_attributes(item.attrs);
+                Item::Use(item)
+            }
+            Item::Macro(mut item) if has_attribute(&item.attrs, "macro_export") => {
+                item.attrs = kept_attributes(item.attrs);
+                item.mac.tokens = Default::default();
+                Item::Macro(item)
+            }
+            _ => continue,
+        };
+        kept.push(item);
+    }
+    kept
+}
+
+/// Keeps trait impls for public types or of public traits, and the public
+/// methods and constants of inherent impls on public types.
+fn reduce_impl(mut item: ItemImpl, types: &BTreeSet<String>) -> Option<ItemImpl> {
+    if is_test_only(&item.attrs) {
+        return None;
+    }
+    let own_type = type_name(&item.self_ty).is_some_and(|name| types.contains(&name));
+    item.attrs = kept_attributes(item.attrs);
+    if let Some((_, trait_path, _)) = &item.trait_ {
+        let own_trait =
+            trait_path.segments.last().is_some_and(|last| types.contains(&last.ident.to_string()));
+        item.items.clear();
+        return (own_type || own_trait).then_some(item);
+    }
+    if !own_type {
+        return None;
+    }
+    item.items = std::mem::take(&mut item.items)
+        .into_iter()
+        .filter_map(|member| match member {
+            ImplItem::Fn(mut method) if is_public(&method.vis) && !is_test_only(&method.attrs) => {
+                method.attrs = kept_attributes(method.attrs);
+                method.block = empty_block();
+                Some(ImplItem::Fn(method))
+            }
+            ImplItem::Const(mut constant) if is_public(&constant.vis) => {
+                constant.attrs = kept_attributes(constant.attrs);
+                Some(ImplItem::Const(constant))
+            }
+            _ => None,
+        })
+        .collect();
+    (!item.items.is_empty()).then_some(item)
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|last| last.ident.to_string()),
+        Type::Reference(reference) => type_name(&reference.elem),
+        _ => None,
+    }
+}
+
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+fn has_attribute(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+fn is_test_only(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg") && attr.meta.to_token_stream().to_string() == "cfg (test)"
+    })
+}
+
+fn kept_attributes(attrs: Vec<Attribute>) -> Vec<Attribute> {
+    attrs
+        .into_iter()
+        .filter(|attr| KEPT_ATTRIBUTES.iter().any(|kept| attr.path().is_ident(kept)))
+        .collect()
+}
+
+fn empty_block() -> Block {
+    parse_quote!({})
+}
+
+/// Lists each item as `module::path: tokens`, with impls split per member,
+/// so that a mismatch names exactly what changed. Items are keyed by their
+/// tokens without whitespace or the trailing commas `rustfmt` adds and
+/// removes.
+fn signatures(items: &[Item]) -> BTreeMap<String, String> {
+    fn collect(items: &[Item], module: &str, out: &mut BTreeMap<String, String>) {
+        for item in items {
+            match item {
+                Item::Mod(inner) => {
+                    let name = format!("{module}::{}", inner.ident);
+                    if let Some((_, items)) = &inner.content {
+                        collect(items, &name, out);
+                    }
+                }
+                Item::Impl(block) if !block.items.is_empty() => {
+                    let mut header = block.clone();
+                    header.items.clear();
+                    let header = header.to_token_stream().to_string();
+                    for member in &block.items {
+                        let member = member.to_token_stream();
+                        insert(out, format!("{module}: {header} {member}"));
+                    }
+                }
+                item => {
+                    insert(out, format!("{module}: {}", item.to_token_stream()));
+                }
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    collect(items, "jd_core", &mut out);
+    out
+}
+
+fn insert(out: &mut BTreeMap<String, String>, item: String) {
+    let mut key: String = item.split_whitespace().collect();
+    for close in [")", "}", "]", ">"] {
+        key = key.replace(&format!(",{close}"), close);
+    }
+    out.insert(key, item);
+}
+
+/// Pretty-prints the snapshot with `rustfmt` when it is installed; the
+/// comparison ignores formatting either way.
+fn format_source(api: &File) -> String {
+    let source = api.to_token_stream().to_string();
+    let header = "// Public API of jd-core, checked by tests/public_api.rs. Regenerate with\n\
+                  // UPDATE_PUBLIC_API=1 cargo test -p jd-core --test public_api\n\n";
+    let formatted = Command::new("rustfmt")
+        .args(["--edition", "2021", "--config", "use_small_heuristics=Max"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()
+        .and_then(|mut rustfmt| {
+            rustfmt.stdin.take()?.write_all(source.as_bytes()).ok()?;
+            let output = rustfmt.wait_with_output().ok()?;
+            output.status.success().then(|| String::from_utf8(output.stdout).ok()).flatten()
+        });
+    format!("{header}{}", formatted.unwrap_or(source))
+}
@@ -0,0 +1,124 @@
+use jd_core::{Diff, DiffElement, DiffOptions, Node, RenderConfig};
+use proptest::prelude::*;
+
+const PARITY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/parity/upstream/jd-v2.2.2");
+
+fn parity_file(relative: &str) -> String {
+    std::fs::read_to_string(format!("{PARITY}/{relative}")).expect("parity fixture")
+}
+
+#[test]
+fn native_reader_round_trips_list_context() {
+    let text = parity_file("translate-jd2patch/input.jd");
+    let diff = Diff::from_native_str(&text).unwrap();
+    assert_eq!(diff.render(&RenderConfig::default()), text);
+    assert_eq!(diff.render_patch().unwrap(), parity_file("translate-jd2patch/output.patch"));
+}
+
+#[test]
+fn native_reader_round_trips_set_paths() {
+    for case in [
+        "arrays-set",
+        "arrays-multiset",
+        "arrays-multiset-nested",
+        "arrays-setkeys",
+        "arrays-setkeys-nested",
+    ] {
+        let text = parity_file(&format!("{case}/diff.jd"));
+        let diff = Diff::from_native_str(&text).unwrap();
+        assert_eq!(diff.render(&RenderConfig::default()), text, "{case}");
+        let raw = diff.render_raw().unwrap();
+        let elements: Vec<DiffElement> = serde_json::from_str(&raw).unwrap();
+        assert_eq!(Diff::from_elements(elements), diff, "{case}");
+    }
+    let diff = Diff::from_native_str("@ [\"tags\",{}]\n+ 1\n").unwrap();
+    assert_eq!(
+        diff.render_patch().unwrap_err().to_string(),
+        "JSON Pointer cannot address set or multiset path element {}"
+    );
+    let err = Diff::from_native_str("@ [\"tags\",[1,2]]\n+ 1\n").unwrap_err();
+    assert!(err.to_string().contains("unsupported path element [1,2]"), "{err}");
+}
+
+#[test]
+fn native_reader_preserves_merge_metadata() {
+    let text = "^ {\"Merge\":true}\n@ [\"a\"]\n+ 1\n@ [\"b\"]\n+\n";
+    let diff = Diff::from_native_str(text).unwrap();
+    assert_eq!(diff.render_merge().unwrap(), "{\"a\":1,\"b\":null}");
+}
+
+#[test]
+fn native_reader_reports_line_numbers() {
+    let err = Diff::from_native_str("@ [\"a\"]\n- 1\n+ 2\n- 3\n").unwrap_err();
+    assert_eq!(err.to_string(), "invalid diff at line 4: unexpected line \"- 3\"");
+}
+
+#[test]
+fn patch_reader_matches_upstream_translation() {
+    for scenario in ["translate-patch2jd", "output-flag-translate-patch2jd"] {
+        let diff = Diff::from_patch_str(&parity_file(&format!("{scenario}/input.patch"))).unwrap();
+        assert_eq!(
+            diff.render(&RenderConfig::default()),
+            parity_file(&format!("{scenario}/output.jd")),
+            "{scenario}"
+        );
+    }
+}
+
+#[test]
+fn patch_reader_rejects_stray_context() {
+    let patch =
+        r#"[{"op":"test","path":"/items/5","value":1},{"op":"add","path":"/items/1","value":2}]"#;
+    let err = Diff::from_patch_str(patch).unwrap_err();
+    assert_eq!(err.to_string(), "unexpected JSON Patch test operation at /items/5");
+}
+
+#[test]
+fn merge_reader_emits_leaf_hunks() {
+    let diff = Diff::from_merge_str(r#"{"config":{"retries":3},"logging":null}"#).unwrap();
+    assert_eq!(
+        diff.render(&RenderConfig::default()),
+        "^ {\"Merge\":true}\n@ [\"config\",\"retries\"]\n+ 3\n@ [\"logging\"]\n+\n"
+    );
+}
+
+fn arb_json_value() -> impl Strategy<Value = serde_json::Value> {
+    use proptest::{collection, string::string_regex};
+
+    let leaf = prop_oneof![
+        Just(serde_json::Value::Null),
+        any::<bool>().prop_map(serde_json::Value::Bool),
+        (-1000i64..1000).prop_map(|n| serde_json::Value::Number(n.into())),
+        string_regex("[a-z]{0,4}").unwrap().prop_map(serde_json::Value::String),
+    ];
+
+    leaf.prop_recursive(3, 6, 4, |inner| {
+        prop_oneof![
+            collection::vec(inner.clone(), 0..4).prop_map(serde_json::Value::Array),
+            collection::btree_map(string_regex("[a-z]{1,4}").unwrap(), inner, 0..4)
+                .prop_map(|map| serde_json::Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn native_render_read_round_trip(a_json in arb_json_value(), b_json in arb_json_value()) {
+        let a = Node::from_json_value(a_json).unwrap();
+        let b = Node::from_json_value(b_json).unwrap();
+        let diff = a.diff(&b, &DiffOptions::default());
+        let read = Diff::from_native_str(&diff.render(&RenderConfig::default())).unwrap();
+        prop_assert_eq!(read, diff);
+    }
+
+    #[test]
+    fn patch_render_read_round_trip(a_json in arb_json_value(), b_json in arb_json_value()) {
+        let a = Node::from_json_value(a_json).unwrap();
+        let b = Node::from_json_value(b_json).unwrap();
+        let diff = a.diff(&b, &DiffOptions::default());
+        let Ok(patch) = diff.render_patch() else { return Ok(()) };
+        let read = Diff::from_patch_str(&patch).unwrap();
+        prop_assert_eq!(read.render_patch().unwrap(), patch);
+        prop_assert_eq!(a.apply_patch(&read).unwrap(), b);
+    }
+}
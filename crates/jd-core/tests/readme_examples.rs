@@ -16,3 +16,12 @@ fn jd_core_readme_example() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(patched, target);
     Ok(())
 }
+
+#[test]
+fn jd_core_readme_facade_example() -> Result<(), jd_core::JdError> {
+    let rendered = jd_core::Jd::new()
+        .precision(0.001)
+        .render_str(r#"{"latency":10.0}"#, r#"{"latency":10.5}"#)?;
+    assert_eq!(rendered, "@ [\"latency\"]\n- 10\n+ 10.5\n");
+    Ok(())
+}
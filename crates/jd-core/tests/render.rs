@@ -26,6 +26,32 @@ fn render_native_string_diff_colorizes() {
     assert!(rendered.contains("\u{1b}[32m"), "expected ANSI green segment");
 }
 
+#[test]
+fn render_native_indent_prefixes_context_lines() {
+    let lhs = Node::from_json_str("[{\"a\":1},2,[]]").unwrap();
+    let rhs = Node::from_json_str("[{\"a\":1},3,[]]").unwrap();
+    let diff = lhs.diff(&rhs, &DiffOptions::default());
+    let rendered = diff.render(&RenderConfig::default().with_indent(1));
+    assert_eq!(rendered, "@ [1]\n  {\n   \"a\": 1\n  }\n- 2\n+ 3\n  []\n");
+}
+
+#[test]
+fn render_patch_rejects_negative_indices() {
+    let diff = Diff::from_native_str("@ [-2]\n+ 1\n").unwrap();
+    assert_eq!(
+        diff.render_patch().unwrap_err().to_string(),
+        "JSON Pointer cannot address negative index -2"
+    );
+}
+
+#[test]
+fn rendered_numbers_parse_back_exactly() {
+    let value = Node::from(107_066_419.117_647_05);
+    let diff = DiffElement::new().with_path(PathSegment::key("a")).with_add(vec![value.clone()]);
+    let native = Diff::from_elements(vec![diff]).render(&RenderConfig::default());
+    assert_eq!(Diff::from_native_str(&native).unwrap().into_elements()[0].add, [value]);
+}
+
 #[test]
 fn render_patch_emits_context_tests() {
     let lhs = Node::from_json_str("[1,2,3]").unwrap();
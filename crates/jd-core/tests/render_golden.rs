@@ -52,13 +52,9 @@ fn render_parity_matches_go_outputs() {
         let lhs = Node::from_json_str(&fixture.lhs).expect("lhs parses");
         let rhs = Node::from_json_str(&fixture.rhs).expect("rhs parses");
 
-        let diff = if fixture.options.iter().any(|opt| opt == "merge") {
-            fixture.diff
-        } else {
-            let computed = lhs.diff(&rhs, &DiffOptions::default());
-            assert_eq!(computed, fixture.diff, "fixture {path:?} diff");
-            computed
-        };
+        let merge = fixture.options.iter().any(|opt| opt == "merge");
+        let diff = lhs.diff(&rhs, &DiffOptions::default().with_merge(merge));
+        assert_eq!(diff, fixture.diff, "fixture {path:?} diff");
 
         if let Some(expected) = fixture.render.native {
             let rendered = diff.render(&RenderConfig::default());
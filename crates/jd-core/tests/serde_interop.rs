@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use jd_core::{diff_values, node, ArrayMode, CanonicalizeError, DiffOptions, JdError, Node};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct Reading {
+    sensor: String,
+    value: f64,
+    tags: Vec<&'static str>,
+}
+
+fn reading(value: f64, tags: Vec<&'static str>) -> Reading {
+    Reading { sensor: "t1".to_string(), value, tags }
+}
+
+#[test]
+fn structs_convert_like_their_json() {
+    let node = Node::from_serialize(&reading(20.5, vec!["a"])).unwrap();
+    assert_eq!(node, node!({"sensor": "t1", "value": 20.5, "tags": ["a"]}));
+    assert_eq!(Node::from_serialize(&Option::<u8>::None).unwrap(), Node::Null);
+}
+
+#[test]
+fn precision_applies_to_struct_fields() {
+    let (before, after) = (reading(20.5, vec![]), reading(20.54, vec![]));
+    let exact = diff_values(&before, &after, &DiffOptions::default()).unwrap();
+    assert_eq!(exact.len(), 1);
+    let tolerant = DiffOptions::default().with_precision(0.1).unwrap();
+    assert!(diff_values(&before, &after, &tolerant).unwrap().is_empty());
+}
+
+#[test]
+fn set_mode_is_reported_instead_of_panicking() {
+    let (before, after) = (reading(1.0, vec!["a", "b"]), reading(1.0, vec!["b", "a", "c"]));
+    assert!(!diff_values(&before, &after, &DiffOptions::default()).unwrap().is_empty());
+    let sets = DiffOptions::default().with_array_mode(ArrayMode::Set).unwrap();
+    let err = diff_values(&before, &after, &sets).unwrap_err();
+    assert!(matches!(err, JdError::UnsupportedArrayMode(ArrayMode::Set)));
+}
+
+#[test]
+fn values_without_json_form_are_rejected() {
+    let map = BTreeMap::from([((1, 2), "pair")]);
+    let err = Node::from_serialize(&map).unwrap_err();
+    assert!(matches!(err, CanonicalizeError::Json(_)));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Backend {
+    Local,
+    Remote { url: String },
+    Pool(Vec<u16>),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Service {
+    name: String,
+    replicas: u8,
+    ratio: f32,
+    offset: i64,
+    backends: Vec<Backend>,
+    timeout: Option<u32>,
+    limits: (u8, u8),
+}
+
+fn service() -> Service {
+    Service {
+        name: "api".to_string(),
+        replicas: 2,
+        ratio: 0.5,
+        offset: -3,
+        backends: vec![
+            Backend::Local,
+            Backend::Remote { url: "https://a".to_string() },
+            Backend::Pool(vec![80, 443]),
+        ],
+        timeout: None,
+        limits: (1, 9),
+    }
+}
+
+#[test]
+fn typed_values_round_trip_through_nodes() {
+    let node = Node::from_serialize(&service()).unwrap();
+    assert_eq!(node.to_t::<Service>().unwrap(), service());
+}
+
+#[test]
+fn patched_nodes_deserialize_into_the_target_struct() {
+    let mut target = service();
+    target.replicas = 4;
+    target.timeout = Some(30);
+    target.backends.pop();
+    let diff = diff_values(&service(), &target, &DiffOptions::default()).unwrap();
+    let patched = Node::from_serialize(&service()).unwrap().apply_patch(&diff).unwrap();
+    assert_eq!(patched.to_t::<Service>().unwrap(), target);
+}
+
+#[test]
+fn void_and_mismatched_nodes() {
+    assert_eq!(Node::Void.to_t::<Option<u8>>().unwrap(), None);
+    assert!(Node::Void.to_t::<u8>().is_err());
+    assert!(node!(256).to_t::<u8>().is_err());
+    assert!(node!(1.5).to_t::<i32>().is_err());
+    assert_eq!(node!(-9007199254740992.0).to_t::<i64>().unwrap(), -9_007_199_254_740_992);
+    let err = node!({"Local": null, "Pool": []}).to_t::<Backend>().unwrap_err();
+    assert!(err.to_string().contains("single key"), "{err}");
+}
@@ -0,0 +1,955 @@
+// Public API of jd-core, checked by tests/public_api.rs. Regenerate with
+// UPDATE_PUBLIC_API=1 cargo test -p jd-core --test public_api
+
+mod arena {
+    #[derive(Debug, Default)]
+    #[private_fields]
+    pub struct NodeArena {}
+    impl NodeArena {
+        pub fn new() -> Self {}
+        pub fn intern(&mut self, node: &Node) -> SharedNode {}
+        pub fn len(&self) -> usize {}
+        pub fn is_empty(&self) -> bool {}
+    }
+    #[derive(Clone)]
+    #[private_fields]
+    pub struct SharedNode();
+    impl SharedNode {
+        pub fn ptr_eq(a: &Self, b: &Self) -> bool {}
+        pub fn as_leaf(&self) -> Option<&Node> {}
+        pub fn as_array(&self) -> Option<&[SharedNode]> {}
+        pub fn as_object(&self) -> Option<&BTreeMap<Arc<str>, SharedNode>> {}
+        pub fn to_node(&self) -> Node {}
+        pub fn diff(&self, other: &Self, options: &DiffOptions) -> Diff {}
+    }
+    impl fmt::Debug for SharedNode {}
+}
+mod comparator {
+    pub trait ValueComparator: fmt::Debug + Send + Sync + RefUnwindSafe + UnwindSafe {
+        fn equals(&self, lhs: &Node, rhs: &Node) -> Option<bool>;
+    }
+}
+mod de {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[private_fields]
+    pub struct DeserializeError {}
+    impl std::fmt::Display for DeserializeError {}
+    impl std::error::Error for DeserializeError {}
+    impl de::Error for DeserializeError {}
+    #[derive(Debug)]
+    #[private_fields]
+    pub struct NodeDeserializer {}
+    impl Node {
+        pub fn into_deserializer(self) -> NodeDeserializer {}
+        pub fn to_t<T: DeserializeOwned>(&self) -> Result<T, DeserializeError> {}
+    }
+    impl<'de> IntoDeserializer<'de, DeserializeError> for Node {}
+    impl<'de> IntoDeserializer<'de, DeserializeError> for NodeDeserializer {}
+    impl<'de> de::Deserializer<'de> for NodeDeserializer {}
+}
+pub mod diff {
+    mod batch {
+        pub fn diff_many<'a, I>(pairs: I, options: &DiffOptions) -> Vec<Diff>
+        where
+            I: IntoIterator<Item = (&'a Node, &'a Node)>,
+        {
+        }
+    }
+    mod format {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum DiffFormat {
+            Jd,
+            Patch,
+            Merge,
+        }
+        impl DiffFormat {
+            pub fn name(self) -> &'static str {}
+            pub fn read(self, input: &str) -> Result<Diff, ReadError> {}
+            pub fn render(self, diff: &Diff, config: &RenderConfig) -> Result<String, RenderError> {
+            }
+        }
+        impl fmt::Display for DiffFormat {}
+        #[derive(Clone, Debug, PartialEq, Eq, thiserror :: Error)]
+        #[private_fields]
+        pub struct ParseDiffFormatError {}
+        impl FromStr for DiffFormat {}
+    }
+    mod path {
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum PathSegment {
+            Key(String),
+            Index(i64),
+            Set,
+            MultiSet,
+            SetKeys(BTreeMap<String, Node>),
+            MultiSetKeys(BTreeMap<String, Node>),
+        }
+        impl PathSegment {
+            pub fn key<S>(value: S) -> Self
+            where
+                S: Into<String>,
+            {
+            }
+            pub fn index<I>(value: I) -> Self
+            where
+                I: Into<i64>,
+            {
+            }
+            pub fn is_set_marker(&self) -> bool {}
+        }
+        impl fmt::Display for PathSegment {}
+        impl Serialize for PathSegment {}
+        impl<'de> Deserialize<'de> for PathSegment {}
+        #[derive(
+            Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[private_fields]
+        pub struct Path();
+        impl Path {
+            pub fn new() -> Self {}
+            pub fn with_segment(mut self, segment: PathSegment) -> Self {}
+            pub fn segments(&self) -> &[PathSegment] {}
+            pub fn len(&self) -> usize {}
+            pub fn is_empty(&self) -> bool {}
+            pub fn drop_last(&self) -> Self {}
+            pub fn into_segments(self) -> Vec<PathSegment> {}
+            pub fn push(&mut self, segment: PathSegment) {}
+            pub fn pop(&mut self) -> Option<PathSegment> {}
+        }
+        impl From<Vec<PathSegment>> for Path {}
+        impl From<PathSegment> for Path {}
+        impl fmt::Display for Path {}
+        impl<'a> IntoIterator for &'a Path {}
+        impl IntoIterator for Path {}
+        pub fn root_path() -> Path {}
+        pub fn path_from_segments<I>(segments: I) -> Path
+        where
+            I: IntoIterator<Item = PathSegment>,
+        {
+        }
+    }
+    mod read {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[private_fields]
+        pub struct ReadError {}
+        impl std::fmt::Display for ReadError {}
+        impl std::error::Error for ReadError {}
+        impl Diff {
+            pub fn from_native_str(input: &str) -> Result<Self, ReadError> {}
+            pub fn from_patch_str(input: &str) -> Result<Self, ReadError> {}
+            pub fn from_merge_str(input: &str) -> Result<Self, ReadError> {}
+        }
+    }
+    mod rebase {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[private_fields]
+        pub struct RebaseError {}
+        impl std::fmt::Display for RebaseError {}
+        impl std::error::Error for RebaseError {}
+        impl Diff {
+            pub fn rebase(&self, onto: &Diff) -> Result<Diff, RebaseError> {}
+        }
+    }
+    mod renderer {
+        pub trait DiffRenderer {
+            fn render_to(&self, diff: &Diff, output: &mut dyn io::Write)
+                -> Result<(), RenderError>;
+            fn render(&self, diff: &Diff) -> Result<String, RenderError> {}
+        }
+        impl DiffRenderer for RenderConfig {}
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct PatchRenderer;
+        impl DiffRenderer for PatchRenderer {}
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct MergeRenderer;
+        impl DiffRenderer for MergeRenderer {}
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct RawRenderer;
+        impl DiffRenderer for RawRenderer {}
+    }
+    mod scratch {
+        #[derive(Debug, Default)]
+        #[private_fields]
+        pub struct DiffScratch {}
+        impl DiffScratch {
+            pub fn new() -> Self {}
+            pub fn render(&mut self, diff: &Diff, config: &RenderConfig) -> &str {}
+        }
+        pub fn diff_nodes_with_scratch(
+            lhs: &Node,
+            rhs: &Node,
+            options: &DiffOptions,
+            scratch: &mut DiffScratch,
+        ) -> Diff {
+        }
+    }
+    mod validate {
+        impl DiffElement {
+            pub fn validate(&self) -> Result<(), ElementError> {}
+        }
+        impl Diff {
+            pub fn validate(&self) -> Result<(), ElementError> {}
+        }
+        #[derive(Clone, Debug, Default)]
+        #[private_fields]
+        pub struct DiffBuilder {}
+        impl DiffBuilder {
+            pub fn new() -> Self {}
+            pub fn with_element(mut self, element: DiffElement) -> Result<Self, ElementError> {}
+            pub fn build(self) -> Diff {}
+        }
+    }
+    pub use batch::diff_many;
+    pub use format::{DiffFormat, ParseDiffFormatError};
+    pub use path::{path_from_segments, root_path, Path, PathSegment};
+    pub use read::ReadError;
+    pub use rebase::RebaseError;
+    pub use renderer::{DiffRenderer, MergeRenderer, PatchRenderer, RawRenderer};
+    pub use scratch::{diff_nodes_with_scratch, DiffScratch};
+    pub use validate::DiffBuilder;
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[non_exhaustive]
+    pub struct DiffMetadata {
+        pub merge: bool,
+        pub set_keys: Option<Vec<String>>,
+        pub color: Option<bool>,
+    }
+    impl DiffMetadata {
+        pub fn merge() -> Self {}
+        pub fn with_set_keys<I, S>(mut self, keys: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+        }
+        pub fn with_color(mut self, enabled: bool) -> Self {}
+    }
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[non_exhaustive]
+    #[private_fields]
+    pub struct DiffElement {
+        pub metadata: Option<DiffMetadata>,
+        pub path: Path,
+        pub before: Vec<Node>,
+        pub remove: Vec<Node>,
+        pub add: Vec<Node>,
+        pub after: Vec<Node>,
+    }
+    impl DiffElement {
+        pub fn new() -> Self {}
+        pub fn with_metadata(mut self, metadata: DiffMetadata) -> Self {}
+        pub fn with_path<P>(mut self, path: P) -> Self
+        where
+            P: Into<Path>,
+        {
+        }
+        pub fn with_before(mut self, before: Vec<Node>) -> Self {}
+        pub fn with_remove(mut self, remove: Vec<Node>) -> Self {}
+        pub fn with_add(mut self, add: Vec<Node>) -> Self {}
+        pub fn with_after(mut self, after: Vec<Node>) -> Self {}
+    }
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[private_fields]
+    pub struct Diff {}
+    #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+    #[private_fields]
+    pub struct RenderConfig {}
+    impl RenderConfig {
+        pub fn new() -> Self {}
+        pub fn with_color(mut self, enabled: bool) -> Self {}
+        pub fn color_enabled(self) -> bool {}
+        pub fn with_max_hunks(mut self, max: usize) -> Self {}
+        pub fn max_hunks(self) -> Option<usize> {}
+        pub fn with_max_value_bytes(mut self, max: usize) -> Self {}
+        pub fn max_value_bytes(self) -> Option<usize> {}
+        pub fn with_indent(mut self, width: usize) -> Self {}
+        pub fn indent(self) -> usize {}
+        pub fn with_sorted_hunks(mut self, enabled: bool) -> Self {}
+        pub fn sorted_hunks(self) -> bool {}
+    }
+    impl RenderConfig {
+        pub fn color(enabled: bool) -> Self {}
+    }
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[private_fields]
+    pub struct RenderError {}
+    impl RenderError {
+        pub fn new(message: impl Into<String>) -> Self {}
+    }
+    impl std::fmt::Display for RenderError {}
+    impl std::error::Error for RenderError {}
+    impl From<serde_json::Error> for RenderError {}
+    impl From<std::io::Error> for RenderError {}
+    impl From<PatchError> for RenderError {}
+    impl Diff {
+        pub fn empty() -> Self {}
+        pub fn from_elements(elements: Vec<DiffElement>) -> Self {}
+        pub fn len(&self) -> usize {}
+        pub fn is_empty(&self) -> bool {}
+        pub fn is_truncated(&self) -> bool {}
+        pub fn iter(&self) -> std::slice::Iter<'_, DiffElement> {}
+        pub fn effective_metadata(&self) -> Vec<DiffMetadata> {}
+        pub fn sorted(&self) -> Self {}
+        pub fn into_elements(self) -> Vec<DiffElement> {}
+        pub fn render(&self, config: &RenderConfig) -> String {}
+        pub fn render_into(&self, config: &RenderConfig, output: &mut String) {}
+        pub fn render_patch(&self) -> Result<String, RenderError> {}
+        pub fn render_merge(&self) -> Result<String, RenderError> {}
+        pub fn render_raw(&self) -> Result<String, RenderError> {}
+        pub fn reverse(&self) -> Result<Diff, RenderError> {}
+        pub fn minimize(&self) -> Diff {}
+    }
+    impl IntoIterator for Diff {}
+    impl<'a> IntoIterator for &'a Diff {}
+    impl From<Vec<DiffElement>> for Diff {}
+    pub fn diff_nodes(lhs: &Node, rhs: &Node, options: &DiffOptions) -> Diff {}
+}
+mod error {
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum CanonicalizeError {
+        Json(serde_json::Error),
+        Yaml(serde_yaml::Error),
+        #[cfg(feature = "toml")]
+        Toml(toml::de::Error),
+        #[cfg(feature = "cbor")]
+        Cbor(ciborium::de::Error<std::io::Error>),
+        #[cfg(feature = "msgpack")]
+        MessagePack(rmp_serde::decode::Error),
+        NumberOutOfRange {
+            value: String,
+        },
+        NonStringYamlKey {
+            found: String,
+        },
+        UnsupportedYamlTag {
+            tag: String,
+        },
+        NotFinite {
+            value: f64,
+        },
+        Limit(LimitError),
+    }
+    impl CanonicalizeError {
+        pub fn location(&self, input: &str) -> Option<Location> {}
+    }
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum EncodeError {
+        Void,
+        #[cfg(feature = "toml")]
+        Toml(toml::ser::Error),
+        #[cfg(feature = "cbor")]
+        Cbor(ciborium::ser::Error<std::io::Error>),
+        #[cfg(feature = "msgpack")]
+        MessagePack(rmp_serde::encode::Error),
+    }
+    #[derive(Clone, Debug, Error, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum OptionsError {
+        PrecisionIncompatible,
+        SetKeysRequireSetMode,
+        InvalidTolerance,
+        EmptySetKey,
+        InvalidSimilarity,
+        InvalidMaxHunks,
+        InvalidSchema { pointer: String, reason: String },
+        UnsupportedArrayMode(crate::ArrayMode),
+        UnsupportedSchema { pointer: String, reason: String },
+        InvalidOpts { reason: String },
+    }
+    #[derive(Clone, Debug, Error, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum ElementError {
+        Empty { path: Path },
+        VoidValue { path: Path },
+        ContextWithoutIndex { path: Path },
+        ExcessContext { path: Path },
+        MultipleValues { path: Path },
+        MergePath { path: Path },
+        MergeRemoval { path: Path },
+        MergeAddition { path: Path },
+    }
+    #[derive(Clone, Debug, Error, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum LimitError {
+        InputTooLarge { limit: usize },
+        TooManyNodes { limit: usize },
+        TooDeep { limit: usize },
+        Timeout { timeout: std::time::Duration },
+    }
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum JdError {
+        Canonicalize(CanonicalizeError),
+        Options(OptionsError),
+        Limit(LimitError),
+        UnsupportedArrayMode(crate::ArrayMode),
+        Render(crate::RenderError),
+    }
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum JsonEditError {
+        Canonicalize(CanonicalizeError),
+        Patch(crate::PatchError),
+        Unsupported { path: Path, reason: String },
+    }
+    #[cfg(feature = "yaml-edit")]
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum YamlEditError {
+        Canonicalize(CanonicalizeError),
+        Patch(crate::PatchError),
+        Unsupported { path: Path, reason: String },
+    }
+}
+pub mod github {
+    pub const VOLATILE_FIELDS: [&str; 5] =
+        ["etag", "last_modified", "node_id", "pushed_at", "updated_at"];
+    pub fn strip_volatile(payload: &Node) -> Node {}
+    pub fn volatile_path_options<'a>(
+        payloads: impl IntoIterator<Item = &'a Node>,
+    ) -> Vec<PathOption> {
+    }
+}
+mod hash {
+    pub type HashCode = [u8; 8];
+    pub fn hash_bytes(input: &[u8]) -> HashCode {}
+    pub fn combine(mut codes: Vec<HashCode>) -> HashCode {}
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[non_exhaustive]
+    pub enum HashAlgorithm {
+        Fnv64,
+        SipHash128 {
+            key: [u64; 2],
+        },
+        #[cfg(feature = "simd")]
+        Xxh3,
+    }
+    impl HashAlgorithm {
+        pub fn sip_hash_128() -> Self {}
+    }
+    impl fmt::Debug for HashAlgorithm {}
+    impl NodeHasher for HashAlgorithm {}
+    pub type Digest = [u8; 16];
+    pub trait NodeHasher: fmt::Debug + Send + Sync + RefUnwindSafe + UnwindSafe {
+        fn width(&self) -> usize;
+        fn digest(&self, tag: u8, input: &[u8]) -> Digest;
+    }
+}
+mod hashed {
+    #[derive(Debug)]
+    #[private_fields]
+    pub struct HashedNode {}
+    impl HashedNode {
+        pub fn new(node: Node, options: &DiffOptions) -> Self {}
+        pub fn node(&self) -> &Node {}
+        pub fn into_node(self) -> Node {}
+        pub fn hash_code(&self) -> HashCode {}
+        pub fn diff(&self, other: &Node) -> Diff {}
+        pub fn equals(&self, other: &Node) -> bool {}
+    }
+}
+mod jd {
+    #[derive(Clone, Debug)]
+    #[private_fields]
+    pub struct Jd {}
+    impl Default for Jd {}
+    impl Jd {
+        pub fn new() -> Self {}
+        pub fn with_options(options: DiffOptions) -> Self {}
+        pub fn set(self) -> Self {}
+        pub fn multiset(self) -> Self {}
+        pub fn set_keys<I, S>(self, keys: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+        }
+        pub fn preset(self, preset: Preset) -> Self {}
+        pub fn precision(self, precision: f64) -> Self {}
+        pub fn relative_precision(self, tolerance: f64) -> Self {}
+        pub fn max_ulps(self, max_ulps: u64) -> Self {}
+        pub fn prune_empty(self) -> Self {}
+        pub fn yaml(mut self) -> Self {}
+        pub fn color(mut self, enabled: bool) -> Self {}
+        pub fn limits(mut self, limits: Limits) -> Self {}
+        pub fn options(&self) -> Result<&DiffOptions, OptionsError> {}
+        pub fn render_config(&self) -> RenderConfig {}
+        pub fn resource_limits(&self) -> Limits {}
+        pub fn parse(&self, input: &str) -> Result<Node, CanonicalizeError> {}
+        pub fn diff(&self, lhs: &Node, rhs: &Node) -> Result<Diff, JdError> {}
+        pub fn diff_str(&self, lhs: &str, rhs: &str) -> Result<Diff, JdError> {}
+        pub fn render_str(&self, lhs: &str, rhs: &str) -> Result<String, JdError> {}
+        pub fn render_as(
+            &self,
+            lhs: &Node,
+            rhs: &Node,
+            format: DiffFormat,
+        ) -> Result<String, JdError> {
+        }
+    }
+}
+mod json_edit {
+    impl Diff {
+        pub fn apply_to_json_str(&self, input: &str) -> Result<String, JsonEditError> {}
+    }
+}
+mod limits {
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[private_fields]
+    pub struct Limits {}
+    impl Limits {
+        pub fn with_max_input_bytes(mut self, bytes: usize) -> Self {}
+        pub fn max_input_bytes(&self) -> Option<usize> {}
+        pub fn with_max_nodes(mut self, nodes: usize) -> Self {}
+        pub fn max_nodes(&self) -> Option<usize> {}
+        pub fn with_max_depth(mut self, depth: usize) -> Self {}
+        pub fn max_depth(&self) -> Option<usize> {}
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {}
+        pub fn timeout(&self) -> Option<Duration> {}
+        pub fn check_input_len(&self, len: usize) -> Result<(), LimitError> {}
+        pub fn check_node(&self, node: &Node) -> Result<(), LimitError> {}
+    }
+}
+mod location {
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    #[private_fields]
+    pub struct Location {}
+    impl Location {
+        pub fn offset(&self) -> usize {}
+        pub fn line(&self) -> usize {}
+        pub fn column(&self) -> usize {}
+        pub fn path(&self) -> Option<&Path> {}
+    }
+}
+mod macros {
+    #[macro_export]
+    macro_rules! node {}
+}
+mod metrics {
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    #[private_fields]
+    pub struct Metrics {}
+    impl Metrics {
+        pub fn depth(&self) -> usize {}
+        pub fn nodes(&self) -> usize {}
+        pub fn string_bytes(&self) -> usize {}
+        pub fn array_lengths(&self) -> &Histogram {}
+        pub fn object_sizes(&self) -> &Histogram {}
+    }
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    #[private_fields]
+    pub struct Histogram {}
+    impl Histogram {
+        pub fn total(&self) -> usize {}
+        pub fn max(&self) -> Option<usize> {}
+        pub fn buckets(&self) -> impl Iterator<Item = (RangeInclusive<usize>, usize)> + '_ {}
+    }
+}
+mod node {
+    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum Node {
+        Void,
+        Null,
+        Bool(bool),
+        Number(Number),
+        String(String),
+        Array(Vec<Node>),
+        Object(BTreeMap<String, Node>),
+    }
+    impl Clone for Node {}
+    impl Drop for Node {}
+    impl Ord for Node {}
+    impl PartialOrd for Node {}
+    impl Node {
+        pub fn from_json_str(input: &str) -> Result<Self, CanonicalizeError> {}
+        pub fn from_json_str_indexed(
+            input: &str,
+        ) -> Result<(Self, crate::ParseIndex), CanonicalizeError> {
+        }
+        pub fn from_json_reader(reader: impl std::io::Read) -> Result<Self, CanonicalizeError> {}
+        pub fn from_yaml_str(input: &str) -> Result<Self, CanonicalizeError> {}
+        #[cfg(feature = "toml")]
+        pub fn from_toml_str(input: &str) -> Result<Self, CanonicalizeError> {}
+        #[cfg(feature = "cbor")]
+        pub fn from_cbor_slice(input: &[u8]) -> Result<Self, CanonicalizeError> {}
+        #[cfg(feature = "msgpack")]
+        pub fn from_msgpack_slice(input: &[u8]) -> Result<Self, CanonicalizeError> {}
+        pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self, CanonicalizeError> {
+        }
+        pub fn from_json_value(value: JsonValue) -> Result<Self, CanonicalizeError> {}
+        pub fn to_json_value(&self) -> Option<JsonValue> {}
+        pub fn to_yaml_string(&self) -> Option<String> {}
+        pub fn to_canonical_json_string(&self) -> Result<String, crate::EncodeError> {}
+        #[cfg(feature = "toml")]
+        pub fn to_toml_string(&self) -> Result<String, crate::EncodeError> {}
+        #[cfg(feature = "cbor")]
+        pub fn to_cbor_vec(&self) -> Result<Vec<u8>, crate::EncodeError> {}
+        #[cfg(feature = "msgpack")]
+        pub fn to_msgpack_vec(&self) -> Result<Vec<u8>, crate::EncodeError> {}
+        pub fn eq_with_options(&self, other: &Self, options: &DiffOptions) -> bool {}
+        pub fn explain_inequality(&self, other: &Self, options: &DiffOptions) -> Option<Path> {}
+        pub fn diff(&self, other: &Self, options: &DiffOptions) -> crate::Diff {}
+        pub fn diff_with_limits(
+            &self,
+            other: &Self,
+            options: &DiffOptions,
+            limits: &Limits,
+        ) -> Result<crate::Diff, LimitError> {
+        }
+        pub fn apply_patch(&self, diff: &crate::Diff) -> Result<Self, PatchError> {}
+        pub fn hash_code(&self, options: &DiffOptions) -> HashCode {}
+        pub fn redact<'a>(
+            &self,
+            paths: impl IntoIterator<Item = &'a Path>,
+            replacement: &Self,
+        ) -> Self {
+        }
+        pub fn sort_arrays_by<K: AsRef<str>>(
+            &self,
+            mut key: impl FnMut(&Path) -> Option<K>,
+        ) -> Self {
+        }
+        pub fn prune_empty(&self) -> Self {}
+        pub fn metrics(&self) -> Metrics {}
+        pub fn walk(&self, visitor: &mut impl crate::Visitor) {}
+    }
+    impl TryFrom<JsonValue> for Node {}
+    impl From<bool> for Node {}
+    impl From<i64> for Node {}
+    impl From<f64> for Node {}
+    impl From<Number> for Node {}
+    impl From<&str> for Node {}
+    impl From<String> for Node {}
+    impl From<Vec<Node>> for Node {}
+    impl From<BTreeMap<String, Node>> for Node {}
+    impl std::ops::Index<&str> for Node {}
+    impl std::ops::Index<usize> for Node {}
+}
+mod node_ref {
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum NodeRef<'a> {
+        Void,
+        Null,
+        Bool(bool),
+        Number(Number),
+        String(Cow<'a, str>),
+        Array(Vec<NodeRef<'a>>),
+        Object(BTreeMap<Cow<'a, str>, NodeRef<'a>>),
+    }
+    impl<'a> NodeRef<'a> {
+        pub fn from_json_str(input: &'a str) -> Result<Self, CanonicalizeError> {}
+        pub fn into_owned(self) -> Node {}
+        pub fn diff(&self, other: &NodeRef<'_>, options: &DiffOptions) -> Diff {}
+    }
+    impl<'de> Deserialize<'de> for NodeRef<'de> {}
+    impl<'de> Visitor<'de> for NodeRefVisitor {}
+}
+mod number {
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    #[private_fields]
+    pub struct Number();
+    impl Number {
+        pub fn new(value: f64) -> Result<Self, CanonicalizeError> {}
+        pub fn get(self) -> f64 {}
+        pub fn equals_with_precision(self, other: Self, precision: f64) -> bool {}
+        pub fn equals_with_relative_precision(self, other: Self, tolerance: f64) -> bool {}
+        pub fn equals_within_ulps(self, other: Self, max_ulps: u64) -> bool {}
+        pub fn hash_code(self) -> crate::hash::HashCode {}
+        pub fn to_json_number(self) -> JsonNumber {}
+    }
+    impl TryFrom<f64> for Number {}
+    impl PartialEq for Number {}
+    impl Eq for Number {}
+    impl PartialOrd for Number {}
+    impl Ord for Number {}
+    impl Hash for Number {}
+}
+mod options {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum ArrayMode {
+        List,
+        Set,
+        MultiSet,
+    }
+    impl Default for ArrayMode {}
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[non_exhaustive]
+    pub enum ListAlignment {
+        Lcs,
+        Patience,
+    }
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[private_fields]
+    pub struct DiffOptions {}
+    impl Default for DiffOptions {}
+    impl DiffOptions {
+        pub fn array_mode(&self) -> ArrayMode {}
+        pub fn precision(&self) -> f64 {}
+        pub fn relative_precision(&self) -> f64 {}
+        pub fn max_ulps(&self) -> u64 {}
+        pub fn numbers_equal(&self, lhs: Number, rhs: Number) -> bool {}
+        pub fn timestamp_tolerance(&self) -> Option<f64> {}
+        pub fn strings_equal(&self, lhs: &str, rhs: &str) -> bool {}
+        pub fn set_keys(&self) -> Option<&[String]> {}
+        pub fn prune_empty(&self) -> bool {}
+        pub fn string_scalars(&self) -> bool {}
+        pub fn merge(&self) -> bool {}
+        pub fn spill_threshold(&self) -> Option<usize> {}
+        pub fn hash_algorithm(&self) -> HashAlgorithm {}
+        pub fn hasher(&self) -> &dyn NodeHasher {}
+        pub fn list_alignment(&self) -> ListAlignment {}
+        pub fn similarity_threshold(&self) -> Option<f64> {}
+        pub fn max_hunks(&self) -> Option<usize> {}
+        pub fn with_array_mode(mut self, mode: ArrayMode) -> Result<Self, OptionsError> {}
+        pub fn with_precision(mut self, precision: f64) -> Result<Self, OptionsError> {}
+        pub fn with_relative_precision(mut self, tolerance: f64) -> Result<Self, OptionsError> {}
+        pub fn with_max_ulps(mut self, max_ulps: u64) -> Result<Self, OptionsError> {}
+        pub fn with_timestamp_tolerance(mut self, seconds: f64) -> Result<Self, OptionsError> {}
+        pub fn with_set_keys<I, S>(mut self, keys: I) -> Result<Self, OptionsError>
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+        }
+        pub fn with_prune_empty(mut self, enabled: bool) -> Self {}
+        pub fn with_string_scalars(mut self, enabled: bool) -> Self {}
+        pub fn with_merge(mut self, enabled: bool) -> Self {}
+        pub fn with_spill_threshold(mut self, bytes: usize) -> Self {}
+        pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {}
+        pub fn with_hasher(mut self, hasher: impl NodeHasher + 'static) -> Self {}
+        pub fn with_comparator(
+            mut self,
+            at: impl IntoIterator<Item = PathMatcher>,
+            comparator: impl ValueComparator + 'static,
+        ) -> Self {
+        }
+        pub fn with_list_alignment(mut self, alignment: ListAlignment) -> Self {}
+        pub fn with_similarity_threshold(mut self, threshold: f64) -> Result<Self, OptionsError> {}
+        pub fn with_max_hunks(mut self, max: usize) -> Result<Self, OptionsError> {}
+        pub fn with_path_option(mut self, option: PathOption) -> Result<Self, OptionsError> {}
+        pub fn preset(preset: Preset) -> Self {}
+        pub fn with_preset(mut self, preset: Preset) -> Self {}
+        pub fn with_json_schema(self, schema: &crate::Node) -> Result<Self, OptionsError> {}
+        pub fn from_json_opts(input: &str) -> Result<Self, OptionsError> {}
+        pub fn to_json_opts(&self) -> String {}
+        pub fn path_options(&self) -> &[PathOption] {}
+    }
+    impl fmt::Display for ArrayMode {}
+}
+mod parse_index {
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    #[private_fields]
+    pub struct ParseIndex {}
+    impl ParseIndex {
+        pub fn span_of(&self, path: &Path) -> Option<&SourceSpan> {}
+        pub fn len(&self) -> usize {}
+        pub fn is_empty(&self) -> bool {}
+    }
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    #[private_fields]
+    pub struct SourceSpan {}
+    impl SourceSpan {
+        pub fn start(&self) -> &Location {}
+        pub fn end(&self) -> &Location {}
+    }
+}
+mod patch {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[private_fields]
+    pub struct PatchError {}
+    impl fmt::Display for PatchError {}
+    impl std::error::Error for PatchError {}
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[private_fields]
+    pub struct RecordFailure {}
+    impl RecordFailure {
+        pub fn record(&self) -> usize {}
+        pub fn error(&self) -> &PatchError {}
+    }
+    impl fmt::Display for RecordFailure {}
+    impl std::error::Error for RecordFailure {}
+    #[derive(Debug)]
+    #[private_fields]
+    pub struct PatchStream<'a, I> {}
+    impl<I> PatchStream<'_, I> {
+        pub fn failures(&self) -> &[RecordFailure] {}
+        pub fn into_failures(self) -> Vec<RecordFailure> {}
+    }
+    impl<I: Iterator<Item = Node>> Iterator for PatchStream<'_, I> {}
+    impl Diff {
+        pub fn apply_to_each<I>(&self, records: I) -> PatchStream<'_, I::IntoIter>
+        where
+            I: IntoIterator<Item = Node>,
+        {
+        }
+    }
+}
+mod path_options {
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum PathMatcher {
+        Key(String),
+        Index(i64),
+        AnyKey,
+        AnyIndex,
+    }
+    impl PathMatcher {
+        pub fn key(key: impl Into<String>) -> Self {}
+    }
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[non_exhaustive]
+    pub enum PathSetting {
+        ArrayMode(ArrayMode),
+        SetKeys(Vec<String>),
+        Precision(f64),
+        TimestampTolerance(f64),
+        DiffOff,
+        DiffOn,
+        Base64,
+    }
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[private_fields]
+    pub struct PathOption {}
+    impl PathOption {
+        pub fn new(at: impl IntoIterator<Item = PathMatcher>) -> Self {}
+        pub fn with_setting(mut self, setting: PathSetting) -> Self {}
+        pub fn at(&self) -> &[PathMatcher] {}
+        pub fn settings(&self) -> &[PathSetting] {}
+    }
+}
+mod preset {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[non_exhaustive]
+    pub enum Preset {
+        GitHub,
+        Kubernetes,
+        OpenApi,
+        Terraform,
+    }
+    impl Preset {
+        pub const ALL: [Self; 4] = [Self::GitHub, Self::Kubernetes, Self::OpenApi, Self::Terraform];
+        pub fn name(self) -> &'static str {}
+        pub fn path_options(self) -> Vec<PathOption> {}
+        pub fn document_path_options(self, lhs: &Node, rhs: &Node) -> Vec<PathOption> {}
+        pub fn normalize(self, node: &Node) -> Node {}
+    }
+    impl fmt::Display for Preset {}
+    #[derive(Clone, Debug, PartialEq, Eq, thiserror :: Error)]
+    #[private_fields]
+    pub struct ParsePresetError {}
+    impl FromStr for Preset {}
+}
+pub mod progress {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum Phase {
+        Parse,
+        Hash,
+        Lcs,
+        Render,
+    }
+    impl fmt::Display for Phase {}
+    pub trait ProgressSink {
+        fn report(&self, phase: Phase, percent: u8);
+    }
+    impl<F: Fn(Phase, u8)> ProgressSink for F {}
+    pub fn install(sink: impl ProgressSink + 'static) -> ProgressGuard {}
+    #[private_fields]
+    pub struct ProgressGuard {}
+    impl Drop for ProgressGuard {}
+    impl fmt::Debug for ProgressGuard {}
+}
+pub mod query {
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    #[private_fields]
+    pub struct JsonPath {}
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[private_fields]
+    pub struct QueryError {}
+    impl fmt::Display for QueryError {}
+    impl std::error::Error for QueryError {}
+    impl JsonPath {
+        pub fn parse(input: &str) -> Result<Self, QueryError> {}
+        pub fn select<'a>(&self, node: &'a Node) -> Vec<(Path, &'a Node)> {}
+        pub fn paths(&self, node: &Node) -> Vec<Path> {}
+        pub fn to_matchers(&self) -> Result<Vec<PathMatcher>, QueryError> {}
+    }
+    impl FromStr for JsonPath {}
+    impl fmt::Display for JsonPath {}
+}
+pub mod task {
+    #[derive(Debug)]
+    #[private_fields]
+    pub struct Task<T> {}
+    impl<T: Send + 'static> Task<T> {
+        pub fn spawn<F>(work: F) -> Self
+        where
+            F: FnOnce() -> T + Send + 'static,
+        {
+        }
+    }
+    impl<T> Task<T> {
+        pub fn is_finished(&self) -> bool {}
+        pub fn wait(self) -> T {}
+    }
+    impl<T> Future for Task<T> {}
+    pub fn diff(jd: &Jd, lhs: Node, rhs: Node) -> Task<Result<Diff, JdError>> {}
+    pub fn patch(node: Node, diff: Diff) -> Task<Result<Node, PatchError>> {}
+}
+pub mod terraform {
+    pub const SENSITIVE_VALUE: &str = "(sensitive value)";
+    pub fn normalize(document: &Node) -> Node {}
+    pub fn mask_sensitive(document: &Node) -> Node {}
+    pub fn key_by_address(document: &Node) -> Node {}
+    pub fn instance_address(resource: &Node, instance: &Node) -> Option<String> {}
+}
+mod visit {
+    pub trait Visitor {
+        fn enter(&mut self, path: &Path, node: &Node) {}
+        fn exit(&mut self, path: &Path, node: &Node) {}
+    }
+}
+#[cfg(feature = "yaml-edit")]
+mod yaml_edit {
+    impl Diff {
+        pub fn apply_to_yaml_str(&self, input: &str) -> Result<String, YamlEditError> {}
+    }
+}
+pub use arena::{NodeArena, SharedNode};
+pub use comparator::ValueComparator;
+pub use de::{DeserializeError, NodeDeserializer};
+pub use diff::{
+    Diff, DiffBuilder, DiffElement, DiffFormat, DiffMetadata, DiffRenderer, DiffScratch,
+    MergeRenderer, ParseDiffFormatError, PatchRenderer, Path, PathSegment, RawRenderer, ReadError,
+    RebaseError, RenderConfig, RenderError,
+};
+#[cfg(feature = "yaml-edit")]
+pub use error::YamlEditError;
+pub use error::{
+    CanonicalizeError, ElementError, EncodeError, JdError, JsonEditError, LimitError, OptionsError,
+};
+pub use hash::{combine, hash_bytes, Digest, HashAlgorithm, HashCode, NodeHasher};
+pub use hashed::HashedNode;
+pub use jd::Jd;
+pub use limits::Limits;
+pub use location::Location;
+pub use metrics::{Histogram, Metrics};
+pub use node::Node;
+pub use node_ref::NodeRef;
+pub use number::Number;
+pub use options::{ArrayMode, DiffOptions, ListAlignment};
+pub use parse_index::{ParseIndex, SourceSpan};
+pub use patch::{PatchError, PatchStream, RecordFailure};
+pub use path_options::{PathMatcher, PathOption, PathSetting};
+pub use preset::{ParsePresetError, Preset};
+pub use visit::Visitor;
+pub fn diff_values<A, B>(lhs: &A, rhs: &B, options: &DiffOptions) -> Result<Diff, JdError>
+where
+    A: serde::Serialize + ?Sized,
+    B: serde::Serialize + ?Sized,
+{
+}
+pub fn equal(lhs: &Node, rhs: &Node, options: &DiffOptions) -> bool {}
+pub fn version() -> &'static str {}
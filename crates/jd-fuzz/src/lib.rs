@@ -25,17 +25,58 @@
 //! ```
 //! jd_fuzz::fuzz_patch(b"example");
 //! ```
+//!
+//! Apply generated, structurally valid diffs:
+//!
+//! ```
+//! jd_fuzz::fuzz_patch_structured(b"example");
+//! ```
+//!
+//! Check that rendered diffs parse back to the same diff:
+//!
+//! ```
+//! jd_fuzz::fuzz_render_roundtrip(b"example");
+//! ```
+//!
+//! Diff under options derived from the input:
+//!
+//! ```
+//! jd_fuzz::fuzz_diff_with_options(b"example");
+//! ```
+//!
+//! Check the algebraic laws of diffs and patches:
+//!
+//! ```
+//! jd_fuzz::fuzz_patch_laws(b"example");
+//! ```
+//!
+//! Check that YAML output parses back to the same node:
+//!
+//! ```
+//! jd_fuzz::fuzz_yaml_roundtrip(b"a: [1, yes, ~]");
+//! ```
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+pub mod seed;
+
+use std::collections::BTreeMap;
+
 use arbitrary::Unstructured;
-use jd_core::{Diff, DiffOptions, Node};
+use jd_core::{
+    ArrayMode, Diff, DiffBuilder, DiffElement, DiffMetadata, DiffOptions, Jd, Node, PathMatcher,
+    PathOption, PathSegment, PathSetting, RenderConfig,
+};
 use serde_json::{self, Map as JsonMap, Number as JsonNumber, Value as JsonValue};
 
 const MAX_DEPTH: usize = 4;
 const MAX_ARRAY_LEN: u8 = 6;
 const MAX_OBJECT_LEN: u8 = 6;
 const MAX_STRING_LEN: u8 = 12;
+const MAX_DIFF_ELEMENTS: u8 = 4;
+const MAX_PATH_LEN: u8 = 4;
+/// Keys shared by generated documents and paths, so that paths often resolve.
+const COMMON_KEYS: [&str; 3] = ["a", "b", "id"];
 
 /// Feeds arbitrary bytes through the JSON and YAML canonicalization routines.
 ///
@@ -103,6 +144,352 @@ pub fn fuzz_patch(data: &[u8]) {
     }
 }
 
+/// Asserts the algebraic laws that diffs of random documents obey.
+///
+/// For a diff `d` from `a` to `b`:
+///
+/// - `d` patches `a` into `b`, and applying `d` to `b` again is rejected or
+///   leaves `b` unchanged;
+/// - `reverse(reverse(d)) == d`;
+/// - composing `d` with `reverse(d)`, that is concatenating them and
+///   [minimizing](Diff::minimize), gives an empty diff.
+///
+/// Merge diffs cannot be reversed; for them the harness checks that
+/// patching twice is a no-op, as RFC 7386 requires. Panics when a law fails.
+///
+/// ```
+/// jd_fuzz::fuzz_patch_laws(&[9; 64]);
+/// ```
+pub fn fuzz_patch_laws(data: &[u8]) {
+    let mut unstructured = Unstructured::new(data);
+    let (Some(lhs), Some(rhs)) = (random_node(&mut unstructured), random_node(&mut unstructured))
+    else {
+        return;
+    };
+    let merge = unstructured.ratio(1, 4).unwrap_or(false);
+    let diff = lhs.diff(&rhs, &DiffOptions::default().with_merge(merge));
+    let context = || format!("{lhs:?}\n{rhs:?}\n{}", diff.render(&RenderConfig::default()));
+
+    let patched = lhs
+        .apply_patch(&diff)
+        .unwrap_or_else(|err| panic!("diff does not apply: {err}\n{}", context()));
+    if merge {
+        assert_eq!(patched.prune_empty(), rhs.prune_empty(), "merge diff missed:\n{}", context());
+        let twice = patched.apply_patch(&diff).expect("merge patches always apply");
+        assert_eq!(twice, patched, "merge diff is not idempotent:\n{}", context());
+        return;
+    }
+    assert_eq!(patched, rhs, "diff missed the target:\n{}", context());
+    if let Ok(twice) = patched.apply_patch(&diff) {
+        assert_eq!(twice, patched, "diff applied twice changed the target:\n{}", context());
+    }
+
+    let reversed = diff.reverse().expect("strict diffs reverse");
+    assert_eq!(reversed.reverse().ok().as_ref(), Some(&diff), "reverse is not an involution");
+    let round_trip = Diff::from_elements(diff.clone().into_iter().chain(reversed).collect());
+    assert!(round_trip.minimize().is_empty(), "diff and its reverse do not cancel:\n{}", context());
+}
+
+/// Writes nodes as YAML and asserts that parsing the output gives them back.
+///
+/// The input is tried both as YAML text, covering whatever the parser
+/// accepts (tags, special floats, non-string keys are rejected up front),
+/// and as a seed for a generated node, covering strings that YAML would
+/// otherwise read as other types. Panics on a mismatch.
+///
+/// ```
+/// jd_fuzz::fuzz_yaml_roundtrip(b"{x: .5, y: '0x10', z: [null, 'true']}");
+/// ```
+pub fn fuzz_yaml_roundtrip(data: &[u8]) {
+    let parsed = std::str::from_utf8(data).ok().and_then(|text| Node::from_yaml_str(text).ok());
+    let generated = random_node(&mut Unstructured::new(data));
+    for node in parsed.into_iter().chain(generated) {
+        let Some(yaml) = node.to_yaml_string() else {
+            continue;
+        };
+        let reparsed = Node::from_yaml_str(&yaml)
+            .unwrap_or_else(|err| panic!("written YAML does not parse: {err}\n{yaml}"));
+        assert_eq!(reparsed, node, "YAML round trip changed the node:\n{yaml}");
+    }
+}
+
+/// Diffs random documents under [`DiffOptions`] derived from the input.
+///
+/// The options combine an array mode, set keys, one numeric tolerance,
+/// merge and prune-empty flags, and a path option. The harness checks that
+/// documents equal under the options produce an empty diff and, unless a
+/// tolerance or `DIFF_OFF` makes equality inexact, that they hash alike and
+/// that a computed diff patches the left document into one equal to the
+/// right. Array modes
+/// without a diff engine yet are reported by [`Jd::diff`] and only exercise
+/// equality and hashing.
+///
+/// ```
+/// jd_fuzz::fuzz_diff_with_options(&[5; 64]);
+/// ```
+pub fn fuzz_diff_with_options(data: &[u8]) {
+    let mut unstructured = Unstructured::new(data);
+    let Ok(options) = random_options(&mut unstructured) else {
+        return;
+    };
+    let (Some(lhs), Some(rhs)) = (random_node(&mut unstructured), random_node(&mut unstructured))
+    else {
+        return;
+    };
+    let equal = lhs.eq_with_options(&rhs, &options);
+    // Tolerances and ignored values make unequal values count as equal. Such
+    // values hash apart, and list context copied from the right document may
+    // not match the left one exactly, as in Go `jd`.
+    let inexact = options.precision() > 0.0
+        || options.relative_precision() > 0.0
+        || options.max_ulps() > 0
+        || options
+            .path_options()
+            .iter()
+            .flat_map(PathOption::settings)
+            .any(|setting| matches!(setting, PathSetting::Precision(_) | PathSetting::DiffOff));
+    if equal && !inexact {
+        assert_eq!(lhs.hash_code(&options), rhs.hash_code(&options), "equal nodes hash apart");
+    }
+    let Ok(diff) = Jd::with_options(options.clone()).diff(&lhs, &rhs) else {
+        return;
+    };
+    if equal {
+        assert!(diff.is_empty(), "equal nodes produced a diff");
+    }
+    let _ = diff.render(&RenderConfig::default());
+    let _ = diff.render_patch();
+    if inexact {
+        let _ = lhs.apply_patch(&diff);
+        return;
+    }
+    let context =
+        || format!("{lhs:?}\n{rhs:?}\n{options:?}\n{}", diff.render(&RenderConfig::default()));
+    let patched = lhs
+        .apply_patch(&diff)
+        .unwrap_or_else(|err| panic!("computed diff does not apply: {err}\n{}", context()));
+    assert!(patched.eq_with_options(&rhs, &options), "patched node differs:\n{}", context());
+}
+
+fn random_options(unstructured: &mut Unstructured<'_>) -> Result<DiffOptions, arbitrary::Error> {
+    // Combinations the options reject are skipped rather than failing.
+    let keep = |options: DiffOptions, update: &dyn Fn(DiffOptions) -> Result<DiffOptions, _>| {
+        update(options.clone()).unwrap_or(options)
+    };
+    let mut options = DiffOptions::default();
+    let mode = *unstructured.choose(&[ArrayMode::List, ArrayMode::Set, ArrayMode::MultiSet])?;
+    options = keep(options, &|options| options.with_array_mode(mode));
+    if unstructured.ratio(1, 4)? {
+        let key = random_key(unstructured)?;
+        options = keep(options, &|options| options.with_set_keys([key.as_str()]));
+    }
+    let tolerance = f64::from(unstructured.int_in_range::<u8>(0..=20)?) / 10.0;
+    options = match unstructured.int_in_range::<u8>(0..=3)? {
+        0 => keep(options, &|options| options.with_precision(tolerance)),
+        1 => keep(options, &|options| options.with_relative_precision(tolerance / 10.0)),
+        2 => {
+            let ulps = unstructured.int_in_range::<u64>(1..=8)?;
+            keep(options, &|options| options.with_max_ulps(ulps))
+        }
+        _ => options,
+    };
+    options = options.with_merge(unstructured.ratio(1, 8)?);
+    options = options.with_prune_empty(unstructured.ratio(1, 8)?);
+    if unstructured.ratio(1, 4)? {
+        let at = match unstructured.int_in_range::<u8>(0..=2)? {
+            0 => PathMatcher::key(random_key(unstructured)?),
+            1 => PathMatcher::AnyIndex,
+            _ => PathMatcher::AnyKey,
+        };
+        let setting = match unstructured.int_in_range::<u8>(0..=3)? {
+            0 => PathSetting::ArrayMode(*unstructured.choose(&[
+                ArrayMode::List,
+                ArrayMode::Set,
+                ArrayMode::MultiSet,
+            ])?),
+            1 => PathSetting::Precision(tolerance),
+            2 => PathSetting::DiffOff,
+            _ => PathSetting::DiffOn,
+        };
+        let option = PathOption::new([at]).with_setting(setting);
+        options = keep(options, &|options| options.with_path_option(option.clone()));
+    }
+    Ok(options)
+}
+
+/// Applies generated diffs to random documents to exercise patch errors.
+///
+/// Unlike [`fuzz_patch`], which rarely gets past deserializing raw bytes as a
+/// diff, this builds syntactically valid but adversarial diffs: random
+/// paths mixing keys, indices, and set markers, context that may not match,
+/// void values, and merge or set-key metadata. Each diff is validated,
+/// reversed, and applied, and every outcome is accepted except a panic.
+///
+/// ```
+/// jd_fuzz::fuzz_patch_structured(&[7; 64]);
+/// ```
+pub fn fuzz_patch_structured(data: &[u8]) {
+    let mut unstructured = Unstructured::new(data);
+    let Some(base) = random_node(&mut unstructured) else {
+        return;
+    };
+    let Ok(diff) = random_diff(&mut unstructured) else {
+        return;
+    };
+    let _ = diff.validate();
+    if let Ok(reversed) = diff.reverse() {
+        let _ = base.apply_patch(&reversed);
+    }
+    let _ = base.apply_patch(&diff);
+}
+
+/// Renders generated diffs and asserts that parsing the output gives the
+/// diff back, for the native format and for JSON Patch.
+///
+/// Elements that fail validation are dropped first, since the native format
+/// has no spelling for them. Native output must parse to elements with the
+/// same paths, values, and merge semantics, and render identically again.
+/// JSON Patch output must parse and render to the same patch. Unlike the
+/// other harnesses this one panics on a mismatch, which is what it checks.
+///
+/// ```
+/// jd_fuzz::fuzz_render_roundtrip(&[3; 64]);
+/// ```
+pub fn fuzz_render_roundtrip(data: &[u8]) {
+    let mut unstructured = Unstructured::new(data);
+    let Ok(diff) = random_diff(&mut unstructured) else {
+        return;
+    };
+    // Only the merge flag has a native spelling, and the builder drops the
+    // elements the native format cannot express.
+    let mut builder = DiffBuilder::new();
+    for mut element in diff {
+        element.metadata =
+            element.metadata.filter(|metadata| metadata.merge).map(|_| DiffMetadata::merge());
+        if let Ok(extended) = builder.clone().with_element(element) {
+            builder = extended;
+        }
+    }
+    let diff = builder.build();
+
+    let config = RenderConfig::default();
+    let native = diff.render(&config);
+    let parsed = Diff::from_native_str(&native)
+        .unwrap_or_else(|err| panic!("rendered diff does not parse: {err}\n{native}"));
+    assert_eq!(parsed.len(), diff.len(), "hunk count changed:\n{native}");
+    let merges = |diff: &Diff| -> Vec<bool> {
+        diff.effective_metadata().iter().map(|metadata| metadata.merge).collect()
+    };
+    assert_eq!(merges(&parsed), merges(&diff), "merge semantics changed:\n{native}");
+    for (parsed, original) in parsed.iter().zip(diff.iter()) {
+        assert_eq!(
+            (&parsed.path, &parsed.before, &parsed.remove, &parsed.add, &parsed.after),
+            (&original.path, &original.before, &original.remove, &original.add, &original.after),
+            "hunk changed:\n{native}"
+        );
+    }
+    assert_eq!(parsed.render(&config), native);
+
+    if let Ok(patch) = diff.render_patch() {
+        let parsed = Diff::from_patch_str(&patch)
+            .unwrap_or_else(|err| panic!("rendered JSON Patch does not parse: {err}\n{patch}"));
+        assert_eq!(parsed.render_patch().ok().as_deref(), Some(patch.as_str()));
+    }
+}
+
+fn random_diff(unstructured: &mut Unstructured<'_>) -> Result<Diff, arbitrary::Error> {
+    let len = unstructured.int_in_range::<u8>(0..=MAX_DIFF_ELEMENTS)?;
+    let mut elements = Vec::with_capacity(usize::from(len));
+    for _ in 0..len {
+        let path = random_path(unstructured)?;
+        // Context mostly accompanies index paths, as in real list hunks.
+        let list = matches!(path.last(), Some(PathSegment::Index(_)));
+        let context = |unstructured: &mut Unstructured<'_>| {
+            if list || unstructured.ratio(1, 8)? {
+                random_values(unstructured, true)
+            } else {
+                Ok(Vec::new())
+            }
+        };
+        let mut element = DiffElement::new()
+            .with_path(path)
+            .with_before(context(unstructured)?)
+            .with_remove(random_values(unstructured, false)?)
+            .with_add(random_values(unstructured, false)?)
+            .with_after(context(unstructured)?);
+        match unstructured.int_in_range::<u8>(0..=3)? {
+            0 => element = element.with_metadata(DiffMetadata::merge()),
+            1 => {
+                let keys = [random_key(unstructured)?];
+                element = element.with_metadata(DiffMetadata::default().with_set_keys(keys));
+            }
+            _ => {}
+        }
+        elements.push(element);
+    }
+    Ok(Diff::from_elements(elements))
+}
+
+fn random_path(unstructured: &mut Unstructured<'_>) -> Result<Vec<PathSegment>, arbitrary::Error> {
+    let len = unstructured.int_in_range::<u8>(0..=MAX_PATH_LEN)?;
+    let mut path = Vec::with_capacity(usize::from(len));
+    for _ in 0..len {
+        let segment = match unstructured.int_in_range::<u8>(0..=7)? {
+            0 | 1 => PathSegment::key(random_key(unstructured)?),
+            2 | 3 => PathSegment::index(unstructured.int_in_range::<i64>(-2..=4)?),
+            4 => PathSegment::Set,
+            5 => PathSegment::MultiSet,
+            6 => PathSegment::SetKeys(random_set_keys(unstructured)?),
+            _ => PathSegment::MultiSetKeys(random_set_keys(unstructured)?),
+        };
+        path.push(segment);
+    }
+    Ok(path)
+}
+
+fn random_set_keys(
+    unstructured: &mut Unstructured<'_>,
+) -> Result<BTreeMap<String, Node>, arbitrary::Error> {
+    // Empty set keys are spelled `{}`, the same as a plain set marker.
+    let len = unstructured.int_in_range::<u8>(1..=2)?;
+    let mut keys = BTreeMap::new();
+    for _ in 0..len {
+        let value = Node::from_json_value(json_leaf(unstructured)?)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        keys.insert(random_key(unstructured)?, value);
+    }
+    Ok(keys)
+}
+
+/// Up to two small values, occasionally void. Void is legitimate in context
+/// (it marks the ends of a list) and adversarial elsewhere.
+fn random_values(
+    unstructured: &mut Unstructured<'_>,
+    context: bool,
+) -> Result<Vec<Node>, arbitrary::Error> {
+    let max = if context { 1 } else { 2 };
+    let len = unstructured.int_in_range::<u8>(0..=max)?;
+    let mut values = Vec::with_capacity(usize::from(len));
+    for _ in 0..len {
+        if unstructured.ratio(1, 8)? {
+            values.push(Node::Void);
+        } else {
+            let value = json_value_from_unstructured(unstructured, MAX_DEPTH - 2)?;
+            values
+                .push(Node::from_json_value(value).map_err(|_| arbitrary::Error::IncorrectFormat)?);
+        }
+    }
+    Ok(values)
+}
+
+fn random_key(unstructured: &mut Unstructured<'_>) -> Result<String, arbitrary::Error> {
+    if unstructured.ratio(3, 4)? {
+        Ok((*unstructured.choose(&COMMON_KEYS)?).to_string())
+    } else {
+        random_string(unstructured)
+    }
+}
+
 fn random_node(unstructured: &mut Unstructured<'_>) -> Option<Node> {
     let value = json_value_from_unstructured(unstructured, 0).ok()?;
     Node::from_json_value(value).ok()
@@ -134,7 +521,7 @@ fn json_value_from_unstructured(
             let len = usize::from(unstructured.int_in_range::<u8>(0..=MAX_OBJECT_LEN)?);
             let mut map = JsonMap::new();
             for _ in 0..len {
-                let key = random_string(unstructured)?;
+                let key = random_key(unstructured)?;
                 let value = json_value_from_unstructured(unstructured, depth + 1)?;
                 map.insert(key, value);
             }
@@ -193,4 +580,53 @@ mod tests {
     fn patch_harness_runs() {
         fuzz_patch(b"patch");
     }
+
+    /// Runs `harness` on a deterministic stream of pseudo-random inputs.
+    fn sweep(harness: fn(&[u8])) {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        for _ in 0..2_000 {
+            let data: Vec<u8> = (0..256)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state.to_le_bytes()[0]
+                })
+                .collect();
+            harness(&data);
+        }
+    }
+
+    #[test]
+    fn structured_patch_harness_survives_generated_diffs() {
+        sweep(fuzz_patch_structured);
+    }
+
+    #[test]
+    fn generated_diffs_round_trip_through_renderers() {
+        sweep(fuzz_render_roundtrip);
+    }
+
+    #[test]
+    fn diffs_obey_patch_laws() {
+        sweep(fuzz_patch_laws);
+    }
+
+    #[test]
+    fn yaml_output_parses_back() {
+        sweep(fuzz_yaml_roundtrip);
+        for text in [
+            "[yes, 'yes', 'no', '~', '', 'null', 'true', '1e3', '0x10', '0o7', '+1', '.inf', '-.nan']",
+            "{'1': -0.0, '': 18446744073709551615, 'y': 1e300, ' a ': \"line\\nbreak\"}",
+            "[0.1, 1.5e-7, -9007199254740993, '- item', '# c', ': x', '\\t', \"'\"]",
+        ] {
+            assert!(Node::from_yaml_str(text).is_ok(), "{text}");
+            fuzz_yaml_roundtrip(text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn diffs_under_generated_options_patch_to_the_target() {
+        sweep(fuzz_diff_with_options);
+    }
 }
@@ -0,0 +1,30 @@
+//! Writes `cargo fuzz` seed corpora generated from the repository fixtures.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use jd_fuzz::seed::write_seed_corpus;
+
+/// Converts fixture document pairs into seed files for every fuzz target.
+#[derive(Debug, Parser)]
+#[command(name = "jd-fuzz-seed", version)]
+struct Cli {
+    /// Directory receiving one subdirectory of seeds per fuzz target.
+    #[arg(long, value_name = "DIR", default_value = "fuzz/corpus")]
+    out: PathBuf,
+    /// Fixture directories to search for document pairs.
+    #[arg(
+        value_name = "FIXTURES",
+        default_values = ["crates/jd-core/tests/fixtures", "docs/parity/upstream"]
+    )]
+    fixtures: Vec<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let written = write_seed_corpus(&cli.fixtures, &cli.out)
+        .with_context(|| format!("failed to write seeds to {}", cli.out.display()))?;
+    eprintln!("Wrote {written} seed file(s) to {}", cli.out.display());
+    Ok(())
+}
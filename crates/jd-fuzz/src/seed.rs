@@ -0,0 +1,451 @@
+//! Seed corpora generated from the repository's fixtures.
+//!
+//! Most harnesses decode their input through [`Unstructured`](arbitrary::Unstructured)
+//! rather than parsing it, so a fixture document handed over as text turns
+//! into an unrelated random node. The encoders here produce the bytes the
+//! generators decode back into a given node or diff, within the generators'
+//! limits: containers keep their first six entries, nesting stops at four
+//! levels, strings keep their first twelve characters with anything outside
+//! printable ASCII replaced by `?`, and numbers that are not a ratio of an
+//! `i32` and a denominator up to 1024 are rounded to the nearest one.
+//!
+//! [`write_seed_corpus`] turns every pair of documents under a set of fixture
+//! directories into seed files laid out the way `cargo fuzz` expects, so
+//! fuzzing starts from realistic documents instead of random bytes.
+//!
+//! ```
+//! use jd_core::Node;
+//!
+//! let node = Node::from_json_str(r#"{"id":1,"tags":["a","b"]}"#).unwrap();
+//! let bytes = jd_fuzz::seed::encode_node(&node);
+//! assert!(bytes.len() < 64);
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use jd_core::{Diff, DiffElement, DiffOptions, Node, PathSegment};
+use serde_json::Value as JsonValue;
+
+use crate::{
+    COMMON_KEYS, MAX_ARRAY_LEN, MAX_DEPTH, MAX_DIFF_ELEMENTS, MAX_OBJECT_LEN, MAX_PATH_LEN,
+    MAX_STRING_LEN,
+};
+
+/// Bytes that `random_options` decodes as [`DiffOptions::default`]: list
+/// mode, no set keys, no tolerance, no merge, no pruning, no path option.
+const DEFAULT_OPTIONS: [u8; 7] = [0, 3, 0, 3, 7, 7, 3];
+/// A `ratio(1, 4)` draw decoding as `true` and as `false`.
+const QUARTER: (u8, u8) = (0, 3);
+/// A `ratio(1, 8)` draw decoding as `true` and as `false`.
+const EIGHTH: (u8, u8) = (0, 7);
+
+/// Two documents found under a fixture directory, as text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixturePair {
+    /// Name derived from the fixture's path, used for the seed files.
+    pub name: String,
+    /// The left-hand document.
+    pub lhs: String,
+    /// The right-hand document.
+    pub rhs: String,
+}
+
+impl FixturePair {
+    /// Returns the seed inputs for each `cargo fuzz` target, keyed by target
+    /// name, or `None` when either document fails to parse.
+    ///
+    /// Text harnesses get the documents verbatim. Structured harnesses get
+    /// the documents and their default and merge diffs encoded for the
+    /// generators, and `patch_apply` also gets the diff serialized as JSON.
+    ///
+    /// ```
+    /// # use jd_fuzz::seed::FixturePair;
+    /// let pair = FixturePair { name: "list".into(), lhs: "[1,2]".into(), rhs: "[1,2,3]".into() };
+    /// let seeds = pair.seeds().unwrap();
+    /// assert!(seeds.iter().any(|(target, seed)| *target == "canonicalize" && seed == b"[1,2]"));
+    /// ```
+    #[must_use]
+    pub fn seeds(&self) -> Option<Vec<(&'static str, Vec<u8>)>> {
+        let parse = |text: &str| Node::from_json_str(text).or_else(|_| Node::from_yaml_str(text));
+        let (lhs, rhs) = (parse(&self.lhs).ok()?, parse(&self.rhs).ok()?);
+        let diff = lhs.diff(&rhs, &DiffOptions::default());
+        let merge = lhs.diff(&rhs, &DiffOptions::default().with_merge(true));
+        let documents = [encode_node(&lhs), encode_node(&rhs)].concat();
+        let mut seeds = Vec::new();
+        for text in [&self.lhs, &self.rhs] {
+            seeds.push(("canonicalize", text.as_bytes().to_vec()));
+            seeds.push(("yaml_roundtrip", text.as_bytes().to_vec()));
+        }
+        seeds.push(("diff", documents.clone()));
+        seeds.push(("diff_options", [&DEFAULT_OPTIONS[..], &documents].concat()));
+        seeds.push(("patch_apply", documents.clone()));
+        if let Ok(json) = serde_json::to_vec(&diff) {
+            seeds.push(("patch_apply", json));
+        }
+        seeds.push(("patch_laws", [&documents[..], &[QUARTER.1]].concat()));
+        seeds.push(("patch_laws", [&documents[..], &[QUARTER.0]].concat()));
+        for diff in [&diff, &merge] {
+            seeds.push(("patch_structured", [encode_node(&lhs), encode_diff(diff)].concat()));
+            seeds.push(("render_roundtrip", encode_diff(diff)));
+        }
+        Some(seeds)
+    }
+}
+
+/// Collects the document pairs under `root`, in path order.
+///
+/// A pair is a JSON file holding `lhs` and `rhs` strings, like the golden
+/// fixtures, or a directory holding `before` and `after` documents (`.json`,
+/// `.yaml`, or `.yml`) or `original.json` and `patched.json`, like the
+/// upstream parity dataset.
+///
+/// ```
+/// # use jd_fuzz::seed::find_fixture_pairs;
+/// let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../jd-core/tests/fixtures");
+/// let pairs = find_fixture_pairs(std::path::Path::new(root)).unwrap();
+/// assert!(pairs.iter().any(|pair| pair.name == "diff-list-append"));
+/// ```
+pub fn find_fixture_pairs(root: &Path) -> io::Result<Vec<FixturePair>> {
+    let mut pairs = Vec::new();
+    collect_pairs(root, root, &mut pairs)?;
+    Ok(pairs)
+}
+
+fn collect_pairs(root: &Path, dir: &Path, pairs: &mut Vec<FixturePair>) -> io::Result<()> {
+    // Directory names such as `jd-v2.2.2` keep their dots.
+    let name = |path: &Path| {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative =
+            if path.is_file() { relative.with_extension("") } else { relative.to_path_buf() };
+        let parts: Vec<_> = relative.iter().map(|part| part.to_string_lossy()).collect();
+        if parts.is_empty() {
+            root.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+        } else {
+            parts.join("-")
+        }
+    };
+    let find = |stem: &str| {
+        ["json", "yaml", "yml"]
+            .iter()
+            .map(|ext| dir.join(format!("{stem}.{ext}")))
+            .find(|p| p.is_file())
+    };
+    for (lhs, rhs) in [("before", "after"), ("original", "patched")] {
+        if let (Some(lhs), Some(rhs)) = (find(lhs), find(rhs)) {
+            let (lhs, rhs) = (fs::read_to_string(lhs)?, fs::read_to_string(rhs)?);
+            pairs.push(FixturePair { name: name(dir), lhs, rhs });
+        }
+    }
+    let mut entries =
+        fs::read_dir(dir)?.map(|entry| Ok(entry?.path())).collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_pairs(root, &path, pairs)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            let Ok(JsonValue::Object(fixture)) = serde_json::from_str(&fs::read_to_string(&path)?)
+            else {
+                continue;
+            };
+            if let (Some(JsonValue::String(lhs)), Some(JsonValue::String(rhs))) =
+                (fixture.get("lhs"), fixture.get("rhs"))
+            {
+                pairs.push(FixturePair { name: name(&path), lhs: lhs.clone(), rhs: rhs.clone() });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes seed files for every fixture pair under `roots` into
+/// `out/<target>/`, the layout `cargo fuzz` reads its corpora from, and
+/// returns how many files were written. Pairs that fail to parse are
+/// skipped.
+///
+/// ```
+/// # use std::path::PathBuf;
+/// let root = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../jd-core/tests/fixtures"));
+/// let out = tempfile::tempdir().unwrap();
+/// let written = jd_fuzz::seed::write_seed_corpus(&[root], out.path()).unwrap();
+/// assert!(written > 0);
+/// assert!(out.path().join("patch_laws/diff-list-append-0").is_file());
+/// ```
+pub fn write_seed_corpus(roots: &[PathBuf], out: &Path) -> io::Result<usize> {
+    let mut written = 0;
+    for root in roots {
+        for pair in find_fixture_pairs(root)? {
+            let Some(seeds) = pair.seeds() else {
+                continue;
+            };
+            let mut counts = std::collections::BTreeMap::new();
+            for (target, seed) in seeds {
+                let count = counts.entry(target).or_insert(0);
+                let dir = out.join(target);
+                fs::create_dir_all(&dir)?;
+                fs::write(dir.join(format!("{}-{count}", pair.name)), seed)?;
+                *count += 1;
+                written += 1;
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Encodes `node` as the bytes the structured harnesses decode back into it.
+///
+/// Nodes beyond the generators' limits come back truncated; see the
+/// [module documentation](self).
+///
+/// ```
+/// # use jd_core::Node;
+/// let bytes = jd_fuzz::seed::encode_node(&Node::from_json_str("[true]").unwrap());
+/// assert_eq!(bytes, [4, 1, 1, 1]);
+/// ```
+#[must_use]
+pub fn encode_node(node: &Node) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    encode_value(node, 0, &mut bytes);
+    bytes
+}
+
+/// Encodes `diff` as the bytes the structured patch and render harnesses
+/// decode back into it.
+///
+/// Besides the node limits, diffs keep their first four elements, paths
+/// their first four segments, indices are clamped to `-2..=4`, and set-key
+/// metadata keeps its first key.
+///
+/// ```
+/// # use jd_core::{DiffOptions, Node};
+/// let lhs = Node::from_json_str(r#"{"a":1}"#).unwrap();
+/// let diff = lhs.diff(&Node::from_json_str(r#"{"a":2}"#).unwrap(), &DiffOptions::default());
+/// assert_eq!(jd_fuzz::seed::encode_diff(&diff)[..3], [1, 1, 0]);
+/// ```
+#[must_use]
+pub fn encode_diff(diff: &Diff) -> Vec<u8> {
+    let elements: Vec<&DiffElement> = diff.iter().take(usize::from(MAX_DIFF_ELEMENTS)).collect();
+    let mut bytes = vec![count(elements.len(), MAX_DIFF_ELEMENTS)];
+    for element in elements {
+        let segments = &element.path.segments()[..element.path.len().min(MAX_PATH_LEN.into())];
+        bytes.push(count(segments.len(), MAX_PATH_LEN));
+        for segment in segments {
+            match segment {
+                PathSegment::Key(key) => {
+                    bytes.push(0);
+                    encode_key(key, &mut bytes);
+                }
+                PathSegment::Index(index) => bytes.extend([2, ((*index).clamp(-2, 4) + 2) as u8]),
+                PathSegment::Set => bytes.push(4),
+                PathSegment::MultiSet => bytes.push(5),
+                PathSegment::SetKeys(keys) | PathSegment::MultiSetKeys(keys) if keys.is_empty() => {
+                    bytes.push(4);
+                }
+                PathSegment::SetKeys(keys) | PathSegment::MultiSetKeys(keys) => {
+                    bytes.push(if matches!(segment, PathSegment::SetKeys(_)) { 6 } else { 7 });
+                    let keys: Vec<_> = keys.iter().take(2).collect();
+                    bytes.push(count(keys.len() - 1, 1));
+                    for (key, value) in keys {
+                        encode_value(value, MAX_DEPTH, &mut bytes);
+                        encode_key(key, &mut bytes);
+                    }
+                }
+            }
+        }
+        let list = matches!(segments.last(), Some(PathSegment::Index(_)));
+        let context = |values: &[Node], bytes: &mut Vec<u8>| {
+            if !list {
+                if values.is_empty() {
+                    bytes.push(EIGHTH.1);
+                    return;
+                }
+                bytes.push(EIGHTH.0);
+            }
+            encode_values(values, 1, bytes);
+        };
+        context(&element.before, &mut bytes);
+        encode_values(&element.remove, 2, &mut bytes);
+        encode_values(&element.add, 2, &mut bytes);
+        context(&element.after, &mut bytes);
+        match &element.metadata {
+            Some(metadata) if metadata.merge => bytes.push(0),
+            Some(metadata) => match metadata.set_keys.as_deref() {
+                Some([key, ..]) => {
+                    bytes.push(1);
+                    encode_key(key, &mut bytes);
+                }
+                _ => bytes.push(2),
+            },
+            None => bytes.push(2),
+        }
+    }
+    bytes
+}
+
+fn encode_values(values: &[Node], max: u8, bytes: &mut Vec<u8>) {
+    let values = &values[..values.len().min(max.into())];
+    bytes.push(count(values.len(), max));
+    for value in values {
+        if matches!(value, Node::Void) {
+            bytes.push(EIGHTH.0);
+        } else {
+            bytes.push(EIGHTH.1);
+            encode_value(value, MAX_DEPTH - 2, bytes);
+        }
+    }
+}
+
+/// Mirrors `json_value_from_unstructured`, and `json_leaf` from
+/// `MAX_DEPTH` on, where containers collapse to null.
+fn encode_value(node: &Node, depth: usize, bytes: &mut Vec<u8>) {
+    match node {
+        Node::Void | Node::Null => bytes.push(0),
+        Node::Bool(value) => bytes.extend([1, u8::from(*value)]),
+        Node::Number(number) => {
+            bytes.push(2);
+            encode_number(number.get(), bytes);
+        }
+        Node::String(value) => {
+            bytes.push(3);
+            encode_string(value, bytes);
+        }
+        Node::Array(_) | Node::Object(_) if depth >= MAX_DEPTH => bytes.push(0),
+        Node::Array(items) => {
+            let items = &items[..items.len().min(MAX_ARRAY_LEN.into())];
+            bytes.extend([4, count(items.len(), MAX_ARRAY_LEN)]);
+            for item in items {
+                encode_value(item, depth + 1, bytes);
+            }
+        }
+        Node::Object(members) => {
+            let members: Vec<_> = members.iter().take(MAX_OBJECT_LEN.into()).collect();
+            bytes.extend([5, count(members.len(), MAX_OBJECT_LEN)]);
+            for (key, value) in members {
+                encode_key(key, bytes);
+                encode_value(value, depth + 1, bytes);
+            }
+        }
+    }
+}
+
+/// Mirrors `random_number`: an `i64`, or an `i32` numerator over a
+/// denominator in `1..=1024`.
+fn encode_number(value: f64, bytes: &mut Vec<u8>) {
+    const I64_BOUND: f64 = 9_223_372_036_854_775_808.0;
+    if value.fract() == 0.0 && (-I64_BOUND..I64_BOUND).contains(&value) {
+        bytes.push(1);
+        bytes.extend((value as i64).to_le_bytes());
+        return;
+    }
+    let numerator = |denominator: u16| {
+        (value * f64::from(denominator)).round().clamp(f64::from(i32::MIN), f64::from(i32::MAX))
+            as i32
+    };
+    let denominator = (1..=1024)
+        .find(|&denominator| f64::from(numerator(denominator)) / f64::from(denominator) == value)
+        .unwrap_or(1024);
+    bytes.push(0);
+    bytes.extend(numerator(denominator).to_le_bytes());
+    bytes.extend((denominator - 1).to_be_bytes());
+}
+
+/// Mirrors `random_key`: one of [`COMMON_KEYS`] or a random string.
+fn encode_key(key: &str, bytes: &mut Vec<u8>) {
+    match COMMON_KEYS.iter().position(|common| *common == key) {
+        Some(index) => bytes.extend([QUARTER.0, index as u8]),
+        None => {
+            bytes.push(QUARTER.1);
+            encode_string(key, bytes);
+        }
+    }
+}
+
+/// Mirrors `random_string`: up to twelve printable ASCII characters.
+fn encode_string(value: &str, bytes: &mut Vec<u8>) {
+    let chars: Vec<char> = value.chars().take(MAX_STRING_LEN.into()).collect();
+    bytes.push(count(chars.len(), MAX_STRING_LEN));
+    for c in chars {
+        let c = if (' '..='~').contains(&c) { c } else { '?' };
+        bytes.push(c as u8 - b' ');
+    }
+}
+
+/// A length already capped at `max`, as a byte.
+fn count(len: usize, max: u8) -> u8 {
+    u8::try_from(len).map_or(max, |len| len.min(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::Unstructured;
+
+    use super::*;
+
+    #[test]
+    fn encoded_nodes_decode_back() {
+        for (text, expected) in [
+            (
+                r#"{"a":[1,-2.5,0.1,"x"],"b":{"id":null,"long key":true},"c":-9007199254740993}"#,
+                None,
+            ),
+            // Nesting past the depth limit collapses to null.
+            (
+                r#"[[[[[1]]]],"",false,12345678901234]"#,
+                Some(r#"[[[[null]]],"",false,12345678901234]"#),
+            ),
+            ("3.141592653589793", Some("3.1416015625")),
+            ("\"tab\\there \u{e9} and more\"", Some("\"tab?here ? a\"")),
+        ] {
+            let node = Node::from_json_str(text).unwrap();
+            let decoded = crate::random_node(&mut Unstructured::new(&encode_node(&node)));
+            let expected = Node::from_json_str(expected.unwrap_or(text)).unwrap();
+            assert_eq!(decoded, Some(expected), "{text}");
+        }
+    }
+
+    #[test]
+    fn encoded_diffs_decode_back() {
+        let lhs = Node::from_json_str(r#"{"a":[1,2,3],"b":{"id":"x"},"gone":true}"#).unwrap();
+        let rhs = Node::from_json_str(r#"{"a":[1,4,3,5],"b":{"id":"y"}}"#).unwrap();
+        for options in [DiffOptions::default(), DiffOptions::default().with_merge(true)] {
+            let diff = lhs.diff(&rhs, &options);
+            let decoded = crate::random_diff(&mut Unstructured::new(&encode_diff(&diff)));
+            assert_eq!(decoded.ok(), Some(diff));
+        }
+    }
+
+    #[test]
+    fn every_fuzz_target_gets_seeds() {
+        let targets = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../fuzz/fuzz_targets");
+        let pair = FixturePair { name: "pair".into(), lhs: "a: 1".into(), rhs: "[1]".into() };
+        let seeds = pair.seeds().unwrap();
+        for entry in fs::read_dir(targets).unwrap() {
+            let path = entry.unwrap().path();
+            let target = path.file_stem().unwrap().to_string_lossy().into_owned();
+            assert!(seeds.iter().any(|(name, _)| *name == target), "no seeds for {target}");
+        }
+    }
+
+    #[test]
+    fn fixture_seeds_pass_their_harnesses() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        for fixtures in ["crates/jd-core/tests/fixtures", "docs/parity/upstream"] {
+            for pair in find_fixture_pairs(&root.join(fixtures)).unwrap() {
+                for (target, seed) in pair.seeds().unwrap() {
+                    let harness: fn(&[u8]) = match target {
+                        "canonicalize" => crate::fuzz_canonicalization,
+                        "yaml_roundtrip" => crate::fuzz_yaml_roundtrip,
+                        "diff" => crate::fuzz_diff,
+                        "diff_options" => crate::fuzz_diff_with_options,
+                        "patch_apply" => crate::fuzz_patch,
+                        "patch_laws" => crate::fuzz_patch_laws,
+                        "patch_structured" => crate::fuzz_patch_structured,
+                        _ => crate::fuzz_render_roundtrip,
+                    };
+                    harness(&seed);
+                }
+            }
+        }
+    }
+}
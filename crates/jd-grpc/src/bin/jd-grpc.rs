@@ -0,0 +1,47 @@
+//! Runs the `jd.v1.Jd` gRPC service.
+
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use jd_core::Limits;
+use jd_grpc::JdService;
+
+/// Serves jd's diff, patch, and translate operations over gRPC.
+#[derive(Debug, Parser)]
+#[command(name = "jd-grpc", version)]
+struct Cli {
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    addr: SocketAddr,
+    /// Refuse documents larger than N bytes, including streamed ones.
+    #[arg(long, value_name = "N")]
+    max_input_bytes: Option<usize>,
+    /// Refuse documents with more than N values.
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<usize>,
+    /// Refuse documents nested more than N levels deep.
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut limits = Limits::default();
+    if let Some(max) = cli.max_input_bytes {
+        limits = limits.with_max_input_bytes(max);
+    }
+    if let Some(max) = cli.max_nodes {
+        limits = limits.with_max_nodes(max);
+    }
+    if let Some(max) = cli.max_depth {
+        limits = limits.with_max_depth(max);
+    }
+    eprintln!("jd gRPC service listening on {}", cli.addr);
+    tonic::transport::Server::builder()
+        .add_service(JdService::new().with_limits(limits).into_server())
+        .serve(cli.addr)
+        .await
+        .with_context(|| format!("failed to serve on {}", cli.addr))
+}
@@ -0,0 +1,417 @@
+//! gRPC diff service for the Rust port of the `jd` tool.
+//!
+//! [`JdService`] implements the `jd.v1.Jd` service from
+//! `proto/jd/v1/jd.proto` with tonic, exposing the diff, patch, and
+//! translate operations of the CLI to service meshes. `DiffStream` and
+//! `PatchStream` move documents larger than one gRPC message as chunked
+//! streams.
+//!
+//! ```no_run
+//! use jd_grpc::JdService;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! tonic::transport::Server::builder()
+//!     .add_service(JdService::new().into_server())
+//!     .serve("127.0.0.1:50051".parse()?)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Documents are JSON or YAML text and options use the `jd -opts` format.
+//! Diffing runs on tokio's blocking pool, so large documents do not stall
+//! the server's executor.
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+// Handlers return tonic's `Status` by value, as its generated traits do.
+#![allow(clippy::result_large_err)]
+
+use jd_core::{
+    CanonicalizeError, Diff, DiffFormat, DiffOptions, Jd, JdError, Limits, Node, OptionsError,
+    PatchError, RenderConfig, RenderError,
+};
+use tokio_stream::StreamExt;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Types generated from `proto/jd/v1/jd.proto`.
+#[allow(missing_docs)]
+pub mod proto {
+    tonic::include_proto!("jd.v1");
+}
+
+use proto::jd_server::{Jd as JdRpc, JdServer};
+use proto::{
+    diff_upload, patch_upload, Chunk, DiffRequest, DiffResponse, DiffUpload, Format, PatchRequest,
+    PatchResponse, PatchUpload, TranslateRequest, TranslateResponse,
+};
+
+/// Size of the chunks `DiffStream` and `PatchStream` reply with.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The `jd.v1.Jd` service.
+#[derive(Clone, Debug)]
+pub struct JdService {
+    limits: Limits,
+    chunk_size: usize,
+}
+
+impl Default for JdService {
+    fn default() -> Self {
+        Self { limits: Limits::default(), chunk_size: DEFAULT_CHUNK_SIZE }
+    }
+}
+
+impl JdService {
+    /// Creates a service without resource limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuses documents and diffs that exceed `limits`, reporting
+    /// `RESOURCE_EXHAUSTED`. `max_input_bytes` also bounds each streamed
+    /// document.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the size of streamed reply chunks; zero is treated as one.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Wraps the service for `tonic::transport::Server::add_service`.
+    pub fn into_server(self) -> JdServer<Self> {
+        JdServer::new(self)
+    }
+
+    /// Parses a document as JSON, or as YAML when it is not JSON, reporting
+    /// the JSON error when both fail.
+    fn parse(&self, field: &str, input: &str) -> Result<Node, Status> {
+        let parsed =
+            Node::from_json_str(input).or_else(|err| Node::from_yaml_str(input).map_err(|_| err));
+        self.checked(field, input, parsed)
+    }
+
+    /// Applies the limits to a parsed document and its source text.
+    fn checked(
+        &self,
+        field: &str,
+        input: &str,
+        parsed: Result<Node, CanonicalizeError>,
+    ) -> Result<Node, Status> {
+        self.limits.check_input_len(input.len()).map_err(|err| limited(field, err))?;
+        let node = parsed.map_err(|err| invalid(field, err))?;
+        self.limits.check_node(&node).map_err(|err| limited(field, err))?;
+        Ok(node)
+    }
+
+    fn diff(&self, lhs: &str, rhs: &str, options: DiffOptions) -> Result<Diff, Status> {
+        let (lhs, rhs) = (self.parse("lhs", lhs)?, self.parse("rhs", rhs)?);
+        Jd::with_options(options).limits(self.limits).diff(&lhs, &rhs).map_err(diff_failed)
+    }
+
+    fn diff_all(&self, request: &DiffRequest) -> Result<DiffResponse, Status> {
+        let options = parse_options(&request.options)?;
+        let diff = self.diff(&request.lhs, &request.rhs, options.clone())?;
+        let merge_diff = if options.merge() {
+            diff.clone()
+        } else {
+            self.diff(&request.lhs, &request.rhs, options.with_merge(true))?
+        };
+        Ok(DiffResponse {
+            equal: diff.is_empty(),
+            jd: render(&diff, Format::Jd)?,
+            patch: render(&diff, Format::Patch)?,
+            merge: render(&merge_diff, Format::Merge)?,
+        })
+    }
+
+    fn diff_one(
+        &self,
+        lhs: &str,
+        rhs: &str,
+        options: &str,
+        format: Format,
+    ) -> Result<String, Status> {
+        let options = parse_options(options)?;
+        let (lhs, rhs) = (self.parse("lhs", lhs)?, self.parse("rhs", rhs)?);
+        Jd::with_options(options)
+            .limits(self.limits)
+            .render_as(&lhs, &rhs, diff_format(format))
+            .map_err(diff_failed)
+    }
+
+    fn patch(&self, diff: &str, format: Format, document: &str) -> Result<String, Status> {
+        let diff = read(diff, format)?;
+        let patched = self.parse("document", document)?.apply_patch(&diff).map_err(conflict)?;
+        Ok(patched.to_json_value().map(|value| value.to_string()).unwrap_or_default())
+    }
+
+    fn translate(&self, request: &TranslateRequest) -> Result<String, Status> {
+        let unsupported =
+            || Status::invalid_argument(format!("unsupported translation: {:?}", request.spec));
+        let (from, to) = request.spec.split_once('2').ok_or_else(unsupported)?;
+        let input = &request.input;
+        match (from, to) {
+            ("json", "yaml") => {
+                let node = self.checked("input", input, Node::from_json_str(input))?;
+                Ok(node.to_yaml_string().unwrap_or_default())
+            }
+            ("yaml", "json") => {
+                let node = self.checked("input", input, Node::from_yaml_str(input))?;
+                Ok(node.to_json_value().map(|value| value.to_string()).unwrap_or_default())
+            }
+            ("jd", "patch" | "merge") | ("patch" | "merge", "jd") => {
+                let from = format_named(from).ok_or_else(unsupported)?;
+                let to = format_named(to).ok_or_else(unsupported)?;
+                render(&read(input, from)?, to)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Splits `text` into reply chunks.
+    fn chunks(&self, text: String) -> Vec<Result<Chunk, Status>> {
+        text.into_bytes()
+            .chunks(self.chunk_size)
+            .map(|data| Ok(Chunk { data: data.to_vec() }))
+            .collect()
+    }
+
+    /// Appends a streamed chunk to `buffer`, enforcing `max_input_bytes`.
+    fn append(&self, field: &str, buffer: &mut Vec<u8>, chunk: Vec<u8>) -> Result<(), Status> {
+        buffer.extend(chunk);
+        self.limits.check_input_len(buffer.len()).map_err(|err| limited(field, err))
+    }
+}
+
+type ChunkStream = tokio_stream::Iter<std::vec::IntoIter<Result<Chunk, Status>>>;
+
+#[tonic::async_trait]
+impl JdRpc for JdService {
+    async fn diff(&self, request: Request<DiffRequest>) -> Result<Response<DiffResponse>, Status> {
+        let service = self.clone();
+        let request = request.into_inner();
+        blocking(move || service.diff_all(&request)).await.map(Response::new)
+    }
+
+    async fn patch(
+        &self,
+        request: Request<PatchRequest>,
+    ) -> Result<Response<PatchResponse>, Status> {
+        let service = self.clone();
+        let request = request.into_inner();
+        let format = request.format();
+        let document = blocking(move || service.patch(&request.diff, format, &request.document));
+        Ok(Response::new(PatchResponse { document: document.await? }))
+    }
+
+    async fn translate(
+        &self,
+        request: Request<TranslateRequest>,
+    ) -> Result<Response<TranslateResponse>, Status> {
+        let service = self.clone();
+        let request = request.into_inner();
+        let output = blocking(move || service.translate(&request)).await?;
+        Ok(Response::new(TranslateResponse { output }))
+    }
+
+    type DiffStreamStream = ChunkStream;
+
+    async fn diff_stream(
+        &self,
+        request: Request<Streaming<DiffUpload>>,
+    ) -> Result<Response<ChunkStream>, Status> {
+        let mut uploads = request.into_inner();
+        let header = match uploads.next().await.transpose()?.and_then(|upload| upload.part) {
+            Some(diff_upload::Part::Header(header)) => header,
+            _ => return Err(Status::invalid_argument("DiffStream must start with a header")),
+        };
+        let (mut lhs, mut rhs) = (Vec::new(), Vec::new());
+        while let Some(upload) = uploads.next().await.transpose()? {
+            match upload.part {
+                Some(diff_upload::Part::Lhs(chunk)) => self.append("lhs", &mut lhs, chunk)?,
+                Some(diff_upload::Part::Rhs(chunk)) => self.append("rhs", &mut rhs, chunk)?,
+                Some(diff_upload::Part::Header(_)) => {
+                    return Err(Status::invalid_argument("DiffStream takes one header"))
+                }
+                None => {}
+            }
+        }
+        let service = self.clone();
+        let format = header.format();
+        let rendered = blocking(move || {
+            let (lhs, rhs) = (utf8("lhs", lhs)?, utf8("rhs", rhs)?);
+            service.diff_one(&lhs, &rhs, &header.options, format)
+        })
+        .await?;
+        Ok(Response::new(tokio_stream::iter(self.chunks(rendered))))
+    }
+
+    type PatchStreamStream = ChunkStream;
+
+    async fn patch_stream(
+        &self,
+        request: Request<Streaming<PatchUpload>>,
+    ) -> Result<Response<ChunkStream>, Status> {
+        let mut uploads = request.into_inner();
+        let header = match uploads.next().await.transpose()?.and_then(|upload| upload.part) {
+            Some(patch_upload::Part::Header(header)) => header,
+            _ => return Err(Status::invalid_argument("PatchStream must start with a header")),
+        };
+        let (mut diff, mut document) = (Vec::new(), Vec::new());
+        while let Some(upload) = uploads.next().await.transpose()? {
+            match upload.part {
+                Some(patch_upload::Part::Diff(chunk)) => self.append("diff", &mut diff, chunk)?,
+                Some(patch_upload::Part::Document(chunk)) => {
+                    self.append("document", &mut document, chunk)?
+                }
+                Some(patch_upload::Part::Header(_)) => {
+                    return Err(Status::invalid_argument("PatchStream takes one header"))
+                }
+                None => {}
+            }
+        }
+        let service = self.clone();
+        let format = header.format();
+        let patched = blocking(move || {
+            service.patch(&utf8("diff", diff)?, format, &utf8("document", document)?)
+        })
+        .await?;
+        Ok(Response::new(tokio_stream::iter(self.chunks(patched))))
+    }
+}
+
+/// Runs CPU-bound work on tokio's blocking pool.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, Status> + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| Status::internal(format!("jd task failed: {err}")))?
+}
+
+fn parse_options(options: &str) -> Result<DiffOptions, Status> {
+    if options.trim().is_empty() {
+        return Ok(DiffOptions::default());
+    }
+    DiffOptions::from_json_opts(options).map_err(|err| match err {
+        OptionsError::UnsupportedArrayMode(_) => Status::unimplemented(err.to_string()),
+        err => invalid("options", err),
+    })
+}
+
+fn format_named(name: &str) -> Option<Format> {
+    match name {
+        "jd" => Some(Format::Jd),
+        "patch" => Some(Format::Patch),
+        "merge" => Some(Format::Merge),
+        _ => None,
+    }
+}
+
+fn diff_format(format: Format) -> DiffFormat {
+    match format {
+        Format::Jd => DiffFormat::Jd,
+        Format::Patch => DiffFormat::Patch,
+        Format::Merge => DiffFormat::Merge,
+    }
+}
+
+fn read(input: &str, format: Format) -> Result<Diff, Status> {
+    diff_format(format).read(input).map_err(|err| invalid("diff", err))
+}
+
+fn render(diff: &Diff, format: Format) -> Result<String, Status> {
+    diff_format(format).render(diff, &RenderConfig::default()).map_err(rejected)
+}
+
+fn rejected(err: RenderError) -> Status {
+    Status::invalid_argument(err.to_string())
+}
+
+fn utf8(field: &str, bytes: Vec<u8>) -> Result<String, Status> {
+    String::from_utf8(bytes).map_err(|_| Status::invalid_argument(format!("{field} is not UTF-8")))
+}
+
+fn invalid(field: &str, err: impl std::fmt::Display) -> Status {
+    Status::invalid_argument(format!("invalid {field}: {err}"))
+}
+
+fn limited(field: &str, err: impl std::fmt::Display) -> Status {
+    Status::resource_exhausted(format!("refusing {field}: {err}"))
+}
+
+fn conflict(err: PatchError) -> Status {
+    Status::failed_precondition(err.to_string())
+}
+
+fn diff_failed(err: JdError) -> Status {
+    match err {
+        JdError::Canonicalize(err) => invalid("document", err),
+        JdError::Options(err) => invalid("options", err),
+        JdError::Limit(err) => Status::resource_exhausted(err.to_string()),
+        err @ JdError::UnsupportedArrayMode(_) => Status::unimplemented(err.to_string()),
+        JdError::Render(err) => rejected(err),
+        err => Status::internal(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_render_in_every_format() {
+        let request = DiffRequest {
+            lhs: r#"{"a":1,"b":[1,2]}"#.into(),
+            rhs: "a: 2\nb: [1, 2]\n".into(),
+            options: String::new(),
+        };
+        let response = JdService::new().diff_all(&request).unwrap();
+        assert!(!response.equal);
+        assert_eq!(response.jd, "@ [\"a\"]\n- 1\n+ 2\n");
+        assert_eq!(
+            response.patch,
+            r#"[{"op":"test","path":"/a","value":1},{"op":"remove","path":"/a","value":1},{"op":"add","path":"/a","value":2}]"#
+        );
+        assert_eq!(response.merge, r#"{"a":2}"#);
+    }
+
+    #[test]
+    fn failures_map_to_status_codes() {
+        let service = JdService::new();
+        let request = |lhs: &str, options: &str| DiffRequest {
+            lhs: lhs.into(),
+            rhs: "{}".into(),
+            options: options.into(),
+        };
+        let code = |request| service.diff_all(&request).unwrap_err().code();
+        assert_eq!(code(request("{", "")), tonic::Code::InvalidArgument);
+        assert_eq!(code(request("{}", r#"["FAST"]"#)), tonic::Code::InvalidArgument);
+        assert_eq!(code(request("[]", r#"["SET"]"#)), tonic::Code::Unimplemented);
+        let limited = JdService::new().with_limits(Limits::default().with_max_nodes(1));
+        assert_eq!(
+            limited.diff_all(&request("[1,2]", "")).unwrap_err().code(),
+            tonic::Code::ResourceExhausted
+        );
+        let stale = service.patch("@ [\"a\"]\n- 3\n+ 2\n", Format::Jd, r#"{"a":1}"#);
+        assert_eq!(stale.unwrap_err().code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn translations_follow_the_jd_table() {
+        let service = JdService::new();
+        let translate = |spec: &str, input: &str| {
+            service.translate(&TranslateRequest { spec: spec.into(), input: input.into() })
+        };
+        assert_eq!(translate("json2yaml", r#"{"a":[1]}"#).unwrap(), "a:\n- 1\n");
+        assert_eq!(
+            translate("merge2jd", r#"{"a":2}"#).unwrap(),
+            "^ {\"Merge\":true}\n@ [\"a\"]\n+ 2\n"
+        );
+        assert!(translate("patch2merge", "[]").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! Round trips through a real gRPC server and client.
+
+use jd_grpc::proto::jd_client::JdClient;
+use jd_grpc::proto::{
+    diff_upload, patch_upload, DiffRequest, DiffStreamHeader, DiffUpload, Format, PatchRequest,
+    PatchStreamHeader, PatchUpload, TranslateRequest,
+};
+use jd_grpc::JdService;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Server};
+
+async fn connect(service: JdService) -> JdClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    JdClient::connect(format!("http://{addr}")).await.unwrap()
+}
+
+async fn collect(stream: tonic::Streaming<jd_grpc::proto::Chunk>) -> Vec<Vec<u8>> {
+    stream.map(|chunk| chunk.unwrap().data).collect().await
+}
+
+#[tokio::test]
+async fn unary_calls_diff_patch_and_translate() {
+    let mut client = connect(JdService::new()).await;
+    let diff = client
+        .diff(DiffRequest {
+            lhs: r#"{"a":1}"#.into(),
+            rhs: r#"{"a":2}"#.into(),
+            options: "".into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!diff.equal);
+    assert_eq!(diff.merge, r#"{"a":2}"#);
+
+    let patched = client
+        .patch(PatchRequest {
+            diff: diff.jd,
+            format: Format::Jd.into(),
+            document: r#"{"a":1}"#.into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(patched.document, r#"{"a":2}"#);
+
+    let translated = client
+        .translate(TranslateRequest { spec: "yaml2json".into(), input: "a: [1]\n".into() })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(translated.output, r#"{"a":[1]}"#);
+
+    let status = client
+        .diff(DiffRequest { lhs: "{".into(), rhs: "{}".into(), options: "".into() })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn streams_carry_documents_in_chunks() {
+    let mut client = connect(JdService::new().with_chunk_size(4)).await;
+    let lhs = format!("[{}]", (0..1000).map(|i| i.to_string()).collect::<Vec<_>>().join(","));
+    let rhs = lhs.replace(",500,", ",-500,");
+    let part = |part| DiffUpload { part: Some(part) };
+    let mut uploads = vec![part(diff_upload::Part::Header(DiffStreamHeader {
+        options: "".into(),
+        format: Format::Merge.into(),
+    }))];
+    uploads.extend(lhs.as_bytes().chunks(100).map(|c| part(diff_upload::Part::Lhs(c.to_vec()))));
+    uploads.extend(rhs.as_bytes().chunks(100).map(|c| part(diff_upload::Part::Rhs(c.to_vec()))));
+    let chunks =
+        collect(client.diff_stream(tokio_stream::iter(uploads)).await.unwrap().into_inner()).await;
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 4));
+    assert_eq!(String::from_utf8(chunks.concat()).unwrap(), rhs);
+
+    let part = |part| PatchUpload { part: Some(part) };
+    let uploads = vec![
+        part(patch_upload::Part::Header(PatchStreamHeader { format: Format::Merge.into() })),
+        part(patch_upload::Part::Diff(br#"{"b":"#.to_vec())),
+        part(patch_upload::Part::Diff(b"true}".to_vec())),
+        part(patch_upload::Part::Document(br#"{"a":1}"#.to_vec())),
+    ];
+    let chunks =
+        collect(client.patch_stream(tokio_stream::iter(uploads)).await.unwrap().into_inner()).await;
+    assert_eq!(String::from_utf8(chunks.concat()).unwrap(), r#"{"a":1,"b":true}"#);
+
+    let headless = vec![part(patch_upload::Part::Document(b"{}".to_vec()))];
+    let status = client.patch_stream(tokio_stream::iter(headless)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
@@ -0,0 +1,70 @@
+// Compares the native addon with the wasm build on the jd-benches fixtures.
+//
+//   npm run build                                        # ./jd.node
+//   wasm-pack build ../jd-wasm --target nodejs --out-dir pkg-node
+//   node bench/compare.mjs [--iterations N]
+//
+// JD_NODE_ADDON and JD_WASM_PKG override where the two builds are loaded
+// from. Without a wasm build only the native addon is measured.
+
+import { existsSync, readFileSync, readdirSync } from "node:fs";
+import { createRequire } from "node:module";
+import { dirname, join, resolve } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const require = createRequire(import.meta.url);
+const here = dirname(fileURLToPath(import.meta.url));
+const root = resolve(here, "..");
+const fixtures = resolve(root, "../jd-benches/fixtures");
+
+const flag = process.argv.indexOf("--iterations");
+const iterations = flag === -1 ? 50 : Number(process.argv[flag + 1]);
+
+const addonPath = process.env.JD_NODE_ADDON ?? join(root, "jd.node");
+const wasmPath = process.env.JD_WASM_PKG ?? resolve(root, "../jd-wasm/pkg-node/jd_wasm.js");
+
+const builds = [["napi", loadOrExit(addonPath)]];
+if (existsSync(wasmPath)) {
+  builds.push(["wasm", require(wasmPath)]);
+} else {
+  console.warn(`no wasm build at ${wasmPath}; measuring the native addon only`);
+}
+
+function loadOrExit(path) {
+  if (!existsSync(path)) {
+    console.error(`no native addon at ${path}; run \`npm run build\` first`);
+    process.exit(1);
+  }
+  return require(path);
+}
+
+// Both builds take `(a, b, ...)` for diff and `(doc, diff)` for patch; only
+// re-rendering differs, as `render(diff, to)` versus `translate(spec, diff)`.
+const operations = {
+  diff: (jd, { before, after }) => jd.diff(before, after),
+  patch: (jd, { before, diff }) => jd.patch(before, diff),
+  render: (jd, { diff }) => (jd.render ? jd.render(diff, "patch") : jd.translate("jd2patch", diff)),
+};
+
+function measure(run) {
+  run(); // warm up
+  const start = process.hrtime.bigint();
+  for (let i = 0; i < iterations; i++) run();
+  return Number(process.hrtime.bigint() - start) / iterations / 1e6;
+}
+
+const rows = [];
+for (const name of readdirSync(fixtures).sort()) {
+  const before = readFileSync(join(fixtures, name, "before.json"), "utf8");
+  const after = readFileSync(join(fixtures, name, "after.json"), "utf8");
+  const input = { before, after, diff: builds[0][1].diff(before, after) };
+  for (const [operation, run] of Object.entries(operations)) {
+    const row = { fixture: name, operation };
+    for (const [build, jd] of builds) row[`${build} (ms)`] = measure(() => run(jd, input)).toFixed(3);
+    if (builds.length === 2) row["wasm / napi"] = (row["wasm (ms)"] / row["napi (ms)"]).toFixed(2);
+    rows.push(row);
+  }
+}
+
+console.log(`${iterations} iterations per measurement`);
+console.table(rows);
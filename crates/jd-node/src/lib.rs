@@ -0,0 +1,139 @@
+//! Node.js native bindings for the Rust port of the `jd` tool.
+//!
+//! Built with napi-rs into a `.node` addon, so Node.js services compute
+//! jd diffs in process instead of spawning the CLI:
+//!
+//! ```js
+//! const jd = require("./jd.node");
+//!
+//! const d = jd.diff('{"a":1}', '{"a":2}');           // '@ ["a"]\n- 1\n+ 2\n'
+//! jd.patch('{"a":1}', d);                             // '{"a":2}'
+//! jd.render(d, "patch");                              // JSON Patch text
+//! jd.diff('[1.0]', '[1.001]', { opts: '[{"precision":0.01}]' }); // ''
+//! ```
+//!
+//! Documents are JSON or YAML strings and diffs are strings in the native
+//! jd format, JSON Patch, or JSON Merge Patch. Failures throw `Error`s.
+#![warn(missing_docs)]
+
+use jd_core::{DiffFormat, DiffOptions, Jd, Node, ParseDiffFormatError, RenderConfig};
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+
+/// Options for [`diff`].
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct JdDiffOptions {
+    /// Options in the format of `jd -opts`, such as
+    /// `'["MERGE",{"precision":0.01}]'`.
+    pub opts: Option<String>,
+    /// Output format: `"jd"`, `"patch"`, or `"merge"`. Defaults to `"merge"`
+    /// when `opts` includes `"MERGE"` and to `"jd"` otherwise; `"merge"`
+    /// implies merge semantics.
+    pub format: Option<String>,
+    /// Colors native output with ANSI escapes.
+    pub color: Option<bool>,
+}
+
+fn diff_format(name: &str) -> Result<DiffFormat> {
+    name.parse()
+        .map_err(|err: ParseDiffFormatError| Error::new(Status::InvalidArg, err.to_string()))
+}
+
+fn failure(err: impl ToString) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+/// Parses a document as JSON, or as YAML when it is not JSON, reporting the
+/// JSON error when both fail.
+fn parse_document(input: &str) -> Result<Node> {
+    Node::from_json_str(input)
+        .or_else(|err| Node::from_yaml_str(input).map_err(|_| err))
+        .map_err(failure)
+}
+
+/// Diffs document `a` against document `b`, each JSON or YAML, and renders
+/// the result.
+#[napi]
+pub fn diff(a: String, b: String, options: Option<JdDiffOptions>) -> Result<String> {
+    let options = options.unwrap_or_default();
+    let diff_options = options
+        .opts
+        .as_deref()
+        .map_or_else(|| Ok(DiffOptions::default()), DiffOptions::from_json_opts)
+        .map_err(failure)?;
+    let format = match options.format.as_deref() {
+        Some(name) => diff_format(name)?,
+        None if diff_options.merge() => DiffFormat::Merge,
+        None => DiffFormat::Jd,
+    };
+    Jd::with_options(diff_options)
+        .color(options.color.unwrap_or(false))
+        .render_as(&parse_document(&a)?, &parse_document(&b)?, format)
+        .map_err(failure)
+}
+
+/// Applies `diff`, written in `format` (`"jd"` by default, `"patch"`, or
+/// `"merge"`), to document `doc` and returns the patched document as JSON.
+#[napi]
+pub fn patch(doc: String, diff: String, format: Option<String>) -> Result<String> {
+    let diff = diff_format(format.as_deref().unwrap_or("jd"))?.read(&diff).map_err(failure)?;
+    let patched = parse_document(&doc)?.apply_patch(&diff).map_err(failure)?;
+    Ok(patched.to_json_value().map(|value| value.to_string()).unwrap_or_default())
+}
+
+/// Re-renders `diff`, written in `from` (`"jd"` by default), in format `to`.
+#[napi]
+pub fn render(diff: String, to: String, from: Option<String>) -> Result<String> {
+    let diff = diff_format(from.as_deref().unwrap_or("jd"))?.read(&diff).map_err(failure)?;
+    diff_format(&to)?.render(&diff, &RenderConfig::default()).map_err(failure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(opts: Option<&str>, format: Option<&str>) -> Option<JdDiffOptions> {
+        Some(JdDiffOptions {
+            opts: opts.map(str::to_string),
+            format: format.map(str::to_string),
+            color: None,
+        })
+    }
+
+    #[test]
+    fn diffs_render_in_each_format() {
+        let (a, b) = (r#"{"a":1,"b":[1,2]}"#.to_string(), r#"{"a":2,"b":[1,2]}"#.to_string());
+        assert_eq!(diff(a.clone(), b.clone(), None).unwrap(), "@ [\"a\"]\n- 1\n+ 2\n");
+        assert_eq!(
+            diff(a.clone(), b.clone(), options(Some(r#"["MERGE"]"#), None)).unwrap(),
+            r#"{"a":2}"#
+        );
+        assert_eq!(diff(a.clone(), b.clone(), options(None, Some("merge"))).unwrap(), r#"{"a":2}"#);
+        let err = diff(a, b, options(None, Some("html"))).unwrap_err();
+        assert_eq!(err.status, Status::InvalidArg);
+    }
+
+    #[test]
+    fn diffs_honor_options_and_yaml_inputs() {
+        let (a, b) = ("a: 1.0\n".to_string(), r#"{"a":1.05}"#.to_string());
+        assert_eq!(diff(a, b, options(Some(r#"[{"precision":0.1}]"#), None)).unwrap(), "");
+        let err = diff("{".to_string(), "{}".to_string(), None).unwrap_err();
+        assert!(err.reason.contains("JSON"), "{}", err.reason);
+    }
+
+    #[test]
+    fn patches_and_renders_round_trip() {
+        let native = "@ [\"a\"]\n- 1\n+ 2\n".to_string();
+        assert_eq!(patch(r#"{"a":1}"#.to_string(), native.clone(), None).unwrap(), r#"{"a":2}"#);
+        let ops = render(native, "patch".to_string(), None).unwrap();
+        assert_eq!(
+            patch(r#"{"a":1}"#.to_string(), ops, Some("patch".to_string())).unwrap(),
+            r#"{"a":2}"#
+        );
+        let native = render(r#"{"a":2}"#.to_string(), "jd".to_string(), Some("merge".to_string()));
+        assert_eq!(render(native.unwrap(), "merge".to_string(), None).unwrap(), r#"{"a":2}"#);
+        let stale = "@ [\"a\"]\n- 3\n+ 2\n".to_string();
+        assert!(patch(r#"{"a":1}"#.to_string(), stale, None).is_err());
+    }
+}
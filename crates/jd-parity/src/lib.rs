@@ -0,0 +1,298 @@
+//! Byte-for-byte parity checks of the Rust `jd` binary against Go `jd`.
+//!
+//! A [`ParityCase`] is a `jd` command line plus the files it reads. Running
+//! it executes the same command with a Go `jd` binary and with the Rust one,
+//! each in a fresh directory holding the inputs, and fails unless the exit
+//! status, standard output, and every file left in the directory (such as
+//! `-o` targets) are identical. Standard error is not compared since the two
+//! implementations word usage errors differently.
+//!
+//! Cases can be built in code or loaded from directories laid out like the
+//! upstream dataset in `docs/parity/upstream`: a `command.txt` holding the
+//! command next to its input files.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use jd_parity::ParityCase;
+//!
+//! let case = ParityCase::new("objects", ["-f", "patch", "a.json", "b.json"])
+//!     .with_input("a.json", r#"{"a":1}"#)
+//!     .with_input("b.json", r#"{"a":2}"#);
+//! case.run(Path::new("/usr/local/bin/jd"), Path::new("target/debug/jd")).unwrap();
+//! ```
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use thiserror::Error;
+
+/// Name of the file holding a case's command in a corpus directory.
+pub const COMMAND_FILE: &str = "command.txt";
+
+/// Errors raised while loading or running parity cases.
+#[derive(Debug, Error)]
+pub enum ParityError {
+    /// A corpus file or scratch directory could not be read or written.
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// The file or directory involved.
+        path: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// A corpus directory's `command.txt` has no command line.
+    #[error("{}: no command found", path.display())]
+    MissingCommand {
+        /// The command file.
+        path: PathBuf,
+    },
+    /// A binary could not be started.
+    #[error("failed to run {}: {source}", binary.display())]
+    Spawn {
+        /// The binary that failed to start.
+        binary: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// The two binaries behaved differently.
+    #[error("{case}: {what} differ\n--- go\n{go}\n+++ rust\n{rust}")]
+    Mismatch {
+        /// Name of the case.
+        case: String,
+        /// What differed: `exit status`, `stdout`, or `file NAME`.
+        what: String,
+        /// What Go `jd` produced, lossily decoded as UTF-8.
+        go: String,
+        /// What the Rust binary produced, lossily decoded as UTF-8.
+        rust: String,
+    },
+}
+
+/// A `jd` invocation compared between Go and Rust.
+///
+/// ```
+/// # use jd_parity::ParityCase;
+/// let case = ParityCase::new("yaml", ["-yaml", "a.yaml", "b.yaml"]).with_input("a.yaml", "a: 1\n");
+/// assert_eq!(case.args(), ["-yaml", "a.yaml", "b.yaml"]);
+/// assert_eq!(case.inputs().len(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParityCase {
+    name: String,
+    args: Vec<String>,
+    inputs: BTreeMap<String, Vec<u8>>,
+}
+
+impl ParityCase {
+    /// Creates a case running `jd` with `args` and no input files.
+    #[must_use]
+    pub fn new<I, S>(name: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            inputs: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a file written to the working directory before `jd` runs.
+    #[must_use]
+    pub fn with_input(mut self, name: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+        self.inputs.insert(name.into(), contents.into());
+        self
+    }
+
+    /// Loads a case from a corpus directory, naming it after the directory.
+    ///
+    /// Lines of `command.txt` that are blank or start with `#` are skipped;
+    /// the rest form one command whose first word is the binary and whose
+    /// remaining whitespace-separated words are the arguments. A `> FILE`
+    /// redirect is dropped because standard output is compared directly.
+    /// Every other file in the directory becomes an input.
+    ///
+    /// ```
+    /// # use jd_parity::ParityCase;
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("command.txt"), "# Run from here\n/tmp/jd -p patch.jd doc.json > out.json\n").unwrap();
+    /// std::fs::write(dir.path().join("doc.json"), "{}").unwrap();
+    /// let case = ParityCase::from_dir(dir.path()).unwrap();
+    /// assert_eq!(case.args(), ["-p", "patch.jd", "doc.json"]);
+    /// assert_eq!(case.inputs().keys().collect::<Vec<_>>(), ["doc.json"]);
+    /// ```
+    pub fn from_dir(dir: &Path) -> Result<Self, ParityError> {
+        let command_path = dir.join(COMMAND_FILE);
+        let command = fs::read_to_string(&command_path).map_err(io_error(&command_path))?;
+        let mut words = command
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .flat_map(str::split_whitespace);
+        if words.next().is_none() {
+            return Err(ParityError::MissingCommand { path: command_path });
+        }
+        let mut args = Vec::new();
+        while let Some(word) = words.next() {
+            if word == ">" {
+                words.next();
+            } else {
+                args.push(word.to_string());
+            }
+        }
+        let name = dir
+            .file_name()
+            .map_or_else(|| dir.display().to_string(), |name| name.to_string_lossy().into_owned());
+        let mut case = Self::new(name, args);
+        for (file, contents) in read_files(dir)? {
+            if file != COMMAND_FILE {
+                case = case.with_input(file, contents);
+            }
+        }
+        Ok(case)
+    }
+
+    /// The case's name, used in reports.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The arguments passed to both binaries.
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The input files by name.
+    #[must_use]
+    pub fn inputs(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.inputs
+    }
+
+    /// Runs the case with both binaries and checks that they agree.
+    ///
+    /// Both run with a private config directory and without `JD_OPTS`, so
+    /// user defaults stay out of the comparison.
+    pub fn run(&self, go_binary: &Path, rust_binary: &Path) -> Result<(), ParityError> {
+        let go = self.execute(go_binary)?;
+        let rust = self.execute(rust_binary)?;
+        let mismatch = |what: String, go: &[u8], rust: &[u8]| ParityError::Mismatch {
+            case: self.name.clone(),
+            what,
+            go: String::from_utf8_lossy(go).into_owned(),
+            rust: String::from_utf8_lossy(rust).into_owned(),
+        };
+        if go.status != rust.status {
+            let status = |code: Option<i32>| code.map_or("signal".to_string(), |c| c.to_string());
+            return Err(mismatch(
+                "exit status".to_string(),
+                status(go.status).as_bytes(),
+                status(rust.status).as_bytes(),
+            ));
+        }
+        if go.stdout != rust.stdout {
+            return Err(mismatch("stdout".to_string(), &go.stdout, &rust.stdout));
+        }
+        let names: std::collections::BTreeSet<&String> =
+            go.files.keys().chain(rust.files.keys()).collect();
+        for name in names {
+            let (go_file, rust_file) = (go.files.get(name), rust.files.get(name));
+            if go_file != rust_file {
+                return Err(mismatch(
+                    format!("file {name}"),
+                    go_file.map_or(&[][..], Vec::as_slice),
+                    rust_file.map_or(&[][..], Vec::as_slice),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&self, binary: &Path) -> Result<Outcome, ParityError> {
+        let scratch = tempfile::tempdir().map_err(io_error(Path::new("scratch directory")))?;
+        let (workdir, config) = (scratch.path().join("work"), scratch.path().join("config"));
+        for dir in [&workdir, &config] {
+            fs::create_dir(dir).map_err(io_error(dir))?;
+        }
+        for (name, contents) in &self.inputs {
+            let path = workdir.join(name);
+            fs::write(&path, contents).map_err(io_error(&path))?;
+        }
+        // A relative binary path would otherwise resolve against the
+        // working directory on some platforms.
+        let binary = if binary.components().count() > 1 {
+            fs::canonicalize(binary).map_err(io_error(binary))?
+        } else {
+            binary.to_path_buf()
+        };
+        let output = Command::new(&binary)
+            .args(&self.args)
+            .current_dir(&workdir)
+            .env("XDG_CONFIG_HOME", &config)
+            .env_remove("JD_OPTS")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|source| ParityError::Spawn { binary: binary.clone(), source })?;
+        Ok(Outcome {
+            status: output.status.code(),
+            stdout: output.stdout,
+            files: read_files(&workdir)?,
+        })
+    }
+}
+
+/// Loads every case under `dir`: the directory itself when it holds a
+/// `command.txt`, otherwise each subdirectory that does, in name order.
+///
+/// ```
+/// # use jd_parity::load_corpus;
+/// let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/parity/upstream/jd-v2.2.2");
+/// let cases = load_corpus(std::path::Path::new(corpus)).unwrap();
+/// assert!(cases.iter().any(|case| case.name() == "default-object"));
+/// ```
+pub fn load_corpus(dir: &Path) -> Result<Vec<ParityCase>, ParityError> {
+    if dir.join(COMMAND_FILE).is_file() {
+        return Ok(vec![ParityCase::from_dir(dir)?]);
+    }
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.join(COMMAND_FILE).is_file() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    dirs.iter().map(|dir| ParityCase::from_dir(dir)).collect()
+}
+
+/// What one binary did for a case.
+struct Outcome {
+    status: Option<i32>,
+    stdout: Vec<u8>,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+/// Reads the regular files directly inside `dir`.
+fn read_files(dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, ParityError> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.is_file() {
+            let contents = fs::read(&path).map_err(io_error(&path))?;
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned());
+            files.insert(name.unwrap_or_default(), contents);
+        }
+    }
+    Ok(files)
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> ParityError + '_ {
+    move |source| ParityError::Io { path: path.to_path_buf(), source }
+}
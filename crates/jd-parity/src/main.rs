@@ -0,0 +1,45 @@
+//! Runs parity corpora against a Go `jd` binary and the Rust one.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::Parser;
+use jd_parity::load_corpus;
+
+/// Compares Go `jd` and the Rust `jd` byte for byte on corpus cases.
+#[derive(Debug, Parser)]
+#[command(name = "jd-parity", version)]
+struct Cli {
+    /// Path to the Go `jd` binary.
+    #[arg(long, value_name = "PATH")]
+    go: PathBuf,
+    /// Path to the Rust `jd` binary.
+    #[arg(long, value_name = "PATH", default_value = "target/debug/jd")]
+    rust: PathBuf,
+    /// Case directories (holding `command.txt`) or directories of cases.
+    #[arg(value_name = "CORPUS", required = true)]
+    corpora: Vec<PathBuf>,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let mut failures = 0;
+    for corpus in &cli.corpora {
+        for case in load_corpus(corpus)? {
+            match case.run(&cli.go, &cli.rust) {
+                Ok(()) => eprintln!("[OK]   {}", case.name()),
+                Err(err) => {
+                    failures += 1;
+                    eprintln!("[FAIL] {err}");
+                }
+            }
+        }
+    }
+    if failures > 0 {
+        eprintln!("{failures} case(s) differ");
+        return Ok(ExitCode::FAILURE);
+    }
+    eprintln!("All parity cases match");
+    Ok(ExitCode::SUCCESS)
+}
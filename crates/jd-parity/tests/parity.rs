@@ -0,0 +1,92 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use jd_parity::{load_corpus, ParityCase, ParityError};
+
+const UPSTREAM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/parity/upstream/jd-v2.2.2");
+
+/// Writes an executable shell script standing in for a `jd` binary.
+fn fake_jd(dir: &Path, name: &str, body: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn mismatch(result: Result<(), ParityError>) -> (String, String, String) {
+    match result {
+        Err(ParityError::Mismatch { what, go, rust, .. }) => (what, go, rust),
+        other => panic!("expected a mismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn identical_behaviour_passes() {
+    let bin = tempfile::tempdir().unwrap();
+    let script = r#"cat "$1"; echo "$2" > out.txt; exit 1"#;
+    let (go, rust) = (fake_jd(bin.path(), "go", script), fake_jd(bin.path(), "rust", script));
+    let case = ParityCase::new("same", ["in.json", "written"]).with_input("in.json", "{}");
+    case.run(&go, &rust).unwrap();
+}
+
+#[test]
+fn differences_are_reported() {
+    let bin = tempfile::tempdir().unwrap();
+    let case = ParityCase::new("case", Vec::<String>::new());
+    let go = fake_jd(bin.path(), "go", "echo same; echo a > out.txt");
+
+    let rust = fake_jd(bin.path(), "status", "echo same; echo a > out.txt; exit 2");
+    assert_eq!(mismatch(case.run(&go, &rust)), ("exit status".into(), "0".into(), "2".into()));
+
+    let rust = fake_jd(bin.path(), "stdout", "echo other; echo a > out.txt");
+    assert_eq!(
+        mismatch(case.run(&go, &rust)),
+        ("stdout".into(), "same\n".into(), "other\n".into())
+    );
+
+    let rust = fake_jd(bin.path(), "file", "echo same; echo b > out.txt");
+    assert_eq!(mismatch(case.run(&go, &rust)), ("file out.txt".into(), "a\n".into(), "b\n".into()));
+
+    let rust = fake_jd(bin.path(), "missing", "echo same");
+    assert_eq!(mismatch(case.run(&go, &rust)), ("file out.txt".into(), "a\n".into(), "".into()));
+}
+
+#[test]
+fn user_defaults_are_isolated() {
+    let bin = tempfile::tempdir().unwrap();
+    let go = fake_jd(bin.path(), "go", "echo \"${JD_OPTS:-unset}\"");
+    let rust = fake_jd(bin.path(), "rust", "echo unset");
+    std::env::set_var("JD_OPTS", "[\"SET\"]");
+    ParityCase::new("env", Vec::<String>::new()).run(&go, &rust).unwrap();
+}
+
+#[test]
+fn upstream_corpus_loads() {
+    let cases = load_corpus(Path::new(UPSTREAM)).unwrap();
+    let patch = cases.iter().find(|case| case.name() == "patch-mode").unwrap();
+    assert_eq!(patch.args(), ["-p", "patch.jd", "original.json"]);
+    assert!(patch.inputs().contains_key("original.json"));
+    assert!(!patch.inputs().contains_key("command.txt"));
+    let single = load_corpus(&Path::new(UPSTREAM).join("arrays-setkeys")).unwrap();
+    assert_eq!(single[0].args(), ["-setkeys", "id", "before.json", "after.json"]);
+}
+
+/// Sweeps the upstream corpus when `JD_PARITY_GO` names a Go `jd` binary
+/// and `JD_PARITY_RUST` a Rust one.
+#[test]
+fn upstream_corpus_matches_go() {
+    let (Some(go), Some(rust)) =
+        (std::env::var_os("JD_PARITY_GO"), std::env::var_os("JD_PARITY_RUST"))
+    else {
+        return;
+    };
+    let failures: Vec<String> = load_corpus(Path::new(UPSTREAM))
+        .unwrap()
+        .iter()
+        .filter_map(|case| case.run(Path::new(&go), Path::new(&rust)).err())
+        .map(|err| err.to_string())
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
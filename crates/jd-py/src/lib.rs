@@ -0,0 +1,188 @@
+//! Python bindings for the Rust port of the `jd` tool.
+//!
+//! Built with PyO3 into a `jd` extension module, so notebooks and pipeline
+//! jobs diff and patch Python data with the same engine as the `jd` binary:
+//!
+//! ```python
+//! import jd
+//!
+//! print(jd.diff({"a": 1, "tags": ["x"]}, {"a": 2, "tags": ["x"]}))
+//! # @ ["a"]
+//! # - 1
+//! # + 2
+//! jd.patch({"a": 1}, '@ ["a"]\n- 1\n+ 2\n')   # {'a': 2}
+//! jd.diff(1.0, 1.001, precision=0.01)           # ''
+//! ```
+//!
+//! Documents are plain Python values: `dict` with `str` keys, `list` and
+//! `tuple`, `str`, `int`, `float`, `bool`, and `None`. Failures raise
+//! `jd.JdError`, a `ValueError`; unsupported values raise `TypeError`.
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use std::collections::BTreeMap;
+
+use jd_core::{DiffFormat, DiffOptions, Jd, Node, Number, Preset};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+
+create_exception!(jd, JdError, PyValueError, "Raised when jd cannot diff, render, or patch.");
+
+fn jd_error(err: impl ToString) -> PyErr {
+    JdError::new_err(err.to_string())
+}
+
+/// Converts a Python value into a canonical node.
+///
+/// Integers outside the range of a double lose precision, as in JSON.
+pub fn to_node(value: &Bound<'_, PyAny>) -> PyResult<Node> {
+    if value.is_none() {
+        return Ok(Node::Null);
+    }
+    // `bool` subclasses `int`, so it must be checked first.
+    if let Ok(flag) = value.downcast::<PyBool>() {
+        return Ok(Node::Bool(flag.is_true()));
+    }
+    if value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>() {
+        let number = match value.extract::<i64>() {
+            Ok(int) => int as f64,
+            Err(_) => value.extract::<f64>()?,
+        };
+        return Number::new(number).map(Node::Number).map_err(jd_error);
+    }
+    if let Ok(text) = value.downcast::<PyString>() {
+        return Ok(Node::String(text.to_str()?.to_owned()));
+    }
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        let mut object = BTreeMap::new();
+        for (key, member) in dict {
+            let key = key.downcast::<PyString>().map_err(|_| {
+                PyTypeError::new_err(format!("object keys must be str, not {}", key.get_type()))
+            })?;
+            object.insert(key.to_str()?.to_owned(), to_node(&member)?);
+        }
+        return Ok(Node::Object(object));
+    }
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        return value
+            .try_iter()?
+            .map(|item| to_node(&item?))
+            .collect::<PyResult<_>>()
+            .map(Node::Array);
+    }
+    Err(PyTypeError::new_err(format!("cannot convert {} to a jd document", value.get_type())))
+}
+
+/// Converts a node into Python values. Integral numbers that a double
+/// represents exactly become `int`; void becomes `None`.
+pub fn to_python(py: Python<'_>, node: &Node) -> PyResult<PyObject> {
+    const EXACT: f64 = 9_007_199_254_740_992.0; // 2^53
+    Ok(match node {
+        Node::Void | Node::Null => py.None(),
+        Node::Bool(flag) => PyBool::new(py, *flag).to_owned().into_any().unbind(),
+        Node::Number(number) => {
+            let value = number.get();
+            if value.fract() == 0.0 && value.abs() <= EXACT {
+                (value as i64).into_pyobject(py)?.into_any().unbind()
+            } else {
+                PyFloat::new(py, value).into_any().unbind()
+            }
+        }
+        Node::String(text) => PyString::new(py, text).into_any().unbind(),
+        Node::Array(items) => {
+            let items =
+                items.iter().map(|item| to_python(py, item)).collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any().unbind()
+        }
+        Node::Object(members) => {
+            let dict = PyDict::new(py);
+            for (key, member) in members {
+                dict.set_item(key, to_python(py, member)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+fn diff_format(format: &str) -> PyResult<DiffFormat> {
+    format.parse().map_err(|_| {
+        PyValueError::new_err(format!(
+            "unknown format {format:?}; expected 'jd', 'patch', or 'merge'"
+        ))
+    })
+}
+
+/// Diffs `a` against `b` and renders the diff as `format`: `"jd"` (the
+/// native format), `"patch"` (JSON Patch), or `"merge"` (JSON Merge Patch).
+///
+/// The `"merge"` format implies merge semantics. `opts` takes options in
+/// the format of `jd -opts`; `preset` (`"github"`, `"kubernetes"`,
+/// `"openapi"`, or `"terraform"`, as `jd --preset`), `set`, `multiset`,
+/// `set_keys`, and `precision` apply on top of them. Set and multiset
+/// arrays raise `JdError` until their diff engine lands.
+#[pyfunction]
+#[pyo3(signature = (a, b, *, set=false, multiset=false, set_keys=None, precision=None, preset=None, opts=None, format="jd"))]
+#[allow(clippy::too_many_arguments)]
+fn diff(
+    a: &Bound<'_, PyAny>,
+    b: &Bound<'_, PyAny>,
+    set: bool,
+    multiset: bool,
+    set_keys: Option<Vec<String>>,
+    precision: Option<f64>,
+    preset: Option<&str>,
+    opts: Option<&str>,
+    format: &str,
+) -> PyResult<String> {
+    let format = diff_format(format)?;
+    let options = opts
+        .map_or_else(|| Ok(DiffOptions::default()), DiffOptions::from_json_opts)
+        .map_err(jd_error)?;
+    let mut jd = Jd::with_options(options);
+    if let Some(preset) = preset {
+        jd = jd.preset(preset.parse::<Preset>().map_err(jd_error)?);
+    }
+    if set {
+        jd = jd.set();
+    }
+    if multiset {
+        jd = jd.multiset();
+    }
+    if let Some(keys) = set_keys {
+        jd = jd.set_keys(keys);
+    }
+    if let Some(precision) = precision {
+        jd = jd.precision(precision);
+    }
+    jd.render_as(&to_node(a)?, &to_node(b)?, format).map_err(jd_error)
+}
+
+/// Applies `diff`, written as `format` (`"jd"`, `"patch"`, or `"merge"`),
+/// to `doc` and returns the patched document.
+#[pyfunction]
+#[pyo3(signature = (doc, diff, *, format="jd"))]
+fn patch(py: Python<'_>, doc: &Bound<'_, PyAny>, diff: &str, format: &str) -> PyResult<PyObject> {
+    let diff = diff_format(format)?.read(diff).map_err(jd_error)?;
+    let patched = to_node(doc)?.apply_patch(&diff).map_err(jd_error)?;
+    to_python(py, &patched)
+}
+
+/// Round-trips `value` through jd's data model, returning it as jd sees
+/// it: tuples become lists and integral floats become ints.
+#[pyfunction]
+fn canonicalize(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    to_python(py, &to_node(value)?)
+}
+
+/// The `jd` Python module.
+#[pymodule]
+#[pyo3(name = "jd")]
+pub fn jd(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("JdError", module.py().get_type::<JdError>())?;
+    module.add_function(wrap_pyfunction!(diff, module)?)?;
+    module.add_function(wrap_pyfunction!(patch, module)?)?;
+    module.add_function(wrap_pyfunction!(canonicalize, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Drives the module from Python code, as users call it.
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Runs `code` with the module imported as `jd`.
+fn run(code: &str) {
+    Python::with_gil(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("jd", pyo3::wrap_pymodule!(jd_py::jd)(py)).unwrap();
+        let code = CString::new(code).unwrap();
+        if let Err(err) = py.run(&code, Some(&globals), None) {
+            err.print(py);
+            panic!("Python code failed: {err}");
+        }
+    });
+}
+
+#[test]
+fn diffs_python_values() {
+    run(r#"
+assert jd.diff({"a": 1, "b": [1, 2]}, {"a": 2, "b": [1, 2]}) == '@ ["a"]\n- 1\n+ 2\n'
+assert jd.diff({"a": 1}, {"a": 1}) == ""
+assert jd.diff((1, 2), [1, 2]) == ""
+assert jd.diff(1.0, 1.001, precision=0.01) == ""
+assert jd.diff({"serial": 1, "lineage": "x"}, {"serial": 2, "lineage": "x"}, preset="terraform") == ""
+assert jd.diff({"a": 1}, {"a": 2}, format="merge") == '{"a":2}'
+assert jd.diff({"a": 1}, {"a": 2}, opts='["MERGE"]', format="merge") == '{"a":2}'
+assert jd.diff(None, True, format="patch").startswith('[{"op":"test"')
+"#);
+}
+
+#[test]
+fn patches_return_python_values() {
+    run(r#"
+assert jd.patch({"a": 1}, '@ ["a"]\n- 1\n+ 2\n') == {"a": 2}
+assert jd.patch({"a": 1}, '{"b": [1.5, "x"]}', format="merge") == {"a": 1, "b": [1.5, "x"]}
+ops = '[{"op":"add","path":"/b","value":null}]'
+assert jd.patch({"a": 1}, ops, format="patch") == {"a": 1, "b": None}
+assert jd.canonicalize({"t": (1, 2.0, False)}) == {"t": [1, 2, False]}
+assert type(jd.canonicalize(2.0)) is int and type(jd.canonicalize(2.5)) is float
+"#);
+}
+
+#[test]
+fn errors_raise_python_exceptions() {
+    run(r#"
+def raises(exc, f):
+    try:
+        f()
+    except exc as err:
+        return str(err)
+    raise AssertionError(f"expected {exc.__name__}")
+
+assert issubclass(jd.JdError, ValueError)
+raises(jd.JdError, lambda: jd.patch({"a": 3}, '@ ["a"]\n- 1\n+ 2\n'))
+raises(jd.JdError, lambda: jd.diff([1], [2], opts='["FAST"]'))
+raises(jd.JdError, lambda: jd.diff([1], [2], preset="helm"))
+raises(jd.JdError, lambda: jd.diff(float("nan"), 1))
+raises(ValueError, lambda: jd.diff(1, 2, format="html"))
+assert "keys must be str" in raises(TypeError, lambda: jd.diff({1: 2}, {}))
+raises(TypeError, lambda: jd.diff(object(), 1))
+"#);
+}
@@ -0,0 +1,139 @@
+//! The operations behind the JavaScript bindings, in plain Rust so they can
+//! be tested natively.
+
+use jd_core::{
+    CanonicalizeError, DiffFormat, DiffOptions, Jd, JdError, Node, OptionsError,
+    ParseDiffFormatError, PatchError, ReadError, RenderConfig, RenderError,
+};
+use thiserror::Error;
+
+/// Errors reported to JavaScript as `Error` messages.
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// A document is neither JSON nor YAML.
+    #[error(transparent)]
+    Parse(#[from] CanonicalizeError),
+    /// The `-opts` string is invalid.
+    #[error(transparent)]
+    Options(#[from] OptionsError),
+    /// Diffing failed.
+    #[error(transparent)]
+    Diff(#[from] JdError),
+    /// A diff could not be read in its format.
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    /// A diff could not be rendered in the requested format.
+    #[error(transparent)]
+    Render(#[from] RenderError),
+    /// A diff does not apply to the document.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+    /// A format name is not one of `jd`, `patch`, or `merge`.
+    #[error(transparent)]
+    UnknownFormat(#[from] ParseDiffFormatError),
+    /// A translation spec is not in the supported table.
+    #[error("unsupported translation: {0:?}")]
+    UnsupportedTranslation(String),
+}
+
+/// Parses a document as JSON, or as YAML when it is not JSON. Every JSON
+/// document is also YAML, so the fallback never changes a JSON result; the
+/// JSON error is reported when both fail.
+fn parse_document(input: &str) -> Result<Node, Error> {
+    Node::from_json_str(input).or_else(|err| Node::from_yaml_str(input).map_err(|_| err.into()))
+}
+
+/// Diffs `a` against `b` under Go `jd`'s `-opts` options and renders the
+/// result as `format`, by default `merge` when the options ask for merge
+/// semantics and `jd` otherwise. The `merge` format implies merge semantics.
+pub(crate) fn diff(
+    a: &str,
+    b: &str,
+    opts: Option<&str>,
+    format: Option<&str>,
+) -> Result<String, Error> {
+    let options = opts.map_or_else(|| Ok(DiffOptions::default()), DiffOptions::from_json_opts)?;
+    let format = match format {
+        Some(name) => name.parse()?,
+        None if options.merge() => DiffFormat::Merge,
+        None => DiffFormat::Jd,
+    };
+    Ok(Jd::with_options(options).render_as(&parse_document(a)?, &parse_document(b)?, format)?)
+}
+
+/// Applies `diff`, written as `format` (`jd` by default), to `doc` and
+/// returns the patched document as JSON.
+pub(crate) fn patch(doc: &str, diff: &str, format: Option<&str>) -> Result<String, Error> {
+    let diff = format.unwrap_or("jd").parse::<DiffFormat>()?.read(diff)?;
+    let patched = parse_document(doc)?.apply_patch(&diff)?;
+    Ok(patched.to_json_value().map(|value| value.to_string()).unwrap_or_default())
+}
+
+/// Translates `input` by a `FROM2TO` spec from the `jd -t` table:
+/// `jd2patch`, `patch2jd`, `jd2merge`, `merge2jd`, `json2yaml`, or
+/// `yaml2json`.
+pub(crate) fn translate(spec: &str, input: &str) -> Result<String, Error> {
+    let unsupported = || Error::UnsupportedTranslation(spec.to_string());
+    let (from, to) = spec.split_once('2').ok_or_else(unsupported)?;
+    match (from, to) {
+        ("json", "yaml") => Ok(Node::from_json_str(input)?.to_yaml_string().unwrap_or_default()),
+        ("yaml", "json") => Ok(Node::from_yaml_str(input)?
+            .to_json_value()
+            .map(|value| value.to_string())
+            .unwrap_or_default()),
+        ("jd", "patch" | "merge") | ("patch" | "merge", "jd") => {
+            let diff = from.parse::<DiffFormat>()?.read(input)?;
+            Ok(to.parse::<DiffFormat>()?.render(&diff, &RenderConfig::default())?)
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_render_in_each_format() {
+        let (a, b) = (r#"{"a":1,"b":[1,2]}"#, r#"{"a":2,"b":[1,2]}"#);
+        assert_eq!(diff(a, b, None, None).unwrap(), "@ [\"a\"]\n- 1\n+ 2\n");
+        assert_eq!(
+            diff(a, b, None, Some("patch")).unwrap(),
+            r#"[{"op":"test","path":"/a","value":1},{"op":"remove","path":"/a","value":1},{"op":"add","path":"/a","value":2}]"#
+        );
+        assert_eq!(diff(a, b, Some(r#"["MERGE"]"#), None).unwrap(), r#"{"a":2}"#);
+        assert_eq!(diff(a, b, None, Some("merge")).unwrap(), r#"{"a":2}"#);
+        assert!(matches!(diff(a, b, None, Some("html")), Err(Error::UnknownFormat(_))));
+    }
+
+    #[test]
+    fn diffs_honor_options_and_yaml_inputs() {
+        let opts = r#"[{"precision":0.1}]"#;
+        assert_eq!(diff("a: 1.0\n", r#"{"a":1.05}"#, Some(opts), None).unwrap(), "");
+        assert!(matches!(diff("{}", "{}", Some(r#"["FAST"]"#), None), Err(Error::Options(_))));
+        let err = diff("{", "{}", None, None).unwrap_err();
+        assert!(err.to_string().contains("JSON"), "{err}");
+    }
+
+    #[test]
+    fn patches_apply_in_each_format() {
+        let doc = r#"{"a":1}"#;
+        assert_eq!(patch(doc, "@ [\"a\"]\n- 1\n+ 2\n", None).unwrap(), r#"{"a":2}"#);
+        let ops = r#"[{"op":"add","path":"/b","value":true}]"#;
+        assert_eq!(patch(doc, ops, Some("patch")).unwrap(), r#"{"a":1,"b":true}"#);
+        assert_eq!(patch(doc, r#"{"a":null}"#, Some("merge")).unwrap(), "{}");
+        assert!(matches!(patch(doc, "@ [\"a\"]\n- 3\n+ 2\n", None), Err(Error::Patch(_))));
+    }
+
+    #[test]
+    fn translations_follow_the_jd_table() {
+        assert_eq!(translate("json2yaml", r#"{"a":[1]}"#).unwrap(), "a:\n- 1\n");
+        assert_eq!(translate("yaml2json", "a: [1]\n").unwrap(), r#"{"a":[1]}"#);
+        let native = translate("merge2jd", r#"{"a":2}"#).unwrap();
+        assert_eq!(translate("jd2merge", &native).unwrap(), r#"{"a":2}"#);
+        assert!(matches!(
+            translate("patch2merge", "[]"),
+            Err(Error::UnsupportedTranslation(spec)) if spec == "patch2merge"
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+//! WebAssembly bindings for the Rust port of the `jd` tool.
+//!
+//! Exposes the `jd-core` engine to JavaScript through `wasm-bindgen`, so
+//! browser and Node.js tooling diff, patch, and translate exactly as the
+//! `jd` binary does. Documents are JSON or YAML strings, diffs are strings
+//! in the native jd format, JSON Patch, or JSON Merge Patch, and failures
+//! are thrown as JavaScript `Error`s.
+//!
+//! ```js
+//! import { diff, patch, translate } from "jd-wasm";
+//!
+//! const d = diff('{"a":1}', '{"a":2}');            // "@ [\"a\"]\n- 1\n+ 2\n"
+//! patch('{"a":1}', d);                              // '{"a":2}'
+//! diff('[1.0]', '[1.001]', '[{"precision":0.01}]'); // options as jd -opts
+//! translate("jd2patch", d);
+//! ```
+//!
+//! Build with `wasm-pack build crates/jd-wasm --target web` for browsers or
+//! `--target nodejs` for Node.js.
+#![warn(missing_docs)]
+
+mod engine;
+
+use wasm_bindgen::prelude::*;
+
+/// Diffs document `a` against document `b`.
+///
+/// `opts` is an options array in the format of `jd -opts`, such as
+/// `'["MERGE",{"precision":0.01}]'`. `format` is `"jd"`, `"patch"`, or
+/// `"merge"`, defaulting to `"merge"` when the options include `"MERGE"` and
+/// to `"jd"` otherwise.
+#[wasm_bindgen]
+pub fn diff(
+    a: &str,
+    b: &str,
+    opts: Option<String>,
+    format: Option<String>,
+) -> Result<String, JsError> {
+    Ok(engine::diff(a, b, opts.as_deref(), format.as_deref())?)
+}
+
+/// Applies `diff`, written in `format` (`"jd"` by default, `"patch"`, or
+/// `"merge"`), to `doc` and returns the patched document as JSON.
+#[wasm_bindgen]
+pub fn patch(doc: &str, diff: &str, format: Option<String>) -> Result<String, JsError> {
+    Ok(engine::patch(doc, diff, format.as_deref())?)
+}
+
+/// Translates `input` according to a `jd -t` spec: `jd2patch`,
+/// `patch2jd`, `jd2merge`, `merge2jd`, `json2yaml`, or `yaml2json`.
+#[wasm_bindgen]
+pub fn translate(spec: &str, input: &str) -> Result<String, JsError> {
+    Ok(engine::translate(spec, input)?)
+}
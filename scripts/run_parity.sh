@@ -1,6 +1,9 @@
 #!/usr/bin/env bash
 set -euo pipefail
 
+# Checks the Rust binary against the outputs recorded from Go jd. To compare
+# against a live Go binary instead, use `cargo run -p jd-parity`.
+
 REPO_ROOT=$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)
 DATASET_DIR="$REPO_ROOT/docs/parity/upstream/jd-v2.2.2"
 
@@ -25,6 +28,10 @@ fi
 tmp_root=$(mktemp -d -t jd-parity.XXXXXX)
 trap 'rm -rf "$tmp_root"' EXIT
 
+# Keep user defaults (config file, JD_OPTS) out of the comparison.
+export XDG_CONFIG_HOME="$tmp_root/config"
+unset JD_OPTS
+
 declare -a failures=()
 
 declare -A stdout_expectations=(
@@ -43,7 +50,13 @@ declare -A file_expectations=(
   [output-flag-dash-filename]=-
   [output-flag-format-merge]=diff.merge
   [output-flag-format-patch]=diff.patch
+  [output-flag-patch-mode]=patched.json
+  [output-flag-translate-jd2patch]=output.patch
+  [output-flag-translate-patch2jd]=output.jd
   [output-flag-yaml]=diff.jd
+  [patch-mode]=patched.json
+  [translate-jd2patch]=output.patch
+  [translate-patch2jd]=output.jd
 )
 
 declare -A expected_failures=(
@@ -52,12 +65,6 @@ declare -A expected_failures=(
   [arrays-set]="-set is not implemented yet"
   [arrays-setkeys]="-setkeys is not implemented yet"
   [arrays-setkeys-nested]="-setkeys is not implemented yet"
-  [output-flag-patch-mode]="Patch mode is not implemented yet"
-  [patch-mode]="Patch mode is not implemented yet"
-  [output-flag-translate-jd2patch]="Translate mode is not implemented yet"
-  [output-flag-translate-patch2jd]="Translate mode is not implemented yet"
-  [translate-jd2patch]="Translate mode is not implemented yet"
-  [translate-patch2jd]="Translate mode is not implemented yet"
 )
 
 run_stdout() {